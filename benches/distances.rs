@@ -0,0 +1,85 @@
+//! Distances over a corridor of boost stars: one `distance_to` call per star
+//! against a single `distances_from` pass, and a full corridor route.
+//!
+//! Run with `cargo bench --bench distances`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use edjc::jump_calculator::JumpCalculator;
+use edjc::types::{distances_from, RouteOptions, SystemCoordinates};
+
+/// `count` neutron stars scattered along a corridor from Sol towards Colonia
+fn corridor(count: usize) -> Vec<SystemCoordinates> {
+    (0..count)
+        .map(|i| {
+            let along = i as f64 / count as f64;
+            let wobble = (i as f64 * 0.37).sin() * 40.0;
+            SystemCoordinates {
+                name: format!("Neutron {i}"),
+                x: -9530.5 * along + wobble,
+                y: -910.28125 * along - wobble,
+                z: 19808.125 * along + wobble,
+                has_neutron_star: true,
+                has_white_dwarf: false,
+                primary_star_kind: None,
+                is_hidden: false,
+                required_permit: None,
+            }
+        })
+        .collect()
+}
+
+fn system(name: &str, x: f64, y: f64, z: f64) -> SystemCoordinates {
+    SystemCoordinates {
+        name: name.to_string(),
+        x,
+        y,
+        z,
+        has_neutron_star: false,
+        has_white_dwarf: false,
+        primary_star_kind: None,
+        is_hidden: false,
+        required_permit: None,
+    }
+}
+
+fn bench_distances(c: &mut Criterion) {
+    let origin = system("Sol", 0.0, 0.0, 0.0);
+    let mut group = c.benchmark_group("distances");
+    for count in [100, 1_000, 10_000] {
+        let stars = corridor(count);
+        group.bench_with_input(
+            BenchmarkId::new("per_element", count),
+            &stars,
+            |b, stars| {
+                b.iter(|| {
+                    stars
+                        .iter()
+                        .map(|star| black_box(&origin).distance_to(star))
+                        .collect::<Vec<f64>>()
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("batch", count), &stars, |b, stars| {
+            b.iter(|| distances_from(black_box(&origin), stars))
+        });
+    }
+    group.finish();
+}
+
+fn bench_corridor_route(c: &mut Criterion) {
+    let calc = JumpCalculator::new();
+    let sol = system("Sol", 0.0, 0.0, 0.0);
+    let colonia = system("Colonia", -9530.5, -910.28125, 19808.125);
+    let options = RouteOptions::default();
+    let stars = corridor(2_000);
+
+    c.bench_function("corridor_route_2000_stars", |b| {
+        b.iter(|| {
+            calc.calculate_route_with_corridor(&sol, &colonia, 60.0, &options, Some(&stars))
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, bench_distances, bench_corridor_route);
+criterion_main!(benches);
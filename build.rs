@@ -12,6 +12,13 @@ fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=Cargo.toml");
 
+    // Add version information, for every target (the plugin reports it)
+    let version = env::var("CARGO_PKG_VERSION").unwrap();
+    println!("cargo:rustc-env=PLUGIN_VERSION={version}");
+
+    let name = env::var("CARGO_PKG_NAME").unwrap();
+    println!("cargo:rustc-env=PLUGIN_NAME={name}");
+
     // Check what we're building - only apply HexChat exports for the main library
     let pkg_name = env::var("CARGO_PKG_NAME").unwrap_or_default();
     let crate_name = env::var("CARGO_CRATE_NAME").unwrap_or_default();
@@ -46,11 +53,4 @@ fn main() {
             println!("cargo:warning=Unknown target OS: {target_os}");
         }
     }
-
-    // Add version information
-    let version = env::var("CARGO_PKG_VERSION").unwrap();
-    println!("cargo:rustc-env=PLUGIN_VERSION={version}");
-
-    let name = env::var("CARGO_PKG_NAME").unwrap();
-    println!("cargo:rustc-env=PLUGIN_NAME={name}");
 }
@@ -0,0 +1,54 @@
+/*!
+Inara API debugging tool.
+
+Queries Inara for the configured commander's location and ship so API key
+and profile visibility problems can be diagnosed outside of HexChat.
+*/
+
+use edjc::config;
+use edjc::inara::InaraClient;
+use std::env;
+
+fn main() -> anyhow::Result<()> {
+    println!("EDJC Inara Debug Tool");
+    println!("=====================");
+
+    let mut args: Vec<String> = env::args().collect();
+    config::apply_config_flag(&mut args)?;
+
+    let config = config::load_config()?;
+
+    let Some(api_key) = config.inara_api_key.as_deref() else {
+        println!("❌ No Inara API key configured. Add 'inara_api_key = \"your_key\"' to edjc.toml");
+        return Ok(());
+    };
+
+    let inara_client = InaraClient::new(api_key)?;
+
+    // Allow looking up a different commander than the configured one
+    let cmdr_name = args.get(1).unwrap_or(&config.cmdr_name);
+
+    println!("Looking up CMDR {cmdr_name} on Inara...");
+    println!();
+
+    match inara_client.get_cmdr_location(cmdr_name) {
+        Ok(system) => println!("✓ Location: {system}"),
+        Err(e) => println!("✗ Location lookup failed: {e}"),
+    }
+
+    match inara_client.get_ship_info(cmdr_name) {
+        Ok(ship) => {
+            println!(
+                "✓ Ship: {} ({})",
+                ship.ship_type,
+                ship.ship_name.as_deref().unwrap_or("unnamed")
+            );
+            if let Some(range) = ship.max_jump_range {
+                println!("  Max jump range: {range:.2} LY");
+            }
+        }
+        Err(e) => println!("✗ Ship lookup failed: {e}"),
+    }
+
+    Ok(())
+}
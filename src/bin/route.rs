@@ -5,78 +5,135 @@ This binary allows users to test the jump calculation functionality
 without loading the HexChat plugin.
 */
 
+use anyhow::Context;
 use edjc::config;
-use edjc::edsm::EdsmClient;
-use edjc::jump_calculator::JumpCalculator;
+use edjc::edsm::{self, EdsmClient};
+use edjc::planner::{self, RoutePlanner};
+use edjc::route_export;
+use edjc::system_source::{LocalSystemSource, SystemSource};
+use edjc::types::{EdjcError, NumberFormat};
+use edjc::EdJumpCalculator;
+use serde_json::json;
 use std::env;
-use std::io::{self, Write};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::Duration;
 
 fn main() -> anyhow::Result<()> {
+    // Get command line arguments
+    let mut args: Vec<String> = env::args().collect();
+    config::apply_config_flag(&mut args)?;
+    let offline_flag = take_flag(&mut args, "--offline");
+    let repl = take_flag(&mut args, "--repl");
+    let export_path = take_option(&mut args, "--export")?;
+
+    // Scripts get the route as JSON on stdout and a non-zero exit on failure
+    if take_flag(&mut args, "--json") {
+        if let Err(e) = print_json_route(&args, offline_flag) {
+            println!("{}", json!({ "error": e.to_string() }));
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     println!("EDJC Route Calculator - Standalone Test");
     println!("=======================================");
 
-    // Load configuration
-    let config = match config::load_config() {
-        Ok(cfg) => cfg,
-        Err(e) => {
-            eprintln!("Warning: Could not load config: {e}");
-            eprintln!("Using default ship jump range of 35.0 LY");
-            println!();
+    let config = load_config();
 
-            // Create a default config
-            config::Config {
-                cmdr_name: "Test CMDR".to_string(),
-                edsm_api_key: None,
-                ship: config::ShipConfig {
-                    name: "Test Ship".to_string(),
-                    laden_jump_range: 35.0,
-                    max_jump_range: None,
-                },
-                cache_timeout_seconds: 300,
-                debug_mode: false,
-                neutron_highway_threshold_ly: 500.0,
-                white_dwarf_threshold_ly: 150.0,
-                result_format: "🚀 {jumps} jumps to {system} ({distance:.1}ly) via {route}"
-                    .to_string(),
-                show_fuel_estimates: false,
-                show_time_estimates: false,
-            }
-        }
-    };
+    let offline = offline_flag || config.offline_mode;
 
     println!("Configuration:");
     println!("  CMDR: {}", config.cmdr_name);
-    println!("  Ship jump range: {:.1} LY", config.ship.laden_jump_range);
+    if offline {
+        println!("  Offline mode: bundled systems only");
+    }
+    let numbers = config.number_format;
+    println!(
+        "  Ship jump range: {}",
+        numbers.distance(config.ship().laden_jump_range)
+    );
     println!();
 
-    // Create clients
-    let edsm_client = EdsmClient::new()?;
-    let jump_calculator = JumpCalculator::new();
+    if args.get(1).map(String::as_str) == Some("--preview") {
+        let plugin = edjc::EdJumpCalculator::from_config(config)?;
+        println!("{}", plugin.handle_preview_command());
+        return Ok(());
+    }
+
+    if args.get(1).map(String::as_str) == Some("--from-file") {
+        let Some(path) = args.get(2) else {
+            println!("Usage: {} --from-file <log>", args[0]);
+            return Ok(());
+        };
+        let log =
+            std::fs::read_to_string(path).with_context(|| format!("Could not read {path}"))?;
+        let plugin = EdJumpCalculator::from_config(config::Config {
+            offline_mode: offline,
+            ..config
+        })?;
+        print_log_routes(&plugin, &log, numbers, &mut io::stdout())?;
+        return Ok(());
+    }
+
+    let edsm_client = edsm_client(&config)?;
+
+    let systems: &dyn SystemSource = if offline {
+        &LocalSystemSource
+    } else {
+        &edsm_client
+    };
 
     // Test EDSM connection
-    print!("Testing EDSM connection... ");
-    io::stdout().flush()?;
+    if !offline {
+        print!("Testing EDSM connection... ");
+        io::stdout().flush()?;
 
-    match edsm_client.test_connection() {
-        Ok(true) => println!("✓ Connected"),
-        Ok(false) => {
-            println!("✗ Connection test failed");
-            return Ok(());
-        }
-        Err(e) => {
-            println!("✗ Connection failed: {e}");
+        let diagnostics = edsm_client.connection_diagnostics();
+        if diagnostics.health.is_healthy() {
+            println!("✓ Connected - {diagnostics}");
+        } else {
+            println!("✗ Connection failed - {diagnostics}");
             return Ok(());
         }
     }
 
-    // Get command line arguments
-    let args: Vec<String> = env::args().collect();
+    if repl {
+        println!("Enter <target_system> [current_system] per line, quoting names with spaces.");
+        println!("quit or Ctrl-D exits.");
+        run_repl(&config, systems, io::stdin().lock(), &mut io::stdout())?;
+        return Ok(());
+    }
 
     if args.len() < 2 {
-        println!("Usage: {} <target_system> [current_system]", args[0]);
+        println!(
+            "Usage: {} [--config <path>] [--offline] [--json] [--export <file>] <target_system> [current_system]",
+            args[0]
+        );
+        println!("       {} [--config <path>] [--offline] --repl", args[0]);
+        println!(
+            "       {} [--config <path>] [--offline] --sysinfo <system>",
+            args[0]
+        );
+        println!("       {} [--config <path>] --preview", args[0]);
+        println!(
+            "       {} [--config <path>] [--offline] --from-file <log>",
+            args[0]
+        );
         println!();
         println!("If current_system is not provided, your CMDR's current location will be");
         println!("retrieved from EDSM automatically (if available).");
+        println!(
+            "With --offline only the bundled systems are known: {}",
+            LocalSystemSource
+                .system_names()
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        println!("With --json the route details are printed as JSON for scripts.");
+        println!("With --export the waypoints are saved as Spansh JSON, or CSV for a .csv file.");
+        println!("With --repl routes are read from stdin, one query per line.");
+        println!("With --from-file every RATSIGNAL in a saved chat log is planned.");
         println!();
         println!("Examples:");
         println!(
@@ -98,17 +155,37 @@ fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    if args[1] == "--sysinfo" {
+        let Some(system_name) = args.get(2) else {
+            println!("Usage: {} --sysinfo <system>", args[0]);
+            return Ok(());
+        };
+
+        match systems.get_system_coordinates(system_name) {
+            Ok(coords) => println!("📍 {}", coords.summary()),
+            Err(e) => {
+                println!("❌ Failed to look up {system_name}: {e}");
+                print_hint(&e);
+            }
+        }
+        return Ok(());
+    }
+
     let target_system = &args[1];
+    let planner = RoutePlanner::new(&config, systems);
     let current_system = if args.len() >= 3 {
         args[2].clone()
+    } else if offline {
+        println!("Offline mode: routing from {}", config.fallback_origin);
+        config.fallback_origin.clone()
     } else {
         // Try to get commander's current location from EDSM
         println!(
-            "Getting {}'s current location from EDSM...",
-            config.cmdr_name
+            "Getting {}'s current location from {}...",
+            config.cmdr_name,
+            systems.name()
         );
-        match edsm_client.get_commander_location(&config.cmdr_name, config.edsm_api_key.as_deref())
-        {
+        match planner.locate_commander() {
             Ok(system) => {
                 println!("✓ Found {} in {}", config.cmdr_name, system);
                 system
@@ -121,8 +198,11 @@ fn main() -> anyhow::Result<()> {
                         "   to access private location data, or enable public profile on EDSM."
                     );
                 }
-                println!("   Using Sol as starting point. You can specify current system as: {} {} <current_system>", args[0], target_system);
-                "Sol".to_string()
+                println!(
+                    "   Using {} as starting point. You can specify current system as: {} {} <current_system>",
+                    config.fallback_origin, args[0], target_system
+                );
+                config.fallback_origin.clone()
             }
         }
     };
@@ -130,46 +210,16 @@ fn main() -> anyhow::Result<()> {
     println!("Calculating route from {current_system} to {target_system}...");
     println!();
 
-    // Get system coordinates with better error handling
-    println!("Looking up {current_system} coordinates...");
-    let current_coords = match edsm_client.get_system_coordinates(&current_system) {
-        Ok(coords) => {
-            println!(
-                "✓ {} found at ({:.1}, {:.1}, {:.1})",
-                current_system, coords.x, coords.y, coords.z
-            );
-            coords
-        }
-        Err(e) => {
-            println!("❌ Failed to get {current_system} coordinates: {e}");
-            return Ok(());
-        }
-    };
-
-    println!("Looking up {target_system} coordinates...");
-    let target_coords = match edsm_client.get_system_coordinates(target_system) {
-        Ok(coords) => {
-            println!(
-                "✓ {} found at ({:.1}, {:.1}, {:.1})",
-                target_system, coords.x, coords.y, coords.z
-            );
-            coords
-        }
+    let jump_range = config.ship().laden_jump_range;
+    let planned = match planner.plan(target_system, jump_range, Some(&current_system)) {
+        Ok(planned) => planned,
         Err(e) => {
-            println!("❌ Failed to get {target_system} coordinates: {e}");
-            println!("   This could mean:");
-            println!("   - System name is misspelled");
-            println!("   - System doesn't exist in EDSM database");
-            println!("   - Network connection issue");
+            println!("❌ Route calculation failed: {e}");
+            print_hint(&e);
             return Ok(());
         }
     };
-
-    // Calculate direct distance
-    let direct_distance = ((target_coords.x - current_coords.x).powi(2)
-        + (target_coords.y - current_coords.y).powi(2)
-        + (target_coords.z - current_coords.z).powi(2))
-    .sqrt();
+    let (current_coords, target_coords) = (&planned.from, &planned.to);
 
     println!("System Information:");
     println!(
@@ -180,7 +230,10 @@ fn main() -> anyhow::Result<()> {
         "  {}: ({:.1}, {:.1}, {:.1})",
         target_system, target_coords.x, target_coords.y, target_coords.z
     );
-    println!("  Direct distance: {direct_distance:.1} LY");
+    println!(
+        "  Direct distance: {}",
+        numbers.distance(current_coords.distance_to(target_coords))
+    );
 
     if current_coords.has_neutron_star {
         println!("  📡 {current_system} has a neutron star!");
@@ -194,34 +247,332 @@ fn main() -> anyhow::Result<()> {
     if target_coords.has_white_dwarf {
         println!("  ⚪ {target_system} has a white dwarf!");
     }
+    for warning in &planned.warnings {
+        println!("  ⚠️ {warning}");
+    }
     println!();
 
-    // Calculate route
-    match jump_calculator.calculate_route(
-        &current_coords,
-        &target_coords,
-        config.ship.laden_jump_range,
-    ) {
-        Ok(result) => {
-            println!("Route Calculation:");
-            println!("  🚀 {} jumps required", result.jumps);
-            println!("  📏 {:.1} LY total route distance", result.total_distance);
-            println!("  🛣️ Route type: {}", result.route_type);
-            println!(
-                "  ⛽ Ship jump range: {:.1} LY",
-                config.ship.laden_jump_range
-            );
-
-            if result.route_type.contains("neutron") {
-                println!("  💫 Using neutron highway for 4x boost!");
-            } else if result.route_type.contains("white dwarf") {
-                println!("  ⭐ Using white dwarf assistance for 1.5x boost!");
+    let result = &planned.result;
+    println!("Route Calculation:");
+    println!("  🚀 {} jumps required", result.jumps);
+    println!(
+        "  📏 {} total route distance",
+        numbers.distance(result.total_distance)
+    );
+    println!("  🛣️ Route type: {}", result.route_type);
+    println!("  ⛽ Ship jump range: {}", numbers.distance(jump_range));
+
+    if result.route_type.contains("neutron") {
+        println!("  💫 Using neutron highway for 4x boost!");
+    } else if result.route_type.contains("white dwarf") {
+        println!("  ⭐ Using white dwarf assistance for 1.5x boost!");
+    }
+
+    if let Some(path) = export_path {
+        let details = planner
+            .jump_calculator()
+            .describe_route(planned.result, planned.jump_range);
+        let waypoints = route_export::write_route_export(&details, Path::new(&path))?;
+        println!();
+        println!("💾 Exported {waypoints} waypoints to {path}");
+    }
+
+    Ok(())
+}
+
+/// Answer "target [origin]" queries read from `input` until `quit` or the end
+/// of input
+///
+/// Every query goes to the same `systems`, so systems looked up before are
+/// served from its cache.
+fn run_repl(
+    config: &config::Config,
+    systems: &dyn SystemSource,
+    input: impl BufRead,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let planner = RoutePlanner::new(config, systems);
+    let jump_range = config.ship().laden_jump_range;
+    let numbers = config.number_format;
+
+    write!(out, "route> ")?;
+    out.flush()?;
+    for line in input.lines() {
+        let query = split_query(&line?);
+        match query.as_slice() {
+            [] => {}
+            [command] if command == "quit" || command == "exit" => break,
+            [target] | [target, _] => {
+                let origin = query.get(1).map(String::as_str);
+                match planner.plan(target, jump_range, origin) {
+                    Ok(planned) => {
+                        let result = &planned.result;
+                        writeln!(
+                            out,
+                            "🚀 {} → {}: {} jumps, {} ({})",
+                            planned.from.name,
+                            planned.to.name,
+                            result.jumps,
+                            numbers.distance(result.total_distance),
+                            result.route_type
+                        )?;
+                        for warning in &planned.warnings {
+                            writeln!(out, "  ⚠️ {warning}")?;
+                        }
+                    }
+                    Err(e) => {
+                        writeln!(out, "❌ Route calculation failed: {e}")?;
+                        if let Some(hint) = e.hint() {
+                            writeln!(out, "   Hint: {hint}")?;
+                        }
+                    }
+                }
             }
+            _ => writeln!(
+                out,
+                "Usage: <target_system> [current_system], quote names with spaces"
+            )?,
         }
+        write!(out, "route> ")?;
+        out.flush()?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Plan a route for every case in a chat log, one line each
+fn print_log_routes(
+    plugin: &EdJumpCalculator,
+    log: &str,
+    numbers: NumberFormat,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let results = plugin.process_log(log);
+    if results.is_empty() {
+        return writeln!(out, "No RATSIGNALs found");
+    }
+
+    for (signal, result) in &results {
+        let case = format!(
+            "Case #{} (CMDR {}, {})",
+            signal.case_number, signal.cmdr_name, signal.system_name
+        );
+        match result {
+            Ok(result) => writeln!(
+                out,
+                "🚀 {case}: {} jumps, {} ({})",
+                result.jumps,
+                numbers.distance(result.total_distance),
+                result.route_type
+            )?,
+            Err(e) => {
+                writeln!(out, "❌ {case}: {e}")?;
+                if let Some(hint) = e.hint() {
+                    writeln!(out, "   Hint: {hint}")?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Split a REPL line into words, keeping "quoted names" together
+fn split_query(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut rest = line.trim();
+    while !rest.is_empty() {
+        let (word, remainder) = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+        };
+        words.push(word.to_string());
+        rest = remainder.trim_start();
+    }
+    words
+}
+
+/// Say what to do about a failed lookup, when there is an obvious answer
+fn print_hint(e: &EdjcError) {
+    if let Some(hint) = e.hint() {
+        println!("   Hint: {hint}");
+    }
+}
+
+/// Load the configuration, or a default one with a 35 LY ship
+fn load_config() -> config::Config {
+    match config::load_config() {
+        Ok(cfg) => cfg,
         Err(e) => {
-            println!("❌ Route calculation failed: {e}");
+            eprintln!("Warning: Could not load config: {e}");
+            eprintln!("Using default ship jump range of 35.0 LY");
+
+            // Create a default config
+            config::Config {
+                cmdr_name: "Test CMDR".to_string(),
+                edsm_api_key: None,
+                ships: vec![config::ShipConfig {
+                    name: "Test Ship".to_string(),
+                    laden_jump_range: 35.0,
+                    max_jump_range: None,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }
         }
     }
+}
+
+/// EDSM client set up from the configuration
+fn edsm_client(config: &config::Config) -> anyhow::Result<EdsmClient> {
+    let mut edsm_client = EdsmClient::new()?
+        .with_request_timeout(
+            edsm::DEFAULT_REQUEST_TIMEOUT.min(Duration::from_secs(config.plan_timeout_seconds)),
+        )
+        .with_include_hidden(config.include_hidden)
+        .with_suggestions(config.suggest_system_names)
+        .with_location_retries(config.cmdr_location_retries, Duration::from_millis(750))
+        .with_location_cache_ttl(Duration::from_secs(config.location_cache_seconds))
+        .with_retry_policy(config.edsm_retry_policy());
+    if config.persist_cache {
+        edsm_client = edsm_client.with_cache_file(config::get_cache_path()?);
+    }
+    Ok(edsm_client)
+}
 
+/// Plan the route given on the command line and print its details as JSON
+fn print_json_route(args: &[String], offline: bool) -> anyhow::Result<()> {
+    let Some(target_system) = args.get(1) else {
+        anyhow::bail!("Usage: {} --json <target_system> [current_system]", args[0]);
+    };
+
+    let config = load_config();
+    let edsm_client = edsm_client(&config)?;
+    let systems: &dyn SystemSource = if offline || config.offline_mode {
+        &LocalSystemSource
+    } else {
+        &edsm_client
+    };
+
+    let origin = args.get(2).map(String::as_str);
+    let planner = RoutePlanner::new(&config, systems);
+    let details = planner::plan_route(&planner, target_system, origin)?;
+    println!("{}", serde_json::to_string_pretty(&details)?);
     Ok(())
 }
+
+/// Remove a flag and its value from the arguments, returning the value
+fn take_option(args: &mut Vec<String>, flag: &str) -> anyhow::Result<Option<String>> {
+    let Some(index) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        anyhow::bail!("{flag} requires a path");
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+/// Remove a flag from the arguments, returning whether it was given
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_query() {
+        assert_eq!(split_query("  Colonia  Deciat "), vec!["Colonia", "Deciat"]);
+        assert_eq!(
+            split_query(r#""Sagittarius A*" "Shinrarta Dezhra""#),
+            vec!["Sagittarius A*", "Shinrarta Dezhra"]
+        );
+        assert_eq!(split_query(r#""Sagittarius A*"#), vec!["Sagittarius A*"]);
+        assert!(split_query("   ").is_empty());
+    }
+
+    #[test]
+    fn test_take_option() {
+        let mut args: Vec<String> = ["route", "--export", "out.csv", "Colonia"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            take_option(&mut args, "--export").unwrap().as_deref(),
+            Some("out.csv")
+        );
+        assert_eq!(args, ["route", "Colonia"]);
+        assert_eq!(take_option(&mut args, "--export").unwrap(), None);
+
+        let mut args = vec!["route".to_string(), "--export".to_string()];
+        assert!(take_option(&mut args, "--export").is_err());
+    }
+
+    #[test]
+    fn test_repl_answers_each_line() {
+        let config = config::Config {
+            ships: vec![config::ShipConfig {
+                laden_jump_range: 50.0,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let input = "Colonia Sol\n\n\"Sagittarius A*\"\nNowhere Sol\na b c\nquit\nColonia\n";
+
+        let mut out = Vec::new();
+        run_repl(&config, &LocalSystemSource, input.as_bytes(), &mut out).unwrap();
+
+        // "quit" stops the loop, so the last Colonia is never planned
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out.lines().collect::<Vec<_>>(),
+            vec![
+                "route> 🚀 Sol → Colonia: 179 jumps, 22000.5 LY (neutron highway)",
+                "route> route> 🚀 Sol → Sagittarius A*: 209 jumps, 25900.0 LY (neutron highway)",
+                "  ⚠️ CMDR position unknown, routing from Sol",
+                "route> ❌ Route calculation failed: System not bundled for offline mode: Nowhere",
+                "   Hint: offline mode only knows a few well-known systems",
+                "route> Usage: <target_system> [current_system], quote names with spaces",
+                "route> ",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_routes() {
+        let plugin = EdJumpCalculator::from_config(config::Config {
+            offline_mode: true,
+            persist_cache: false,
+            ships: vec![config::ShipConfig {
+                laden_jump_range: 50.0,
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+        .unwrap();
+        let log = r#"<MechaSqueak[BOT]> RATSIGNAL Case #7 PC - CMDR Whit3Arrow - System: "Colonia" - Language: English (en-US)
+<RatOne> 7 cr
+<MechaSqueak[BOT]> RATSIGNAL Case #8 PC - CMDR Lost - System: "Nowhere" - Language: English (en-US)
+<MechaSqueak[BOT]> RATSIGNAL Case #7 PC - CMDR Whit3Arrow - System: "Colonia" - Language: English (en-US)
+"#;
+
+        let mut out = Vec::new();
+        print_log_routes(&plugin, log, NumberFormat::default(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out.lines().collect::<Vec<_>>(),
+            vec![
+                "🚀 Case #7 (CMDR Whit3Arrow, Colonia): 179 jumps, 22000.5 LY (neutron highway)",
+                "❌ Case #8 (CMDR Lost, Nowhere): System not bundled for offline mode: Nowhere",
+                "   Hint: offline mode only knows a few well-known systems",
+            ]
+        );
+
+        let mut out = Vec::new();
+        print_log_routes(&plugin, "nothing to see", NumberFormat::default(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "No RATSIGNALs found\n");
+    }
+}
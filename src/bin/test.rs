@@ -7,7 +7,7 @@ fn main() -> anyhow::Result<()> {
 
     // Create EDSM client
     let edsm_client = match EdsmClient::new() {
-        Ok(client) => client,
+        Ok(client) => client.with_suggestions(true),
         Err(e) => {
             println!("Failed to create EDSM client: {e}");
             return Ok(());
@@ -18,10 +18,11 @@ fn main() -> anyhow::Result<()> {
     print!("Testing EDSM API connection... ");
     io::stdout().flush()?;
 
-    match edsm_client.test_connection() {
-        Ok(true) => println!("✓ Connection successful"),
-        Ok(false) => println!("✗ Connection failed - unexpected data"),
-        Err(e) => println!("✗ Connection failed: {e}"),
+    let diagnostics = edsm_client.connection_diagnostics();
+    if diagnostics.health.is_healthy() {
+        println!("✓ Connection successful - {diagnostics}");
+    } else {
+        println!("✗ Connection failed - {diagnostics}");
     }
 
     // Test getting system coordinates
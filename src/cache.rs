@@ -0,0 +1,147 @@
+/*!
+Where the EDSM client keeps looked-up coordinates.
+
+By default that's an in-process moka cache with a one-hour TTL. Deployments
+running several processes against EDSM (a bot next to the plugin, say) can
+share one cache instead by implementing [`CoordinateCache`] over Redis, a
+file or anything else, and handing it to
+[`EdsmClient::with_cache`](crate::edsm::EdsmClient::with_cache).
+
+Entries are JSON strings under keys like `coords:<lowercased name>`,
+`coords:#<EDSM system id>` and `sphere:<x>:<y>:<z>:<radius>`;
+implementations store them as given and decide for themselves when they
+expire.
+
+The Inara client can share the EDSM client's cache
+([`InaraClient::with_shared_cache`](crate::inara::InaraClient::with_shared_cache)),
+so a system either of them looked up is known to both. Coordinates entries
+say which source filled them: Inara's lack the boost star and permit data,
+so the EDSM client looks those systems up again rather than serve them.
+*/
+
+use log::warn;
+use moka::sync::Cache;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+use crate::types::SystemCoordinates;
+
+/// A string cache for system coordinates and area lookups
+///
+/// Implementations are shared between threads and by all clones of a client.
+pub trait CoordinateCache: Debug + Send + Sync {
+    /// The value cached under `key`, if it's there and still fresh
+    fn get(&self, key: &str) -> Option<String>;
+
+    /// Cache `value` under `key`, replacing what was there
+    fn insert(&self, key: String, value: String);
+
+    /// Forget what's cached under `key`
+    fn invalidate(&self, key: &str);
+
+    /// Forget everything, for `/edjc-refresh all`
+    fn invalidate_all(&self);
+}
+
+impl CoordinateCache for Cache<String, String> {
+    fn get(&self, key: &str) -> Option<String> {
+        Cache::get(self, key)
+    }
+
+    fn insert(&self, key: String, value: String) {
+        Cache::insert(self, key, value);
+    }
+
+    fn invalidate(&self, key: &str) {
+        Cache::invalidate(self, key);
+    }
+
+    fn invalidate_all(&self) {
+        Cache::invalidate_all(self);
+    }
+}
+
+/// Source recorded for coordinates the EDSM client looked up
+pub const EDSM_SOURCE: &str = "EDSM";
+/// Source recorded for coordinates reloaded from the EDSM client's cache file
+pub const CACHE_FILE_SOURCE: &str = "cache file";
+/// Source recorded for coordinates the Inara client looked up; Inara has no
+/// star or permit data, so the EDSM client doesn't take these as answers
+pub const INARA_SOURCE: &str = "Inara";
+
+/// A coordinates entry with the source that looked it up
+#[derive(Serialize, Deserialize)]
+struct CachedCoordinates {
+    #[serde(flatten)]
+    coordinates: SystemCoordinates,
+    /// "EDSM", "Inara", ...; missing in entries from before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+}
+
+/// Cache key of a system's coordinates, by the (normalized) name it was
+/// looked up by
+pub fn coordinates_key(system_name: &str) -> String {
+    format!("coords:{}", system_name.to_lowercase())
+}
+
+/// Coordinates entry for a system looked up from `source`
+pub fn encode_coordinates(coordinates: &SystemCoordinates, source: &str) -> Option<String> {
+    serde_json::to_string(&CachedCoordinates {
+        coordinates: coordinates.clone(),
+        source: Some(source.to_string()),
+    })
+    .ok()
+}
+
+/// The coordinates in an entry, and the source that filled it if recorded
+///
+/// Entries written by other processes or older versions may have both boost
+/// star flags set; they're normalized on the way out.
+pub fn decode_coordinates(entry: &str) -> Option<(SystemCoordinates, Option<String>)> {
+    let mut cached: CachedCoordinates = serde_json::from_str(entry).ok()?;
+    if cached.coordinates.normalize_star_flags() {
+        warn!(
+            "Cached coordinates for {} had contradictory boost star flags",
+            cached.coordinates.name
+        );
+    }
+    Some((cached.coordinates, cached.source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinates_entries_record_their_source() {
+        let coordinates: SystemCoordinates = serde_json::from_str(
+            r#"{"name": "Fuelum", "x": 52.0, "y": -52.65625, "z": 49.8125,
+                "has_neutron_star": false, "has_white_dwarf": false}"#,
+        )
+        .unwrap();
+        let entry = encode_coordinates(&coordinates, "Inara").unwrap();
+        let (decoded, source) = decode_coordinates(&entry).unwrap();
+        assert_eq!(decoded.name, "Fuelum");
+        assert_eq!(source.as_deref(), Some("Inara"));
+
+        // Plain coordinates, as older entries and other processes store them
+        let plain = serde_json::to_string(&coordinates).unwrap();
+        let (decoded, source) = decode_coordinates(&plain).unwrap();
+        assert_eq!(decoded.z, 49.8125);
+        assert_eq!(source, None);
+        assert!(serde_json::from_str::<SystemCoordinates>(&entry).is_ok());
+
+        assert_eq!(
+            coordinates_key("Col 285 Sector AB-C d1-2"),
+            "coords:col 285 sector ab-c d1-2"
+        );
+
+        // A contradictory entry comes back as a single kind of star
+        let both = r#"{"name": "Odd", "x": 0.0, "y": 0.0, "z": 0.0,
+            "has_neutron_star": true, "has_white_dwarf": true}"#;
+        let (decoded, _) = decode_coordinates(both).unwrap();
+        assert!(decoded.has_neutron_star);
+        assert!(!decoded.has_white_dwarf);
+    }
+}
@@ -7,43 +7,188 @@ including API keys and plugin preferences.
 
 use anyhow::{anyhow, Result};
 use log::{info, warn};
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::edsm::RetryPolicy;
+use crate::jump_calculator::{DEFAULT_NEUTRON_THRESHOLD_LY, DEFAULT_WHITE_DWARF_THRESHOLD_LY};
+use crate::presets;
+use crate::types::{
+    ConfigError, CooldownPolicy, FsdProfile, JumpRounding, LocationSource, MissingLocationPolicy,
+    NumberFormat, OutputMode, RouteBackend, RouteOptimization,
+};
+
+/// How MechaSqueak formats RATSIGNALs
+///
+/// Fields are separated by a hyphen, en dash or em dash depending on the channel.
+pub const DEFAULT_RATSIGNAL_PATTERN: &str = r#"RATSIGNAL\s*[-–—]?\s*Case\s*#(?P<case>\d+)(?P<tags>[^-–—]*)[-–—]\s*CMDR\s+(?P<cmdr>.+?)\s*[-–—]\s*System:\s*"(?P<system>[^"]+)"(?:\s*\((?P<info>[^)]*)\))?(?:\s*[-–—]\s*Language:\s*(?P<language>.*))?"#;
+
+/// The compact RATSIGNAL some channels relay, with the case number last
+///
+/// `RATSIGNAL - CMDR Whit3Arrow - System: "Fuelum" - PC ODY (Case #3)`; the
+/// system may be unquoted and labeled `Reported System:`, and the platform
+/// labeled `Platform:`, followed by optional `O2:` and `Language:` fields.
+pub const COMPACT_RATSIGNAL_PATTERN: &str = r#"RATSIGNAL\s*[-–—]?\s*CMDR\s+(?P<cmdr>.+?)\s*[-–—]\s*(?:Reported\s+)?System:\s*"?(?P<system>[^"(]+?)"?(?:\s*\((?P<info>[^)]*)\))?\s*[-–—]\s*(?:Platform:\s*)?(?P<tags>[^-–—(]*?)\s*(?:[-–—]\s*O2:\s*\S+\s*)?(?:[-–—]\s*Language:\s*(?P<language>.+?)\s*)?\(Case\s*#(?P<case>\d+)\)"#;
+
+/// RATSIGNAL formats recognized without a `ratsignal_pattern`, tried in order
+pub const BUILTIN_RATSIGNAL_PATTERNS: [&str; 2] =
+    [DEFAULT_RATSIGNAL_PATTERN, COMPACT_RATSIGNAL_PATTERN];
+
+/// Fallback for a RATSIGNAL none of the formats match: only the quoted
+/// system (and the note after it, if any) is needed to plan a route
+pub const PARTIAL_RATSIGNAL_PATTERN: &str =
+    r#"System:\s*"(?P<system>[^"]+)"(?:\s*\((?P<info>[^)]*)\))?"#;
+
+/// How relay bots announce a closed case: `Closed case #3 (CMDR Whit3Arrow)`
+/// or `Case #3 (CMDR Whit3Arrow) has been closed`
+pub const CASE_CLOSED_PATTERNS: [&str; 2] = [
+    r"(?i)\bclosed\s+case\s*#(?P<case>\d+)",
+    r"(?i)\bcase\s*#(?P<case>\d+)\b.*?\b(?:closed|cleared)\b",
+];
+
+/// How relay bots announce rats assigned to a case: `Assigned RatOne to
+/// case #3` or `Case #3 assigned to RatOne`
+pub const CASE_ASSIGNED_PATTERNS: [&str; 2] = [
+    r"(?i)\bassigned\b.*?\bto\s+case\s*#(?P<case>\d+)",
+    r"(?i)\bcase\s*#(?P<case>\d+)\b.*?\bassigned\b",
+];
+
+/// Named groups a `ratsignal_pattern` must capture
+const REQUIRED_RATSIGNAL_GROUPS: [&str; 3] = ["case", "cmdr", "system"];
 
 /// Plugin configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    /// CMDR name for location lookups
+    /// CMDR name for location lookups (required, checked at load time)
+    #[serde(default)]
     pub cmdr_name: String,
 
     /// EDSM API key for accessing private commander data
+    ///
+    /// `EDSM_API_KEY` in the environment takes precedence.
     #[serde(default)]
     pub edsm_api_key: Option<String>,
 
-    /// Ship name and jump range configuration
-    pub ship: ShipConfig,
+    /// Inara API key, used as a secondary source for commander data
+    ///
+    /// `INARA_API_KEY` in the environment takes precedence.
+    #[serde(default)]
+    pub inara_api_key: Option<String>,
+
+    /// Most requests per minute sent to Inara (0 = unlimited)
+    #[serde(default = "default_inara_requests_per_minute")]
+    pub inara_requests_per_minute: u32,
+
+    /// System routes start from when the commander's location is unknown
+    #[serde(default = "default_fallback_origin")]
+    pub fallback_origin: String,
+
+    /// RATSIGNALs when the commander's location is unknown: "fallback"
+    /// routes from `fallback_origin`, "skip" only says the origin is unknown,
+    /// "ask" prompts for one
+    #[serde(default)]
+    pub on_missing_location: MissingLocationPolicy,
+
+    /// Ship profiles (`[[ships]]`); a legacy single `[ship]` table loads as one profile
+    #[serde(alias = "ship", default, deserialize_with = "deserialize_ships")]
+    pub ships: Vec<ShipConfig>,
+
+    /// Built-in range preset (see [`crate::presets`]) for ships without a
+    /// `laden_jump_range`, or the only ship when none is configured
+    #[serde(default)]
+    pub ship_preset: Option<String>,
+
+    /// Name of the active ship profile; empty selects the first profile
+    #[serde(default)]
+    pub active_ship: String,
+
+    /// How far, in percent, the active ship's `max_jump_range` may be from
+    /// the one Inara reports before /verify-ship warns about it
+    #[serde(default = "default_ship_range_tolerance_percent")]
+    pub ship_range_tolerance_percent: f64,
 
     /// Cache timeout in seconds
     #[serde(default = "default_cache_timeout")]
     pub cache_timeout_seconds: u64,
 
+    /// Seconds a route may take to plan, all lookups included, before it
+    /// is given up with a timeout error
+    #[serde(default = "default_plan_timeout")]
+    pub plan_timeout_seconds: u64,
+
     /// Enable debug logging
     #[serde(default)]
     pub debug_mode: bool,
 
-    /// Minimum distance threshold for suggesting neutron highway
+    /// Minimum distance threshold for suggesting neutron highway; shorter
+    /// routes never plan neutron supercharges
     #[serde(default = "default_neutron_threshold")]
     pub neutron_highway_threshold_ly: f64,
 
-    /// Minimum distance threshold for suggesting white dwarf assistance
+    /// Minimum distance threshold for suggesting white dwarf assistance;
+    /// shorter routes never plan white dwarf supercharges
     #[serde(default = "default_white_dwarf_threshold")]
     pub white_dwarf_threshold_ly: f64,
 
+    /// Jump range multiplier when supercharged at a neutron star
+    #[serde(default = "default_neutron_multiplier")]
+    pub neutron_multiplier: f64,
+
+    /// Jump range multiplier when supercharged at a white dwarf
+    #[serde(default = "default_white_dwarf_multiplier")]
+    pub white_dwarf_multiplier: f64,
+
+    /// Neutron stars further than this from the straight line aren't routed
+    /// through, however many jumps they would save
+    #[serde(default = "default_max_detour_ly")]
+    pub max_detour_ly: f64,
+
+    /// The same limit as a percentage of the route's length; the larger of
+    /// the two applies (0 uses `max_detour_ly` alone)
+    #[serde(default)]
+    pub max_detour_percent: f64,
+
+    /// Prefer supercharging at neutron stars in populated systems, so a
+    /// stranded pilot has rescue options along the way
+    #[serde(default)]
+    pub prefer_populated: bool,
+
+    /// With `prefer_populated`, how many more jumps a populated waypoint may
+    /// cost than the best one elsewhere (0 only breaks ties)
+    #[serde(default = "default_populated_detour_jumps")]
+    pub populated_detour_jumps: u32,
+
+    /// Grid in LY that corridor sphere queries are snapped to, so routes
+    /// through the same region reuse cached results (0 = exact centres)
+    #[serde(default = "default_corridor_bucket_ly")]
+    pub corridor_bucket_ly: f64,
+
     /// Format string for jump calculation results
     #[serde(default = "default_result_format")]
     pub result_format: String,
 
+    /// Format string for RATSIGNAL notices; when unset, a customized
+    /// `result_format` or else [`DEFAULT_RATSIGNAL_FORMAT`]
+    #[serde(default)]
+    pub ratsignal_format: Option<String>,
+
+    /// Regex recognizing RATSIGNALs, replacing the built-in formats
+    /// ([`BUILTIN_RATSIGNAL_PATTERNS`]) for relay bots that format them
+    /// differently
+    ///
+    /// Needs the named groups `case`, `cmdr` and `system`; `tags` (platform
+    /// and game mode), `info` (system details) and `language` are optional.
+    #[serde(default)]
+    pub ratsignal_pattern: Option<String>,
+
+    /// Format string for /route responses; `result_format` when unset
+    #[serde(default)]
+    pub route_format: Option<String>,
+
     /// Whether to show fuel estimates
     #[serde(default = "default_show_fuel")]
     pub show_fuel_estimates: bool,
@@ -51,6 +196,212 @@ pub struct Config {
     /// Whether to show time estimates
     #[serde(default = "default_show_time")]
     pub show_time_estimates: bool,
+
+    /// How distances, fuel and minutes are written: "plain" (25900.0),
+    /// "en" (25,900.0) or "de" (25.900,0)
+    #[serde(default)]
+    pub number_format: NumberFormat,
+
+    /// How a direct route's jump count is shown when its last jump is only
+    /// partly needed: "ceil" (8), "nearest" (7 or 8) or "range" ("7–8")
+    #[serde(default)]
+    pub jump_rounding: JumpRounding,
+
+    /// Whether notices and responses use emoji; plain ASCII markers ("[OK]",
+    /// "[ERR]", "[WARN]") otherwise, for themes that draw emoji as boxes
+    #[serde(default = "default_use_emoji")]
+    pub use_emoji: bool,
+
+    /// Longest line printed at once, in bytes (0 = no limit); longer lines
+    /// are wrapped between words into several prints
+    #[serde(default = "default_max_line_length")]
+    pub max_line_length: usize,
+
+    /// Whether to also estimate the jumps from the case back to the home origin
+    #[serde(default)]
+    pub show_return_estimate: bool,
+
+    /// Whether notices for deep-space cases name the nearest populated
+    /// system within 100 LY of the case, at the cost of a lookup per notice
+    #[serde(default)]
+    pub show_nearest_station: bool,
+
+    /// System that notices and /sysinfo measure the target's distance from,
+    /// e.g. "Fuelum" or "Shinrarta Dezhra"
+    #[serde(default = "default_reference_system")]
+    pub reference_system: String,
+
+    /// Add heuristic notes about the route (e.g. steep vertical routes)
+    #[serde(default)]
+    pub show_route_hints: bool,
+
+    /// Report routes needing more jumps than this as not feasible for the ship
+    #[serde(default)]
+    pub infeasible_jump_count: Option<u32>,
+
+    /// RATSIGNALs for routes with fewer jumps than this get no notice
+    #[serde(default)]
+    pub min_jumps_to_announce: u32,
+
+    /// Seconds a case number is remembered, so re-posted RATSIGNALs get no
+    /// second notice (0 answers every time)
+    #[serde(default = "default_dedupe_window_seconds")]
+    pub dedupe_window_seconds: u64,
+
+    /// Say so when a relay bot closes a case (closed cases are forgotten
+    /// either way, so the number can be announced again)
+    #[serde(default)]
+    pub announce_case_closed: bool,
+
+    /// Least seconds between two RATSIGNAL notices (0 shows every notice
+    /// straight away)
+    #[serde(default)]
+    pub announce_cooldown_seconds: u64,
+
+    /// What happens to notices during the cooldown: "queue" shows them
+    /// later, "drop" only logs them
+    #[serde(default)]
+    pub cooldown_policy: CooldownPolicy,
+
+    /// Route to the landmark named in a RATSIGNAL when the case's system
+    /// isn't in EDSM
+    #[serde(default = "default_route_via_landmark")]
+    pub route_via_landmark: bool,
+
+    /// Where RATSIGNAL notices go: "console" prints them for you only,
+    /// "channel" says them in the channel the signal came from. Command
+    /// responses are always printed locally
+    #[serde(default)]
+    pub output_mode: OutputMode,
+
+    /// Only report boosted routes, never fall back to a direct route
+    #[serde(default)]
+    pub require_boost: bool,
+
+    /// Report "you're already in X" instead of a 0-jump route when origin is the target
+    #[serde(default = "default_abort_on_same_system")]
+    pub abort_on_same_system: bool,
+
+    /// Platforms whose RATSIGNALs get a response ("PC", "XB", "PS"); empty for all
+    #[serde(default)]
+    pub platforms: Vec<String>,
+
+    /// Nicks whose RATSIGNALs get a response, e.g. test or alternate relay bots
+    #[serde(default = "default_trigger_nicks")]
+    pub trigger_nicks: Vec<String>,
+
+    /// Match `trigger_nicks` regardless of case
+    #[serde(default)]
+    pub trigger_nicks_ignore_case: bool,
+
+    /// Pick routes by fewest "jumps" or shortest estimated "time"
+    #[serde(default)]
+    pub optimize_for: RouteOptimization,
+
+    /// Average time per jump in seconds, for time estimates and when
+    /// optimizing for time
+    #[serde(default = "default_seconds_per_jump")]
+    pub seconds_per_jump: f64,
+
+    /// Extra seconds charged per supercharged jump, for time estimates and
+    /// when optimizing for time
+    #[serde(default = "default_boost_penalty_seconds")]
+    pub boost_penalty_seconds: f64,
+
+    /// Further seconds charged per neutron supercharge, in time estimates and
+    /// when optimizing for time, so the neutron highway only wins once it
+    /// saves real time
+    #[serde(default = "default_neutron_penalty_seconds")]
+    pub neutron_penalty_seconds: f64,
+
+    /// Extra seconds per fuel scoop stop in time estimates and when
+    /// optimizing for time, for ships with a `fuel_capacity`
+    #[serde(default = "default_scoop_stop_seconds")]
+    pub scoop_stop_seconds: f64,
+
+    /// Plan with the range growing from laden towards `max_jump_range` as
+    /// the route burns fuel, for ships with a `max_jump_range` and a
+    /// `fuel_capacity`
+    #[serde(default)]
+    pub interpolate_jump_range: bool,
+
+    /// Premium FSD injections carried, spent on a route's longest gaps (or
+    /// replaced by a cheaper grade that saves as many jumps)
+    #[serde(default)]
+    pub premium_injections: u32,
+
+    /// Extra attempts for the EDSM commander position lookup before falling back
+    #[serde(default = "default_cmdr_location_retries")]
+    pub cmdr_location_retries: u32,
+
+    /// Seconds a looked-up commander position is trusted before asking again
+    #[serde(default = "default_location_cache_seconds")]
+    pub location_cache_seconds: u64,
+
+    /// Attempts per EDSM request when EDSM answers 429 or 5xx
+    #[serde(default = "default_edsm_retry_attempts")]
+    pub edsm_retry_attempts: u32,
+
+    /// Delay before the first EDSM retry in milliseconds, doubled per retry
+    #[serde(default = "default_edsm_retry_base_delay_ms")]
+    pub edsm_retry_base_delay_ms: u64,
+
+    /// Also resolve systems EDSM flags as hidden (e.g. duplicates)
+    #[serde(default)]
+    pub include_hidden: bool,
+
+    /// Suggest similarly named systems when EDSM doesn't know one
+    #[serde(default = "default_suggest_system_names")]
+    pub suggest_system_names: bool,
+
+    /// Keep looked-up system coordinates on disk between sessions
+    #[serde(default = "default_persist_cache")]
+    pub persist_cache: bool,
+
+    /// Look systems up in the bundled list instead of EDSM, with no network access
+    #[serde(default)]
+    pub offline_mode: bool,
+
+    /// Systems kept warm in the cache by a background refresh
+    #[serde(default)]
+    pub prefetch_systems: Vec<String>,
+
+    /// Journal folder or file whose latest `Loadout` supplies the active
+    /// ship's `max_jump_range`
+    #[serde(default)]
+    pub journal_path: Option<PathBuf>,
+
+    /// Where the commander's location is looked up, tried in order until one
+    /// knows it; sources that aren't configured are skipped
+    #[serde(default = "default_location_source_priority")]
+    pub location_source_priority: Vec<LocationSource>,
+
+    /// File every calculated route is appended to, as JSON Lines or (for a
+    /// `.csv` file) CSV
+    #[serde(default)]
+    pub route_log_path: Option<PathBuf>,
+
+    /// Localhost port for the `GET /route` HTTP endpoint; only served when
+    /// built with the `http-api` feature
+    #[serde(default)]
+    pub http_api_port: Option<u16>,
+
+    /// `tcp://host:port` or `ws://host:port/path` of a rat-bot relaying
+    /// signals, read as if the first trigger nick had said them in a channel
+    #[serde(default)]
+    pub signal_feed_url: Option<String>,
+
+    /// Minutes spent per case outside of jumping (briefing, fuel transfer, ...)
+    #[serde(default = "default_case_overhead_minutes")]
+    pub case_overhead_minutes: f64,
+
+    /// Plan routes with the built-in calculator or Spansh's neutron plotter
+    #[serde(default)]
+    pub route_backend: RouteBackend,
+
+    /// Spansh efficiency (1-100), lower values trade detours for fewer jumps
+    #[serde(default = "default_spansh_efficiency")]
+    pub spansh_efficiency: u8,
 }
 
 /// Ship configuration
@@ -59,12 +410,237 @@ pub struct ShipConfig {
     /// Ship name/type (e.g., "Anaconda", "Asp Explorer")
     pub name: String,
 
-    /// Laden jump range in light years (realistic jump range with cargo/fuel)
+    /// Laden jump range in light years (realistic jump range with cargo/fuel);
+    /// 0 when left to `ship_preset`
+    #[serde(default)]
     pub laden_jump_range: f64,
 
     /// Optional: Maximum jump range (empty/optimized)
     #[serde(default)]
     pub max_jump_range: Option<f64>,
+
+    /// Optional: FSD class (2-7), for fuel estimates
+    #[serde(default)]
+    pub fsd_class: Option<u8>,
+
+    /// Optional: FSD rating (A-E), for fuel estimates
+    #[serde(default)]
+    pub fsd_rating: Option<char>,
+
+    /// Optional: total ship mass in tons, for fuel estimates
+    #[serde(default)]
+    pub ship_mass: Option<f64>,
+
+    /// Optional: optimal mass of an engineered FSD in tons, for fuel
+    /// estimates (default: the stock drive's)
+    #[serde(default)]
+    pub fsd_optimal_mass: Option<f64>,
+
+    /// Optional: main fuel tank capacity in tons, to warn about fuel scooping
+    #[serde(default)]
+    pub fuel_capacity: Option<f64>,
+}
+
+impl ShipConfig {
+    /// The FSD fuel model, if the drive and mass are configured
+    ///
+    /// Errors if only some of `fsd_class`, `fsd_rating` and `ship_mass` are
+    /// set, or `fsd_optimal_mass` is set without them.
+    pub fn fsd_profile(&self) -> Result<Option<FsdProfile>> {
+        match (self.fsd_class, self.fsd_rating, self.ship_mass) {
+            (Some(class), Some(rating), Some(mass)) => Ok(Some(
+                FsdProfile::new(class, rating, mass)?.with_optimal_mass(self.fsd_optimal_mass),
+            )),
+            (None, None, None) if self.fsd_optimal_mass.is_some() => Err(anyhow!(
+                "fsd_optimal_mass needs fsd_class, fsd_rating and ship_mass"
+            )),
+            (None, None, None) => Ok(None),
+            _ => Err(anyhow!(
+                "Set all of fsd_class, fsd_rating and ship_mass (or none of them)"
+            )),
+        }
+    }
+}
+
+impl Config {
+    /// Retry policy for EDSM requests
+    pub fn edsm_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.edsm_retry_attempts,
+            base_delay: Duration::from_millis(self.edsm_retry_base_delay_ms),
+        }
+    }
+
+    /// Template for RATSIGNAL notices
+    pub fn ratsignal_template(&self) -> &str {
+        match &self.ratsignal_format {
+            Some(format) => format,
+            None if self.result_format == default_result_format() => DEFAULT_RATSIGNAL_FORMAT,
+            None => &self.result_format,
+        }
+    }
+
+    /// The regexes recognizing RATSIGNALs, in the order they are tried:
+    /// `ratsignal_pattern` alone, or else the built-in formats
+    ///
+    /// Errors if the pattern doesn't compile or lacks a required group.
+    pub fn ratsignal_regexes(&self) -> Result<Vec<Regex>> {
+        match self.ratsignal_pattern.as_deref() {
+            Some(pattern) => Ok(vec![ratsignal_regex(pattern)?]),
+            None => BUILTIN_RATSIGNAL_PATTERNS
+                .iter()
+                .map(|pattern| ratsignal_regex(pattern))
+                .collect(),
+        }
+    }
+
+    /// Template for /route responses
+    pub fn route_template(&self) -> &str {
+        self.route_format.as_deref().unwrap_or(&self.result_format)
+    }
+
+    /// Whether RATSIGNALs for a platform get a response
+    ///
+    /// "XBOX" counts as "XB" and "PS4"/"PS5" as "PS"; signals with an unknown
+    /// platform always get one.
+    pub fn responds_to_platform(&self, platform: &str) -> bool {
+        if self.platforms.is_empty() || platform == "Unknown" {
+            return true;
+        }
+        let platform = platform_family(platform);
+        self.platforms
+            .iter()
+            .any(|allowed| platform_family(allowed) == platform)
+    }
+
+    /// Whether messages from `sender` are checked for RATSIGNALs
+    pub fn is_trigger_nick(&self, sender: &str) -> bool {
+        is_trigger_nick(&self.trigger_nicks, self.trigger_nicks_ignore_case, sender)
+    }
+
+    /// The active ship profile
+    ///
+    /// Falls back to the first profile if `active_ship` names none of them.
+    pub fn ship(&self) -> &ShipConfig {
+        static NO_SHIP: OnceLock<ShipConfig> = OnceLock::new();
+
+        self.find_ship(&self.active_ship)
+            .or(self.ships.first())
+            .unwrap_or_else(|| NO_SHIP.get_or_init(ShipConfig::default))
+    }
+
+    /// Look up a ship profile by name (case-insensitive)
+    pub fn find_ship(&self, name: &str) -> Option<&ShipConfig> {
+        find_ship(&self.ships, name)
+    }
+
+    /// Fill in `ship_preset`'s range for ships configured without one, or
+    /// add the preset as the only ship when there are none
+    ///
+    /// Ranges configured for a ship are kept. Errors for an unknown preset.
+    pub fn apply_ship_preset(&mut self) -> Result<()> {
+        let Some(name) = &self.ship_preset else {
+            return Ok(());
+        };
+        let preset = presets::find_preset(name).ok_or_else(|| {
+            anyhow!(
+                "Unknown ship_preset '{name}', choose one of: {}",
+                presets::preset_names()
+            )
+        })?;
+
+        if self.ships.is_empty() {
+            self.ships.push(preset.ship());
+        }
+        for ship in self
+            .ships
+            .iter_mut()
+            .filter(|ship| ship.laden_jump_range == 0.0)
+        {
+            ship.laden_jump_range = preset.laden_jump_range;
+        }
+        Ok(())
+    }
+
+    /// Replace the API keys with those set in [`EDSM_API_KEY_ENV`] and
+    /// [`INARA_API_KEY_ENV`]; an empty variable counts as unset
+    pub fn apply_env_api_keys(&mut self) {
+        for (variable, key) in [
+            (EDSM_API_KEY_ENV, &mut self.edsm_api_key),
+            (INARA_API_KEY_ENV, &mut self.inara_api_key),
+        ] {
+            if let Some(value) = env_api_key(variable) {
+                info!("Using the API key from {variable}");
+                *key = Some(value);
+            }
+        }
+    }
+}
+
+/// Environment variable with the EDSM API key, taking precedence over `edsm_api_key`
+pub const EDSM_API_KEY_ENV: &str = "EDSM_API_KEY";
+
+/// Environment variable with the Inara API key, taking precedence over `inara_api_key`
+pub const INARA_API_KEY_ENV: &str = "INARA_API_KEY";
+
+/// An API key set in the environment, if it isn't blank
+fn env_api_key(variable: &str) -> Option<String> {
+    std::env::var(variable)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Compile a RATSIGNAL pattern, checking it captures the required groups
+fn ratsignal_regex(pattern: &str) -> Result<Regex> {
+    let regex = Regex::new(pattern).map_err(|e| anyhow!("Invalid ratsignal_pattern: {e}"))?;
+
+    let names: Vec<&str> = regex.capture_names().flatten().collect();
+    if let Some(missing) = REQUIRED_RATSIGNAL_GROUPS
+        .iter()
+        .find(|group| !names.contains(group))
+    {
+        return Err(anyhow!(
+            "ratsignal_pattern needs a (?P<{missing}>...) group"
+        ));
+    }
+    Ok(regex)
+}
+
+/// Look up a ship profile by name (case-insensitive) in a list of profiles
+pub fn find_ship<'a>(ships: &'a [ShipConfig], name: &str) -> Option<&'a ShipConfig> {
+    ships
+        .iter()
+        .find(|ship| ship.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Whether `sender` is one of the trigger nicks, ignoring case if asked to
+pub fn is_trigger_nick(trigger_nicks: &[String], ignore_case: bool, sender: &str) -> bool {
+    trigger_nicks.iter().any(|nick| {
+        if ignore_case {
+            nick.eq_ignore_ascii_case(sender)
+        } else {
+            nick == sender
+        }
+    })
+}
+
+/// Accept either a single `[ship]` table or a list of `[[ships]]` tables
+fn deserialize_ships<'de, D>(deserializer: D) -> std::result::Result<Vec<ShipConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Ships {
+        One(ShipConfig),
+        Many(Vec<ShipConfig>),
+    }
+
+    Ok(match Ships::deserialize(deserializer)? {
+        Ships::One(ship) => vec![ship],
+        Ships::Many(ships) => ships,
+    })
 }
 
 impl Default for Config {
@@ -72,14 +648,77 @@ impl Default for Config {
         Self {
             cmdr_name: String::new(),
             edsm_api_key: None,
-            ship: ShipConfig::default(),
+            inara_api_key: None,
+            inara_requests_per_minute: default_inara_requests_per_minute(),
+            fallback_origin: default_fallback_origin(),
+            on_missing_location: MissingLocationPolicy::default(),
+            ships: vec![ShipConfig::default()],
+            active_ship: String::new(),
+            ship_range_tolerance_percent: default_ship_range_tolerance_percent(),
+            ship_preset: None,
             cache_timeout_seconds: default_cache_timeout(),
+            plan_timeout_seconds: default_plan_timeout(),
             debug_mode: false,
             neutron_highway_threshold_ly: default_neutron_threshold(),
             white_dwarf_threshold_ly: default_white_dwarf_threshold(),
+            neutron_multiplier: default_neutron_multiplier(),
+            white_dwarf_multiplier: default_white_dwarf_multiplier(),
+            max_detour_ly: default_max_detour_ly(),
+            max_detour_percent: 0.0,
+            prefer_populated: false,
+            populated_detour_jumps: default_populated_detour_jumps(),
+            corridor_bucket_ly: default_corridor_bucket_ly(),
             result_format: default_result_format(),
+            ratsignal_format: None,
+            ratsignal_pattern: None,
+            route_format: None,
             show_fuel_estimates: default_show_fuel(),
             show_time_estimates: default_show_time(),
+            number_format: NumberFormat::default(),
+            jump_rounding: JumpRounding::default(),
+            use_emoji: default_use_emoji(),
+            max_line_length: default_max_line_length(),
+            show_return_estimate: false,
+            show_nearest_station: false,
+            reference_system: default_reference_system(),
+            show_route_hints: false,
+            infeasible_jump_count: None,
+            min_jumps_to_announce: 0,
+            dedupe_window_seconds: default_dedupe_window_seconds(),
+            announce_case_closed: false,
+            announce_cooldown_seconds: 0,
+            cooldown_policy: CooldownPolicy::default(),
+            route_via_landmark: default_route_via_landmark(),
+            output_mode: OutputMode::default(),
+            require_boost: false,
+            abort_on_same_system: default_abort_on_same_system(),
+            platforms: Vec::new(),
+            trigger_nicks: default_trigger_nicks(),
+            trigger_nicks_ignore_case: false,
+            optimize_for: RouteOptimization::default(),
+            seconds_per_jump: default_seconds_per_jump(),
+            boost_penalty_seconds: default_boost_penalty_seconds(),
+            neutron_penalty_seconds: default_neutron_penalty_seconds(),
+            scoop_stop_seconds: default_scoop_stop_seconds(),
+            interpolate_jump_range: false,
+            premium_injections: 0,
+            cmdr_location_retries: default_cmdr_location_retries(),
+            location_cache_seconds: default_location_cache_seconds(),
+            edsm_retry_attempts: default_edsm_retry_attempts(),
+            edsm_retry_base_delay_ms: default_edsm_retry_base_delay_ms(),
+            include_hidden: false,
+            suggest_system_names: default_suggest_system_names(),
+            persist_cache: default_persist_cache(),
+            offline_mode: false,
+            prefetch_systems: Vec::new(),
+            journal_path: None,
+            location_source_priority: default_location_source_priority(),
+            route_log_path: None,
+            http_api_port: None,
+            signal_feed_url: None,
+            case_overhead_minutes: default_case_overhead_minutes(),
+            route_backend: RouteBackend::default(),
+            spansh_efficiency: default_spansh_efficiency(),
         }
     }
 }
@@ -90,6 +729,11 @@ impl Default for ShipConfig {
             name: "Unknown Ship".to_string(),
             laden_jump_range: 30.0, // Reasonable default
             max_jump_range: None,
+            fsd_class: None,
+            fsd_rating: None,
+            ship_mass: None,
+            fsd_optimal_mass: None,
+            fuel_capacity: None,
         }
     }
 }
@@ -99,11 +743,28 @@ fn default_cache_timeout() -> u64 {
     300
 } // 5 minutes
 fn default_neutron_threshold() -> f64 {
-    500.0
+    DEFAULT_NEUTRON_THRESHOLD_LY
+}
+
+fn default_plan_timeout() -> u64 {
+    20
 }
 fn default_white_dwarf_threshold() -> f64 {
-    150.0
+    DEFAULT_WHITE_DWARF_THRESHOLD_LY
 }
+fn default_neutron_multiplier() -> f64 {
+    4.0
+}
+fn default_white_dwarf_multiplier() -> f64 {
+    1.5
+}
+fn default_max_detour_ly() -> f64 {
+    50.0
+}
+/// RATSIGNAL notice used unless `ratsignal_format` or `result_format` is set,
+/// saying where the case is and where the route starts
+pub const DEFAULT_RATSIGNAL_FORMAT: &str = "🚀 {case}: {jumps} jumps to {system} ({distance:.1}ly, {region}) via {route} route (from {from} with {range:.1}ly range)";
+
 fn default_result_format() -> String {
     "🚀 {jumps} jumps to {system} ({distance:.1}ly) via {route}".to_string()
 }
@@ -113,36 +774,153 @@ fn default_show_fuel() -> bool {
 fn default_show_time() -> bool {
     false
 }
+/// Platform tag without its console generation, e.g. "PS" for "PS5"
+fn platform_family(platform: &str) -> String {
+    let platform = platform.trim().to_ascii_uppercase();
+    if platform == "XBOX" {
+        return "XB".to_string();
+    }
+    platform
+        .trim_end_matches(|c: char| c.is_ascii_digit())
+        .to_string()
+}
+
+fn default_dedupe_window_seconds() -> u64 {
+    300
+}
+fn default_trigger_nicks() -> Vec<String> {
+    vec!["MechaSqueak[BOT]".to_string()]
+}
+fn default_route_via_landmark() -> bool {
+    true
+}
+
+fn default_populated_detour_jumps() -> u32 {
+    1
+}
+
+fn default_corridor_bucket_ly() -> f64 {
+    crate::edsm::DEFAULT_CORRIDOR_BUCKET_LY
+}
+
+fn default_reference_system() -> String {
+    "Sol".to_string()
+}
+
+fn default_use_emoji() -> bool {
+    true
+}
+
+fn default_max_line_length() -> usize {
+    // An IRC line is 512 bytes, sender prefix included
+    400
+}
+
+fn default_location_source_priority() -> Vec<LocationSource> {
+    vec![
+        LocationSource::Journal,
+        LocationSource::Edsm,
+        LocationSource::Inara,
+    ]
+}
+
+fn default_abort_on_same_system() -> bool {
+    true
+}
+fn default_fallback_origin() -> String {
+    crate::planner::DEFAULT_FALLBACK_ORIGIN.to_string()
+}
+
+fn default_cmdr_location_retries() -> u32 {
+    1
+}
+fn default_location_cache_seconds() -> u64 {
+    crate::edsm::DEFAULT_LOCATION_CACHE_SECONDS
+}
+fn default_edsm_retry_attempts() -> u32 {
+    3
+}
+fn default_edsm_retry_base_delay_ms() -> u64 {
+    500
+}
+fn default_case_overhead_minutes() -> f64 {
+    10.0
+}
+fn default_spansh_efficiency() -> u8 {
+    60
+}
+fn default_inara_requests_per_minute() -> u32 {
+    crate::inara::DEFAULT_REQUESTS_PER_MINUTE
+}
+
+fn default_ship_range_tolerance_percent() -> f64 {
+    5.0
+}
+fn default_persist_cache() -> bool {
+    true
+}
+fn default_suggest_system_names() -> bool {
+    true
+}
+fn default_seconds_per_jump() -> f64 {
+    120.0
+}
+fn default_boost_penalty_seconds() -> f64 {
+    60.0
+}
+fn default_neutron_penalty_seconds() -> f64 {
+    90.0
+}
+fn default_scoop_stop_seconds() -> f64 {
+    180.0
+}
 
 /// Load configuration from file or create default
+///
+/// A freshly created default configuration has no CMDR name, so this returns
+/// [`ConfigError::MissingCmdrName`] until the user fills it in.
 pub fn load_config() -> Result<Config> {
     let config_path = get_config_path()?;
 
     if config_path.exists() {
-        info!("Loading configuration from: {config_path:?}");
-        let config_content = fs::read_to_string(&config_path)?;
-        let config: Config = toml::from_str(&config_content)
-            .map_err(|e| anyhow!("Failed to parse config file: {}", e))?;
-
-        // Validate required settings
-        if config.cmdr_name.is_empty() {
-            warn!("CMDR name not configured. Please set it in the config file.");
-        }
-
-        if config.ship.laden_jump_range <= 0.0 {
-            warn!("Invalid ship jump range configured. Using default.");
-        }
-
-        Ok(config)
+        load_config_from(&config_path)
     } else {
         info!("Configuration file not found, creating default: {config_path:?}");
         let config = Config::default();
         save_config(&config)?;
-        Ok(config)
+        Err(ConfigError::MissingCmdrName(config_path.display().to_string()).into())
+    }
+}
+
+/// Load configuration from a specific file
+///
+/// `EDSM_API_KEY` and `INARA_API_KEY`, when set, override the file's API keys.
+pub fn load_config_from(config_path: &Path) -> Result<Config> {
+    info!("Loading configuration from: {config_path:?}");
+    let config_content = fs::read_to_string(config_path)?;
+    let mut config: Config = toml::from_str(&config_content)
+        .map_err(|e| anyhow!("Failed to parse config file: {}", e))?;
+    config.apply_ship_preset()?;
+    config.apply_env_api_keys();
+
+    // Validate required settings
+    if config.cmdr_name.trim().is_empty() {
+        return Err(ConfigError::MissingCmdrName(config_path.display().to_string()).into());
+    }
+
+    if config.ship().laden_jump_range <= 0.0 {
+        warn!("Invalid ship jump range configured. Using default.");
     }
+
+    config.ratsignal_regexes()?;
+
+    Ok(config)
 }
 
 /// Save configuration to file
+///
+/// API keys that came from the environment aren't written out: the file
+/// keeps whatever keys it had.
 pub fn save_config(config: &Config) -> Result<()> {
     let config_path = get_config_path()?;
 
@@ -151,19 +929,99 @@ pub fn save_config(config: &Config) -> Result<()> {
         fs::create_dir_all(parent)?;
     }
 
-    let config_content = toml::to_string_pretty(config)?;
+    let config = without_env_api_keys(config, &config_path);
+    let config_content = toml::to_string_pretty(&config)?;
     fs::write(&config_path, config_content)?;
 
     info!("Configuration saved to: {config_path:?}");
     Ok(())
 }
 
+/// `config` with any API key taken from the environment put back to the
+/// one in the file at `config_path` (none if there isn't a file)
+fn without_env_api_keys(config: &Config, config_path: &Path) -> Config {
+    let mut config = config.clone();
+    let from_env = |variable: &str, key: &Option<String>| {
+        key.is_some() && env_api_key(variable).as_ref() == key.as_ref()
+    };
+    let edsm_from_env = from_env(EDSM_API_KEY_ENV, &config.edsm_api_key);
+    let inara_from_env = from_env(INARA_API_KEY_ENV, &config.inara_api_key);
+    if !edsm_from_env && !inara_from_env {
+        return config;
+    }
+
+    let file: Option<toml::Table> = fs::read_to_string(config_path)
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok());
+    let file_key = |name: &str| {
+        file.as_ref()
+            .and_then(|file| file.get(name))
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+    };
+    if edsm_from_env {
+        config.edsm_api_key = file_key("edsm_api_key");
+    }
+    if inara_from_env {
+        config.inara_api_key = file_key("inara_api_key");
+    }
+    config
+}
+
+/// Environment variable that points at a specific config file
+pub const CONFIG_PATH_ENV: &str = "EDJC_CONFIG";
+
+/// Config file path set explicitly (e.g. from a `--config` flag)
+static CONFIG_PATH_OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Use a specific config file instead of the derived location
+pub fn set_config_path_override(path: Option<PathBuf>) {
+    *CONFIG_PATH_OVERRIDE
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = path;
+}
+
+/// Remove a `--config <path>` flag from command line arguments and apply it
+pub fn apply_config_flag(args: &mut Vec<String>) -> Result<()> {
+    if let Some(index) = args.iter().position(|arg| arg == "--config") {
+        if index + 1 >= args.len() {
+            return Err(anyhow!("--config requires a path"));
+        }
+        let path = args.remove(index + 1);
+        args.remove(index);
+        set_config_path_override(Some(PathBuf::from(path)));
+    }
+    Ok(())
+}
+
 /// Get the configuration file path
+///
+/// Precedence: an explicit override (`--config`), then the `EDJC_CONFIG`
+/// environment variable, then the platform config directory.
 pub fn get_config_path() -> Result<PathBuf> {
+    if let Some(path) = CONFIG_PATH_OVERRIDE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+    {
+        return Ok(path);
+    }
+
+    if let Ok(path) = std::env::var(CONFIG_PATH_ENV) {
+        if !path.trim().is_empty() {
+            return Ok(PathBuf::from(path));
+        }
+    }
+
     let config_dir = get_config_directory()?;
     Ok(config_dir.join("edjc.toml"))
 }
 
+/// Get the path of the persisted coordinate cache, next to the configuration file
+pub fn get_cache_path() -> Result<PathBuf> {
+    Ok(get_config_path()?.with_file_name("edsm_cache.json"))
+}
+
 /// Get the configuration directory
 fn get_config_directory() -> Result<PathBuf> {
     // Try to use XDG config directory on Unix, AppData on Windows
@@ -194,36 +1052,278 @@ pub fn create_sample_config() -> Result<()> {
 # 
 # This plugin uses EDSM (Elite Dangerous Star Map) for system coordinates
 # and jump calculations. No API key is required for EDSM.
+#
+# To use a different file, set EDJC_CONFIG=/path/to/edjc.toml
+# (or pass --config /path/to/edjc.toml to the standalone tools).
+#
+# API keys can be kept out of this file: EDSM_API_KEY and INARA_API_KEY in
+# the environment take precedence over edsm_api_key and inara_api_key.
 
 # Your CMDR name (required) - this is your Elite Dangerous pilot name
 cmdr_name = "YOUR_CMDR_NAME"
 
+# Optional: Inara API key, used when EDSM doesn't know your location
+# inara_api_key = "your-inara-key"
+# Most requests per minute sent to Inara (0 = unlimited)
+inara_requests_per_minute = 10
+
+# System routes start from when your location is unknown (e.g. "Fuelum")
+fallback_origin = "Sol"
+
+# RATSIGNALs while your location is unknown: "fallback" routes from
+# fallback_origin, "skip" says the origin is unknown, "ask" asks for it
+on_missing_location = "fallback"
+
+# /verify-ship warns when your ship's range on Inara is more than this many
+# percent away from its max_jump_range (default: 5.0)
+ship_range_tolerance_percent = 5.0
+
+# Don't know your laden range? A built-in preset fills it in for a ship
+# without laden_jump_range, or stands in for the ship when there is no
+# [ship] table: mandalay-explorer, anaconda-explorer, dbx-explorer,
+# krait-phantom-explorer, asp-explorer, krait-mk2-combat, anaconda-combat,
+# python-combat, fdl-combat
+# ship_preset = "dbx-explorer"
+
 # Ship configuration
+# For several ships, use one [[ships]] table per ship instead of [ship] and
+# pick one with a top-level active_ship = "<name>" (or /ship <name> in HexChat)
 [ship]
 # Ship name/type (for display purposes)
 name = "Asp Explorer"
 # Laden jump range in light years (your realistic jump range with cargo/fuel)
 laden_jump_range = 35.0
-# Optional: Maximum jump range when empty/optimized
+# Optional: Maximum jump range when empty/optimized (used by /route --optimized)
 # max_jump_range = 60.0
+# Optional: FSD and total ship mass (hull, modules, fuel, cargo) for accurate
+# fuel estimates; set all three or none
+# fsd_class = 5
+# fsd_rating = "A"
+# ship_mass = 420.0
+# Optional: optimal mass of an engineered FSD in tons (default: the stock
+# drive's)
+# fsd_optimal_mass = 1627.5
+# Optional: main fuel tank capacity in tons, for /fuel's scooping advice and
+# the scoop stops shown with the fuel estimate
+# fuel_capacity = 32.0
 
 # Cache timeout in seconds (default: 300 = 5 minutes)
 cache_timeout_seconds = 300
 
+# Seconds a route may take to plan, all EDSM lookups included, before it is
+# given up instead of stalling HexChat (default: 20)
+plan_timeout_seconds = 20
+
 # Enable debug logging (default: false)
 debug_mode = false
 
 # Distance thresholds for route suggestions
+# Shorter routes don't consider neutron / white dwarf supercharges at all.
+# Routes at least neutron_highway_threshold_ly long are plotted through real
+# neutron stars found along the way (a few extra EDSM lookups)
 neutron_highway_threshold_ly = 500.0
 white_dwarf_threshold_ly = 150.0
 
+# Jump range multipliers when supercharging at a neutron star / white dwarf
+# (lower them to plan conservatively)
+neutron_multiplier = 4.0
+white_dwarf_multiplier = 1.5
+
+# Neutron stars further than this off the straight line are never routed
+# through, so a small boost doesn't send you hundreds of LY sideways.
+# max_detour_percent allows a share of the route's length instead, whichever
+# is larger (0 = max_detour_ly only)
+max_detour_ly = 50.0
+max_detour_percent = 0.0
+
+# Prefer neutron stars in populated systems, where a pilot running dry can be
+# reached more easily, at up to populated_detour_jumps extra jumps per star
+# (0 = only between equally good stars; extra lookups)
+prefer_populated = false
+populated_detour_jumps = 1
+
+# Corridor searches snap their EDSM sphere queries to a grid this many LY
+# apart, so routes to nearby cases reuse the same cached spheres
+# (0 = query exactly along each route)
+corridor_bucket_ly = 20.0
+
 # Result format string
-# Available placeholders: {jumps}, {system}, {distance}, {route}
+# Available placeholders: {jumps}, {system}, {distance}, {route}, {from}, {to},
+# {fuel}, {time}, {range}, {region} and (RATSIGNAL notices only) {case}
+# Use /preview (or `route --preview`) to see how it renders
 result_format = "🚀 {jumps} jumps to {system} ({distance:.1}ly) via {route}"
+# Separate formats for RATSIGNAL notices and /route, when they should differ
+# (notices without {case} are prefixed with the case label). Unless either
+# format is set, notices also give the region, origin and jump range.
+# ratsignal_format = "🚀 {case}: {jumps}j to {system} ({region})"
+# route_format = "🚀 {jumps} jumps to {system} ({distance:.1}ly, {region}) via {route} from {from} with {range}ly range"
+
+# Recognize RATSIGNALs from relay bots with a different format. Needs the named
+# groups case, cmdr and system; tags (platform/mode), info and language are optional
+# ratsignal_pattern = 'Case #(?P<case>\d+) (?P<tags>\w+) - CMDR (?P<cmdr>.+?) - System: "(?P<system>[^"]+)"'
 
 # Show additional estimates
 show_fuel_estimates = false
 show_time_estimates = false
+
+# How numbers are written: "plain" (25900.0), "en" (25,900.0) or "de" (25.900,0)
+number_format = "plain"
+
+# Jump count of a direct route whose last jump is only partly needed:
+# "ceil" (always round up), "nearest", or "range" ("7–8 jumps" when the last
+# jump is nearly complete)
+jump_rounding = "ceil"
+
+# Emoji in notices and responses; false writes [OK], [ERR] and [WARN] instead,
+# for themes and terminals that show emoji as boxes
+use_emoji = true
+
+# Longest line printed at once, in bytes (0 = no limit); longer responses
+# are wrapped between words into several lines
+max_line_length = 400
+
+# Also estimate the jumps from the case back to the home origin (extra lookup)
+show_return_estimate = false
+
+# Name the nearest populated system to deep-space cases (extra lookup)
+show_nearest_station = false
+
+# Report the target's distance from this system, e.g. "Fuelum"
+reference_system = "Sol"
+
+# Add heuristic notes about the route (e.g. "steep vertical route")
+show_route_hints = false
+
+# Report routes needing more jumps than this as "not feasible with current ship"
+# infeasible_jump_count = 500
+
+# Stay quiet about cases closer than this many jumps (0 = announce all)
+min_jumps_to_announce = 0
+
+# Answer a re-posted RATSIGNAL for the same case only after this many seconds
+# (0 = answer every time)
+dedupe_window_seconds = 300
+
+# Print "Case #N closed" when the relay bot closes a case; closed cases are
+# forgotten either way, so a new case with the same number is announced
+announce_case_closed = false
+
+# Wait at least this many seconds between two RATSIGNAL notices (0 = no wait)
+announce_cooldown_seconds = 0
+
+# Notices during the wait: "queue" shows them (the latest 10) once it's over,
+# "drop" only logs them
+cooldown_policy = "queue"
+
+# When a case's system isn't in EDSM, route to the landmark from its signal
+# ("Brown dwarf 51 LY from Fuelum") instead
+route_via_landmark = true
+
+# Where RATSIGNAL notices go: "console" prints them in your current tab only,
+# "channel" says them in the channel the signal came from. Command responses
+# are always printed locally
+output_mode = "console"
+
+# Only report boosted (neutron/white dwarf) routes, never a direct fallback
+require_boost = false
+
+# Say "you're already in X" instead of reporting a 0-jump route
+abort_on_same_system = true
+
+# Only respond to RATSIGNALs for these platforms ("PC", "XB", "PS"); empty
+# means all. Signals without a platform tag are always answered
+# platforms = ["PC"]
+platforms = []
+
+# Nicks whose RATSIGNALs are answered, e.g. a test relay bot; set
+# trigger_nicks_ignore_case to match them regardless of case
+trigger_nicks = ["MechaSqueak[BOT]"]
+trigger_nicks_ignore_case = false
+
+# Pick routes by fewest "jumps" (default) or shortest estimated "time"
+optimize_for = "jumps"
+# Time model for {time} estimates and when optimizing for time: seconds per
+# jump, extra per supercharge, and extra per scoop stop (counted for ships
+# with a fuel_capacity)
+seconds_per_jump = 120.0
+boost_penalty_seconds = 60.0
+scoop_stop_seconds = 180.0
+# Each neutron supercharge costs this much more in time estimates and when
+# optimizing for time (finding the star, the jet cone, heat), so short routes
+# stay direct
+neutron_penalty_seconds = 90.0
+
+# Plan with the range growing from laden towards the ship's max_jump_range as
+# the route burns fuel, rather than the laden range for every jump (needs the
+# ship's fuel_capacity to tell how much of the tank a route burns)
+interpolate_jump_range = false
+
+# Premium FSD injections (+100% range for one jump) you carry; routes spend
+# them on their longest gaps, naming a basic (+25%) or standard (+50%) one
+# instead where that saves as many jumps
+premium_injections = 0
+
+# Retry the commander position lookup this many times before falling back
+# (EDSM can briefly miss your position right after a jump)
+cmdr_location_retries = 1
+
+# Trust a looked-up commander position for this many seconds; system
+# coordinates are cached separately for an hour
+location_cache_seconds = 60
+
+# Attempts per EDSM request when EDSM is rate limiting (429) or failing (5xx),
+# backing off from edsm_retry_base_delay_ms (doubling) or as EDSM asks
+edsm_retry_attempts = 3
+edsm_retry_base_delay_ms = 500
+
+# Also resolve systems EDSM flags as hidden/duplicate (e.g. newly reported systems)
+include_hidden = false
+
+# When EDSM doesn't know a system, ask it for similarly named ones and add
+# "did you mean: ..." to the error (one more request per unknown system)
+suggest_system_names = true
+
+# Keep looked-up system coordinates in edsm_cache.json next to this file, so
+# they survive HexChat restarts (entries still expire after an hour)
+persist_cache = true
+
+# Route between a few bundled systems (Sol, Deciat, Maia, Colonia,
+# Sagittarius A*, Beagle Point) without any network access
+offline_mode = false
+
+# Systems to keep warm in the cache for the whole session (e.g. staging systems)
+# prefetch_systems = ["Fuelum", "Sol", "Colonia"]
+prefetch_systems = []
+
+# Elite Dangerous journal folder (or a single journal file). When set, the
+# MaxJumpRange of your latest Loadout replaces the ship's max_jump_range
+# journal_path = 'C:\Users\YOU\Saved Games\Frontier Developments\Elite Dangerous'
+
+# Where your current location comes from, tried in order until one knows it:
+# "journal" (your latest jump, needs journal_path), "edsm" and "inara" (needs
+# inara_api_key). Sources that aren't set up are skipped.
+location_source_priority = ["journal", "edsm", "inara"]
+
+# Record every calculated route (case, time, from, to, jumps, distance, route
+# type) in this file: one JSON object per line, or CSV for a .csv file
+# route_log_path = 'C:\Users\YOU\Documents\edjc-routes.jsonl'
+
+# Answer GET http://127.0.0.1:<port>/route?to=<system>&from=<system> with the
+# route as JSON, for overlays (plugins built with the http-api feature only)
+# http_api_port = 8791
+
+# Also read signals from a rat-bot relaying them: one per line over TCP
+# ("tcp://127.0.0.1:8792") or per WebSocket message ("ws://127.0.0.1:8792/")
+# signal_feed_url = "tcp://127.0.0.1:8792"
+
+# Minutes per case spent outside of jumping, used by /capacity
+case_overhead_minutes = 10.0
+
+# Plan routes with the "builtin" calculator or Spansh's neutron plotter ("spansh").
+# Spansh is accurate for long hauls but slower; failures fall back to builtin.
+route_backend = "builtin"
+# Spansh efficiency (1-100): lower allows bigger detours for fewer jumps
+spansh_efficiency = 60
 "#;
 
     // Create config directory if it doesn't exist
@@ -239,18 +1339,90 @@ show_time_estimates = false
 
 /// Validate configuration
 pub fn validate_config(config: &Config) -> Result<()> {
-    if config.cmdr_name.is_empty() {
-        return Err(anyhow!("CMDR name is required but not configured"));
+    if config.cmdr_name.trim().is_empty() {
+        return Err(ConfigError::MissingCmdrName("edjc.toml".to_string()).into());
+    }
+
+    if config.fallback_origin.trim().is_empty() {
+        return Err(anyhow!("Fallback origin system must not be empty"));
     }
 
-    if config.ship.laden_jump_range <= 0.0 {
-        return Err(anyhow!("Ship laden jump range must be greater than 0"));
+    if config.reference_system.trim().is_empty() {
+        return Err(anyhow!("Reference system must not be empty"));
+    }
+
+    if let Some(name) = &config.ship_preset {
+        if presets::find_preset(name).is_none() {
+            return Err(anyhow!(
+                "Unknown ship_preset '{name}', choose one of: {}",
+                presets::preset_names()
+            ));
+        }
+    }
+
+    if let Some(url) = &config.signal_feed_url {
+        crate::signal_feed::FeedUrl::parse(url)?;
+    }
+
+    if config.ships.is_empty() {
+        return Err(anyhow!("At least one ship profile must be configured"));
+    }
+
+    if config
+        .trigger_nicks
+        .iter()
+        .all(|nick| nick.trim().is_empty())
+    {
+        return Err(anyhow!("At least one trigger nick must be configured"));
+    }
+
+    config.ratsignal_regexes()?;
+
+    if !config.active_ship.trim().is_empty() && config.find_ship(&config.active_ship).is_none() {
+        return Err(anyhow!(
+            "Active ship '{}' does not match any ship profile",
+            config.active_ship
+        ));
+    }
+
+    for ship in &config.ships {
+        if ship.laden_jump_range <= 0.0 {
+            return Err(anyhow!("Ship laden jump range must be greater than 0"));
+        }
+
+        if let Some(max_jump_range) = ship.max_jump_range {
+            if max_jump_range < ship.laden_jump_range {
+                return Err(anyhow!(
+                    "Ship max jump range ({:.2} LY) cannot be smaller than laden jump range ({:.2} LY)",
+                    max_jump_range,
+                    ship.laden_jump_range
+                ));
+            }
+        }
+
+        if ship.fuel_capacity.is_some_and(|capacity| capacity <= 0.0) {
+            return Err(anyhow!("Ship fuel capacity must be greater than 0"));
+        }
+
+        if ship.fsd_optimal_mass.is_some_and(|mass| mass <= 0.0) {
+            return Err(anyhow!("FSD optimal mass must be greater than 0"));
+        }
+
+        ship.fsd_profile()?;
     }
 
     if config.cache_timeout_seconds == 0 {
         return Err(anyhow!("Cache timeout must be greater than 0"));
     }
 
+    if config.plan_timeout_seconds == 0 {
+        return Err(anyhow!("Plan timeout must be greater than 0"));
+    }
+
+    if config.ship_range_tolerance_percent < 0.0 {
+        return Err(anyhow!("Ship range tolerance must be non-negative"));
+    }
+
     if config.neutron_highway_threshold_ly < 0.0 {
         return Err(anyhow!("Neutron highway threshold must be non-negative"));
     }
@@ -259,13 +1431,56 @@ pub fn validate_config(config: &Config) -> Result<()> {
         return Err(anyhow!("White dwarf threshold must be non-negative"));
     }
 
+    if config.neutron_multiplier < 1.0 || config.white_dwarf_multiplier < 1.0 {
+        return Err(anyhow!("Supercharge multipliers must be at least 1.0"));
+    }
+
+    if config.max_detour_ly < 0.0 || config.max_detour_percent < 0.0 {
+        return Err(anyhow!("Detour limits must be non-negative"));
+    }
+
+    if config.neutron_penalty_seconds < 0.0 {
+        return Err(anyhow!("Neutron penalty must be non-negative"));
+    }
+
+    if !(0.0..=crate::edsm::SPHERE_RADIUS_LY).contains(&config.corridor_bucket_ly) {
+        return Err(anyhow!(
+            "Corridor bucket must be between 0 and {} LY",
+            crate::edsm::SPHERE_RADIUS_LY
+        ));
+    }
+
+    if config.infeasible_jump_count == Some(0) {
+        return Err(anyhow!("Infeasible jump count must be greater than 0"));
+    }
+
+    if let Some(limit) = config.infeasible_jump_count {
+        if config.min_jumps_to_announce > limit {
+            return Err(anyhow!(
+                "Minimum jumps to announce ({}) cannot exceed the infeasible jump count ({limit})",
+                config.min_jumps_to_announce
+            ));
+        }
+    }
+
+    if config.edsm_retry_attempts == 0 {
+        return Err(anyhow!("EDSM retry attempts must be at least 1"));
+    }
+
+    if config.case_overhead_minutes <= 0.0 {
+        return Err(anyhow!("Case overhead must be greater than 0 minutes"));
+    }
+
+    if !(1..=100).contains(&config.spansh_efficiency) {
+        return Err(anyhow!("Spansh efficiency must be between 1 and 100"));
+    }
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    #[allow(unused_imports)]
     use tempfile::tempdir;
 
     #[test]
@@ -280,11 +1495,12 @@ mod tests {
     fn test_config_validation() {
         let config = Config {
             cmdr_name: "TestCMDR".to_string(),
-            ship: ShipConfig {
+            ships: vec![ShipConfig {
                 name: "Test Ship".to_string(),
                 laden_jump_range: 30.0,
                 max_jump_range: Some(50.0),
-            },
+                ..Default::default()
+            }],
             ..Default::default()
         };
 
@@ -298,11 +1514,12 @@ mod tests {
 
         let config = Config {
             cmdr_name: "TestCMDR".to_string(),
-            ship: ShipConfig {
+            ships: vec![ShipConfig {
                 name: "Test Ship".to_string(),
                 laden_jump_range: 0.0, // Invalid jump range
                 max_jump_range: None,
-            },
+                ..Default::default()
+            }],
             ..Default::default()
         };
         assert!(validate_config(&config).is_err());
@@ -313,6 +1530,369 @@ mod tests {
             ..Default::default()
         };
         assert!(validate_config(&config).is_err());
+
+        let config = Config {
+            cmdr_name: "TestCMDR".to_string(),
+            plan_timeout_seconds: 0,
+            ..Default::default()
+        };
+        assert!(validate_config(&config).is_err());
+
+        let config = Config {
+            cmdr_name: "TestCMDR".to_string(),
+            fallback_origin: " ".to_string(),
+            ..Default::default()
+        };
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("Fallback origin"));
+
+        let config = Config {
+            cmdr_name: "TestCMDR".to_string(),
+            reference_system: String::new(),
+            ..Default::default()
+        };
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("Reference system"));
+
+        let config = Config {
+            cmdr_name: "TestCMDR".to_string(),
+            signal_feed_url: Some("wss://bot.example".to_string()),
+            ..Default::default()
+        };
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("wss://"));
+
+        let config = Config {
+            cmdr_name: "TestCMDR".to_string(),
+            trigger_nicks: Vec::new(),
+            ..Default::default()
+        };
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("trigger nick"));
+
+        let config = Config {
+            cmdr_name: "TestCMDR".to_string(),
+            min_jumps_to_announce: 600,
+            infeasible_jump_count: Some(500),
+            ..Default::default()
+        };
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("Minimum jumps to announce"));
+
+        let config = Config {
+            cmdr_name: "TestCMDR".to_string(),
+            max_detour_percent: -5.0,
+            ..Default::default()
+        };
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("Detour limits"));
+
+        let config = Config {
+            cmdr_name: "TestCMDR".to_string(),
+            neutron_penalty_seconds: -90.0,
+            ..Default::default()
+        };
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("Neutron penalty"));
+
+        for corridor_bucket_ly in [-1.0, 150.0, f64::NAN] {
+            let config = Config {
+                cmdr_name: "TestCMDR".to_string(),
+                corridor_bucket_ly,
+                ..Default::default()
+            };
+            let err = validate_config(&config).unwrap_err();
+            assert!(err.to_string().contains("Corridor bucket"));
+        }
+    }
+
+    #[test]
+    fn test_ratsignal_pattern_validation() {
+        let config = |pattern: &str| Config {
+            cmdr_name: "TestCMDR".to_string(),
+            ratsignal_pattern: Some(pattern.to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(Config::default().ratsignal_regexes().unwrap().len(), 2);
+        assert!(
+            validate_config(&config(r"#(?P<case>\d+) (?P<cmdr>\S+) in (?P<system>.+)")).is_ok()
+        );
+
+        let err = validate_config(&config(r"(?P<case>\d+")).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Invalid ratsignal_pattern"),
+            "{err}"
+        );
+
+        let err = validate_config(&config(r"#(?P<case>\d+) (?P<cmdr>\S+)")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ratsignal_pattern needs a (?P<system>...) group"
+        );
+    }
+
+    #[test]
+    fn test_jump_range_consistency() {
+        let ship_config = |laden_jump_range, max_jump_range| Config {
+            cmdr_name: "TestCMDR".to_string(),
+            ships: vec![ShipConfig {
+                name: "Test Ship".to_string(),
+                laden_jump_range,
+                max_jump_range,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert!(validate_config(&ship_config(30.0, Some(50.0))).is_ok());
+        assert!(validate_config(&ship_config(30.0, Some(30.0))).is_ok());
+        assert!(validate_config(&ship_config(30.0, None)).is_ok());
+
+        let err = validate_config(&ship_config(50.0, Some(30.0))).unwrap_err();
+        assert!(err.to_string().contains("max jump range"));
+    }
+
+    #[test]
+    fn test_missing_cmdr_name_fails_to_load() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("edjc.toml");
+        fs::write(&path, "[ship]\nname = \"DBX\"\nladen_jump_range = 50.0\n").unwrap();
+
+        let err = load_config_from(&path).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ConfigError>(),
+            Some(ConfigError::MissingCmdrName(_))
+        ));
+
+        fs::write(
+            &path,
+            "cmdr_name = \"   \"\n[ship]\nname = \"DBX\"\nladen_jump_range = 50.0\n",
+        )
+        .unwrap();
+        let err = load_config_from(&path).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ConfigError>(),
+            Some(ConfigError::MissingCmdrName(_))
+        ));
+
+        fs::write(
+            &path,
+            "cmdr_name = \"Whit3Arrow\"\n[ship]\nname = \"DBX\"\nladen_jump_range = 50.0\n",
+        )
+        .unwrap();
+        assert_eq!(load_config_from(&path).unwrap().cmdr_name, "Whit3Arrow");
+    }
+
+    #[test]
+    fn test_fsd_profile_from_ship_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("edjc.toml");
+        fs::write(
+            &path,
+            "cmdr_name = \"Whit3Arrow\"\n[ship]\nname = \"Asp Explorer\"\nladen_jump_range = 29.0\nfsd_class = 5\nfsd_rating = \"A\"\nship_mass = 420.0\n",
+        )
+        .unwrap();
+        let config = load_config_from(&path).unwrap();
+        let fsd = config.ship().fsd_profile().unwrap().unwrap();
+        assert_eq!((fsd.class, fsd.rating, fsd.ship_mass), (5, 'A', 420.0));
+        assert_eq!(fsd.optimal_mass(), 1050.0);
+
+        let engineered = ShipConfig {
+            fsd_optimal_mass: Some(1627.5),
+            ..config.ship().clone()
+        };
+        let fsd = engineered.fsd_profile().unwrap().unwrap();
+        assert_eq!(fsd.optimal_mass(), 1627.5);
+
+        assert!(ShipConfig::default().fsd_profile().unwrap().is_none());
+        let drive_missing = ShipConfig {
+            fsd_optimal_mass: Some(1627.5),
+            ..Default::default()
+        };
+        assert!(drive_missing.fsd_profile().is_err());
+
+        let partial = Config {
+            cmdr_name: "TestCMDR".to_string(),
+            ships: vec![ShipConfig {
+                fsd_class: Some(5),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let err = validate_config(&partial).unwrap_err();
+        assert!(err.to_string().contains("fsd_class"));
+    }
+
+    #[test]
+    fn test_legacy_ship_table_becomes_one_profile() {
+        let config: Config = toml::from_str(
+            "cmdr_name = \"Whit3Arrow\"\n[ship]\nname = \"DBX\"\nladen_jump_range = 50.0\n",
+        )
+        .unwrap();
+        assert_eq!(config.ships.len(), 1);
+        assert_eq!(config.ship().name, "DBX");
+        assert!(validate_config(&config).is_ok());
+
+        // Saved configs use the new layout and load back the same
+        let saved: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(saved.ships.len(), 1);
+        assert_eq!(saved.ship().laden_jump_range, 50.0);
+    }
+
+    #[test]
+    fn test_command_formats_fall_back_to_result_format() {
+        let ship = "[ship]\nname = \"DBX\"\nladen_jump_range = 50.0\n";
+        let config: Config = toml::from_str(&format!(
+            "cmdr_name = \"Whit3Arrow\"\nresult_format = \"{{jumps}}j\"\n{ship}"
+        ))
+        .unwrap();
+        assert_eq!(config.ratsignal_template(), "{jumps}j");
+        assert_eq!(config.route_template(), "{jumps}j");
+
+        let config: Config = toml::from_str(&format!(
+            "cmdr_name = \"Whit3Arrow\"\nroute_format = \"{{from}} -> {{to}}\"\n{ship}"
+        ))
+        .unwrap();
+        assert_eq!(config.ratsignal_template(), DEFAULT_RATSIGNAL_FORMAT);
+        assert_eq!(config.route_template(), "{from} -> {to}");
+    }
+
+    #[test]
+    fn test_ship_profiles_and_selector() {
+        let mut config: Config = toml::from_str(
+            "cmdr_name = \"Whit3Arrow\"\nactive_ship = \"dbx\"\n\n[[ships]]\nname = \"Anaconda\"\nladen_jump_range = 30.0\n\n[[ships]]\nname = \"DBX\"\nladen_jump_range = 50.0\n",
+        )
+        .unwrap();
+        assert_eq!(config.ships.len(), 2);
+        assert_eq!(config.ship().name, "DBX");
+        assert!(validate_config(&config).is_ok());
+
+        config.active_ship = String::new();
+        assert_eq!(config.ship().name, "Anaconda");
+
+        config.active_ship = "Cutter".to_string();
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("Cutter"));
+
+        config.ships.clear();
+        assert!(validate_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_ship_preset_fills_missing_ranges() {
+        let parse = |toml: &str| {
+            let mut config: Config = toml::from_str(toml).unwrap();
+            config.apply_ship_preset().map(|()| config)
+        };
+
+        // No ship at all: the preset is the ship
+        let config = parse("cmdr_name = \"Whit3Arrow\"\nship_preset = \"dbx-explorer\"\n").unwrap();
+        assert_eq!(config.ship().name, "Diamondback Explorer (explorer)");
+        assert_eq!(config.ship().laden_jump_range, 60.0);
+        assert!(validate_config(&config).is_ok());
+
+        // A ship without a range gets the preset's, an explicit range wins
+        let config = parse(
+            "cmdr_name = \"Whit3Arrow\"\nship_preset = \"fdl-combat\"\n\n[[ships]]\nname = \"My FDL\"\n\n[[ships]]\nname = \"DBX\"\nladen_jump_range = 50.0\n",
+        )
+        .unwrap();
+        assert_eq!(config.ships[0].name, "My FDL");
+        assert_eq!(config.ships[0].laden_jump_range, 25.0);
+        assert_eq!(config.ships[1].laden_jump_range, 50.0);
+
+        // Without a preset a missing range is still an error
+        let config = parse("cmdr_name = \"Whit3Arrow\"\n[ship]\nname = \"DBX\"\n").unwrap();
+        assert!(validate_config(&config).is_err());
+
+        let err = parse("cmdr_name = \"Whit3Arrow\"\nship_preset = \"sidewinder\"\n").unwrap_err();
+        assert!(err.to_string().contains("dbx-explorer"), "{err}");
+        let config = Config {
+            cmdr_name: "Whit3Arrow".to_string(),
+            ship_preset: Some("sidewinder".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_api_keys_from_env_override_file() {
+        let _guard = crate::test_support::CONFIG_PATH_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("edjc.toml");
+        fs::write(
+            &path,
+            "cmdr_name = \"Whit3Arrow\"\nedsm_api_key = \"file-edsm\"\ninara_api_key = \"file-inara\"\n",
+        )
+        .unwrap();
+        std::env::remove_var(EDSM_API_KEY_ENV);
+        std::env::remove_var(INARA_API_KEY_ENV);
+
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(config.edsm_api_key.as_deref(), Some("file-edsm"));
+        assert_eq!(config.inara_api_key.as_deref(), Some("file-inara"));
+
+        std::env::set_var(EDSM_API_KEY_ENV, "env-edsm");
+        std::env::set_var(INARA_API_KEY_ENV, "  ");
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(config.edsm_api_key.as_deref(), Some("env-edsm"));
+        // Blank counts as unset
+        assert_eq!(config.inara_api_key.as_deref(), Some("file-inara"));
+
+        // Saving keeps the key from the environment out of the file
+        set_config_path_override(Some(path.clone()));
+        save_config(&config).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("file-edsm"), "{saved}");
+        assert!(!saved.contains("env-edsm"), "{saved}");
+
+        set_config_path_override(None);
+        std::env::remove_var(EDSM_API_KEY_ENV);
+        std::env::remove_var(INARA_API_KEY_ENV);
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(config.edsm_api_key.as_deref(), Some("file-edsm"));
+    }
+
+    #[test]
+    fn test_config_path_from_env_and_flag() {
+        let _guard = crate::test_support::CONFIG_PATH_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = tempdir().unwrap();
+
+        let env_path = dir.path().join("profile-a.toml");
+        fs::write(
+            &env_path,
+            "cmdr_name = \"Profile A\"\n[ship]\nname = \"DBX\"\nladen_jump_range = 50.0\n",
+        )
+        .unwrap();
+        std::env::set_var(CONFIG_PATH_ENV, &env_path);
+        assert_eq!(get_config_path().unwrap(), env_path);
+        assert_eq!(load_config().unwrap().cmdr_name, "Profile A");
+
+        let flag_path = dir.path().join("profile-b.toml");
+        fs::write(
+            &flag_path,
+            "cmdr_name = \"Profile B\"\n[ship]\nname = \"Anaconda\"\nladen_jump_range = 30.0\n",
+        )
+        .unwrap();
+        let mut args = vec![
+            "route".to_string(),
+            "--config".to_string(),
+            flag_path.display().to_string(),
+            "Colonia".to_string(),
+        ];
+        apply_config_flag(&mut args).unwrap();
+        assert_eq!(args, vec!["route", "Colonia"]);
+        assert_eq!(load_config().unwrap().cmdr_name, "Profile B");
+
+        set_config_path_override(None);
+        std::env::remove_var(CONFIG_PATH_ENV);
+
+        let mut args = vec!["route".to_string(), "--config".to_string()];
+        assert!(apply_config_flag(&mut args).is_err());
     }
 
     #[test]
@@ -0,0 +1,97 @@
+/*!
+Setup diagnostics for the `/edjc-test` command.
+
+Configuration problems and unreachable APIs otherwise only show up in the log
+at load time. [`Diagnostics`] collects the outcome of each live check so the
+plugin can print a summary in HexChat and tests can inspect it directly.
+*/
+
+/// Outcome of a single check
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticCheck {
+    /// What was checked ("EDSM", "CMDR location", ...)
+    pub name: &'static str,
+    /// Whether the check passed
+    pub passed: bool,
+    /// What was found, or why the check failed
+    pub detail: String,
+}
+
+/// Outcomes of all checks, in the order they ran
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diagnostics {
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+impl Diagnostics {
+    /// Record a passed check
+    pub fn pass(&mut self, name: &'static str, detail: impl Into<String>) {
+        self.push(name, true, detail.into());
+    }
+
+    /// Record a failed check
+    pub fn fail(&mut self, name: &'static str, detail: impl Into<String>) {
+        self.push(name, false, detail.into());
+    }
+
+    fn push(&mut self, name: &'static str, passed: bool, detail: String) {
+        self.checks.push(DiagnosticCheck {
+            name,
+            passed,
+            detail,
+        });
+    }
+
+    /// Whether every check passed
+    pub fn all_passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// The check with the given name, if it ran
+    pub fn check(&self, name: &str) -> Option<&DiagnosticCheck> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    /// One line per check, marked ✓ or ✗, after a summary line
+    pub fn render(&self) -> String {
+        let failed = self.checks.iter().filter(|check| !check.passed).count();
+        let summary = if failed == 0 {
+            "🩺 EDJC self-test: all checks passed".to_string()
+        } else {
+            format!(
+                "🩺 EDJC self-test: {failed} of {} checks failed",
+                self.checks.len()
+            )
+        };
+
+        self.checks.iter().fold(summary, |output, check| {
+            let mark = if check.passed { "✓" } else { "✗" };
+            format!("{output}\n{mark} {}: {}", check.name, check.detail)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_marks_each_check() {
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.pass("Configuration", "OK");
+        assert!(diagnostics.all_passed());
+        assert_eq!(
+            diagnostics.render(),
+            "🩺 EDJC self-test: all checks passed\n✓ Configuration: OK"
+        );
+
+        diagnostics.fail("EDSM", "Timeout");
+        assert!(!diagnostics.all_passed());
+        assert_eq!(
+            diagnostics.render(),
+            "🩺 EDJC self-test: 1 of 2 checks failed\n✓ Configuration: OK\n✗ EDSM: Timeout"
+        );
+        assert!(!diagnostics.check("EDSM").unwrap().passed);
+        assert_eq!(diagnostics.check("Inara"), None);
+    }
+}
@@ -5,38 +5,284 @@ This module handles communication with the EDSM API to retrieve system coordinat
 for jump calculations.
 */
 
-use anyhow::{anyhow, Result};
-use log::debug;
+use anyhow::Result;
+use chrono::Utc;
+use log::{debug, warn};
 use moka::sync::Cache;
-use reqwest::blocking::Client;
-use serde::Deserialize;
-use std::time::Duration;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, ACCEPT, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::types::SystemCoordinates;
+use crate::cache::{self, CoordinateCache, CACHE_FILE_SOURCE, EDSM_SOURCE};
+use crate::stats::{Counter, Stats};
+use crate::system_source::LocalSystemSource;
+use crate::telemetry::ApiSpan;
+use crate::types::{
+    ConnectionDiagnostics, ConnectionHealth, Deadline, EdjcError, EdjcResult, SecurityLevel,
+    StarInfo, SystemCandidate, SystemCoordinates, SystemInfo,
+};
 
-const EDSM_API_URL: &str = "https://www.edsm.net/api-v1";
-const EDSM_LOGS_API_URL: &str = "https://www.edsm.net/api-logs-v1";
-const CACHE_TTL_SECONDS: u64 = 3600; // 1 hour (EDSM data changes rarely)
+pub(crate) const EDSM_API_URL: &str = "https://www.edsm.net/api-v1";
+pub(crate) const EDSM_LOGS_API_URL: &str = "https://www.edsm.net/api-logs-v1";
+pub(crate) const CACHE_TTL_SECONDS: u64 = 3600; // 1 hour (EDSM data changes rarely)
+/// How long a commander's location is trusted; commanders jump constantly
+pub const DEFAULT_LOCATION_CACHE_SECONDS: u64 = 60;
+/// How long a system EDSM didn't know is answered "not found" without asking
+/// again; short, since new systems are submitted all the time
+pub const DEFAULT_NOT_FOUND_CACHE_SECONDS: u64 = 60;
+/// How long a single request may take unless `with_request_timeout` says otherwise
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest wait honoured from a `Retry-After` header
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+/// Most systems requested in one `/systems` call
+const MAX_SYSTEMS_PER_REQUEST: usize = 50;
+/// Most similarly named systems suggested for an unknown one
+const MAX_SUGGESTIONS: usize = 3;
+/// Largest radius EDSM's sphere-systems endpoint accepts
+pub const SPHERE_RADIUS_LY: f64 = 100.0;
+/// Longest side EDSM's cube-systems endpoint accepts
+pub const MAX_CUBE_SIZE_LY: f64 = 200.0;
+/// Most sphere-systems requests made to survey one route's corridor
+const MAX_CORRIDOR_QUERIES: usize = 8;
+/// Grid corridor spheres are snapped to unless `with_corridor_bucket` says
+/// otherwise, so routes through the same region share cached queries
+pub const DEFAULT_CORRIDOR_BUCKET_LY: f64 = 20.0;
+/// Position lookup status for an unknown commander or API key; without an
+/// API key it is also what a private flight log looks like
+const CMDR_NOT_FOUND_MSGNUM: i32 = 203;
 
 /// EDSM API client
-#[derive(Debug)]
+///
+/// Cloning is cheap and clones share the same cache.
+#[derive(Debug, Clone)]
 pub struct EdsmClient {
     client: Client,
-    cache: Cache<String, String>,
+    /// Coordinates and area lookups, moka's unless replaced with `with_cache`
+    cache: Arc<dyn CoordinateCache>,
+    /// Commander locations, kept apart from coordinates for their short TTL
+    location_cache: Cache<String, String>,
+    /// Names EDSM answered "not found" for, so a misspelled system that keeps
+    /// coming up isn't requested every time, with the systems suggested instead
+    not_found_cache: Cache<String, Option<Vec<String>>>,
+    include_hidden: bool,
+    suggest_names: bool,
+    api_url: String,
+    logs_api_url: String,
+    location_retries: u32,
+    location_retry_delay: Duration,
+    retry_policy: RetryPolicy,
+    /// Throttle overrides; endpoints not listed use their default
+    rate_limits: HashMap<Endpoint, RateLimit>,
+    /// Token buckets per endpoint, shared by all clones
+    buckets: Arc<Mutex<HashMap<Endpoint, TokenBucket>>>,
+    /// Where looked-up coordinates are kept between sessions, when enabled
+    cache_file: Option<Arc<CacheFile>>,
+    /// Cache hits and misses and failed requests are counted here
+    stats: Arc<Stats>,
+    request_timeout: Duration,
+    /// Requests and retries are cut short to end by this; never by default
+    deadline: Deadline,
+    /// Corridor sphere centres are snapped to a grid this coarse (0 = exact)
+    corridor_bucket_ly: f64,
+}
+
+/// Coordinate cache entries persisted to disk
+///
+/// Shared by all clones of a client; saved when the last one is dropped.
+#[derive(Debug)]
+struct CacheFile {
+    path: PathBuf,
+    cache: Arc<dyn CoordinateCache>,
+    ttl: Duration,
+    /// When each system's coordinates were fetched (Unix seconds), by cache name
+    fetched_at: Mutex<HashMap<String, i64>>,
+    /// Whether anything was fetched since the last save
+    dirty: AtomicBool,
+}
+
+/// A cached system as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedCoordinates {
+    /// Lowercased name the system was looked up by
+    name: String,
+    fetched_at: i64,
+    coordinates: SystemCoordinates,
+}
+
+/// How EDSM requests are retried when EDSM is rate limiting (429) or having
+/// trouble (5xx)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts per request, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every retry after it
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Backoff before retry number `retry` (1-based)
+    fn backoff(&self, retry: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// EDSM endpoints the client calls, each throttled separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// `api-v1/system`
+    System,
+    /// `api-v1/systems`
+    Systems,
+    /// `api-v1/sphere-systems`
+    SphereSystems,
+    /// `api-v1/cube-systems`
+    CubeSystems,
+    /// `api-logs-v1/get-position`
+    CommanderPosition,
+}
+
+impl Endpoint {
+    fn path(self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::Systems => "systems",
+            Self::SphereSystems => "sphere-systems",
+            Self::CubeSystems => "cube-systems",
+            Self::CommanderPosition => "get-position",
+        }
+    }
+
+    /// Whether the endpoint belongs to the logs API rather than the system API
+    fn is_logs_api(self) -> bool {
+        matches!(self, Self::CommanderPosition)
+    }
+
+    /// Throttle used unless overridden
+    ///
+    /// EDSM rate-limits the logs API much harder than the system API.
+    pub fn default_rate_limit(self) -> RateLimit {
+        if self.is_logs_api() {
+            RateLimit {
+                burst: 3,
+                interval: Duration::from_secs(1),
+            }
+        } else {
+            RateLimit {
+                burst: 10,
+                interval: Duration::from_millis(200),
+            }
+        }
+    }
+}
+
+/// How fast requests to one endpoint may be sent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Requests that may be sent back to back
+    pub burst: u32,
+    /// Time for one more request to be allowed; zero disables throttling
+    pub interval: Duration,
+}
+
+/// Token bucket behind a [`RateLimit`]
+#[derive(Debug)]
+struct TokenBucket {
+    /// Requests that may go out right away; negative while callers wait
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst.max(1)),
+            refilled_at: now,
+        }
+    }
+
+    /// Take a token, returning how long to wait before sending
+    ///
+    /// The token is reserved right away, so concurrent callers queue up
+    /// behind each other instead of all waking at the same moment.
+    fn acquire(&mut self, limit: RateLimit, now: Instant) -> Duration {
+        if limit.interval.is_zero() {
+            return Duration::ZERO;
+        }
+
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() / limit.interval.as_secs_f64())
+            .min(f64::from(limit.burst.max(1)));
+        self.refilled_at = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            limit.interval.mul_f64(-self.tokens)
+        }
+    }
+}
+
+/// Outcome of warming the cache for a batch of systems
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrefetchReport {
+    /// Systems fetched from EDSM and added to the cache
+    pub newly_cached: usize,
+    /// Systems that were already cached
+    pub already_cached: usize,
+    /// Requested systems EDSM didn't return
+    pub not_found: Vec<String>,
 }
 
 /// EDSM system response
 #[derive(Debug, Deserialize)]
-struct EdsmSystemResponse {
+pub(crate) struct EdsmSystemResponse {
     name: String,
+    /// Only present with `showId`
+    #[serde(default)]
+    id: Option<u64>,
     coords: Option<EdsmCoordinates>,
     #[serde(rename = "primaryStar")]
     primary_star: Option<EdsmStar>,
+    /// Only present (and true) for hidden systems when `includeHidden` is set
+    #[serde(default, alias = "isHidden")]
+    hidden: bool,
+    #[serde(default, rename = "requirePermit")]
+    require_permit: bool,
+    #[serde(default, rename = "permitName")]
+    permit_name: Option<String>,
+    /// Only present with `showInformation`; an empty array for unpopulated systems
+    #[serde(default)]
+    information: serde_json::Value,
+}
+
+/// The `information` block of an EDSM system response
+#[derive(Debug, Default, Deserialize)]
+struct EdsmInformation {
+    population: Option<u64>,
+    security: Option<String>,
 }
 
 /// EDSM commander location response
 #[derive(Debug, Deserialize)]
-struct EdsmCommanderResponse {
+pub(crate) struct EdsmCommanderResponse {
     #[serde(rename = "msgnum")]
     msg_num: Option<i32>,
     msg: Option<String>,
@@ -60,222 +306,3219 @@ struct EdsmStar {
 
 impl EdsmClient {
     /// Create a new EDSM client
-    pub fn new() -> Result<Self> {
+    pub fn new() -> EdjcResult<Self> {
         let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .user_agent("Elite Dangerous Jump Calculator/0.1.0")
+            .timeout(DEFAULT_REQUEST_TIMEOUT)
+            .user_agent(crate::USER_AGENT)
             .build()?;
 
-        let cache = Cache::builder()
+        let cache: Cache<String, String> = Cache::builder()
             .time_to_live(Duration::from_secs(CACHE_TTL_SECONDS))
             .max_capacity(1000)
             .build();
 
-        Ok(Self { client, cache })
+        Ok(Self {
+            client,
+            cache: Arc::new(cache),
+            location_cache: location_cache(Duration::from_secs(DEFAULT_LOCATION_CACHE_SECONDS)),
+            not_found_cache: not_found_cache(Duration::from_secs(DEFAULT_NOT_FOUND_CACHE_SECONDS)),
+            include_hidden: false,
+            suggest_names: false,
+            api_url: EDSM_API_URL.to_string(),
+            logs_api_url: EDSM_LOGS_API_URL.to_string(),
+            location_retries: 0,
+            location_retry_delay: Duration::ZERO,
+            retry_policy: RetryPolicy::default(),
+            rate_limits: HashMap::new(),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            cache_file: None,
+            stats: Arc::new(Stats::default()),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            corridor_bucket_ly: DEFAULT_CORRIDOR_BUCKET_LY,
+            deadline: Deadline::never(),
+        })
+    }
+
+    /// Give up on a single request after `timeout` (30 seconds by default)
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// A clone of the client whose requests, retries and retry waits all end
+    /// by `deadline`
+    ///
+    /// A request that would start after it fails with
+    /// [`EdjcError::Timeout`], and one still running is cut off then.
+    pub fn bounded_by(&self, deadline: &Deadline) -> Self {
+        Self {
+            deadline: *deadline,
+            ..self.clone()
+        }
+    }
+
+    /// Snap corridor sphere centres to a `bucket_ly` grid (20 LY by default)
+    ///
+    /// Overlapping corridors then ask EDSM for the same spheres and get them
+    /// from the cache. Coarser buckets share more but shift each sphere
+    /// further off the route's line; 0 queries the exact centres.
+    pub fn with_corridor_bucket(mut self, bucket_ly: f64) -> Self {
+        self.corridor_bucket_ly = bucket_ly.max(0.0);
+        self
+    }
+
+    /// Count cache hits and misses and failed requests in shared counters
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Trust a looked-up commander location for `ttl` (system coordinates
+    /// keep their own, much longer TTL)
+    pub fn with_location_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.location_cache = location_cache(ttl);
+        self
+    }
+
+    /// Answer "not found" for a system EDSM didn't know for `ttl` before
+    /// asking again (`Duration::ZERO` always asks)
+    pub fn with_not_found_ttl(mut self, ttl: Duration) -> Self {
+        self.not_found_cache = not_found_cache(ttl);
+        self
+    }
+
+    /// Keep coordinates and area lookups in `cache` instead of the
+    /// in-process one, e.g. one shared by several processes
+    ///
+    /// Call this before [`with_cache_file`](Self::with_cache_file), which
+    /// persists whichever cache is in place at the time.
+    pub fn with_cache(mut self, cache: impl CoordinateCache + 'static) -> Self {
+        self.cache = Arc::new(cache);
+        self
+    }
+
+    /// The cache coordinates are kept in, to share with another client
+    /// (see [`InaraClient::with_shared_cache`](crate::inara::InaraClient::with_shared_cache))
+    pub fn coordinate_cache(&self) -> Arc<dyn CoordinateCache> {
+        Arc::clone(&self.cache)
+    }
+
+    /// Keep looked-up coordinates in `path` so they survive restarts
+    ///
+    /// Entries still within the cache TTL are loaded right away; a missing or
+    /// unreadable file just starts an empty cache.
+    pub fn with_cache_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let cache_file = CacheFile {
+            path: path.into(),
+            cache: self.cache.clone(),
+            ttl: self.cache_ttl(),
+            fetched_at: Mutex::new(HashMap::new()),
+            dirty: AtomicBool::new(false),
+        };
+        match cache_file.load() {
+            Ok(loaded) => debug!(
+                "Loaded {loaded} cached systems from {}",
+                cache_file.path.display()
+            ),
+            Err(e) => warn!(
+                "Ignoring coordinate cache {}: {e}",
+                cache_file.path.display()
+            ),
+        }
+        self.cache_file = Some(Arc::new(cache_file));
+        self
+    }
+
+    /// Write the coordinate cache to its file, if persistence is enabled
+    pub fn save_cache(&self) -> EdjcResult<()> {
+        match &self.cache_file {
+            Some(cache_file) => cache_file
+                .save()
+                .map_err(|e| EdjcError::Cache(e.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Retry rate-limited and failing EDSM requests according to `policy`
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Throttle requests to `endpoint` to `limit` instead of its default
+    pub fn with_rate_limit(mut self, endpoint: Endpoint, limit: RateLimit) -> Self {
+        self.rate_limits.insert(endpoint, limit);
+        self.buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&endpoint);
+        self
+    }
+
+    /// Retry failed commander position lookups `retries` times, `delay` apart
+    ///
+    /// EDSM can briefly not know a position right after a jump while it
+    /// ingests the journal, so a short retry avoids falling back needlessly.
+    pub fn with_location_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.location_retries = retries;
+        self.location_retry_delay = delay;
+        self
+    }
+
+    /// Point the client at different API endpoints: the `api-v1` base
+    /// (systems, spheres) and the `api-logs-v1` base (commander positions),
+    /// e.g. a mirror or a local mock server
+    pub fn with_api_urls(mut self, api_url: &str, logs_api_url: &str) -> Self {
+        self.api_url = api_url.to_string();
+        self.logs_api_url = logs_api_url.to_string();
+        self
+    }
+
+    /// Also resolve systems EDSM flags as hidden (e.g. duplicates)
+    pub fn with_include_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    /// Suggest up to three similarly named systems when EDSM doesn't know one
+    /// ("System not found: Colonai (did you mean: Colonia?)"), at the cost of
+    /// one more request per unknown system
+    pub fn with_suggestions(mut self, suggest_names: bool) -> Self {
+        self.suggest_names = suggest_names;
+        self
     }
 
     /// Get system coordinates from EDSM
-    pub fn get_system_coordinates(&self, system_name: &str) -> Result<SystemCoordinates> {
-        let cache_key = format!("coords:{}", system_name.to_lowercase());
+    pub fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+        self.get_system_coordinates_by(system_name, None)
+    }
 
-        // Check cache first
-        if let Some(cached) = self.cache.get(&cache_key) {
-            if let Ok(coords) = serde_json::from_str::<SystemCoordinates>(&cached) {
-                debug!("Cache hit for system coordinates: {system_name}");
-                return Ok(coords);
+    /// Get system coordinates from EDSM, by id when one is given
+    ///
+    /// A few systems share their name with another in a different sector;
+    /// the id (from [`system_candidates`](Self::system_candidates)) picks
+    /// one of them. It has to belong to a system of that name.
+    pub fn get_system_coordinates_by(
+        &self,
+        system_name: &str,
+        system_id: Option<u64>,
+    ) -> EdjcResult<SystemCoordinates> {
+        let system_name = normalize_system_name(system_name);
+        let span = ApiSpan::system_coordinates(&system_name);
+        if let Some(id) = system_id {
+            let coordinates = self.get_system_by_id(id)?;
+            if !coordinates.name.eq_ignore_ascii_case(&system_name) {
+                return Err(EdjcError::system_not_found(format!(
+                    "{system_name} with id {id} (that's {})",
+                    coordinates.name
+                )));
             }
+            return Ok(coordinates);
         }
 
-        debug!("Fetching coordinates for system: {system_name}");
+        // Check cache first
+        if let Some(suggestions) = self.known_not_found(&system_name) {
+            debug!("Cached not found: {system_name}");
+            span.cache(true);
+            // A batch lookup only learnt that the system is unknown
+            let suggestions = suggestions.unwrap_or_else(|| {
+                let suggestions = self.suggestions_for(&system_name);
+                self.remember_not_found(&system_name, Some(suggestions.clone()));
+                suggestions
+            });
+            return Err(EdjcError::SystemNotFound {
+                name: system_name,
+                suggestions,
+            });
+        }
+        if let Some(coords) = self.cached_coordinates(&system_name) {
+            debug!("Cache hit for system coordinates: {system_name}");
+            span.cache(true);
+            return Ok(coords);
+        }
 
-        let url = format!("{EDSM_API_URL}/system");
-        let response = self
-            .client
-            .get(&url)
-            .query(&[
-                ("systemName", system_name),
-                ("showCoordinates", "1"),
-                ("showPrimaryStar", "1"),
-            ])
-            .send()?;
+        span.cache(false);
+        self.refresh_system_coordinates(&system_name)
+    }
+
+    /// Get a system's security, population and primary star from EDSM
+    ///
+    /// EDSM doesn't list stations here; `has_stations` is set for populated
+    /// systems, which always have at least one.
+    pub fn get_system_info(&self, system_name: &str) -> EdjcResult<SystemInfo> {
+        let system_name = &normalize_system_name(system_name);
+        debug!("Fetching information for system: {system_name}");
+
+        let mut query = system_query(system_name, self.include_hidden).to_vec();
+        query.push(("showInformation", "1"));
+        let response = self.request(Endpoint::System, &query)?;
 
         if !response.status().is_success() {
-            return Err(anyhow!("EDSM API request failed: {}", response.status()));
+            return Err(request_failed(response.status()));
         }
 
-        let system_data: EdsmSystemResponse = response.json()?;
+        let system_data = system_response(system_name, decode(response)?)?;
+        let info = system_info_from_response(system_name, system_data)?;
+        self.cache_coordinates(system_name, &info.coordinates);
 
-        let coords = system_data
-            .coords
-            .ok_or_else(|| anyhow!("System '{}' not found or has no coordinates", system_name))?;
+        Ok(info)
+    }
+
+    /// Get coordinates for several systems, in the order they were asked for
+    ///
+    /// Cached systems are served from the cache; the rest are fetched in
+    /// batches of up to 50 per request. Fails naming every system EDSM
+    /// doesn't know.
+    pub fn get_systems_coordinates(&self, names: &[&str]) -> EdjcResult<Vec<SystemCoordinates>> {
+        let names: Vec<String> = names
+            .iter()
+            .map(|name| normalize_system_name(name))
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut known: HashMap<String, SystemCoordinates> = HashMap::new();
+        let mut missing: Vec<&str> = Vec::new();
+        let mut requested = HashSet::new();
+
+        for &name in &names {
+            let key = name.to_lowercase();
+            if !requested.insert(key.clone()) {
+                continue;
+            }
+            if self.known_not_found(name).is_some() {
+                continue;
+            }
+            match self.cached_coordinates(name) {
+                Some(coords) => {
+                    known.insert(key, coords);
+                }
+                None => missing.push(name),
+            }
+        }
+
+        for coords in self.fetch_systems(&missing)? {
+            known.insert(coords.name.to_lowercase(), coords);
+        }
+        for name in missing {
+            if !known.contains_key(&name.to_lowercase()) {
+                self.remember_not_found(name, None);
+            }
+        }
+
+        let unknown: Vec<&str> = names
+            .iter()
+            .copied()
+            .filter(|name| !known.contains_key(&name.to_lowercase()))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(EdjcError::system_not_found(unknown.join(", ")));
+        }
 
-        // Determine if system has neutron star or white dwarf
-        let (has_neutron_star, has_white_dwarf) = if let Some(star) = &system_data.primary_star {
-            let star_type = star.star_type.as_deref().unwrap_or("");
-            let sub_type = star.sub_type.as_deref().unwrap_or("");
+        Ok(names
+            .iter()
+            .map(|name| known[&name.to_lowercase()].clone())
+            .collect())
+    }
 
-            let has_neutron = star_type.contains("Neutron") || sub_type.contains("Neutron");
-            let has_white_dwarf = star_type.contains("White Dwarf")
-                || sub_type.contains("DA")
-                || sub_type.contains("DB")
-                || sub_type.contains("DC");
+    /// Cache a system's coordinates under the name it was looked up by
+    fn cache_coordinates(&self, system_name: &str, coordinates: &SystemCoordinates) {
+        let name = system_name.to_lowercase();
+        self.not_found_cache.invalidate(&name);
+        if let Some(entry) = cache::encode_coordinates(coordinates, EDSM_SOURCE) {
+            self.cache
+                .insert(cache::coordinates_key(system_name), entry);
+        }
+        if let Some(cache_file) = &self.cache_file {
+            cache_file.record(name);
+        }
+    }
 
-            (has_neutron, has_white_dwarf)
+    /// Coordinates of a system, if cached with EDSM's data
+    ///
+    /// Entries another source filled (Inara, through a shared cache) lack the
+    /// boost star and permit data, so they count as a miss.
+    fn cached_coordinates(&self, system_name: &str) -> Option<SystemCoordinates> {
+        let coordinates = self
+            .cache
+            .get(&cache::coordinates_key(system_name))
+            .and_then(|cached| cache::decode_coordinates(&cached))
+            .and_then(|(coordinates, source)| match source.as_deref() {
+                None | Some(EDSM_SOURCE | CACHE_FILE_SOURCE) => Some(coordinates),
+                Some(source) => {
+                    debug!("Cached coordinates for {system_name} came from {source}, asking EDSM");
+                    None
+                }
+            });
+        self.stats.record(if coordinates.is_some() {
+            Counter::CacheHits
         } else {
-            (false, false)
+            Counter::CacheMisses
+        });
+        coordinates
+    }
+
+    /// If EDSM recently said it doesn't know a system, the systems suggested
+    /// instead (`None` when they weren't looked up)
+    fn known_not_found(&self, system_name: &str) -> Option<Option<Vec<String>>> {
+        let suggestions = self.not_found_cache.get(&system_name.to_lowercase());
+        if suggestions.is_some() {
+            self.stats.record(Counter::CacheHits);
+        }
+        suggestions
+    }
+
+    /// Remember that EDSM doesn't know a system, for `with_not_found_ttl`,
+    /// with the systems suggested instead if they were looked up
+    fn remember_not_found(&self, system_name: &str, suggestions: Option<Vec<String>>) {
+        self.not_found_cache
+            .insert(system_name.to_lowercase(), suggestions);
+    }
+
+    /// Systems named like one EDSM doesn't know, closest first
+    ///
+    /// EDSM's `/systems` endpoint matches names by prefix, so this asks for
+    /// systems starting with all but the last couple of characters of the name
+    /// (a typo near the end is the common case) and keeps the ones within a
+    /// few edits of it.
+    pub fn suggest_system_names(&self, system_name: &str) -> EdjcResult<Vec<String>> {
+        let system_name = normalize_system_name(system_name);
+        let Some(prefix) = suggestion_prefix(&system_name) else {
+            return Ok(Vec::new());
+        };
+        debug!("Looking for systems named like {system_name} (prefix {prefix})");
+
+        let response = self.request(
+            Endpoint::Systems,
+            &[
+                ("systemName", prefix),
+                ("includeHidden", if self.include_hidden { "1" } else { "0" }),
+            ],
+        )?;
+
+        if !response.status().is_success() {
+            return Err(request_failed(response.status()));
+        }
+
+        // Like a batch lookup, no match at all comes back as an empty object
+        let candidates: Vec<String> = match decode(response)? {
+            serde_json::Value::Array(systems) => systems
+                .iter()
+                .filter_map(|system| system.get("name")?.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
         };
 
-        let coordinates = SystemCoordinates {
-            name: system_data.name,
-            x: coords.x,
-            y: coords.y,
-            z: coords.z,
-            has_neutron_star,
-            has_white_dwarf,
+        Ok(closest_names(&system_name, candidates))
+    }
+
+    /// Suggestions for an unknown system, or none when they are turned off or
+    /// can't be had
+    fn suggestions_for(&self, system_name: &str) -> Vec<String> {
+        if !self.suggest_names {
+            return Vec::new();
+        }
+        self.suggest_system_names(system_name).unwrap_or_else(|e| {
+            debug!("No suggestions for {system_name}: {e}");
+            Vec::new()
+        })
+    }
+
+    /// Fetch system coordinates from EDSM, bypassing (and updating) the cache
+    pub fn refresh_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+        let system_name = &normalize_system_name(system_name);
+        debug!("Fetching coordinates for system: {system_name}");
+
+        let response = self.request(
+            Endpoint::System,
+            &system_query(system_name, self.include_hidden),
+        )?;
+
+        if !response.status().is_success() {
+            return Err(request_failed(response.status()));
+        }
+
+        let system_data = match system_response(system_name, decode(response)?) {
+            Err(EdjcError::SystemNotFound { .. }) => {
+                let suggestions = self.suggestions_for(system_name);
+                self.remember_not_found(system_name, Some(suggestions.clone()));
+                return Err(EdjcError::SystemNotFound {
+                    name: system_name.to_string(),
+                    suggestions,
+                });
+            }
+            result => result?,
         };
+        let coordinates = coordinates_from_response(system_name, system_data)?;
+        self.cache_coordinates(system_name, &coordinates);
 
-        // Cache the result
-        if let Ok(cached_data) = serde_json::to_string(&coordinates) {
-            self.cache.insert(cache_key, cached_data);
+        Ok(coordinates)
+    }
+
+    /// Get a system's coordinates by its EDSM id
+    ///
+    /// Cached under `#<id>`, apart from the system's name, which may be
+    /// shared with another system.
+    pub fn get_system_by_id(&self, system_id: u64) -> EdjcResult<SystemCoordinates> {
+        let key = format!("#{system_id}");
+        if let Some(coords) = self.cached_coordinates(&key) {
+            debug!("Cache hit for system id {system_id}");
+            return Ok(coords);
+        }
+        debug!("Fetching coordinates for system id: {system_id}");
+
+        let id = system_id.to_string();
+        let response = self.request(
+            Endpoint::System,
+            &[
+                ("systemId", id.as_str()),
+                ("showId", "1"),
+                ("showCoordinates", "1"),
+                ("showPrimaryStar", "1"),
+                ("showPermit", "1"),
+                ("includeHidden", if self.include_hidden { "1" } else { "0" }),
+            ],
+        )?;
+
+        if !response.status().is_success() {
+            return Err(request_failed(response.status()));
         }
 
+        let system_data = system_response(&key, decode(response)?)?;
+        let coordinates = coordinates_from_response(&key, system_data)?;
+        self.cache_coordinates(&key, &coordinates);
+
         Ok(coordinates)
     }
 
-    /// Get commander's current location from EDSM
-    pub fn get_commander_location(&self, cmdr_name: &str, api_key: Option<&str>) -> Result<String> {
-        let cache_key = format!("cmdr_location:{}", cmdr_name.to_lowercase());
+    /// Every system EDSM knows by this name, with their ids
+    ///
+    /// Usually one; more when the name is shared by systems in different
+    /// sectors, which the caller can then tell apart by position and pick
+    /// by id. Systems without coordinates are left out.
+    pub fn system_candidates(&self, system_name: &str) -> EdjcResult<Vec<SystemCandidate>> {
+        let system_name = &normalize_system_name(system_name);
+        let response = self.request(
+            Endpoint::Systems,
+            &[
+                ("systemName", system_name.as_str()),
+                ("showId", "1"),
+                ("showCoordinates", "1"),
+                ("showPrimaryStar", "1"),
+                ("showPermit", "1"),
+                ("includeHidden", if self.include_hidden { "1" } else { "0" }),
+            ],
+        )?;
 
-        // Check cache first (shorter TTL for commander location as it changes frequently)
-        if let Some(cached) = self.cache.get(&cache_key) {
-            debug!("Cache hit for commander location: {cmdr_name}");
-            return Ok(cached);
+        if !response.status().is_success() {
+            return Err(request_failed(response.status()));
         }
 
-        debug!("Fetching commander location for: {cmdr_name}");
+        let candidates = system_candidates(system_name, decode(response)?);
+        debug!("{} system(s) named {system_name} on EDSM", candidates.len());
+        if candidates.is_empty() {
+            return Err(EdjcError::system_not_found(system_name.as_str()));
+        }
+        Ok(candidates)
+    }
 
-        let url = format!("{EDSM_LOGS_API_URL}/get-position");
+    /// Resolve a batch of systems into the cache, up to 50 per request
+    ///
+    /// Systems that are already cached are not requested again.
+    pub fn prefetch_systems(&self, system_names: &[String]) -> EdjcResult<PrefetchReport> {
+        let mut report = PrefetchReport::default();
 
-        // Build query parameters
-        let mut query_params = vec![("commanderName", cmdr_name), ("showCoordinates", "1")];
+        let system_names: Vec<String> = system_names
+            .iter()
+            .map(|name| normalize_system_name(name))
+            .collect();
+        let missing: Vec<&str> = system_names
+            .iter()
+            .map(String::as_str)
+            .filter(|name| {
+                let cached = self
+                    .cache
+                    .get(&format!("coords:{}", name.to_lowercase()))
+                    .is_some();
+                if cached {
+                    report.already_cached += 1;
+                }
+                !cached
+            })
+            .collect();
 
-        // Add API key if provided
-        if let Some(key) = api_key {
-            query_params.push(("apiKey", key));
+        if missing.is_empty() {
+            return Ok(report);
         }
 
-        let response = self.client.get(&url).query(&query_params).send()?;
+        debug!("Prefetching {} systems from EDSM", missing.len());
 
-        if !response.status().is_success() {
-            return Err(anyhow!("EDSM API request failed: {}", response.status()));
+        let resolved: Vec<String> = self
+            .fetch_systems(&missing)?
+            .into_iter()
+            .map(|coords| coords.name.to_lowercase())
+            .collect();
+
+        for name in missing {
+            if resolved.contains(&name.to_lowercase()) {
+                report.newly_cached += 1;
+            } else {
+                report.not_found.push(name.to_string());
+            }
         }
 
-        let commander_data: EdsmCommanderResponse = response.json()?;
+        Ok(report)
+    }
+
+    /// Fetch systems from EDSM's `/systems` endpoint and cache them
+    ///
+    /// Returns the systems EDSM knows, named as EDSM spells them; unknown
+    /// systems are simply missing from the result.
+    fn fetch_systems(&self, names: &[&str]) -> EdjcResult<Vec<SystemCoordinates>> {
+        let mut resolved = Vec::new();
+
+        for batch in names.chunks(MAX_SYSTEMS_PER_REQUEST) {
+            let mut query: Vec<(&str, &str)> =
+                batch.iter().map(|&name| ("systemName[]", name)).collect();
+            query.extend([
+                ("showCoordinates", "1"),
+                ("showPrimaryStar", "1"),
+                ("showPermit", "1"),
+                ("includeHidden", if self.include_hidden { "1" } else { "0" }),
+            ]);
+
+            let response = self.request(Endpoint::Systems, &query)?;
 
-        // Check for API errors
-        if let Some(msg_num) = commander_data.msg_num {
-            if msg_num != 100 {
-                let error_msg = commander_data.msg.unwrap_or("Unknown error".to_string());
-                return Err(anyhow!("EDSM API error {}: {}", msg_num, error_msg));
+            if !response.status().is_success() {
+                return Err(request_failed(response.status()));
+            }
+
+            // EDSM answers with an empty object instead of an array when nothing matched
+            let systems: Vec<EdsmSystemResponse> = match decode(response)? {
+                serde_json::Value::Array(systems) => systems
+                    .into_iter()
+                    .filter_map(|system| serde_json::from_value(system).ok())
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            for system_data in systems {
+                let name = system_data.name.clone();
+                if let Ok(coordinates) = coordinates_from_response(&name, system_data) {
+                    self.cache_coordinates(&name, &coordinates);
+                    resolved.push(coordinates);
+                }
             }
         }
 
-        let system_name = commander_data.system.ok_or_else(|| {
-            anyhow!(
-                "Commander '{}' not found or no location data available",
-                cmdr_name
-            )
+        Ok(resolved)
+    }
+
+    /// Neutron stars along the straight line between two systems
+    ///
+    /// Surveys the corridor with evenly spaced sphere-systems queries, at most
+    /// `MAX_CORRIDOR_QUERIES` per route, so very long routes are only sampled.
+    pub fn corridor_neutron_stars(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+    ) -> EdjcResult<Vec<SystemCoordinates>> {
+        self.corridor_neutron_stars_by(from, to, &Deadline::never())
+    }
+
+    /// [`Self::corridor_neutron_stars`], failing with a timeout instead of
+    /// making the next query once `deadline` has passed
+    pub fn corridor_neutron_stars_by(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        deadline: &Deadline,
+    ) -> EdjcResult<Vec<SystemCoordinates>> {
+        let client = self.bounded_by(deadline);
+        let bucket = self.corridor_bucket_ly;
+        let neutron_stars = corridor_systems(from, to, bucket, deadline, |(x, y, z)| {
+            let systems = client.sphere_systems(x, y, z, SPHERE_RADIUS_LY)?;
+            Ok(systems
+                .into_iter()
+                .filter(|system| system.is_neutron_boost())
+                .collect())
         })?;
 
-        // Cache the result with shorter TTL (commander location changes frequently)
-        self.cache.insert(cache_key, system_name.clone());
+        debug!(
+            "Found {} neutron stars between {} and {}",
+            neutron_stars.len(),
+            from.name,
+            to.name
+        );
+        Ok(neutron_stars)
+    }
 
-        Ok(system_name)
+    /// Populated systems along the straight line between two systems,
+    /// surveyed like [`Self::corridor_neutron_stars`]
+    ///
+    /// Failing with a timeout instead of making the next query once
+    /// `deadline` has passed.
+    pub fn corridor_populated_systems_by(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        deadline: &Deadline,
+    ) -> EdjcResult<Vec<SystemCoordinates>> {
+        let client = self.bounded_by(deadline);
+        let bucket = self.corridor_bucket_ly;
+        let populated = corridor_systems(from, to, bucket, deadline, |center| {
+            client.populated_systems(center, SPHERE_RADIUS_LY)
+        })?;
+
+        debug!(
+            "Found {} populated systems between {} and {}",
+            populated.len(),
+            from.name,
+            to.name
+        );
+        Ok(populated)
     }
 
-    /// Calculate distance between two systems
-    pub fn calculate_distance(&self, from_system: &str, to_system: &str) -> Result<f64> {
-        let from_coords = self.get_system_coordinates(from_system)?;
-        let to_coords = self.get_system_coordinates(to_system)?;
-        Ok(calculate_3d_distance(&from_coords, &to_coords))
+    /// The closest neutron star within `radius_ly` of a system
+    ///
+    /// The radius is capped at what EDSM accepts (100 LY). Returns `None` if
+    /// the sphere has no neutron stars.
+    pub fn nearest_neutron_star(
+        &self,
+        from: &SystemCoordinates,
+        radius_ly: f64,
+    ) -> EdjcResult<Option<SystemCoordinates>> {
+        let systems =
+            self.sphere_systems(from.x, from.y, from.z, radius_ly.min(SPHERE_RADIUS_LY))?;
+
+        Ok(systems
+            .into_iter()
+            .filter(|system| system.is_neutron_boost())
+            .min_by(|a, b| from.distance_to(a).total_cmp(&from.distance_to(b))))
     }
 
-    /// Test connection to EDSM by looking up Sol
-    pub fn test_connection(&self) -> Result<bool> {
-        debug!("Testing EDSM connection with Sol system");
+    /// The closest populated system within `radius_ly` of a system, where a
+    /// commander stranded in deep space can find a station
+    ///
+    /// The radius is capped at what EDSM accepts (100 LY). Returns `None` if
+    /// nobody lives in the sphere; the system itself counts when populated.
+    pub fn nearest_populated_system(
+        &self,
+        from: &SystemCoordinates,
+        radius_ly: f64,
+    ) -> EdjcResult<Option<SystemCoordinates>> {
+        let systems =
+            self.populated_systems((from.x, from.y, from.z), radius_ly.min(SPHERE_RADIUS_LY))?;
 
-        match self.get_system_coordinates("Sol") {
-            Ok(coords) => {
-                // Sol should be at (0, 0, 0)
-                let distance_from_origin =
-                    (coords.x.powi(2) + coords.y.powi(2) + coords.z.powi(2)).sqrt();
-                Ok(distance_from_origin < 1.0)
-            }
-            Err(_) => Ok(false),
+        Ok(systems
+            .into_iter()
+            .min_by(|a, b| from.distance_to(a).total_cmp(&from.distance_to(b))))
+    }
+
+    /// Populated systems within `radius` LY of a point
+    fn populated_systems(
+        &self,
+        (x, y, z): (f64, f64, f64),
+        radius: f64,
+    ) -> EdjcResult<Vec<SystemCoordinates>> {
+        let cache_key = format!("populated:{x:.0}:{y:.0}:{z:.0}:{radius:.0}");
+        let radius = format!("{radius:.0}");
+        self.query_systems(
+            Endpoint::SphereSystems,
+            cache_key,
+            (x, y, z),
+            ("radius", &radius),
+            true,
+        )
+    }
+
+    /// Systems in a cube of `size_ly` per side centred on a system, via
+    /// EDSM's cube-systems endpoint
+    ///
+    /// Covers a box rather than a sphere, which suits enumerating a dense
+    /// neutron field around a waypoint; the results can be passed to
+    /// `JumpCalculator::calculate_route_with_corridor` as candidates. EDSM
+    /// accepts at most 200 LY per side.
+    pub fn systems_in_cube(
+        &self,
+        center: &SystemCoordinates,
+        size_ly: f64,
+    ) -> EdjcResult<Vec<SystemCoordinates>> {
+        if !(size_ly > 0.0 && size_ly <= MAX_CUBE_SIZE_LY) {
+            return Err(EdjcError::EdsmApi(format!(
+                "cube size must be above 0 and at most {MAX_CUBE_SIZE_LY:.0} LY per side, got {size_ly} LY"
+            )));
         }
+
+        let (x, y, z) = (center.x, center.y, center.z);
+        let cache_key = format!("cube:{x:.0}:{y:.0}:{z:.0}:{size_ly:.0}");
+        let size = format!("{size_ly:.0}");
+        self.query_systems(
+            Endpoint::CubeSystems,
+            cache_key,
+            (x, y, z),
+            ("size", &size),
+            false,
+        )
     }
-}
 
-/// Calculate 3D distance between two system coordinates
-fn calculate_3d_distance(from: &SystemCoordinates, to: &SystemCoordinates) -> f64 {
-    let dx = to.x - from.x;
-    let dy = to.y - from.y;
-    let dz = to.z - from.z;
-    (dx.powi(2) + dy.powi(2) + dz.powi(2)).sqrt()
-}
+    /// Systems within `radius` LY of a point, via EDSM's sphere-systems endpoint
+    fn sphere_systems(
+        &self,
+        x: f64,
+        y: f64,
+        z: f64,
+        radius: f64,
+    ) -> EdjcResult<Vec<SystemCoordinates>> {
+        let cache_key = format!("sphere:{x:.0}:{y:.0}:{z:.0}:{radius:.0}");
+        let radius = format!("{radius:.0}");
+        self.query_systems(
+            Endpoint::SphereSystems,
+            cache_key,
+            (x, y, z),
+            ("radius", &radius),
+            false,
+        )
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Systems in a region around a point, with coordinates and primary stars
+    ///
+    /// `extent` is the endpoint's size parameter (a sphere's radius or a
+    /// cube's side). With `populated_only`, EDSM is also asked for each
+    /// system's population and the uninhabited ones are left out.
+    fn query_systems(
+        &self,
+        endpoint: Endpoint,
+        cache_key: String,
+        (x, y, z): (f64, f64, f64),
+        extent: (&str, &str),
+        populated_only: bool,
+    ) -> EdjcResult<Vec<SystemCoordinates>> {
+        if let Some(cached) = self.cache.get(&cache_key) {
+            if let Ok(systems) = serde_json::from_str::<Vec<SystemCoordinates>>(&cached) {
+                debug!(
+                    "Cache hit for {} around {x:.0}, {y:.0}, {z:.0}",
+                    endpoint.path()
+                );
+                return Ok(systems);
+            }
+        }
 
-    #[test]
-    fn test_distance_calculation() {
-        let sol = SystemCoordinates {
-            name: "Sol".to_string(),
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-            has_neutron_star: false,
-            has_white_dwarf: false,
-        };
+        let (x, y, z) = (format!("{x:.2}"), format!("{y:.2}"), format!("{z:.2}"));
+        let mut query = vec![
+            ("x", x.as_str()),
+            ("y", y.as_str()),
+            ("z", z.as_str()),
+            extent,
+            ("showCoordinates", "1"),
+            ("showPrimaryStar", "1"),
+        ];
+        if populated_only {
+            query.push(("showInformation", "1"));
+        }
+        let response = self.request(endpoint, &query)?;
 
-        let alpha_centauri = SystemCoordinates {
-            name: "Alpha Centauri".to_string(),
-            x: 3.03125,
-            y: -0.09375,
-            z: 3.15625,
-            has_neutron_star: false,
-            has_white_dwarf: false,
+        if !response.status().is_success() {
+            return Err(request_failed(response.status()));
+        }
+
+        // Like /systems, an empty region comes back as an empty object
+        let systems: Vec<SystemCoordinates> = match decode(response)? {
+            serde_json::Value::Array(systems) => systems
+                .into_iter()
+                .filter_map(|system| serde_json::from_value::<EdsmSystemResponse>(system).ok())
+                .filter(|system| !populated_only || is_populated(system))
+                .filter_map(|system| {
+                    let name = system.name.clone();
+                    coordinates_from_response(&name, system).ok()
+                })
+                .collect(),
+            _ => Vec::new(),
         };
 
-        let distance = calculate_3d_distance(&sol, &alpha_centauri);
-        // Alpha Centauri is approximately 4.3 LY from Sol
-        assert!((distance - 4.3).abs() < 0.5);
+        if let Ok(cached_data) = serde_json::to_string(&systems) {
+            self.cache.insert(cache_key, cached_data);
+        }
+
+        Ok(systems)
     }
 
-    #[test]
-    fn test_large_distance_calculation() {
-        let sol = SystemCoordinates {
-            name: "Sol".to_string(),
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-            has_neutron_star: false,
-            has_white_dwarf: false,
-        };
+    /// Forget a commander's cached location so the next lookup asks EDSM again
+    pub fn invalidate_location(&self, cmdr_name: &str) {
+        self.location_cache
+            .invalidate(&format!("cmdr_location:{}", cmdr_name.to_lowercase()));
+    }
 
-        // Sagittarius A* coordinates (approximate)
-        let sagittarius_a = SystemCoordinates {
-            name: "Sagittarius A*".to_string(),
-            x: 25.21875,
-            y: -20.90625,
-            z: 25899.96875,
-            has_neutron_star: false,
-            has_white_dwarf: false,
-        };
+    /// Drop everything cached, including the coordinates persisted to disk
+    pub fn clear_cache(&self) {
+        self.cache.invalidate_all();
+        self.location_cache.invalidate_all();
+        self.not_found_cache.invalidate_all();
+        if let Some(cache_file) = &self.cache_file {
+            cache_file.clear();
+        }
+    }
+
+    /// How long looked-up systems stay cached
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(CACHE_TTL_SECONDS)
+    }
+
+    /// Get commander's current location from EDSM
+    pub fn get_commander_location(
+        &self,
+        cmdr_name: &str,
+        api_key: Option<&str>,
+    ) -> EdjcResult<String> {
+        let cache_key = format!("cmdr_location:{}", cmdr_name.to_lowercase());
+        let span = ApiSpan::commander_location(cmdr_name);
+
+        // Check cache first (shorter TTL for commander location as it changes frequently)
+        if let Some(cached) = self.location_cache.get(&cache_key) {
+            debug!("Cache hit for commander location: {cmdr_name}");
+            span.cache(true);
+            span.record("system", &cached);
+            return Ok(cached);
+        }
+        span.cache(false);
+
+        let mut attempt = 0;
+        let system_name = loop {
+            match self.fetch_commander_location(cmdr_name, api_key) {
+                Ok(system_name) => break system_name,
+                Err(e)
+                    if attempt < self.location_retries
+                        && !matches!(
+                            e,
+                            EdjcError::ProfilePrivate(_) | EdjcError::ApiKeyMissing(_)
+                        )
+                        && self.deadline_allows(self.location_retry_delay) =>
+                {
+                    attempt += 1;
+                    debug!("Commander location lookup failed ({e}), retry {attempt}");
+                    std::thread::sleep(self.location_retry_delay);
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        // Cache the result with shorter TTL (commander location changes frequently)
+        span.record("system", &system_name);
+        self.location_cache.insert(cache_key, system_name.clone());
+
+        Ok(system_name)
+    }
+
+    /// Fetch the commander's position from EDSM, without caching or retries
+    fn fetch_commander_location(
+        &self,
+        cmdr_name: &str,
+        api_key: Option<&str>,
+    ) -> EdjcResult<String> {
+        debug!("Fetching commander location for: {cmdr_name}");
+
+        let response = self.request(
+            Endpoint::CommanderPosition,
+            &commander_query(cmdr_name, api_key),
+        )?;
+
+        if !response.status().is_success() {
+            return Err(request_failed(response.status()));
+        }
+
+        commander_system(cmdr_name, api_key, decode(response)?)
+    }
+
+    /// GET an EDSM endpoint; every request to EDSM goes through here
+    ///
+    /// Each attempt waits for the endpoint's throttle first. Retries 429 and
+    /// 5xx responses, waiting as long as a `Retry-After` header asks (up to
+    /// `MAX_RETRY_AFTER`) or backing off exponentially. The last response is
+    /// returned as is once attempts run out, so callers report the failing
+    /// status.
+    fn request(&self, endpoint: Endpoint, params: &[(&str, &str)]) -> EdjcResult<Response> {
+        self.request_with_attempts(endpoint, params, self.retry_policy.max_attempts)
+    }
+
+    /// [`Self::request`] with its own attempt limit
+    fn request_with_attempts(
+        &self,
+        endpoint: Endpoint,
+        params: &[(&str, &str)],
+        max_attempts: u32,
+    ) -> EdjcResult<Response> {
+        let base_url = if endpoint.is_logs_api() {
+            &self.logs_api_url
+        } else {
+            &self.api_url
+        };
+        let url = format!("{base_url}/{}", endpoint.path());
+        self.deadline
+            .check(&format!("asking EDSM's {}", endpoint.path()))?;
+        debug!("EDSM request: {url}");
+
+        let request = self
+            .client
+            .get(&url)
+            .header(ACCEPT, "application/json")
+            .query(params);
+        let response = self
+            .send_with_retry(endpoint, request, max_attempts)
+            .map_err(|e| match e {
+                EdjcError::Network(e) if e.is_timeout() && self.deadline.has_passed() => {
+                    EdjcError::Timeout(format!("EDSM's {} didn't answer in time", endpoint.path()))
+                }
+                e => e,
+            });
+        if !response
+            .as_ref()
+            .is_ok_and(|response| response.status().is_success())
+        {
+            self.stats.record(Counter::ApiErrors);
+        }
+        response
+    }
+
+    fn send_with_retry(
+        &self,
+        endpoint: Endpoint,
+        request: RequestBuilder,
+        max_attempts: u32,
+    ) -> EdjcResult<Response> {
+        let mut attempt = 1;
+        loop {
+            self.throttle(endpoint);
+            // Set on each attempt: clones don't keep the timeout, and the
+            // time left before the deadline shrinks
+            let timeout = self
+                .deadline
+                .remaining()
+                .map_or(self.request_timeout, |remaining| {
+                    remaining.min(self.request_timeout)
+                });
+            let Some(this_attempt) = request.try_clone() else {
+                return Ok(request.timeout(timeout).send()?);
+            };
+            let response = this_attempt.timeout(timeout).send()?;
+
+            let status = response.status();
+            let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+            if !retryable || attempt >= max_attempts {
+                return Ok(response);
+            }
+
+            let delay = retry_after(response.headers())
+                .unwrap_or_else(|| self.retry_policy.backoff(attempt));
+            if !self.deadline_allows(delay) {
+                debug!("EDSM answered {status}, no time left to retry in {delay:?}");
+                return Ok(response);
+            }
+            debug!(
+                "EDSM answered {status}, retry {attempt} of {} in {delay:?}",
+                max_attempts - 1
+            );
+            std::thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    /// Whether waiting `delay` before retrying still leaves time before the
+    /// deadline
+    fn deadline_allows(&self, delay: Duration) -> bool {
+        self.deadline
+            .remaining()
+            .is_none_or(|remaining| delay < remaining)
+    }
+
+    /// Wait until `endpoint`'s rate limit allows another request
+    fn throttle(&self, endpoint: Endpoint) {
+        let limit = self
+            .rate_limits
+            .get(&endpoint)
+            .copied()
+            .unwrap_or_else(|| endpoint.default_rate_limit());
+        let now = Instant::now();
+        let wait = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(endpoint)
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .acquire(limit, now);
+
+        if !wait.is_zero() {
+            debug!("Throttling EDSM {} request for {wait:?}", endpoint.path());
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Calculate distance between two systems
+    pub fn calculate_distance(&self, from_system: &str, to_system: &str) -> EdjcResult<f64> {
+        let from_coords = self.get_system_coordinates(from_system)?;
+        let to_coords = self.get_system_coordinates(to_system)?;
+        Ok(calculate_3d_distance(&from_coords, &to_coords))
+    }
+
+    /// Test connection to EDSM by looking up Sol
+    ///
+    /// Bypasses the cache and makes a single attempt so the result reflects
+    /// the current state of the network.
+    pub fn test_connection(&self) -> ConnectionHealth {
+        self.connection_diagnostics().health
+    }
+
+    /// [`Self::test_connection`] with the round-trip latency, HTTP status
+    /// and where EDSM puts Sol
+    pub fn connection_diagnostics(&self) -> ConnectionDiagnostics {
+        debug!("Testing EDSM connection with Sol system");
+        let mut diagnostics = ConnectionDiagnostics {
+            health: ConnectionHealth::Healthy,
+            latency: None,
+            status: None,
+            sol_offset_ly: None,
+        };
+
+        let started = Instant::now();
+        let response = match self.request_with_attempts(
+            Endpoint::System,
+            &[("systemName", "Sol"), ("showCoordinates", "1")],
+            1,
+        ) {
+            Ok(response) => response,
+            Err(EdjcError::Network(e)) => {
+                diagnostics.health = ConnectionHealth::from_reqwest_error(&e);
+                return diagnostics;
+            }
+            Err(e) => {
+                diagnostics.health = ConnectionHealth::UnexpectedResponse(e.to_string());
+                return diagnostics;
+            }
+        };
+        diagnostics.status = Some(response.status().as_u16());
+
+        if !response.status().is_success() {
+            diagnostics.latency = Some(started.elapsed());
+            diagnostics.health = ConnectionHealth::Http(response.status().as_u16());
+            return diagnostics;
+        }
+
+        let system_data = response.json::<EdsmSystemResponse>();
+        diagnostics.latency = Some(started.elapsed());
+        let system_data = match system_data {
+            Ok(data) => data,
+            Err(e) => {
+                diagnostics.health = ConnectionHealth::UnexpectedResponse(e.to_string());
+                return diagnostics;
+            }
+        };
+
+        let sol_offset = system_data
+            .coords
+            .map(|coords| (coords.x.powi(2) + coords.y.powi(2) + coords.z.powi(2)).sqrt());
+        diagnostics.sol_offset_ly = sol_offset;
+        diagnostics.health = match sol_offset {
+            // Sol should be at (0, 0, 0)
+            Some(offset) if offset < 1.0 => ConnectionHealth::Healthy,
+            Some(_) => ConnectionHealth::UnexpectedResponse("Sol is not at the origin".to_string()),
+            None => ConnectionHealth::UnexpectedResponse("Sol has no coordinates".to_string()),
+        };
+        diagnostics
+    }
+}
+
+impl CacheFile {
+    /// Whether an entry fetched at `fetched_at` has outlived the TTL
+    fn is_expired(&self, fetched_at: i64, now: i64) -> bool {
+        now.saturating_sub(fetched_at) >= self.ttl.as_secs() as i64
+    }
+
+    /// Note that a system's coordinates were just fetched
+    fn record(&self, name: String) {
+        self.fetched_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name, Utc::now().timestamp());
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Forget every persisted entry; the file is emptied on the next save
+    fn clear(&self) {
+        self.fetched_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Load unexpired entries into the cache, returning how many were loaded
+    fn load(&self) -> Result<usize> {
+        if !self.path.exists() {
+            return Ok(0);
+        }
+
+        let entries: Vec<PersistedCoordinates> =
+            serde_json::from_str(&fs::read_to_string(&self.path)?)?;
+        let now = Utc::now().timestamp();
+        let mut fetched_at = self.fetched_at.lock().unwrap_or_else(|e| e.into_inner());
+        let mut loaded = 0;
+
+        for entry in entries {
+            if self.is_expired(entry.fetched_at, now) {
+                continue;
+            }
+            // Reloaded entries get a fresh in-memory TTL, but keep their
+            // original timestamp so they still expire on disk
+            if let Some(cached) = cache::encode_coordinates(&entry.coordinates, CACHE_FILE_SOURCE) {
+                self.cache
+                    .insert(cache::coordinates_key(&entry.name), cached);
+            }
+            fetched_at.insert(entry.name, entry.fetched_at);
+            loaded += 1;
+        }
+
+        Ok(loaded)
+    }
+
+    /// Write the unexpired entries still in the cache, if anything changed
+    fn save(&self) -> Result<()> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let now = Utc::now().timestamp();
+        let entries: Vec<PersistedCoordinates> = self
+            .fetched_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(_, &fetched_at)| !self.is_expired(fetched_at, now))
+            .filter_map(|(name, &fetched_at)| {
+                let cached = self.cache.get(&format!("coords:{name}"))?;
+                let (coordinates, source) = cache::decode_coordinates(&cached)?;
+                // Another client may have filled the entry since
+                if source.as_deref() == Some(cache::INARA_SOURCE) {
+                    return None;
+                }
+                Some(PersistedCoordinates {
+                    name: name.clone(),
+                    fetched_at,
+                    coordinates,
+                })
+            })
+            .collect();
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write aside and rename, so a crash never leaves a truncated cache
+        let partial = self.path.with_extension("json.tmp");
+        fs::write(&partial, serde_json::to_string(&entries)?)?;
+        fs::rename(&partial, &self.path)?;
+
+        debug!(
+            "Saved {} cached systems to {}",
+            entries.len(),
+            self.path.display()
+        );
+        Ok(())
+    }
+}
+
+impl Drop for CacheFile {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            warn!("Failed to save coordinate cache: {e}");
+        }
+    }
+}
+
+/// Error for a request EDSM answered with a failure status
+pub(crate) fn request_failed(status: StatusCode) -> EdjcError {
+    EdjcError::EdsmApi(format!("request failed: {status}"))
+}
+
+/// Decode a JSON response body, reporting malformed bodies as parse errors
+fn decode<T: serde::de::DeserializeOwned>(response: Response) -> EdjcResult<T> {
+    response
+        .json()
+        .map_err(|e| EdjcError::Parse(format!("Unexpected EDSM response: {e}")))
+}
+
+/// Read a single system response, which EDSM sends as `[]` for unknown systems
+pub(crate) fn system_response(
+    system_name: &str,
+    body: serde_json::Value,
+) -> EdjcResult<EdsmSystemResponse> {
+    match body {
+        serde_json::Value::Array(systems) if systems.is_empty() => {
+            Err(EdjcError::system_not_found(system_name))
+        }
+        body => serde_json::from_value(body)
+            .map_err(|e| EdjcError::Parse(format!("Unexpected EDSM response: {e}"))),
+    }
+}
+
+/// The systems named exactly `system_name` in a `/systems` response, which
+/// also lists names it only starts with (and is `{}` when nothing matched)
+fn system_candidates(system_name: &str, body: serde_json::Value) -> Vec<SystemCandidate> {
+    let serde_json::Value::Array(systems) = body else {
+        return Vec::new();
+    };
+    systems
+        .into_iter()
+        .filter_map(|system| serde_json::from_value::<EdsmSystemResponse>(system).ok())
+        .filter(|system| system.name.eq_ignore_ascii_case(system_name))
+        .filter_map(|system| {
+            let id = system.id?;
+            let coordinates = coordinates_from_response(system_name, system).ok()?;
+            Some(SystemCandidate { id, coordinates })
+        })
+        .collect()
+}
+
+/// Delay requested by a `Retry-After` header, in seconds or as an HTTP date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+                .to_std()
+                .unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+/// Query parameters for a single system lookup
+pub(crate) fn system_query(system_name: &str, include_hidden: bool) -> [(&str, &str); 5] {
+    [
+        ("systemName", system_name),
+        ("showCoordinates", "1"),
+        ("showPrimaryStar", "1"),
+        ("showPermit", "1"),
+        ("includeHidden", if include_hidden { "1" } else { "0" }),
+    ]
+}
+
+/// Query parameters for a commander position lookup (API key only if given)
+pub(crate) fn commander_query<'a>(
+    cmdr_name: &'a str,
+    api_key: Option<&'a str>,
+) -> Vec<(&'static str, &'a str)> {
+    let mut query_params = vec![("commanderName", cmdr_name), ("showCoordinates", "1")];
+    if let Some(key) = api_key {
+        query_params.push(("apiKey", key));
+    }
+    query_params
+}
+
+/// Extract the commander's system from a position response
+///
+/// EDSM can't tell a missing commander from one it won't show: without an API
+/// key that is reported as [`EdjcError::ApiKeyMissing`], and with one as
+/// [`EdjcError::ProfilePrivate`].
+pub(crate) fn commander_system(
+    cmdr_name: &str,
+    api_key: Option<&str>,
+    commander_data: EdsmCommanderResponse,
+) -> EdjcResult<String> {
+    // Check for API errors
+    if let Some(msg_num) = commander_data.msg_num {
+        if msg_num == CMDR_NOT_FOUND_MSGNUM {
+            return Err(match api_key {
+                Some(_) => EdjcError::ProfilePrivate(cmdr_name.to_string()),
+                None => EdjcError::ApiKeyMissing(cmdr_name.to_string()),
+            });
+        }
+        if msg_num != 100 {
+            let error_msg = commander_data.msg.unwrap_or("Unknown error".to_string());
+            return Err(EdjcError::EdsmApi(format!("{msg_num}: {error_msg}")));
+        }
+    }
+
+    commander_data
+        .system
+        .ok_or_else(|| EdjcError::CmdrNotFound(format!("{cmdr_name} (no location data available)")))
+}
+
+/// Cache for commander locations, which go stale after `ttl`
+fn location_cache(ttl: Duration) -> Cache<String, String> {
+    Cache::builder()
+        .time_to_live(ttl)
+        .max_capacity(1000)
+        .build()
+}
+
+fn not_found_cache(ttl: Duration) -> Cache<String, Option<Vec<String>>> {
+    Cache::builder()
+        .time_to_live(ttl)
+        .max_capacity(1000)
+        .build()
+}
+
+/// Name prefix to look up suggestions by: all but the last two characters,
+/// but at least three; `None` for names too short to suggest anything for
+fn suggestion_prefix(system_name: &str) -> Option<&str> {
+    let length = system_name.chars().count();
+    if length < 3 {
+        return None;
+    }
+    let keep = length.saturating_sub(2).max(3);
+    let end = system_name
+        .char_indices()
+        .nth(keep)
+        .map_or(system_name.len(), |(index, _)| index);
+    Some(&system_name[..end])
+}
+
+/// The candidates within a few edits of `system_name`, closest first and at
+/// most [`MAX_SUGGESTIONS`] of them
+fn closest_names(system_name: &str, candidates: Vec<String>) -> Vec<String> {
+    let wanted = system_name.to_lowercase();
+    let max_distance = (wanted.chars().count() / 3).max(2);
+    let mut scored: Vec<(usize, String)> = candidates
+        .into_iter()
+        .map(|name| (edit_distance(&wanted, &name.to_lowercase()), name))
+        .filter(|(distance, name)| {
+            *distance <= max_distance && !name.eq_ignore_ascii_case(system_name)
+        })
+        .collect();
+    scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+    scored.dedup_by(|a, b| a.1.eq_ignore_ascii_case(&b.1));
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Levenshtein distance between two names, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Error for a system response without coordinates: EDSM knows systems by
+/// name before anyone has trilaterated them
+fn coordinates_unavailable(system_name: &str, known_name: &str) -> EdjcError {
+    if known_name.trim().is_empty() {
+        EdjcError::system_not_found(system_name)
+    } else {
+        EdjcError::CoordinatesUnavailable(known_name.to_string())
+    }
+}
+
+/// Convert an EDSM system response into coordinates with boost star flags
+pub(crate) fn coordinates_from_response(
+    system_name: &str,
+    system_data: EdsmSystemResponse,
+) -> EdjcResult<SystemCoordinates> {
+    let coords = system_data
+        .coords
+        .ok_or_else(|| coordinates_unavailable(system_name, &system_data.name))?;
+
+    // Determine if system has neutron star or white dwarf
+    let primary_star = system_data.primary_star.as_ref().map(|star| {
+        classify_star(
+            star.star_type.as_deref().unwrap_or(""),
+            star.sub_type.as_deref().unwrap_or(""),
+        )
+    });
+    let has_neutron_star = primary_star.as_ref().is_some_and(StarInfo::is_neutron_star);
+    let has_white_dwarf = primary_star.as_ref().is_some_and(StarInfo::is_white_dwarf);
+
+    // EDSM occasionally returns an empty name; keep the output readable
+    let name = if system_data.name.trim().is_empty() {
+        debug!("EDSM returned an empty name for '{system_name}', using the query name");
+        system_name.to_string()
+    } else {
+        system_data.name
+    };
+
+    let mut coordinates = SystemCoordinates {
+        name,
+        x: coords.x,
+        y: coords.y,
+        z: coords.z,
+        has_neutron_star,
+        has_white_dwarf,
+        primary_star_kind: primary_star.map(|star| star.kind),
+        is_hidden: system_data.hidden,
+        required_permit: system_data
+            .require_permit
+            .then(|| system_data.permit_name.unwrap_or_default()),
+    };
+    if coordinates.normalize_star_flags() {
+        warn!(
+            "Contradictory primary star data for {} (neutron star and white dwarf), keeping the {}",
+            coordinates.name,
+            if coordinates.has_neutron_star {
+                "neutron star"
+            } else {
+                "white dwarf"
+            }
+        );
+    }
+    Ok(coordinates)
+}
+
+/// Whether a system response requested with `showInformation` has people
+/// living there
+fn is_populated(system_data: &EdsmSystemResponse) -> bool {
+    EdsmInformation::deserialize(&system_data.information).is_ok_and(|information| {
+        information
+            .population
+            .is_some_and(|population| population > 0)
+    })
+}
+
+/// Systems along the straight line between two systems, surveyed with
+/// evenly spaced sphere queries (`survey` gets each sphere's centre)
+///
+/// At most `MAX_CORRIDOR_QUERIES` spheres per route, so very long routes are
+/// only sampled. Centres are snapped to a `bucket_ly` grid so nearby routes
+/// survey the same (cached) spheres. Systems found by several spheres are
+/// kept once.
+fn corridor_systems(
+    from: &SystemCoordinates,
+    to: &SystemCoordinates,
+    bucket_ly: f64,
+    deadline: &Deadline,
+    survey: impl Fn((f64, f64, f64)) -> EdjcResult<Vec<SystemCoordinates>>,
+) -> EdjcResult<Vec<SystemCoordinates>> {
+    let distance = calculate_3d_distance(from, to);
+    let queries =
+        ((distance / (2.0 * SPHERE_RADIUS_LY)).ceil() as usize).clamp(1, MAX_CORRIDOR_QUERIES);
+
+    let mut found: Vec<SystemCoordinates> = Vec::new();
+    for index in 0..queries {
+        deadline.check("surveying the route corridor")?;
+        let t = (index as f64 + 0.5) / queries as f64;
+        let snap = |from: f64, to: f64| quantize(from + (to - from) * t, bucket_ly);
+        let systems = survey((snap(from.x, to.x), snap(from.y, to.y), snap(from.z, to.z)))?;
+
+        for system in systems {
+            if !found.iter().any(|known| known.name == system.name) {
+                found.push(system);
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// The nearest multiple of `bucket_ly`, or `value` itself for a zero bucket
+fn quantize(value: f64, bucket_ly: f64) -> f64 {
+    if bucket_ly > 0.0 {
+        (value / bucket_ly).round() * bucket_ly
+    } else {
+        value
+    }
+}
+
+/// Build a [`SystemInfo`] from a system response requested with `showInformation`
+fn system_info_from_response(
+    system_name: &str,
+    mut system_data: EdsmSystemResponse,
+) -> EdjcResult<SystemInfo> {
+    // Unpopulated systems come with `"information": []`
+    let information: EdsmInformation =
+        serde_json::from_value(system_data.information.take()).unwrap_or_default();
+    let primary_star = system_data.primary_star.as_ref().and_then(star_info);
+    let coordinates = coordinates_from_response(system_name, system_data)?;
+    let population = information.population.filter(|&population| population > 0);
+
+    Ok(SystemInfo {
+        coordinates,
+        distance_from_reference: None,
+        has_stations: population.is_some(),
+        population,
+        primary_star,
+        security: information
+            .security
+            .as_deref()
+            .and_then(SecurityLevel::from_str),
+    })
+}
+
+/// Describe EDSM's primary star, if it has a type
+fn star_info(star: &EdsmStar) -> Option<StarInfo> {
+    let star_type = star.star_type.as_deref()?;
+    Some(classify_star(
+        star_type,
+        star.sub_type.as_deref().unwrap_or(""),
+    ))
+}
+
+/// Classify a star from EDSM's `type` and `subType` strings
+///
+/// EDSM spells white dwarfs like "White Dwarf (DA) Star"; the spectral class
+/// may also appear on its own ("DAZ"). Every D-sequence class counts.
+/// Neutron stars turn up as "Neutron Star" in either field, in any case, or
+/// as the journal's bare "N". Black holes are checked first: they can't
+/// supercharge, whatever else the description says.
+fn classify_star(star_type: &str, sub_type: &str) -> StarInfo {
+    let described = [star_type, sub_type];
+    let lowercase = described.map(|s| s.trim().to_lowercase());
+
+    if lowercase
+        .iter()
+        .any(|s| s.contains("black hole") || s.contains("blackhole") || s == "h")
+    {
+        return StarInfo::black_hole();
+    }
+    if lowercase.iter().any(|s| s.contains("neutron") || s == "n") {
+        return StarInfo::neutron_star();
+    }
+
+    let white_dwarf_class = described
+        .iter()
+        .flat_map(|s| spectral_classes(s))
+        .find(|class| is_white_dwarf_class(class));
+    if let Some(class) = white_dwarf_class {
+        return StarInfo::white_dwarf(class);
+    }
+    if lowercase.iter().any(|s| s.starts_with("white dwarf")) {
+        return StarInfo::white_dwarf("D");
+    }
+
+    let class = spectral_classes(star_type)
+        .next()
+        .or_else(|| spectral_classes(sub_type).next())
+        .unwrap_or_default();
+    StarInfo::regular_star(star_type, class)
+}
+
+/// Candidate spectral classes in an EDSM star string: the leading word
+/// ("K (Yellow-Orange) Star") and anything in parentheses ("White Dwarf (DA) Star")
+fn spectral_classes(s: &str) -> impl Iterator<Item = &str> {
+    let parenthesized = s
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(class, _)| class.trim());
+    s.split_whitespace().next().into_iter().chain(parenthesized)
+}
+
+/// Whether a spectral class is a white dwarf's: D followed by up to three of
+/// the D-sequence letters (DA, DAB, DAZ, DAV, DB, DO, DQ, DC, DX, ...)
+fn is_white_dwarf_class(class: &str) -> bool {
+    class
+        .strip_prefix('D')
+        .is_some_and(|rest| rest.len() <= 3 && rest.chars().all(|c| "ABOQCXZV".contains(c)))
+}
+
+/// A system name as typed in chat, tidied up for lookups and cache keys
+///
+/// Trims and collapses whitespace, gives well-known systems their canonical
+/// spelling ("SOL" is "Sol") and spells the boxel and mass code of
+/// procedurally generated names the way the game does ("rs-t D3-94" is
+/// "RS-T d3-94"). Other words are left as typed; EDSM ignores case.
+pub fn normalize_system_name(name: &str) -> String {
+    let mut words: Vec<String> = name.split_whitespace().map(str::to_string).collect();
+    let normalized = words.join(" ");
+    if let Some(known) = LocalSystemSource
+        .system_names()
+        .find(|known| known.eq_ignore_ascii_case(&normalized))
+    {
+        return known.to_string();
+    }
+
+    // "<sector> AB-C d12-34": two boxel letters, a dash and a letter, then
+    // the mass code (a-h) with its number(s)
+    let is_boxel = |word: &str| {
+        let bytes = word.as_bytes();
+        bytes.len() == 4
+            && bytes[2] == b'-'
+            && [bytes[0], bytes[1], bytes[3]]
+                .iter()
+                .all(u8::is_ascii_alphabetic)
+    };
+    let is_mass_code = |word: &str| {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .is_some_and(|code| matches!(code.to_ascii_lowercase(), 'a'..='h'))
+            && word.len() > 1
+            && chars
+                .as_str()
+                .split('-')
+                .all(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+    };
+    if let [.., boxel, mass_code] = words.as_mut_slice() {
+        if is_boxel(boxel) && is_mass_code(mass_code) {
+            *boxel = boxel.to_ascii_uppercase();
+            *mass_code = mass_code.to_ascii_lowercase();
+            return words.join(" ");
+        }
+    }
+    normalized
+}
+
+/// Calculate 3D distance between two system coordinates
+///
+/// EDSM's coordinates are on the 1/32 LY grid, see
+/// [`SystemCoordinates::distance_to_precise`].
+pub(crate) fn calculate_3d_distance(from: &SystemCoordinates, to: &SystemCoordinates) -> f64 {
+    from.distance_to_precise(to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PrimaryStarKind;
+
+    #[test]
+    fn test_distance_calculation() {
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let alpha_centauri = SystemCoordinates {
+            name: "Alpha Centauri".to_string(),
+            x: 3.03125,
+            y: -0.09375,
+            z: 3.15625,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let distance = calculate_3d_distance(&sol, &alpha_centauri);
+        // Alpha Centauri is about 4.38 LY from Sol
+        assert!((distance - 4.377120022057882).abs() < 1e-12, "{distance}");
+    }
+
+    #[test]
+    fn test_large_distance_calculation() {
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        // Sagittarius A* as EDSM has it
+        let sagittarius_a = SystemCoordinates {
+            name: "Sagittarius A*".to_string(),
+            x: 25.21875,
+            y: -20.90625,
+            z: 25899.96875,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
 
         let distance = calculate_3d_distance(&sol, &sagittarius_a);
-        // Sagittarius A* is approximately 25,900 LY from Sol
-        assert!((distance - 25900.0).abs() < 100.0);
+        assert!((distance - 25899.989465395873).abs() < 1e-9, "{distance}");
+    }
+
+    #[test]
+    fn test_cached_coordinates_keep_full_precision() {
+        // Every 1/32 LY step is a short decimal, which JSON writes and reads
+        // back exactly
+        for step in (-2_900_000_i64..2_900_000).step_by(997) {
+            let value = step as f64 / 32.0;
+            let json = serde_json::to_string(&value).unwrap();
+            assert_eq!(serde_json::from_str::<f64>(&json).unwrap(), value, "{json}");
+        }
+
+        let client = EdsmClient::new().unwrap();
+        let beagle_point = SystemCoordinates {
+            name: "Beagle Point".to_string(),
+            x: -1111.5625,
+            y: -134.21875,
+            z: 65269.75,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        client.cache_coordinates("Beagle Point", &beagle_point);
+        let cached = client.cached_coordinates("beagle point").unwrap();
+        assert_eq!(
+            (cached.x, cached.y, cached.z),
+            (beagle_point.x, beagle_point.y, beagle_point.z)
+        );
+    }
+
+    #[test]
+    fn test_hidden_system_response() {
+        let response: EdsmSystemResponse = serde_json::from_str(
+            r#"{
+                "name": "Col 285 Sector AB-C d1-2",
+                "coords": {"x": 10.0, "y": -5.5, "z": 120.25},
+                "hidden": true
+            }"#,
+        )
+        .unwrap();
+
+        let coords = coordinates_from_response("Col 285 Sector AB-C d1-2", response).unwrap();
+        assert!(coords.is_hidden);
+        assert_eq!(coords.hidden_note(), Some("(hidden/duplicate system)"));
+        assert!(coords.summary().ends_with("(hidden/duplicate system)"));
+
+        let response: EdsmSystemResponse =
+            serde_json::from_str(r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#)
+                .unwrap();
+        assert!(
+            !coordinates_from_response("Sol", response)
+                .unwrap()
+                .is_hidden
+        );
+    }
+
+    #[test]
+    fn test_empty_name_falls_back_to_query() {
+        let response: EdsmSystemResponse =
+            serde_json::from_str(r#"{"name": "", "coords": {"x": 1.0, "y": 2.0, "z": 3.0}}"#)
+                .unwrap();
+
+        let coords = coordinates_from_response("Fuelum", response).unwrap();
+        assert_eq!(coords.name, "Fuelum");
+    }
+
+    #[test]
+    fn test_connection_diagnostics() {
+        let server = crate::test_support::MockServer::start();
+        server.respond_after(
+            "/api/system",
+            Duration::from_millis(150),
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 3.0, "y": 4.0, "z": 0.0}}"#,
+        );
+        server.respond("/api/system", 503, "");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let diagnostics = client.connection_diagnostics();
+        assert_eq!(diagnostics.health, ConnectionHealth::Healthy);
+        assert_eq!(diagnostics.status, Some(200));
+        assert_eq!(diagnostics.sol_offset_ly, Some(0.0));
+        let latency = diagnostics.latency.unwrap();
+        assert!(latency >= Duration::from_millis(150), "{latency:?}");
+        assert!(!diagnostics.is_slow());
+        assert!(
+            diagnostics.to_string().starts_with("connection OK ("),
+            "{diagnostics}"
+        );
+
+        let diagnostics = client.connection_diagnostics();
+        assert_eq!(diagnostics.sol_offset_ly, Some(5.0));
+        assert!(matches!(
+            diagnostics.health,
+            ConnectionHealth::UnexpectedResponse(_)
+        ));
+
+        let diagnostics = client.connection_diagnostics();
+        assert_eq!(diagnostics.health, ConnectionHealth::Http(503));
+        assert_eq!(diagnostics.status, Some(503));
+        assert!(diagnostics.latency.is_some());
+
+        let slow = ConnectionDiagnostics {
+            latency: Some(Duration::from_millis(2500)),
+            ..diagnostics
+        };
+        assert!(slow.is_slow());
+        assert_eq!(slow.to_string(), "server returned HTTP 503 (slow, 2500 ms)");
+    }
+
+    #[test]
+    fn test_prefetch_populates_cache() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/systems",
+            200,
+            r#"[
+                {"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}},
+                {"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}
+            ]"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let systems = vec!["Fuelum".to_string(), "sol".to_string()];
+
+        let report = client.prefetch_systems(&systems).unwrap();
+        assert_eq!(report.newly_cached, 2);
+        assert_eq!(report.already_cached, 0);
+        assert!(report.not_found.is_empty());
+
+        let report = client.prefetch_systems(&systems).unwrap();
+        assert_eq!(report.newly_cached, 0);
+        assert_eq!(report.already_cached, 2);
+        assert_eq!(server.hits("/api/systems"), 1);
+
+        // Served from the cache without another request
+        assert_eq!(client.get_system_coordinates("FUELUM").unwrap().x, 52.0);
+        assert_eq!(server.hits("/api/system"), 0);
+    }
+
+    /// Two systems called "Hyades Sector DL-X b1-2" plus one the name is a
+    /// prefix of, as EDSM's `/systems` lists them
+    const DUPLICATE_NAME_RESPONSE: &str = r#"[
+        {"name": "Hyades Sector DL-X b1-2", "id": 11,
+         "coords": {"x": -10.0, "y": 20.0, "z": -100.0}},
+        {"name": "Hyades Sector DL-X b1-2", "id": 12,
+         "coords": {"x": 300.0, "y": 5.0, "z": 800.0},
+         "primaryStar": {"type": "Neutron Star", "subType": ""}},
+        {"name": "Hyades Sector DL-X b1-20", "id": 13,
+         "coords": {"x": 1.0, "y": 2.0, "z": 3.0}}
+    ]"#;
+
+    #[test]
+    fn test_duplicate_names_list_candidates() {
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/systems", 200, DUPLICATE_NAME_RESPONSE);
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let candidates = client.system_candidates("hyades sector dl-x b1-2").unwrap();
+        let ids: Vec<u64> = candidates.iter().map(|candidate| candidate.id).collect();
+        assert_eq!(ids, vec![11, 12]);
+        assert_eq!(candidates[1].coordinates.x, 300.0);
+        assert!(candidates[1].coordinates.has_neutron_star);
+        assert!(server.requests("/api/systems")[0].contains("showId=1"));
+
+        server.respond("/api/systems", 200, "{}");
+        assert!(matches!(
+            client.system_candidates("Nowhere"),
+            Err(EdjcError::SystemNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_system_by_id() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Hyades Sector DL-X b1-2", "id": 12,
+                "coords": {"x": 300.0, "y": 5.0, "z": 800.0}}"#,
+        );
+        server.respond("/api/system", 200, "[]");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let coords = client
+            .get_system_coordinates_by("Hyades Sector DL-X b1-2", Some(12))
+            .unwrap();
+        assert_eq!(coords.x, 300.0);
+        assert!(server.requests("/api/system")[0].contains("systemId=12"));
+
+        // Cached by id, not by the shared name
+        assert_eq!(client.get_system_by_id(12).unwrap().z, 800.0);
+        assert_eq!(server.hits("/api/system"), 1);
+        assert!(client
+            .cached_coordinates("Hyades Sector DL-X b1-2")
+            .is_none());
+
+        // An id belonging to another name is refused
+        let err = client
+            .get_system_coordinates_by("Sol", Some(12))
+            .unwrap_err();
+        assert!(err.to_string().contains("Sol with id 12"), "{err}");
+
+        assert!(matches!(
+            client.get_system_by_id(99),
+            Err(EdjcError::SystemNotFound { .. })
+        ));
+    }
+
+    /// An in-memory stand-in for a shared (Redis, file) cache that records
+    /// every key looked up
+    #[derive(Debug, Clone, Default)]
+    struct FakeCache {
+        entries: Arc<Mutex<HashMap<String, String>>>,
+        lookups: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl CoordinateCache for FakeCache {
+        fn get(&self, key: &str) -> Option<String> {
+            self.lookups.lock().unwrap().push(key.to_string());
+            self.entries.lock().unwrap().get(key).cloned()
+        }
+
+        fn insert(&self, key: String, value: String) {
+            self.entries.lock().unwrap().insert(key, value);
+        }
+
+        fn invalidate(&self, key: &str) {
+            self.entries.lock().unwrap().remove(key);
+        }
+
+        fn invalidate_all(&self) {
+            self.entries.lock().unwrap().clear();
+        }
+    }
+
+    #[test]
+    fn test_custom_cache_consulted_before_edsm() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Maia", "coords": {"x": -81.78125, "y": -149.4375, "z": -343.375}}"#,
+        );
+        let cache = FakeCache::default();
+        cache.insert(
+            "coords:fuelum".to_string(),
+            r#"{"name": "Fuelum", "x": 52.0, "y": -52.65625, "z": 49.8125,
+                "has_neutron_star": false, "has_white_dwarf": false}"#
+                .to_string(),
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_cache(cache.clone());
+
+        // Already in the shared cache: EDSM isn't asked
+        assert_eq!(client.get_system_coordinates("Fuelum").unwrap().x, 52.0);
+        assert_eq!(server.hits("/api/system"), 0);
+        assert_eq!(*cache.lookups.lock().unwrap(), vec!["coords:fuelum"]);
+
+        // Fetched once, then stored for the next process to find
+        assert_eq!(client.get_system_coordinates("Maia").unwrap().name, "Maia");
+        assert_eq!(client.get_system_coordinates("maia").unwrap().name, "Maia");
+        assert_eq!(server.hits("/api/system"), 1);
+        assert!(cache.entries.lock().unwrap().contains_key("coords:maia"));
+
+        client.clear_cache();
+        assert!(cache.entries.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_system_info_from_information_block() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0},
+                "information": {"allegiance": "Federation", "government": "Democracy",
+                    "population": 22780919531, "security": "High", "economy": "Refinery"},
+                "primaryStar": {"type": "G (White-Yellow) Star", "isScoopable": true}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Shinrarta Dezhra", "coords": {"x": 55.71875, "y": 17.59375, "z": 27.15625},
+                "information": {"population": 85206935, "security": "Anarchy"}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum Neutron", "coords": {"x": 1.0, "y": 2.0, "z": 3.0},
+                "information": [], "primaryStar": {"type": "Neutron Star"}}"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let sol = client.get_system_info("Sol").unwrap();
+        assert_eq!(sol.security, Some(SecurityLevel::High));
+        assert_eq!(sol.population, Some(22780919531));
+        assert!(sol.has_stations);
+        let star = sol.primary_star.unwrap();
+        assert_eq!(star.star_class, "G");
+        assert!(!star.can_supercharge);
+        assert!(server.requests("/api/system")[0].contains("showInformation=1"));
+
+        let anarchy = client.get_system_info("Shinrarta Dezhra").unwrap();
+        assert_eq!(anarchy.security, Some(SecurityLevel::Anarchy));
+
+        // Unpopulated systems send an empty array instead of an object
+        let empty = client.get_system_info("Fuelum Neutron").unwrap();
+        assert_eq!(empty.security, None);
+        assert_eq!(empty.population, None);
+        assert!(!empty.has_stations);
+        assert_eq!(empty.primary_star.unwrap().supercharge_multiplier, 4.0);
+
+        // The coordinates are cached for route planning
+        client.get_system_coordinates("Sol").unwrap();
+        assert_eq!(server.hits("/api/system"), 3);
+    }
+
+    #[test]
+    fn test_classify_white_dwarf_subclasses() {
+        for class in [
+            "D", "DA", "DAB", "DAO", "DAZ", "DAV", "DB", "DBZ", "DBV", "DO", "DOV", "DQ", "DC",
+            "DCV", "DX",
+        ] {
+            let star = classify_star(&format!("White Dwarf ({class}) Star"), "");
+            assert!(star.is_white_dwarf(), "{class}");
+            assert_eq!(star.kind, PrimaryStarKind::WhiteDwarf);
+            assert_eq!(star.star_class, class);
+            assert_eq!(star.supercharge_multiplier, 1.5);
+
+            // A bare spectral class in subType is enough
+            let star = classify_star("", class);
+            assert!(star.is_white_dwarf(), "{class}");
+            assert_eq!(star.star_class, class);
+        }
+    }
+
+    #[test]
+    fn test_classify_other_stars() {
+        let star = classify_star("Neutron Star", "");
+        assert!(star.is_neutron_star());
+        assert_eq!(star.supercharge_multiplier, 4.0);
+
+        let star = classify_star("K (Yellow-Orange) Star", "");
+        assert!(!star.can_supercharge);
+        assert_eq!(star.star_class, "K");
+        assert_eq!(star.kind, PrimaryStarKind::MainSequence);
+
+        // Names starting with D that aren't D-sequence classes
+        for (star_type, sub_type) in [
+            ("Draconis Star", ""),
+            ("L (Brown dwarf) Star", "Dwarf"),
+            ("T Tauri Star", "DAE"),
+        ] {
+            let star = classify_star(star_type, sub_type);
+            assert!(!star.is_white_dwarf(), "{star_type} / {sub_type}");
+            assert_eq!(
+                star.kind,
+                PrimaryStarKind::Other,
+                "{star_type} / {sub_type}"
+            );
+            assert!(!star.can_supercharge);
+        }
+    }
+
+    #[test]
+    fn test_classify_neutron_star_phrasings() {
+        for (star_type, sub_type) in [
+            ("Neutron Star", ""),
+            ("", "Neutron Star"),
+            ("neutron star", ""),
+            ("NEUTRON STAR", ""),
+            ("Star", "neutron"),
+            (" Neutron ", ""),
+            ("N", ""),
+        ] {
+            let star = classify_star(star_type, sub_type);
+            assert!(star.is_neutron_star(), "{star_type:?} / {sub_type:?}");
+            assert_eq!(star.supercharge_multiplier, 4.0);
+        }
+    }
+
+    #[test]
+    fn test_black_hole_is_not_a_boost() {
+        for (star_type, sub_type) in [
+            ("Black Hole", ""),
+            ("Supermassive Black Hole", ""),
+            ("", "black hole"),
+            ("SupermassiveBlackHole", ""),
+            ("H", ""),
+            // Black holes are sometimes filed next to neutron stars
+            ("Black Hole", "Neutron Star"),
+        ] {
+            let star = classify_star(star_type, sub_type);
+            assert!(star.is_black_hole(), "{star_type:?} / {sub_type:?}");
+            assert_eq!(star.kind, PrimaryStarKind::BlackHole);
+            assert!(!star.is_neutron_star());
+            assert!(!star.can_supercharge);
+            assert_eq!(star.supercharge_multiplier, 1.0);
+        }
+
+        let response: EdsmSystemResponse = serde_json::from_str(
+            r#"{"name": "Sagittarius A*", "coords": {"x": 25.2, "y": -20.9, "z": 25899.9},
+                "primaryStar": {"type": "Supermassive Black Hole", "name": "Sagittarius A*"}}"#,
+        )
+        .unwrap();
+        let system = coordinates_from_response("Sagittarius A*", response).unwrap();
+        assert!(!system.has_neutron_star && !system.can_supercharge());
+        assert_eq!(system.primary_star_kind, Some(PrimaryStarKind::BlackHole));
+
+        // A black hole another source also flagged as a neutron star
+        let flagged = SystemCoordinates {
+            has_neutron_star: true,
+            ..system
+        };
+        assert!(!flagged.can_supercharge());
+        assert_eq!(flagged.supercharge_multiplier(), 1.0);
+    }
+
+    #[test]
+    fn test_primary_star_kind_from_response() {
+        for (primary_star, kind) in [
+            (r#"{"type": "T Tauri Star"}"#, PrimaryStarKind::Other),
+            (
+                r#"{"type": "White Dwarf (DAV) Star"}"#,
+                PrimaryStarKind::WhiteDwarf,
+            ),
+            (
+                r#"{"type": "White Dwarf (DQ) Star"}"#,
+                PrimaryStarKind::WhiteDwarf,
+            ),
+            (
+                r#"{"type": "", "subType": "DCV"}"#,
+                PrimaryStarKind::WhiteDwarf,
+            ),
+            (r#"{"type": "Neutron Star"}"#, PrimaryStarKind::Neutron),
+            (
+                r#"{"type": "M (Red dwarf) Star"}"#,
+                PrimaryStarKind::MainSequence,
+            ),
+        ] {
+            let response: EdsmSystemResponse = serde_json::from_str(&format!(
+                r#"{{"name": "Test", "coords": {{"x": 0.0, "y": 0.0, "z": 0.0}},
+                    "primaryStar": {primary_star}}}"#
+            ))
+            .unwrap();
+            let system = coordinates_from_response("Test", response).unwrap();
+            assert_eq!(system.primary_star_kind, Some(kind), "{primary_star}");
+            assert_eq!(system.can_supercharge(), kind.can_supercharge());
+        }
+    }
+
+    #[test]
+    fn test_nearest_neutron_star() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[
+                {"distance": 12.0, "name": "Yellow Star", "coords": {"x": 12.0, "y": 0.0, "z": 0.0},
+                 "primaryStar": {"type": "G (White-Yellow) Star", "isScoopable": true}},
+                {"distance": 40.0, "name": "Far Neutron", "coords": {"x": 0.0, "y": 40.0, "z": 0.0},
+                 "primaryStar": {"type": "Neutron Star", "isScoopable": false}},
+                {"distance": 25.0, "name": "Near Neutron", "coords": {"x": 0.0, "y": 0.0, "z": -25.0},
+                 "primaryStar": {"type": "Neutron Star", "isScoopable": false}},
+                {"distance": 18.0, "name": "Dwarf", "coords": {"x": 0.0, "y": 18.0, "z": 0.0},
+                 "primaryStar": {"type": "White Dwarf (DA) Star", "isScoopable": false}},
+                {"distance": 30.0, "name": "Unknown Star", "coords": {"x": 30.0, "y": 0.0, "z": 0.0}}
+            ]"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let nearest = client.nearest_neutron_star(&sol, 250.0).unwrap().unwrap();
+        assert_eq!(nearest.name, "Near Neutron");
+        assert_eq!(sol.distance_to(&nearest), 25.0);
+        // The radius is capped at EDSM's limit
+        assert!(server.requests("/api/sphere-systems")[0].contains("radius=100"));
+
+        // An empty sphere is an empty object
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/sphere-systems", 200, "{}");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        assert!(client.nearest_neutron_star(&sol, 50.0).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_corridor_populated_systems() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[
+                {"name": "Outpost", "coords": {"x": 0.0, "y": 0.0, "z": 95.0},
+                 "information": {"population": 450}},
+                {"name": "Empty", "coords": {"x": 0.0, "y": 0.0, "z": 60.0}, "information": []}
+            ]"#,
+        );
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[
+                {"name": "Outpost", "coords": {"x": 0.0, "y": 0.0, "z": 95.0},
+                 "information": {"population": 450}},
+                {"name": "Colony", "coords": {"x": 0.0, "y": 0.0, "z": 240.0},
+                 "information": {"population": 12000}}
+            ]"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let system = |name: &str, z: f64| SystemCoordinates {
+            name: name.to_string(),
+            x: 0.0,
+            y: 0.0,
+            z,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let populated = client
+            .corridor_populated_systems_by(
+                &system("Start", 0.0),
+                &system("Finish", 300.0),
+                &Deadline::never(),
+            )
+            .unwrap();
+        let names: Vec<&str> = populated.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Outpost", "Colony"]);
+        let requests = server.requests("/api/sphere-systems");
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.contains("showInformation=1")));
+    }
+
+    #[test]
+    fn test_nearest_populated_system() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[
+                {"distance": 8.0, "name": "Empty Close", "coords": {"x": 8.0, "y": 0.0, "z": 0.0},
+                 "information": []},
+                {"distance": 60.0, "name": "Far Outpost", "coords": {"x": 0.0, "y": 60.0, "z": 0.0},
+                 "information": {"allegiance": "Independent", "population": 12000}},
+                {"distance": 35.0, "name": "Near Outpost", "coords": {"x": 0.0, "y": 0.0, "z": -35.0},
+                 "information": {"allegiance": "Independent", "population": 450}},
+                {"distance": 20.0, "name": "Abandoned", "coords": {"x": 0.0, "y": 20.0, "z": 0.0},
+                 "information": {"population": 0}},
+                {"distance": 30.0, "name": "No Information", "coords": {"x": 30.0, "y": 0.0, "z": 0.0}}
+            ]"#,
+        );
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[{"name": "Empty Close", "coords": {"x": 8.0, "y": 0.0, "z": 0.0}, "information": []}]"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let case = SystemCoordinates {
+            name: "Deep Case".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let nearest = client
+            .nearest_populated_system(&case, 250.0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(nearest.name, "Near Outpost");
+        assert_eq!(case.distance_to(&nearest), 35.0);
+        let request = &server.requests("/api/sphere-systems")[0];
+        assert!(request.contains("showInformation=1"), "{request}");
+        assert!(request.contains("radius=100"), "{request}");
+
+        // Nobody within reach
+        assert!(client
+            .nearest_populated_system(&case, 50.0)
+            .unwrap()
+            .is_none());
+        // Both spheres are cached
+        client.nearest_populated_system(&case, 50.0).unwrap();
+        assert_eq!(server.hits("/api/sphere-systems"), 2);
+    }
+
+    #[test]
+    fn test_cache_survives_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("edsm_cache.json");
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+        let client = || {
+            EdsmClient::new()
+                .unwrap()
+                .with_api_urls(&server.url("/api"), &server.url("/logs"))
+                .with_cache_file(&path)
+        };
+
+        let first = client();
+        first.get_system_coordinates("Fuelum").unwrap();
+        drop(first);
+        assert!(path.exists());
+
+        // A new session answers from the file without asking EDSM
+        let second = client();
+        assert_eq!(second.get_system_coordinates("fuelum").unwrap().x, 52.0);
+        assert_eq!(server.hits("/api/system"), 1);
+    }
+
+    #[test]
+    fn test_expired_cache_entries_are_not_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("edsm_cache.json");
+        let fresh = Utc::now().timestamp();
+        let stale = fresh - CACHE_TTL_SECONDS as i64;
+        std::fs::write(
+            &path,
+            format!(
+                r#"[
+                    {{"name": "sol", "fetched_at": {fresh}, "coordinates": {{"name": "Sol",
+                      "x": 0.0, "y": 0.0, "z": 0.0, "has_neutron_star": false,
+                      "has_white_dwarf": false}}}},
+                    {{"name": "fuelum", "fetched_at": {stale}, "coordinates": {{"name": "Fuelum",
+                      "x": 52.0, "y": -52.65625, "z": 49.8125, "has_neutron_star": false,
+                      "has_white_dwarf": false}}}}
+                ]"#
+            ),
+        )
+        .unwrap();
+
+        let client = EdsmClient::new().unwrap().with_cache_file(&path);
+        assert!(client.cached_coordinates("Sol").is_some());
+        assert!(client.cached_coordinates("Fuelum").is_none());
+    }
+
+    #[test]
+    fn test_normalize_system_name() {
+        assert_eq!(normalize_system_name("  colonia "), "Colonia");
+        assert_eq!(normalize_system_name("SOL"), "Sol");
+        assert_eq!(normalize_system_name("Sagittarius  A*"), "Sagittarius A*");
+        assert_eq!(normalize_system_name("sagittarius\ta*"), "Sagittarius A*");
+
+        // Procedural names get the game's casing for boxel and mass code
+        assert_eq!(
+            normalize_system_name(" eol prou rs-t D3-94"),
+            "eol prou RS-T d3-94"
+        );
+        assert_eq!(
+            normalize_system_name("Synuefe  xr-h C11-9"),
+            "Synuefe XR-H c11-9"
+        );
+
+        // Anything else keeps its casing
+        assert_eq!(normalize_system_name("fuelum "), "fuelum");
+        assert_eq!(normalize_system_name("Wolf   359"), "Wolf 359");
+        assert_eq!(normalize_system_name("   "), "");
+    }
+
+    #[test]
+    fn test_lookups_use_normalized_names() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sagittarius A*", "coords": {"x": 25.21875, "y": -20.90625, "z": 25899.96875}}"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let first = client.get_system_coordinates(" sagittarius  a* ").unwrap();
+        let request = &server.requests("/api/system")[0];
+        assert!(request.contains("systemName=Sagittarius+A*&"), "{request}");
+
+        // Differently typed, the same cache entry
+        let second = client.get_system_coordinates("SAGITTARIUS A*").unwrap();
+        assert_eq!(first.name, second.name);
+        assert_eq!(server.hits("/api/system"), 1);
+    }
+
+    #[test]
+    fn test_batch_coordinates_keep_input_order() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Colonia", "coords": {"x": -9530.5, "y": -910.28125, "z": 19808.125}}"#,
+        );
+        server.respond(
+            "/api/systems",
+            200,
+            r#"[
+                {"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}},
+                {"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125},
+                 "primaryStar": {"type": "Neutron Star"}}
+            ]"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        client.get_system_coordinates("Colonia").unwrap();
+
+        let systems = client
+            .get_systems_coordinates(&["fuelum", "Colonia", "Sol", "FUELUM"])
+            .unwrap();
+        let names: Vec<&str> = systems.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Fuelum", "Colonia", "Sol", "Fuelum"]);
+        assert!(systems[0].has_neutron_star);
+
+        // Only the uncached systems were requested, each once
+        assert_eq!(server.hits("/api/systems"), 1);
+        let request = &server.requests("/api/systems")[0];
+        assert!(!request.contains("Colonia"));
+        assert_eq!(request.matches("systemName").count(), 2);
+
+        client.get_systems_coordinates(&["Sol", "Fuelum"]).unwrap();
+        assert_eq!(server.hits("/api/systems"), 1);
+    }
+
+    #[test]
+    fn test_batch_coordinates_report_unknown_systems() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/systems",
+            200,
+            r#"[{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}]"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let err = client
+            .get_systems_coordinates(&["Nowhere", "Sol", "Atlantis"])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "System not found: Nowhere, Atlantis");
+    }
+
+    #[test]
+    fn test_system_without_coordinates() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Col 285 Sector XY-Z a1-2"}"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let err = client
+            .get_system_coordinates("col 285 sector xy-z a1-2")
+            .unwrap_err();
+        assert!(
+            matches!(&err, EdjcError::CoordinatesUnavailable(name) if name == "Col 285 Sector XY-Z a1-2"),
+            "{err}"
+        );
+        assert_eq!(err.hint(), Some("ask for a nearby known system"));
+
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/system", 200, "[]");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let err = client.get_system_coordinates("Nowhere").unwrap_err();
+        assert!(matches!(err, EdjcError::SystemNotFound { .. }), "{err}");
+    }
+
+    #[test]
+    fn test_unknown_system_and_network_errors_are_distinct() {
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/system", 200, "[]");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let err = client.get_system_coordinates("Nowhere").unwrap_err();
+        assert!(
+            matches!(&err, EdjcError::SystemNotFound { name, .. } if name == "Nowhere"),
+            "{err}"
+        );
+        assert_eq!(err.hint(), Some("check the spelling"));
+
+        // Nothing listens on a port that was just released
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let unreachable = format!("http://127.0.0.1:{port}");
+        let client = client.with_api_urls(&unreachable, &unreachable);
+
+        let err = client.get_system_coordinates("Sol").unwrap_err();
+        assert!(matches!(err, EdjcError::Network(_)), "{err}");
+        assert_eq!(err.hint(), Some("server unreachable, try again later"));
+
+        let err = client
+            .get_commander_location("Whit3Arrow", None)
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::Network(_)), "{err}");
+    }
+
+    #[test]
+    fn test_unknown_system_suggestions() {
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/system", 200, "[]");
+        server.respond(
+            "/api/systems",
+            200,
+            r#"[{"name": "Colonia"}, {"name": "Colonia Hub"}, {"name": "Colonis"},
+                {"name": "Colonnaise Sector AA-A h0"}, {"name": "Colon Sector"}]"#,
+        );
+        server.respond("/api/systems", 200, "{}");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_suggestions(true);
+
+        let err = client.get_system_coordinates("Colonai").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "System not found: Colonai (did you mean: Colonia, Colonis?)"
+        );
+        assert!(
+            matches!(&err, EdjcError::SystemNotFound { name, suggestions }
+                if name == "Colonai" && suggestions == &["Colonia", "Colonis"]),
+            "{err:?}"
+        );
+        assert!(server.requests("/api/systems")[0].contains("systemName=Colon&"));
+
+        // The suggestions are remembered with the miss
+        let err = client.get_system_coordinates("colonai").unwrap_err();
+        assert!(err.to_string().contains("did you mean: Colonia"), "{err}");
+        assert_eq!(server.hits("/api/systems"), 1);
+
+        // Nothing named like gibberish
+        let err = client.get_system_coordinates("Xqzvvkjw").unwrap_err();
+        assert_eq!(err.to_string(), "System not found: Xqzvvkjw");
+        assert_eq!(server.hits("/api/systems"), 2);
+
+        // Without suggestions only the system itself is looked up
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let err = client.get_system_coordinates("Colonai").unwrap_err();
+        assert_eq!(err.to_string(), "System not found: Colonai");
+        assert_eq!(server.hits("/api/systems"), 2);
+    }
+
+    #[test]
+    fn test_batch_miss_still_gets_suggestions() {
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/systems", 200, "{}");
+        server.respond("/api/systems", 200, r#"[{"name": "Colonia"}]"#);
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_suggestions(true);
+
+        let err = client.get_systems_coordinates(&["Colonai"]).unwrap_err();
+        assert_eq!(err.to_string(), "System not found: Colonai");
+
+        // The batch miss is remembered, but not as having nothing to suggest
+        let err = client.get_system_coordinates("Colonai").unwrap_err();
+        assert!(
+            matches!(&err, EdjcError::SystemNotFound { suggestions, .. } if suggestions == &["Colonia"]),
+            "{err:?}"
+        );
+        assert_eq!(server.hits("/api/system"), 0);
+        client.get_system_coordinates("Colonai").unwrap_err();
+        assert_eq!(server.hits("/api/systems"), 2);
+    }
+
+    #[test]
+    fn test_closest_names() {
+        assert_eq!(edit_distance("fuelom", "fuelum"), 1);
+        assert_eq!(edit_distance("colonai", "colonia"), 2);
+        assert_eq!(edit_distance("", "sol"), 3);
+        assert_eq!(suggestion_prefix("Fuelom"), Some("Fuel"));
+        assert_eq!(suggestion_prefix("Sola"), Some("Sol"));
+        assert_eq!(suggestion_prefix("So"), None);
+
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        assert_eq!(
+            closest_names(
+                "Fuelom",
+                names(&["Fuelum", "Fuel Depot", "FUELUN", "Fuelum Alpha"])
+            ),
+            vec!["Fuelum", "FUELUN"]
+        );
+        assert!(closest_names("Fuelom", names(&["Fuelsdottir Sector"])).is_empty());
+    }
+
+    #[test]
+    fn test_not_found_is_cached_briefly() {
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/system", 200, "[]");
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Nowhere", "coords": {"x": 1.0, "y": 2.0, "z": 3.0}}"#,
+        );
+        server.respond("/api/systems", 200, "[]");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        // The second lookup within the window never reaches EDSM
+        for _ in 0..2 {
+            let err = client.get_system_coordinates("Nowhere").unwrap_err();
+            assert!(matches!(err, EdjcError::SystemNotFound { .. }), "{err}");
+        }
+        assert!(client.get_systems_coordinates(&["NOWHERE"]).is_err());
+        assert_eq!(server.hits("/api/system"), 1);
+        assert_eq!(server.hits("/api/systems"), 0);
+
+        // Not remembered as coordinates, and dropped once EDSM has the system
+        assert!(client.cached_coordinates("Nowhere").is_none());
+        client.refresh_system_coordinates("Nowhere").unwrap();
+        assert_eq!(client.get_system_coordinates("Nowhere").unwrap().x, 1.0);
+        assert_eq!(server.hits("/api/system"), 2);
+
+        // Batches remember the names EDSM didn't return
+        assert!(client.get_systems_coordinates(&["Elsewhere"]).is_err());
+        assert!(client.get_system_coordinates("Elsewhere").is_err());
+        assert_eq!(server.hits("/api/systems"), 1);
+        assert_eq!(server.hits("/api/system"), 2);
+
+        // Without a window every lookup asks again
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/system", 200, "[]");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_not_found_ttl(Duration::ZERO);
+        assert!(client.get_system_coordinates("Nowhere").is_err());
+        assert!(client.get_system_coordinates("Nowhere").is_err());
+        assert_eq!(server.hits("/api/system"), 2);
+    }
+
+    #[test]
+    fn test_batch_coordinates_are_chunked() {
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/systems", 200, "{}");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let names: Vec<String> = (0..120).map(|i| format!("System {i}")).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        assert!(client.get_systems_coordinates(&names).is_err());
+        assert_eq!(server.hits("/api/systems"), 3);
+    }
+
+    #[test]
+    fn test_neutron_highway_through_corridor() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[
+                {"distance": 45.0, "name": "Neutron A", "coords": {"x": 20.0, "y": 0.0, "z": 0.0},
+                 "primaryStar": {"type": "Neutron Star", "isScoopable": false}},
+                {"distance": 35.0, "name": "Neutron B", "coords": {"x": 100.0, "y": 0.0, "z": 0.0},
+                 "primaryStar": {"type": "Neutron Star", "isScoopable": false}},
+                {"distance": 50.0, "name": "Plain G", "coords": {"x": 140.0, "y": 0.0, "z": 0.0},
+                 "primaryStar": {"type": "G (White-Yellow) Star", "isScoopable": true}},
+                {"distance": 115.0, "name": "Neutron C", "coords": {"x": 180.0, "y": 0.0, "z": 0.0},
+                 "primaryStar": {"type": "Neutron Star", "isScoopable": false}}
+            ]"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let from = SystemCoordinates {
+            name: "Start".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let to = SystemCoordinates {
+            name: "Finish".to_string(),
+            x: 260.0,
+            ..from.clone()
+        };
+
+        let neutrons = client.corridor_neutron_stars(&from, &to).unwrap();
+        assert_eq!(neutrons.len(), 3);
+        let hits = server.hits("/api/sphere-systems");
+        assert_eq!(hits, 2);
+
+        // Surveyed once; a second lookup is served from the cache
+        client.corridor_neutron_stars(&from, &to).unwrap();
+        assert_eq!(server.hits("/api/sphere-systems"), hits);
+
+        // Start -> A -> B -> C -> Finish, supercharging at every neutron star
+        let result = crate::jump_calculator::JumpCalculator::new()
+            .with_boost_thresholds(0.0, 0.0)
+            .calculate_route_with_corridor(
+                &from,
+                &to,
+                20.0,
+                &crate::types::RouteOptions::default(),
+                Some(&neutrons),
+            )
+            .unwrap();
+        assert_eq!(result.jumps, 4);
+        assert_eq!(result.route_type, "neutron highway (3 boosts)");
+    }
+
+    #[test]
+    fn test_systems_in_cube() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/cube-systems",
+            200,
+            r#"[
+                {"name": "Field Neutron", "coords": {"x": 40.0, "y": -10.0, "z": 25.0},
+                 "primaryStar": {"type": "Neutron Star", "isScoopable": false}},
+                {"name": "Field Dwarf", "coords": {"x": -30.0, "y": 5.0, "z": 60.0},
+                 "primaryStar": {"type": "White Dwarf (DA) Star", "isScoopable": false}},
+                {"name": "Field K", "coords": {"x": 10.0, "y": 0.0, "z": -45.0},
+                 "primaryStar": {"type": "K (Yellow-Orange) Star", "isScoopable": true}},
+                {"name": "Unknown Coords"}
+            ]"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let center = SystemCoordinates {
+            name: "Waypoint".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let systems = client.systems_in_cube(&center, 150.0).unwrap();
+        let names: Vec<&str> = systems.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Field Neutron", "Field Dwarf", "Field K"]);
+        assert!(systems[0].has_neutron_star);
+        assert!(systems[1].has_white_dwarf);
+        assert_eq!(systems[2].z, -45.0);
+
+        let request = &server.requests("/api/cube-systems")[0];
+        assert!(request.contains("size=150"));
+        assert!(request.contains("showPrimaryStar=1"));
+
+        // Cached like the sphere queries
+        client.systems_in_cube(&center, 150.0).unwrap();
+        assert_eq!(server.hits("/api/cube-systems"), 1);
+
+        // Over EDSM's cap, or empty, is refused before asking EDSM
+        for size in [200.5, 0.0, f64::NAN] {
+            let error = client.systems_in_cube(&center, size).unwrap_err();
+            assert!(error.to_string().contains("at most 200 LY per side"));
+        }
+        assert_eq!(server.hits("/api/cube-systems"), 1);
+        assert!(client.systems_in_cube(&center, 200.0).is_ok());
+    }
+
+    #[test]
+    fn test_corridor_queries_are_capped() {
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/sphere-systems", 200, "{}");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let from = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let to = SystemCoordinates {
+            name: "Colonia".to_string(),
+            x: -9530.5,
+            y: -910.28125,
+            z: 19808.125,
+            ..from.clone()
+        };
+
+        assert!(client
+            .corridor_neutron_stars(&from, &to)
+            .unwrap()
+            .is_empty());
+        assert_eq!(server.hits("/api/sphere-systems"), MAX_CORRIDOR_QUERIES);
+    }
+
+    #[test]
+    fn test_overlapping_corridors_share_sphere_queries() {
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/sphere-systems", 200, "[]");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let fuelum = SystemCoordinates {
+            name: "Fuelum".to_string(),
+            x: 52.0,
+            y: -52.65625,
+            z: 49.8125,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        // Two cases a few LY apart: one sphere each, in the same bucket
+        let case = SystemCoordinates {
+            name: "Case One".to_string(),
+            z: 199.0,
+            ..fuelum.clone()
+        };
+        let neighbour = SystemCoordinates {
+            name: "Case Two".to_string(),
+            x: 55.0,
+            z: 207.0,
+            ..fuelum.clone()
+        };
+
+        client.corridor_neutron_stars(&fuelum, &case).unwrap();
+        client.corridor_neutron_stars(&fuelum, &neighbour).unwrap();
+        assert_eq!(server.hits("/api/sphere-systems"), 1);
+        let request = &server.requests("/api/sphere-systems")[0];
+        assert!(request.contains("x=60.00") && request.contains("z=120.00"));
+
+        // Without buckets each corridor asks for its own sphere
+        let exact = client.clone().with_corridor_bucket(0.0);
+        exact.clear_cache();
+        exact.corridor_neutron_stars(&fuelum, &case).unwrap();
+        exact.corridor_neutron_stars(&fuelum, &neighbour).unwrap();
+        assert_eq!(server.hits("/api/sphere-systems"), 3);
+    }
+
+    #[test]
+    fn test_quantize() {
+        assert_eq!(quantize(75.0, 20.0), 80.0);
+        assert_eq!(quantize(-52.65625, 20.0), -60.0);
+        assert_eq!(quantize(-52.65625, 0.0), -52.65625);
+    }
+
+    #[test]
+    fn test_retries_rate_limits_and_server_errors() {
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/system", 503, "");
+        server.respond_with_headers("/api/system", 429, &[("Retry-After", "0")], "");
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::ZERO,
+            });
+
+        let coords = client.get_system_coordinates("Fuelum").unwrap();
+        assert_eq!(coords.name, "Fuelum");
+        assert_eq!(server.hits("/api/system"), 3);
+
+        // Out of attempts: the last status is reported
+        let server = crate::test_support::MockServer::start();
+        server.respond("/logs/get-position", 502, "");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 2,
+                base_delay: Duration::ZERO,
+            });
+        let err = client
+            .get_commander_location("Whit3Arrow", None)
+            .unwrap_err();
+        assert!(err.to_string().contains("502"), "{err}");
+        assert_eq!(server.hits("/logs/get-position"), 2);
+
+        // Client errors are not retried
+        let server = crate::test_support::MockServer::start();
+        let client = client.with_api_urls(&server.url("/api"), &server.url("/logs"));
+        assert!(client.get_system_coordinates("Nowhere").is_err());
+        assert_eq!(server.hits("/api/system"), 1);
+    }
+
+    #[test]
+    fn test_deadline_bounds_requests_and_retries() {
+        let server = crate::test_support::MockServer::start();
+        server.respond_with_headers("/api/system", 429, &[("Retry-After", "60")], "");
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        // A Retry-After longer than the time left isn't waited for
+        let started = Instant::now();
+        let deadline = Deadline::after(Duration::from_secs(5));
+        let err = client
+            .bounded_by(&deadline)
+            .get_system_coordinates("Fuelum")
+            .unwrap_err();
+        assert!(err.to_string().contains("429"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(server.hits("/api/system"), 1);
+
+        // A slow answer is cut off at the deadline
+        let server = crate::test_support::MockServer::start();
+        server.respond_after("/api/system", Duration::from_secs(5), 200, "{}");
+        let client = client.with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let deadline = Deadline::after(Duration::from_millis(200));
+        let err = client
+            .bounded_by(&deadline)
+            .get_system_coordinates("Deciat")
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::Timeout(_)), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Nothing is asked once it has passed
+        let hits = server.hits("/api/system");
+        assert!(matches!(
+            client.bounded_by(&deadline).get_system_coordinates("Maia"),
+            Err(EdjcError::Timeout(_))
+        ));
+        assert_eq!(server.hits("/api/system"), hits);
+    }
+
+    #[test]
+    fn test_retry_delays() {
+        let policy = RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, "2".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+        headers.insert(RETRY_AFTER, "86400".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(MAX_RETRY_AFTER));
+        // A date in the past means "now"
+        headers.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limit = RateLimit {
+            burst: 2,
+            interval: Duration::from_millis(100),
+        };
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(limit, start);
+
+        assert_eq!(bucket.acquire(limit, start), Duration::ZERO);
+        assert_eq!(bucket.acquire(limit, start), Duration::ZERO);
+        // Out of tokens: each caller queues behind the previous one
+        assert_eq!(bucket.acquire(limit, start), Duration::from_millis(100));
+        assert_eq!(bucket.acquire(limit, start), Duration::from_millis(200));
+
+        // Refilling never saves up more than the burst
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.acquire(limit, later), Duration::ZERO);
+        assert_eq!(bucket.acquire(limit, later), Duration::ZERO);
+        assert_eq!(bucket.acquire(limit, later), Duration::from_millis(100));
+
+        let unlimited = RateLimit {
+            burst: 1,
+            interval: Duration::ZERO,
+        };
+        assert_eq!(bucket.acquire(unlimited, later), Duration::ZERO);
+        assert_eq!(bucket.acquire(unlimited, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_logs_api_requests_are_spaced_out() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Fuelum"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+        let interval = Duration::from_millis(150);
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_rate_limit(
+                Endpoint::CommanderPosition,
+                RateLimit { burst: 1, interval },
+            );
+
+        let start = Instant::now();
+        for cmdr in ["Whit3Arrow", "Surly Badger", "Mecha"] {
+            client.get_commander_location(cmdr, None).unwrap();
+        }
+        assert_eq!(server.hits("/logs/get-position"), 3);
+        assert!(start.elapsed() >= interval * 2, "{:?}", start.elapsed());
+
+        // The system API has its own bucket and isn't held up
+        let start = Instant::now();
+        client.refresh_system_coordinates("Fuelum").unwrap();
+        client.refresh_system_coordinates("Fuelum").unwrap();
+        assert!(start.elapsed() < interval, "{:?}", start.elapsed());
+    }
+
+    #[test]
+    fn test_permit_response() {
+        let response: EdsmSystemResponse = serde_json::from_str(
+            r#"{
+                "name": "Sol",
+                "coords": {"x": 0.0, "y": 0.0, "z": 0.0},
+                "requirePermit": true,
+                "permitName": "Sol"
+            }"#,
+        )
+        .unwrap();
+        let coords = coordinates_from_response("Sol", response).unwrap();
+        assert_eq!(coords.required_permit.as_deref(), Some("Sol"));
+    }
+
+    #[test]
+    fn test_commander_location_api_key_in_query() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Fuelum"}"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        client
+            .get_commander_location("Whit3Arrow", Some("secret-key"))
+            .unwrap();
+        client.invalidate_location("Whit3Arrow");
+        client.get_commander_location("Whit3Arrow", None).unwrap();
+
+        let requests = server.requests("/logs/get-position");
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains("apiKey=secret-key"));
+        assert!(requests[0].contains("commanderName=Whit3Arrow"));
+        assert!(!requests[1].contains("apiKey"));
+    }
+
+    #[test]
+    fn test_location_expires_before_coordinates() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Fuelum"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_location_cache_ttl(Duration::from_millis(200));
+
+        client.get_commander_location("Whit3Arrow", None).unwrap();
+        client.get_system_coordinates("Fuelum").unwrap();
+        client.get_commander_location("Whit3Arrow", None).unwrap();
+        assert_eq!(server.hits("/logs/get-position"), 1);
+
+        std::thread::sleep(Duration::from_millis(300));
+        client.get_commander_location("Whit3Arrow", None).unwrap();
+        client.get_system_coordinates("Fuelum").unwrap();
+        assert_eq!(server.hits("/logs/get-position"), 2);
+        assert_eq!(server.hits("/api/system"), 1);
+    }
+
+    #[test]
+    fn test_private_profile_detected() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 203, "msg": "Commander name/API Key not found"}"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_location_retries(2, Duration::ZERO);
+
+        // Without an API key EDSM won't say where the commander is
+        let err = client
+            .get_commander_location("Whit3Arrow", None)
+            .unwrap_err();
+        assert!(
+            matches!(&err, EdjcError::ApiKeyMissing(name) if name == "Whit3Arrow"),
+            "{err}"
+        );
+        assert_eq!(err.hint(), Some("set edsm_api_key"));
+        // Retrying won't add the key
+        assert_eq!(server.hits("/logs/get-position"), 1);
+
+        let err = client
+            .get_commander_location("Whit3Arrow", Some("api-key"))
+            .unwrap_err();
+        assert!(
+            matches!(&err, EdjcError::ProfilePrivate(name) if name == "Whit3Arrow"),
+            "{err}"
+        );
+        assert_eq!(
+            err.hint(),
+            Some("check edsm_api_key or make the flight log public on EDSM")
+        );
+        // Nor make the flight log public
+        assert_eq!(server.hits("/logs/get-position"), 2);
     }
 }
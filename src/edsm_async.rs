@@ -0,0 +1,256 @@
+/*!
+Async EDSM API client for tokio applications.
+
+[`EdsmClientAsync`] mirrors the lookups of the blocking
+[`EdsmClient`](crate::edsm::EdsmClient) on top of `reqwest::Client`, so bots
+that embed this crate can resolve systems without blocking their runtime. It
+shares the blocking client's request parameters and response handling; only
+the transport and the cache (moka's async variant) differ.
+*/
+
+use log::debug;
+use moka::future::Cache;
+use reqwest::Client;
+use std::time::Duration;
+
+use crate::edsm::{
+    calculate_3d_distance, commander_query, commander_system, coordinates_from_response,
+    normalize_system_name, request_failed, system_query, system_response, CACHE_TTL_SECONDS,
+    DEFAULT_LOCATION_CACHE_SECONDS, EDSM_API_URL, EDSM_LOGS_API_URL,
+};
+use crate::types::{EdjcError, EdjcResult, SystemCoordinates};
+
+/// Async EDSM API client
+///
+/// Cloning is cheap and clones share the same cache.
+#[derive(Debug, Clone)]
+pub struct EdsmClientAsync {
+    client: Client,
+    cache: Cache<String, String>,
+    location_cache: Cache<String, String>,
+    include_hidden: bool,
+    api_url: String,
+    logs_api_url: String,
+}
+
+impl EdsmClientAsync {
+    /// Create a new async EDSM client
+    pub fn new() -> EdjcResult<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent(crate::USER_AGENT)
+            .build()?;
+
+        let cache = Cache::builder()
+            .time_to_live(Duration::from_secs(CACHE_TTL_SECONDS))
+            .max_capacity(1000)
+            .build();
+
+        Ok(Self {
+            client,
+            cache,
+            location_cache: location_cache(Duration::from_secs(DEFAULT_LOCATION_CACHE_SECONDS)),
+            include_hidden: false,
+            api_url: EDSM_API_URL.to_string(),
+            logs_api_url: EDSM_LOGS_API_URL.to_string(),
+        })
+    }
+
+    /// Point the client at different API endpoints, like
+    /// [`EdsmClient::with_api_urls`](crate::edsm::EdsmClient::with_api_urls)
+    pub fn with_api_urls(mut self, api_url: &str, logs_api_url: &str) -> Self {
+        self.api_url = api_url.to_string();
+        self.logs_api_url = logs_api_url.to_string();
+        self
+    }
+
+    /// Also resolve systems EDSM flags as hidden (e.g. duplicates)
+    pub fn with_include_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    /// Trust a looked-up commander location for `ttl`
+    pub fn with_location_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.location_cache = location_cache(ttl);
+        self
+    }
+
+    /// Get system coordinates from EDSM
+    pub async fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+        let system_name = &normalize_system_name(system_name);
+        let cache_key = format!("coords:{}", system_name.to_lowercase());
+
+        if let Some(cached) = self.cache.get(&cache_key).await {
+            if let Ok(coords) = serde_json::from_str::<SystemCoordinates>(&cached) {
+                debug!("Cache hit for system coordinates: {system_name}");
+                return Ok(coords);
+            }
+        }
+
+        debug!("Fetching coordinates for system: {system_name}");
+
+        let url = format!("{}/system", self.api_url);
+        let response = self
+            .client
+            .get(&url)
+            .query(&system_query(system_name, self.include_hidden))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(request_failed(response.status()));
+        }
+
+        let system_data = system_response(system_name, decode(response).await?)?;
+        let coordinates = coordinates_from_response(system_name, system_data)?;
+
+        if let Ok(cached_data) = serde_json::to_string(&coordinates) {
+            self.cache.insert(cache_key, cached_data).await;
+        }
+
+        Ok(coordinates)
+    }
+
+    /// Get commander's current location from EDSM
+    pub async fn get_commander_location(
+        &self,
+        cmdr_name: &str,
+        api_key: Option<&str>,
+    ) -> EdjcResult<String> {
+        let cache_key = format!("cmdr_location:{}", cmdr_name.to_lowercase());
+
+        if let Some(cached) = self.location_cache.get(&cache_key).await {
+            debug!("Cache hit for commander location: {cmdr_name}");
+            return Ok(cached);
+        }
+
+        debug!("Fetching commander location for: {cmdr_name}");
+
+        let url = format!("{}/get-position", self.logs_api_url);
+        let response = self
+            .client
+            .get(&url)
+            .query(&commander_query(cmdr_name, api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(request_failed(response.status()));
+        }
+
+        let system_name = commander_system(cmdr_name, api_key, decode(response).await?)?;
+        self.location_cache
+            .insert(cache_key, system_name.clone())
+            .await;
+
+        Ok(system_name)
+    }
+
+    /// Calculate distance between two systems
+    pub async fn calculate_distance(&self, from_system: &str, to_system: &str) -> EdjcResult<f64> {
+        let (from_coords, to_coords) = tokio::try_join!(
+            self.get_system_coordinates(from_system),
+            self.get_system_coordinates(to_system)
+        )?;
+        Ok(calculate_3d_distance(&from_coords, &to_coords))
+    }
+}
+
+/// Cache for commander locations, which go stale after `ttl`
+fn location_cache(ttl: Duration) -> Cache<String, String> {
+    Cache::builder()
+        .time_to_live(ttl)
+        .max_capacity(1000)
+        .build()
+}
+
+/// Decode a JSON response body, reporting malformed bodies as parse errors
+async fn decode<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> EdjcResult<T> {
+    response
+        .json()
+        .await
+        .map_err(|e| EdjcError::Parse(format!("Unexpected EDSM response: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MockServer;
+
+    fn client(server: &MockServer) -> EdsmClientAsync {
+        EdsmClientAsync::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+    }
+
+    #[tokio::test]
+    async fn test_system_coordinates_are_cached() {
+        let server = MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125},
+                "primaryStar": {"type": "Neutron Star"}}"#,
+        );
+        let client = client(&server);
+
+        let coords = client.get_system_coordinates("Fuelum").await.unwrap();
+        assert_eq!(coords.name, "Fuelum");
+        assert!(coords.has_neutron_star);
+
+        client.get_system_coordinates("FUELUM").await.unwrap();
+        assert_eq!(server.hits("/api/system"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_commander_location() {
+        let server = MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Fuelum"}"#,
+        );
+        let client = client(&server);
+
+        let system = client
+            .get_commander_location("Whit3Arrow", Some("secret-key"))
+            .await
+            .unwrap();
+        assert_eq!(system, "Fuelum");
+        assert!(server.requests("/logs/get-position")[0].contains("apiKey=secret-key"));
+
+        let server = MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 203, "msg": "Commander name/API Key not found"}"#,
+        );
+        let err = self::client(&server)
+            .get_commander_location("Nobody", Some("secret-key"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::ProfilePrivate(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_calculate_distance() {
+        let server = MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03125, "y": -0.09375, "z": 3.15625}}"#,
+        );
+
+        let distance = client(&server)
+            .calculate_distance("Sol", "Alpha Centauri")
+            .await
+            .unwrap();
+        assert!((distance - 4.377).abs() < 0.01, "{distance}");
+    }
+}
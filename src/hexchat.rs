@@ -1,8 +1,7 @@
 use libc::{c_char, c_int, c_void};
-use std::ffi::CStr;
-
-/// HexChat plugin handle (opaque pointer)
-pub type HexChatPlugin = c_void;
+use std::ffi::{CStr, CString};
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Mutex;
 
 /// HexChat hook return values
 pub const HEXCHAT_EAT_NONE: c_int = 0; // Don't eat this event, pass it on
@@ -13,84 +12,781 @@ pub const HEXCHAT_EAT_PLUGIN: c_int = 2; // Don't let other plugins see this eve
 #[allow(dead_code)]
 pub const HEXCHAT_EAT_ALL: c_int = 3; // Don't let anything see this event
 
+/// Default hook priority
+pub const HEXCHAT_PRI_NORM: c_int = 0;
+
 /// HexChat context handle
 #[allow(dead_code)]
 pub type HexChatContext = c_void;
 
-/// HexChat hook handle  
-#[allow(dead_code)]
+/// HexChat hook handle
 pub type HexChatHook = c_void;
 
-/// Function pointer type for HexChat callbacks
-#[allow(dead_code)]
+/// Function pointer type for HexChat command callbacks
 pub type HexChatCallback = extern "C" fn(
     word: *const *const c_char,
     word_eol: *const *const c_char,
     user_data: *mut c_void,
 ) -> c_int;
 
+/// Function pointer type for HexChat print (text event) callbacks
+pub type HexChatPrintCallback =
+    extern "C" fn(word: *const *const c_char, user_data: *mut c_void) -> c_int;
+
+/// `hexchat_hook_command` from the plugin function table
+pub type HookCommandFn = unsafe extern "C" fn(
+    ph: *mut HexChatPlugin,
+    name: *const c_char,
+    pri: c_int,
+    callback: HexChatCallback,
+    help_text: *const c_char,
+    user_data: *mut c_void,
+) -> *mut HexChatHook;
+
+/// `hexchat_hook_print` from the plugin function table
+pub type HookPrintFn = unsafe extern "C" fn(
+    ph: *mut HexChatPlugin,
+    name: *const c_char,
+    pri: c_int,
+    callback: HexChatPrintCallback,
+    user_data: *mut c_void,
+) -> *mut HexChatHook;
+
+/// Function pointer type for HexChat timer callbacks; returning 0 stops the timer
+pub type HexChatTimerCallback = extern "C" fn(user_data: *mut c_void) -> c_int;
+
+/// `hexchat_hook_timer` from the plugin function table
+pub type HookTimerFn = unsafe extern "C" fn(
+    ph: *mut HexChatPlugin,
+    timeout: c_int,
+    callback: HexChatTimerCallback,
+    user_data: *mut c_void,
+) -> *mut HexChatHook;
+
+/// `hexchat_unhook` from the plugin function table; returns the hook's user data
+pub type UnhookFn =
+    unsafe extern "C" fn(ph: *mut HexChatPlugin, hook: *mut HexChatHook) -> *mut c_void;
+
+/// `hexchat_print` from the plugin function table
+pub type PrintFn = unsafe extern "C" fn(ph: *mut HexChatPlugin, text: *const c_char);
+
+/// `hexchat_command` from the plugin function table
+pub type CommandFn = unsafe extern "C" fn(ph: *mut HexChatPlugin, command: *const c_char);
+
+/// The plugin handle HexChat passes to `hexchat_plugin_init`
+///
+/// Mirrors the leading fields of `struct _hexchat_plugin` in `hexchat-plugin.h`.
+/// HexChat owns the struct; we only ever read function pointers from it, so the
+/// trailing fields we don't use are left out. Entries we never call are kept as
+/// opaque pointers purely to preserve the layout.
+#[repr(C)]
+pub struct HexChatPlugin {
+    pub hexchat_hook_command: Option<HookCommandFn>,
+    pub hexchat_hook_server: *const c_void,
+    pub hexchat_hook_print: Option<HookPrintFn>,
+    pub hexchat_hook_timer: Option<HookTimerFn>,
+    pub hexchat_hook_fd: *const c_void,
+    pub hexchat_unhook: Option<UnhookFn>,
+    pub hexchat_print: Option<PrintFn>,
+    pub hexchat_printf: *const c_void,
+    pub hexchat_command: Option<CommandFn>,
+}
+
 // Global plugin handle storage
-static mut PLUGIN_HANDLE: *mut HexChatPlugin = std::ptr::null_mut();
+static PLUGIN_HANDLE: AtomicPtr<HexChatPlugin> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Addresses of the hooks registered through this module, for [`unhook_all`]
+static HOOKS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
 
 /// Store the plugin handle for later use
 pub fn store_plugin_handle(handle: *mut HexChatPlugin) {
-    unsafe {
-        PLUGIN_HANDLE = handle;
+    PLUGIN_HANDLE.store(handle, Ordering::SeqCst);
+}
+
+/// Forget the plugin handle (on unload), so nothing calls into HexChat anymore
+pub fn clear_plugin_handle() {
+    PLUGIN_HANDLE.store(std::ptr::null_mut(), Ordering::SeqCst);
+}
+
+/// Keep a hook HexChat handed out, so [`unhook_all`] can remove it again
+fn remember_hook(hook: *mut HexChatHook) -> *mut HexChatHook {
+    if !hook.is_null() {
+        HOOKS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(hook as usize);
     }
+    hook
 }
 
-/// Simple API initialization that just stores the handle
-pub unsafe fn init_hexchat_api_from_arg(
-    plugin_handle: *mut HexChatPlugin,
-    _arg: *const c_char,
-) -> bool {
-    PLUGIN_HANDLE = plugin_handle;
-    true
+/// Remove every hook registered through this module
+///
+/// Called on unload, so no command, print or timer callback points into the
+/// old plugin state anymore, and a reload can register the same hooks again.
+/// Returns how many hooks were removed.
+pub fn unhook_all() -> usize {
+    let hooks = std::mem::take(&mut *HOOKS.lock().unwrap_or_else(|e| e.into_inner()));
+    let Some((handle, unhook)) =
+        plugin_handle().and_then(|handle| Some((handle, unsafe { (*handle).hexchat_unhook? })))
+    else {
+        return 0;
+    };
+
+    for &hook in &hooks {
+        unsafe { unhook(handle, hook as *mut HexChatHook) };
+    }
+    hooks.len()
 }
 
-/// Print text to HexChat - for now just use stderr which shows in HexChat
+/// The stored plugin handle, if HexChat has given us one
+fn plugin_handle() -> Option<*mut HexChatPlugin> {
+    let handle = PLUGIN_HANDLE.load(Ordering::SeqCst);
+    (!handle.is_null()).then_some(handle)
+}
+
+/// Print text to the current HexChat tab
+///
+/// Falls back to stderr when running outside HexChat (tests, standalone use).
 pub fn hexchat_print(text: *const c_char) {
+    if text.is_null() {
+        return;
+    }
+
     unsafe {
-        if !text.is_null() {
-            if let Ok(text_str) = CStr::from_ptr(text).to_str() {
-                // Use eprintln! which will appear in HexChat's console
-                eprintln!("[EDJC] {}", text_str);
+        if let Some(handle) = plugin_handle() {
+            if let Some(print) = (*handle).hexchat_print {
+                print(handle, text);
+                return;
             }
         }
+
+        eprintln!("[EDJC] {}", CStr::from_ptr(text).to_string_lossy());
     }
 }
 
-/// Register a command hook - disabled for now to prevent crashes
+/// Print text in the current HexChat tab
+///
+/// See [`to_c_string`] for what happens to NUL bytes.
+pub fn print_text(text: &str) {
+    hexchat_print(to_c_string(text).as_ptr());
+}
+
+/// Run a HexChat command (without the leading slash)
+pub fn hexchat_command(command: *const c_char) -> bool {
+    if command.is_null() {
+        return false;
+    }
+
+    unsafe {
+        match plugin_handle().and_then(|handle| Some((handle, (*handle).hexchat_command?))) {
+            Some((handle, run)) => {
+                run(handle, command);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Say `text` in the current context, i.e. the channel whose event is being
+/// handled, so everyone there sees it
+///
+/// The text is passed through [`sanitize_chat_text`] first, since it usually
+/// carries names from a RATSIGNAL. Falls back to [`hexchat_print`] (and
+/// returns false) when HexChat can't run commands.
+pub fn print_to_active_channel(text: &str) -> bool {
+    let text = sanitize_chat_text(text);
+    if text.is_empty() {
+        return false;
+    }
+
+    let command = to_c_string(&format!("SAY {text}"));
+    if hexchat_command(command.as_ptr()) {
+        return true;
+    }
+
+    print_text(&text);
+    false
+}
+
+/// Stand-in for characters a C string can't carry
+pub const PLACEHOLDER: char = '\u{FFFD}';
+
+/// A C string for HexChat, with any NUL bytes replaced by [`PLACEHOLDER`]
+///
+/// System and commander names come from chat and APIs, and one with a NUL in
+/// it would otherwise fail `CString::new`; the rest of the text is kept.
+pub fn to_c_string(text: &str) -> CString {
+    let text = if text.contains('\0') {
+        text.replace('\0', &PLACEHOLDER.to_string())
+    } else {
+        text.to_string()
+    };
+    CString::new(text).expect("NUL bytes were replaced")
+}
+
+/// Make text safe to send as part of a command
+///
+/// Control characters (line breaks that would start another IRC command, NUL,
+/// CTCP and formatting codes) become spaces, and the result is trimmed.
+pub fn sanitize_chat_text(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Split a response into the lines to print, none longer than `max_len`
+/// bytes (0 = no limit)
+///
+/// Each line of the response is printed as is if it fits, otherwise wrapped
+/// between words; only a single word longer than `max_len` is broken, at a
+/// character boundary. A line's indentation is kept on its first part.
+pub fn chunk_lines(text: &str, max_len: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    for line in text.lines() {
+        if max_len == 0 || line.len() <= max_len {
+            chunks.push(line.to_string());
+            continue;
+        }
+
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let mut chunk = if indent.len() < max_len {
+            indent.to_string()
+        } else {
+            String::new()
+        };
+        for word in line.split_whitespace() {
+            let separator = usize::from(!chunk.trim_start().is_empty());
+            if chunk.len() + separator + word.len() <= max_len {
+                if separator == 1 {
+                    chunk.push(' ');
+                }
+                chunk.push_str(word);
+                continue;
+            }
+            if !chunk.trim().is_empty() {
+                chunks.push(std::mem::take(&mut chunk));
+            }
+            chunk.clear();
+
+            let mut word = word;
+            while word.len() > max_len {
+                let mut split = max_len;
+                while !word.is_char_boundary(split) {
+                    split -= 1;
+                }
+                // A character wider than the limit still has to go somewhere
+                if split == 0 {
+                    split = word.chars().next().map_or(word.len(), char::len_utf8);
+                }
+                chunks.push(word[..split].to_string());
+                word = &word[split..];
+            }
+            chunk.push_str(word);
+        }
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk);
+        }
+    }
+    chunks
+}
+
+/// Print a response in the current HexChat tab, one print per line of
+/// [`chunk_lines`]
+pub fn print_chunked(text: &str, max_len: usize) {
+    for line in chunk_lines(text, max_len) {
+        print_text(&line);
+    }
+}
+
+/// Register a command hook
+///
+/// Returns a null hook if HexChat hasn't provided a plugin handle.
 pub fn hexchat_hook_command(
     name: *const c_char,
-    _callback: Option<HexChatCallback>,
-    _user_data: *mut c_void,
+    callback: Option<HexChatCallback>,
+    help_text: *const c_char,
+    user_data: *mut c_void,
 ) -> *mut HexChatHook {
+    let (Some(callback), false) = (callback, name.is_null()) else {
+        return std::ptr::null_mut();
+    };
+
     unsafe {
-        let cmd_name = if !name.is_null() {
-            CStr::from_ptr(name).to_string_lossy().into_owned()
-        } else {
-            "unknown".to_string()
-        };
-        
-        // For now, just log that we would register the command
-        eprintln!("[EDJC] Would register command hook for: {}", cmd_name);
-        eprintln!("[EDJC] Command hooks temporarily disabled for stability");
-        
-        // Return a dummy hook pointer
-        1 as *mut HexChatHook
+        match plugin_handle().and_then(|handle| Some((handle, (*handle).hexchat_hook_command?))) {
+            Some((handle, hook)) => remember_hook(hook(
+                handle,
+                name,
+                HEXCHAT_PRI_NORM,
+                callback,
+                help_text,
+                user_data,
+            )),
+            None => {
+                eprintln!(
+                    "[EDJC] Cannot register /{} outside HexChat",
+                    CStr::from_ptr(name).to_string_lossy()
+                );
+                std::ptr::null_mut()
+            }
+        }
+    }
+}
+
+/// Register a print hook for a text event (e.g. "Channel Message")
+///
+/// Returns a null hook if HexChat hasn't provided a plugin handle.
+pub fn hexchat_hook_print(
+    event: *const c_char,
+    callback: Option<HexChatPrintCallback>,
+    user_data: *mut c_void,
+) -> *mut HexChatHook {
+    let (Some(callback), false) = (callback, event.is_null()) else {
+        return std::ptr::null_mut();
+    };
+
+    unsafe {
+        match plugin_handle().and_then(|handle| Some((handle, (*handle).hexchat_hook_print?))) {
+            Some((handle, hook)) => {
+                remember_hook(hook(handle, event, HEXCHAT_PRI_NORM, callback, user_data))
+            }
+            None => {
+                eprintln!(
+                    "[EDJC] Cannot hook \"{}\" outside HexChat",
+                    CStr::from_ptr(event).to_string_lossy()
+                );
+                std::ptr::null_mut()
+            }
+        }
     }
 }
 
+/// Hook a callback HexChat calls every `timeout_ms` milliseconds, until it
+/// returns 0
+pub fn hexchat_hook_timer(
+    timeout_ms: c_int,
+    callback: Option<HexChatTimerCallback>,
+    user_data: *mut c_void,
+) -> *mut HexChatHook {
+    let Some(callback) = callback else {
+        return std::ptr::null_mut();
+    };
+
+    unsafe {
+        match plugin_handle().and_then(|handle| Some((handle, (*handle).hexchat_hook_timer?))) {
+            Some((handle, hook)) => remember_hook(hook(handle, timeout_ms, callback, user_data)),
+            None => {
+                eprintln!("[EDJC] Cannot hook a timer outside HexChat");
+                std::ptr::null_mut()
+            }
+        }
+    }
+}
+
+/// A single entry of a HexChat word array
+///
+/// For "Channel Message" events `word[1]` is the sender and `word[2]` the text.
+///
+/// # Safety
+///
+/// `word` must be null or point to a HexChat word array with at least
+/// `index + 1` entries.
+pub unsafe fn word_at(word: *const *const c_char, index: isize) -> String {
+    if word.is_null() {
+        return String::new();
+    }
+    c_str_to_string(*word.offset(index))
+}
+
+/// Everything after the command name in a command callback's `word_eol`
+///
+/// HexChat's word arrays are 1-based: `word[1]` is the command name and
+/// `word_eol[2]` holds the rest of the line, so multi-word arguments
+/// ("Sagittarius A*") arrive whole. Surrounding whitespace is trimmed.
+///
+/// # Safety
+///
+/// `word_eol` must be null or point to HexChat's word_eol array.
+pub unsafe fn command_args(word_eol: *const *const c_char) -> String {
+    if word_eol.is_null() {
+        return String::new();
+    }
+    c_str_to_string(*word_eol.offset(2)).trim().to_string()
+}
+
 /// Utility function to safely convert C strings
 pub fn c_str_to_string(c_str: *const c_char) -> String {
     if c_str.is_null() {
         return String::new();
     }
 
-    unsafe {
-        CStr::from_ptr(c_str)
-            .to_string_lossy()
-            .into_owned()
+    unsafe { CStr::from_ptr(c_str).to_string_lossy().into_owned() }
+}
+
+#[cfg(test)]
+mod string_tests {
+    use super::*;
+
+    #[test]
+    fn test_to_c_string_replaces_nul() {
+        assert_eq!(
+            to_c_string("Col 285 Sector AB-C d1-2").to_str().unwrap(),
+            "Col 285 Sector AB-C d1-2"
+        );
+        assert_eq!(
+            to_c_string("Col 285\0Sector\0").to_str().unwrap(),
+            "Col 285\u{FFFD}Sector\u{FFFD}"
+        );
+        assert_eq!(to_c_string("").as_bytes(), b"");
+        assert_eq!(
+            to_c_string("Pru Aescs NC-M d7-192 (Hawking's Gap)")
+                .to_str()
+                .unwrap(),
+            "Pru Aescs NC-M d7-192 (Hawking's Gap)"
+        );
+
+        // Printing without a HexChat handle goes to stderr rather than panicking
+        let _handle = crate::test_support::HEXCHAT_HANDLE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        print_text("Swoiwns \0 VN-B d1-6");
+    }
+
+    #[test]
+    fn test_long_responses_are_chunked_between_words() {
+        let route = "Sol → Colonia: 12 jumps via neutron highway, 22000 LY, ~45 min";
+        let long = [route; 10].join(" | ");
+        assert_eq!(long.len(), 10 * route.len() + 9 * 3);
+
+        let chunks = chunk_lines(&long, 200);
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 200));
+        // Rejoined, nothing was lost and no word was cut in two
+        assert_eq!(chunks.join(" "), long);
+        let words: Vec<&str> = long.split(' ').collect();
+        for chunk in &chunks {
+            assert!(
+                chunk.split(' ').all(|word| words.contains(&word)),
+                "{chunk}"
+            );
+        }
+
+        // Line breaks are kept, indentation only where the line starts
+        let listed = format!("📋 Open cases (2):\n  #3 Colonia: {route}\n  #4 Sol: 1 jump");
+        assert_eq!(
+            chunk_lines(&listed, 40),
+            [
+                "📋 Open cases (2):",
+                "  #3 Colonia: Sol → Colonia: 12 jumps",
+                "via neutron highway, 22000 LY, ~45 min",
+                "  #4 Sol: 1 jump",
+            ]
+        );
+        assert_eq!(chunk_lines(&listed, 0).len(), 3);
+
+        // Only a word longer than the limit is broken, on a char boundary
+        assert_eq!(
+            chunk_lines("Col 285 Sector AB-C d1-2 Ηλιος", 6),
+            ["Col", "285", "Sector", "AB-C", "d1-2", "Ηλι", "ος"]
+        );
+        assert!(chunk_lines("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_c_str_to_string_is_lossy() {
+        let name = CString::new(b"Sagittarius A\xff*".to_vec()).unwrap();
+        assert_eq!(c_str_to_string(name.as_ptr()), "Sagittarius A\u{FFFD}*");
+        assert_eq!(c_str_to_string(std::ptr::null()), "");
+
+        let text = sanitize_chat_text("Col 285\0Sector\r\nQUIT");
+        assert_eq!(text, "Col 285 Sector  QUIT");
+        assert!(CString::new(text).is_ok());
+    }
+}
+
+#[cfg(all(test, feature = "hexchat-smoke"))]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::sync::atomic::AtomicUsize;
+
+    static PRINTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static HOOKED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static COMMANDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    unsafe extern "C" fn fake_print(_ph: *mut HexChatPlugin, text: *const c_char) {
+        PRINTED.lock().unwrap().push(c_str_to_string(text));
+    }
+
+    unsafe extern "C" fn fake_command(_ph: *mut HexChatPlugin, command: *const c_char) {
+        COMMANDS.lock().unwrap().push(c_str_to_string(command));
+    }
+
+    unsafe extern "C" fn fake_hook_command(
+        _ph: *mut HexChatPlugin,
+        name: *const c_char,
+        _pri: c_int,
+        callback: HexChatCallback,
+        _help_text: *const c_char,
+        user_data: *mut c_void,
+    ) -> *mut HexChatHook {
+        HOOKED.lock().unwrap().push(c_str_to_string(name));
+
+        // Invoke the callback the way HexChat would for "/<name> Fuelum"
+        let empty = CString::new("").unwrap();
+        let args = CString::new("Fuelum").unwrap();
+        let word = [empty.as_ptr(), name, args.as_ptr(), empty.as_ptr()];
+        let word_eol = [empty.as_ptr(), name, args.as_ptr(), empty.as_ptr()];
+        callback(word.as_ptr(), word_eol.as_ptr(), user_data);
+
+        std::ptr::dangling_mut::<HexChatHook>()
+    }
+
+    unsafe extern "C" fn fake_hook_print(
+        _ph: *mut HexChatPlugin,
+        name: *const c_char,
+        _pri: c_int,
+        callback: HexChatPrintCallback,
+        user_data: *mut c_void,
+    ) -> *mut HexChatHook {
+        HOOKED.lock().unwrap().push(c_str_to_string(name));
+
+        // Deliver a "Channel Message" event: word[1] sender, word[2] text
+        let empty = CString::new("").unwrap();
+        let sender = CString::new("MechaSqueak[BOT]").unwrap();
+        let text = CString::new("RATSIGNAL").unwrap();
+        let word = [
+            empty.as_ptr(),
+            sender.as_ptr(),
+            text.as_ptr(),
+            empty.as_ptr(),
+        ];
+        callback(word.as_ptr(), user_data);
+
+        std::ptr::dangling_mut::<HexChatHook>()
+    }
+
+    unsafe extern "C" fn fake_hook_timer(
+        _ph: *mut HexChatPlugin,
+        timeout: c_int,
+        callback: HexChatTimerCallback,
+        user_data: *mut c_void,
+    ) -> *mut HexChatHook {
+        HOOKED.lock().unwrap().push(format!("timer {timeout}"));
+        // Fire once, as HexChat would after `timeout` ms
+        callback(user_data);
+        std::ptr::dangling_mut::<HexChatHook>()
+    }
+
+    extern "C" fn echo_tick(_user_data: *mut c_void) -> c_int {
+        let tick = CString::new("tick").unwrap();
+        hexchat_print(tick.as_ptr());
+        1
+    }
+
+    extern "C" fn echo_message(word: *const *const c_char, _user_data: *mut c_void) -> c_int {
+        let (sender, text) = unsafe { (word_at(word, 1), word_at(word, 2)) };
+        let line = CString::new(format!("<{sender}> {text}")).unwrap();
+        hexchat_print(line.as_ptr());
+        HEXCHAT_EAT_NONE
+    }
+
+    extern "C" fn echo_args(
+        _word: *const *const c_char,
+        word_eol: *const *const c_char,
+        _user_data: *mut c_void,
+    ) -> c_int {
+        let args = unsafe { command_args(word_eol) };
+        let text = CString::new(format!("args: {args}")).unwrap();
+        hexchat_print(text.as_ptr());
+        HEXCHAT_EAT_ALL
+    }
+
+    /// Hooks the reload test's fake HexChat has handed out and not removed
+    static LIVE_HOOKS: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe extern "C" fn counting_hook_command(
+        _ph: *mut HexChatPlugin,
+        _name: *const c_char,
+        _pri: c_int,
+        _callback: HexChatCallback,
+        _help_text: *const c_char,
+        _user_data: *mut c_void,
+    ) -> *mut HexChatHook {
+        LIVE_HOOKS.fetch_add(1, Ordering::SeqCst);
+        std::ptr::dangling_mut::<HexChatHook>()
+    }
+
+    unsafe extern "C" fn counting_hook_print(
+        _ph: *mut HexChatPlugin,
+        _name: *const c_char,
+        _pri: c_int,
+        _callback: HexChatPrintCallback,
+        _user_data: *mut c_void,
+    ) -> *mut HexChatHook {
+        LIVE_HOOKS.fetch_add(1, Ordering::SeqCst);
+        std::ptr::dangling_mut::<HexChatHook>()
+    }
+
+    unsafe extern "C" fn counting_unhook(
+        _ph: *mut HexChatPlugin,
+        _hook: *mut HexChatHook,
+    ) -> *mut c_void {
+        LIVE_HOOKS.fetch_sub(1, Ordering::SeqCst);
+        std::ptr::null_mut()
+    }
+
+    #[test]
+    fn test_fake_vtable_smoke() {
+        let _handle = crate::test_support::HEXCHAT_HANDLE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let mut vtable = HexChatPlugin {
+            hexchat_hook_command: Some(fake_hook_command),
+            hexchat_hook_server: std::ptr::null(),
+            hexchat_hook_print: Some(fake_hook_print),
+            hexchat_hook_timer: Some(fake_hook_timer),
+            hexchat_hook_fd: std::ptr::null(),
+            hexchat_unhook: None,
+            hexchat_print: Some(fake_print),
+            hexchat_printf: std::ptr::null(),
+            hexchat_command: None,
+        };
+        store_plugin_handle(&mut vtable);
+
+        let hello = CString::new("hello").unwrap();
+        hexchat_print(hello.as_ptr());
+
+        let name = CString::new("route").unwrap();
+        let hook = hexchat_hook_command(
+            name.as_ptr(),
+            Some(echo_args),
+            std::ptr::null(),
+            std::ptr::null_mut(),
+        );
+        assert!(!hook.is_null());
+
+        let event = CString::new("Channel Message").unwrap();
+        let hook = hexchat_hook_print(event.as_ptr(), Some(echo_message), std::ptr::null_mut());
+        assert!(!hook.is_null());
+
+        let hook = hexchat_hook_timer(1000, Some(echo_tick), std::ptr::null_mut());
+        assert!(!hook.is_null());
+
+        // No hexchat_command in the table: nothing is called
+        assert!(!hexchat_command(hello.as_ptr()));
+
+        // Nor hexchat_unhook: the hooks are only forgotten
+        assert_eq!(unhook_all(), 0);
+        clear_plugin_handle();
+
+        assert_eq!(
+            *HOOKED.lock().unwrap(),
+            vec!["route", "Channel Message", "timer 1000"]
+        );
+        assert_eq!(
+            *PRINTED.lock().unwrap(),
+            vec![
+                "hello",
+                "args: Fuelum",
+                "<MechaSqueak[BOT]> RATSIGNAL",
+                "tick"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_print_to_active_channel() {
+        let _handle = crate::test_support::HEXCHAT_HANDLE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let mut vtable = HexChatPlugin {
+            hexchat_hook_command: None,
+            hexchat_hook_server: std::ptr::null(),
+            hexchat_hook_print: None,
+            hexchat_hook_timer: None,
+            hexchat_hook_fd: std::ptr::null(),
+            hexchat_unhook: None,
+            hexchat_print: None,
+            hexchat_printf: std::ptr::null(),
+            hexchat_command: Some(fake_command),
+        };
+        store_plugin_handle(&mut vtable);
+
+        assert!(print_to_active_channel("🚀 Case #7: 3 jumps to Fuelum"));
+        // A CMDR name can't smuggle in a second IRC command
+        assert!(print_to_active_channel(
+            "Case #8: CMDR Evil\r\nQUIT :bye\u{1}ACTION\u{1} \t"
+        ));
+        assert!(!print_to_active_channel("\r\n"));
+
+        clear_plugin_handle();
+
+        assert_eq!(
+            *COMMANDS.lock().unwrap(),
+            vec![
+                "SAY 🚀 Case #7: 3 jumps to Fuelum",
+                "SAY Case #8: CMDR Evil  QUIT :bye ACTION",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reload_in_the_same_session() {
+        let _handle = crate::test_support::HEXCHAT_HANDLE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let _guard = crate::test_support::CONFIG_PATH_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        crate::config::set_config_path_override(Some(dir.path().join("edjc.toml")));
+        crate::config::save_config(&crate::config::Config {
+            cmdr_name: "TestCMDR".to_string(),
+            offline_mode: true,
+            persist_cache: false,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut vtable = HexChatPlugin {
+            hexchat_hook_command: Some(counting_hook_command),
+            hexchat_hook_server: std::ptr::null(),
+            hexchat_hook_print: Some(counting_hook_print),
+            hexchat_hook_timer: None,
+            hexchat_hook_fd: std::ptr::null(),
+            hexchat_unhook: Some(counting_unhook),
+            hexchat_print: Some(fake_print),
+            hexchat_printf: std::ptr::null(),
+            hexchat_command: None,
+        };
+        let mut registered = None;
+        for _ in 0..2 {
+            let mut name = std::ptr::null();
+            let mut desc = std::ptr::null();
+            let mut version = std::ptr::null();
+            let loaded = unsafe {
+                crate::hexchat_plugin_init(
+                    &mut vtable,
+                    &mut name,
+                    &mut desc,
+                    &mut version,
+                    std::ptr::null(),
+                )
+            };
+            assert_eq!(loaded, 1);
+            let hooks = LIVE_HOOKS.load(Ordering::SeqCst);
+            assert!(hooks > 0);
+            // The second load registers the same hooks, not twice as many
+            assert_eq!(*registered.get_or_insert(hooks), hooks);
+
+            assert_eq!(crate::hexchat_plugin_deinit(), 1);
+            assert_eq!(LIVE_HOOKS.load(Ordering::SeqCst), 0);
+            assert!(crate::plugin().is_none());
+        }
+
+        crate::config::set_config_path_override(None);
     }
 }
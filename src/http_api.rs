@@ -0,0 +1,290 @@
+/*!
+Local HTTP endpoint for overlays and stream widgets (`http-api` feature).
+
+[`HttpApi`] answers `GET /route?to=<system>&from=<system>` with the planned
+route as JSON, so external tools can reuse the plugin's planner and cached
+EDSM data without parsing chat. It only listens on localhost; `from` is
+optional and defaults to the commander's location.
+*/
+
+use anyhow::Result;
+use log::{debug, info, warn};
+use serde_json::json;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::jump_calculator::RouteDetails;
+use crate::types::{EdjcError, EdjcResult};
+
+/// How long a client may take to send its request or read the response
+/// before the connection is dropped, so an idle client can't hold up the
+/// others or shutdown
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Plans the route to `to`, from `from` or the commander's location
+type RouteHandler = dyn Fn(&str, Option<&str>) -> EdjcResult<RouteDetails> + Send + Sync;
+
+/// Background thread serving the HTTP endpoint
+#[derive(Debug)]
+pub struct HttpApi {
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl HttpApi {
+    /// Listen on `127.0.0.1:port` (0 picks a free port) and answer route
+    /// requests with `route`
+    pub fn start<F>(port: u16, route: F) -> Result<Self>
+    where
+        F: Fn(&str, Option<&str>) -> EdjcResult<RouteDetails> + Send + Sync + 'static,
+    {
+        Self::start_with_timeout(port, CLIENT_TIMEOUT, route)
+    }
+
+    /// [`Self::start`], dropping clients that stay silent for `timeout`
+    fn start_with_timeout<F>(port: u16, timeout: Duration, route: F) -> Result<Self>
+    where
+        F: Fn(&str, Option<&str>) -> EdjcResult<RouteDetails> + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let addr = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread_stopped = Arc::clone(&stopped);
+        let route: Box<RouteHandler> = Box::new(route);
+        let handle = thread::Builder::new()
+            .name("edjc-http-api".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if thread_stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    match stream {
+                        Ok(stream) => handle_connection(stream, timeout, route.as_ref()),
+                        Err(e) => warn!("HTTP API connection failed: {e}"),
+                    }
+                }
+            })?;
+
+        info!("HTTP API listening on http://{addr}");
+        Ok(Self {
+            addr,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    /// Address the endpoint listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop accepting requests and wait for the thread to finish
+    pub fn shutdown(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it notices the flag
+        let _ = TcpStream::connect(self.addr);
+
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!("HTTP API thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for HttpApi {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Read one request and write the response, giving up on clients silent
+/// for `timeout`
+fn handle_connection(stream: TcpStream, timeout: Duration, route: &RouteHandler) {
+    if let Err(e) = stream
+        .set_read_timeout(Some(timeout))
+        .and_then(|()| stream.set_write_timeout(Some(timeout)))
+    {
+        warn!("HTTP API could not set a client timeout: {e}");
+        return;
+    }
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // Requests have no body worth reading, but drain the headers
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).is_err() || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or("/");
+    debug!("HTTP API request: {method} {target}");
+    let (status, body) = respond(method, target, route);
+
+    let mut stream = &stream;
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        reason(status),
+        body.len()
+    );
+    let _ = stream.flush();
+}
+
+/// Status code and JSON body for a request
+fn respond(method: &str, target: &str, route: &RouteHandler) -> (u16, String) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != "/route" {
+        return error(404, "Not found, use GET /route?to=<system>&from=<system>");
+    }
+    if method != "GET" {
+        return error(405, "Only GET is supported");
+    }
+
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| percent_decode(value))
+            .filter(|value| !value.trim().is_empty())
+    };
+    let Some(to) = param("to") else {
+        return error(400, "Missing the 'to' system");
+    };
+    let from = param("from");
+
+    match route(to.trim(), from.as_deref().map(str::trim)) {
+        Ok(details) => match serde_json::to_string(&details) {
+            Ok(body) => (200, body),
+            Err(e) => error(500, &e.to_string()),
+        },
+        Err(e) => {
+            let status = match e {
+                EdjcError::SystemNotFound { .. } | EdjcError::SystemNotBundled(_) => 404,
+                _ => 502,
+            };
+            error(status, &e.to_string())
+        }
+    }
+}
+
+fn error(status: u16, message: &str) -> (u16, String) {
+    (status, json!({ "error": message }).to_string())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        502 => "Bad Gateway",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Decode a query string value ("Sagittarius%20A%2A", "Beagle+Point")
+fn percent_decode(value: &str) -> String {
+    let hex = |byte: u8| (byte as char).to_digit(16);
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                (Some(high), Some(low)) => {
+                    decoded.push((high * 16 + low) as u8);
+                    i += 2;
+                }
+                _ => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jump_calculator::JumpCalculator;
+    use crate::system_source::{LocalSystemSource, SystemSource};
+
+    fn get(api: &HttpApi, target: &str) -> (u16, serde_json::Value) {
+        let response = reqwest::blocking::get(format!("http://{}{target}", api.addr())).unwrap();
+        let status = response.status().as_u16();
+        (status, response.json().unwrap())
+    }
+
+    #[test]
+    fn test_route_requests() {
+        let mut api = HttpApi::start(0, |to, from| {
+            let systems = LocalSystemSource;
+            let from = systems.get_system_coordinates(from.unwrap_or("Sol"))?;
+            let to = systems.get_system_coordinates(to)?;
+            JumpCalculator::new().get_route_details(&from, &to, 50.0)
+        })
+        .unwrap();
+        assert!(api.addr().ip().is_loopback());
+
+        let (status, body) = get(&api, "/route?to=Sagittarius%20A%2A&from=Beagle+Point");
+        assert_eq!(status, 200);
+        assert_eq!(body["result"]["from_system"], "Beagle Point");
+        assert_eq!(body["result"]["to_system"], "Sagittarius A*");
+
+        let (status, body) = get(&api, "/route?to=Fuelum");
+        assert_eq!(status, 404);
+        assert!(body["error"].as_str().unwrap().contains("Fuelum"));
+        assert_eq!(get(&api, "/route?from=Sol").0, 400);
+        assert_eq!(get(&api, "/distance?to=Sol").0, 404);
+
+        api.shutdown();
+        assert!(TcpStream::connect(api.addr()).is_err());
+    }
+
+    #[test]
+    fn test_failed_lookups_are_bad_gateway() {
+        let api = HttpApi::start(0, |_, _| Err(EdjcError::EdsmApi("down".to_string()))).unwrap();
+        let (status, body) = get(&api, "/route?to=Sol");
+        assert_eq!(status, 502);
+        assert_eq!(body["error"], "EDSM API error: down");
+    }
+
+    #[test]
+    fn test_idle_clients_time_out() {
+        let mut api = HttpApi::start_with_timeout(0, Duration::from_millis(200), |_, _| {
+            Err(EdjcError::system_not_found("Nowhere"))
+        })
+        .unwrap();
+
+        // Connects and never sends a request
+        let _idle = TcpStream::connect(api.addr()).unwrap();
+        let started = std::time::Instant::now();
+        assert_eq!(get(&api, "/route?to=Nowhere").0, 404);
+        api.shutdown();
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("Beagle+Point"), "Beagle Point");
+        assert_eq!(percent_decode("Sagittarius%20A%2a"), "Sagittarius A*");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
@@ -0,0 +1,712 @@
+/*!
+Inara API client for commander and ship data.
+
+This module handles communication with the Inara API, which is used as a
+secondary source for commander location, ship information and system
+coordinates when EDSM has nothing to offer.
+
+Inara limits how often an application may call it, so requests are spaced
+at least a minimum interval apart, and several lookups can be packed into a
+single request with [`InaraClient::get_bulk`].
+
+System coordinates go in a cache of their own, which can be shared with the
+EDSM client's ([`InaraClient::with_shared_cache`]) so that Inara isn't asked
+for a system EDSM has already looked up. Inara knows nothing of boost stars
+or permits, so EDSM still looks up the systems only Inara has answered.
+*/
+
+use log::debug;
+use moka::sync::Cache;
+use reqwest::blocking::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cache::{self, CoordinateCache, INARA_SOURCE};
+use crate::edsm::normalize_system_name;
+use crate::types::{EdjcError, EdjcResult, SystemCoordinates};
+
+const INARA_API_URL: &str = "https://inara.cz/inapi/v1/";
+const INARA_APP_NAME: &str = "EDJC";
+const CACHE_TTL_SECONDS: u64 = 300; // 5 minutes (commander data changes often)
+/// Requests per minute allowed by default
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 10;
+
+/// Inara event status codes
+const STATUS_OK: i32 = 200;
+const STATUS_NO_DATA: i32 = 204;
+
+/// Inara API client
+#[derive(Debug)]
+pub struct InaraClient {
+    client: Client,
+    cache: Cache<String, String>,
+    /// System coordinates, possibly shared with the EDSM client
+    coordinates: Arc<dyn CoordinateCache>,
+    /// Keys of the coordinates entries this client filled, so clearing its
+    /// cache leaves the EDSM client's entries alone
+    coordinate_keys: Mutex<HashSet<String>>,
+    api_key: String,
+    api_url: String,
+    /// Minimum time between two requests
+    min_interval: Duration,
+    /// When the last request was sent
+    last_request: Mutex<Option<Instant>>,
+}
+
+/// Inara request envelope
+#[derive(Debug, Serialize)]
+struct InaraRequest {
+    header: InaraHeader,
+    events: Vec<InaraEvent>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InaraHeader {
+    app_name: String,
+    app_version: String,
+    is_being_developed: bool,
+    #[serde(rename = "APIkey")]
+    api_key: String,
+}
+
+/// A single event sent to Inara
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InaraEvent {
+    pub event_name: String,
+    pub event_timestamp: String,
+    pub event_data: Value,
+}
+
+/// Inara response envelope
+#[derive(Debug, Deserialize)]
+struct InaraResponse {
+    header: InaraResponseHeader,
+    #[serde(default)]
+    events: Vec<InaraEventResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InaraResponseHeader {
+    event_status: i32,
+    event_status_text: Option<String>,
+}
+
+/// Inara's answer to a single event
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InaraEventResponse {
+    pub event_status: i32,
+    pub event_status_text: Option<String>,
+    pub event_data: Option<Value>,
+}
+
+/// Commander profile as returned by `getCommanderProfile`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InaraCommanderProfile {
+    starsystem_name: Option<String>,
+    commander_main_ship: Option<InaraShipInfo>,
+}
+
+/// Ship information for a commander
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InaraShipInfo {
+    /// Ship type (e.g., "Anaconda")
+    pub ship_type: String,
+    /// User-given ship name
+    #[serde(default)]
+    pub ship_name: Option<String>,
+    /// User-given ship ident
+    #[serde(default)]
+    pub ship_ident: Option<String>,
+    /// Ship role as set on Inara
+    #[serde(default)]
+    pub ship_role: Option<String>,
+    /// Maximum jump range reported by Inara, if known
+    #[serde(default, rename = "shipMaxJumpRange")]
+    pub max_jump_range: Option<f64>,
+}
+
+/// Star system as returned by `getStarSystem`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InaraStarSystem {
+    starsystem_name: String,
+    starsystem_coords: Option<[f64; 3]>,
+}
+
+impl InaraEvent {
+    /// Create a new event stamped with the current time
+    pub fn new(event_name: &str, event_data: Value) -> Self {
+        Self {
+            event_name: event_name.to_string(),
+            event_timestamp: chrono::Utc::now().to_rfc3339(),
+            event_data,
+        }
+    }
+
+    /// `getCommanderProfile` event for a commander
+    pub fn commander_profile(cmdr_name: &str) -> Self {
+        Self::new("getCommanderProfile", json!({ "searchName": cmdr_name }))
+    }
+
+    /// `getStarSystem` event for a system
+    pub fn star_system(system_name: &str) -> Self {
+        Self::new("getStarSystem", json!({ "starsystemName": system_name }))
+    }
+}
+
+impl InaraClient {
+    /// Create a new Inara client
+    pub fn new(api_key: &str) -> EdjcResult<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent(crate::USER_AGENT)
+            .build()?;
+
+        let cache = Cache::builder()
+            .time_to_live(Duration::from_secs(CACHE_TTL_SECONDS))
+            .max_capacity(1000)
+            .build();
+
+        Ok(Self {
+            client,
+            coordinates: Arc::new(cache.clone()),
+            coordinate_keys: Mutex::new(HashSet::new()),
+            cache,
+            api_key: api_key.to_string(),
+            api_url: INARA_API_URL.to_string(),
+            min_interval: request_interval(DEFAULT_REQUESTS_PER_MINUTE),
+            last_request: Mutex::new(None),
+        })
+    }
+
+    /// Point the client at a different API endpoint, e.g. a local mock server
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.to_string();
+        self
+    }
+
+    /// Send at most `requests_per_minute` requests per minute (0 = unlimited)
+    pub fn with_rate_limit(mut self, requests_per_minute: u32) -> Self {
+        self.min_interval = request_interval(requests_per_minute);
+        self
+    }
+
+    /// Keep system coordinates in `cache`, usually the EDSM client's
+    /// [`coordinate_cache`](crate::edsm::EdsmClient::coordinate_cache)
+    pub fn with_shared_cache(mut self, cache: Arc<dyn CoordinateCache>) -> Self {
+        self.coordinates = cache;
+        self
+    }
+
+    /// Get a commander's current location from Inara
+    pub fn get_cmdr_location(&self, cmdr_name: &str) -> EdjcResult<String> {
+        let cache_key = format!("cmdr_location:{}", cmdr_name.to_lowercase());
+
+        if let Some(cached) = self.cache.get(&cache_key) {
+            debug!("Cache hit for Inara commander location: {cmdr_name}");
+            return Ok(cached);
+        }
+
+        debug!("Fetching commander location from Inara for: {cmdr_name}");
+
+        let event = self.send_event(InaraEvent::commander_profile(cmdr_name))?;
+        let system_name = cmdr_location(cmdr_name, event)?;
+
+        self.cache.insert(cache_key, system_name.clone());
+        Ok(system_name)
+    }
+
+    /// Forget a commander's cached location so the next lookup asks Inara again
+    pub fn invalidate_location(&self, cmdr_name: &str) {
+        self.cache
+            .invalidate(&format!("cmdr_location:{}", cmdr_name.to_lowercase()));
+    }
+
+    /// Drop everything this client cached
+    ///
+    /// Coordinates the EDSM client looked up stay in a shared cache.
+    pub fn clear_cache(&self) {
+        self.cache.invalidate_all();
+        let keys = std::mem::take(&mut *self.coordinate_keys.lock().unwrap());
+        for key in keys {
+            let filled_by_inara = self
+                .coordinates
+                .get(&key)
+                .and_then(|entry| cache::decode_coordinates(&entry))
+                .is_some_and(|(_, source)| source.as_deref() == Some(INARA_SOURCE));
+            if filled_by_inara {
+                self.coordinates.invalidate(&key);
+            }
+        }
+    }
+
+    /// Get a commander's current ship from Inara
+    pub fn get_ship_info(&self, cmdr_name: &str) -> EdjcResult<InaraShipInfo> {
+        debug!("Fetching ship info from Inara for: {cmdr_name}");
+
+        let event = self.send_event(InaraEvent::commander_profile(cmdr_name))?;
+        ship_info(cmdr_name, event)
+    }
+
+    /// Get system coordinates from Inara
+    pub fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+        let system_name = &normalize_system_name(system_name);
+        let cache_key = cache::coordinates_key(system_name);
+
+        if let Some(cached) = self.coordinates.get(&cache_key) {
+            if let Some((coords, source)) = cache::decode_coordinates(&cached) {
+                debug!(
+                    "Cache hit for Inara system coordinates: {system_name} (from {})",
+                    source.as_deref().unwrap_or("an unrecorded source")
+                );
+                return Ok(coords);
+            }
+        }
+
+        debug!("Fetching coordinates from Inara for system: {system_name}");
+
+        let event = self.send_event(InaraEvent::star_system(system_name))?;
+        let system: InaraStarSystem =
+            event_data(event, || EdjcError::system_not_found(system_name))?;
+
+        let [x, y, z] = system.starsystem_coords.ok_or_else(|| {
+            EdjcError::CoordinatesUnavailable(format!("{system_name} (on Inara)"))
+        })?;
+
+        let coordinates = SystemCoordinates {
+            name: system.starsystem_name,
+            x,
+            y,
+            z,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        if let Some(entry) = cache::encode_coordinates(&coordinates, INARA_SOURCE) {
+            self.coordinates.insert(cache_key.clone(), entry);
+            self.coordinate_keys.lock().unwrap().insert(cache_key);
+        }
+
+        Ok(coordinates)
+    }
+
+    /// Send a single event to Inara and return its response
+    fn send_event(&self, event: InaraEvent) -> EdjcResult<InaraEventResponse> {
+        self.get_bulk(vec![event])?
+            .into_iter()
+            .next()
+            .ok_or_else(|| EdjcError::InaraApi("response contained no events".to_string()))
+    }
+
+    /// Send several events in one request and return their responses in order
+    ///
+    /// Use [`cmdr_location`], [`ship_info`] and friends to decode the
+    /// responses of the matching [`InaraEvent`] constructors.
+    pub fn get_bulk(&self, events: Vec<InaraEvent>) -> EdjcResult<Vec<InaraEventResponse>> {
+        let event_count = events.len();
+        let request = InaraRequest {
+            header: InaraHeader {
+                app_name: INARA_APP_NAME.to_string(),
+                app_version: crate::PLUGIN_VERSION.to_string(),
+                is_being_developed: cfg!(debug_assertions),
+                api_key: self.api_key.clone(),
+            },
+            events,
+        };
+
+        self.throttle();
+        let response = self.client.post(&self.api_url).json(&request).send()?;
+
+        if !response.status().is_success() {
+            return Err(EdjcError::InaraApi(format!(
+                "request failed: {}",
+                response.status()
+            )));
+        }
+
+        let inara_response: InaraResponse = response
+            .json()
+            .map_err(|e| EdjcError::Parse(format!("Unexpected Inara response: {e}")))?;
+
+        // A header status of 400 means the whole request was rejected (bad key etc.)
+        if inara_response.header.event_status >= 400 {
+            return Err(EdjcError::InaraApi(format!(
+                "{}: {}",
+                inara_response.header.event_status,
+                inara_response
+                    .header
+                    .event_status_text
+                    .unwrap_or("Unknown error".to_string())
+            )));
+        }
+
+        if inara_response.events.len() != event_count {
+            return Err(EdjcError::InaraApi(format!(
+                "expected {event_count} events in response, got {}",
+                inara_response.events.len()
+            )));
+        }
+
+        Ok(inara_response.events)
+    }
+
+    /// Wait until the minimum interval since the previous request has passed
+    fn throttle(&self) {
+        let mut last_request = self
+            .last_request
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(last) = *last_request {
+            let wait = self.min_interval.saturating_sub(last.elapsed());
+            if !wait.is_zero() {
+                debug!("Throttling Inara request for {wait:?}");
+                thread::sleep(wait);
+            }
+        }
+
+        *last_request = Some(Instant::now());
+    }
+}
+
+/// Minimum time between requests for a requests-per-minute ceiling
+fn request_interval(requests_per_minute: u32) -> Duration {
+    match requests_per_minute {
+        0 => Duration::ZERO,
+        n => Duration::from_secs(60) / n,
+    }
+}
+
+/// Decode a commander's location from a `getCommanderProfile` response
+pub fn cmdr_location(cmdr_name: &str, event: InaraEventResponse) -> EdjcResult<String> {
+    let profile = commander_profile(cmdr_name, event)?;
+    profile.starsystem_name.ok_or_else(|| {
+        EdjcError::CmdrNotFound(format!("{cmdr_name} (no location shared on Inara)"))
+    })
+}
+
+/// Decode a commander's ship from a `getCommanderProfile` response
+pub fn ship_info(cmdr_name: &str, event: InaraEventResponse) -> EdjcResult<InaraShipInfo> {
+    let profile = commander_profile(cmdr_name, event)?;
+    profile
+        .commander_main_ship
+        .ok_or_else(|| EdjcError::CmdrNotFound(format!("{cmdr_name} (no ship shared on Inara)")))
+}
+
+/// Decode a `getCommanderProfile` response
+fn commander_profile(
+    cmdr_name: &str,
+    event: InaraEventResponse,
+) -> EdjcResult<InaraCommanderProfile> {
+    event_data(event, || EdjcError::CmdrNotFound(cmdr_name.to_string()))
+}
+
+/// Extract and deserialize the data of a single Inara event response
+///
+/// Inara answers "nothing found" either with status 204 or with status 200 and
+/// a missing/null `eventData`; both map onto the error produced by `not_found`.
+/// Any other status is reported as an Inara API error, and data that doesn't
+/// match the expected shape as a parsing error.
+fn event_data<T: DeserializeOwned>(
+    event: InaraEventResponse,
+    not_found: impl FnOnce() -> EdjcError,
+) -> EdjcResult<T> {
+    match event.event_status {
+        STATUS_OK | STATUS_NO_DATA => match event.event_data {
+            Some(data) if !data.is_null() => serde_json::from_value(data)
+                .map_err(|e| EdjcError::Parse(format!("Unexpected Inara event data: {e}"))),
+            _ => Err(not_found()),
+        },
+        status => Err(EdjcError::InaraApi(format!(
+            "{}: {}",
+            status,
+            event
+                .event_status_text
+                .unwrap_or("Unknown error".to_string())
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MockServer;
+
+    fn parse_event(json: &str) -> InaraEventResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_null_event_data_is_not_found() {
+        let event = parse_event(r#"{"eventStatus": 200, "eventData": null}"#);
+        let err = event_data::<InaraCommanderProfile>(event, || {
+            EdjcError::CmdrNotFound("Whit3Arrow".to_string())
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            EdjcError::CmdrNotFound(name) if name == "Whit3Arrow"
+        ));
+
+        let event = parse_event(r#"{"eventStatus": 204}"#);
+        let err = event_data::<InaraCommanderProfile>(event, || {
+            EdjcError::CmdrNotFound("Whit3Arrow".to_string())
+        })
+        .unwrap_err();
+        assert!(matches!(err, EdjcError::CmdrNotFound(_)));
+    }
+
+    #[test]
+    fn test_event_data_errors_are_distinguished() {
+        let event = parse_event(r#"{"eventStatus": 400, "eventStatusText": "Invalid API key"}"#);
+        let err = event_data::<InaraCommanderProfile>(event, || {
+            EdjcError::CmdrNotFound("Whit3Arrow".to_string())
+        })
+        .unwrap_err();
+        assert!(matches!(err, EdjcError::InaraApi(_)));
+
+        let event = parse_event(r#"{"eventStatus": 200, "eventData": {"shipType": 42}}"#);
+        let err = event_data::<InaraShipInfo>(event, || {
+            EdjcError::CmdrNotFound("Whit3Arrow".to_string())
+        })
+        .unwrap_err();
+        assert!(matches!(err, EdjcError::Parse(_)));
+    }
+
+    #[test]
+    fn test_public_methods_report_typed_errors() {
+        let server = MockServer::start();
+        let client = InaraClient::new("test-key")
+            .unwrap()
+            .with_api_url(&server.url("/inara"))
+            .with_rate_limit(0);
+        // One response per request below, in order
+        let no_data = r#"{"header": {"eventStatus": 200}, "events": [{"eventStatus": 204}]}"#;
+        let no_details = r#"{"header": {"eventStatus": 200}, "events": [
+            {"eventStatus": 200, "eventData": {"starsystemName": "Fuelum"}}
+        ]}"#;
+        server.respond("/inara", 200, no_data);
+        server.respond("/inara", 200, no_data);
+        server.respond("/inara", 200, no_details);
+        server.respond("/inara", 200, no_details);
+        server.respond(
+            "/inara",
+            200,
+            r#"{"header": {"eventStatus": 400, "eventStatusText": "Invalid API key"}}"#,
+        );
+        server.respond("/inara", 503, "");
+        server.respond("/inara", 200, "<html>maintenance</html>");
+
+        let err = client.get_cmdr_location("Whit3Arrow").unwrap_err();
+        assert!(matches!(err, EdjcError::CmdrNotFound(_)), "{err}");
+        let err = client.get_system_coordinates("Nowhere").unwrap_err();
+        assert!(matches!(err, EdjcError::SystemNotFound { .. }), "{err}");
+
+        let err = client.get_ship_info("Whit3Arrow").unwrap_err();
+        assert!(matches!(err, EdjcError::CmdrNotFound(_)), "{err}");
+        let err = client.get_system_coordinates("Fuelum").unwrap_err();
+        assert!(matches!(err, EdjcError::CoordinatesUnavailable(_)), "{err}");
+
+        let err = client
+            .get_bulk(vec![InaraEvent::star_system("Sol")])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Inara API error: 400: Invalid API key");
+
+        let err = client.get_cmdr_location("Dr. Jameson").unwrap_err();
+        assert!(matches!(err, EdjcError::InaraApi(_)), "{err}");
+
+        let err = client.get_system_coordinates("Sol").unwrap_err();
+        assert!(matches!(err, EdjcError::Parse(_)), "{err}");
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let client = client.with_api_url(&format!("http://127.0.0.1:{port}/inara"));
+        let err = client.get_system_coordinates("Maia").unwrap_err();
+        assert!(matches!(err, EdjcError::Network(_)), "{err}");
+        assert_eq!(err.hint(), Some("server unreachable, try again later"));
+    }
+
+    #[test]
+    fn test_commander_profile_parsing() {
+        let event = parse_event(
+            r#"{
+                "eventStatus": 200,
+                "eventData": {
+                    "commanderName": "Whit3Arrow",
+                    "starsystemName": "Fuelum",
+                    "commanderMainShip": {
+                        "shipType": "Diamondback Explorer",
+                        "shipName": "Rat Trap",
+                        "shipMaxJumpRange": 58.2
+                    }
+                }
+            }"#,
+        );
+        let profile: InaraCommanderProfile =
+            event_data(event, || EdjcError::CmdrNotFound("Whit3Arrow".to_string())).unwrap();
+
+        assert_eq!(profile.starsystem_name.as_deref(), Some("Fuelum"));
+        let ship = profile.commander_main_ship.unwrap();
+        assert_eq!(ship.ship_type, "Diamondback Explorer");
+        assert_eq!(ship.max_jump_range, Some(58.2));
+    }
+
+    #[test]
+    fn test_coordinates_are_shared_with_edsm() {
+        use crate::edsm::EdsmClient;
+
+        let server = MockServer::start();
+        let inara_system = |name: &str, coords: &str| {
+            format!(
+                r#"{{
+                    "header": {{"eventStatus": 200}},
+                    "events": [{{"eventStatus": 200, "eventData": {{
+                        "starsystemName": "{name}",
+                        "starsystemCoords": {coords}
+                    }}}}]
+                }}"#
+            )
+        };
+        server.respond(
+            "/inara",
+            200,
+            &inara_system("Fuelum", "[52.0, -52.65625, 49.8125]"),
+        );
+        server.respond(
+            "/inara",
+            200,
+            &inara_system("Achenar", "[67.5, -119.46875, 24.84375]"),
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125},
+                "primaryStar": {"type": "Neutron Star", "isScoopable": false}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Colonia", "coords": {"x": -9530.5, "y": -910.28125, "z": 19808.125}}"#,
+        );
+        let edsm = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let inara = InaraClient::new("test-key")
+            .unwrap()
+            .with_api_url(&server.url("/inara"))
+            .with_rate_limit(0)
+            .with_shared_cache(edsm.coordinate_cache());
+        let source = |key: &str| {
+            let cached = edsm.coordinate_cache().get(key)?;
+            cache::decode_coordinates(&cached).and_then(|(_, source)| source)
+        };
+
+        // Found on Inara, which knows nothing about the star, so EDSM still
+        // asks for its own data and replaces the entry
+        assert_eq!(inara.get_system_coordinates("fuelum").unwrap().z, 49.8125);
+        assert_eq!(source("coords:fuelum").as_deref(), Some("Inara"));
+        let fuelum = edsm.get_system_coordinates("  FUELUM ").unwrap();
+        assert_eq!(fuelum.name, "Fuelum");
+        assert!(fuelum.has_neutron_star);
+        assert_eq!(server.hits("/api/system"), 1);
+        assert_eq!(source("coords:fuelum").as_deref(), Some("EDSM"));
+
+        // EDSM's entries serve Inara lookups
+        assert_eq!(edsm.get_system_coordinates("Colonia").unwrap().x, -9530.5);
+        let colonia = inara.get_system_coordinates("colonia").unwrap();
+        assert_eq!(colonia.name, "Colonia");
+        assert_eq!(server.hits("/inara"), 1);
+        assert_eq!(source("coords:colonia").as_deref(), Some("EDSM"));
+
+        // Clearing Inara's cache only drops what Inara filled
+        assert_eq!(inara.get_system_coordinates("achenar").unwrap().x, 67.5);
+        inara.clear_cache();
+        assert_eq!(source("coords:achenar"), None);
+        assert_eq!(source("coords:fuelum").as_deref(), Some("EDSM"));
+        assert_eq!(source("coords:colonia").as_deref(), Some("EDSM"));
+    }
+
+    #[test]
+    fn test_bulk_events_share_one_request() {
+        let server = MockServer::start();
+        server.respond(
+            "/inara",
+            200,
+            r#"{
+                "header": {"eventStatus": 200},
+                "events": [
+                    {"eventStatus": 200, "eventData": {"starsystemName": "Fuelum"}},
+                    {"eventStatus": 200, "eventData": {
+                        "starsystemName": "Sol",
+                        "commanderMainShip": {"shipType": "Anaconda"}
+                    }}
+                ]
+            }"#,
+        );
+        let client = InaraClient::new("test-key")
+            .unwrap()
+            .with_api_url(&server.url("/inara"));
+
+        let mut responses = client
+            .get_bulk(vec![
+                InaraEvent::commander_profile("Whit3Arrow"),
+                InaraEvent::commander_profile("Dr. Jameson"),
+            ])
+            .unwrap()
+            .into_iter();
+
+        assert_eq!(server.hits("/inara"), 1);
+        let request = &server.requests("/inara")[0];
+        assert!(request.contains("Whit3Arrow"));
+        assert!(request.contains("Dr. Jameson"));
+
+        let location = cmdr_location("Whit3Arrow", responses.next().unwrap()).unwrap();
+        assert_eq!(location, "Fuelum");
+        let ship = ship_info("Dr. Jameson", responses.next().unwrap()).unwrap();
+        assert_eq!(ship.ship_type, "Anaconda");
+    }
+
+    #[test]
+    fn test_requests_are_throttled() {
+        assert_eq!(request_interval(0), Duration::ZERO);
+        assert_eq!(request_interval(10), Duration::from_secs(6));
+
+        let server = MockServer::start();
+        server.respond(
+            "/inara",
+            200,
+            r#"{"header": {"eventStatus": 200}, "events": [{"eventStatus": 204}]}"#,
+        );
+        let mut client = InaraClient::new("test-key")
+            .unwrap()
+            .with_api_url(&server.url("/inara"));
+        client.min_interval = Duration::from_millis(100);
+
+        let start = Instant::now();
+        client
+            .get_bulk(vec![InaraEvent::star_system("Sol")])
+            .unwrap();
+        client
+            .get_bulk(vec![InaraEvent::star_system("Sol")])
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(server.hits("/inara"), 2);
+    }
+}
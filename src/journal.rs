@@ -0,0 +1,422 @@
+/*!
+Ship data from the Elite Dangerous journal.
+
+The game writes one JSON event per line to `Journal.<timestamp>.<part>.log`
+files in its save folder, starting a new file every session (and every few
+hundred thousand lines). Each `Loadout` event records the current ship's
+`MaxJumpRange`, which gives an up-to-date range without any API, and every
+jump records the system arrived in, which [`JournalLocation`] offers as the
+commander's location.
+
+The game keeps the current journal open while it runs. Locations are read
+from the end of the file backwards, so a long session's journal isn't read
+whole for every route, and a read the game blocks (Windows reports a sharing
+or lock violation) is retried briefly before giving up.
+*/
+
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// The part of a journal event we care about
+#[derive(Debug, Deserialize)]
+struct JournalEvent {
+    event: String,
+    #[serde(rename = "MaxJumpRange")]
+    max_jump_range: Option<f64>,
+    #[serde(rename = "StarSystem")]
+    star_system: Option<String>,
+}
+
+/// Events after which the commander is in `StarSystem`
+const LOCATION_EVENTS: [&str; 3] = ["Location", "FSDJump", "CarrierJump"];
+
+/// How much of a journal is read at a time, from the end, looking for the
+/// latest location
+const TAIL_CHUNK_BYTES: u64 = 64 * 1024;
+
+/// Journals searched for a location, newest first: a session that has only
+/// just started has no `Location` event yet, but the one before it does
+const LOCATION_JOURNALS: usize = 2;
+
+/// Reads of a journal the game has locked, and the wait between them
+const LOCKED_READ_ATTEMPTS: u32 = 5;
+const LOCKED_READ_DELAY: Duration = Duration::from_millis(50);
+
+/// The commander's location according to the journal at a path
+#[derive(Debug, Clone)]
+pub struct JournalLocation {
+    path: PathBuf,
+}
+
+impl JournalLocation {
+    /// Read locations from a journal folder or file
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// The system of the latest jump, read afresh on every call
+    pub fn current_system(&self) -> Result<String> {
+        read_location_from_journal(&self.path)
+    }
+}
+
+/// The ship's maximum jump range from the latest `Loadout` event
+///
+/// `path` is either a journal file or the journal folder, in which case the
+/// most recently modified `Journal.*.log` is read.
+pub fn read_jump_range_from_journal(path: &Path) -> Result<f64> {
+    let (journal, events) = read_events(path)?;
+    debug!("Reading jump range from {journal:?}");
+
+    events
+        .into_iter()
+        .filter(|event| event.event == "Loadout")
+        .find_map(|event| event.max_jump_range)
+        .ok_or_else(|| anyhow!("No Loadout event with a MaxJumpRange in {journal:?}"))
+}
+
+/// The system the latest `Location`, `FSDJump` or `CarrierJump` event put
+/// the commander in
+///
+/// For a folder, the newest journal is searched and, if it has no such event
+/// yet, the one before it.
+pub fn read_location_from_journal(path: &Path) -> Result<String> {
+    let journals = if path.is_dir() {
+        journals_newest_first(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    for journal in journals.iter().take(LOCATION_JOURNALS) {
+        debug!("Reading location from {journal:?}");
+        let location = retry_locked(journal, || tail_location(journal, TAIL_CHUNK_BYTES))?;
+        if let Some(system) = location {
+            return Ok(system);
+        }
+    }
+    Err(anyhow!("No jump or location event in {:?}", journals[0]))
+}
+
+/// The `StarSystem` of the last location event in a journal, reading it
+/// backwards `chunk_bytes` at a time
+fn tail_location(journal: &Path, chunk_bytes: u64) -> io::Result<Option<String>> {
+    let mut file = File::open(journal)?;
+    let mut end = file.metadata()?.len();
+    // The start of a line that began in an earlier part of the file
+    let mut carried = Vec::new();
+
+    while end > 0 {
+        let start = end.saturating_sub(chunk_bytes);
+        let mut chunk = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.append(&mut carried);
+
+        // Up to the first line break, the line may go on before `start`
+        let complete = match start {
+            0 => 0,
+            _ => chunk
+                .iter()
+                .position(|&byte| byte == b'\n')
+                .map_or(chunk.len(), |index| index + 1),
+        };
+        let location = chunk[complete..]
+            .split(|&byte| byte == b'\n')
+            .rev()
+            // A line being written while we read is incomplete JSON; skip it
+            .filter_map(|line| serde_json::from_slice::<JournalEvent>(line).ok())
+            .filter(|event| LOCATION_EVENTS.contains(&event.event.as_str()))
+            .find_map(|event| event.star_system);
+        if location.is_some() {
+            return Ok(location);
+        }
+
+        chunk.truncate(complete);
+        carried = chunk;
+        end = start;
+    }
+    Ok(None)
+}
+
+/// Run a read of `journal`, trying again for a moment while the game has
+/// it locked
+fn retry_locked<T>(journal: &Path, mut read: impl FnMut() -> io::Result<T>) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        match read() {
+            Err(e) if is_locked(&e) && attempt < LOCKED_READ_ATTEMPTS => {
+                debug!("{journal:?} is locked ({e}), retrying");
+                thread::sleep(LOCKED_READ_DELAY);
+                attempt += 1;
+            }
+            result => return result.with_context(|| format!("Could not read {journal:?}")),
+        }
+    }
+}
+
+/// Whether a read failed because another process holds the file
+fn is_locked(error: &io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    error.kind() == io::ErrorKind::PermissionDenied
+        || (cfg!(windows) && matches!(error.raw_os_error(), Some(32 | 33)))
+}
+
+/// The journal read and its events, newest first
+fn read_events(path: &Path) -> Result<(PathBuf, Vec<JournalEvent>)> {
+    let journal = if path.is_dir() {
+        newest_journal(path)?
+    } else {
+        path.to_path_buf()
+    };
+
+    let contents =
+        fs::read_to_string(&journal).with_context(|| format!("Could not read {journal:?}"))?;
+    // Newest events are at the end of the file
+    let events = contents
+        .lines()
+        .rev()
+        // A line being written while we read is incomplete JSON; skip it
+        .filter_map(|line| serde_json::from_str::<JournalEvent>(line).ok())
+        .collect();
+    Ok((journal, events))
+}
+
+/// The most recently modified `Journal.*.log` in a folder
+fn newest_journal(dir: &Path) -> Result<PathBuf> {
+    Ok(journals_newest_first(dir)?.remove(0))
+}
+
+/// The `Journal.*.log` files in a folder, the most recently modified first
+///
+/// Fails if there are none.
+fn journals_newest_first(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut journals = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Could not list {dir:?}"))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !(name.starts_with("Journal.") && name.ends_with(".log")) {
+            continue;
+        }
+
+        journals.push((entry.metadata()?.modified()?, entry.path()));
+    }
+    if journals.is_empty() {
+        return Err(anyhow!("No Journal.*.log files in {dir:?}"));
+    }
+
+    journals.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(journals.into_iter().map(|(_, path)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+
+    const SAMPLE_JOURNAL: &str = include_str!("../tests/fixtures/Journal.sample.log");
+    /// A jump from Sol to Barnard's Star, cut off while the next is written
+    const JUMPS_JOURNAL: &str = include_str!("../tests/fixtures/Journal.jumps.log");
+
+    fn write_journal(dir: &Path, name: &str, contents: &str, age: Duration) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_latest_loadout_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_journal(
+            dir.path(),
+            "Journal.2026-10-01T120000.01.log",
+            SAMPLE_JOURNAL,
+            Duration::ZERO,
+        );
+
+        // The Anaconda's loadout comes first, then the swap to the DBX
+        assert_eq!(read_jump_range_from_journal(&path).unwrap(), 58.42);
+    }
+
+    #[test]
+    fn test_newest_journal_in_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let anaconda = SAMPLE_JOURNAL
+            .lines()
+            .take(3)
+            .collect::<Vec<_>>()
+            .join("\n");
+        write_journal(
+            dir.path(),
+            "Journal.2026-10-01T120000.01.log",
+            SAMPLE_JOURNAL,
+            Duration::from_secs(3600),
+        );
+        write_journal(
+            dir.path(),
+            "Journal.2026-10-02T090000.01.log",
+            &anaconda,
+            Duration::from_secs(60),
+        );
+        write_journal(dir.path(), "Status.json", "{}", Duration::ZERO);
+
+        assert_eq!(read_jump_range_from_journal(dir.path()).unwrap(), 21.63);
+    }
+
+    #[test]
+    fn test_journal_without_loadout() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = read_jump_range_from_journal(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("No Journal.*.log files"), "{err}");
+
+        let path = write_journal(
+            dir.path(),
+            "Journal.2026-10-01T120000.01.log",
+            "{ \"event\":\"Fileheader\" }\n{ \"event\":\"Loadout\", \"Ship\": ",
+            Duration::ZERO,
+        );
+        let err = read_jump_range_from_journal(&path).unwrap_err();
+        assert!(err.to_string().contains("No Loadout event"), "{err}");
+    }
+
+    #[test]
+    fn test_location_from_latest_jump() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_journal(
+            dir.path(),
+            "Journal.2026-10-01T120000.01.log",
+            SAMPLE_JOURNAL,
+            Duration::ZERO,
+        );
+        assert_eq!(
+            read_location_from_journal(&path).unwrap(),
+            "Eol Prou RS-T d3-94"
+        );
+
+        // Before the jump, the Location event at login
+        let at_login = SAMPLE_JOURNAL
+            .lines()
+            .take(4)
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&path, at_login).unwrap();
+        let location = JournalLocation::new(dir.path());
+        assert_eq!(location.current_system().unwrap(), "Fuelum");
+
+        let anaconda = SAMPLE_JOURNAL
+            .lines()
+            .take(3)
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&path, anaconda).unwrap();
+        let err = location.current_system().unwrap_err();
+        assert!(
+            err.to_string().contains("No jump or location event"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_location_from_a_sequence_of_jumps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_journal(
+            dir.path(),
+            "Journal.2026-10-03T180000.01.log",
+            JUMPS_JOURNAL,
+            Duration::ZERO,
+        );
+        let location = JournalLocation::new(dir.path());
+        assert_eq!(location.current_system().unwrap(), "Barnard's Star");
+
+        // However the file is cut into chunks, even a byte at a time
+        for chunk_bytes in (1..200).chain([JUMPS_JOURNAL.len() as u64]) {
+            assert_eq!(
+                tail_location(&path, chunk_bytes).unwrap().as_deref(),
+                Some("Barnard's Star"),
+                "{chunk_bytes} bytes at a time"
+            );
+        }
+
+        // Each jump in turn, as the journal is written
+        let jumps: Vec<&str> = JUMPS_JOURNAL.lines().collect();
+        for (lines, expected) in [(3, "Sol"), (5, "Alpha Centauri"), (9, "Wolf 359")] {
+            fs::write(&path, jumps[..lines].join("\n")).unwrap();
+            assert_eq!(location.current_system().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_new_session_falls_back_to_previous_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        write_journal(
+            dir.path(),
+            "Journal.2026-10-03T180000.01.log",
+            JUMPS_JOURNAL,
+            Duration::from_secs(3600),
+        );
+        // The game has only just started the next session
+        let header = JUMPS_JOURNAL.lines().take(2).collect::<Vec<_>>().join("\n");
+        write_journal(
+            dir.path(),
+            "Journal.2026-10-04T090000.01.log",
+            &header,
+            Duration::from_secs(5),
+        );
+
+        assert_eq!(
+            read_location_from_journal(dir.path()).unwrap(),
+            "Barnard's Star"
+        );
+    }
+
+    #[test]
+    fn test_locked_journal_is_retried() {
+        let journal = Path::new("Journal.2026-10-03T180000.01.log");
+        let locked = || io::Error::from(io::ErrorKind::PermissionDenied);
+
+        let mut reads = 0;
+        let read = retry_locked(journal, || {
+            reads += 1;
+            if reads < 3 {
+                Err(locked())
+            } else {
+                Ok("Sol")
+            }
+        });
+        assert_eq!(read.unwrap(), "Sol");
+        assert_eq!(reads, 3);
+
+        let mut reads = 0;
+        let err = retry_locked(journal, || -> io::Result<()> {
+            reads += 1;
+            Err(locked())
+        })
+        .unwrap_err();
+        assert_eq!(reads, LOCKED_READ_ATTEMPTS);
+        assert!(err.to_string().contains("Could not read"), "{err}");
+
+        // Other errors aren't worth waiting for
+        let mut reads = 0;
+        retry_locked(journal, || -> io::Result<()> {
+            reads += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        })
+        .unwrap_err();
+        assert_eq!(reads, 1);
+    }
+}
@@ -6,21 +6,185 @@ taking into account ship jump ranges and stellar phenomena that
 affect jump range (neutron stars and white dwarfs).
 */
 
-use anyhow::Result;
 use log::debug;
+use serde::Serialize;
+use std::collections::HashSet;
 
-use crate::types::{JumpResult, SystemCoordinates};
+use crate::system_source::SystemSource;
+use crate::types::{
+    distances_from, CorridorHop, EdjcError, EdjcResult, FsdProfile, JumpResult, JumpRounding,
+    NumberFormat, RouteLeg, RouteOptimization, RouteOptions, SystemCoordinates, TimeModel,
+};
+
+/// Route type reported when `require_boost` is set but no boosted route helps
+pub const NO_BOOST_ROUTE: &str = "no boost route available";
+
+/// Route type reported when origin and destination are (practically) the same place
+pub const ALREADY_IN_SYSTEM_ROUTE: &str = "already in system";
+
+/// Route type of a route whose first jump is supercharged at the origin
+/// ([`RouteOptions::boost_at_origin`])
+pub const ORIGIN_BOOST_ROUTE: &str = "supercharge at start";
+
+/// Route type of the direct estimate given when pathfinding runs out of time
+pub const TIMED_OUT_ROUTE: &str = "direct (estimate — pathfinding timed out)";
+
+/// Distances below this count as being in the system already; EDSM
+/// coordinates are on a 1/32 LY grid
+const SAME_SYSTEM_DISTANCE_LY: f64 = 0.05;
+
+/// Share of a route's distance along the Y axis above which it counts as steep
+const STEEP_ROUTE_RATIO: f64 = 0.6;
+
+/// Shortest route worth considering a neutron highway for, by default
+pub const DEFAULT_NEUTRON_THRESHOLD_LY: f64 = 500.0;
+
+/// Shortest route worth considering white dwarf supercharges for, by default
+pub const DEFAULT_WHITE_DWARF_THRESHOLD_LY: f64 = 150.0;
+
+/// Route type label for a neutron route supercharging `boosts` times
+pub fn neutron_route_type(boosts: u32) -> String {
+    match boosts {
+        1 => "neutron highway (1 boost)".to_string(),
+        n => format!("neutron highway ({n} boosts)"),
+    }
+}
+
+/// Jumps to cover `distance_ly` with a `jump_range` LY jump, given a boost
+///
+/// White dwarf and neutron star boosts are estimated the way
+/// [`JumpCalculator`] estimates them without corridor data: one jump to reach
+/// a boost star, then boosted jumps for 80% of the distance. Synthesis boosts
+/// a single jump. Needs a positive jump range.
+pub fn jumps_for_distance(distance_ly: f64, jump_range: f64, boost: StellarBoost) -> u32 {
+    match boost {
+        StellarBoost::None => direct_jumps(distance_ly, jump_range),
+        StellarBoost::WhiteDwarf | StellarBoost::NeutronStar => {
+            let (boosted, normal) = boosted_jumps(distance_ly, jump_range, boost.multiplier());
+            1 + boosted + normal
+        }
+        StellarBoost::SynthesisBasic
+        | StellarBoost::SynthesisStandard
+        | StellarBoost::SynthesisPremium => {
+            jumps_with_first_boost(distance_ly, jump_range, boost.multiplier())
+        }
+    }
+}
+
+/// Jumps and route type of the best route over a distance, for callers that
+/// already know it
+///
+/// Same as [`JumpCalculator::calculate_route_with_options`] with a default
+/// calculator, minus the systems. Needs a positive jump range.
+pub fn best_route(distance_ly: f64, range: f64, options: &RouteOptions) -> (u32, String) {
+    JumpCalculator::new().best_route(distance_ly, range, options)
+}
+
+/// Jumps to cover a distance without any boost
+fn direct_jumps(distance: f64, jump_range: f64) -> u32 {
+    (distance / jump_range).ceil() as u32
+}
+
+/// Distance over jump range, when a route is flown in plain unboosted
+/// jumps so its last one may only be partly needed
+fn exact_direct_jumps(candidate: &RouteCandidate, distance: f64, jump_range: f64) -> Option<f64> {
+    (candidate.boosts == 0 && candidate.jumps == direct_jumps(distance, jump_range))
+        .then_some(distance / jump_range)
+}
+
+/// Boosted and unboosted jumps of an estimated boost route: 80% of the
+/// distance at `multiplier` times the range, the rest unboosted
+fn boosted_jumps(distance: f64, jump_range: f64, multiplier: f64) -> (u32, u32) {
+    let boosted = direct_jumps(distance * 0.8, jump_range * multiplier);
+    let normal = direct_jumps(distance * 0.2, jump_range);
+    (boosted, normal)
+}
+
+/// Jumps to cover a distance when the first jump is boosted by `multiplier`
+fn jumps_with_first_boost(distance: f64, jump_range: f64, multiplier: f64) -> u32 {
+    let boosted_range = jump_range * multiplier;
+    if distance <= 0.0 {
+        0
+    } else if distance <= boosted_range {
+        1
+    } else {
+        1 + direct_jumps(distance - boosted_range, jump_range)
+    }
+}
+
+/// Where the neutron highway candidate comes from
+enum NeutronHighway<'a> {
+    /// Estimated from the distance alone
+    Estimated,
+    /// Routed through the neutron stars known along the corridor
+    Through {
+        from: &'a SystemCoordinates,
+        to: &'a SystemCoordinates,
+        neutron_stars: &'a [SystemCoordinates],
+    },
+}
 
 /// Jump route calculator
 #[derive(Debug)]
-pub struct JumpCalculator;
+pub struct JumpCalculator {
+    /// Fuel model of the configured drive, when known
+    fsd: Option<FsdProfile>,
+    /// Range multiplier after supercharging at a neutron star
+    neutron_multiplier: f64,
+    /// Range multiplier after supercharging at a white dwarf
+    white_dwarf_multiplier: f64,
+    /// Shortest route considered for the neutron highway
+    neutron_threshold_ly: f64,
+    /// Shortest route considered for white dwarf supercharges
+    white_dwarf_threshold_ly: f64,
+    /// Time per jump, supercharge and scoop stop for the `{time}` estimate
+    time_model: TimeModel,
+    /// Main tank capacity in tons, to estimate scoop stops for the time estimate
+    fuel_capacity: Option<f64>,
+    /// Lowercased names of populated systems, for `prefer_populated`
+    populated_systems: HashSet<String>,
+}
+
+/// A candidate route considered by the calculator
+#[derive(Debug, Clone)]
+struct RouteCandidate {
+    /// Total number of jumps
+    jumps: u32,
+    /// Number of those jumps that are supercharged
+    boosts: u32,
+    /// Number of the supercharges taken at neutron stars
+    neutron_boosts: u32,
+    /// Route type label
+    route_type: String,
+    /// Distances flown with unboosted jumps, where FSD injections can help
+    gaps: Vec<f64>,
+    /// Waypoints, one per gap, when the route goes through known systems
+    legs: Vec<RouteLeg>,
+}
+
+impl RouteCandidate {
+    /// Estimated wall-clock time, charging a penalty for every supercharged
+    /// jump and a further one for every neutron supercharge
+    ///
+    /// The neutron penalty keeps short routes direct when the neutron
+    /// highway only saves a jump or two: finding the star, flying into the
+    /// cone and cooling down take longer than the jumps saved.
+    fn estimated_seconds(&self, options: &RouteOptions) -> f64 {
+        self.jumps as f64 * options.seconds_per_jump
+            + self.boosts as f64 * options.boost_penalty_seconds
+            + self.neutron_boosts as f64 * options.neutron_penalty_seconds
+    }
+}
 
 /// Types of stellar phenomena that affect jump range
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StellarBoost {
     None,
-    WhiteDwarf,  // 1.5x multiplier
-    NeutronStar, // 4.0x multiplier
+    WhiteDwarf,        // 1.5x multiplier
+    NeutronStar,       // 4.0x multiplier
+    SynthesisBasic,    // 1.25x multiplier, single jump
+    SynthesisStandard, // 1.5x multiplier, single jump
+    SynthesisPremium,  // 2.0x multiplier, single jump
 }
 
 impl StellarBoost {
@@ -30,14 +194,104 @@ impl StellarBoost {
             StellarBoost::None => 1.0,
             StellarBoost::WhiteDwarf => 1.5,
             StellarBoost::NeutronStar => 4.0,
+            StellarBoost::SynthesisBasic => 1.25,
+            StellarBoost::SynthesisStandard => 1.5,
+            StellarBoost::SynthesisPremium => 2.0,
+        }
+    }
+
+    /// Route type label for routes boosted this way ("neutron highway"), or
+    /// the injection grade's name for synthesis boosts ("premium")
+    pub fn label(self) -> &'static str {
+        match self {
+            StellarBoost::None => "direct",
+            StellarBoost::WhiteDwarf => "white dwarf assisted",
+            StellarBoost::NeutronStar => "neutron highway",
+            StellarBoost::SynthesisBasic => "basic",
+            StellarBoost::SynthesisStandard => "standard",
+            StellarBoost::SynthesisPremium => "premium",
         }
     }
 }
 
+/// FSD injection grades, cheapest first
+const INJECTION_GRADES: [StellarBoost; 3] = [
+    StellarBoost::SynthesisBasic,
+    StellarBoost::SynthesisStandard,
+    StellarBoost::SynthesisPremium,
+];
+
 impl JumpCalculator {
     /// Create a new jump calculator
     pub fn new() -> Self {
-        Self
+        Self {
+            fsd: None,
+            neutron_multiplier: StellarBoost::NeutronStar.multiplier(),
+            white_dwarf_multiplier: StellarBoost::WhiteDwarf.multiplier(),
+            neutron_threshold_ly: DEFAULT_NEUTRON_THRESHOLD_LY,
+            white_dwarf_threshold_ly: DEFAULT_WHITE_DWARF_THRESHOLD_LY,
+            time_model: TimeModel::default(),
+            fuel_capacity: None,
+            populated_systems: HashSet::new(),
+        }
+    }
+
+    /// Plan with different supercharge multipliers than the canonical 4x / 1.5x
+    pub fn with_multipliers(mut self, neutron: f64, white_dwarf: f64) -> Self {
+        self.neutron_multiplier = neutron;
+        self.white_dwarf_multiplier = white_dwarf;
+        self
+    }
+
+    /// Only consider boosted routes at least this long (0 always considers them)
+    pub fn with_boost_thresholds(mut self, neutron_ly: f64, white_dwarf_ly: f64) -> Self {
+        self.neutron_threshold_ly = neutron_ly;
+        self.white_dwarf_threshold_ly = white_dwarf_ly;
+        self
+    }
+
+    /// Jump range multiplier used for a boost type
+    fn multiplier(&self, boost: StellarBoost) -> f64 {
+        match boost {
+            StellarBoost::NeutronStar => self.neutron_multiplier,
+            StellarBoost::WhiteDwarf => self.white_dwarf_multiplier,
+            StellarBoost::None
+            | StellarBoost::SynthesisBasic
+            | StellarBoost::SynthesisStandard
+            | StellarBoost::SynthesisPremium => boost.multiplier(),
+        }
+    }
+
+    /// Estimate fuel with the drive's fuel formula instead of a flat rate
+    pub fn with_fsd_profile(mut self, fsd: Option<FsdProfile>) -> Self {
+        self.fsd = fsd;
+        self
+    }
+
+    /// Estimate route times with a different time model
+    pub fn with_time_model(mut self, time_model: TimeModel) -> Self {
+        self.time_model = time_model;
+        self
+    }
+
+    /// Count scoop stops for a tank of this size in the time estimate
+    pub fn with_fuel_capacity(mut self, fuel_capacity: Option<f64>) -> Self {
+        self.fuel_capacity = fuel_capacity;
+        self
+    }
+
+    /// Know which systems are populated, for routes planned with
+    /// `prefer_populated`
+    pub fn with_populated_systems(mut self, systems: &[SystemCoordinates]) -> Self {
+        self.populated_systems = systems
+            .iter()
+            .map(|system| system.name.to_lowercase())
+            .collect();
+        self
+    }
+
+    fn is_populated(&self, system: &SystemCoordinates) -> bool {
+        self.populated_systems.contains(&system.name.to_lowercase())
     }
 
     /// Calculate the optimal route between two systems
@@ -46,52 +300,347 @@ impl JumpCalculator {
         from: &SystemCoordinates,
         to: &SystemCoordinates,
         base_jump_range: f64,
-    ) -> Result<JumpResult> {
+    ) -> EdjcResult<JumpResult> {
+        self.calculate_route_with_options(from, to, base_jump_range, &RouteOptions::default())
+    }
+
+    /// Calculate the optimal route between two systems, looking both up by name
+    pub fn calculate_route_between(
+        &self,
+        systems: &dyn SystemSource,
+        from: &str,
+        to: &str,
+        base_jump_range: f64,
+    ) -> EdjcResult<JumpResult> {
+        let from = systems.get_system_coordinates(from)?;
+        let to = systems.get_system_coordinates(to)?;
+        self.calculate_route(&from, &to, base_jump_range)
+    }
+
+    /// Calculate the optimal route between two systems using explicit route options
+    pub fn calculate_route_with_options(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        base_jump_range: f64,
+        options: &RouteOptions,
+    ) -> EdjcResult<JumpResult> {
+        self.calculate_route_with_corridor(from, to, base_jump_range, options, None)
+    }
+
+    /// Calculate the optimal route, using real neutron stars along the corridor
+    ///
+    /// Fails with [`EdjcError::InvalidJumpRange`] unless the jump range is a
+    /// positive number of light years.
+    ///
+    /// With `corridor_neutrons` set, the neutron highway candidate is routed
+    /// through those stars (see [`Self::neutron_highway_candidate`]) instead of
+    /// being estimated; without it this is [`Self::calculate_route_with_options`].
+    /// Stars further off the straight line than the options' detour budget
+    /// are left out.
+    pub fn calculate_route_with_corridor(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        base_jump_range: f64,
+        options: &RouteOptions,
+        corridor_neutrons: Option<&[SystemCoordinates]>,
+    ) -> EdjcResult<JumpResult> {
+        if !(base_jump_range > 0.0 && base_jump_range.is_finite()) {
+            return Err(EdjcError::InvalidJumpRange(base_jump_range));
+        }
         let total_distance = self.calculate_distance(from, to);
+        let base_jump_range = self.effective_jump_range(base_jump_range, total_distance, options);
 
         debug!(
             "Calculating route from {} to {} ({}ly)",
             from.name, to.name, total_distance
         );
 
-        // Calculate jumps for different scenarios
-        let normal_jumps = self.calculate_jumps_direct(total_distance, base_jump_range);
+        let highway = match corridor_neutrons {
+            Some(neutron_stars) => NeutronHighway::Through {
+                from,
+                to,
+                neutron_stars,
+            },
+            None => NeutronHighway::Estimated,
+        };
+        let best = self.best_candidate(total_distance, base_jump_range, options, &to.name, highway);
+        let exact_jumps = exact_direct_jumps(&best, total_distance, base_jump_range);
 
-        // Check if we can use neutron highway
-        let neutron_jumps = self.calculate_jumps_with_boost(
+        Ok(JumpResult {
+            jumps: best.jumps,
             total_distance,
-            base_jump_range,
-            StellarBoost::NeutronStar,
-        );
+            route_type: best.route_type,
+            from_system: from.name.clone(),
+            to_system: to.name.clone(),
+            exact_jumps,
+            legs: best.legs,
+            supercharges: best.boosts,
+        })
+    }
 
-        // Check if white dwarf route is better
-        let white_dwarf_jumps = self.calculate_jumps_with_boost(
-            total_distance,
+    /// Jumps and route type of the best route over a distance, without any
+    /// systems to route through
+    ///
+    /// Boosted routes are estimated, as by [`Self::calculate_route_with_options`].
+    /// Needs a positive jump range.
+    pub fn best_route(
+        &self,
+        distance_ly: f64,
+        base_jump_range: f64,
+        options: &RouteOptions,
+    ) -> (u32, String) {
+        let base_jump_range = self.effective_jump_range(base_jump_range, distance_ly, options);
+        let best = self.best_candidate(
+            distance_ly,
             base_jump_range,
-            StellarBoost::WhiteDwarf,
+            options,
+            "",
+            NeutronHighway::Estimated,
         );
+        (best.jumps, best.route_type)
+    }
 
-        // Determine the best route
-        let (jumps, route_type) =
-            if neutron_jumps < normal_jumps && neutron_jumps < white_dwarf_jumps {
-                (neutron_jumps, "neutron highway".to_string())
-            } else if white_dwarf_jumps < normal_jumps {
-                (white_dwarf_jumps, "white dwarf assisted".to_string())
-            } else {
-                (normal_jumps, "direct".to_string())
+    /// Pick the best of the direct and boosted routes over a distance
+    ///
+    /// `destination` names the direct route's only leg. Routes shorter than
+    /// the boost thresholds only go direct, and with `require_boost` a direct
+    /// winner comes back as [`NO_BOOST_ROUTE`] with no jumps.
+    fn best_candidate(
+        &self,
+        total_distance: f64,
+        base_jump_range: f64,
+        options: &RouteOptions,
+        destination: &str,
+        highway: NeutronHighway,
+    ) -> RouteCandidate {
+        if total_distance < SAME_SYSTEM_DISTANCE_LY {
+            debug!("Already at the destination ({total_distance:.2} LY away)");
+            return RouteCandidate {
+                jumps: 0,
+                boosts: 0,
+                neutron_boosts: 0,
+                route_type: ALREADY_IN_SYSTEM_ROUTE.to_string(),
+                gaps: Vec::new(),
+                legs: Vec::new(),
             };
+        }
 
-        Ok(JumpResult {
-            jumps,
-            total_distance,
-            route_type,
+        // Calculate jumps for the different scenarios, in order of preference on ties
+        let mut candidates =
+            vec![self.direct_candidate(total_distance, base_jump_range, destination)];
+        // Shorter routes don't justify detouring to a boost star, and pilots
+        // may rule either kind out entirely
+        if options.use_white_dwarfs && self.estimate_white_dwarf_availability(total_distance) {
+            candidates.push(self.boosted_candidate(
+                total_distance,
+                base_jump_range,
+                StellarBoost::WhiteDwarf,
+            ));
+        }
+        if options.use_neutron_stars && self.estimate_neutron_availability(total_distance) {
+            match highway {
+                NeutronHighway::Through {
+                    from,
+                    to,
+                    neutron_stars,
+                } => candidates.extend(
+                    self.neutron_highway_candidate(
+                        from,
+                        to,
+                        base_jump_range,
+                        neutron_stars,
+                        options.detour_budget(total_distance),
+                        options
+                            .prefer_populated
+                            .then_some(options.populated_detour_jumps),
+                    ),
+                ),
+                NeutronHighway::Estimated => candidates.push(self.boosted_candidate(
+                    total_distance,
+                    base_jump_range,
+                    StellarBoost::NeutronStar,
+                )),
+            }
+        }
+
+        if options.boost_at_origin {
+            candidates.push(self.origin_boost_candidate(
+                total_distance,
+                base_jump_range,
+                destination,
+            ));
+        }
+
+        if options.premium_injections > 0 {
+            for candidate in &mut candidates {
+                self.spend_injections(candidate, base_jump_range, options.premium_injections);
+            }
+        }
+
+        // Determine the best route; a later candidate must be strictly better to win
+        let best = candidates
+            .into_iter()
+            .reduce(|best, candidate| {
+                let better = match options.optimize_for {
+                    RouteOptimization::Jumps => candidate.jumps < best.jumps,
+                    RouteOptimization::Time => {
+                        let seconds = |route: &RouteCandidate| {
+                            self.candidate_seconds(route, total_distance, base_jump_range, options)
+                        };
+                        seconds(&candidate) < seconds(&best)
+                    }
+                };
+                if better {
+                    candidate
+                } else {
+                    best
+                }
+            })
+            .expect("the direct route is always a candidate");
+
+        if best.boosts == 0 && options.require_boost {
+            debug!("No boosted route beats direct and require_boost is set");
+            return RouteCandidate {
+                jumps: 0,
+                boosts: 0,
+                neutron_boosts: 0,
+                route_type: NO_BOOST_ROUTE.to_string(),
+                gaps: Vec::new(),
+                legs: Vec::new(),
+            };
+        }
+        best
+    }
+
+    /// Estimated wall-clock time of a candidate route, in seconds
+    ///
+    /// Adds the scoop stops of [`Self::estimate_scoop_stops`] to
+    /// [`RouteCandidate::estimated_seconds`] when the tank capacity is known,
+    /// so a route burning more fuel pays for the extra stops it needs.
+    fn candidate_seconds(
+        &self,
+        candidate: &RouteCandidate,
+        total_distance: f64,
+        base_jump_range: f64,
+        options: &RouteOptions,
+    ) -> f64 {
+        let scoop_stops = options.fuel_capacity.map_or(0, |capacity| {
+            let fuel = self.route_fuel(total_distance, candidate.jumps, base_jump_range);
+            self.estimate_scoop_stops(fuel, capacity)
+        });
+
+        candidate.estimated_seconds(options) + scoop_stops as f64 * options.scoop_stop_seconds
+    }
+
+    /// Direct, white dwarf assisted and neutron highway routes between two
+    /// systems, side by side
+    ///
+    /// Unlike [`Self::calculate_route_with_options`], every scenario is worked
+    /// out whatever the distance and the options' boost star choices, so the
+    /// trade-offs can be shown; boosted routes are estimated. FSD injections
+    /// in the options are spent on each scenario alike.
+    ///
+    /// The `planned` route (e.g. through the neutron stars found along the
+    /// corridor) stands in for the estimate of its own kind, so the
+    /// comparison agrees with what `/route` plans.
+    pub fn compare_routes(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        base_jump_range: f64,
+        options: &RouteOptions,
+        planned: Option<&JumpResult>,
+    ) -> EdjcResult<RouteComparison> {
+        if !(base_jump_range > 0.0 && base_jump_range.is_finite()) {
+            return Err(EdjcError::InvalidJumpRange(base_jump_range));
+        }
+        let total_distance = self.calculate_distance(from, to);
+        let jump_range = self.effective_jump_range(base_jump_range, total_distance, options);
+
+        let describe = |result: JumpResult| RouteScenario {
+            estimated_time_minutes: self
+                .describe_route(result.clone(), base_jump_range)
+                .estimated_time_minutes,
+            route_type: result.route_type,
+            jumps: result.jumps,
+            supercharges: result.supercharges,
+        };
+        let scenario = |boost: StellarBoost, mut candidate: RouteCandidate| {
+            if let Some(planned) = planned.filter(|planned| route_boost(planned) == Some(boost)) {
+                return describe(planned.clone());
+            }
+            if options.premium_injections > 0 {
+                self.spend_injections(&mut candidate, jump_range, options.premium_injections);
+            }
+            describe(JumpResult {
+                jumps: candidate.jumps,
+                total_distance,
+                route_type: candidate.route_type,
+                from_system: from.name.clone(),
+                to_system: to.name.clone(),
+                legs: candidate.legs,
+                supercharges: candidate.boosts,
+                exact_jumps: None,
+            })
+        };
+
+        Ok(RouteComparison {
             from_system: from.name.clone(),
             to_system: to.name.clone(),
+            total_distance,
+            direct: scenario(
+                StellarBoost::None,
+                self.direct_candidate(total_distance, jump_range, &to.name),
+            ),
+            white_dwarf: scenario(
+                StellarBoost::WhiteDwarf,
+                self.boosted_candidate(total_distance, jump_range, StellarBoost::WhiteDwarf),
+            ),
+            neutron_highway: scenario(
+                StellarBoost::NeutronStar,
+                self.boosted_candidate(total_distance, jump_range, StellarBoost::NeutronStar),
+            ),
         })
     }
 
+    /// Average range of a jump over `distance` LY, given the laden range
+    ///
+    /// With `interpolate_range` and a known tank capacity, range grows
+    /// roughly linearly from laden towards the maximum as the tank drains.
+    /// A route burning a fraction of the tank only gets that fraction of the
+    /// way, and jumps cover the logarithmic mean of the range it starts and
+    /// ends with. Otherwise every jump is assumed laden, or with
+    /// `use_max_range` at the maximum range.
+    fn effective_jump_range(
+        &self,
+        laden_jump_range: f64,
+        distance: f64,
+        options: &RouteOptions,
+    ) -> f64 {
+        if options.use_max_range {
+            return options.planning_range(laden_jump_range);
+        }
+        let (Some(max), Some(capacity)) = (options.max_jump_range, options.fuel_capacity) else {
+            return laden_jump_range;
+        };
+        if !options.interpolate_range || max <= laden_jump_range {
+            return laden_jump_range;
+        }
+
+        let laden_jumps = self.calculate_jumps_direct(distance, laden_jump_range);
+        let fuel = self.route_fuel(distance, laden_jumps, laden_jump_range);
+        let burnt = (fuel / capacity).min(1.0);
+        let end_range = laden_jump_range + (max - laden_jump_range) * burnt;
+        if end_range <= laden_jump_range {
+            return laden_jump_range;
+        }
+        (end_range - laden_jump_range) / (end_range / laden_jump_range).ln()
+    }
+
     /// Calculate distance between two systems in 3D space
-    fn calculate_distance(&self, from: &SystemCoordinates, to: &SystemCoordinates) -> f64 {
+    pub fn calculate_distance(&self, from: &SystemCoordinates, to: &SystemCoordinates) -> f64 {
         let dx = to.x - from.x;
         let dy = to.y - from.y;
         let dz = to.z - from.z;
@@ -99,42 +648,393 @@ impl JumpCalculator {
         (dx * dx + dy * dy + dz * dz).sqrt()
     }
 
+    /// The unboosted route straight to `destination`
+    fn direct_candidate(
+        &self,
+        distance: f64,
+        jump_range: f64,
+        destination: &str,
+    ) -> RouteCandidate {
+        let jumps = self.calculate_jumps_direct(distance, jump_range);
+        RouteCandidate {
+            jumps,
+            boosts: 0,
+            neutron_boosts: 0,
+            route_type: "direct".to_string(),
+            gaps: vec![distance],
+            legs: vec![RouteLeg {
+                system: destination.to_string(),
+                boost_star: false,
+                jumps,
+                distance,
+            }],
+        }
+    }
+
+    /// The route straight to `destination` with the first jump supercharged
+    /// at the origin's neutron star, the rest unboosted
+    fn origin_boost_candidate(
+        &self,
+        distance: f64,
+        jump_range: f64,
+        destination: &str,
+    ) -> RouteCandidate {
+        let boosted_range = jump_range * self.neutron_multiplier;
+        let jumps = jumps_with_first_boost(distance, jump_range, self.neutron_multiplier);
+        RouteCandidate {
+            jumps,
+            boosts: 1,
+            // The star is right there, no finding or flying to it
+            neutron_boosts: 0,
+            route_type: ORIGIN_BOOST_ROUTE.to_string(),
+            gaps: vec![(distance - boosted_range).max(0.0)],
+            legs: vec![RouteLeg {
+                system: destination.to_string(),
+                boost_star: false,
+                jumps,
+                distance,
+            }],
+        }
+    }
+
+    /// The unboosted route straight to the destination as a
+    /// [`TIMED_OUT_ROUTE`], for when there was no time to look for better
+    pub fn timed_out_estimate(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        base_jump_range: f64,
+        options: &RouteOptions,
+    ) -> JumpResult {
+        let total_distance = self.calculate_distance(from, to);
+        let jump_range = self.effective_jump_range(base_jump_range, total_distance, options);
+        let candidate = self.direct_candidate(total_distance, jump_range, &to.name);
+        JumpResult {
+            jumps: candidate.jumps,
+            total_distance,
+            route_type: TIMED_OUT_ROUTE.to_string(),
+            from_system: from.name.clone(),
+            to_system: to.name.clone(),
+            exact_jumps: exact_direct_jumps(&candidate, total_distance, jump_range),
+            legs: candidate.legs,
+            supercharges: 0,
+        }
+    }
+
     /// Calculate jumps using direct routing (no boosts)
     fn calculate_jumps_direct(&self, distance: f64, jump_range: f64) -> u32 {
-        (distance / jump_range).ceil() as u32
+        jumps_for_distance(distance, jump_range, StellarBoost::None)
     }
 
-    /// Calculate jumps using stellar boost routing
-    fn calculate_jumps_with_boost(
+    /// Build the route candidate for a stellar boost type
+    fn boosted_candidate(
         &self,
         distance: f64,
         base_jump_range: f64,
         boost: StellarBoost,
-    ) -> u32 {
+    ) -> RouteCandidate {
         // Simplified calculation assuming we can find boost stars along the route
         // In reality, this would require pathfinding through actual stellar data
 
-        let boosted_range = base_jump_range * boost.multiplier();
-
         // Assume we need to make one extra jump to reach a boost star
         // and can use boosted jumps for most of the journey
         let boost_overhead = 1; // Extra jump to reach boost star
-        let boosted_jumps = ((distance * 0.8) / boosted_range).ceil() as u32;
-        let normal_jumps = ((distance * 0.2) / base_jump_range).ceil() as u32;
+        let (boosted_jumps, normal_jumps) =
+            boosted_jumps(distance, base_jump_range, self.multiplier(boost));
+
+        RouteCandidate {
+            jumps: boost_overhead + boosted_jumps + normal_jumps,
+            boosts: boosted_jumps,
+            neutron_boosts: match boost {
+                StellarBoost::NeutronStar => boosted_jumps,
+                _ => 0,
+            },
+            route_type: boost.label().to_string(),
+            gaps: vec![distance * 0.2],
+            // Estimated, so there are no actual waypoints to report
+            legs: Vec::new(),
+        }
+    }
+
+    /// Spend up to `budget` FSD injections on a candidate's gaps
+    ///
+    /// Each injection boosts the range of a single unboosted jump. They go to
+    /// the longest gap where one more premium injection still saves a jump;
+    /// injections that wouldn't save anything are kept. Each gap then uses the
+    /// cheapest grade that saves as many jumps as premium ones would.
+    fn spend_injections(&self, candidate: &mut RouteCandidate, base_jump_range: f64, budget: u32) {
+        let gaps = &candidate.gaps;
+        let mut longest_first: Vec<usize> = (0..gaps.len()).collect();
+        longest_first.sort_by(|&a, &b| gaps[b].total_cmp(&gaps[a]));
+        let mut spent = vec![0; gaps.len()];
+        let mut used = 0;
+
+        while used < budget {
+            let Some(&gap) = longest_first.iter().find(|&&gap| {
+                let jumps = |injections| {
+                    self.calculate_jumps_injected(
+                        gaps[gap],
+                        base_jump_range,
+                        injections,
+                        StellarBoost::SynthesisPremium,
+                    )
+                };
+                jumps(spent[gap] + 1) < jumps(spent[gap])
+            }) else {
+                break;
+            };
+            spent[gap] += 1;
+            used += 1;
+        }
+
+        if used == 0 {
+            return;
+        }
+
+        let mut per_grade = [0; INJECTION_GRADES.len()];
+        let saved: Vec<u32> = gaps
+            .iter()
+            .zip(&spent)
+            .map(|(&gap, &injections)| {
+                if injections == 0 {
+                    return 0;
+                }
+                let jumps =
+                    |grade| self.calculate_jumps_injected(gap, base_jump_range, injections, grade);
+                let best = jumps(StellarBoost::SynthesisPremium);
+                let cheapest = INJECTION_GRADES
+                    .iter()
+                    .position(|&grade| jumps(grade) == best)
+                    .expect("premium matches itself");
+                per_grade[cheapest] += injections;
+                self.calculate_jumps_direct(gap, base_jump_range) - best
+            })
+            .collect();
+        candidate.jumps -= saved.iter().sum::<u32>();
+        for (leg, saved) in candidate.legs.iter_mut().zip(saved) {
+            leg.jumps -= saved;
+        }
+
+        // Most powerful grade first: "with 2 premium boosts and 1 basic boost"
+        let grades: Vec<String> = INJECTION_GRADES
+            .iter()
+            .zip(per_grade)
+            .rev()
+            .filter(|&(_, count)| count > 0)
+            .map(|(grade, count)| match count {
+                1 => format!("1 {} boost", grade.label()),
+                n => format!("{n} {} boosts", grade.label()),
+            })
+            .collect();
+        candidate.route_type = format!("{} with {}", candidate.route_type, grades.join(" and "));
+    }
+
+    /// Jumps to cover a distance when up to `injections` of them are
+    /// injected with the given grade
+    fn calculate_jumps_injected(
+        &self,
+        distance: f64,
+        jump_range: f64,
+        injections: u32,
+        grade: StellarBoost,
+    ) -> u32 {
+        let injected_range = jump_range * grade.multiplier();
+        if distance <= injections as f64 * injected_range {
+            (distance / injected_range).ceil() as u32
+        } else {
+            injections
+                + self.calculate_jumps_direct(
+                    distance - injections as f64 * injected_range,
+                    jump_range,
+                )
+        }
+    }
+
+    /// Route greedily through known neutron stars
+    ///
+    /// From the current position, picks the neutron star that minimises the
+    /// estimated total (jumps to reach it, then finishing supercharged) and
+    /// takes it only if that beats finishing from where we are. Each step
+    /// strictly lowers the estimate, so the loop always ends. Returns `None`
+    /// if no neutron star helps.
+    ///
+    /// Stars more than `max_detour_ly` from the straight line between `from`
+    /// and `to` are never considered, however many jumps they would save.
+    ///
+    /// With `populated_detour_jumps`, a star in a populated system wins over
+    /// the others as long as the route through it is at most that many jumps
+    /// longer (and on ties whatever the number).
+    fn neutron_highway_candidate(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        base_jump_range: f64,
+        neutron_stars: &[SystemCoordinates],
+        max_detour_ly: f64,
+        populated_detour_jumps: Option<u32>,
+    ) -> Option<RouteCandidate> {
+        let hop = |from: &SystemCoordinates, to: &SystemCoordinates, supercharged: bool| {
+            self.calculate_jumps_from(
+                self.calculate_distance(from, to),
+                base_jump_range,
+                supercharged,
+            )
+        };
+
+        // Indices into `neutron_stars`, so each pass can score the whole
+        // corridor with one batch of distances
+        let mut remaining: Vec<usize> = (0..neutron_stars.len())
+            .filter(|&index| neutron_stars[index].is_neutron_boost())
+            .filter(|&index| {
+                let star = &neutron_stars[index];
+                let detour = distance_from_course(star, from, to);
+                if detour > max_detour_ly {
+                    debug!(
+                        "Skipping {}: {detour:.1} LY off course (limit {max_detour_ly:.1} LY)",
+                        star.name
+                    );
+                }
+                detour <= max_detour_ly
+            })
+            .collect();
+        let onward: Vec<u32> = distances_from(to, neutron_stars)
+            .into_iter()
+            .map(|distance| self.calculate_jumps_from(distance, base_jump_range, true))
+            .collect();
+        let mut position = from;
+        let mut supercharged = false;
+        let mut jumps = 0;
+        let mut boosts = 0;
+        let mut gaps = Vec::new();
+        let mut legs = Vec::new();
+        let mut fly =
+            |from: &SystemCoordinates, to: &SystemCoordinates, supercharged: bool, jumps: u32| {
+                // The supercharged jump itself can't be injected, only what follows it
+                let distance = self.calculate_distance(from, to);
+                let boosted_range = base_jump_range * self.multiplier(StellarBoost::NeutronStar);
+                gaps.push(if supercharged {
+                    (distance - boosted_range).max(0.0)
+                } else {
+                    distance
+                });
+                legs.push(RouteLeg {
+                    system: to.name.clone(),
+                    boost_star: to.is_neutron_boost(),
+                    jumps,
+                    distance,
+                });
+            };
+
+        loop {
+            let finish = hop(position, to, supercharged);
+            let reaches = distances_from(position, neutron_stars);
+            let next = remaining
+                .iter()
+                .enumerate()
+                .map(|(slot, &index)| {
+                    let reach =
+                        self.calculate_jumps_from(reaches[index], base_jump_range, supercharged);
+                    (slot, reach + onward[index], reach, &neutron_stars[index])
+                })
+                // Only stars that save jumps are worth the supercharge
+                .filter(|&(_, total, _, _)| total < finish)
+                .min_by_key(|&(_, total, reach, star)| {
+                    let allowance = match populated_detour_jumps {
+                        Some(detour) if self.is_populated(star) => Some(detour),
+                        _ => None,
+                    };
+                    // A populated star counts as up to `detour` jumps shorter
+                    // and wins ties; otherwise the nearest star leaves more
+                    // chances to boost later
+                    (
+                        total.saturating_sub(allowance.unwrap_or(0)),
+                        allowance.is_none(),
+                        total,
+                        reach,
+                    )
+                });
+
+            match next {
+                Some((slot, _, reach, _)) => {
+                    let star = &neutron_stars[remaining.remove(slot)];
+                    debug!("Supercharging at {}", star.name);
+                    fly(position, star, supercharged, reach);
+                    jumps += reach;
+                    boosts += 1;
+                    position = star;
+                    supercharged = true;
+                }
+                _ => {
+                    fly(position, to, supercharged, finish);
+                    jumps += finish;
+                    break;
+                }
+            }
+        }
+
+        (boosts > 0).then(|| RouteCandidate {
+            jumps,
+            boosts,
+            neutron_boosts: boosts,
+            route_type: neutron_route_type(boosts),
+            gaps,
+            legs,
+        })
+    }
 
-        boost_overhead + boosted_jumps + normal_jumps
+    /// Jumps to cover a distance, the first one supercharged if `supercharged`
+    fn calculate_jumps_from(&self, distance: f64, jump_range: f64, supercharged: bool) -> u32 {
+        if supercharged {
+            jumps_with_first_boost(
+                distance,
+                jump_range,
+                self.multiplier(StellarBoost::NeutronStar),
+            )
+        } else {
+            self.calculate_jumps_direct(distance, jump_range)
+        }
+    }
+
+    /// Warnings about reaching the target system (permits, hidden systems)
+    pub fn route_warnings(&self, to: &SystemCoordinates) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        // Permits only gate entering a system, so the origin never needs one
+        if let Some(note) = to.permit_note() {
+            warnings.push(format!("{} {}", to.name, note));
+        }
+        if let Some(note) = to.hidden_note() {
+            warnings.push(format!("{} {}", to.name, note));
+        }
+
+        warnings
+    }
+
+    /// Heuristic hints about a route that may affect how it can be flown
+    ///
+    /// Routes that mostly cross the galactic plane (a large Y traversal
+    /// relative to the total distance) tend to find fewer boost stars.
+    pub fn route_hints(&self, from: &SystemCoordinates, to: &SystemCoordinates) -> Vec<String> {
+        let mut hints = Vec::new();
+
+        let total_distance = self.calculate_distance(from, to);
+        if total_distance > 0.0 && (to.y - from.y).abs() / total_distance > STEEP_ROUTE_RATIO {
+            hints.push("steep vertical route; fewer boost stars likely".to_string());
+        }
+
+        hints
     }
 
     /// Estimate if a neutron highway route is available
     pub fn estimate_neutron_availability(&self, distance: f64) -> bool {
         // Neutron stars are relatively rare, so only worth it for longer routes
-        distance > 500.0
+        distance >= self.neutron_threshold_ly
     }
 
     /// Estimate if white dwarf assistance is worthwhile
     pub fn estimate_white_dwarf_availability(&self, distance: f64) -> bool {
         // White dwarfs are more common than neutron stars
-        distance > 150.0
+        distance >= self.white_dwarf_threshold_ly
     }
 
     /// Calculate fuel usage for a route (approximate)
@@ -147,66 +1047,383 @@ impl JumpCalculator {
         jumps as f64 * base_fuel_per_jump * range_factor
     }
 
+    /// Fuel for a route using the drive's fuel formula
+    ///
+    /// Assumes evenly spread jumps, none longer than the ship's jump range.
+    /// A supercharged jump burns the fuel of an unboosted jump of a quarter
+    /// of the distance, so boosted routes come out slightly high.
+    pub fn estimate_route_fuel(
+        &self,
+        fsd: &FsdProfile,
+        distance: f64,
+        jumps: u32,
+        jump_range: f64,
+    ) -> f64 {
+        if jumps == 0 {
+            return 0.0;
+        }
+
+        let jump_distance = (distance / jumps as f64).min(jump_range);
+        jumps as f64 * fsd.fuel_for_jump(jump_distance)
+    }
+
+    /// Fuel for a route, with the drive's fuel formula when the FSD is known
+    fn route_fuel(&self, distance: f64, jumps: u32, jump_range: f64) -> f64 {
+        match &self.fsd {
+            Some(fsd) => self.estimate_route_fuel(fsd, distance, jumps, jump_range),
+            None => self.estimate_fuel_usage(jumps, jump_range),
+        }
+    }
+
+    /// Count the fuel scoop stops needed to fly a corridor
+    ///
+    /// Starts with a full tank and only scoops (back to full) at a scoopable
+    /// star when the remaining fuel wouldn't reach the next scoopable star or
+    /// the end of the corridor. Returns `None` if the ship runs dry before it
+    /// can scoop.
+    pub fn count_scoop_stops(
+        &self,
+        hops: &[CorridorHop],
+        fuel_per_jump: f64,
+        fuel_capacity: f64,
+    ) -> Option<u32> {
+        let mut fuel = fuel_capacity;
+        let mut stops = 0;
+
+        for (index, hop) in hops.iter().enumerate() {
+            fuel -= fuel_per_jump;
+            if fuel < 0.0 {
+                debug!("Ran out of fuel on the way to {}", hop.system);
+                return None;
+            }
+
+            if hop.is_scoopable() {
+                // Jumps until the next chance to scoop (or the end of the corridor)
+                let jumps_ahead = hops[index + 1..]
+                    .iter()
+                    .position(CorridorHop::is_scoopable)
+                    .map_or(hops.len() - index - 1, |next| next + 1);
+
+                if fuel < jumps_ahead as f64 * fuel_per_jump {
+                    fuel = fuel_capacity;
+                    stops += 1;
+                }
+            }
+        }
+
+        Some(stops)
+    }
+
+    /// Rough number of fuel scoop stops to burn `fuel` tons of fuel
+    ///
+    /// Starts with a full tank and assumes every stop scoops back to full,
+    /// for routes without corridor data to place the stops on.
+    pub fn estimate_scoop_stops(&self, fuel: f64, fuel_capacity: f64) -> u32 {
+        ((fuel / fuel_capacity).ceil() as u32).saturating_sub(1)
+    }
+
     /// Get detailed route information
     pub fn get_route_details(
         &self,
         from: &SystemCoordinates,
         to: &SystemCoordinates,
         base_jump_range: f64,
-    ) -> Result<RouteDetails> {
+    ) -> EdjcResult<RouteDetails> {
         let result = self.calculate_route(from, to, base_jump_range)?;
-        let fuel_usage = self.estimate_fuel_usage(result.jumps, base_jump_range);
 
         Ok(RouteDetails {
-            result: result.clone(),
+            warnings: self.route_warnings(to),
+            ..self.describe_route(result, base_jump_range)
+        })
+    }
+
+    /// Fuel and time estimates for an already planned route
+    ///
+    /// The time covers every jump, the supercharges on the way (neutron ones
+    /// costing more) and, when the tank capacity is known, the scoop stops
+    /// needed to refuel.
+    pub fn describe_route(&self, result: JumpResult, base_jump_range: f64) -> RouteDetails {
+        let fuel_usage = self.route_fuel(result.total_distance, result.jumps, base_jump_range);
+        let scoop_stops = self
+            .fuel_capacity
+            .map(|capacity| self.estimate_scoop_stops(fuel_usage, capacity));
+
+        RouteDetails {
             estimated_fuel_usage: fuel_usage,
-            estimated_time_minutes: result.jumps as f64 * 2.0, // 2 minutes per jump average
+            estimated_time_minutes: self.time_model.minutes(
+                result.jumps,
+                result.supercharges,
+                neutron_supercharges(&result),
+                scoop_stops.unwrap_or(0),
+            ),
             can_use_neutron: self.estimate_neutron_availability(result.total_distance),
             can_use_white_dwarf: self.estimate_white_dwarf_availability(result.total_distance),
-        })
+            scoop_stops,
+            warnings: Vec::new(),
+            legs: result.legs.clone(),
+            result,
+        }
     }
 }
 
 /// Detailed route information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RouteDetails {
     pub result: JumpResult,
     pub estimated_fuel_usage: f64,
     pub estimated_time_minutes: f64,
     pub can_use_neutron: bool,
     pub can_use_white_dwarf: bool,
+    /// Fuel scoop stops needed on the way, when the tank capacity is known
+    pub scoop_stops: Option<u32>,
+    /// Things the rescuer should know before flying the route
+    pub warnings: Vec<String>,
+    /// Waypoints of the route in order; empty for estimated routes
+    pub legs: Vec<RouteLeg>,
 }
 
-impl Default for JumpCalculator {
-    fn default() -> Self {
-        Self::new()
+impl RouteDetails {
+    /// Format the route like [`JumpResult::format`], plus the `{fuel}` (tons)
+    /// and `{time}` (minutes) placeholders
+    pub fn format(&self, template: &str) -> String {
+        self.format_with(template, NumberFormat::Plain)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Format the route like [`Self::format`], writing numbers in a number format
+    pub fn format_with(&self, template: &str, numbers: NumberFormat) -> String {
+        self.format_rounded(template, numbers, JumpRounding::Ceil)
+    }
 
-    #[test]
-    fn test_distance_calculation() {
-        let calc = JumpCalculator::new();
+    /// Format the route like [`Self::format_with`], showing the jump count
+    /// with a rounding policy
+    pub fn format_rounded(
+        &self,
+        template: &str,
+        numbers: NumberFormat,
+        rounding: JumpRounding,
+    ) -> String {
+        self.result
+            .format_rounded(template, numbers, rounding)
+            .replace("{fuel}", &numbers.format(self.estimated_fuel_usage, 1))
+            .replace("{time}", &numbers.format(self.estimated_time_minutes, 0))
+    }
 
-        let sol = SystemCoordinates {
-            name: "Sol".to_string(),
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-            has_neutron_star: false,
-            has_white_dwarf: false,
-        };
+    /// Render the route with a `result_format` template, appending the
+    /// fuel and time estimates when enabled and not already in the template
+    pub fn render(
+        &self,
+        template: &str,
+        show_fuel: bool,
+        show_time: bool,
+        numbers: NumberFormat,
+        rounding: JumpRounding,
+    ) -> String {
+        format!(
+            "{}{}",
+            self.format_rounded(template, numbers, rounding),
+            self.estimates(
+                show_fuel && !template.contains("{fuel}"),
+                show_time && !template.contains("{time}"),
+                numbers,
+            )
+        )
+    }
 
-        let alpha_centauri = SystemCoordinates {
-            name: "Alpha Centauri".to_string(),
+    /// The " | ⛽ ~Nt fuel | ⏱️ ~N min" suffix for the enabled estimates,
+    /// with the scoop stops after the fuel when the route needs any
+    pub fn estimates(&self, show_fuel: bool, show_time: bool, numbers: NumberFormat) -> String {
+        let mut estimates = String::new();
+
+        if show_fuel {
+            estimates.push_str(&format!(
+                " | ⛽ ~{}t fuel",
+                numbers.format(self.estimated_fuel_usage, 1)
+            ));
+            if let Some(note) = self.scoop_note() {
+                estimates.push_str(&format!(" ({note})"));
+            }
+        }
+        if show_time {
+            estimates.push_str(&format!(
+                " | ⏱️ ~{} min",
+                numbers.format(self.estimated_time_minutes, 0)
+            ));
+        }
+
+        estimates
+    }
+
+    /// How many cases of this length could be handled per hour, given the
+    /// minutes spent on each case outside of jumping
+    pub fn cases_per_hour(&self, overhead_minutes: f64) -> f64 {
+        60.0 / (self.estimated_time_minutes + overhead_minutes)
+    }
+
+    /// One line per leg, numbered ("1. Neutron A: 2 jumps, 140.0 LY (supercharge)")
+    pub fn leg_breakdown(&self) -> Vec<String> {
+        self.legs
+            .iter()
+            .enumerate()
+            .map(|(index, leg)| format!("{}. {}", index + 1, leg.describe()))
+            .collect()
+    }
+
+    /// Fuel note for the response ("N scoop stops"), when the route needs any
+    pub fn scoop_note(&self) -> Option<String> {
+        self.scoop_stops
+            .filter(|&stops| stops > 0)
+            .map(|stops| match stops {
+                1 => "1 scoop stop".to_string(),
+                n => format!("{n} scoop stops"),
+            })
+    }
+}
+
+/// One route scenario of a [`RouteComparison`]
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteScenario {
+    /// Route type label, e.g. "white dwarf assisted"
+    pub route_type: String,
+    pub jumps: u32,
+    /// Jumps among those that are supercharged
+    pub supercharges: u32,
+    pub estimated_time_minutes: f64,
+}
+
+impl RouteScenario {
+    /// One-line summary, e.g. "neutron highway: 15 jumps (7 supercharged), ~37 min"
+    pub fn describe(&self, numbers: NumberFormat) -> String {
+        let supercharged = match self.supercharges {
+            0 => String::new(),
+            n => format!(" ({n} supercharged)"),
+        };
+        format!(
+            "{}: {} {}{supercharged}, ~{} min",
+            self.route_type,
+            self.jumps,
+            if self.jumps == 1 { "jump" } else { "jumps" },
+            numbers.format(self.estimated_time_minutes, 0)
+        )
+    }
+}
+
+/// The direct and boosted routes between two systems, for `/compare`
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteComparison {
+    pub from_system: String,
+    pub to_system: String,
+    pub total_distance: f64,
+    pub direct: RouteScenario,
+    pub white_dwarf: RouteScenario,
+    pub neutron_highway: RouteScenario,
+}
+
+impl RouteComparison {
+    /// The scenarios from least to most boosted: direct, white dwarf
+    /// assisted, neutron highway
+    pub fn scenarios(&self) -> [&RouteScenario; 3] {
+        [&self.direct, &self.white_dwarf, &self.neutron_highway]
+    }
+
+    /// The scenario taking the least time, the less boosted one on ties
+    pub fn fastest(&self) -> &RouteScenario {
+        self.scenarios()
+            .into_iter()
+            .reduce(|fastest, scenario| {
+                if scenario.estimated_time_minutes < fastest.estimated_time_minutes {
+                    scenario
+                } else {
+                    fastest
+                }
+            })
+            .expect("there are always three scenarios")
+    }
+}
+
+impl Default for JumpCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The kind of boost a route relies on, by its route type label; `None` for
+/// routes that fit no single kind (a supercharge at the start)
+fn route_boost(route: &JumpResult) -> Option<StellarBoost> {
+    [
+        StellarBoost::NeutronStar,
+        StellarBoost::WhiteDwarf,
+        StellarBoost::None,
+    ]
+    .into_iter()
+    .find(|boost| route.route_type.starts_with(boost.label()))
+}
+
+/// Supercharges of a route taken at neutron stars found along the way; a
+/// supercharge at the start doesn't need finding
+fn neutron_supercharges(route: &JumpResult) -> u32 {
+    match route_boost(route) {
+        Some(StellarBoost::NeutronStar) => route.supercharges,
+        _ => 0,
+    }
+}
+
+/// How far a point lies from the straight line between two systems: the
+/// perpendicular distance, or the distance to the nearer end for points
+/// beyond either end
+fn distance_from_course(
+    point: &SystemCoordinates,
+    from: &SystemCoordinates,
+    to: &SystemCoordinates,
+) -> f64 {
+    let course = [to.x - from.x, to.y - from.y, to.z - from.z];
+    let offset = [point.x - from.x, point.y - from.y, point.z - from.z];
+    let length_squared: f64 = course.iter().map(|c| c * c).sum();
+    let along = if length_squared > 0.0 {
+        let dot: f64 = course.iter().zip(offset).map(|(c, o)| c * o).sum();
+        (dot / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    course
+        .iter()
+        .zip(offset)
+        .map(|(c, o)| (o - c * along).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system_source::LocalSystemSource;
+
+    #[test]
+    fn test_distance_calculation() {
+        let calc = JumpCalculator::new();
+
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let alpha_centauri = SystemCoordinates {
+            name: "Alpha Centauri".to_string(),
             x: 3.03,
             y: 1.39,
             z: 0.16,
             has_neutron_star: false,
             has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
         };
 
         let distance = calc.calculate_distance(&sol, &alpha_centauri);
@@ -224,10 +1441,1361 @@ mod tests {
         assert_eq!(jumps, 4); // 99ly / 25ly = 3.96, rounded up to 4
     }
 
+    #[test]
+    fn test_jumps_for_distance() {
+        assert_eq!(jumps_for_distance(100.0, 25.0, StellarBoost::None), 4);
+        assert_eq!(jumps_for_distance(0.0, 25.0, StellarBoost::None), 0);
+        // One jump to the star, 800 LY at 200 LY, 200 LY at 50 LY
+        assert_eq!(
+            jumps_for_distance(1000.0, 50.0, StellarBoost::NeutronStar),
+            9
+        );
+        // 800 LY at 75 LY and 200 LY at 50 LY
+        assert_eq!(
+            jumps_for_distance(1000.0, 50.0, StellarBoost::WhiteDwarf),
+            16
+        );
+        // 50 LY injected, then 10 LY
+        assert_eq!(
+            jumps_for_distance(60.0, 25.0, StellarBoost::SynthesisPremium),
+            2
+        );
+        assert_eq!(
+            jumps_for_distance(20.0, 25.0, StellarBoost::SynthesisBasic),
+            1
+        );
+    }
+
+    #[test]
+    fn test_best_route_matches_calculate_route() {
+        let calc = JumpCalculator::new();
+        let sol = LocalSystemSource.get_system_coordinates("Sol").unwrap();
+        let colonia = LocalSystemSource.get_system_coordinates("Colonia").unwrap();
+        let distance = calc.calculate_distance(&sol, &colonia);
+
+        for options in [
+            RouteOptions::default(),
+            RouteOptions {
+                use_neutron_stars: false,
+                ..Default::default()
+            },
+            RouteOptions {
+                optimize_for: RouteOptimization::Time,
+                premium_injections: 3,
+                ..Default::default()
+            },
+        ] {
+            let route = calc
+                .calculate_route_with_options(&sol, &colonia, 30.0, &options)
+                .unwrap();
+            assert_eq!(
+                best_route(distance, 30.0, &options),
+                (route.jumps, route.route_type)
+            );
+        }
+        assert_eq!(
+            best_route(0.0, 30.0, &RouteOptions::default()),
+            (0, ALREADY_IN_SYSTEM_ROUTE.to_string())
+        );
+    }
+
+    #[test]
+    fn test_jumps_grow_with_distance_and_shrink_with_range() {
+        let boosts = [
+            StellarBoost::None,
+            StellarBoost::WhiteDwarf,
+            StellarBoost::NeutronStar,
+            StellarBoost::SynthesisBasic,
+            StellarBoost::SynthesisStandard,
+            StellarBoost::SynthesisPremium,
+        ];
+        let distances: Vec<f64> = (0..400).map(|i| i as f64 * 7.3).collect();
+        let ranges: Vec<f64> = (1..60).map(|i| i as f64 * 1.7).collect();
+        // With no thresholds every boost is always considered, so only the
+        // numbers change between neighbouring distances
+        let calc = JumpCalculator::new().with_boost_thresholds(0.0, 0.0);
+        let options = RouteOptions::default();
+
+        for &range in &ranges {
+            for pair in distances.windows(2) {
+                let (near, far) = (pair[0], pair[1]);
+                for boost in boosts {
+                    assert!(
+                        jumps_for_distance(near, range, boost)
+                            <= jumps_for_distance(far, range, boost),
+                        "{boost:?} at {range} LY: {near} LY vs {far} LY"
+                    );
+                }
+                assert!(
+                    calc.best_route(near, range, &options).0
+                        <= calc.best_route(far, range, &options).0,
+                    "best route at {range} LY: {near} LY vs {far} LY"
+                );
+            }
+        }
+        for &distance in &distances {
+            for pair in ranges.windows(2) {
+                let (short, long) = (pair[0], pair[1]);
+                for boost in boosts {
+                    assert!(
+                        jumps_for_distance(distance, short, boost)
+                            >= jumps_for_distance(distance, long, boost),
+                        "{boost:?} over {distance} LY: {short} LY vs {long} LY"
+                    );
+                }
+                assert!(
+                    best_route(distance, short, &options).0
+                        >= best_route(distance, long, &options).0,
+                    "best route over {distance} LY: {short} LY vs {long} LY"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_place_is_already_in_system() {
+        let calc = JumpCalculator::new();
+        let at = |name: &str, x: f64| SystemCoordinates {
+            name: name.to_string(),
+            x,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let result = calc
+            .calculate_route(&at("Sol", 0.0), &at("SOL", 0.0), 30.0)
+            .unwrap();
+        assert_eq!(result.jumps, 0);
+        assert_eq!(result.route_type, ALREADY_IN_SYSTEM_ROUTE);
+        assert!(result.legs.is_empty());
+
+        // Within EDSM's coordinate precision, even under another name
+        let result = calc
+            .calculate_route(&at("Sol", 0.0), &at("Sol A", 0.03125), 30.0)
+            .unwrap();
+        assert_eq!(result.route_type, ALREADY_IN_SYSTEM_ROUTE);
+        assert!(result.is_same_system());
+
+        // Less than a light year apart is still a jump
+        let result = calc
+            .calculate_route(&at("Sol", 0.0), &at("Nearby", 0.5), 30.0)
+            .unwrap();
+        assert_eq!(result.jumps, 1);
+        assert_eq!(result.route_type, "direct");
+        assert!(!result.is_same_system());
+    }
+
+    #[test]
+    fn test_require_boost_without_boost_stars() {
+        let calc = JumpCalculator::new();
+
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let nearby = SystemCoordinates {
+            name: "Alpha Centauri".to_string(),
+            x: 3.03,
+            y: 1.39,
+            z: 0.16,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let result = calc.calculate_route(&sol, &nearby, 30.0).unwrap();
+        assert_eq!(result.route_type, "direct");
+        assert_eq!(result.jumps, 1);
+
+        let options = RouteOptions {
+            require_boost: true,
+            ..Default::default()
+        };
+        let result = calc
+            .calculate_route_with_options(&sol, &nearby, 30.0, &options)
+            .unwrap();
+        assert_eq!(result.route_type, NO_BOOST_ROUTE);
+        assert_eq!(result.jumps, 0);
+    }
+
+    #[test]
+    fn test_disabled_boost_stars() {
+        let calc = JumpCalculator::new();
+        let at = |name: &str, z: f64| SystemCoordinates {
+            name: name.to_string(),
+            x: 0.0,
+            y: 0.0,
+            z,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let (sol, colonia) = (at("Sol", 0.0), at("Colonia", 22000.0));
+        let route = |use_neutron_stars, use_white_dwarfs| {
+            let options = RouteOptions {
+                use_neutron_stars,
+                use_white_dwarfs,
+                ..Default::default()
+            };
+            calc.calculate_route_with_options(&sol, &colonia, 50.0, &options)
+                .unwrap()
+        };
+
+        let both = route(true, true);
+        assert_eq!(both.route_type, "neutron highway");
+
+        // Neutron stars would be shorter, but are ruled out
+        let no_neutron = route(false, true);
+        assert_eq!(no_neutron.route_type, "white dwarf assisted");
+        assert!(no_neutron.jumps > both.jumps);
+
+        let neither = route(false, false);
+        assert_eq!(neither.route_type, "direct");
+        assert_eq!(neither.jumps, 440);
+        assert_eq!(neither.supercharges, 0);
+    }
+
+    #[test]
+    fn test_time_optimization_prefers_direct() {
+        let calc = JumpCalculator::new().with_boost_thresholds(0.0, 0.0);
+
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 200.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let by_jumps = RouteOptions {
+            boost_penalty_seconds: 300.0,
+            ..Default::default()
+        };
+        let result = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &by_jumps)
+            .unwrap();
+        assert_eq!(result.route_type, "neutron highway");
+        assert_eq!(result.jumps, 5);
+        assert_eq!(result.exact_jumps, None);
+
+        let by_time = RouteOptions {
+            optimize_for: RouteOptimization::Time,
+            ..by_jumps
+        };
+        let result = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &by_time)
+            .unwrap();
+        assert_eq!(result.route_type, "direct");
+        assert_eq!(result.jumps, 7);
+        // 200 LY at 30 LY is 6.67 jumps
+        let exact = result.exact_jumps.unwrap();
+        assert!((exact - 200.0 / 30.0).abs() < 1e-9, "{exact}");
+        let rounded = |rounding| result.format_rounded("{jumps}", NumberFormat::Plain, rounding);
+        assert_eq!(rounded(JumpRounding::Ceil), "7");
+        assert_eq!(rounded(JumpRounding::Nearest), "7");
+        assert_eq!(rounded(JumpRounding::Range), "7");
+    }
+
+    #[test]
+    fn test_boost_at_origin() {
+        let calc = JumpCalculator::new();
+        let at = |name: &str, z: f64| SystemCoordinates {
+            name: name.to_string(),
+            x: 0.0,
+            y: 0.0,
+            z,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let (case, home) = (at("Case", 0.0), at("Home", 200.0));
+        let boosted = RouteOptions {
+            boost_at_origin: true,
+            ..Default::default()
+        };
+
+        let plain = calc.calculate_route(&case, &home, 30.0).unwrap();
+        assert_eq!((plain.jumps, plain.route_type.as_str()), (7, "direct"));
+
+        // 120 LY on the first jump, then 80 LY in three
+        let result = calc
+            .calculate_route_with_options(&case, &home, 30.0, &boosted)
+            .unwrap();
+        assert_eq!(result.route_type, ORIGIN_BOOST_ROUTE);
+        assert_eq!((result.jumps, result.supercharges), (4, 1));
+        assert_eq!(result.exact_jumps, None);
+
+        // Not worth it when the destination is a single jump away anyway
+        let near = at("Near", 25.0);
+        let result = calc
+            .calculate_route_with_options(&case, &near, 30.0, &boosted)
+            .unwrap();
+        assert_eq!((result.jumps, result.route_type.as_str()), (1, "direct"));
+    }
+
+    #[test]
+    fn test_neutron_penalty_keeps_short_routes_direct() {
+        // No distance thresholds, so only the time weighing decides
+        let calc = JumpCalculator::new().with_boost_thresholds(0.0, 0.0);
+        let sol = LocalSystemSource.get_system_coordinates("Sol").unwrap();
+        let at = |z: f64| SystemCoordinates {
+            name: "Target".to_string(),
+            z,
+            ..sol.clone()
+        };
+        let by_time = RouteOptions {
+            optimize_for: RouteOptimization::Time,
+            ..Default::default()
+        };
+
+        // Two supercharges would save two jumps, but not the time they take
+        let short = calc
+            .calculate_route_with_options(&sol, &at(200.0), 30.0, &by_time)
+            .unwrap();
+        assert_eq!((short.route_type.as_str(), short.jumps), ("direct", 7));
+
+        let long = calc
+            .calculate_route_with_options(&sol, &at(5000.0), 30.0, &by_time)
+            .unwrap();
+        assert_eq!(
+            (long.route_type.as_str(), long.jumps),
+            ("neutron highway", 69)
+        );
+
+        // Without the neutron penalty, the nominally shorter route wins
+        let unweighted = RouteOptions {
+            neutron_penalty_seconds: 0.0,
+            ..by_time
+        };
+        let short = calc
+            .calculate_route_with_options(&sol, &at(200.0), 30.0, &unweighted)
+            .unwrap();
+        assert_eq!(
+            (short.route_type.as_str(), short.jumps),
+            ("neutron highway", 5)
+        );
+    }
+
+    #[test]
+    fn test_time_optimization_counts_scoop_stops() {
+        let calc = JumpCalculator::new().with_boost_thresholds(0.0, 0.0);
+        let sol = LocalSystemSource.get_system_coordinates("Sol").unwrap();
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            z: 200.0,
+            ..sol.clone()
+        };
+        let by_time = RouteOptions {
+            optimize_for: RouteOptimization::Time,
+            ..Default::default()
+        };
+
+        // Without a tank size the neutron penalty keeps the route direct
+        let result = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &by_time)
+            .unwrap();
+        assert_eq!((result.route_type.as_str(), result.jumps), ("direct", 7));
+
+        // 21 t direct needs two refills of an 8 t tank, the 15 t highway one,
+        // and the stop saved outweighs the supercharges
+        let small_tank = RouteOptions {
+            fuel_capacity: Some(8.0),
+            ..by_time
+        };
+        let result = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &small_tank)
+            .unwrap();
+        assert_eq!(
+            (result.route_type.as_str(), result.jumps),
+            ("neutron highway", 5)
+        );
+
+        // Free stops don't tip the balance
+        let free_stops = RouteOptions {
+            scoop_stop_seconds: 0.0,
+            ..small_tank.clone()
+        };
+        let result = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &free_stops)
+            .unwrap();
+        assert_eq!((result.route_type.as_str(), result.jumps), ("direct", 7));
+
+        // A big tank needs no stops either way
+        let big_tank = RouteOptions {
+            fuel_capacity: Some(32.0),
+            ..small_tank
+        };
+        let result = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &big_tank)
+            .unwrap();
+        assert_eq!((result.route_type.as_str(), result.jumps), ("direct", 7));
+    }
+
+    #[test]
+    fn test_premium_injections_stay_within_budget() {
+        let calc = JumpCalculator::new().with_boost_thresholds(0.0, 0.0);
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            z: 200.0,
+            ..sol.clone()
+        };
+
+        // Without injections: 5 jumps along the neutron highway
+        let result = calc.calculate_route(&sol, &target, 30.0).unwrap();
+        assert_eq!(result.jumps, 5);
+
+        // One injection closes the highway's unboosted tail
+        let options = RouteOptions {
+            premium_injections: 1,
+            ..Default::default()
+        };
+        let result = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &options)
+            .unwrap();
+        // A standard injection (45 LY) covers the 40 LY tail as well as a premium one
+        assert_eq!(result.route_type, "neutron highway with 1 standard boost");
+        assert_eq!(result.jumps, 4);
+
+        // A fourth injection wouldn't save a jump on the direct route, so it's kept
+        for budget in [3, 10] {
+            let options = RouteOptions {
+                premium_injections: budget,
+                ..Default::default()
+            };
+            let result = calc
+                .calculate_route_with_options(&sol, &target, 30.0, &options)
+                .unwrap();
+            assert_eq!(result.route_type, "direct with 3 premium boosts");
+            assert_eq!(result.jumps, 4);
+        }
+    }
+
+    #[test]
+    fn test_injections_go_to_the_longest_gap() {
+        let calc = JumpCalculator::new();
+        let mut candidate = RouteCandidate {
+            jumps: 5,
+            boosts: 1,
+            neutron_boosts: 1,
+            route_type: "neutron highway (1 boost)".to_string(),
+            gaps: vec![15.0, 50.0],
+            legs: vec![
+                RouteLeg {
+                    system: "Neutron".to_string(),
+                    boost_star: true,
+                    jumps: 2,
+                    distance: 15.0,
+                },
+                RouteLeg {
+                    system: "Target".to_string(),
+                    boost_star: false,
+                    jumps: 3,
+                    distance: 130.0,
+                },
+            ],
+        };
+
+        calc.spend_injections(&mut candidate, 20.0, 1);
+        // 50 LY takes 3 jumps, or 2 with one injected; 15 LY takes 1 either way
+        assert_eq!(candidate.jumps, 4);
+        assert_eq!(candidate.legs[0].jumps, 2);
+        assert_eq!(candidate.legs[1].jumps, 2);
+        // A standard injection (30 LY) saves that jump as well as a premium one
+        assert_eq!(
+            candidate.route_type,
+            "neutron highway (1 boost) with 1 standard boost"
+        );
+        let premium = StellarBoost::SynthesisPremium;
+        assert_eq!(calc.calculate_jumps_injected(50.0, 20.0, 2, premium), 2);
+        assert_eq!(calc.calculate_jumps_injected(50.0, 20.0, 0, premium), 3);
+        assert_eq!(
+            calc.calculate_jumps_injected(50.0, 20.0, 1, StellarBoost::SynthesisBasic),
+            3
+        );
+
+        // Mixed grades when gaps need different ones
+        let mut candidate = RouteCandidate {
+            jumps: 6,
+            boosts: 0,
+            neutron_boosts: 0,
+            route_type: "direct".to_string(),
+            gaps: vec![80.0, 25.0],
+            legs: Vec::new(),
+        };
+        calc.spend_injections(&mut candidate, 20.0, 3);
+        // 80 LY: two premium injections make it two jumps; 25 LY: one basic
+        assert_eq!(candidate.jumps, 6 - 2 - 1);
+        assert_eq!(
+            candidate.route_type,
+            "direct with 2 premium boosts and 1 basic boost"
+        );
+    }
+
+    #[test]
+    fn test_legs_reconstruct_the_route() {
+        let calc = JumpCalculator::new().with_boost_thresholds(0.0, 0.0);
+        let system = |name: &str, x: f64, has_neutron_star: bool| SystemCoordinates {
+            name: name.to_string(),
+            x,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let start = system("Start", 0.0, false);
+        let finish = system("Finish", 260.0, false);
+        let neutrons = [
+            system("A", 15.0, true),
+            system("B", 95.0, true),
+            system("C", 175.0, true),
+        ];
+
+        let result = calc
+            .calculate_route_with_corridor(
+                &start,
+                &finish,
+                20.0,
+                &RouteOptions::default(),
+                Some(&neutrons),
+            )
+            .unwrap();
+        let systems: Vec<&str> = result.legs.iter().map(|leg| leg.system.as_str()).collect();
+        assert_eq!(systems, ["A", "B", "C", "Finish"]);
+        assert!(result.legs[..3].iter().all(|leg| leg.boost_star));
+        assert_eq!(result.supercharges, 3);
+        assert_eq!(
+            result.legs.iter().map(|leg| leg.jumps).sum::<u32>(),
+            result.jumps
+        );
+        let distance: f64 = result.legs.iter().map(|leg| leg.distance).sum();
+        assert!((distance - result.total_distance).abs() < 1e-9);
+
+        let details = calc.describe_route(result, 20.0);
+        assert_eq!(
+            details.leg_breakdown()[0],
+            "1. A: 1 jump, 15.0 LY (supercharge)"
+        );
+
+        // A direct route is a single leg to the destination
+        let nearby = system("Nearby", 50.0, false);
+        let result = calc
+            .calculate_route_with_corridor(
+                &start,
+                &nearby,
+                20.0,
+                &RouteOptions::default(),
+                Some(&[]),
+            )
+            .unwrap();
+        assert_eq!(result.route_type, "direct");
+        assert_eq!(result.legs.len(), 1);
+        assert_eq!(result.legs[0].jumps, result.jumps);
+        assert_eq!(result.legs[0].distance, result.total_distance);
+    }
+
+    #[test]
+    fn test_prefer_populated_waypoints() {
+        let system = |name: &str, x: f64, has_neutron_star: bool| SystemCoordinates {
+            name: name.to_string(),
+            x,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let start = system("Start", 0.0, false);
+        let finish = system("Finish", 100.0, false);
+        let remote = system("Remote", 15.0, true);
+        // As good as Remote (3 jumps in all), just a jump further in
+        let outpost = system("Outpost", 25.0, true);
+        // A jump worse than Remote
+        let colony = system("Colony", 45.0, true);
+        let calc = JumpCalculator::new()
+            .with_boost_thresholds(0.0, 0.0)
+            .with_populated_systems(&[outpost.clone(), colony.clone()]);
+        let route = |neutrons: &[SystemCoordinates], options: &RouteOptions| {
+            let result = calc
+                .calculate_route_with_corridor(&start, &finish, 20.0, options, Some(neutrons))
+                .unwrap();
+            (result.legs[0].system.clone(), result.jumps)
+        };
+        let indifferent = RouteOptions::default();
+        let ties_only = RouteOptions {
+            prefer_populated: true,
+            populated_detour_jumps: 0,
+            ..Default::default()
+        };
+        let one_jump = RouteOptions {
+            prefer_populated: true,
+            populated_detour_jumps: 1,
+            ..Default::default()
+        };
+
+        // Between equally good stars, the populated one wins
+        let tied = [remote.clone(), outpost];
+        assert_eq!(route(&tied, &indifferent), ("Remote".to_string(), 3));
+        assert_eq!(route(&tied, &ties_only), ("Outpost".to_string(), 3));
+
+        // A worse one only within populated_detour_jumps
+        let apart = [remote, colony];
+        assert_eq!(route(&apart, &ties_only), ("Remote".to_string(), 3));
+        assert_eq!(route(&apart, &one_jump), ("Colony".to_string(), 4));
+
+        // Without knowing what's populated there's nothing to prefer
+        let result = JumpCalculator::new()
+            .with_boost_thresholds(0.0, 0.0)
+            .calculate_route_with_corridor(&start, &finish, 20.0, &one_jump, Some(&apart))
+            .unwrap();
+        assert_eq!(result.legs[0].system, "Remote");
+    }
+
+    #[test]
+    fn test_detour_budget() {
+        // Only the neutron highway, with boosts so strong any star would pay off
+        let calc = JumpCalculator::new()
+            .with_boost_thresholds(0.0, f64::INFINITY)
+            .with_multipliers(100.0, 1.5);
+        let star = |name: &str, x: f64, y: f64| SystemCoordinates {
+            name: name.to_string(),
+            x,
+            y,
+            z: 0.0,
+            has_neutron_star: true,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let start = SystemCoordinates {
+            has_neutron_star: false,
+            ..star("Start", 0.0, 0.0)
+        };
+        let finish = SystemCoordinates {
+            has_neutron_star: false,
+            ..star("Finish", 1000.0, 0.0)
+        };
+
+        assert_eq!(
+            distance_from_course(&star("On", 400.0, 0.0), &start, &finish),
+            0.0
+        );
+        assert_eq!(
+            distance_from_course(&star("Off", 400.0, 30.0), &start, &finish),
+            30.0
+        );
+        // Behind the start counts from the start itself
+        assert_eq!(
+            distance_from_course(&star("Back", -40.0, 30.0), &start, &finish),
+            50.0
+        );
+
+        let route = |stars: &[SystemCoordinates], options: &RouteOptions| {
+            let result = calc
+                .calculate_route_with_corridor(&start, &finish, 20.0, options, Some(stars))
+                .unwrap();
+            result
+                .legs
+                .iter()
+                .filter(|leg| leg.boost_star)
+                .map(|leg| leg.system.clone())
+                .collect::<Vec<_>>()
+        };
+        let defaults = RouteOptions::default();
+
+        // 40 LY off course is within the default 50 LY budget
+        assert_eq!(route(&[star("Near", 100.0, 40.0)], &defaults), ["Near"]);
+        // 300 LY sideways is not, however many jumps the boost would save
+        assert!(route(&[star("Far", 100.0, 300.0)], &defaults).is_empty());
+        let tight = RouteOptions {
+            max_detour_ly: 20.0,
+            ..Default::default()
+        };
+        assert!(route(&[star("Near", 100.0, 40.0)], &tight).is_empty());
+        assert_eq!(route(&[star("Close", 100.0, 10.0)], &tight), ["Close"]);
+
+        // A percentage of the route's length widens the budget on long routes
+        let relative = RouteOptions {
+            max_detour_percent: 35.0,
+            ..tight.clone()
+        };
+        assert_eq!(relative.detour_budget(1000.0), 350.0);
+        assert_eq!(relative.detour_budget(10.0), 20.0);
+        assert_eq!(route(&[star("Far", 100.0, 300.0)], &relative), ["Far"]);
+    }
+
+    #[test]
+    fn test_interpolated_jump_range() {
+        let calc = JumpCalculator::new();
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            x: 1000.0,
+            ..sol.clone()
+        };
+        // An empty corridor leaves out the estimated neutron highway
+        let no_neutrons: &[SystemCoordinates] = &[];
+        let jumps = |options: &RouteOptions| {
+            calc.calculate_route_with_corridor(&sol, &target, 30.0, options, Some(no_neutrons))
+                .unwrap()
+                .jumps
+        };
+
+        let flat = RouteOptions {
+            max_jump_range: Some(40.0),
+            fuel_capacity: Some(32.0),
+            ..Default::default()
+        };
+        let interpolated = RouteOptions {
+            interpolate_range: true,
+            ..flat.clone()
+        };
+
+        // 30 LY laden, 40 LY empty, and the ~102t burnt empties the tank
+        // again and again: jumps average ~34.8 LY instead of 30, so the white
+        // dwarf assisted route drops from 1 + 18 + 7 to 1 + 16 + 6 jumps
+        assert_eq!(jumps(&flat), 26);
+        assert_eq!(jumps(&interpolated), 23);
+        let range = calc.effective_jump_range(30.0, 1000.0, &interpolated);
+        assert!((range - 34.76).abs() < 0.01, "{range}");
+
+        // A short hop on a big tank barely lightens the ship: 4 jumps burn
+        // 12t of 120t, so the range only grows from 30 to 31 LY
+        let big_tank = RouteOptions {
+            fuel_capacity: Some(120.0),
+            ..interpolated.clone()
+        };
+        let range = calc.effective_jump_range(30.0, 100.0, &big_tank);
+        assert!((range - 30.5).abs() < 0.01, "{range}");
+        let short = Some(&[][..]);
+        let hop = SystemCoordinates {
+            name: "Hop".to_string(),
+            x: 100.0,
+            ..sol.clone()
+        };
+        let result = calc
+            .calculate_route_with_corridor(&sol, &hop, 30.0, &big_tank, short)
+            .unwrap();
+        assert_eq!(result.jumps, 4);
+
+        // Without a larger maximum range, or the tank size to tell how much
+        // fuel burns, there is nothing to interpolate
+        let no_max = RouteOptions {
+            interpolate_range: true,
+            fuel_capacity: Some(32.0),
+            ..Default::default()
+        };
+        assert_eq!(jumps(&no_max), 26);
+        let no_tank = RouteOptions {
+            fuel_capacity: None,
+            ..interpolated.clone()
+        };
+        assert_eq!(jumps(&no_tank), 26);
+
+        let flat = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &flat)
+            .unwrap();
+        let interpolated = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &interpolated)
+            .unwrap();
+        assert!(interpolated.jumps < flat.jumps);
+    }
+
+    #[test]
+    fn test_invalid_jump_range() {
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            z: 200.0,
+            ..sol.clone()
+        };
+
+        let calc = JumpCalculator::new();
+        for range in [0.0, -30.0, f64::NAN, f64::INFINITY] {
+            let err = calc.calculate_route(&sol, &target, range).unwrap_err();
+            assert!(matches!(err, EdjcError::InvalidJumpRange(_)), "{err}");
+        }
+        let err = calc.get_route_details(&sol, &target, 0.0).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid jump range: 0");
+        assert_eq!(err.hint(), Some("jump ranges must be above 0 LY"));
+    }
+
+    #[test]
+    fn test_boost_thresholds() {
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            z: 200.0,
+            ..sol.clone()
+        };
+
+        // 200 LY is below the default neutron threshold
+        let calc = JumpCalculator::new();
+        assert!(!calc.estimate_neutron_availability(200.0));
+        assert!(calc.estimate_white_dwarf_availability(200.0));
+        let result = calc.calculate_route(&sol, &target, 30.0).unwrap();
+        assert_eq!(result.route_type, "direct");
+        assert_eq!(result.jumps, 7);
+
+        // Lowering the threshold brings the neutron highway back
+        let calc = JumpCalculator::new().with_boost_thresholds(200.0, 150.0);
+        assert!(calc.estimate_neutron_availability(200.0));
+        let result = calc.calculate_route(&sol, &target, 30.0).unwrap();
+        assert_eq!(result.route_type, "neutron highway");
+        assert_eq!(result.jumps, 5);
+
+        // Below both thresholds nothing is boosted, even when asked to
+        let calc = JumpCalculator::new().with_boost_thresholds(1000.0, 1000.0);
+        let options = RouteOptions {
+            require_boost: true,
+            ..Default::default()
+        };
+        let result = calc
+            .calculate_route_with_options(&sol, &target, 30.0, &options)
+            .unwrap();
+        assert_eq!(result.route_type, NO_BOOST_ROUTE);
+        let details = calc.get_route_details(&sol, &target, 30.0).unwrap();
+        assert!(!details.can_use_neutron);
+        assert!(!details.can_use_white_dwarf);
+    }
+
+    #[test]
+    fn test_custom_neutron_multiplier() {
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            z: 200.0,
+            ..sol.clone()
+        };
+
+        let canonical = JumpCalculator::new()
+            .with_boost_thresholds(0.0, 0.0)
+            .calculate_route(&sol, &target, 30.0)
+            .unwrap();
+        assert_eq!(canonical.route_type, "neutron highway");
+        assert_eq!(canonical.jumps, 5);
+
+        // A conservative 2x boost needs more jumps, but still beats direct (7)
+        let conservative = JumpCalculator::new()
+            .with_multipliers(2.0, 1.5)
+            .with_boost_thresholds(0.0, 0.0)
+            .calculate_route(&sol, &target, 30.0)
+            .unwrap();
+        assert_eq!(conservative.route_type, "neutron highway");
+        assert_eq!(conservative.jumps, 6);
+
+        // Supercharged hops along a corridor use it too
+        let calc = JumpCalculator::new().with_multipliers(2.0, 1.5);
+        assert_eq!(calc.calculate_jumps_from(100.0, 30.0, true), 3);
+        assert_eq!(
+            JumpCalculator::new().calculate_jumps_from(100.0, 30.0, true),
+            1
+        );
+    }
+
+    #[test]
+    fn test_scoop_stops_over_corridor() {
+        let calc = JumpCalculator::new();
+        let hop = |system: &str, class: &str| CorridorHop {
+            system: system.to_string(),
+            star_class: Some(class.to_string()),
+        };
+
+        let corridor = vec![
+            hop("A", "K (Yellow-Orange) Star"),
+            hop("B", "Neutron Star"),
+            hop("C", "L (Brown dwarf) Star"),
+            hop("D", "M (Red dwarf) Star"),
+            hop("E", "White Dwarf (DA) Star"),
+            hop("F", "T (Brown dwarf) Star"),
+            hop("G", "G (White-Yellow) Star"),
+            hop("H", "Neutron Star"),
+        ];
+
+        // 4 jumps per tank: A reaches D, D must scoop to reach G, G reaches the end
+        assert_eq!(calc.count_scoop_stops(&corridor, 2.0, 8.0), Some(1));
+        // 3 jumps per tank: scoop at every scoopable star
+        assert_eq!(calc.count_scoop_stops(&corridor, 2.0, 6.0), Some(3));
+        // Plenty of fuel: never needs to scoop
+        assert_eq!(calc.count_scoop_stops(&corridor, 1.0, 16.0), Some(0));
+        // Only 2 jumps per tank: stranded between A and D
+        assert_eq!(calc.count_scoop_stops(&corridor, 2.0, 4.0), None);
+
+        assert_eq!(calc.estimate_scoop_stops(9.0, 32.0), 0);
+        assert_eq!(calc.estimate_scoop_stops(32.0, 32.0), 0);
+        assert_eq!(calc.estimate_scoop_stops(9.0, 4.0), 2);
+    }
+
+    #[test]
+    fn test_route_fuel_uses_fsd_profile() {
+        let fsd = FsdProfile::new(5, 'A', 420.0).unwrap();
+        let calc = JumpCalculator::new().with_fsd_profile(Some(fsd));
+
+        // Five 20 LY jumps
+        let fuel = calc.estimate_route_fuel(&fsd, 100.0, 5, 20.0);
+        assert!((fuel - 5.0 * fsd.fuel_for_jump(20.0)).abs() < 1e-9);
+        assert_eq!(calc.estimate_route_fuel(&fsd, 0.0, 0, 20.0), 0.0);
+    }
+
     #[test]
     fn test_stellar_boost_multipliers() {
         assert_eq!(StellarBoost::None.multiplier(), 1.0);
         assert_eq!(StellarBoost::WhiteDwarf.multiplier(), 1.5);
         assert_eq!(StellarBoost::NeutronStar.multiplier(), 4.0);
+        assert_eq!(StellarBoost::SynthesisBasic.multiplier(), 1.25);
+        assert_eq!(StellarBoost::SynthesisStandard.multiplier(), 1.5);
+        assert_eq!(StellarBoost::SynthesisPremium.multiplier(), 2.0);
+    }
+
+    #[test]
+    fn test_fuel_and_time_placeholders() {
+        let calc = JumpCalculator::new();
+        let details = calc.describe_route(
+            JumpResult {
+                jumps: 5,
+                total_distance: 123.5,
+                route_type: "neutron highway".to_string(),
+                from_system: "Sol".to_string(),
+                to_system: "Colonia".to_string(),
+                legs: Vec::new(),
+                supercharges: 0,
+                exact_jumps: None,
+            },
+            20.0,
+        );
+        assert_eq!(details.estimated_fuel_usage, 10.0);
+        assert_eq!(details.estimated_time_minutes, 10.0);
+
+        let template = "{jumps} jumps to {system}, {fuel}t in ~{time} min";
+        assert_eq!(
+            details.format(template),
+            "5 jumps to Colonia, 10.0t in ~10 min"
+        );
+        // Placed estimates aren't appended a second time
+        assert_eq!(
+            details.render(
+                template,
+                true,
+                true,
+                NumberFormat::Plain,
+                JumpRounding::Ceil
+            ),
+            details.format(template)
+        );
+        assert_eq!(
+            details.render(
+                "{jumps} jumps, {fuel}t",
+                true,
+                true,
+                NumberFormat::Plain,
+                JumpRounding::Ceil
+            ),
+            "5 jumps, 10.0t | ⏱️ ~10 min"
+        );
+        assert_eq!(
+            details.render(
+                "{jumps} jumps",
+                false,
+                false,
+                NumberFormat::Plain,
+                JumpRounding::Ceil
+            ),
+            "5 jumps"
+        );
+        assert_eq!(
+            details.render(
+                "{distance} LY",
+                true,
+                false,
+                NumberFormat::De,
+                JumpRounding::Ceil
+            ),
+            "123,5 LY | ⛽ ~10,0t fuel"
+        );
+    }
+
+    #[test]
+    fn test_cases_per_hour() {
+        let calc = JumpCalculator::new().with_boost_thresholds(0.0, 0.0);
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            x: 100.0,
+            ..sol.clone()
+        };
+
+        // 100 LY at 20 LY range: 3 neutron highway jumps, 2 minutes each,
+        // and two and a half for the neutron supercharge
+        let details = calc.get_route_details(&sol, &target, 20.0).unwrap();
+        assert_eq!(details.result.jumps, 3);
+        assert_eq!(details.estimated_time_minutes, 8.5);
+        assert_eq!(details.cases_per_hour(11.5), 3.0);
+        assert_eq!(details.cases_per_hour(51.5), 1.0);
+    }
+
+    #[test]
+    fn test_time_estimate_counts_supercharges_and_scooping() {
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            x: 1000.0,
+            ..sol.clone()
+        };
+
+        // Without boosts, 34 jumps at 2 minutes each
+        let direct = JumpCalculator::new()
+            .with_multipliers(1.0, 1.0)
+            .get_route_details(&sol, &target, 30.0)
+            .unwrap();
+        assert_eq!(direct.result.route_type, "direct");
+        assert_eq!(direct.result.supercharges, 0);
+        assert_eq!(direct.estimated_time_minutes, 68.0);
+
+        // 15 jumps, 7 of them supercharged at a minute and a half more each
+        // for finding the neutron star
+        let calc = JumpCalculator::new();
+        let neutron = calc.get_route_details(&sol, &target, 30.0).unwrap();
+        assert_eq!(neutron.result.route_type, "neutron highway");
+        assert_eq!(neutron.result.jumps, 15);
+        assert_eq!(neutron.result.supercharges, 7);
+        assert_eq!(neutron.estimated_time_minutes, 30.0 + 7.0 + 10.5);
+        // Fewer jumps still beat the supercharge overhead
+        assert!(neutron.estimated_time_minutes < direct.estimated_time_minutes);
+
+        let time_model = TimeModel {
+            seconds_per_jump: 60.0,
+            supercharge_seconds: 120.0,
+            neutron_seconds: 60.0,
+            scoop_stop_seconds: 300.0,
+        };
+        let neutron = calc
+            .with_time_model(time_model)
+            .get_route_details(&sol, &target, 30.0)
+            .unwrap();
+        assert_eq!(neutron.estimated_time_minutes, 15.0 + 14.0 + 7.0);
+        assert_eq!(neutron.scoop_stops, None);
+
+        // 45t of fuel from a 16t tank takes two scoop stops
+        let neutron = JumpCalculator::new()
+            .with_time_model(time_model)
+            .with_fuel_capacity(Some(16.0))
+            .get_route_details(&sol, &target, 30.0)
+            .unwrap();
+        assert_eq!(neutron.estimated_fuel_usage, 45.0);
+        assert_eq!(neutron.estimated_time_minutes, 15.0 + 14.0 + 7.0 + 10.0);
+        assert_eq!(neutron.scoop_stops, Some(2));
+        assert_eq!(
+            neutron.estimates(true, false, NumberFormat::Plain),
+            " | ⛽ ~45.0t fuel (2 scoop stops)"
+        );
+    }
+
+    #[test]
+    fn test_compare_routes() {
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let target = SystemCoordinates {
+            name: "Target".to_string(),
+            x: 1000.0,
+            ..sol.clone()
+        };
+        let calc = JumpCalculator::new();
+        let options = RouteOptions::default();
+
+        let comparison = calc
+            .compare_routes(&sol, &target, 30.0, &options, None)
+            .unwrap();
+        assert_eq!(comparison.total_distance, 1000.0);
+        let [direct, white_dwarf, neutron] = comparison.scenarios();
+        assert_eq!(
+            (
+                direct.route_type.as_str(),
+                direct.jumps,
+                direct.supercharges
+            ),
+            ("direct", 34, 0)
+        );
+        assert_eq!(white_dwarf.route_type, "white dwarf assisted");
+        assert_eq!(
+            (
+                neutron.route_type.as_str(),
+                neutron.jumps,
+                neutron.supercharges
+            ),
+            ("neutron highway", 15, 7)
+        );
+        // More boost, fewer jumps and less time
+        assert!(neutron.jumps < white_dwarf.jumps && white_dwarf.jumps < direct.jumps);
+        assert!(neutron.estimated_time_minutes < direct.estimated_time_minutes);
+        // 18 white dwarf supercharges cost more time than the 8 jumps they save
+        assert_eq!((white_dwarf.jumps, white_dwarf.supercharges), (26, 18));
+        assert_eq!(white_dwarf.estimated_time_minutes, 70.0);
+        assert_eq!(direct.estimated_time_minutes, 68.0);
+        assert_eq!(neutron.estimated_time_minutes, 47.5);
+        assert_eq!(comparison.fastest().route_type, "neutron highway");
+
+        // Slow enough neutron supercharges leave the direct route fastest
+        let slow_neutrons = TimeModel {
+            neutron_seconds: 300.0,
+            ..TimeModel::default()
+        };
+        let comparison = JumpCalculator::new()
+            .with_time_model(slow_neutrons)
+            .compare_routes(&sol, &target, 30.0, &options, None)
+            .unwrap();
+        assert_eq!(comparison.neutron_highway.estimated_time_minutes, 72.0);
+        assert_eq!(comparison.fastest().route_type, "direct");
+
+        // The neutron figures match what the calculator picks
+        let chosen = calc.calculate_route(&sol, &target, 30.0).unwrap();
+        assert_eq!(chosen.jumps, neutron.jumps);
+
+        // A planned route replaces the estimate of its kind
+        let corridor = JumpResult {
+            jumps: 19,
+            supercharges: 4,
+            route_type: "neutron highway (4 boosts)".to_string(),
+            ..chosen.clone()
+        };
+        let comparison = calc
+            .compare_routes(&sol, &target, 30.0, &options, Some(&corridor))
+            .unwrap();
+        let planned = &comparison.neutron_highway;
+        assert_eq!(
+            (
+                planned.route_type.as_str(),
+                planned.jumps,
+                planned.supercharges
+            ),
+            ("neutron highway (4 boosts)", 19, 4)
+        );
+        assert_eq!(comparison.direct.jumps, 34);
+        assert_eq!(comparison.white_dwarf.jumps, 26);
+
+        // Every scenario is shown, even when the route is too short for boosts
+        let near = SystemCoordinates {
+            name: "Near".to_string(),
+            x: 100.0,
+            ..sol.clone()
+        };
+        let comparison = calc
+            .compare_routes(&sol, &near, 30.0, &options, None)
+            .unwrap();
+        assert_eq!(comparison.direct.jumps, 4);
+        assert_eq!(comparison.neutron_highway.route_type, "neutron highway");
+        assert_eq!(
+            comparison.neutron_highway.describe(NumberFormat::Plain),
+            "neutron highway: 3 jumps (1 supercharged), ~8 min"
+        );
+
+        assert!(matches!(
+            calc.compare_routes(&sol, &target, 0.0, &options, None),
+            Err(EdjcError::InvalidJumpRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_steep_vertical_route_hint() {
+        let calc = JumpCalculator::new();
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let above_plane = SystemCoordinates {
+            name: "Above".to_string(),
+            x: 100.0,
+            y: 900.0,
+            z: 150.0,
+            ..sol.clone()
+        };
+        let along_plane = SystemCoordinates {
+            name: "Along".to_string(),
+            x: 900.0,
+            y: 100.0,
+            z: 150.0,
+            ..sol.clone()
+        };
+
+        assert_eq!(
+            calc.route_hints(&sol, &above_plane),
+            vec!["steep vertical route; fewer boost stars likely"]
+        );
+        assert!(calc.route_hints(&sol, &along_plane).is_empty());
+        assert!(calc.route_hints(&sol, &sol).is_empty());
+    }
+
+    #[test]
+    fn test_permit_route_warning() {
+        let calc = JumpCalculator::new();
+        let barnards = SystemCoordinates {
+            name: "Barnard's Star".to_string(),
+            x: -3.03125,
+            y: 1.375,
+            z: 4.9375,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            required_permit: Some("Sol".to_string()),
+            ..barnards.clone()
+        };
+
+        let details = calc.get_route_details(&barnards, &sol, 30.0).unwrap();
+        assert_eq!(details.warnings, vec!["Sol requires the Sol permit"]);
+
+        let details = calc.get_route_details(&sol, &barnards, 30.0).unwrap();
+        assert!(details.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_route_between_bundled_systems() {
+        let calc = JumpCalculator::new();
+
+        let result = calc
+            .calculate_route_between(&LocalSystemSource, "Sol", "Beagle Point", 60.0)
+            .unwrap();
+        assert_eq!(result.from_system, "Sol");
+        assert_eq!(result.to_system, "Beagle Point");
+        assert!(result.total_distance > 65000.0);
+        assert!(result.jumps > 0);
+
+        let err = calc
+            .calculate_route_between(&LocalSystemSource, "Sol", "Fuelum", 60.0)
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::SystemNotBundled(_)));
     }
 }
@@ -22,7 +22,7 @@ No API keys or external authentication required - uses free EDSM data.
 ## Usage
 
 The plugin automatically triggers when it detects a RATSIGNAL message from MechaSqueak[BOT]
-containing system information. Users can also test the plugin manually using `/route <system>`.
+(or another nick in `trigger_nicks`) containing system information. Users can also test the plugin manually using `/route <system>`.
 
 Example trigger:
 ```text
@@ -33,359 +33,7412 @@ Example manual test:
 ```text
 /route Colonia
 ```
+
+## Embedding
+
+[`EdJumpCalculatorBuilder`] sets the calculator up from code instead of
+`edjc.toml`, e.g. with the bundled offline systems:
+
+```no_run
+use edjc::system_source::LocalSystemSource;
+use edjc::EdJumpCalculator;
+
+let calculator = EdJumpCalculator::builder()
+    .cmdr_name("Whit3Arrow")
+    .ship_jump_range(45.0)
+    .source(Box::new(LocalSystemSource))
+    .build()?;
+println!("{}", calculator.handle_route_command("Colonia"));
+# Ok::<(), anyhow::Error>(())
+```
 */
 
+pub mod cache;
 pub mod config;
+pub mod diagnostics;
 pub mod edsm;
+#[cfg(feature = "async")]
+pub mod edsm_async;
 mod hexchat;
+#[cfg(feature = "http-api")]
+pub mod http_api;
+pub mod inara;
+pub mod journal;
 pub mod jump_calculator;
+pub mod output;
+pub mod planner;
+pub mod prefetch;
+pub mod presets;
+pub mod regions;
+pub mod route_export;
+pub mod route_log;
+pub mod ship_build;
+pub mod signal_feed;
+pub mod spansh;
+pub mod stats;
+pub mod system_source;
+mod telemetry;
+#[cfg(test)]
+mod test_support;
 pub mod types;
 
 use anyhow::Result;
 use libc::c_char;
 use log::{error, info, warn};
 use regex::Regex;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ffi::CString;
-use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 
-use crate::edsm::EdsmClient;
-use crate::jump_calculator::JumpCalculator;
-use crate::types::JumpResult;
+use crate::config::{Config, ShipConfig};
+use crate::diagnostics::Diagnostics;
+use crate::edsm::{EdsmClient, DEFAULT_REQUEST_TIMEOUT};
+use crate::inara::InaraClient;
+use crate::journal::JournalLocation;
+use crate::jump_calculator::{JumpCalculator, RouteDetails, NO_BOOST_ROUTE};
+use crate::output::{ChannelSink, HexChatSink, OutputSink};
+use crate::planner::{PlannedRoute, RoutePlanner};
+use crate::prefetch::PrefetchRefresher;
+use crate::regions::Region;
+use crate::route_log::{RouteLogEntry, RouteLogger};
+use crate::signal_feed::SignalFeed;
+use crate::spansh::SpanshClient;
+use crate::stats::{Counter, Stats};
+use crate::system_source::{LocalSystemSource, SystemSource};
+use crate::types::{
+    CaseEvent, CaseUpdate, ConnectionHealth, CooldownPolicy, Deadline, EdjcError, EdjcResult,
+    JumpResult, LandmarkHint, LocationSource, MissingLocationPolicy, NumberFormat, OutputMode,
+    RatsignalInfo, RouteBackend, SystemCoordinates,
+};
 
 /// Global plugin instance
-static PLUGIN: OnceLock<EdJumpCalculator> = OnceLock::new();
+///
+/// Set by `hexchat_plugin_init` and taken out again by
+/// `hexchat_plugin_deinit`, which removes the plugin's hooks and shuts it
+/// down (see [`EdJumpCalculator::shutdown`]). HexChat may keep the library
+/// loaded across an unload and load in the same session, so a reload finds
+/// this empty again rather than still holding the old instance.
+static PLUGIN: RwLock<Option<Arc<EdJumpCalculator>>> = RwLock::new(None);
+
+/// The loaded plugin instance, if any
+///
+/// Callbacks hold their own reference, so an unload while one runs doesn't
+/// pull the state out from under it.
+fn plugin() -> Option<Arc<EdJumpCalculator>> {
+    PLUGIN.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Plugin version, from Cargo.toml by way of build.rs
+pub const PLUGIN_VERSION: &str = env!("PLUGIN_VERSION");
+
+/// User agent sent to EDSM, Inara and Spansh
+pub(crate) const USER_AGENT: &str =
+    concat!("Elite Dangerous Jump Calculator/", env!("PLUGIN_VERSION"));
+
+/// Case distance /capacity assumes when none is given
+const REPRESENTATIVE_CASE_DISTANCE_LY: f64 = 100.0;
+
+/// Pause between commander position lookup attempts
+const CMDR_LOCATION_RETRY_DELAY: Duration = Duration::from_millis(750);
+
+/// Most notices held back by the announce cooldown; older ones are dropped
+const MAX_QUEUED_NOTICES: usize = 10;
+
+/// Callback run with each RATSIGNAL whose route was calculated
+type RatsignalCallback = dyn Fn(&RatsignalInfo, &JumpResult) + Send + Sync;
+
+/// Registered [`RatsignalCallback`], so the plugin stays `Debug`
+struct RatsignalHandler(Box<RatsignalCallback>);
+
+impl std::fmt::Debug for RatsignalHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RatsignalHandler")
+    }
+}
+
+/// What became of a chat message, see [`EdJumpCalculator::process_signal`]
+#[derive(Debug)]
+pub enum SignalOutcome {
+    /// Not a RATSIGNAL, or one this commander doesn't respond to (other
+    /// platform, already announced)
+    Ignored,
+    /// A RATSIGNAL whose case details couldn't be read
+    Unparseable,
+    /// A route to the case was planned
+    Planned {
+        signal: RatsignalInfo,
+        route: Box<PlannedRoute>,
+    },
+    /// Planning a route to the case failed
+    Failed {
+        signal: RatsignalInfo,
+        error: EdjcError,
+    },
+    /// A case was closed or assigned
+    CaseUpdated(CaseUpdate),
+}
+
+/// The config fields /edjc-set can change while the plugin runs
+#[derive(Debug, Clone)]
+struct Settings {
+    cmdr_name: String,
+    result_format: String,
+    trigger_nicks: Vec<String>,
+}
+
+impl Settings {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            cmdr_name: config.cmdr_name.clone(),
+            result_format: config.result_format.clone(),
+            trigger_nicks: config.trigger_nicks.clone(),
+        }
+    }
+}
+
+/// Settings /edjc-set accepts, as listed in its usage
+const SETTABLE_KEYS: [&str; 4] = [
+    "laden_jump_range",
+    "cmdr_name",
+    "result_format",
+    "trigger_nicks",
+];
 
 /// Main plugin structure
 #[derive(Debug)]
 pub struct EdJumpCalculator {
     edsm_client: EdsmClient,
-    jump_calculator: JumpCalculator,
-    ratsignal_regex: Regex,
-    cmdr_name: String,
-    edsm_api_key: Option<String>,
-    ship_jump_range: f64,
+    /// Source used instead of EDSM for system and CMDR lookups, when injected
+    system_source: Option<Box<dyn SystemSource>>,
+    /// Secondary source for the commander's location, when an Inara API key is set
+    inara_client: Option<InaraClient>,
+    /// Neutron plotter used instead of the built-in calculator, when selected
+    spansh_client: Option<SpanshClient>,
+    /// Name of the ship profile routes are planned for, switched with /ship
+    active_ship: RwLock<String>,
+    /// Ship profiles, the configured ones plus those added with /import-ship
+    ships: RwLock<Vec<ShipConfig>>,
+    /// Config fields /edjc-set changes at runtime
+    settings: RwLock<Settings>,
+    /// RATSIGNAL formats, tried in order
+    ratsignal_regexes: Vec<Regex>,
+    /// Looser patterns for a RATSIGNAL none of the formats match
+    partial_ratsignal: PartialRatsignal,
+    /// Case closed and assigned formats, tried in order
+    case_update_regexes: Vec<(CaseEvent, Regex)>,
+    config: Config,
+    output: Box<dyn OutputSink>,
+    prefetch: Mutex<Option<PrefetchRefresher>>,
+    /// Whether the private EDSM profile (or missing API key) notice was shown
+    /// this session
+    private_profile_noticed: AtomicBool,
+    /// Embedder hook for processed RATSIGNALs, unused by the HexChat plugin
+    on_ratsignal: Option<RatsignalHandler>,
+    /// When each recent case number was last announced, for `dedupe_window_seconds`
+    seen_cases: Mutex<HashMap<String, Instant>>,
+    /// RATSIGNALs seen this session and not closed yet, by case number, for /edjc-cases
+    open_cases: Mutex<HashMap<String, OpenCase>>,
+    /// Last notice shown and those held back, for `announce_cooldown_seconds`
+    announce_gate: Mutex<AnnounceGate>,
+    /// The last route shown by /route or a RATSIGNAL notice, for /route-last
+    last_route: Mutex<Option<PlannedRoute>>,
+    /// Coordinates of `reference_system`, once looked up
+    reference_coordinates: OnceLock<SystemCoordinates>,
+    /// Maximum jump range from the journal at `journal_path`, read at startup
+    journal_jump_range: Option<f64>,
+    /// Commander's location from the journal at `journal_path`
+    journal_location: Option<JournalLocation>,
+    /// Log every calculated route is appended to, when `route_log_path` is set
+    route_logger: Option<RouteLogger>,
+    /// Session counters for /edjc-stats, shared with the EDSM client
+    stats: Arc<Stats>,
+    /// Local `GET /route` endpoint, once started
+    #[cfg(feature = "http-api")]
+    http_api: Mutex<Option<http_api::HttpApi>>,
+    /// Connection to the rat-bot at `signal_feed_url`, if set
+    signal_feed: Mutex<Option<SignalFeed>>,
+}
+
+/// A RATSIGNAL that hasn't been closed yet, as listed by /edjc-cases
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenCase {
+    pub case_number: String,
+    pub system_name: String,
+    /// Jumps to the case, if a route could be planned
+    pub jumps: Option<u32>,
+    /// When the signal came in
+    pub seen_at: Instant,
+}
+
+/// When the last RATSIGNAL notice was shown, and the notices waiting for
+/// `announce_cooldown_seconds` to pass
+#[derive(Debug, Default)]
+struct AnnounceGate {
+    last_shown: Option<Instant>,
+    queued: VecDeque<String>,
+}
+
+/// Builds an [`EdJumpCalculator`] from settings given in code
+///
+/// Unlike [`EdJumpCalculator::new`], nothing is read from `edjc.toml` and the
+/// coordinate cache isn't persisted, so embedders and tests can set up a
+/// calculator without touching the filesystem. Settings are not validated
+/// beyond the RATSIGNAL pattern; call [`EdJumpCalculator::validate_config`]
+/// for a full check.
+#[derive(Debug)]
+pub struct EdJumpCalculatorBuilder {
+    config: Config,
+    source: Option<Box<dyn SystemSource>>,
+}
+
+impl Default for EdJumpCalculatorBuilder {
+    fn default() -> Self {
+        Self {
+            config: Config {
+                persist_cache: false,
+                ..Default::default()
+            },
+            source: None,
+        }
+    }
+}
+
+impl EdJumpCalculatorBuilder {
+    /// Start from the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from a complete configuration; later calls change it further
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// CMDR whose location routes start from
+    pub fn cmdr_name(mut self, cmdr_name: impl Into<String>) -> Self {
+        self.config.cmdr_name = cmdr_name.into();
+        self
+    }
+
+    /// Laden jump range of the active ship profile
+    pub fn ship_jump_range(mut self, laden_jump_range: f64) -> Self {
+        if self.config.ships.is_empty() {
+            self.config.ships.push(ShipConfig::default());
+        }
+        let active = self.config.active_ship.trim();
+        let index = self
+            .config
+            .ships
+            .iter()
+            .position(|ship| ship.name.eq_ignore_ascii_case(active))
+            .unwrap_or(0);
+        self.config.ships[index].laden_jump_range = laden_jump_range;
+        self
+    }
+
+    /// Look systems and the commander up in `source` instead of EDSM
+    pub fn source(mut self, source: Box<dyn SystemSource>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Recognize RATSIGNALs with a custom regex, see `ratsignal_pattern`
+    pub fn ratsignal_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.config.ratsignal_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Create the calculator
+    ///
+    /// Fails if the RATSIGNAL pattern doesn't compile or lacks a required group.
+    pub fn build(self) -> Result<EdJumpCalculator> {
+        let calculator = EdJumpCalculator::from_config(self.config)?;
+        Ok(match self.source {
+            Some(source) => calculator.with_system_source(source),
+            None => calculator,
+        })
+    }
 }
 
 impl EdJumpCalculator {
-    /// Initialize the plugin
+    /// Initialize the plugin from `edjc.toml`
     pub fn new() -> Result<Self> {
-        let config = config::load_config()?;
+        Self::builder().config(config::load_config()?).build()
+    }
+
+    /// Set a calculator up from code, see [`EdJumpCalculatorBuilder`]
+    pub fn builder() -> EdJumpCalculatorBuilder {
+        EdJumpCalculatorBuilder::new()
+    }
+
+    /// Initialize the plugin from an already-loaded configuration
+    pub fn from_config(mut config: Config) -> Result<Self> {
+        config.apply_ship_preset()?;
+        let stats = Arc::new(Stats::default());
+        let mut edsm_client = EdsmClient::new()?
+            .with_stats(Arc::clone(&stats))
+            .with_request_timeout(
+                DEFAULT_REQUEST_TIMEOUT.min(Duration::from_secs(config.plan_timeout_seconds)),
+            )
+            .with_include_hidden(config.include_hidden)
+            .with_suggestions(config.suggest_system_names)
+            .with_location_retries(config.cmdr_location_retries, CMDR_LOCATION_RETRY_DELAY)
+            .with_location_cache_ttl(Duration::from_secs(config.location_cache_seconds))
+            .with_corridor_bucket(config.corridor_bucket_ly)
+            .with_retry_policy(config.edsm_retry_policy());
+        if config.persist_cache {
+            edsm_client = edsm_client.with_cache_file(config::get_cache_path()?);
+        }
+        let prefetch = if config.offline_mode {
+            None
+        } else {
+            Self::start_prefetch(&config, &edsm_client)?
+        };
+        let signal_feed = config
+            .signal_feed_url
+            .as_deref()
+            .map(SignalFeed::start)
+            .transpose()?;
+        let inara_client = config
+            .inara_api_key
+            .as_deref()
+            .map(InaraClient::new)
+            .transpose()?
+            .map(|client| {
+                client
+                    .with_rate_limit(config.inara_requests_per_minute)
+                    .with_shared_cache(edsm_client.coordinate_cache())
+            });
+        let spansh_client = match config.route_backend {
+            RouteBackend::Spansh => Some(SpanshClient::new()?),
+            RouteBackend::Builtin => None,
+        };
+        let journal_jump_range = config.journal_path.as_deref().and_then(|path| {
+            match journal::read_jump_range_from_journal(path) {
+                Ok(range) => {
+                    info!("Max jump range from the journal: {range:.2} LY");
+                    Some(range)
+                }
+                Err(e) => {
+                    warn!("Could not read the jump range from the journal: {e}");
+                    None
+                }
+            }
+        });
+        let journal_location = config.journal_path.as_deref().map(JournalLocation::new);
+        let route_logger = config.route_log_path.as_deref().and_then(|path| {
+            RouteLogger::open(path)
+                .inspect_err(|e| warn!("Could not open route log {path:?}: {e}"))
+                .ok()
+        });
 
         Ok(Self {
-            edsm_client: EdsmClient::new()?,
-            jump_calculator: JumpCalculator::new(),
-            ratsignal_regex: Regex::new(
-                r#"RATSIGNAL.*?Case\s*#(\d+).*?CMDR\s+([^–]+).*?System:\s*"([^"]+)".*?Language:\s*([^(]*)"#,
-            )?,
-            cmdr_name: config.cmdr_name,
-            edsm_api_key: config.edsm_api_key,
-            ship_jump_range: config.ship.laden_jump_range,
+            edsm_client,
+            system_source: None,
+            inara_client,
+            spansh_client,
+            active_ship: RwLock::new(config.ship().name.clone()),
+            ships: RwLock::new(config.ships.clone()),
+            settings: RwLock::new(Settings::from_config(&config)),
+            ratsignal_regexes: config.ratsignal_regexes()?,
+            partial_ratsignal: PartialRatsignal::new(),
+            case_update_regexes: case_update_regexes(),
+            config,
+            output: Box::new(HexChatSink),
+            prefetch: Mutex::new(prefetch),
+            private_profile_noticed: AtomicBool::new(false),
+            on_ratsignal: None,
+            seen_cases: Mutex::new(HashMap::new()),
+            open_cases: Mutex::new(HashMap::new()),
+            announce_gate: Mutex::new(AnnounceGate::default()),
+            last_route: Mutex::new(None),
+            reference_coordinates: OnceLock::new(),
+            journal_jump_range,
+            journal_location,
+            route_logger,
+            stats,
+            #[cfg(feature = "http-api")]
+            http_api: Mutex::new(None),
+            signal_feed: Mutex::new(signal_feed),
         })
     }
 
-    /// Validate plugin configuration
-    pub fn validate_config(&self) -> Result<()> {
-        if self.cmdr_name.is_empty() {
-            return Err(anyhow::anyhow!(
-                "CMDR name is not configured. Please set 'cmdr_name' in edjc.toml"
-            ));
+    /// Start keeping the configured `prefetch_systems` warm, if any
+    fn start_prefetch(
+        config: &Config,
+        edsm_client: &EdsmClient,
+    ) -> Result<Option<PrefetchRefresher>> {
+        if config.prefetch_systems.is_empty() {
+            return Ok(None);
         }
 
-        if self.ship_jump_range <= 0.0 {
-            return Err(anyhow::anyhow!(
-                "Ship laden jump range must be greater than 0. Please set 'ship.laden_jump_range' in edjc.toml"
-            ));
+        let client = edsm_client.clone();
+        let refresher = PrefetchRefresher::start(
+            config.prefetch_systems.clone(),
+            prefetch::refresh_interval(edsm_client.cache_ttl()),
+            move |system| {
+                client.refresh_system_coordinates(system)?;
+                Ok(())
+            },
+        )?;
+
+        info!(
+            "Keeping {} prefetched systems warm",
+            config.prefetch_systems.len()
+        );
+        Ok(Some(refresher))
+    }
+
+    /// Stop background work (the prefetch thread, the HTTP API, the signal
+    /// feed), save the coordinate cache and flush the route log before
+    /// unloading
+    ///
+    /// The plugin stays usable afterwards, just without background work, and
+    /// calling this again only saves and flushes again. Notices still waiting
+    /// out `announce_cooldown_seconds` are logged and dropped.
+    pub fn shutdown(&self) {
+        let refresher = self
+            .prefetch
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Some(mut refresher) = refresher {
+            refresher.shutdown();
+        }
+        #[cfg(feature = "http-api")]
+        if let Some(mut api) = self
+            .http_api
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+        {
+            api.shutdown();
+        }
+        let signal_feed = self
+            .signal_feed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Some(mut signal_feed) = signal_feed {
+            signal_feed.shutdown();
         }
 
-        // Test EDSM API connection
-        match self.edsm_client.test_connection() {
-            Ok(true) => {
-                info!("EDSM API connection successful");
-                Ok(())
+        // Callbacks may still hold the plugin when it's unloaded, so save explicitly
+        if let Err(e) = self.edsm_client.save_cache() {
+            warn!("Failed to save coordinate cache: {e}");
+        }
+        if let Some(route_logger) = &self.route_logger {
+            if let Err(e) = route_logger.flush() {
+                warn!("Failed to flush the route log: {e}");
             }
-            Ok(false) => Err(anyhow::anyhow!("EDSM API connection test failed")),
-            Err(e) => Err(anyhow::anyhow!("EDSM API connection failed: {}", e)),
         }
-    }
 
-    /// Process a chat message and check for RATSIGNAL
-    pub fn process_message(&self, sender: &str, message: &str) -> Result<Option<String>> {
-        // Only process messages from MechaSqueak[BOT]
-        if sender != "MechaSqueak[BOT]" {
-            return Ok(None);
+        let mut gate = self.announce_gate.lock().unwrap_or_else(|e| e.into_inner());
+        for notice in gate.queued.drain(..) {
+            info!("Unloading, dropping queued notice: {notice}");
         }
+    }
 
-        if let Some(captures) = self.ratsignal_regex.captures(message) {
-            let case_number = captures.get(1).map(|m| m.as_str()).unwrap_or("Unknown");
-            let distressed_cmdr = captures
-                .get(2)
-                .map(|m| m.as_str().trim())
-                .unwrap_or("Unknown");
-            let target_system = captures.get(3).unwrap().as_str();
-            let language = captures
-                .get(4)
-                .map(|m| m.as_str().trim())
-                .unwrap_or("Unknown");
+    /// Replace the sink that plugin output is delivered to
+    pub fn with_output_sink(mut self, output: Box<dyn OutputSink>) -> Self {
+        self.output = output;
+        self
+    }
 
-            info!(
-                "RATSIGNAL detected - Case #{case_number}, CMDR: {distressed_cmdr}, System: {target_system}, Language: {language}"
-            );
+    /// Look systems and the commander up in another source instead of EDSM
+    pub fn with_system_source(mut self, system_source: Box<dyn SystemSource>) -> Self {
+        self.system_source = Some(system_source);
+        self
+    }
 
-            match self.calculate_jumps_with_origin(target_system) {
-                Ok((result, origin_system)) => {
-                    let response = format!(
-                        "🚀 Case #{}: {} jumps to {} ({:.1}ly) via {} route (from {} with {:.1}ly range)",
-                        case_number,
-                        result.jumps,
-                        target_system,
-                        result.total_distance,
-                        result.route_type,
-                        origin_system,
-                        self.ship_jump_range
-                    );
-                    Ok(Some(response))
-                }
-                Err(e) => {
-                    error!("Failed to calculate jumps for case #{case_number}: {e}");
-                    Ok(Some(format!(
-                        "❌ Case #{case_number}: Jump calculation failed for {target_system} - {e}"
-                    )))
-                }
-            }
-        } else {
-            // Check if it's a RATSIGNAL but didn't match our pattern
-            if message.contains("RATSIGNAL") {
-                warn!("RATSIGNAL detected but couldn't parse: {message}");
-                Ok(Some(
-                    "⚠️ RATSIGNAL detected but couldn't parse system information".to_string(),
-                ))
-            } else {
-                Ok(None)
-            }
-        }
+    /// Call `handler` with every RATSIGNAL whose route is calculated, e.g. to
+    /// log cases or forward them elsewhere
+    pub fn with_ratsignal_handler(
+        mut self,
+        handler: impl Fn(&RatsignalInfo, &JumpResult) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_ratsignal = Some(RatsignalHandler(Box::new(handler)));
+        self
     }
 
-    /// Handle the /route command for testing
-    pub fn handle_route_command(&self, target_system: &str) -> String {
-        if target_system.trim().is_empty() {
-            return "Usage: /route <system_name>".to_string();
+    /// Deliver a (possibly multi-line) response to the output sink, a line
+    /// at a time, wrapping lines longer than `max_line_length`
+    pub fn emit(&self, text: &str) {
+        for line in self.lines(text) {
+            self.output.emit(&line);
         }
+    }
 
-        let system_name = target_system.trim();
+    /// Text split into the lines it is delivered as (see [`hexchat::chunk_lines`])
+    fn lines(&self, text: &str) -> Vec<String> {
+        hexchat::chunk_lines(&self.glyphs(text), self.config.max_line_length)
+    }
 
-        match self.calculate_jumps_with_origin(system_name) {
-            Ok((result, origin_system)) => {
-                format!(
-                    "🚀 Route to {}: {} jumps ({:.1} LY) via {} route (from {} with {:.1} LY range)",
-                    system_name,
-                    result.jumps,
-                    result.total_distance,
-                    result.route_type,
-                    origin_system,
-                    self.ship_jump_range
-                )
-            }
-            Err(e) => {
-                error!("Failed to calculate route to {system_name}: {e}");
-                format!("❌ Route calculation failed for {system_name}: {e}")
-            }
+    /// Text as it is shown: unchanged, or with `use_emoji = false` its
+    /// emoji replaced by plain markers
+    fn glyphs(&self, text: &str) -> String {
+        if self.config.use_emoji {
+            text.to_string()
+        } else {
+            output::plain_glyphs(text)
         }
     }
 
-    /// Calculate jumps to target system and return both result and origin system
-    fn calculate_jumps_with_origin(&self, target_system: &str) -> Result<(JumpResult, String)> {
-        // Try to get commander's current location from EDSM
-        let current_system = match self
-            .edsm_client
-            .get_commander_location(&self.cmdr_name, self.edsm_api_key.as_deref())
-        {
-            Ok(system) => {
-                info!(
-                    "Using CMDR {}'s current location: {}",
-                    self.cmdr_name, system
-                );
-                system
-            }
-            Err(e) => {
-                warn!("Could not get CMDR location from EDSM: {e}. Using Sol as fallback.");
-                "Sol".to_string()
-            }
-        };
+    /// Deliver a RATSIGNAL notice: to the output sink, or said in the
+    /// channel with `output_mode = "channel"`
+    pub fn announce(&self, text: &str) {
+        match self.config.output_mode {
+            OutputMode::Console => self.emit(text),
+            OutputMode::Channel => self
+                .lines(text)
+                .iter()
+                .for_each(|line| ChannelSink.emit(line)),
+        }
+    }
 
-        // Get system coordinates from EDSM
-        let current_coords = self.edsm_client.get_system_coordinates(&current_system)?;
-        let target_coords = self.edsm_client.get_system_coordinates(target_system)?;
+    /// Where systems and the commander are looked up: an injected source,
+    /// the bundled systems in offline mode, or EDSM
+    fn systems(&self) -> &dyn SystemSource {
+        if let Some(system_source) = &self.system_source {
+            system_source.as_ref()
+        } else if self.config.offline_mode {
+            &LocalSystemSource
+        } else {
+            &self.edsm_client
+        }
+    }
 
-        // Calculate jump route using the configured ship jump range
-        let result = self.jump_calculator.calculate_route(
-            &current_coords,
-            &target_coords,
-            self.ship_jump_range,
-        )?;
+    /// The active ship profile
+    ///
+    /// A jump range read from the journal replaces its `max_jump_range`.
+    fn ship(&self) -> ShipConfig {
+        let ship = self.active_profile();
+        ShipConfig {
+            max_jump_range: self.journal_jump_range.or(ship.max_jump_range),
+            ..ship
+        }
+    }
 
-        Ok((result, current_system))
+    /// The active ship profile as configured (or imported)
+    fn active_profile(&self) -> ShipConfig {
+        let active = self.active_ship.read().unwrap_or_else(|e| e.into_inner());
+        let ships = self.ships.read().unwrap_or_else(|e| e.into_inner());
+        config::find_ship(&ships, &active)
+            .or(ships.first())
+            .cloned()
+            .unwrap_or_default()
     }
-}
 
-/// Initialize HexChat integration - basic version without command hooks
-unsafe fn init_hexchat_integration(
-    plugin_handle: *mut hexchat::HexChatPlugin,
-    arg: *const c_char,
-) -> Result<()> {
-    // Store plugin handle for later use
-    hexchat::store_plugin_handle(plugin_handle);
+    /// The config as changed at runtime: ship profiles, the active one and
+    /// the /edjc-set settings
+    fn runtime_config(&self) -> Config {
+        let settings = self.settings();
+        Config {
+            active_ship: self
+                .active_ship
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+            ships: self.ships.read().unwrap_or_else(|e| e.into_inner()).clone(),
+            cmdr_name: settings.cmdr_name,
+            result_format: settings.result_format,
+            trigger_nicks: settings.trigger_nicks,
+            ..self.config.clone()
+        }
+    }
 
-    // Initialize HexChat API
-    if !hexchat::init_hexchat_api_from_arg(plugin_handle, arg) {
-        warn!("Could not initialize HexChat API from arg parameter");
+    /// Save the runtime config, ship profiles included, to the config file
+    fn save_ships(&self) -> Result<()> {
+        config::save_config(&self.runtime_config())
     }
 
-    // Register the /route command - temporarily disabled for stability
-    let route_cmd = CString::new("route")?;
-    let _route_hook = hexchat::hexchat_hook_command(
-        route_cmd.as_ptr(),
-        Some(route_command_callback),
-        std::ptr::null_mut(),
-    );
+    /// The /edjc-set settings currently in effect
+    fn settings(&self) -> Settings {
+        self.settings
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
 
-    // Print startup messages
-    let startup_msg =
-        CString::new("[EDJC] Plugin loaded successfully! RATSIGNAL detection is active.")?;
-    hexchat::hexchat_print(startup_msg.as_ptr());
+    /// The commander whose location routes start from
+    fn cmdr_name(&self) -> String {
+        self.settings
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .cmdr_name
+            .clone()
+    }
 
-    let help_msg = CString::new("[EDJC] Note: /route command temporarily disabled for stability. Use standalone calculator for testing.")?;
-    hexchat::hexchat_print(help_msg.as_ptr());
+    /// Whether `sender` is one of the relay bots RATSIGNALs are taken from
+    fn is_trigger_nick(&self, sender: &str) -> bool {
+        let settings = self.settings.read().unwrap_or_else(|e| e.into_inner());
+        config::is_trigger_nick(
+            &settings.trigger_nicks,
+            self.config.trigger_nicks_ignore_case,
+            sender,
+        )
+    }
 
-    Ok(())
-}
+    /// The active ship's laden jump range
+    fn ship_jump_range(&self) -> f64 {
+        self.ship().laden_jump_range
+    }
 
-// HexChat plugin export functions
+    /// Jump calculator for the active ship
+    fn jump_calculator(&self) -> JumpCalculator {
+        self.planner().jump_calculator()
+    }
 
-/// Initialize the HexChat plugin.
-///
-/// This function is called by HexChat when the plugin is loaded.
-///
-/// # Safety
-///
-/// This function is unsafe because it:
-/// - Dereferences raw pointers (`plugin_name`, `plugin_desc`, `plugin_version`) without null checks
-/// - Assumes the pointers point to valid memory locations that can be written to
-/// - Converts Rust `CString`s to raw pointers and transfers ownership to HexChat
-/// - Calls other unsafe functions that interact with HexChat's C API
-///
-/// The caller (HexChat) must ensure that:
-/// - All pointer parameters point to valid, writable memory
-/// - The plugin handle is valid for the lifetime of the plugin
-/// - The arg parameter, if not null, points to valid C string data
-#[no_mangle]
-pub unsafe extern "C" fn hexchat_plugin_init(
-    plugin_handle: *mut hexchat::HexChatPlugin,
-    plugin_name: *mut *const c_char,
-    plugin_desc: *mut *const c_char,
-    plugin_version: *mut *const c_char,
-    arg: *const c_char,
-) -> i32 {
-    // Initialize logging
-    if let Err(e) = env_logger::try_init() {
-        eprintln!("Failed to initialize logger: {e}");
+    /// Whether a route starts in its own target system and should be reported as such
+    fn already_in_target(&self, result: &JumpResult) -> bool {
+        self.config.abort_on_same_system && result.is_same_system()
     }
 
-    // Set plugin info
-    *plugin_name = CString::new("Elite Dangerous Jump Calculator")
-        .unwrap()
-        .into_raw();
-    *plugin_desc = CString::new("Calculates jumps to RATSIGNAL systems")
-        .unwrap()
-        .into_raw();
-    *plugin_version = CString::new("0.1.0").unwrap().into_raw();
+    /// Whether a route needs more jumps than the ship can feasibly make
+    fn is_infeasible(&self, result: &JumpResult) -> bool {
+        self.config
+            .infeasible_jump_count
+            .is_some_and(|limit| result.jumps > limit)
+    }
 
-    // Initialize plugin
-    match EdJumpCalculator::new() {
-        Ok(plugin) => {
-            // Validate configuration
-            if let Err(e) = plugin.validate_config() {
-                error!("Configuration validation failed: {e}");
+    /// Validate plugin configuration
+    pub fn validate_config(&self) -> Result<()> {
+        config::validate_config(&self.config)?;
+        if self.config.offline_mode {
+            info!("Offline mode, skipping the EDSM connection test");
+            return Ok(());
+        }
 
-                // Still try to initialize but warn user
-                let error_msg = format!("[EDJC] Configuration error: {e}");
-                hexchat::hexchat_print(CString::new(error_msg).unwrap().as_ptr());
+        // Test EDSM API connection
+        match self.edsm_client.test_connection() {
+            ConnectionHealth::Healthy => {
+                info!("EDSM API connection successful");
+                Ok(())
             }
+            health => Err(anyhow::anyhow!("EDSM API connection failed: {}", health)),
+        }
+    }
 
-            // Set up HexChat API integration
-            if let Err(e) = init_hexchat_integration(plugin_handle, arg) {
-                warn!("HexChat integration limited: {e}");
-            } else {
-                info!("HexChat integration initialized");
-            }
+    /// Re-run the setup checks live: configuration, EDSM, Inara (when
+    /// configured) and the commander's current location
+    pub fn run_diagnostics(&self) -> Diagnostics {
+        let mut diagnostics = Diagnostics::default();
 
-            PLUGIN.set(plugin).unwrap();
+        match config::validate_config(&self.config) {
+            Ok(()) => diagnostics.pass("Configuration", "OK"),
+            Err(e) => diagnostics.fail("Configuration", e.to_string()),
+        }
 
-            info!("EDJC plugin initialized successfully");
-            info!("Monitoring for RATSIGNAL messages from MechaSqueak[BOT]");
+        if self.config.offline_mode {
+            diagnostics.pass("EDSM", "skipped in offline mode");
+        } else {
+            let edsm = self.edsm_client.connection_diagnostics();
+            if edsm.health.is_healthy() {
+                diagnostics.pass("EDSM", edsm.to_string());
+            } else {
+                diagnostics.fail("EDSM", edsm.to_string());
+            }
+        }
 
-            1 // Success
+        let cmdr_name = &self.cmdr_name();
+        if let Some(inara_client) = &self.inara_client {
+            inara_client.invalidate_location(cmdr_name);
+            match inara_client.get_cmdr_location(cmdr_name) {
+                Ok(system) => diagnostics.pass("Inara", format!("CMDR {cmdr_name} in {system}")),
+                Err(e) => diagnostics.fail("Inara", e.to_string()),
+            }
         }
-        Err(e) => {
-            error!("Failed to initialize EDJC plugin: {e}");
-            0 // Failure
+
+        // Ask again rather than trust a location cached before a jump
+        self.edsm_client.invalidate_location(cmdr_name);
+        match self.planner().locate_commander() {
+            Ok(system) => {
+                diagnostics.pass("CMDR location", format!("CMDR {cmdr_name} in {system}"))
+            }
+            Err(e) => diagnostics.fail(
+                "CMDR location",
+                match e.hint() {
+                    Some(hint) => format!("{e} ({hint})"),
+                    None => e.to_string(),
+                },
+            ),
         }
+
+        diagnostics
     }
-}
 
-/// Deinitialize the HexChat plugin.
-///
-/// This function is called by HexChat when the plugin is being unloaded.
-/// Returns 1 on success, 0 on failure.
-#[no_mangle]
-pub extern "C" fn hexchat_plugin_deinit() -> i32 {
-    info!("EDJC plugin deinitialized");
-    1
-}
+    /// Parse a RATSIGNAL message into its parts
+    ///
+    /// Each known format is tried in turn (MechaSqueak's verbose one first,
+    /// then the compact one), unless `ratsignal_pattern` replaces them. The
+    /// language is reported as its code (e.g. "en-US") when the signal
+    /// includes one, otherwise as the language name.
+    pub fn parse_ratsignal(&self, message: &str) -> Option<RatsignalInfo> {
+        let captures = self
+            .ratsignal_regexes
+            .iter()
+            .find_map(|regex| regex.captures(message))?;
+        let group = |name: &str| {
+            captures
+                .name(name)
+                .map(|m| m.as_str().trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
 
-/// Callback for chat messages - placeholder for future implementation
-#[allow(dead_code)]
-extern "C" fn message_callback(
-    _word: *const *const c_char,
-    _word_eol: *const *const c_char,
-    _user_data: *mut libc::c_void,
-) -> i32 {
-    // This would be implemented when we have proper HexChat API access
-    // For now, just return HEXCHAT_EAT_NONE
-    hexchat::HEXCHAT_EAT_NONE
-}
+        // Tags between the case number and the CMDR, e.g. "PC ODY"
+        let tags = group("tags").unwrap_or_default();
+        let mut tags: Vec<&str> = tags.split_whitespace().collect();
+        let platform = match tags.first() {
+            Some(tag) if is_platform_tag(tag) => tags.remove(0).to_string(),
+            _ => "Unknown".to_string(),
+        };
 
-/// Callback for the /route command
-extern "C" fn route_command_callback(
-    word: *const *const c_char,
-    _word_eol: *const *const c_char,
-    _user_data: *mut libc::c_void,
-) -> i32 {
-    if let Some(plugin) = PLUGIN.get() {
-        unsafe {
-            // Parse the command arguments
-            let target_system = if !word.is_null() {
-                // word[0] is the command name ("/route"), word[1] is the first argument
-                let word1_ptr = *word.offset(1);
-                if !word1_ptr.is_null() {
-                    hexchat::c_str_to_string(word1_ptr)
-                } else {
-                    String::new()
-                }
-            } else {
-                String::new()
-            };
+        Some(RatsignalInfo {
+            case_number: group("case")?,
+            platform,
+            mode: tags
+                .last()
+                .and_then(|tag| game_mode(tag))
+                .map(str::to_string),
+            cmdr_name: group("cmdr")?,
+            system_name: group("system")?,
+            landmark: group("info").as_deref().and_then(LandmarkHint::parse),
+            system_info: group("info"),
+            language: group("language").and_then(|language| parse_language(&language)),
+            raw_message: message.to_string(),
+        })
+    }
 
-            // Handle the route command
-            let response = plugin.handle_route_command(&target_system);
+    /// Recover what can be recovered from a RATSIGNAL that
+    /// [`parse_ratsignal`](Self::parse_ratsignal) couldn't parse
+    ///
+    /// Only the quoted `System: "..."` is needed; the case number, CMDR and
+    /// platform are picked up if they can be found, otherwise the case is
+    /// [`UNKNOWN_CASE`] and the rest "Unknown".
+    pub fn parse_partial_ratsignal(&self, message: &str) -> Option<RatsignalInfo> {
+        if !message.contains("RATSIGNAL") {
+            return None;
+        }
+        self.partial_ratsignal.parse(message)
+    }
 
-            // Send the response to HexChat
-            let response_cstr = std::ffi::CString::new(response).unwrap();
-            hexchat::hexchat_print(response_cstr.as_ptr());
+    /// Parse a relay bot's message about a case being closed or assigned
+    ///
+    /// RATSIGNALs themselves are never updates, whatever their system is called.
+    pub fn parse_case_update(&self, message: &str) -> Option<CaseUpdate> {
+        if message.contains("RATSIGNAL") {
+            return None;
         }
-    } else {
-        let error_msg = std::ffi::CString::new("❌ Plugin not initialized").unwrap();
-        hexchat::hexchat_print(error_msg.as_ptr());
+        self.case_update_regexes.iter().find_map(|(event, regex)| {
+            let captures = regex.captures(message)?;
+            Some(CaseUpdate {
+                case_number: captures["case"].to_string(),
+                event: *event,
+            })
+        })
     }
 
-    hexchat::HEXCHAT_EAT_ALL // Consume the command so HexChat doesn't show "unknown command"
+    /// Process a chat message and check for RATSIGNAL
+    ///
+    /// Renders the outcome of [`process_signal`](Self::process_signal) as the
+    /// notice to print, if any.
+    pub fn process_message(&self, sender: &str, message: &str) -> Result<Option<String>> {
+        let response = self.signal_response(sender, message, SideEffects::Apply)?;
+        Ok(response.and_then(|response| self.throttle_notice(self.glyphs(&response))))
+    }
+
+    /// The notice [`process_message`](Self::process_message) would print for
+    /// a chat message, without changing anything
+    ///
+    /// The signal is parsed and its route planned, but nothing is recorded:
+    /// the case isn't marked as announced or open, nothing is counted in the
+    /// stats (not even the lookups made), the route isn't logged or kept for
+    /// /route-last and the RATSIGNAL handler isn't called. A case announced
+    /// already previews as ignored, as it would be;
+    /// `announce_cooldown_seconds` is not applied.
+    pub fn process_message_preview(&self, sender: &str, message: &str) -> Result<Option<String>> {
+        let response =
+            stats::uncounted(|| self.signal_response(sender, message, SideEffects::Skip))?;
+        Ok(response.map(|response| self.glyphs(&response)))
+    }
+
+    /// A notice if `announce_cooldown_seconds` have passed since the last
+    /// one; otherwise it's queued or dropped, per `cooldown_policy`
+    fn throttle_notice(&self, notice: String) -> Option<String> {
+        let cooldown = Duration::from_secs(self.config.announce_cooldown_seconds);
+        if cooldown.is_zero() {
+            return Some(notice);
+        }
+
+        let now = Instant::now();
+        let mut gate = self.announce_gate.lock().unwrap_or_else(|e| e.into_inner());
+        let due = gate
+            .last_shown
+            .is_none_or(|shown| now.duration_since(shown) >= cooldown);
+        match self.config.cooldown_policy {
+            CooldownPolicy::Drop if !due => {
+                info!("Announce cooldown, dropping notice: {notice}");
+                None
+            }
+            CooldownPolicy::Drop => {
+                gate.last_shown = Some(now);
+                Some(notice)
+            }
+            CooldownPolicy::Queue => {
+                if gate.queued.len() >= MAX_QUEUED_NOTICES {
+                    if let Some(stale) = gate.queued.pop_front() {
+                        info!("Announce queue full, dropping notice: {stale}");
+                    }
+                }
+                gate.queued.push_back(notice);
+                if due {
+                    gate.last_shown = Some(now);
+                    gate.queued.pop_front()
+                } else {
+                    info!("Announce cooldown, {} notice(s) queued", gate.queued.len());
+                    None
+                }
+            }
+        }
+    }
+
+    /// The oldest notice held back by `announce_cooldown_seconds`, once the
+    /// cooldown has passed
+    ///
+    /// The HexChat plugin polls this every second; embedders calling
+    /// [`process_message`](Self::process_message) should do the same.
+    pub fn next_queued_notice(&self) -> Option<String> {
+        let cooldown = Duration::from_secs(self.config.announce_cooldown_seconds);
+        let now = Instant::now();
+        let mut gate = self.announce_gate.lock().unwrap_or_else(|e| e.into_inner());
+        if gate
+            .last_shown
+            .is_some_and(|shown| now.duration_since(shown) < cooldown)
+        {
+            return None;
+        }
+
+        let notice = gate.queued.pop_front()?;
+        gate.last_shown = Some(now);
+        Some(notice)
+    }
+
+    /// Notices for the lines received from `signal_feed_url` since the last
+    /// call
+    ///
+    /// Each line goes through [`process_message`](Self::process_message) as
+    /// if the first trigger nick had said it. The HexChat plugin polls this
+    /// from a timer, since HexChat may only be called from its own thread.
+    pub fn process_signal_feed(&self) -> Vec<String> {
+        let lines = match &*self.signal_feed.lock().unwrap_or_else(|e| e.into_inner()) {
+            Some(signal_feed) => signal_feed.received(),
+            None => return Vec::new(),
+        };
+        let sender = self
+            .settings()
+            .trigger_nicks
+            .first()
+            .cloned()
+            .unwrap_or_default();
+
+        lines
+            .iter()
+            .filter_map(|line| match self.process_message(&sender, line) {
+                Ok(notice) => notice,
+                Err(e) => {
+                    error!("Failed to process a line from the signal feed: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The notice for a chat message, as [`process_message`](Self::process_message)
+    /// returns it before `use_emoji` is applied
+    fn signal_response(
+        &self,
+        sender: &str,
+        message: &str,
+        effects: SideEffects,
+    ) -> Result<Option<String>> {
+        match self.process_signal_with(sender, message, effects) {
+            SignalOutcome::Ignored => Ok(None),
+            SignalOutcome::Unparseable => Ok(Some(
+                "⚠️ RATSIGNAL detected but couldn't parse system information".to_string(),
+            )),
+            SignalOutcome::Planned { signal, route } => {
+                if route.result.jumps < self.config.min_jumps_to_announce {
+                    info!(
+                        "Not announcing case #{}, only {} jumps away",
+                        signal.case_number, route.result.jumps
+                    );
+                    return Ok(None);
+                }
+                Ok(Some(self.case_response(&signal, Ok(*route), effects)))
+            }
+            SignalOutcome::Failed { signal, error } => {
+                Ok(Some(self.case_response(&signal, Err(error), effects)))
+            }
+            SignalOutcome::CaseUpdated(CaseUpdate {
+                case_number,
+                event: CaseEvent::Closed,
+            }) if self.config.announce_case_closed => {
+                Ok(Some(format!("✅ Case #{case_number} closed")))
+            }
+            SignalOutcome::CaseUpdated(_) => Ok(None),
+        }
+    }
+
+    /// Process a chat message and plan a route for its RATSIGNAL, if any
+    ///
+    /// Unlike [`process_message`](Self::process_message), the outcome is
+    /// returned as data, for bots that branch on it rather than print it.
+    pub fn process_signal(&self, sender: &str, message: &str) -> SignalOutcome {
+        self.process_signal_with(sender, message, SideEffects::Apply)
+    }
+
+    /// [`Self::process_signal`], changing the plugin's state only with
+    /// [`SideEffects::Apply`]
+    fn process_signal_with(
+        &self,
+        sender: &str,
+        message: &str,
+        effects: SideEffects,
+    ) -> SignalOutcome {
+        let apply = effects == SideEffects::Apply;
+        // Only process messages from the relay bots (MechaSqueak[BOT] by default)
+        if !self.is_trigger_nick(sender) {
+            return SignalOutcome::Ignored;
+        }
+
+        let signal = self.parse_ratsignal(message).or_else(|| {
+            let signal = self.parse_partial_ratsignal(message)?;
+            warn!(
+                "Partial parse of RATSIGNAL, recovered case #{} in {}: {message}",
+                signal.case_number, signal.system_name
+            );
+            Some(signal)
+        });
+        let Some(signal) = signal else {
+            if let Some(update) = self.parse_case_update(message) {
+                if apply {
+                    self.track_case_update(&update);
+                }
+                return SignalOutcome::CaseUpdated(update);
+            }
+            // Check if it's a RATSIGNAL but didn't match our pattern
+            if message.contains("RATSIGNAL") {
+                warn!("RATSIGNAL detected but couldn't parse: {message}");
+                if apply {
+                    self.stats.record(Counter::SignalsSeen);
+                }
+                return SignalOutcome::Unparseable;
+            }
+            return SignalOutcome::Ignored;
+        };
+        if apply {
+            self.stats.record(Counter::SignalsSeen);
+            self.stats.record(Counter::SignalsParsed);
+        }
+
+        if !self.config.responds_to_platform(&signal.platform) {
+            info!(
+                "Ignoring case #{} on {} (not in platforms)",
+                signal.case_number, signal.platform
+            );
+            return SignalOutcome::Ignored;
+        }
+        if self.is_repeated_case(&signal.case_number, effects) {
+            info!("Case #{} already announced, ignoring", signal.case_number);
+            return SignalOutcome::Ignored;
+        }
+
+        let case_number = signal.case_number.as_str();
+        let platform = signal.platform.as_str();
+        let distressed_cmdr = signal.cmdr_name.as_str();
+        let target_system = signal.system_name.as_str();
+        let language = signal.language.as_deref().unwrap_or("Unknown");
+
+        info!(
+            "RATSIGNAL detected - Case #{case_number} ({platform}), CMDR: {distressed_cmdr}, System: {target_system}, Language: {language}"
+        );
+
+        let planned = self
+            .plan_case_route_with(target_system, effects)
+            .or_else(|e| self.plan_via_landmark(&signal, e, effects));
+        if !apply {
+            return match planned {
+                Ok(route) => SignalOutcome::Planned {
+                    signal,
+                    route: Box::new(route),
+                },
+                Err(error) => SignalOutcome::Failed { signal, error },
+            };
+        }
+        self.track_open_case(&signal, planned.as_ref().ok());
+        match planned {
+            Ok(route) => {
+                // A case whose route failed is tried again when re-posted
+                self.remember_case(&signal.case_number);
+                if let Some(RatsignalHandler(handler)) = &self.on_ratsignal {
+                    handler(&signal, &route.result);
+                }
+                SignalOutcome::Planned {
+                    signal,
+                    route: Box::new(route),
+                }
+            }
+            Err(error) => SignalOutcome::Failed { signal, error },
+        }
+    }
+
+    /// Plan a route for every RATSIGNAL in a block of pasted chat or a log file
+    ///
+    /// Each line is parsed on its own and lines without a signal are skipped.
+    /// A case that comes up more than once is planned for its first signal
+    /// only. Unlike chat messages, signals aren't filtered by sender, platform
+    /// or `dedupe_window_seconds`.
+    pub fn process_log(&self, text: &str) -> Vec<(RatsignalInfo, EdjcResult<JumpResult>)> {
+        let mut cases = HashSet::new();
+        text.lines()
+            .filter_map(|line| self.parse_ratsignal(line))
+            .filter(|signal| cases.insert(signal.case_number.clone()))
+            .map(|signal| {
+                let planned = self
+                    .plan_case_route(&signal.system_name)
+                    .or_else(|e| self.plan_via_landmark(&signal, e, SideEffects::Apply))
+                    .map(|planned| planned.result);
+                (signal, planned)
+            })
+            .collect()
+    }
+
+    /// Route to the signal's landmark instead when its system isn't in EDSM,
+    /// passing any other error through
+    fn plan_via_landmark(
+        &self,
+        signal: &RatsignalInfo,
+        error: EdjcError,
+        effects: SideEffects,
+    ) -> EdjcResult<PlannedRoute> {
+        let unknown_system = matches!(
+            error,
+            EdjcError::SystemNotFound { .. }
+                | EdjcError::SystemNotBundled(_)
+                | EdjcError::CoordinatesUnavailable(_)
+        );
+        let landmark = match &signal.landmark {
+            Some(landmark) if unknown_system && self.config.route_via_landmark => landmark,
+            _ => return Err(error),
+        };
+
+        info!(
+            "{} not found for case #{}, routing to landmark {}",
+            signal.system_name, signal.case_number, landmark.reference_system
+        );
+        let mut planned = self.plan_case_route_with(&landmark.reference_system, effects)?;
+        planned.warnings.insert(
+            0,
+            format!(
+                "{} isn't in EDSM, routed to {} instead (the case's {} is {} LY from it)",
+                signal.system_name,
+                landmark.reference_system,
+                landmark.body_type.to_lowercase(),
+                self.config.number_format.format(landmark.distance_ly, 0)
+            ),
+        );
+        Ok(planned)
+    }
+
+    /// Whether a case was already announced within `dedupe_window_seconds`,
+    /// forgetting cases whose window has passed (unless previewing)
+    fn is_repeated_case(&self, case_number: &str, effects: SideEffects) -> bool {
+        let window = Duration::from_secs(self.config.dedupe_window_seconds);
+        // Signals without a case number can't be told apart
+        if window.is_zero() || case_number == UNKNOWN_CASE {
+            return false;
+        }
+
+        let now = Instant::now();
+        let mut seen = self.seen_cases.lock().unwrap_or_else(|e| e.into_inner());
+        if effects == SideEffects::Apply {
+            seen.retain(|_, announced| now.duration_since(*announced) < window);
+        }
+        seen.get(case_number)
+            .is_some_and(|announced| now.duration_since(*announced) < window)
+    }
+
+    /// Remember a case whose route was announced, so re-posts of it within
+    /// `dedupe_window_seconds` are ignored
+    fn remember_case(&self, case_number: &str) {
+        if self.config.dedupe_window_seconds == 0 || case_number == UNKNOWN_CASE {
+            return;
+        }
+        self.seen_cases
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(case_number.to_string(), Instant::now());
+    }
+
+    /// Forget a closed case, so a new case given its number is announced;
+    /// an assigned one counts as just seen, so it isn't announced again
+    /// while rats are on it
+    fn track_case_update(&self, update: &CaseUpdate) {
+        let mut seen = self.seen_cases.lock().unwrap_or_else(|e| e.into_inner());
+        match update.event {
+            CaseEvent::Closed => {
+                info!("Case #{} closed", update.case_number);
+                seen.remove(&update.case_number);
+                self.open_cases
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&update.case_number);
+            }
+            CaseEvent::Assigned if self.config.dedupe_window_seconds > 0 => {
+                info!("Case #{} assigned", update.case_number);
+                seen.insert(update.case_number.clone(), Instant::now());
+            }
+            CaseEvent::Assigned => {}
+        }
+    }
+
+    /// Remember a RATSIGNAL as open until the relay bot closes it
+    ///
+    /// Signals without a case number are left out, as nothing could close them.
+    fn track_open_case(&self, signal: &RatsignalInfo, route: Option<&PlannedRoute>) {
+        if signal.case_number == UNKNOWN_CASE {
+            return;
+        }
+        self.open_cases
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                signal.case_number.clone(),
+                OpenCase {
+                    case_number: signal.case_number.clone(),
+                    system_name: signal.system_name.clone(),
+                    jumps: route.map(|route| route.result.jumps),
+                    seen_at: Instant::now(),
+                },
+            );
+    }
+
+    /// Cases seen this session and not closed yet, the most recent first
+    pub fn open_cases(&self) -> Vec<OpenCase> {
+        let mut cases: Vec<OpenCase> = self
+            .open_cases
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        cases.sort_by_key(|case| std::cmp::Reverse(case.seen_at));
+        cases
+    }
+
+    /// Re-plan every open case with the active ship, most recent first,
+    /// after a ship switch or range change left their jump counts stale
+    ///
+    /// Each case comes back as it was tracked, with the new jump count or
+    /// why it couldn't be planned; cases that fail keep their old count.
+    pub fn recompute_open_cases(&self) -> Vec<(OpenCase, EdjcResult<u32>)> {
+        self.open_cases()
+            .into_iter()
+            .map(|case| {
+                let jumps = self
+                    .plan_case_route(&case.system_name)
+                    .map(|planned| planned.result.jumps);
+                if let Ok(jumps) = jumps {
+                    // Closed while we were planning: leave it closed
+                    if let Some(open) = self
+                        .open_cases
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .get_mut(&case.case_number)
+                    {
+                        open.jumps = Some(jumps);
+                    }
+                }
+                (case, jumps)
+            })
+            .collect()
+    }
+
+    /// Render the response to a RATSIGNAL for a planned (or failed) route
+    ///
+    /// With [`SideEffects::Apply`], a shown route is kept for /route-last
+    /// and written to the route log.
+    fn case_response(
+        &self,
+        signal: &RatsignalInfo,
+        planned: EdjcResult<PlannedRoute>,
+        effects: SideEffects,
+    ) -> String {
+        let case = signal.case_label();
+        let target_system = signal.system_name.as_str();
+
+        match planned {
+            Ok(planned) if self.already_in_target(&planned.result) => format!(
+                "📍 {}: you're already in {}",
+                case, planned.result.to_system
+            ),
+            Ok(planned) if self.is_infeasible(&planned.result) => format!(
+                "🚫 {}: {} is not feasible with current ship (needs ~{} jumps)",
+                case, target_system, planned.result.jumps
+            ),
+            Ok(planned) if planned.result.route_type == NO_BOOST_ROUTE => format!(
+                "⚠️ {}: no boost route available to {} ({} from {})",
+                case,
+                target_system,
+                self.config
+                    .number_format
+                    .distance(planned.result.total_distance),
+                planned.origin
+            ),
+            Ok(planned) => {
+                if effects == SideEffects::Apply {
+                    self.remember_route(&planned);
+                    self.log_route(Some(&signal.case_number), &planned);
+                }
+                let config = self.runtime_config();
+                let template = config.ratsignal_template();
+                let rendered = self
+                    .render_route(template, &planned)
+                    .replace("{case}", &case);
+                // A notice has to say which case it is about
+                let response = if template.contains("{case}") {
+                    rendered
+                } else {
+                    format!("{case}: {rendered}")
+                };
+                let response = self.append_warnings(response, &planned);
+                let response = self.append_nearest_station(response, &planned);
+                let response = self.append_reference_distance(response, &planned);
+                let response = self.append_route_hints(response, &planned);
+                self.append_return_estimate(response, target_system)
+            }
+            Err(EdjcError::OriginUnknown(_))
+                if self.config.on_missing_location == MissingLocationPolicy::Ask =>
+            {
+                format!(
+                    "❓ {case}: origin unknown, which system are you in? Route with /route \"{target_system}\" <your system>"
+                )
+            }
+            Err(EdjcError::OriginUnknown(_)) => {
+                format!("📍 {case}: origin unknown — set your location source")
+            }
+            Err(e @ EdjcError::CoordinatesUnavailable(_)) => {
+                warn!("No coordinates yet for case #{}: {e}", signal.case_number);
+                format!(
+                    "📡 {case}: {target_system} is known to EDSM but not yet trilaterated, ask for a nearby known system"
+                )
+            }
+            Err(e) => {
+                error!(
+                    "Failed to calculate jumps for case #{}: {e}",
+                    signal.case_number
+                );
+                format!(
+                    "❌ {case}: Jump calculation failed for {target_system} - {}",
+                    explain_error(&e)
+                )
+            }
+        }
+    }
+
+    /// Handle the /route command for testing
+    ///
+    /// With `--verbose` first, the route's waypoints are listed leg by leg.
+    /// With `--optimized`, the route is planned with the ship's maximum
+    /// (empty, fuel-optimized) jump range instead of the laden one.
+    /// `--range <ly>` anywhere in the arguments plans this one route with
+    /// that jump range, e.g. for a ship that isn't configured.
+    /// `--preset <name>` does the same with a built-in build's range
+    /// (see [`presets`]); `--range` wins when both are given.
+    /// `--from-coords x,y,z` starts the route at coordinates from the galaxy
+    /// map instead of a system EDSM has to find.
+    pub fn handle_route_command(&self, target_system: &str) -> String {
+        let (target_system, from_coords) = match split_from_coords_flag(target_system) {
+            Ok(split) => split,
+            Err(value) => {
+                return format!(
+                    "❌ --from-coords needs three numbers as x,y,z, got '{value}' (e.g. /route Colonia --from-coords 25.2,-20.9,25899.9)"
+                )
+            }
+        };
+        let (target_system, range_override) = match split_range_flag(&target_system) {
+            Ok(split) => split,
+            Err(value) => {
+                return format!(
+                    "❌ --range needs a jump range greater than 0 LY, got '{value}' (e.g. /route Colonia --range 60)"
+                )
+            }
+        };
+        let (target_system, preset) = match split_preset_flag(&target_system) {
+            Ok(split) => split,
+            Err(value) => {
+                return format!(
+                    "❌ Unknown --preset '{value}', choose one of: {}",
+                    presets::preset_names()
+                )
+            }
+        };
+        let range_override = range_override.or(preset.map(|preset| preset.laden_jump_range));
+        let jump_range = range_override.unwrap_or_else(|| self.ship_jump_range());
+        let (target_system, no_neutron) = split_word_flag(&target_system, "--no-neutron");
+        let (target_system, destination_boost) =
+            split_word_flag(&target_system, "--destination-boost");
+        let (target_system, no_white_dwarf) = split_word_flag(&target_system, "--no-whitedwarf");
+        let mut planner = self
+            .planner()
+            .with_boost_stars(!no_neutron, !no_white_dwarf);
+        if let Some(coordinates) = &from_coords {
+            planner = planner.with_origin_coordinates(coordinates.clone());
+        }
+        let (target_system, verbose) = split_verbose_flag(&target_system);
+        let (target_system, optimized) = split_optimized_flag(target_system);
+        let (target_system, verbose) = match verbose {
+            true => (target_system, true),
+            false => split_verbose_flag(target_system),
+        };
+        let (endpoints, range_delta) = split_range_delta(target_system);
+        let (system_name, origin) = split_route_endpoints(endpoints);
+        let origin = match (&from_coords, origin) {
+            (Some(_), Some(origin)) => {
+                return format!("❌ Give either the origin {origin} or --from-coords, not both")
+            }
+            (Some(coordinates), None) => Some(coordinates.name.clone()),
+            (None, origin) => origin,
+        };
+        let (system_name, origin) = (system_name.as_str(), origin.as_deref());
+        if system_name.is_empty() {
+            return "Usage: /route [--verbose] [--optimized] <system_name> [+range_delta] [--range <ly>] [--preset <name>] [--from-coords x,y,z] [--no-neutron] [--no-whitedwarf] [--destination-boost] | /route [--verbose] [--optimized] \"<system_name>\" <origin> [+range_delta] [--range <ly>] [--preset <name>] [--no-neutron] [--no-whitedwarf] [--destination-boost]".to_string();
+        }
+        if destination_boost {
+            if optimized || range_delta.is_some() {
+                return "❌ --destination-boost can't be combined with --optimized or a range change".to_string();
+            }
+            let planned = self.plan_round_trip_with(planner, system_name, jump_range, origin, true);
+            return self.round_trip_response(system_name, planned);
+        }
+        if optimized {
+            if range_delta.is_some() || range_override.is_some() {
+                return "❌ --optimized plans with max_jump_range and can't be combined with a range change".to_string();
+            }
+            return self.optimized_route_response(planner, system_name, origin, verbose);
+        }
+
+        let upgraded_range = range_delta.map(|delta| jump_range + delta);
+        if let (Some(delta), Some(range)) = (range_delta, upgraded_range) {
+            if range <= 0.0 {
+                let numbers = self.config.number_format;
+                return format!(
+                    "❌ A range change of {} LY leaves no usable jump range (currently {})",
+                    numbers.signed(delta, 1),
+                    numbers.distance(jump_range)
+                );
+            }
+        }
+
+        let planned = self.plan_route_with(&planner, system_name, jump_range, origin);
+        let current_jumps = match &planned {
+            Ok(planned) if !self.already_in_target(&planned.result) => Some(planned.result.jumps),
+            _ => None,
+        };
+        let legs = match &planned {
+            Ok(planned) if verbose && current_jumps.is_some() => Some(self.leg_breakdown(planned)),
+            _ => None,
+        };
+        let response = self.route_response(system_name, planned);
+        let response = match legs {
+            Some(legs) => format!("{response}\n{legs}"),
+            None => response,
+        };
+
+        let (Some(delta), Some(range), Some(current_jumps)) =
+            (range_delta, upgraded_range, current_jumps)
+        else {
+            return response;
+        };
+
+        match self.plan_route_with(&planner, system_name, range, origin) {
+            Ok(upgraded) => format!(
+                "{response}\n{}",
+                upgrade_comparison(
+                    current_jumps,
+                    &upgraded.result,
+                    range,
+                    delta,
+                    self.config.number_format
+                )
+            ),
+            Err(e) => {
+                warn!("Could not compare route at {range:.1} LY: {e}");
+                response
+            }
+        }
+    }
+
+    /// The /route --optimized response: the route at the ship's maximum
+    /// jump range, saying which range was used
+    fn optimized_route_response(
+        &self,
+        planner: RoutePlanner<'_>,
+        system_name: &str,
+        origin: Option<&str>,
+        verbose: bool,
+    ) -> String {
+        let planner = planner.with_max_range(true);
+        let planned = self.plan_route_with(&planner, system_name, self.ship_jump_range(), origin);
+        let note = match &planned {
+            Ok(planned) if !self.already_in_target(&planned.result) => Some(format!(
+                "📏 Planned with a {} {} range",
+                self.config.number_format.distance(planned.jump_range),
+                if self.ship().max_jump_range.is_some() {
+                    "maximum (unladen)"
+                } else {
+                    "laden"
+                }
+            )),
+            _ => None,
+        };
+        let legs = match &planned {
+            Ok(planned) if verbose && note.is_some() => Some(self.leg_breakdown(planned)),
+            _ => None,
+        };
+
+        let response = self.route_response(system_name, planned);
+        [Some(response), note, legs]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Render the /route response for a planned (or failed) route
+    fn route_response(&self, system_name: &str, planned: EdjcResult<PlannedRoute>) -> String {
+        match planned {
+            Ok(planned) if self.already_in_target(&planned.result) => {
+                format!("📍 You're already in {}", planned.result.to_system)
+            }
+            Ok(planned) if self.is_infeasible(&planned.result) => format!(
+                "🚫 {} is not feasible with current ship (needs ~{} jumps)",
+                system_name, planned.result.jumps
+            ),
+            Ok(planned) if planned.result.route_type == NO_BOOST_ROUTE => format!(
+                "⚠️ No boost route available to {} ({} from {})",
+                system_name,
+                self.config
+                    .number_format
+                    .distance(planned.result.total_distance),
+                planned.origin
+            ),
+            Ok(planned) => {
+                self.remember_route(&planned);
+                self.log_route(None, &planned);
+                let response = self.render_route(self.runtime_config().route_template(), &planned);
+                let response = self.append_warnings(response, &planned);
+                let response = self.append_route_hints(response, &planned);
+                self.append_return_estimate(response, system_name)
+            }
+            Err(e) => {
+                error!("Failed to calculate route to {system_name}: {e}");
+                format!(
+                    "❌ Route calculation failed for {system_name}: {}",
+                    explain_error(&e)
+                )
+            }
+        }
+    }
+
+    /// Plan a route with its fuel and time estimates and warnings, starting
+    /// from `origin` or the commander's location
+    pub fn route_details(
+        &self,
+        target_system: &str,
+        origin: Option<&str>,
+    ) -> EdjcResult<RouteDetails> {
+        let details = planner::plan_route(&self.planner(), target_system, origin)?;
+        self.stats.record(Counter::RoutesComputed);
+        Ok(details)
+    }
+
+    /// Serve `GET /route` on localhost at `http_api_port`, if it is set
+    ///
+    /// Returns the address listened on. The endpoint stops with [`Self::shutdown`].
+    #[cfg(feature = "http-api")]
+    pub fn start_http_api(self: &Arc<Self>) -> Result<Option<std::net::SocketAddr>> {
+        let Some(port) = self.config.http_api_port else {
+            return Ok(None);
+        };
+        let plugin = Arc::clone(self);
+        let api = http_api::HttpApi::start(port, move |to, from| plugin.route_details(to, from))?;
+        let addr = api.addr();
+        *self.http_api.lock().unwrap_or_else(|e| e.into_inner()) = Some(api);
+        Ok(Some(addr))
+    }
+
+    /// Keep a shown route for /route-last
+    fn remember_route(&self, planned: &PlannedRoute) {
+        *self.last_route.lock().unwrap_or_else(|e| e.into_inner()) = Some(planned.clone());
+    }
+
+    /// Append a calculated route to the route log, if one is configured
+    ///
+    /// A failed write is only logged, the response goes out regardless.
+    fn log_route(&self, case: Option<&str>, planned: &PlannedRoute) {
+        let Some(route_logger) = &self.route_logger else {
+            return;
+        };
+        if let Err(e) = route_logger.log(&RouteLogEntry::new(case, planned)) {
+            warn!("Could not write to the route log: {e}");
+        }
+    }
+
+    /// Handle the /route-last command: show the last route again with the
+    /// current format, without looking anything up
+    pub fn handle_route_last_command(&self) -> String {
+        let last_route = self.last_route.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(planned) = last_route.as_ref() else {
+            return "🔁 No route calculated yet this session".to_string();
+        };
+
+        let response = format!(
+            "🔁 Last route from {}: {}",
+            planned.origin,
+            self.render_route(self.runtime_config().route_template(), planned)
+        );
+        let response = self.append_warnings(response, planned);
+        self.append_route_hints(response, planned)
+    }
+
+    /// Handle the /edjc-about command: the plugin's version, commander, ship
+    /// and which data sources are enabled
+    pub fn handle_about_command(&self) -> String {
+        let ship = self.ship();
+        let edsm = if self.config.offline_mode {
+            "off (offline mode)"
+        } else {
+            "on"
+        };
+        let inara = if self.inara_client.is_some() {
+            "on"
+        } else {
+            "off"
+        };
+        format!(
+            "ℹ️ {} {PLUGIN_VERSION}: CMDR {}, ship {} ({}), EDSM {edsm}, Inara {inara}",
+            env!("PLUGIN_NAME"),
+            self.cmdr_name(),
+            ship.name,
+            self.config.number_format.distance(ship.laden_jump_range)
+        )
+    }
+
+    /// Handle the /edjc-cases command: the open cases, the most recent first
+    pub fn handle_cases_command(&self) -> String {
+        let cases = self.open_cases();
+        if cases.is_empty() {
+            return "📋 No open cases this session".to_string();
+        }
+
+        let mut response = format!("📋 Open cases ({}):", cases.len());
+        for case in cases {
+            let jumps = case
+                .jumps
+                .map_or_else(|| "no route".to_string(), jump_count);
+            response.push_str(&format!(
+                "\n  #{} {}: {}, {}",
+                case.case_number,
+                case.system_name,
+                jumps,
+                format_age(case.seen_at.elapsed())
+            ));
+        }
+        response
+    }
+
+    /// Handle the /edjc-recompute command: re-plan the open cases for the
+    /// active ship's jump range
+    ///
+    /// Cases whose system no longer resolves are skipped and keep their
+    /// previous jump count.
+    pub fn handle_recompute_command(&self) -> String {
+        let recomputed = self.recompute_open_cases();
+        if recomputed.is_empty() {
+            return "📋 No open cases to recompute".to_string();
+        }
+
+        let ship = self.ship();
+        let mut response = format!(
+            "🔄 Recomputed {} open case{} for {} ({} LY):",
+            recomputed.len(),
+            if recomputed.len() == 1 { "" } else { "s" },
+            ship.name,
+            self.config.number_format.format(ship.laden_jump_range, 1)
+        );
+        for (case, jumps) in recomputed {
+            let line = match (jumps, case.jumps) {
+                (Ok(jumps), Some(previous)) if jumps != previous => {
+                    format!("{} (was {previous})", jump_count(jumps))
+                }
+                (Ok(jumps), _) => jump_count(jumps),
+                (Err(e), _) => format!("skipped, {e}"),
+            };
+            response.push_str(&format!(
+                "\n  #{} {}: {}",
+                case.case_number, case.system_name, line
+            ));
+        }
+        response
+    }
+
+    /// Handle the /edjc-stats command: what the plugin did this session
+    pub fn handle_stats_command(&self) -> String {
+        format!("📊 This session: {}", self.stats.summary())
+    }
+
+    /// Handle the /preview command: render `result_format` against sample data
+    pub fn handle_preview_command(&self) -> String {
+        let sample = RouteDetails {
+            result: JumpResult {
+                jumps: 5,
+                total_distance: 123.5,
+                route_type: "neutron".to_string(),
+                from_system: "Sol".to_string(),
+                to_system: "Colonia".to_string(),
+                legs: Vec::new(),
+                supercharges: 0,
+                exact_jumps: None,
+            },
+            estimated_fuel_usage: 17.5,
+            estimated_time_minutes: 10.0,
+            can_use_neutron: true,
+            can_use_white_dwarf: false,
+            scoop_stops: None,
+            warnings: Vec::new(),
+            legs: Vec::new(),
+        };
+
+        format!(
+            "🔍 Preview: {}",
+            sample.render(
+                &self.settings().result_format,
+                self.config.show_fuel_estimates,
+                self.config.show_time_estimates,
+                self.config.number_format,
+                self.config.jump_rounding,
+            )
+        )
+    }
+
+    /// Handle the /ship command: list the ship profiles, or switch to one
+    ///
+    /// The choice is saved to the config file so it survives a restart.
+    pub fn handle_ship_command(&self, name: &str) -> String {
+        let name = name.trim();
+        let numbers = self.config.number_format;
+        let describe = |ship: &ShipConfig| {
+            format!(
+                "{} ({})",
+                ship.name,
+                numbers.distance(ship.laden_jump_range)
+            )
+        };
+
+        let ships = self.ships.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if name.is_empty() {
+            let active = self.ship();
+            let profiles: Vec<String> = ships
+                .iter()
+                .map(|ship| {
+                    if ship.name == active.name {
+                        format!("{} [active]", describe(ship))
+                    } else {
+                        describe(ship)
+                    }
+                })
+                .collect();
+            return format!("🚢 Ship profiles: {}", profiles.join(", "));
+        }
+
+        let Some(ship) = config::find_ship(&ships, name) else {
+            let available: Vec<&str> = ships.iter().map(|ship| ship.name.as_str()).collect();
+            return format!(
+                "❌ Unknown ship profile: {name} (available: {})",
+                available.join(", ")
+            );
+        };
+
+        *self.active_ship.write().unwrap_or_else(|e| e.into_inner()) = ship.name.clone();
+        info!("Switched to ship profile {}", ship.name);
+
+        match self.save_ships() {
+            Ok(()) => format!("🚢 Active ship: {}", describe(ship)),
+            Err(e) => {
+                warn!("Could not save the active ship: {e}");
+                format!("🚢 Active ship: {} (not saved: {e})", describe(ship))
+            }
+        }
+    }
+
+    /// Handle the /edjc-set command: change one of [`SETTABLE_KEYS`] and
+    /// save it to the config file
+    ///
+    /// `laden_jump_range` applies to the active ship profile and
+    /// `trigger_nicks` takes a comma-separated list. The changed config is
+    /// validated as a whole before anything takes effect.
+    pub fn handle_set_command(&self, args: &str) -> String {
+        let usage = format!(
+            "Usage: /edjc-set <key> <value>, keys: {}",
+            SETTABLE_KEYS.join(", ")
+        );
+        let Some((key, value)) = args.trim().split_once(char::is_whitespace) else {
+            return usage;
+        };
+        let (key, value) = (key.to_ascii_lowercase(), value.trim());
+
+        let mut config = self.runtime_config();
+        match key.as_str() {
+            "laden_jump_range" | "range" => {
+                let range = match value.trim_end_matches("LY").trim().parse::<f64>() {
+                    Ok(range) if range.is_finite() => range,
+                    _ => return format!("❌ Not a jump range in LY: {value}"),
+                };
+                let active = self.active_profile().name;
+                let Some(ship) = config.ships.iter_mut().find(|ship| ship.name == active) else {
+                    return "❌ No active ship profile to set the range of".to_string();
+                };
+                ship.laden_jump_range = range;
+            }
+            "cmdr_name" => config.cmdr_name = value.to_string(),
+            "result_format" => {
+                if value.is_empty() {
+                    return "❌ result_format must not be empty".to_string();
+                }
+                config.result_format = value.to_string();
+            }
+            "trigger_nicks" => {
+                config.trigger_nicks = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|nick| !nick.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            _ => {
+                return format!(
+                    "❌ Unknown setting: {key} (settable: {})",
+                    SETTABLE_KEYS.join(", ")
+                )
+            }
+        }
+        if let Err(e) = config::validate_config(&config) {
+            return format!("❌ {e:#}");
+        }
+
+        *self.ships.write().unwrap_or_else(|e| e.into_inner()) = config.ships.clone();
+        *self.settings.write().unwrap_or_else(|e| e.into_inner()) = Settings::from_config(&config);
+        info!("Set {key} to {value}");
+
+        let key = if key == "range" {
+            "laden_jump_range"
+        } else {
+            key.as_str()
+        };
+        match config::save_config(&config) {
+            Ok(()) => format!("⚙️ {key} = {value}"),
+            Err(e) => {
+                warn!("Could not save {key}: {e}");
+                format!("⚙️ {key} = {value} (not saved: {e})")
+            }
+        }
+    }
+
+    /// Handle the /verify-ship command: compare the active ship profile's
+    /// jump range with the one Inara reports for the CMDR's current ship
+    ///
+    /// Inara reports the maximum range, so that is compared when the profile
+    /// sets `max_jump_range`. Otherwise the laden range can only be checked
+    /// not to exceed it.
+    pub fn handle_verify_ship_command(&self) -> String {
+        let Some(inara_client) = &self.inara_client else {
+            return "❌ /verify-ship looks your ship up on Inara: set inara_api_key in edjc.toml"
+                .to_string();
+        };
+        let inara_ship = match inara_client.get_ship_info(&self.cmdr_name()) {
+            Ok(ship) => ship,
+            Err(e) => {
+                return format!(
+                    "❌ Could not get your ship from Inara: {}",
+                    explain_error(&e)
+                )
+            }
+        };
+        let ship_label = match &inara_ship.ship_name {
+            Some(name) => format!("{name} ({})", inara_ship.ship_type),
+            None => inara_ship.ship_type.clone(),
+        };
+        let Some(inara_range) = inara_ship.max_jump_range.filter(|range| *range > 0.0) else {
+            return format!("⚠️ Inara doesn't list a jump range for {ship_label}");
+        };
+
+        let profile = self.active_profile();
+        let numbers = self.config.number_format;
+        let Some(configured) = profile.max_jump_range else {
+            let laden = profile.laden_jump_range;
+            let comparison = format!(
+                "laden_jump_range {} LY in ship profile '{}', {} LY maximum on Inara for {ship_label}",
+                numbers.format(laden, 1),
+                profile.name,
+                numbers.format(inara_range, 1),
+            );
+            // Laden is below the maximum by design; only above it is wrong
+            return if laden > inara_range {
+                format!("⚠️ Laden range exceeds Inara's maximum: {comparison}. Update laden_jump_range if the ship was re-engineered")
+            } else {
+                format!("✓ Laden range is within Inara's maximum: {comparison}. Set max_jump_range to compare it exactly")
+            };
+        };
+
+        let delta = configured - inara_range;
+        let percent = delta / inara_range * 100.0;
+        let comparison = format!(
+            "max_jump_range {} LY in ship profile '{}', {} LY on Inara for {ship_label} ({}{} LY, {}{}%)",
+            numbers.format(configured, 1),
+            profile.name,
+            numbers.format(inara_range, 1),
+            if delta >= 0.0 { "+" } else { "" },
+            numbers.format(delta, 1),
+            if percent >= 0.0 { "+" } else { "" },
+            numbers.format(percent, 1),
+        );
+        if percent.abs() > self.config.ship_range_tolerance_percent {
+            format!(
+                "⚠️ Jump range differs by more than {}%: {comparison}. Update max_jump_range if the ship was re-engineered",
+                self.config.ship_range_tolerance_percent
+            )
+        } else {
+            format!("✓ Jump range matches Inara: {comparison}")
+        }
+    }
+
+    /// Handle the /import-ship command: add a ship profile from a Coriolis or
+    /// EDSY build export, replacing a profile of the same name
+    ///
+    /// The profile is saved to the config file; /ship switches to it.
+    pub fn handle_import_ship_command(&self, path: &str) -> String {
+        let path = path.trim().trim_matches('"');
+        if path.is_empty() {
+            return "Usage: /import-ship <build.json>".to_string();
+        }
+        let ship = match ship_build::import_ship_build(std::path::Path::new(path)) {
+            Ok(ship) => ship,
+            Err(e) => return format!("❌ {e:#}"),
+        };
+
+        let numbers = self.config.number_format;
+        let mut summary = format!(
+            "{} ({} laden",
+            ship.name,
+            numbers.distance(ship.laden_jump_range)
+        );
+        if let Some(max) = ship.max_jump_range {
+            summary.push_str(&format!(", {} max", numbers.distance(max)));
+        }
+        summary.push(')');
+
+        {
+            let mut ships = self.ships.write().unwrap_or_else(|e| e.into_inner());
+            match ships
+                .iter_mut()
+                .find(|profile| profile.name.eq_ignore_ascii_case(&ship.name))
+            {
+                Some(profile) => *profile = ship.clone(),
+                None => ships.push(ship.clone()),
+            }
+        }
+        info!("Imported ship profile {} from {path}", ship.name);
+
+        match self.save_ships() {
+            Ok(()) => format!(
+                "🚢 Imported {summary}, switch to it with /ship {}",
+                ship.name
+            ),
+            Err(e) => {
+                warn!("Could not save the imported ship: {e}");
+                format!("🚢 Imported {summary} for this session (not saved: {e})")
+            }
+        }
+    }
+
+    /// Handle the /capacity command: estimate cases per hour for a case distance
+    pub fn handle_capacity_command(&self, distance: &str) -> String {
+        let distance = distance.trim();
+        let distance_ly = if distance.is_empty() {
+            REPRESENTATIVE_CASE_DISTANCE_LY
+        } else {
+            match distance.parse::<f64>() {
+                Ok(ly) if ly > 0.0 => ly,
+                _ => return "Usage: /capacity [distance_ly]".to_string(),
+            }
+        };
+
+        let origin = SystemCoordinates {
+            name: "origin".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let case = SystemCoordinates {
+            name: "case".to_string(),
+            x: distance_ly,
+            ..origin.clone()
+        };
+
+        let numbers = self.config.number_format;
+        match self
+            .jump_calculator()
+            .get_route_details(&origin, &case, self.ship_jump_range())
+        {
+            Ok(details) => format!(
+                "⏱️ Capacity: ~{} cases/hour at {} LY ({} jumps, ~{} min + {} min overhead per case)",
+                numbers.format(details.cases_per_hour(self.config.case_overhead_minutes), 1),
+                numbers.format(distance_ly, 0),
+                details.result.jumps,
+                numbers.format(details.estimated_time_minutes, 0),
+                numbers.format(self.config.case_overhead_minutes, 0)
+            ),
+            Err(e) => format!("❌ Capacity estimate failed: {e}"),
+        }
+    }
+
+    /// Handle the /fuel command: estimate a route's fuel against the ship's tank
+    ///
+    /// Routes burning more than the active ship's `fuel_capacity` need fuel
+    /// scooping on the way, so the approximate number of stops is included.
+    pub fn handle_fuel_command(&self, target_system: &str) -> String {
+        let (system_name, origin) = split_route_endpoints(target_system);
+        if system_name.is_empty() {
+            return "Usage: /fuel <system_name> | /fuel \"<system_name>\" <origin>".to_string();
+        }
+
+        let jump_range = self.ship_jump_range();
+        let planned = match self.plan_route(&system_name, jump_range, origin.as_deref()) {
+            Ok(planned) => planned,
+            Err(e) => {
+                error!("Failed to estimate fuel to {system_name}: {e}");
+                return format!(
+                    "❌ Fuel estimate failed for {system_name}: {}",
+                    explain_error(&e)
+                );
+            }
+        };
+        if self.already_in_target(&planned.result) {
+            return format!("📍 You're already in {}", planned.result.to_system);
+        }
+
+        let planner = self.planner();
+        let calculator = planner.jump_calculator();
+        let details = calculator.describe_route(planned.result, jump_range);
+        let numbers = self.config.number_format;
+        let estimate = format!(
+            "⛽ ~{}t fuel for {} jumps to {}",
+            numbers.format(details.estimated_fuel_usage, 1),
+            details.result.jumps,
+            details.result.to_system
+        );
+
+        match (planner.route_options().fuel_capacity, details.scoop_stops) {
+            (Some(capacity), Some(0)) => format!("{estimate}, fits in one {capacity:.0}t tank"),
+            (Some(capacity), Some(stops)) => format!(
+                "{estimate}, more than your {capacity:.0}t tank: scoop on the way (~{stops} stops by tank size)"
+            ),
+            _ => format!("{estimate} (set fuel_capacity to check it against your tank)"),
+        }
+    }
+
+    /// Handle the /compare command: the direct, white dwarf assisted and
+    /// neutron highway routes to a system side by side
+    ///
+    /// Each scenario gets its jumps and time estimate; the fastest is marked
+    /// and the route `/route` would pick is named below them.
+    pub fn handle_compare_command(&self, args: &str) -> String {
+        let (system_name, origin) = split_route_endpoints(args);
+        if system_name.is_empty() {
+            return "Usage: /compare <system_name> | /compare \"<system_name>\" <origin>"
+                .to_string();
+        }
+
+        let planner = self.planner();
+        let jump_range = self.ship_jump_range();
+        let compared = self
+            .plan_route_with(&planner, &system_name, jump_range, origin.as_deref())
+            .and_then(|planned| {
+                let comparison = planner.jump_calculator().compare_routes(
+                    &planned.from,
+                    &planned.to,
+                    jump_range,
+                    &planner.route_options(),
+                    Some(&planned.result),
+                )?;
+                Ok((planned, comparison))
+            });
+        let (planned, comparison) = match compared {
+            Ok(compared) => compared,
+            Err(e) => {
+                error!("Failed to compare routes to {system_name}: {e}");
+                return format!(
+                    "❌ Route comparison failed for {system_name}: {}",
+                    explain_error(&e)
+                );
+            }
+        };
+        if self.already_in_target(&planned.result) {
+            return format!("📍 You're already in {}", planned.result.to_system);
+        }
+
+        let numbers = self.config.number_format;
+        let fastest = comparison.fastest().route_type.as_str();
+        let mut lines = vec![format!(
+            "⚖️ {} → {} ({}, {} LY range):",
+            comparison.from_system,
+            comparison.to_system,
+            numbers.distance(comparison.total_distance),
+            numbers.format(planned.jump_range, 1)
+        )];
+        lines.extend(comparison.scenarios().iter().map(|scenario| {
+            let marker = if scenario.route_type == fastest {
+                " ⏩ fastest"
+            } else {
+                ""
+            };
+            format!("  {}{marker}", scenario.describe(numbers))
+        }));
+        lines.push(format!(
+            "🧭 /route plans {} jumps via {}",
+            planned.result.jumps, planned.result.route_type
+        ));
+        self.append_warnings(lines.join("\n"), &planned)
+    }
+
+    /// Handle the /roundtrip command: the route to a system and back again
+    ///
+    /// Reports the combined jumps and distance, then each way on its own line.
+    /// With `--destination-boost` the way back starts supercharged at the
+    /// destination.
+    pub fn handle_roundtrip_command(&self, args: &str) -> String {
+        let (args, destination_boost) = split_word_flag(args, "--destination-boost");
+        let (system_name, origin) = split_route_endpoints(&args);
+        if system_name.is_empty() {
+            return "Usage: /roundtrip [--destination-boost] <system_name> | /roundtrip [--destination-boost] \"<system_name>\" <origin>"
+                .to_string();
+        }
+
+        let planned = self.plan_round_trip(&system_name, origin.as_deref(), destination_boost);
+        self.round_trip_response(&system_name, planned)
+    }
+
+    /// The /roundtrip response for the way there and back, or the error
+    fn round_trip_response(
+        &self,
+        system_name: &str,
+        planned: EdjcResult<(PlannedRoute, PlannedRoute)>,
+    ) -> String {
+        let (there, back) = match planned {
+            Ok(legs) => legs,
+            Err(e) => {
+                error!("Failed to plan a round trip to {system_name}: {e}");
+                return format!(
+                    "❌ Round trip calculation failed for {system_name}: {}",
+                    explain_error(&e)
+                );
+            }
+        };
+        if self.already_in_target(&there.result) {
+            return format!("📍 You're already in {}", there.result.to_system);
+        }
+
+        let numbers = self.config.number_format;
+        let leg = |planned: &PlannedRoute| {
+            format!(
+                "{} jumps ({}) via {}",
+                planned.result.jumps,
+                numbers.distance(planned.result.total_distance),
+                planned.result.route_type
+            )
+        };
+        let response = format!(
+            "🔁 Round trip {} ⇄ {}: {} jumps ({})\n➡️ There: {}\n↩️ Back: {}",
+            there.result.from_system,
+            there.result.to_system,
+            there.result.jumps + back.result.jumps,
+            numbers.distance(there.result.total_distance + back.result.total_distance),
+            leg(&there),
+            leg(&back)
+        );
+        self.append_warnings(response, &there)
+    }
+
+    /// Plan the way to a system and, separately, the way back to the origin
+    ///
+    /// The return is planned from the target's end, so it can supercharge at
+    /// other stars than the way there (or at none). With `destination_boost`
+    /// it starts with a supercharge at the target; the way there doesn't.
+    fn plan_round_trip(
+        &self,
+        target_system: &str,
+        origin: Option<&str>,
+        destination_boost: bool,
+    ) -> EdjcResult<(PlannedRoute, PlannedRoute)> {
+        self.plan_round_trip_with(
+            self.planner(),
+            target_system,
+            self.ship_jump_range(),
+            origin,
+            destination_boost,
+        )
+    }
+
+    /// [`Self::plan_round_trip`] with a given planner and jump range
+    fn plan_round_trip_with(
+        &self,
+        planner: RoutePlanner<'_>,
+        target_system: &str,
+        jump_range: f64,
+        origin: Option<&str>,
+        destination_boost: bool,
+    ) -> EdjcResult<(PlannedRoute, PlannedRoute)> {
+        let there = self.plan_route_with(&planner, target_system, jump_range, origin)?;
+        // Both ends are known now, and the start may be coordinates given by
+        // hand rather than a system that can be looked up
+        let back = planner
+            .with_origin_coordinates(there.to.clone())
+            .with_target_coordinates(there.from.clone())
+            .with_origin_boost(destination_boost)
+            .plan_from(&there.from.name, jump_range, Some(there.to.name.clone()))?;
+        self.stats.record(Counter::RoutesComputed);
+        Ok((there, back))
+    }
+
+    /// Handle the /prefetch command: warm the cache for a batch of systems
+    ///
+    /// Accepts system names separated by commas (or by spaces for single-word
+    /// names), or `@<path>` to read one system per line from a file.
+    pub fn handle_prefetch_command(&self, args: &str) -> String {
+        let args = args.trim();
+        let systems = if let Some(path) = args.strip_prefix('@') {
+            match std::fs::read_to_string(path.trim()) {
+                Ok(contents) => parse_system_list(&contents),
+                Err(e) => return format!("❌ Could not read prefetch file {}: {e}", path.trim()),
+            }
+        } else {
+            parse_system_list(args)
+        };
+
+        if systems.is_empty() {
+            return "Usage: /prefetch <system1>, <system2>, ... | /prefetch @<file>".to_string();
+        }
+        if self.config.offline_mode {
+            return "❌ Prefetch is not available in offline mode".to_string();
+        }
+
+        match self.edsm_client.prefetch_systems(&systems) {
+            Ok(report) => {
+                let mut response = format!(
+                    "📦 Prefetched {} systems: {} newly cached, {} already cached",
+                    systems.len(),
+                    report.newly_cached,
+                    report.already_cached
+                );
+                if !report.not_found.is_empty() {
+                    response.push_str(&format!(" (not found: {})", report.not_found.join(", ")));
+                }
+                response
+            }
+            Err(e) => {
+                error!("Failed to prefetch systems: {e}");
+                format!("❌ Prefetch failed: {e}")
+            }
+        }
+    }
+
+    /// Handle the /edjc-refresh command: forget the commander's cached
+    /// location, or with `all` everything both clients have cached
+    pub fn handle_refresh_command(&self, args: &str) -> String {
+        match args.trim() {
+            "" => {
+                let cmdr_name = &self.cmdr_name();
+                self.edsm_client.invalidate_location(cmdr_name);
+                if let Some(inara_client) = &self.inara_client {
+                    inara_client.invalidate_location(cmdr_name);
+                }
+                format!("🔄 Forgot CMDR {cmdr_name}'s cached location")
+            }
+            "all" => {
+                self.edsm_client.clear_cache();
+                if let Some(inara_client) = &self.inara_client {
+                    inara_client.clear_cache();
+                }
+                "🔄 Cleared all cached systems and locations".to_string()
+            }
+            _ => "Usage: /edjc-refresh [all]".to_string(),
+        }
+    }
+
+    /// Handle the /sysinfo command
+    pub fn handle_sysinfo_command(&self, system_name: &str) -> String {
+        let system_name = system_name.trim();
+        if system_name.is_empty() {
+            return "Usage: /sysinfo <system_name>".to_string();
+        }
+
+        // Only EDSM knows more than where a system is
+        let details = if self.system_source.is_none() && !self.config.offline_mode {
+            self.edsm_client
+                .get_system_info(system_name)
+                .map(|mut info| {
+                    info.distance_from_reference = self.distance_from_reference(&info.coordinates);
+                    (info.details(), info.distance_from_reference)
+                })
+        } else {
+            self.systems()
+                .get_system_coordinates(system_name)
+                .map(|coords| {
+                    (
+                        format!("📍 {}", coords.summary()),
+                        self.distance_from_reference(&coords),
+                    )
+                })
+        };
+
+        match details {
+            Ok((details, Some(distance))) => {
+                format!("{details}\n{}", self.reference_line(distance))
+            }
+            Ok((details, None)) => details,
+            Err(e) => {
+                error!("Failed to look up {system_name}: {e}");
+                format!(
+                    "❌ System lookup failed for {system_name}: {}",
+                    explain_error(&e)
+                )
+            }
+        }
+    }
+
+    /// Handle the /distance command: straight-line distance between two systems
+    ///
+    /// Names are split like /route's: `/distance "Sagittarius A*" Deciat`
+    /// measures between the two, a single name measures from the commander.
+    pub fn handle_distance_command(&self, args: &str) -> String {
+        let (system_name, origin) = split_route_endpoints(args);
+        if system_name.is_empty() {
+            return "Usage: /distance <system> | /distance \"<system>\" <other_system>".to_string();
+        }
+
+        let Some(origin) = origin.or_else(|| self.commander_location()) else {
+            return format!(
+                "❌ CMDR position unknown, use /distance \"{system_name}\" <other_system>"
+            );
+        };
+
+        let distance = self
+            .systems()
+            .get_system_coordinates(&origin)
+            .and_then(|from| {
+                let to = self.systems().get_system_coordinates(&system_name)?;
+                Ok((from.distance_to(&to), from, to))
+            });
+        match distance {
+            Ok((distance, from, to)) => {
+                format!(
+                    "📏 {} → {}: {}",
+                    from.name,
+                    to.name,
+                    self.config.number_format.distance(distance)
+                )
+            }
+            Err(e) => {
+                error!("Failed to measure {origin} to {system_name}: {e}");
+                format!(
+                    "❌ Distance lookup failed for {system_name}: {}",
+                    explain_error(&e)
+                )
+            }
+        }
+    }
+
+    /// Handle the /neutron command: the nearest neutron star to a system (the
+    /// commander's location by default)
+    pub fn handle_neutron_command(&self, system_name: &str) -> String {
+        if self.config.offline_mode {
+            return "❌ Neutron star lookups are not available in offline mode".to_string();
+        }
+
+        let system_name = system_name.trim();
+        let Some(origin) = Some(system_name.to_string())
+            .filter(|name| !name.is_empty())
+            .or_else(|| self.commander_location())
+        else {
+            return "❌ CMDR position unknown, use /neutron <system>".to_string();
+        };
+
+        // Only EDSM can search the sphere, but the origin comes from the
+        // configured source like every other lookup
+        let nearest = self
+            .systems()
+            .get_system_coordinates(&origin)
+            .and_then(|from| {
+                let star = self
+                    .edsm_client
+                    .nearest_neutron_star(&from, edsm::SPHERE_RADIUS_LY)?;
+                Ok((from, star))
+            });
+        match nearest {
+            Ok((from, Some(star))) => format!(
+                "💫 Nearest neutron star to {}: {} ({})",
+                from.name,
+                star.name,
+                self.config.number_format.distance(from.distance_to(&star))
+            ),
+            Ok((from, None)) => format!(
+                "⚠️ No neutron stars within {} LY of {}",
+                self.config.number_format.format(edsm::SPHERE_RADIUS_LY, 0),
+                from.name
+            ),
+            Err(e) => {
+                error!("Failed to find a neutron star near {origin}: {e}");
+                format!(
+                    "❌ Neutron star lookup failed for {origin}: {}",
+                    explain_error(&e)
+                )
+            }
+        }
+    }
+
+    /// Calculate jumps to target system and return both result and origin system
+    #[cfg(test)]
+    fn calculate_jumps_with_origin(&self, target_system: &str) -> EdjcResult<PlannedRoute> {
+        self.plan_route(target_system, self.ship_jump_range(), None)
+    }
+
+    /// Plan a route to a case's system, or fail with
+    /// [`EdjcError::OriginUnknown`] when the commander's location is unknown
+    /// and `on_missing_location` isn't "fallback"
+    fn plan_case_route(&self, target_system: &str) -> EdjcResult<PlannedRoute> {
+        self.plan_case_route_with(target_system, SideEffects::Apply)
+    }
+
+    /// [`Self::plan_case_route`], counting the route in the stats only with
+    /// [`SideEffects::Apply`]
+    fn plan_case_route_with(
+        &self,
+        target_system: &str,
+        effects: SideEffects,
+    ) -> EdjcResult<PlannedRoute> {
+        let planner = self.planner();
+        let deadline = planner.deadline();
+        let origin = self.commander_location_with(&planner, &deadline, effects);
+        if origin.is_none() && self.config.on_missing_location != MissingLocationPolicy::Fallback {
+            return Err(EdjcError::OriginUnknown(self.cmdr_name()));
+        }
+
+        // Without an origin, the planner starts from fallback_origin
+        let planned =
+            planner.plan_from_by(target_system, self.ship_jump_range(), origin, &deadline)?;
+        if effects == SideEffects::Apply {
+            self.stats.record(Counter::RoutesComputed);
+        }
+        Ok(planned)
+    }
+
+    /// Route planner for the active ship, wired to the plugin's clients
+    fn planner(&self) -> RoutePlanner<'_> {
+        let planner = RoutePlanner::new(&self.config, self.systems())
+            .with_ship(self.ship())
+            .with_cmdr_name(self.cmdr_name());
+        if self.config.offline_mode {
+            return planner;
+        }
+
+        let mut planner = planner
+            .with_neutron_corridor(&self.edsm_client)
+            .with_location_sources(self.location_sources());
+        if let Some(spansh_client) = &self.spansh_client {
+            planner = planner.with_spansh(spansh_client);
+        }
+        planner
+    }
+
+    /// Sources for the commander's location in `location_source_priority`
+    /// order, leaving out those that aren't configured
+    fn location_sources(&self) -> Vec<&dyn SystemSource> {
+        self.config
+            .location_source_priority
+            .iter()
+            .filter_map(|source| match source {
+                LocationSource::Journal => self
+                    .journal_location
+                    .as_ref()
+                    .map(|journal| journal as &dyn SystemSource),
+                LocationSource::Edsm => Some(self.systems()),
+                LocationSource::Inara => self
+                    .inara_client
+                    .as_ref()
+                    .map(|inara| inara as &dyn SystemSource),
+            })
+            .collect()
+    }
+
+    /// Look up the commander's current location from the sources in
+    /// `location_source_priority`
+    fn commander_location(&self) -> Option<String> {
+        let planner = self.planner();
+        self.commander_location_by(&planner, &planner.deadline())
+    }
+
+    /// [`Self::commander_location`] within a route's planning deadline
+    fn commander_location_by(
+        &self,
+        planner: &RoutePlanner<'_>,
+        deadline: &Deadline,
+    ) -> Option<String> {
+        self.commander_location_with(planner, deadline, SideEffects::Apply)
+    }
+
+    /// [`Self::commander_location_by`], showing the notice for a private
+    /// profile or missing API key only with [`SideEffects::Apply`]
+    fn commander_location_with(
+        &self,
+        planner: &RoutePlanner<'_>,
+        deadline: &Deadline,
+        effects: SideEffects,
+    ) -> Option<String> {
+        if self.config.offline_mode {
+            return None;
+        }
+
+        match planner.locate_commander_by(deadline) {
+            Ok(system) => Some(system),
+            Err(_) if effects == SideEffects::Skip => None,
+            Err(e @ (EdjcError::ProfilePrivate(_) | EdjcError::ApiKeyMissing(_))) => {
+                // Once per session; every route would repeat it otherwise
+                if !self.private_profile_noticed.swap(true, Ordering::Relaxed) {
+                    self.emit(&format!("⚠️ {e}: {}", e.hint().unwrap_or_default()));
+                }
+                None
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// Plan a route to the target system from the commander's location for a jump range
+    fn plan_route(
+        &self,
+        target_system: &str,
+        jump_range: f64,
+        origin: Option<&str>,
+    ) -> EdjcResult<PlannedRoute> {
+        self.plan_route_with(&self.planner(), target_system, jump_range, origin)
+    }
+
+    /// Plan a route like [`Self::plan_route`] with a specific planner
+    fn plan_route_with(
+        &self,
+        planner: &RoutePlanner<'_>,
+        target_system: &str,
+        jump_range: f64,
+        origin: Option<&str>,
+    ) -> EdjcResult<PlannedRoute> {
+        // Locating the commander counts towards the route's deadline
+        let deadline = planner.deadline();
+        let origin = origin
+            .map(str::to_string)
+            .or_else(|| self.commander_location_by(planner, &deadline));
+        let planned = planner.plan_from_by(target_system, jump_range, origin, &deadline)?;
+        self.stats.record(Counter::RoutesComputed);
+        Ok(planned)
+    }
+
+    /// Render a planned route with a format template, filling in the
+    /// `{region}` and `{range}` placeholders and the enabled estimates
+    fn render_route(&self, template: &str, planned: &PlannedRoute) -> String {
+        let details = self
+            .jump_calculator()
+            .describe_route(planned.result.clone(), planned.jump_range);
+        let range = self.config.number_format.format(planned.jump_range, 1);
+
+        details
+            .render(
+                template,
+                self.config.show_fuel_estimates,
+                self.config.show_time_estimates,
+                self.config.number_format,
+                self.config.jump_rounding,
+            )
+            .replace("{region}", planned.region.as_str())
+            .replace("{range:.1}", &range)
+            .replace("{range}", &range)
+    }
+
+    /// The route's legs, one per line, for `/route --verbose`
+    fn leg_breakdown(&self, planned: &PlannedRoute) -> String {
+        let details = self
+            .jump_calculator()
+            .describe_route(planned.result.clone(), planned.jump_range);
+        let lines = details.leg_breakdown();
+        if lines.is_empty() {
+            return "🧭 No waypoints for an estimated route".to_string();
+        }
+        lines
+            .iter()
+            .map(|line| format!("🧭 {line}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Append each route warning to a response on its own line
+    fn append_warnings(&self, response: String, planned: &PlannedRoute) -> String {
+        planned.warnings.iter().fold(response, |response, warning| {
+            format!("{response}\n⚠️ {warning}")
+        })
+    }
+
+    /// Append route hints to a response, if enabled
+    fn append_route_hints(&self, response: String, planned: &PlannedRoute) -> String {
+        if !self.config.show_route_hints || planned.hints.is_empty() {
+            return response;
+        }
+
+        format!("{response}\n🧭 Note: {}", planned.hints.join("; "))
+    }
+
+    /// Append the nearest populated system to the notice for a deep-space
+    /// case, if `show_nearest_station` is set
+    ///
+    /// Needs EDSM's sphere search, so offline mode and injected sources go
+    /// without.
+    fn append_nearest_station(&self, response: String, planned: &PlannedRoute) -> String {
+        if !self.config.show_nearest_station
+            || planned.region != Region::DeepSpace
+            || self.config.offline_mode
+            || self.system_source.is_some()
+        {
+            return response;
+        }
+
+        let case = &planned.to;
+        match self
+            .edsm_client
+            .nearest_populated_system(case, edsm::SPHERE_RADIUS_LY)
+        {
+            Ok(Some(station)) => format!(
+                "{response}\n🛰️ nearest station: {} ({})",
+                station.name,
+                self.config
+                    .number_format
+                    .distance(case.distance_to(&station))
+            ),
+            Ok(None) => format!(
+                "{response}\n🛰️ nearest station: none within {} LY",
+                self.config.number_format.format(edsm::SPHERE_RADIUS_LY, 0)
+            ),
+            Err(e) => {
+                warn!("Could not look for a station near {}: {e}", case.name);
+                response
+            }
+        }
+    }
+
+    /// Distance from `reference_system` to `target`
+    ///
+    /// The reference is looked up once; until that succeeds, every call
+    /// tries again. `None` when it can't be found, or is the target itself.
+    fn distance_from_reference(&self, target: &SystemCoordinates) -> Option<f64> {
+        let reference = &self.config.reference_system;
+        if target.name.eq_ignore_ascii_case(reference.trim()) {
+            return None;
+        }
+
+        let coordinates = match self.reference_coordinates.get() {
+            Some(coordinates) => coordinates,
+            None => match self.systems().get_system_coordinates(reference) {
+                Ok(coordinates) => self.reference_coordinates.get_or_init(|| coordinates),
+                Err(e) => {
+                    warn!("Could not look up the reference system {reference}: {e}");
+                    return None;
+                }
+            },
+        };
+        Some(coordinates.distance_to(target))
+    }
+
+    /// Append the target's distance from `reference_system` to a response
+    ///
+    /// Left out for routes from the reference itself, whose distance the
+    /// route already gives.
+    fn append_reference_distance(&self, response: String, planned: &PlannedRoute) -> String {
+        let reference = self.config.reference_system.trim();
+        if planned.from.name.eq_ignore_ascii_case(reference) {
+            return response;
+        }
+        match self.distance_from_reference(&planned.to) {
+            Some(distance) => format!("{response}\n{}", self.reference_line(distance)),
+            None => response,
+        }
+    }
+
+    /// "📏 22,000.0 LY from Fuelum"
+    fn reference_line(&self, distance: f64) -> String {
+        format!(
+            "📏 {} from {}",
+            self.config.number_format.distance(distance),
+            self.reference_coordinates
+                .get()
+                .map_or(self.config.reference_system.as_str(), |reference| {
+                    reference.name.as_str()
+                })
+        )
+    }
+
+    /// Append the return-to-bubble estimate to a response, if enabled
+    fn append_return_estimate(&self, response: String, target_system: &str) -> String {
+        if !self.config.show_return_estimate {
+            return response;
+        }
+
+        let estimate = self
+            .systems()
+            .get_system_coordinates(target_system)
+            .and_then(|target| {
+                let home = self
+                    .systems()
+                    .get_system_coordinates(&self.config.fallback_origin)?;
+                Ok(self.return_estimate(&target, &home))
+            });
+
+        match estimate {
+            Ok(Some(line)) => format!("{response}\n{line}"),
+            Ok(None) => response,
+            Err(e) => {
+                warn!("Could not estimate return trip from {target_system}: {e}");
+                response
+            }
+        }
+    }
+
+    /// Estimate the jumps from a case system back to the home origin
+    ///
+    /// Returns `None` when `show_return_estimate` is disabled.
+    fn return_estimate(
+        &self,
+        target: &SystemCoordinates,
+        home: &SystemCoordinates,
+    ) -> Option<String> {
+        if !self.config.show_return_estimate {
+            return None;
+        }
+
+        let result = self
+            .jump_calculator()
+            .calculate_route(target, home, self.ship_jump_range())
+            .ok()?;
+
+        Some(format!(
+            "↩️ Return to {}: {} jumps ({}) via {} route",
+            result.to_system,
+            result.jumps,
+            self.config.number_format.distance(result.total_distance),
+            result.route_type
+        ))
+    }
+}
+
+/// Whether handling a message may change the plugin's state (announced and
+/// open cases, stats, the route log, /route-last) or, for
+/// [`EdJumpCalculator::process_message_preview`], only work out the result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SideEffects {
+    Apply,
+    Skip,
+}
+
+/// Case number of a partially parsed RATSIGNAL that didn't give one
+pub const UNKNOWN_CASE: &str = "?";
+
+/// Loose patterns for the parts of a RATSIGNAL, for when none of the full
+/// formats match
+#[derive(Debug)]
+struct PartialRatsignal {
+    system: Regex,
+    case: Regex,
+    cmdr: Regex,
+}
+
+impl PartialRatsignal {
+    fn new() -> Self {
+        let regex = |pattern| Regex::new(pattern).expect("built-in partial pattern");
+        Self {
+            system: regex(config::PARTIAL_RATSIGNAL_PATTERN),
+            case: regex(r"(?i)\bcase\s*#\s*(?P<case>\d+)"),
+            cmdr: regex(r#"\bCMDR\s+(?P<cmdr>[^"\-–—~|]+?)\s*(?:[-–—~|]|System:|$)"#),
+        }
+    }
+
+    /// The signal, if at least its system can be found
+    fn parse(&self, message: &str) -> Option<RatsignalInfo> {
+        let captures = self.system.captures(message)?;
+        let system_name = captures["system"].trim().to_string();
+        if system_name.is_empty() {
+            return None;
+        }
+        let system_info = captures
+            .name("info")
+            .map(|info| info.as_str().trim().to_string())
+            .filter(|info| !info.is_empty());
+        let find = |regex: &Regex, name: &str| {
+            regex
+                .captures(message)
+                .map(|captures| captures[name].trim().to_string())
+        };
+
+        Some(RatsignalInfo {
+            case_number: find(&self.case, "case").unwrap_or_else(|| UNKNOWN_CASE.to_string()),
+            platform: message
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .find(|word| is_platform_tag(word))
+                .map_or_else(|| "Unknown".to_string(), str::to_ascii_uppercase),
+            mode: None,
+            cmdr_name: find(&self.cmdr, "cmdr").unwrap_or_else(|| "Unknown".to_string()),
+            system_name,
+            landmark: system_info.as_deref().and_then(LandmarkHint::parse),
+            system_info,
+            language: None,
+            raw_message: message.to_string(),
+        })
+    }
+}
+
+/// The built-in case closed and assigned formats, closed ones first
+fn case_update_regexes() -> Vec<(CaseEvent, Regex)> {
+    let closed = config::CASE_CLOSED_PATTERNS.map(|pattern| (CaseEvent::Closed, pattern));
+    let assigned = config::CASE_ASSIGNED_PATTERNS.map(|pattern| (CaseEvent::Assigned, pattern));
+    closed
+        .into_iter()
+        .chain(assigned)
+        .map(|(event, pattern)| (event, Regex::new(pattern).expect("built-in case pattern")))
+        .collect()
+}
+
+/// Map a RATSIGNAL mode marker onto the game mode name
+///
+/// MechaSqueak always sends upper-case markers; anything else is ignored.
+fn game_mode(tag: &str) -> Option<&'static str> {
+    match tag {
+        "ODY" => Some("Odyssey"),
+        "HOR" => Some("Horizons"),
+        "LIVE" => Some("Live"),
+        _ => None,
+    }
+}
+
+/// Whether a RATSIGNAL tag names a platform ("PC", "PS4", "XB", ...)
+fn is_platform_tag(tag: &str) -> bool {
+    let tag = tag.to_ascii_uppercase();
+    tag == "PC"
+        || tag == "XB"
+        || tag == "XBOX"
+        || (tag.starts_with("PS") && tag[2..].chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Pick the language code out of a RATSIGNAL language suffix such as
+/// "English (United States) (en-US) (ODY_SIGNAL)", falling back to the name
+fn parse_language(suffix: &str) -> Option<String> {
+    let code = suffix.split(['(', ')']).map(str::trim).find(|part| {
+        let mut pieces = part.split('-');
+        pieces
+            .next()
+            .is_some_and(|lang| lang.len() == 2 && lang.chars().all(|c| c.is_ascii_lowercase()))
+            && pieces.all(|region| {
+                !region.is_empty() && region.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+    });
+
+    let name = suffix.split('(').next().unwrap_or("").trim();
+    code.or((!name.is_empty()).then_some(name))
+        .map(str::to_string)
+}
+
+/// Parse a list of system names separated by commas or newlines, or by
+/// whitespace when neither is present; `#` starts a comment line
+fn parse_system_list(input: &str) -> Vec<String> {
+    let names: Vec<&str> = if input.contains([',', '\n']) {
+        input.split([',', '\n']).collect()
+    } else {
+        input.split_whitespace().collect()
+    };
+
+    names
+        .into_iter()
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && !name.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Split a trailing jump range delta ("Colonia +13") off a /route argument
+fn split_range_delta(input: &str) -> (&str, Option<f64>) {
+    let input = input.trim();
+    if let Some((system_name, last)) = input.rsplit_once(char::is_whitespace) {
+        if last.starts_with(['+', '-']) {
+            if let Ok(delta) = last.parse::<f64>() {
+                return (system_name.trim_end(), Some(delta));
+            }
+        }
+    }
+    (input, None)
+}
+
+/// An error message, followed by what to do about it when that is obvious
+/// ("check the spelling" for unknown systems, "EDSM unreachable" for network
+/// failures)
+fn explain_error(e: &EdjcError) -> String {
+    match e.hint() {
+        Some(hint) => format!("{e} ({hint})"),
+        None => e.to_string(),
+    }
+}
+
+/// "1 jump" or "5 jumps"
+fn jump_count(jumps: u32) -> String {
+    if jumps == 1 {
+        "1 jump".to_string()
+    } else {
+        format!("{jumps} jumps")
+    }
+}
+
+/// How long ago something happened, to the minute: "just now", "12m ago",
+/// "2h 05m ago"
+fn format_age(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    match minutes {
+        0 => "just now".to_string(),
+        1..=59 => format!("{minutes}m ago"),
+        _ => format!("{}h {:02}m ago", minutes / 60, minutes % 60),
+    }
+}
+
+/// Strip a leading `--verbose` (or `-v`) flag from /route arguments
+fn split_verbose_flag(input: &str) -> (&str, bool) {
+    split_flag(input, &["--verbose", "-v"])
+}
+
+/// Strip a leading `--optimized` (or `-o`) flag from /route arguments
+fn split_optimized_flag(input: &str) -> (&str, bool) {
+    split_flag(input, &["--optimized", "-o"])
+}
+
+/// Strip a leading flag, given by any of its spellings, from command arguments
+fn split_flag<'a>(input: &'a str, spellings: &[&str]) -> (&'a str, bool) {
+    let input = input.trim_start();
+    for flag in spellings {
+        if let Some(rest) = input.strip_prefix(flag) {
+            if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+                return (rest, true);
+            }
+        }
+    }
+    (input, false)
+}
+
+/// Strip a flag from anywhere in /route arguments, returning whether it was
+/// given
+fn split_word_flag(input: &str, flag: &str) -> (String, bool) {
+    let mut tokens: Vec<&str> = input.split_whitespace().collect();
+    let before = tokens.len();
+    tokens.retain(|token| *token != flag);
+    if tokens.len() == before {
+        return (input.to_string(), false);
+    }
+    (tokens.join(" "), true)
+}
+
+/// Strip a `--range <ly>` flag from anywhere in /route arguments
+///
+/// Errors with the given value (empty when missing) unless it is a positive
+/// number of light years.
+fn split_range_flag(input: &str) -> std::result::Result<(String, Option<f64>), String> {
+    let mut tokens: Vec<&str> = input.split_whitespace().collect();
+    let Some(flag) = tokens.iter().position(|token| *token == "--range") else {
+        return Ok((input.to_string(), None));
+    };
+
+    let value = tokens.get(flag + 1).copied().unwrap_or_default();
+    match value.parse::<f64>() {
+        Ok(range) if range > 0.0 && range.is_finite() => {
+            tokens.drain(flag..=flag + 1);
+            Ok((tokens.join(" "), Some(range)))
+        }
+        _ => Err(value.to_string()),
+    }
+}
+
+/// Strip a `--preset <name>` flag from anywhere in /route arguments
+///
+/// Errors with the given name (empty when missing) unless it is a built-in preset.
+fn split_preset_flag(
+    input: &str,
+) -> std::result::Result<(String, Option<&'static presets::ShipPreset>), String> {
+    let mut tokens: Vec<&str> = input.split_whitespace().collect();
+    let Some(flag) = tokens.iter().position(|token| *token == "--preset") else {
+        return Ok((input.to_string(), None));
+    };
+
+    let value = tokens.get(flag + 1).copied().unwrap_or_default();
+    match presets::find_preset(value) {
+        Some(preset) => {
+            tokens.drain(flag..=flag + 1);
+            Ok((tokens.join(" "), Some(preset)))
+        }
+        None => Err(value.to_string()),
+    }
+}
+
+/// Strip a `--from-coords x,y,z` flag from anywhere in /route arguments,
+/// giving the coordinates as a system named "Manual"
+///
+/// Errors with the given value (empty when missing) unless it is three
+/// comma-separated numbers.
+fn split_from_coords_flag(
+    input: &str,
+) -> std::result::Result<(String, Option<SystemCoordinates>), String> {
+    let mut tokens: Vec<&str> = input.split_whitespace().collect();
+    let Some(flag) = tokens.iter().position(|token| *token == "--from-coords") else {
+        return Ok((input.to_string(), None));
+    };
+
+    let value = tokens.get(flag + 1).copied().unwrap_or_default();
+    let numbers: Option<Vec<f64>> = value
+        .split(',')
+        .map(|number| number.trim().parse::<f64>().ok().filter(|n| n.is_finite()))
+        .collect();
+    let Some(&[x, y, z]) = numbers.as_deref() else {
+        return Err(value.to_string());
+    };
+
+    tokens.drain(flag..=flag + 1);
+    let origin = SystemCoordinates {
+        name: "Manual".to_string(),
+        x,
+        y,
+        z,
+        has_neutron_star: false,
+        has_white_dwarf: false,
+        primary_star_kind: None,
+        is_hidden: false,
+        required_permit: None,
+    };
+    Ok((tokens.join(" "), Some(origin)))
+}
+
+/// Split /route arguments into the target and an optional explicit origin
+///
+/// An origin is only read when quotes are used, so unquoted multi-word names
+/// keep working: `"Sagittarius A*" Deciat` plots Deciat → Sagittarius A*,
+/// while `Sagittarius A*` is a single target.
+fn split_route_endpoints(input: &str) -> (String, Option<String>) {
+    let input = input.trim();
+    if !input.contains('"') {
+        return (input.to_string(), None);
+    }
+
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        rest = &rest[start..];
+        let (token, remainder) = match rest.strip_prefix('"') {
+            // An unterminated quote runs to the end of the line
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest
+                .split_once(|c: char| c.is_whitespace() || c == '"')
+                .map_or((rest, ""), |(word, _)| (word, &rest[word.len()..])),
+        };
+        tokens.push(token.trim().to_string());
+        rest = remainder;
+    }
+
+    let mut tokens = tokens.into_iter().filter(|token| !token.is_empty());
+    let target = tokens.next().unwrap_or_default();
+    let origin = tokens.collect::<Vec<_>>().join(" ");
+    (target, (!origin.is_empty()).then_some(origin))
+}
+
+/// Describe how a different jump range changes the number of jumps
+fn upgrade_comparison(
+    current_jumps: u32,
+    upgraded: &JumpResult,
+    upgraded_range: f64,
+    delta: f64,
+    numbers: NumberFormat,
+) -> String {
+    let change = match upgraded.jumps.cmp(&current_jumps) {
+        std::cmp::Ordering::Less => format!("{} fewer", current_jumps - upgraded.jumps),
+        std::cmp::Ordering::Greater => format!("{} more", upgraded.jumps - current_jumps),
+        std::cmp::Ordering::Equal => "no change".to_string(),
+    };
+
+    format!(
+        "🔧 With {} range ({}): {} jumps ({})",
+        numbers.distance(upgraded_range),
+        numbers.signed(delta, 1),
+        upgraded.jumps,
+        change
+    )
+}
+
+/// Commands registered with HexChat: name, callback and help text
+const COMMANDS: [(&str, hexchat::HexChatCallback, &str); 21] = [
+    (
+        "route",
+        route_command_callback,
+        "Usage: /route [--verbose] [--optimized] <system> [+range_delta] [--range <ly>] [--preset <name>] [--from-coords x,y,z] [--no-neutron] [--no-whitedwarf] [--destination-boost] | /route \"<system>\" <origin>, calculate jumps to a system (--optimized uses max_jump_range, --range plans with another jump range, --preset with a typical build's, --from-coords starts at galaxy map coordinates, --no-neutron and --no-whitedwarf never supercharge at those stars, --destination-boost adds the way back starting with a supercharge at the destination)",
+    ),
+    (
+        "distance",
+        distance_command_callback,
+        "Usage: /distance <system> | /distance \"<system>\" <other_system>, straight-line distance in LY",
+    ),
+    (
+        "neutron",
+        neutron_command_callback,
+        "Usage: /neutron [system], find the nearest neutron star to supercharge at",
+    ),
+    (
+        "sysinfo",
+        sysinfo_command_callback,
+        "Usage: /sysinfo <system>, show a system's position, star, security, population and permit",
+    ),
+    (
+        "prefetch",
+        prefetch_command_callback,
+        "Usage: /prefetch <system1>, <system2>, ... | /prefetch @<file>, warm the system cache",
+    ),
+    (
+        "capacity",
+        capacity_command_callback,
+        "Usage: /capacity [distance_ly], estimate cases handled per hour",
+    ),
+    (
+        "fuel",
+        fuel_command_callback,
+        "Usage: /fuel <system>, estimate a route's fuel and scoop stops",
+    ),
+    (
+        "roundtrip",
+        roundtrip_command_callback,
+        "Usage: /roundtrip [--destination-boost] <system> | /roundtrip \"<system>\" <origin>, jumps there and back again (--destination-boost: the way back starts with a supercharge at the destination)",
+    ),
+    (
+        "compare",
+        compare_command_callback,
+        "Usage: /compare <system> | /compare \"<system>\" <origin>, direct, white dwarf and neutron routes side by side",
+    ),
+    (
+        "ship",
+        ship_command_callback,
+        "Usage: /ship [name], list ship profiles or switch the active one",
+    ),
+    (
+        "route-last",
+        route_last_command_callback,
+        "Usage: /route-last, show the last calculated route again",
+    ),
+    (
+        "preview",
+        preview_command_callback,
+        "Usage: /preview, render result_format with sample data",
+    ),
+    (
+        "edjc-about",
+        about_command_callback,
+        "Usage: /edjc-about, show the plugin version, CMDR, ship and enabled data sources",
+    ),
+    (
+        "edjc-refresh",
+        refresh_command_callback,
+        "Usage: /edjc-refresh [all], forget the cached CMDR location (or everything cached)",
+    ),
+    (
+        "edjc-set",
+        set_command_callback,
+        "Usage: /edjc-set <key> <value>, change laden_jump_range, cmdr_name, result_format or trigger_nicks and save it",
+    ),
+    (
+        "import-ship",
+        import_ship_command_callback,
+        "Usage: /import-ship <build.json>, add a ship profile from a Coriolis or EDSY export",
+    ),
+    (
+        "verify-ship",
+        verify_ship_command_callback,
+        "Usage: /verify-ship, compare the active ship profile's jump range with your ship on Inara",
+    ),
+    (
+        "edjc-stats",
+        stats_command_callback,
+        "Usage: /edjc-stats, count the signals, routes, cache hits and API errors this session",
+    ),
+    (
+        "edjc-cases",
+        cases_command_callback,
+        "Usage: /edjc-cases, list the RATSIGNALs seen this session that haven't been closed",
+    ),
+    (
+        "edjc-recompute",
+        recompute_command_callback,
+        "Usage: /edjc-recompute, re-plan the open cases with the active ship's jump range (e.g. after /ship)",
+    ),
+    (
+        "edjc-test",
+        self_test_command_callback,
+        "Usage: /edjc-test, check the configuration, API access and CMDR location",
+    ),
+];
+
+/// Initialize HexChat integration: register the plugin's commands and the
+/// channel message hook used for RATSIGNAL detection (and the announce queue
+/// timer, when notices are queued)
+fn init_hexchat_integration(config: &Config) -> Result<()> {
+    // One hook failing shouldn't take the others down with it
+    let mut failed = Vec::new();
+
+    for (name, callback, help) in COMMANDS {
+        let name_c = CString::new(name)?;
+        let help_c = CString::new(help)?;
+        // HexChat copies the name and help text, so they only need to live for the call
+        let hook = hexchat::hexchat_hook_command(
+            name_c.as_ptr(),
+            Some(callback),
+            help_c.as_ptr(),
+            std::ptr::null_mut(),
+        );
+        if hook.is_null() {
+            error!("Could not register /{name}");
+            failed.push(format!("/{name}"));
+        }
+    }
+
+    let event = CString::new("Channel Message")?;
+    let hook =
+        hexchat::hexchat_hook_print(event.as_ptr(), Some(message_callback), std::ptr::null_mut());
+    if hook.is_null() {
+        error!("Could not hook channel messages");
+        failed.push("channel messages".to_string());
+    }
+
+    // Notices queued during the announce cooldown are shown from a timer
+    if config.announce_cooldown_seconds > 0 && config.cooldown_policy == CooldownPolicy::Queue {
+        let hook =
+            hexchat::hexchat_hook_timer(1000, Some(announce_queue_callback), std::ptr::null_mut());
+        if hook.is_null() {
+            error!("Could not hook the announce queue timer");
+            failed.push("announce queue timer".to_string());
+        }
+    }
+
+    // Lines from the signal feed arrive on another thread; handle them here
+    if config.signal_feed_url.is_some() {
+        let hook =
+            hexchat::hexchat_hook_timer(500, Some(signal_feed_callback), std::ptr::null_mut());
+        if hook.is_null() {
+            error!("Could not hook the signal feed timer");
+            failed.push("signal feed timer".to_string());
+        }
+    }
+
+    // Print startup messages
+    hexchat::print_text(&format!(
+        "[EDJC] Plugin {PLUGIN_VERSION} loaded successfully! RATSIGNAL detection is active."
+    ));
+
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!("could not hook {}", failed.join(", ")));
+    }
+    Ok(())
+}
+
+// HexChat plugin export functions
+
+/// Initialize the HexChat plugin.
+///
+/// This function is called by HexChat when the plugin is loaded.
+///
+/// # Safety
+///
+/// This function is unsafe because it:
+/// - Dereferences raw pointers (`plugin_name`, `plugin_desc`, `plugin_version`) without null checks
+/// - Assumes the pointers point to valid memory locations that can be written to
+/// - Converts Rust `CString`s to raw pointers and transfers ownership to HexChat
+/// - Calls other unsafe functions that interact with HexChat's C API
+///
+/// The caller (HexChat) must ensure that:
+/// - All pointer parameters point to valid, writable memory
+/// - The plugin handle points to HexChat's function table and stays valid until
+///   `hexchat_plugin_deinit` returns
+#[no_mangle]
+pub unsafe extern "C" fn hexchat_plugin_init(
+    plugin_handle: *mut hexchat::HexChatPlugin,
+    plugin_name: *mut *const c_char,
+    plugin_desc: *mut *const c_char,
+    plugin_version: *mut *const c_char,
+    _arg: *const c_char,
+) -> i32 {
+    // Keep the handle first so everything below can print into HexChat
+    hexchat::store_plugin_handle(plugin_handle);
+
+    // Initialize logging
+    if let Err(e) = env_logger::try_init() {
+        eprintln!("Failed to initialize logger: {e}");
+    }
+
+    // Set plugin info
+    *plugin_name = hexchat::to_c_string("Elite Dangerous Jump Calculator").into_raw();
+    *plugin_desc = hexchat::to_c_string("Calculates jumps to RATSIGNAL systems").into_raw();
+    *plugin_version = hexchat::to_c_string(PLUGIN_VERSION).into_raw();
+
+    // Initialize plugin
+    match EdJumpCalculator::new() {
+        Ok(plugin) => {
+            // Validate configuration
+            if let Err(e) = plugin.validate_config() {
+                error!("Configuration validation failed: {e}");
+
+                // Still try to initialize but warn user
+                let error_msg = format!("[EDJC] Configuration error: {e}");
+                hexchat::print_chunked(&error_msg, plugin.config.max_line_length);
+            }
+
+            // Set up HexChat API integration
+            if let Err(e) = init_hexchat_integration(&plugin.config) {
+                warn!("HexChat integration limited: {e}");
+            } else {
+                info!("HexChat integration initialized");
+            }
+
+            let trigger_nicks = plugin.config.trigger_nicks.join(", ");
+            let previous = PLUGIN
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .replace(Arc::new(plugin));
+            // Only without a deinit in between; don't leave the old one running
+            if let Some(previous) = previous {
+                warn!("EDJC was initialized twice, replacing the previous instance");
+                previous.shutdown();
+            }
+            start_http_api();
+
+            info!("EDJC plugin {PLUGIN_VERSION} initialized successfully");
+            info!("Monitoring for RATSIGNAL messages from {trigger_nicks}");
+
+            1 // Success
+        }
+        Err(e) => {
+            error!("Failed to initialize EDJC plugin: {e}");
+            let error_msg = format!("[EDJC] Failed to load: {e}");
+            hexchat::print_text(&error_msg);
+            0 // Failure
+        }
+    }
+}
+
+/// Start the plugin's HTTP endpoint when `http_api_port` is set
+#[cfg(feature = "http-api")]
+fn start_http_api() {
+    if let Some(plugin) = plugin() {
+        if let Err(e) = plugin.start_http_api() {
+            error!("Failed to start the HTTP API: {e}");
+        }
+    }
+}
+
+/// Without the `http-api` feature, only say why `http_api_port` is ignored
+#[cfg(not(feature = "http-api"))]
+fn start_http_api() {
+    if plugin().is_some_and(|plugin| plugin.config.http_api_port.is_some()) {
+        warn!("http_api_port is set, but this build has no http-api feature");
+    }
+}
+
+/// Deinitialize the HexChat plugin.
+///
+/// This function is called by HexChat when the plugin is being unloaded.
+/// Returns 1 on success, 0 on failure.
+#[no_mangle]
+pub extern "C" fn hexchat_plugin_deinit() -> i32 {
+    let unhooked = hexchat::unhook_all();
+    let plugin = PLUGIN.write().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(plugin) = plugin {
+        plugin.shutdown();
+    }
+    hexchat::clear_plugin_handle();
+    info!("EDJC plugin deinitialized, {unhooked} hooks removed");
+    1
+}
+
+/// Callback for "Channel Message" events: feeds every channel line to the
+/// RATSIGNAL detector and prints the case response, if any
+extern "C" fn message_callback(word: *const *const c_char, _user_data: *mut libc::c_void) -> i32 {
+    if let Some(plugin) = plugin() {
+        // Channel Message: word[1] is the sender, word[2] the message text
+        let (sender, text) = unsafe { (hexchat::word_at(word, 1), hexchat::word_at(word, 2)) };
+
+        match plugin.process_message(&sender, &text) {
+            Ok(Some(response)) => plugin.announce(&response),
+            Ok(None) => {}
+            Err(e) => error!("Failed to process message from {sender}: {e}"),
+        }
+    }
+
+    // Never eat the message, other plugins (and the user) still need to see it
+    hexchat::HEXCHAT_EAT_NONE
+}
+
+/// Timer callback showing notices queued during `announce_cooldown_seconds`
+extern "C" fn announce_queue_callback(_user_data: *mut libc::c_void) -> i32 {
+    if let Some(plugin) = plugin() {
+        if let Some(notice) = plugin.next_queued_notice() {
+            plugin.announce(&notice);
+        }
+    }
+
+    // Keep the timer running
+    1
+}
+
+/// Timer callback announcing the signals received from `signal_feed_url`
+extern "C" fn signal_feed_callback(_user_data: *mut libc::c_void) -> i32 {
+    if let Some(plugin) = plugin() {
+        for notice in plugin.process_signal_feed() {
+            plugin.announce(&notice);
+        }
+    }
+
+    // Keep the timer running
+    1
+}
+
+/// Callback for the /route command
+extern "C" fn route_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            // The whole rest of the line, so multi-word names and a trailing
+            // range delta ("/route Colonia +13") survive
+            let target_system = hexchat::command_args(word_eol);
+
+            // Handle the route command
+            let response = plugin.handle_route_command(&target_system);
+
+            // Send the response to the output sink
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL // Consume the command so HexChat doesn't show "unknown command"
+}
+
+/// Callback for the /distance command
+extern "C" fn distance_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let systems = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_distance_command(&systems);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /neutron command
+extern "C" fn neutron_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let system_name = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_neutron_command(&system_name);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /sysinfo command
+extern "C" fn sysinfo_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            // The whole rest of the line, so multi-word names survive
+            let system_name = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_sysinfo_command(&system_name);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /preview command
+extern "C" fn preview_command_callback(
+    _word: *const *const c_char,
+    _word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        plugin.emit(&plugin.handle_preview_command());
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /edjc-about command
+extern "C" fn about_command_callback(
+    _word: *const *const c_char,
+    _word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        plugin.emit(&plugin.handle_about_command());
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /edjc-stats command
+extern "C" fn stats_command_callback(
+    _word: *const *const c_char,
+    _word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        plugin.emit(&plugin.handle_stats_command());
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /edjc-cases command
+extern "C" fn cases_command_callback(
+    _word: *const *const c_char,
+    _word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        plugin.emit(&plugin.handle_cases_command());
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /edjc-recompute command
+extern "C" fn recompute_command_callback(
+    _word: *const *const c_char,
+    _word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        plugin.emit(&plugin.handle_recompute_command());
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /verify-ship command
+extern "C" fn verify_ship_command_callback(
+    _word: *const *const c_char,
+    _word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        plugin.emit(&plugin.handle_verify_ship_command());
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /prefetch command
+extern "C" fn prefetch_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let systems = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_prefetch_command(&systems);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /capacity command
+extern "C" fn capacity_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let distance = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_capacity_command(&distance);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /fuel command
+extern "C" fn fuel_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let target_system = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_fuel_command(&target_system);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /roundtrip command
+extern "C" fn roundtrip_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let target_system = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_roundtrip_command(&target_system);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /compare command
+extern "C" fn compare_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let target_system = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_compare_command(&target_system);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /ship command
+extern "C" fn ship_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let name = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_ship_command(&name);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /edjc-set command
+extern "C" fn set_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let args = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_set_command(&args);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /import-ship command
+extern "C" fn import_ship_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let path = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_import_ship_command(&path);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /edjc-refresh command
+extern "C" fn refresh_command_callback(
+    _word: *const *const c_char,
+    word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        unsafe {
+            let args = hexchat::command_args(word_eol);
+
+            let response = plugin.handle_refresh_command(&args);
+            plugin.emit(&response);
+        }
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /route-last command
+extern "C" fn route_last_command_callback(
+    _word: *const *const c_char,
+    _word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        plugin.emit(&plugin.handle_route_last_command());
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+/// Callback for the /edjc-test command
+extern "C" fn self_test_command_callback(
+    _word: *const *const c_char,
+    _word_eol: *const *const c_char,
+    _user_data: *mut libc::c_void,
+) -> i32 {
+    if let Some(plugin) = plugin() {
+        plugin.emit(&plugin.run_diagnostics().render());
+    } else {
+        hexchat::print_text("❌ Plugin not initialized");
+    }
+
+    hexchat::HEXCHAT_EAT_ALL
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EdjcResult;
+
+    fn test_config() -> Config {
+        Config {
+            cmdr_name: "TestCMDR".to_string(),
+            persist_cache: false,
+            // The fixtures are far shorter than the default boost thresholds
+            neutron_highway_threshold_ly: 0.0,
+            white_dwarf_threshold_ly: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn system(name: &str, x: f64, y: f64, z: f64) -> SystemCoordinates {
+        SystemCoordinates {
+            name: name.to_string(),
+            x,
+            y,
+            z,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        }
+    }
+
+    #[test]
+    fn test_output_sink_captures_lines() {
+        let sink = std::sync::Arc::new(output::MemorySink::new());
+        let plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_output_sink(Box::new(sink.clone()));
+
+        plugin.emit("🚀 Route to Colonia: 5 jumps\n↩️ Return to Sol: 5 jumps");
+        plugin.emit(&plugin.handle_sysinfo_command("  "));
+
+        assert_eq!(
+            sink.lines(),
+            vec![
+                "🚀 Route to Colonia: 5 jumps",
+                "↩️ Return to Sol: 5 jumps",
+                "Usage: /sysinfo <system_name>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_long_output_is_wrapped_to_max_line_length() {
+        let sink = std::sync::Arc::new(output::MemorySink::new());
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            max_line_length: 60,
+            ..test_config()
+        })
+        .unwrap()
+        .with_output_sink(Box::new(sink.clone()));
+
+        let response = plugin.handle_route_command("--verbose Colonia Sol");
+        assert!(response.lines().any(|line| line.len() > 60), "{response}");
+        plugin.emit(&response);
+
+        let lines = sink.lines();
+        assert!(lines.len() > response.lines().count(), "{lines:?}");
+        assert!(lines.iter().all(|line| line.len() <= 60), "{lines:?}");
+        assert_eq!(
+            lines.join(" ").split_whitespace().collect::<Vec<_>>(),
+            response.split_whitespace().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_about_command() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        let about = plugin.handle_about_command();
+        assert!(about.contains(env!("CARGO_PKG_VERSION")), "{about}");
+        assert_eq!(
+            about,
+            format!(
+                "ℹ️ edjc {PLUGIN_VERSION}: CMDR TestCMDR, ship Unknown Ship (30.0 LY), EDSM on, Inara off"
+            )
+        );
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            ..test_config()
+        })
+        .unwrap();
+        assert!(plugin
+            .handle_about_command()
+            .ends_with("EDSM off (offline mode), Inara off"));
+    }
+
+    #[test]
+    fn test_nearest_station_for_deep_space_cases() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[{"name": "Explorer's Anchorage", "coords": {"x": 25.0, "y": -20.0, "z": 25880.0},
+                 "information": {"population": 2500}}]"#,
+        );
+        server.respond("/api/sphere-systems", 200, "{}");
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            show_nearest_station: true,
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let planned = |to: SystemCoordinates, region| PlannedRoute {
+            result: JumpResult {
+                jumps: 300,
+                total_distance: to.distance_to(&system("Sol", 0.0, 0.0, 0.0)),
+                route_type: "neutron highway".to_string(),
+                from_system: "Sol".to_string(),
+                to_system: to.name.clone(),
+                legs: Vec::new(),
+                supercharges: 0,
+                exact_jumps: None,
+            },
+            origin: "Sol".to_string(),
+            jump_range: 30.0,
+            from: system("Sol", 0.0, 0.0, 0.0),
+            to,
+            region,
+            warnings: Vec::new(),
+            hints: Vec::new(),
+        };
+
+        let case = planned(
+            system("Sagittarius A*", 25.0, -20.0, 25900.0),
+            Region::DeepSpace,
+        );
+        assert_eq!(
+            plugin.append_nearest_station("🚀 Route".to_string(), &case),
+            "🚀 Route\n🛰️ nearest station: Explorer's Anchorage (20.0 LY)"
+        );
+        let case = planned(
+            system("Beagle Point", -1111.5625, -134.21875, 65269.75),
+            Region::DeepSpace,
+        );
+        assert_eq!(
+            plugin.append_nearest_station("🚀 Route".to_string(), &case),
+            "🚀 Route\n🛰️ nearest station: none within 100 LY"
+        );
+
+        // Only deep space, and only when asked for
+        let bubble = planned(system("Fuelum", 52.0, -52.65625, 49.8125), Region::Bubble);
+        assert_eq!(
+            plugin.append_nearest_station("🚀 Route".to_string(), &bubble),
+            "🚀 Route"
+        );
+        plugin.config.show_nearest_station = false;
+        assert_eq!(
+            plugin.append_nearest_station("🚀 Route".to_string(), &case),
+            "🚀 Route"
+        );
+        assert_eq!(server.hits("/api/sphere-systems"), 2);
+    }
+
+    #[test]
+    fn test_distance_from_reference_system() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            reference_system: "Deciat".to_string(),
+            ..test_config()
+        })
+        .unwrap();
+        let deciat = LocalSystemSource.get_system_coordinates("Deciat").unwrap();
+        let colonia = LocalSystemSource.get_system_coordinates("Colonia").unwrap();
+        let expected = format!("📏 {:.1} LY from Deciat", deciat.distance_to(&colonia));
+
+        let signal = r#"RATSIGNAL - Case #4 PC – CMDR Whit3Arrow – System: "Colonia" (Region 18) – Language: English (en-US)"#;
+        let notice = plugin
+            .process_message("MechaSqueak[BOT]", signal)
+            .unwrap()
+            .unwrap();
+        assert!(notice.ends_with(&format!("\n{expected}")), "{notice}");
+        assert_eq!(
+            plugin.reference_coordinates.get().map(|c| c.name.as_str()),
+            Some("Deciat")
+        );
+
+        let sysinfo = plugin.handle_sysinfo_command("Colonia");
+        assert_eq!(sysinfo.lines().last(), Some(expected.as_str()));
+        // Nothing to report for the reference itself
+        assert!(!plugin
+            .handle_sysinfo_command("Deciat")
+            .contains("LY from Deciat"));
+    }
+
+    #[test]
+    fn test_missing_location_policies() {
+        // Offline, the commander's location is never known
+        let signal = r#"RATSIGNAL - Case #4 PC – CMDR Whit3Arrow – System: "Colonia" (Region 18) – Language: English (en-US)"#;
+        let notice = |on_missing_location| {
+            let plugin = EdJumpCalculator::from_config(Config {
+                offline_mode: true,
+                on_missing_location,
+                ..test_config()
+            })
+            .unwrap();
+            plugin
+                .process_message("MechaSqueak[BOT]", signal)
+                .unwrap()
+                .unwrap()
+        };
+
+        assert_eq!(
+            notice(MissingLocationPolicy::Fallback),
+            "🚀 Case #4 [PC]: 295 jumps to Colonia (22000.5ly, Colonia region) via neutron highway route (from Sol with 30.0ly range)\n⚠️ CMDR position unknown, routing from Sol"
+        );
+        assert_eq!(
+            notice(MissingLocationPolicy::Skip),
+            "📍 Case #4 [PC]: origin unknown — set your location source"
+        );
+        assert_eq!(
+            notice(MissingLocationPolicy::Ask),
+            "❓ Case #4 [PC]: origin unknown, which system are you in? Route with /route \"Colonia\" <your system>"
+        );
+
+        // A known location routes as usual whatever the policy
+        let plugin = EdJumpCalculator::from_config(Config {
+            on_missing_location: MissingLocationPolicy::Skip,
+            ..test_config()
+        })
+        .unwrap()
+        .with_system_source(Box::new(FakeSource));
+        match plugin.process_signal(
+            "MechaSqueak[BOT]",
+            r#"RATSIGNAL - Case #5 PC – CMDR Whit3Arrow – System: "Target" (Region 18) – Language: English (en-US)"#,
+        ) {
+            SignalOutcome::Planned { route, .. } => assert_eq!(route.origin, "Home"),
+            outcome => panic!("expected a route, got {outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn test_plain_text_notices() {
+        let signal = r#"RATSIGNAL - Case #4 PC – CMDR Whit3Arrow – System: "Colonia" (Region 18) – Language: English (en-US)"#;
+        let responses = |use_emoji| {
+            let sink = std::sync::Arc::new(output::MemorySink::new());
+            let plugin = EdJumpCalculator::from_config(Config {
+                offline_mode: true,
+                use_emoji,
+                ..test_config()
+            })
+            .unwrap()
+            .with_output_sink(Box::new(sink.clone()));
+            let notice = plugin
+                .process_message("MechaSqueak[BOT]", signal)
+                .unwrap()
+                .unwrap();
+            plugin.emit(&plugin.handle_route_command("Nowhere"));
+            (notice, sink.lines())
+        };
+
+        let (notice, lines) = responses(true);
+        assert_eq!(
+            notice,
+            "🚀 Case #4 [PC]: 295 jumps to Colonia (22000.5ly, Colonia region) via neutron highway route (from Sol with 30.0ly range)\n⚠️ CMDR position unknown, routing from Sol"
+        );
+        assert_eq!(
+            lines,
+            vec!["❌ Route calculation failed for Nowhere: System not bundled for offline mode: Nowhere (offline mode only knows a few well-known systems)"]
+        );
+
+        // The same results, readable without emoji
+        let (notice, lines) = responses(false);
+        assert_eq!(
+            notice,
+            "[OK] Case #4 [PC]: 295 jumps to Colonia (22000.5ly, Colonia region) via neutron highway route (from Sol with 30.0ly range)\n[WARN] CMDR position unknown, routing from Sol"
+        );
+        assert_eq!(
+            lines,
+            vec!["[ERR] Route calculation failed for Nowhere: System not bundled for offline mode: Nowhere (offline mode only knows a few well-known systems)"]
+        );
+    }
+
+    #[test]
+    fn test_announce_follows_output_mode() {
+        let sink = std::sync::Arc::new(output::MemorySink::new());
+        let plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_output_sink(Box::new(sink.clone()));
+        plugin.announce("🚀 Case #7: 3 jumps to Fuelum");
+        assert_eq!(sink.lines(), vec!["🚀 Case #7: 3 jumps to Fuelum"]);
+
+        // Channel notices bypass the sink and go through HexChat
+        let _handle = test_support::HEXCHAT_HANDLE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let sink = std::sync::Arc::new(output::MemorySink::new());
+        let plugin = EdJumpCalculator::from_config(Config {
+            output_mode: OutputMode::Channel,
+            ..test_config()
+        })
+        .unwrap()
+        .with_output_sink(Box::new(sink.clone()));
+        plugin.announce("🚀 Case #7: 3 jumps to Fuelum");
+        assert!(sink.lines().is_empty());
+    }
+
+    #[test]
+    fn test_same_system_is_already_here() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        let colonia = system("Colonia", -9530.5, -910.28125, 19808.125);
+        let result = plugin
+            .jump_calculator()
+            .calculate_route(
+                &system("colonia", -9530.5, -910.28125, 19808.125),
+                &colonia,
+                30.0,
+            )
+            .unwrap();
+        assert!(plugin.already_in_target(&result));
+        assert_eq!(
+            plugin.route_response(
+                "Colonia",
+                Ok(PlannedRoute {
+                    result: result.clone(),
+                    origin: "colonia".to_string(),
+                    jump_range: 30.0,
+                    from: system("colonia", -9530.5, -910.28125, 19808.125),
+                    to: colonia.clone(),
+                    region: Region::ColoniaRegion,
+                    warnings: Vec::new(),
+                    hints: Vec::new(),
+                })
+            ),
+            "📍 You're already in Colonia"
+        );
+
+        let elsewhere = plugin
+            .jump_calculator()
+            .calculate_route(&system("Sol", 0.0, 0.0, 0.0), &colonia, 30.0)
+            .unwrap();
+        assert!(!plugin.already_in_target(&elsewhere));
+
+        // Another name for the same coordinates is the same place
+        let same_place = plugin
+            .jump_calculator()
+            .calculate_route(
+                &system("Colonia Hub", -9530.5, -910.28125, 19808.125),
+                &colonia,
+                30.0,
+            )
+            .unwrap();
+        assert!(plugin.already_in_target(&same_place));
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            abort_on_same_system: false,
+            ..test_config()
+        })
+        .unwrap();
+        assert!(!plugin.already_in_target(&result));
+    }
+
+    #[test]
+    fn test_return_estimate_only_when_enabled() {
+        let sol = system("Sol", 0.0, 0.0, 0.0);
+        let case = system("Far Flung System", 0.0, 0.0, 280.0);
+
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        assert!(plugin.return_estimate(&case, &sol).is_none());
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            show_return_estimate: true,
+            ..test_config()
+        })
+        .unwrap();
+        let line = plugin.return_estimate(&case, &sol).unwrap();
+
+        let reverse = JumpCalculator::new()
+            .with_boost_thresholds(0.0, 0.0)
+            .calculate_route(&case, &sol, plugin.ship_jump_range())
+            .unwrap();
+        assert_eq!(
+            line,
+            format!(
+                "↩️ Return to Sol: {} jumps ({:.1} LY) via {} route",
+                reverse.jumps, reverse.total_distance, reverse.route_type
+            )
+        );
+    }
+
+    #[test]
+    fn test_preview_uses_result_format() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            result_format: "{jumps}j -> {system} [{route}]".to_string(),
+            show_time_estimates: true,
+            ..test_config()
+        })
+        .unwrap();
+
+        assert_eq!(
+            plugin.handle_preview_command(),
+            "🔍 Preview: 5j -> Colonia [neutron] | ⏱️ ~10 min"
+        );
+    }
+
+    #[test]
+    fn test_ship_command_switches_and_saves_profile() {
+        let _guard = test_support::CONFIG_PATH_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("edjc.toml");
+        config::set_config_path_override(Some(path.clone()));
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            ships: vec![
+                config::ShipConfig {
+                    name: "Anaconda".to_string(),
+                    laden_jump_range: 30.0,
+                    ..Default::default()
+                },
+                config::ShipConfig {
+                    name: "DBX".to_string(),
+                    laden_jump_range: 50.0,
+                    ..Default::default()
+                },
+            ],
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(plugin.ship_jump_range(), 30.0);
+
+        assert_eq!(
+            plugin.handle_ship_command("dbx"),
+            "🚢 Active ship: DBX (50.0 LY)"
+        );
+        assert_eq!(plugin.ship_jump_range(), 50.0);
+        assert_eq!(
+            plugin.handle_ship_command(""),
+            "🚢 Ship profiles: Anaconda (30.0 LY), DBX (50.0 LY) [active]"
+        );
+        assert_eq!(
+            plugin.handle_ship_command("Cutter"),
+            "❌ Unknown ship profile: Cutter (available: Anaconda, DBX)"
+        );
+        assert_eq!(plugin.ship_jump_range(), 50.0);
+
+        let saved = config::load_config_from(&path).unwrap();
+        assert_eq!(saved.active_ship, "DBX");
+        assert_eq!(saved.ship().laden_jump_range, 50.0);
+
+        config::set_config_path_override(None);
+    }
+
+    #[test]
+    fn test_import_ship_command() {
+        let _guard = test_support::CONFIG_PATH_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("edjc.toml");
+        config::set_config_path_override(Some(path.clone()));
+        let build = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/coriolis_dbx.json"
+        );
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            ships: vec![config::ShipConfig {
+                name: "Anaconda".to_string(),
+                laden_jump_range: 30.0,
+                ..Default::default()
+            }],
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(
+            plugin.handle_import_ship_command(&format!("\"{build}\"")),
+            "🚢 Imported Rat Trap (60.2 LY laden, 65.3 LY max), switch to it with /ship Rat Trap"
+        );
+        // Importing again replaces the profile
+        plugin.handle_import_ship_command(build);
+        assert_eq!(
+            plugin.handle_ship_command(""),
+            "🚢 Ship profiles: Anaconda (30.0 LY) [active], Rat Trap (60.2 LY)"
+        );
+        assert_eq!(
+            plugin.handle_ship_command("rat trap"),
+            "🚢 Active ship: Rat Trap (60.2 LY)"
+        );
+        assert_eq!(plugin.ship().fsd_class, Some(5));
+
+        let saved = config::load_config_from(&path).unwrap();
+        assert_eq!(saved.ships.len(), 2);
+        assert_eq!(saved.ship().name, "Rat Trap");
+        assert_eq!(saved.ship().ship_mass, Some(349.41));
+
+        let response = plugin.handle_import_ship_command("missing.json");
+        assert!(
+            response.starts_with("❌ Could not read \"missing.json\""),
+            "{response}"
+        );
+        assert_eq!(
+            plugin.handle_import_ship_command(" "),
+            "Usage: /import-ship <build.json>"
+        );
+
+        config::set_config_path_override(None);
+    }
+
+    #[test]
+    fn test_set_command_valid_range() {
+        let _guard = test_support::CONFIG_PATH_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("edjc.toml");
+        config::set_config_path_override(Some(path.clone()));
+
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        assert_eq!(
+            plugin.handle_set_command("range 42.5"),
+            "⚙️ laden_jump_range = 42.5"
+        );
+        assert_eq!(plugin.ship_jump_range(), 42.5);
+        assert_eq!(
+            plugin.handle_set_command("trigger_nicks MechaSqueak[BOT], DrillSqueak[BOT]"),
+            "⚙️ trigger_nicks = MechaSqueak[BOT], DrillSqueak[BOT]"
+        );
+        assert!(plugin.is_trigger_nick("DrillSqueak[BOT]"));
+        assert_eq!(
+            plugin.handle_set_command("cmdr_name Whit3Arrow"),
+            "⚙️ cmdr_name = Whit3Arrow"
+        );
+
+        let saved = config::load_config_from(&path).unwrap();
+        assert_eq!(saved.ship().laden_jump_range, 42.5);
+        assert_eq!(saved.cmdr_name, "Whit3Arrow");
+        assert_eq!(saved.trigger_nicks.len(), 2);
+
+        config::set_config_path_override(None);
+    }
+
+    #[test]
+    fn test_set_command_invalid_range() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        let range = plugin.ship_jump_range();
+
+        assert_eq!(
+            plugin.handle_set_command("laden_jump_range abc"),
+            "❌ Not a jump range in LY: abc"
+        );
+        assert_eq!(
+            plugin.handle_set_command("laden_jump_range -5"),
+            "❌ Ship laden jump range must be greater than 0"
+        );
+        assert_eq!(
+            plugin.handle_set_command("trigger_nicks ,"),
+            "❌ At least one trigger nick must be configured"
+        );
+        assert_eq!(plugin.ship_jump_range(), range);
+    }
+
+    #[test]
+    fn test_set_command_unknown_key() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        assert_eq!(
+            plugin.handle_set_command("edsm_api_key hunter2"),
+            "❌ Unknown setting: edsm_api_key (settable: laden_jump_range, cmdr_name, result_format, trigger_nicks)"
+        );
+        assert!(plugin
+            .handle_set_command("cmdr_name")
+            .starts_with("Usage: /edjc-set"));
+    }
+
+    #[test]
+    fn test_capacity_command() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            ships: vec![config::ShipConfig {
+                laden_jump_range: 20.0,
+                ..Default::default()
+            }],
+            case_overhead_minutes: 8.0,
+            ..test_config()
+        })
+        .unwrap();
+
+        // Three jumps and a supercharge
+        assert_eq!(
+            plugin.handle_capacity_command("100"),
+            "⏱️ Capacity: ~3.6 cases/hour at 100 LY (3 jumps, ~8 min + 8 min overhead per case)"
+        );
+        assert_eq!(
+            plugin.handle_capacity_command("far"),
+            "Usage: /capacity [distance_ly]"
+        );
+
+        // The configured separators apply to every number
+        let plugin = EdJumpCalculator::from_config(Config {
+            number_format: NumberFormat::De,
+            ..plugin.config.clone()
+        })
+        .unwrap();
+        assert_eq!(
+            plugin.handle_capacity_command("100"),
+            "⏱️ Capacity: ~3,6 cases/hour at 100 LY (3 jumps, ~8 min + 8 min overhead per case)"
+        );
+    }
+
+    #[test]
+    fn test_route_hints_gated_on_config() {
+        let planned = PlannedRoute {
+            result: JumpResult {
+                jumps: 10,
+                total_distance: 905.5,
+                route_type: "neutron highway".to_string(),
+                from_system: "Sol".to_string(),
+                to_system: "Above".to_string(),
+                legs: Vec::new(),
+                supercharges: 0,
+                exact_jumps: None,
+            },
+            origin: "Sol".to_string(),
+            jump_range: 30.0,
+            from: system("Sol", 0.0, 0.0, 0.0),
+            to: system("Above", 0.0, 900.0, 100.0),
+            region: Region::DeepSpace,
+            warnings: Vec::new(),
+            hints: vec!["steep vertical route; fewer boost stars likely".to_string()],
+        };
+
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        assert_eq!(
+            plugin.append_route_hints("🚀 Route".to_string(), &planned),
+            "🚀 Route"
+        );
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            show_route_hints: true,
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(
+            plugin.append_route_hints("🚀 Route".to_string(), &planned),
+            "🚀 Route\n🧭 Note: steep vertical route; fewer boost stars likely"
+        );
+    }
+
+    #[test]
+    fn test_cmdr_location_retry_avoids_fallback() {
+        let server = test_support::MockServer::start();
+        server.respond("/logs/get-position", 404, "");
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Fuelum"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_location_retries(1, Duration::ZERO);
+
+        let planned = plugin.calculate_jumps_with_origin("Fuelum").unwrap();
+        assert_eq!(planned.origin, "Fuelum");
+        assert_eq!(server.hits("/logs/get-position"), 2);
+    }
+
+    #[test]
+    fn test_range_delta_parsing() {
+        assert_eq!(split_range_delta("Colonia +13"), ("Colonia", Some(13.0)));
+        assert_eq!(
+            split_range_delta(" Sagittarius A* -2.5 "),
+            ("Sagittarius A*", Some(-2.5))
+        );
+        assert_eq!(
+            split_range_delta("Col 285 Sector AB-C d1-2"),
+            ("Col 285 Sector AB-C d1-2", None)
+        );
+        assert_eq!(split_range_delta("+13"), ("+13", None));
+
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        assert!(plugin
+            .handle_route_command("Colonia -100")
+            .contains("leaves no usable jump range"));
+    }
+
+    #[test]
+    fn test_boost_thresholds_from_config() {
+        let route = |neutron_highway_threshold_ly: f64| {
+            EdJumpCalculator::from_config(Config {
+                neutron_highway_threshold_ly,
+                white_dwarf_threshold_ly: 150.0,
+                ..test_config()
+            })
+            .unwrap()
+            .with_system_source(Box::new(FakeSource))
+            .handle_route_command("Target")
+        };
+
+        assert!(route(100.0).starts_with("🚀 3 jumps to Target (100.0ly) via neutron highway"));
+        assert!(route(150.0).starts_with("🚀 4 jumps to Target (100.0ly) via direct"));
+    }
+
+    #[test]
+    fn test_route_from_coordinates() {
+        let plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_system_source(Box::new(FakeSource));
+
+        // "Manual" isn't a system the source knows; it is never looked up
+        let response = plugin.handle_route_command("Target --from-coords 30,0,40.0");
+        assert!(
+            response.starts_with("🚀 2 jumps to Target (50.0ly)"),
+            "{response}"
+        );
+        let last_route = plugin.last_route.lock().unwrap();
+        let planned = last_route.as_ref().unwrap();
+        assert_eq!(planned.origin, "Manual");
+        assert_eq!((planned.from.x, planned.from.z), (30.0, 40.0));
+        drop(last_route);
+
+        assert_eq!(
+            plugin.handle_route_command("\"Target\" Home --from-coords 1,2,3"),
+            "❌ Give either the origin Home or --from-coords, not both"
+        );
+
+        // The way back ends at the coordinates, not at a system named after them
+        let response =
+            plugin.handle_route_command("Target --from-coords 30,0,40.0 --destination-boost");
+        assert_eq!(
+            response,
+            "🔁 Round trip Manual ⇄ Target: 3 jumps (100.0 LY)\n➡️ There: 2 jumps (50.0 LY) via direct\n↩️ Back: 1 jumps (50.0 LY) via supercharge at start"
+        );
+    }
+
+    #[test]
+    fn test_route_from_malformed_coordinates() {
+        assert_eq!(
+            split_from_coords_flag("--from-coords -9530.5,-910.28125,19808.125 Colonia")
+                .unwrap()
+                .0,
+            "Colonia"
+        );
+        for value in ["1,2", "1,2,3,4", "1,two,3", "1,,3", "1,2,inf"] {
+            assert_eq!(
+                split_from_coords_flag(&format!("Colonia --from-coords {value}")).unwrap_err(),
+                value
+            );
+        }
+        assert_eq!(
+            split_from_coords_flag("Colonia --from-coords").unwrap_err(),
+            ""
+        );
+
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        assert_eq!(
+            plugin.handle_route_command("Colonia --from-coords 1;2;3"),
+            "❌ --from-coords needs three numbers as x,y,z, got '1;2;3' (e.g. /route Colonia --from-coords 25.2,-20.9,25899.9)"
+        );
+    }
+
+    #[test]
+    fn test_route_range_flag() {
+        assert_eq!(
+            split_range_flag("Colonia --range 60"),
+            Ok(("Colonia".to_string(), Some(60.0)))
+        );
+        assert_eq!(
+            split_range_flag("--range 42.5 \"Sagittarius A*\" Deciat"),
+            Ok(("\"Sagittarius A*\" Deciat".to_string(), Some(42.5)))
+        );
+        assert_eq!(
+            split_range_flag("Col 285 Sector AB-C d1-2"),
+            Ok(("Col 285 Sector AB-C d1-2".to_string(), None))
+        );
+        assert_eq!(
+            split_range_flag("Colonia --range -5"),
+            Err("-5".to_string())
+        );
+        assert_eq!(split_range_flag("Colonia --range"), Err(String::new()));
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            ..test_config()
+        })
+        .unwrap();
+        let jumps = |args: &str| -> u32 {
+            let response = plugin.handle_route_command(args);
+            response
+                .strip_prefix("🚀 ")
+                .and_then(|rest| rest.split_whitespace().next())
+                .and_then(|jumps| jumps.parse().ok())
+                .unwrap_or_else(|| panic!("no route in {response:?}"))
+        };
+
+        // A one-off range, leaving the configured ship alone
+        assert!(jumps("Colonia --range 60") < jumps("Colonia"));
+        assert_eq!(plugin.ship_jump_range(), 30.0);
+        assert!(plugin
+            .handle_route_command("Colonia --range 60")
+            .lines()
+            .next()
+            .unwrap()
+            .contains("to Colonia"));
+
+        assert_eq!(
+            plugin.handle_route_command("Colonia --range 0"),
+            "❌ --range needs a jump range greater than 0 LY, got '0' (e.g. /route Colonia --range 60)"
+        );
+        assert!(plugin
+            .handle_route_command("Colonia --range fast")
+            .starts_with("❌ --range needs a jump range"));
+        assert!(plugin
+            .handle_route_command("--optimized Colonia --range 60")
+            .contains("can't be combined"));
+    }
+
+    #[test]
+    fn test_route_preset_flag() {
+        let preset = |input: &str| {
+            split_preset_flag(input).map(|(rest, preset)| (rest, preset.map(|p| p.name)))
+        };
+        assert_eq!(
+            preset("Colonia --preset DBX-Explorer"),
+            Ok(("Colonia".to_string(), Some("dbx-explorer")))
+        );
+        assert_eq!(preset("Colonia"), Ok(("Colonia".to_string(), None)));
+        assert_eq!(
+            preset("Colonia --preset sidewinder"),
+            Err("sidewinder".to_string())
+        );
+        assert_eq!(preset("Colonia --preset"), Err(String::new()));
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            ..test_config()
+        })
+        .unwrap();
+        let route = |args: &str| plugin.handle_route_command(args);
+
+        // The preset's 60 LY for this route only; an explicit range wins
+        assert_eq!(
+            route("Colonia --preset dbx-explorer"),
+            route("Colonia --range 60")
+        );
+        assert_ne!(route("Colonia --preset dbx-explorer"), route("Colonia"));
+        assert_eq!(
+            route("Colonia --preset dbx-explorer --range 25"),
+            route("Colonia --range 25")
+        );
+        assert_eq!(plugin.ship_jump_range(), 30.0);
+        assert!(route("Colonia --preset sidewinder")
+            .starts_with("❌ Unknown --preset 'sidewinder', choose one of: mandalay-explorer"));
+    }
+
+    #[test]
+    fn test_route_without_boost_stars() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            route_format: Some("{jumps} jumps via {route}".to_string()),
+            ..test_config()
+        })
+        .unwrap();
+
+        let route = |args: &str| {
+            let response = plugin.handle_route_command(args);
+            response.lines().next().unwrap().to_string()
+        };
+
+        assert_eq!(route("Colonia"), "295 jumps via neutron highway");
+        assert_eq!(
+            route("--no-neutron Colonia"),
+            "540 jumps via white dwarf assisted"
+        );
+        assert_eq!(
+            route("Colonia --no-whitedwarf --no-neutron"),
+            "734 jumps via direct"
+        );
+        assert_eq!(
+            split_word_flag("\"Sagittarius A*\" --no-neutron Sol", "--no-neutron"),
+            ("\"Sagittarius A*\" Sol".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn test_route_endpoint_parsing() {
+        let split = |input| split_route_endpoints(input);
+        let owned =
+            |target: &str, origin: Option<&str>| (target.to_string(), origin.map(str::to_string));
+
+        // One argument, quoted or not, keeps the commander's location as origin
+        assert_eq!(split("Colonia"), owned("Colonia", None));
+        assert_eq!(split(" Sagittarius A* "), owned("Sagittarius A*", None));
+        assert_eq!(
+            split("Col 285 Sector AB-C d1-2"),
+            owned("Col 285 Sector AB-C d1-2", None)
+        );
+        assert_eq!(split("\"Beagle Point\""), owned("Beagle Point", None));
+
+        // Two arguments: target, then origin
+        assert_eq!(
+            split("\"Sagittarius A*\" Deciat"),
+            owned("Sagittarius A*", Some("Deciat"))
+        );
+        assert_eq!(
+            split("Colonia \"Beagle Point\""),
+            owned("Colonia", Some("Beagle Point"))
+        );
+        assert_eq!(
+            split("\"Sagittarius A*\" \"Beagle Point\""),
+            owned("Sagittarius A*", Some("Beagle Point"))
+        );
+        assert_eq!(
+            split("\"Sagittarius A*\" Beagle Point"),
+            owned("Sagittarius A*", Some("Beagle Point"))
+        );
+        assert_eq!(split("\"Sol"), owned("Sol", None));
+
+        // The range delta is split off first
+        let (endpoints, delta) = split_range_delta("\"Sagittarius A*\" Deciat +13");
+        assert_eq!(delta, Some(13.0));
+        assert_eq!(split(endpoints), owned("Sagittarius A*", Some("Deciat")));
+    }
+
+    #[test]
+    fn test_route_arguments_from_word_eol() {
+        // word_eol as HexChat builds it for "/route <args>": [unused, line, args, ""]
+        let route_args = |line: &str| {
+            let args = line.strip_prefix("route").unwrap_or_default();
+            let strings = ["", line, args, ""].map(|s| CString::new(s).unwrap());
+            let word_eol = strings.each_ref().map(|s| s.as_ptr());
+            let args = unsafe { hexchat::command_args(word_eol.as_ptr()) };
+            split_route_endpoints(&args)
+        };
+
+        assert_eq!(
+            route_args("route Sagittarius A*"),
+            ("Sagittarius A*".to_string(), None)
+        );
+        assert_eq!(
+            route_args("route Shinrarta Dezhra"),
+            ("Shinrarta Dezhra".to_string(), None)
+        );
+        assert_eq!(
+            route_args("route Shinrarta Dezhra   \t"),
+            ("Shinrarta Dezhra".to_string(), None)
+        );
+        assert_eq!(
+            route_args("route \"Sagittarius A*\" Shinrarta Dezhra "),
+            (
+                "Sagittarius A*".to_string(),
+                Some("Shinrarta Dezhra".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn test_unusual_system_names_do_not_panic() {
+        // Bytes that aren't UTF-8 arrive from HexChat as replacement characters
+        let line = CString::new(b"route Col 285 Sector \xc0\xffAB-C d1-2".to_vec()).unwrap();
+        let empty = CString::default();
+        let word_eol = [empty.as_ptr(), line.as_ptr(), line.as_ptr(), empty.as_ptr()];
+        let args = unsafe { hexchat::command_args(word_eol.as_ptr()) };
+        assert!(args.contains("Sector \u{FFFD}\u{FFFD}AB-C"), "{args}");
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            ..test_config()
+        })
+        .unwrap();
+        let _handle = test_support::HEXCHAT_HANDLE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        for name in [
+            args.as_str(),
+            "Col 285\0Sector",
+            "Hawking's Gap (\"HIP 1\")",
+        ] {
+            let response = plugin.handle_route_command(name);
+            assert!(!response.is_empty());
+            // HexChat gets the whole response, whatever the name held
+            let text = hexchat::to_c_string(&response);
+            assert!(text.to_bytes().len() >= response.len(), "{response}");
+            hexchat::print_text(&response);
+        }
+    }
+
+    #[test]
+    fn test_failed_hooks_do_not_stop_registration() {
+        // Outside HexChat every hook fails, so all of them are attempted
+        let _handle = test_support::HEXCHAT_HANDLE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let err = init_hexchat_integration(&test_config()).unwrap_err();
+        let message = err.to_string();
+        for (name, _, _) in COMMANDS {
+            assert!(message.contains(&format!("/{name}")), "{message}");
+        }
+        assert!(message.ends_with("channel messages"), "{message}");
+    }
+
+    #[test]
+    fn test_explicit_origin_skips_commander_lookup() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Deciat", "coords": {"x": 122.625, "y": -0.8125, "z": -47.28125}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sagittarius A*", "coords": {"x": 25.21875, "y": -20.90625, "z": 25899.96875}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let response = plugin.handle_route_command("\"Sagittarius A*\" Deciat");
+        assert!(response.contains("to Sagittarius A*"), "{response}");
+        assert_eq!(server.hits("/logs/get-position"), 0);
+        let requests = server.requests("/api/system");
+        assert!(requests[0].contains("systemName=Deciat"));
+        assert!(requests[1].contains("systemName=Sagittarius"));
+    }
+
+    #[test]
+    fn test_optimized_route_uses_max_range() {
+        assert_eq!(split_optimized_flag("--optimized Sol"), (" Sol", true));
+        assert_eq!(split_optimized_flag("-o Sol"), (" Sol", true));
+        assert_eq!(split_optimized_flag("Sol"), ("Sol", false));
+
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Target", "coords": {"x": 0.0, "y": 0.0, "z": 100.0}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            ships: vec![config::ShipConfig {
+                laden_jump_range: 20.0,
+                max_jump_range: Some(35.0),
+                ..Default::default()
+            }],
+            result_format: "{jumps} jumps at {range} LY".to_string(),
+            // Direct routes only, so the jump count follows the range
+            neutron_multiplier: 1.0,
+            white_dwarf_multiplier: 1.0,
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let laden = plugin.handle_route_command("\"Target\" Sol");
+        let optimized = plugin.handle_route_command("--optimized \"Target\" Sol");
+        assert_eq!(laden, "5 jumps at 20.0 LY");
+        assert_eq!(
+            optimized,
+            "3 jumps at 35.0 LY\n📏 Planned with a 35.0 LY maximum (unladen) range"
+        );
+        assert!(plugin
+            .handle_route_command("-v -o \"Target\" Sol")
+            .starts_with("3 jumps at 35.0 LY\n📏 "));
+        assert!(plugin
+            .handle_route_command("-o Target +5")
+            .starts_with("❌ --optimized"));
+
+        plugin.ships.get_mut().unwrap()[0].max_jump_range = None;
+        assert_eq!(
+            plugin.handle_route_command("-o \"Target\" Sol"),
+            "5 jumps at 20.0 LY\n⚠️ No max_jump_range configured, planned with the laden range\n📏 Planned with a 20.0 LY laden range"
+        );
+    }
+
+    #[test]
+    fn test_route_last_recalls_without_lookups() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03125, "y": -0.09375, "z": 3.15625}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        assert_eq!(
+            plugin.handle_route_last_command(),
+            "🔁 No route calculated yet this session"
+        );
+
+        let response = plugin.handle_route_command("\"Alpha Centauri\" Sol");
+        assert_eq!(response, "🚀 1 jumps to Alpha Centauri (4.4ly) via direct");
+        let hits = server.hits("/api/system");
+
+        plugin.edsm_client.clear_cache();
+        assert_eq!(
+            plugin.handle_route_last_command(),
+            format!("🔁 Last route from Sol: {response}")
+        );
+
+        // Shown with the format as it is now
+        plugin.config.route_format = Some("{jumps}j to {system} from {from}".to_string());
+        assert_eq!(
+            plugin.handle_route_last_command(),
+            "🔁 Last route from Sol: 1j to Alpha Centauri from Sol"
+        );
+        assert_eq!(server.hits("/api/system"), hits);
+    }
+
+    #[test]
+    fn test_journal_supplies_max_jump_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Journal.2026-10-01T120000.01.log");
+        std::fs::write(&path, include_str!("../tests/fixtures/Journal.sample.log")).unwrap();
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            journal_path: Some(dir.path().to_path_buf()),
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(plugin.ship().max_jump_range, Some(58.42));
+        assert_eq!(plugin.ship_jump_range(), 30.0);
+
+        // An unreadable journal leaves the configured range alone
+        let plugin = EdJumpCalculator::from_config(Config {
+            journal_path: Some(dir.path().join("missing")),
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(plugin.ship().max_jump_range, None);
+    }
+
+    #[test]
+    fn test_location_source_priority() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Journal.2026-10-01T120000.01.log"),
+            "{ \"event\":\"FSDJump\", \"StarSystem\":\"Target\", \"JumpDist\":30.0 }\n",
+        )
+        .unwrap();
+        let plugin = |priority: Vec<LocationSource>| {
+            EdJumpCalculator::from_config(Config {
+                journal_path: Some(dir.path().to_path_buf()),
+                location_source_priority: priority,
+                ..test_config()
+            })
+            .unwrap()
+            .with_system_source(Box::new(FakeSource))
+        };
+
+        // The journal (in Target) outranks the system source (in Home) by default
+        let journal_first = plugin(Config::default().location_source_priority);
+        assert_eq!(
+            journal_first.commander_location().as_deref(),
+            Some("Target")
+        );
+        let planned = journal_first.calculate_jumps_with_origin("Home").unwrap();
+        assert_eq!(planned.origin, "Target");
+
+        let edsm_first = plugin(vec![LocationSource::Edsm, LocationSource::Journal]);
+        assert_eq!(edsm_first.commander_location().as_deref(), Some("Home"));
+
+        // Unconfigured sources are skipped; a journal without jumps falls through
+        let inara_first = plugin(vec![LocationSource::Inara, LocationSource::Edsm]);
+        assert_eq!(inara_first.commander_location().as_deref(), Some("Home"));
+        std::fs::write(
+            dir.path().join("Journal.2026-10-01T120000.01.log"),
+            "{ \"event\":\"Fileheader\" }\n",
+        )
+        .unwrap();
+        assert_eq!(journal_first.commander_location().as_deref(), Some("Home"));
+
+        assert_eq!(plugin(Vec::new()).commander_location(), None);
+    }
+
+    #[test]
+    fn test_verbose_route_lists_legs() {
+        assert_eq!(split_verbose_flag("--verbose Sol"), (" Sol", true));
+        assert_eq!(
+            split_verbose_flag("-v \"Sol\" Deciat"),
+            (" \"Sol\" Deciat", true)
+        );
+        assert_eq!(split_verbose_flag("-vSol"), ("-vSol", false));
+
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Target", "coords": {"x": 0.0, "y": 0.0, "z": 50.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Far Target", "coords": {"x": 0.0, "y": 0.0, "z": 100.0}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let response = plugin.handle_route_command("--verbose \"Target\" Sol");
+        let lines: Vec<&str> = response.lines().collect();
+        assert!(lines[0].starts_with("🚀 2 jumps to Target"), "{response}");
+        assert_eq!(lines[1], "🧭 1. Target: 2 jumps, 50.0 LY");
+
+        let response = plugin.handle_route_command("\"Target\" Sol");
+        assert!(!response.contains("🧭"), "{response}");
+
+        // The estimated neutron highway has no actual waypoints
+        let response = plugin.handle_route_command("-v \"Far Target\" Sol");
+        assert!(response.contains("via neutron highway"), "{response}");
+        assert!(response.ends_with("🧭 No waypoints for an estimated route"));
+    }
+
+    #[test]
+    fn test_route_response_with_estimates() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Target", "coords": {"x": 0.0, "y": 0.0, "z": 100.0}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            show_fuel_estimates: true,
+            show_time_estimates: true,
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let response = plugin.handle_route_command("\"Target\" Sol");
+        assert_eq!(
+            response,
+            "🚀 3 jumps to Target (100.0ly) via neutron highway | ⛽ ~9.0t fuel | ⏱️ ~8 min"
+        );
+    }
+
+    #[test]
+    fn test_fuel_command() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03125, "y": -0.09375, "z": 3.15625}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            ships: vec![config::ShipConfig {
+                fuel_capacity: Some(32.0),
+                ..Default::default()
+            }],
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        assert_eq!(
+            plugin.handle_fuel_command("\"Alpha Centauri\" Sol"),
+            "⛽ ~3.0t fuel for 1 jumps to Alpha Centauri, fits in one 32t tank"
+        );
+        assert!(plugin.handle_fuel_command("").starts_with("Usage: /fuel"));
+    }
+
+    #[test]
+    fn test_fuel_command_needs_scooping() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Target", "coords": {"x": 0.0, "y": 0.0, "z": 100.0}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            ships: vec![config::ShipConfig {
+                fuel_capacity: Some(4.0),
+                ..Default::default()
+            }],
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        assert_eq!(
+            plugin.handle_fuel_command("\"Target\" Sol"),
+            "⛽ ~9.0t fuel for 3 jumps to Target, more than your 4t tank: scoop on the way (~2 stops by tank size)"
+        );
+
+        plugin.ships.get_mut().unwrap()[0].fuel_capacity = None;
+        assert!(plugin
+            .handle_fuel_command("\"Target\" Sol")
+            .ends_with("(set fuel_capacity to check it against your tank)"));
+    }
+
+    #[test]
+    fn test_command_specific_formats() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Sol"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Target", "coords": {"x": 0.0, "y": 0.0, "z": 50.0}}"#,
+        );
+        let signal = r#"RATSIGNAL Case #3 PC - CMDR Rescuee - System: "Target""#;
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            result_format: "{jumps}j to {system}".to_string(),
+            dedupe_window_seconds: 0,
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        // Both fall back to result_format; notices get the case label
+        assert_eq!(
+            plugin.process_message("MechaSqueak[BOT]", signal).unwrap(),
+            Some("Case #3 [PC]: 2j to Target".to_string())
+        );
+        assert_eq!(plugin.handle_route_command("Target"), "2j to Target");
+
+        plugin.config.ratsignal_format =
+            Some("🚨 {case} → {system} ({region}): {jumps}j".to_string());
+        plugin.config.route_format = Some("{from} → {to}: {jumps} jumps at {range} LY".to_string());
+        assert_eq!(
+            plugin.process_message("MechaSqueak[BOT]", signal).unwrap(),
+            Some("🚨 Case #3 [PC] → Target (Bubble): 2j".to_string())
+        );
+        assert_eq!(
+            plugin.handle_route_command("Target"),
+            "Sol → Target: 2 jumps at 30.0 LY"
+        );
+    }
+
+    #[test]
+    fn test_distance_between_two_systems() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Deciat", "coords": {"x": 122.625, "y": -0.8125, "z": -47.28125}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let response = plugin.handle_distance_command("\"Sol\" Deciat");
+        assert_eq!(response, "📏 Deciat → Sol: 131.4 LY");
+        assert_eq!(server.hits("/logs/get-position"), 0);
+
+        assert!(plugin.handle_distance_command(" ").starts_with("Usage:"));
+    }
+
+    #[test]
+    fn test_distance_from_commander_location() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Sol"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let response = plugin.handle_distance_command("Fuelum");
+        assert_eq!(response, "📏 Sol → Fuelum: 89.2 LY");
+        assert_eq!(server.hits("/logs/get-position"), 1);
+    }
+
+    #[test]
+    fn test_neutron_command() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Sol"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[{"name": "Near Neutron", "coords": {"x": 0.0, "y": 0.0, "z": -25.0},
+                 "primaryStar": {"type": "Neutron Star"}}]"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        assert_eq!(
+            plugin.handle_neutron_command(""),
+            "💫 Nearest neutron star to Sol: Near Neutron (25.0 LY)"
+        );
+        assert_eq!(
+            plugin.handle_neutron_command("Sol"),
+            "💫 Nearest neutron star to Sol: Near Neutron (25.0 LY)"
+        );
+        assert_eq!(server.hits("/logs/get-position"), 1);
+
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond("/api/sphere-systems", 200, "{}");
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        assert_eq!(
+            plugin.handle_neutron_command("Sol"),
+            "⚠️ No neutron stars within 100 LY of Sol"
+        );
+
+        // The origin comes from an injected source, not EDSM
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[{"name": "Far Neutron", "coords": {"x": 0.0, "y": 40.0, "z": 0.0},
+                 "primaryStar": {"type": "Neutron Star"}}]"#,
+        );
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let plugin = plugin.with_system_source(Box::new(FakeSource));
+        assert_eq!(
+            plugin.handle_neutron_command(""),
+            "💫 Nearest neutron star to Home: Far Neutron (40.0 LY)"
+        );
+        assert_eq!(server.hits("/api/system"), 0);
+        assert_eq!(server.hits("/logs/get-position"), 0);
+    }
+
+    #[test]
+    fn test_upgrade_comparison() {
+        let calc = JumpCalculator::new();
+        let sol = system("Sol", 0.0, 0.0, 0.0);
+        let colonia = system("Colonia", -9530.5, -910.28125, 19808.125);
+
+        let current = calc.calculate_route(&sol, &colonia, 42.0).unwrap();
+        let upgraded = calc.calculate_route(&sol, &colonia, 55.0).unwrap();
+        assert!(upgraded.jumps < current.jumps);
+
+        assert_eq!(
+            upgrade_comparison(current.jumps, &upgraded, 55.0, 13.0, NumberFormat::Plain),
+            format!(
+                "🔧 With 55.0 LY range (+13.0): {} jumps ({} fewer)",
+                upgraded.jumps,
+                current.jumps - upgraded.jumps
+            )
+        );
+        assert!(
+            upgrade_comparison(upgraded.jumps, &upgraded, 55.0, 0.0, NumberFormat::Plain)
+                .ends_with("(no change)")
+        );
+        assert!(
+            upgrade_comparison(current.jumps, &upgraded, 55.0, 13.0, NumberFormat::De)
+                .starts_with("🔧 With 55,0 LY range (+13,0): ")
+        );
+    }
+
+    #[test]
+    fn test_infeasible_route() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            infeasible_jump_count: Some(200),
+            ..test_config()
+        })
+        .unwrap();
+        let planned = |jumps| PlannedRoute {
+            result: JumpResult {
+                jumps,
+                total_distance: 65279.4,
+                route_type: "neutron highway".to_string(),
+                from_system: "Sol".to_string(),
+                to_system: "Beagle Point".to_string(),
+                legs: Vec::new(),
+                supercharges: 0,
+                exact_jumps: None,
+            },
+            origin: "Sol".to_string(),
+            jump_range: 30.0,
+            from: system("Sol", 0.0, 0.0, 0.0),
+            to: system("Beagle Point", -1111.5625, -134.21875, 65269.75),
+            region: Region::DeepSpace,
+            warnings: Vec::new(),
+            hints: Vec::new(),
+        };
+
+        assert_eq!(
+            plugin.route_response("Beagle Point", Ok(planned(412))),
+            "🚫 Beagle Point is not feasible with current ship (needs ~412 jumps)"
+        );
+        assert!(plugin
+            .route_response("Beagle Point", Ok(planned(200)))
+            .starts_with("🚀 200 jumps to Beagle Point"));
+
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        assert!(!plugin.is_infeasible(&planned(412).result));
+    }
+
+    #[test]
+    fn test_system_list_parsing() {
+        assert_eq!(parse_system_list("Fuelum Sol"), vec!["Fuelum", "Sol"]);
+        assert_eq!(
+            parse_system_list("Sagittarius A*, Beagle Point ,"),
+            vec!["Sagittarius A*", "Beagle Point"]
+        );
+        assert_eq!(
+            parse_system_list("# staging\nFuelum\n\nSol\n"),
+            vec!["Fuelum", "Sol"]
+        );
+    }
+
+    #[test]
+    fn test_warnings_rendered_one_per_line() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        let planned = PlannedRoute {
+            result: JumpResult {
+                jumps: 1,
+                total_distance: 6.0,
+                route_type: "direct".to_string(),
+                from_system: "Barnard's Star".to_string(),
+                to_system: "Sol".to_string(),
+                legs: Vec::new(),
+                supercharges: 0,
+                exact_jumps: None,
+            },
+            origin: "Barnard's Star".to_string(),
+            jump_range: 30.0,
+            from: system("Barnard's Star", -3.03125, 1.375, 4.9375),
+            to: system("Sol", 0.0, 0.0, 0.0),
+            region: Region::Bubble,
+            warnings: vec![
+                "CMDR position unknown, routing from Sol".to_string(),
+                "Sol requires the Sol permit".to_string(),
+            ],
+            hints: Vec::new(),
+        };
+
+        let response = plugin.route_response("Sol", Ok(planned));
+        let lines: Vec<&str> = response.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("🚀 1 jumps to Sol"));
+        assert_eq!(lines[1], "⚠️ CMDR position unknown, routing from Sol");
+        assert_eq!(lines[2], "⚠️ Sol requires the Sol permit");
+    }
+
+    #[test]
+    fn test_parse_ratsignal_with_system_info() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        let message = r#"RATSIGNAL Case #3 PC ODY - CMDR Whit3Arrow - System: "CRUCIS SECTOR IW-N A6-5" (Brown dwarf 51 LY from Fuelum) - Language: English (United States) (en-US) (ODY_SIGNAL)"#;
+
+        let signal = plugin.parse_ratsignal(message).unwrap();
+        assert_eq!(signal.case_number, "3");
+        assert_eq!(signal.platform, "PC");
+        assert_eq!(signal.mode.as_deref(), Some("Odyssey"));
+        assert_eq!(signal.cmdr_name, "Whit3Arrow");
+        assert_eq!(signal.system_name, "CRUCIS SECTOR IW-N A6-5");
+        assert_eq!(
+            signal.system_info.as_deref(),
+            Some("Brown dwarf 51 LY from Fuelum")
+        );
+        let landmark = signal.landmark.unwrap();
+        assert_eq!(landmark.reference_system, "Fuelum");
+        assert_eq!(landmark.distance_ly, 51.0);
+        assert_eq!(signal.language.as_deref(), Some("en-US"));
+        assert_eq!(signal.raw_message, message);
+    }
+
+    #[test]
+    fn test_parse_ratsignal_without_system_info() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        let signal = plugin
+            .parse_ratsignal(
+                r#"RATSIGNAL Case #12 Xbox – CMDR Some-Hyphen Name – System: "Fuelum" – Language: German (de-DE)"#,
+            )
+            .unwrap();
+
+        assert_eq!(signal.case_number, "12");
+        assert_eq!(signal.platform, "Xbox");
+        assert_eq!(signal.mode, None);
+        assert_eq!(signal.cmdr_name, "Some-Hyphen Name");
+        assert_eq!(signal.system_name, "Fuelum");
+        assert_eq!(signal.system_info, None);
+        assert_eq!(signal.landmark, None);
+        assert_eq!(signal.language.as_deref(), Some("de-DE"));
+    }
+
+    #[test]
+    fn test_parse_ratsignal_with_custom_pattern() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            ratsignal_pattern: Some(
+                r"SOS #(?P<case>\d+) \[(?P<tags>[^\]]*)\] (?P<cmdr>.+?) @ (?P<system>[^(]+?)(?: \((?P<info>[^)]*)\))?$"
+                    .to_string(),
+            ),
+            ..test_config()
+        })
+        .unwrap();
+
+        let signal = plugin
+            .parse_ratsignal("SOS #21 [PC ODY] Whit3Arrow @ Fuelum (Neutron star 0 LY from Fuelum)")
+            .unwrap();
+        assert_eq!(signal.case_number, "21");
+        assert_eq!(signal.platform, "PC");
+        assert_eq!(signal.mode.as_deref(), Some("Odyssey"));
+        assert_eq!(signal.cmdr_name, "Whit3Arrow");
+        assert_eq!(signal.system_name, "Fuelum");
+        assert_eq!(signal.landmark.unwrap().body_type, "Neutron star");
+        assert_eq!(signal.language, None);
+
+        // The built-in format no longer matches
+        assert!(plugin
+            .parse_ratsignal(r#"RATSIGNAL Case #12 Xbox – CMDR Rescuee – System: "Fuelum""#)
+            .is_none());
+
+        let err = EdJumpCalculator::from_config(Config {
+            ratsignal_pattern: Some(r"(?P<case>\d+) (?P<system>.+)".to_string()),
+            ..test_config()
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("(?P<cmdr>...)"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_ratsignal_compact_formats() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+
+        let signal = plugin
+            .parse_ratsignal(
+                r#"RATSIGNAL - CMDR Some-Hyphen Name - System: "CRUCIS SECTOR IW-N A6-5" (Brown dwarf 51 LY from Fuelum) - PC ODY (Case #8)"#,
+            )
+            .unwrap();
+        assert_eq!(signal.case_number, "8");
+        assert_eq!(signal.platform, "PC");
+        assert_eq!(signal.mode.as_deref(), Some("Odyssey"));
+        assert_eq!(signal.cmdr_name, "Some-Hyphen Name");
+        assert_eq!(signal.system_name, "CRUCIS SECTOR IW-N A6-5");
+        assert_eq!(signal.landmark.unwrap().reference_system, "Fuelum");
+        assert_eq!(signal.language, None);
+
+        // Labeled fields, an unquoted system and a language
+        let signal = plugin
+            .parse_ratsignal(
+                "RATSIGNAL – CMDR Whit3Arrow – Reported System: Col 285 Sector AB-C d1-2 – Platform: XB – O2: OK – Language: German (de-DE) (Case #3) (XB_SIGNAL)",
+            )
+            .unwrap();
+        assert_eq!(signal.case_number, "3");
+        assert_eq!(signal.platform, "XB");
+        assert_eq!(signal.cmdr_name, "Whit3Arrow");
+        assert_eq!(signal.system_name, "Col 285 Sector AB-C d1-2");
+        assert_eq!(signal.system_info, None);
+        assert_eq!(signal.language.as_deref(), Some("de-DE"));
+
+        // Only a RATSIGNAL no format recognizes is reported as unparseable
+        let plugin = EdJumpCalculator::from_config(Config {
+            dedupe_window_seconds: 0,
+            ..test_config()
+        })
+        .unwrap()
+        .with_system_source(Box::new(FakeSource));
+        assert!(matches!(
+            plugin.process_signal(
+                "MechaSqueak[BOT]",
+                r#"RATSIGNAL - CMDR Rescuee - System: "Target" - PC (Case #5)"#
+            ),
+            SignalOutcome::Planned { .. }
+        ));
+        assert!(matches!(
+            plugin.process_signal(
+                "MechaSqueak[BOT]",
+                "RATSIGNAL - CMDR Rescuee - lost somewhere (Case #5)"
+            ),
+            SignalOutcome::Unparseable
+        ));
+    }
+
+    #[test]
+    fn test_preview_has_no_side_effects() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("routes.jsonl");
+        let handled = Arc::new(AtomicBool::new(false));
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            dedupe_window_seconds: 300,
+            route_log_path: Some(log_path.clone()),
+            ..test_config()
+        })
+        .unwrap()
+        .with_ratsignal_handler({
+            let handled = Arc::clone(&handled);
+            move |_, _| handled.store(true, Ordering::Relaxed)
+        });
+        let message = r#"RATSIGNAL - Case #11 PC – CMDR Whit3Arrow – System: "Colonia" – Language: English (en-US)"#;
+        let seen = || plugin.seen_cases.lock().unwrap().clone();
+        let stats = plugin.handle_stats_command();
+
+        let preview = plugin
+            .process_message_preview("MechaSqueak[BOT]", message)
+            .unwrap()
+            .unwrap();
+        assert!(preview.starts_with("🚀 Case #11 [PC]: "), "{preview}");
+        for _ in 0..3 {
+            assert_eq!(
+                plugin
+                    .process_message_preview("MechaSqueak[BOT]", message)
+                    .unwrap()
+                    .as_deref(),
+                Some(preview.as_str())
+            );
+        }
+        assert!(seen().is_empty());
+        assert!(plugin.open_cases().is_empty());
+        assert_eq!(plugin.handle_stats_command(), stats);
+        assert!(plugin.last_route.lock().unwrap().is_none());
+        assert!(!handled.load(Ordering::Relaxed));
+        let logged = || {
+            plugin.route_logger.as_ref().unwrap().flush().unwrap();
+            std::fs::read_to_string(&log_path).unwrap_or_default()
+        };
+        assert!(logged().is_empty());
+
+        // The real thing still announces it, and then previews as ignored
+        assert_eq!(
+            plugin.process_message("MechaSqueak[BOT]", message).unwrap(),
+            Some(preview)
+        );
+        assert!(handled.load(Ordering::Relaxed));
+        assert_eq!(logged().lines().count(), 1);
+        let announced = seen();
+        assert_eq!(announced.len(), 1);
+        assert_eq!(
+            plugin
+                .process_message_preview("MechaSqueak[BOT]", message)
+                .unwrap(),
+            None
+        );
+        assert_eq!(seen(), announced);
+
+        // Closing a case is previewed without forgetting it
+        let closed = "Closed case #11 (CMDR Whit3Arrow)";
+        plugin
+            .process_message_preview("MechaSqueak[BOT]", closed)
+            .unwrap();
+        assert_eq!(seen(), announced);
+        assert_eq!(plugin.open_cases().len(), 1);
+    }
+
+    #[test]
+    fn test_partial_parse_recovers_system() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            dedupe_window_seconds: 0,
+            ..test_config()
+        })
+        .unwrap()
+        .with_system_source(Box::new(FakeSource));
+
+        // Tilde separators and a "Lang" label: no full format matches
+        let message = r#"RATSIGNAL ~ Case #9 ~ PS5 ~ CMDR Rescuee ~ System: "Target" (Brown dwarf 12 LY from Home) ~ Lang: de"#;
+        assert!(plugin.parse_ratsignal(message).is_none());
+        let signal = plugin.parse_partial_ratsignal(message).unwrap();
+        assert_eq!(signal.case_number, "9");
+        assert_eq!(signal.platform, "PS5");
+        assert_eq!(signal.cmdr_name, "Rescuee");
+        assert_eq!(signal.system_name, "Target");
+        assert_eq!(signal.landmark.unwrap().reference_system, "Home");
+        assert_eq!(signal.language, None);
+
+        match plugin.process_signal("MechaSqueak[BOT]", message) {
+            SignalOutcome::Planned { signal, route } => {
+                assert_eq!(signal.case_number, "9");
+                assert_eq!(route.result.to_system, "Target");
+                assert!(route.result.jumps > 0);
+            }
+            outcome => panic!("{outcome:?}"),
+        }
+
+        // Nothing but the system
+        let message = r#"RATSIGNAL!! System: "Target""#;
+        let signal = plugin.parse_partial_ratsignal(message).unwrap();
+        assert_eq!(signal.case_number, UNKNOWN_CASE);
+        assert_eq!(signal.platform, "Unknown");
+        assert_eq!(signal.cmdr_name, "Unknown");
+        let response = plugin.process_message("MechaSqueak[BOT]", message).unwrap();
+        assert!(
+            response.as_deref().unwrap().contains("Case #? [Unknown]"),
+            "{response:?}"
+        );
+        assert!(plugin
+            .open_cases()
+            .iter()
+            .all(|case| case.case_number == "9"));
+
+        // Only RATSIGNALs are recovered, and only with a quoted system
+        assert!(plugin
+            .parse_partial_ratsignal(r#"Fuel rats, System: "Target""#)
+            .is_none());
+        assert!(plugin
+            .parse_partial_ratsignal("RATSIGNAL - Case #5 somewhere")
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_ratsignal_without_language() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        let signal = plugin
+            .parse_ratsignal(
+                r#"RATSIGNAL Case #7 PS LIVE - CMDR Rescuee - System: "Col 285 Sector AB-C d1-2" (Unconfirmed)"#,
+            )
+            .unwrap();
+
+        assert_eq!(signal.mode.as_deref(), Some("Live"));
+        assert_eq!(signal.system_info.as_deref(), Some("Unconfirmed"));
+        assert_eq!(signal.language, None);
+
+        assert!(plugin.parse_ratsignal("RATSIGNAL something odd").is_none());
+        assert_eq!(parse_language("English"), Some("English".to_string()));
+    }
+
+    #[test]
+    fn test_case_to_permit_locked_system() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Barnard's Star"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Barnard's Star", "coords": {"x": -3.03125, "y": 1.375, "z": 4.9375}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0},
+                "requirePermit": true, "permitName": "Sol"}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let response = plugin
+            .process_message(
+                "MechaSqueak[BOT]",
+                r#"RATSIGNAL Case #7 PC - CMDR Rescuee - System: "Sol""#,
+            )
+            .unwrap()
+            .unwrap();
+        let lines: Vec<&str> = response.lines().collect();
+        assert!(
+            lines[0].starts_with("🚀 Case #7 [PC]: 1 jumps to Sol"),
+            "{response}"
+        );
+        assert_eq!(lines[1], "⚠️ Sol requires the Sol permit");
+        assert!(server.requests("/api/system")[1].contains("showPermit=1"));
+    }
+
+    #[test]
+    fn test_platform_in_case_response() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+
+        let signal = plugin
+            .parse_ratsignal(r#"RATSIGNAL Case #4 PS4 - CMDR Rescuee - System: "Fuelum""#)
+            .unwrap();
+        assert_eq!(signal.platform, "PS4");
+        let response = plugin.case_response(
+            &signal,
+            Err(EdjcError::EdsmApi("offline".to_string())),
+            SideEffects::Apply,
+        );
+        assert_eq!(
+            response,
+            "❌ Case #4 [PS4]: Jump calculation failed for Fuelum - EDSM API error: offline"
+        );
+
+        let signal = plugin
+            .parse_ratsignal(r#"RATSIGNAL Case #5 - CMDR Rescuee - System: "Fuelum""#)
+            .unwrap();
+        assert_eq!(signal.platform, "Unknown");
+        assert_eq!(signal.case_label(), "Case #5 [Unknown]");
+
+        let signal = plugin
+            .parse_ratsignal(r#"RATSIGNAL Case #6 Switch ODY - CMDR Rescuee - System: "Fuelum""#)
+            .unwrap();
+        assert_eq!(signal.platform, "Unknown");
+        assert_eq!(signal.mode.as_deref(), Some("Odyssey"));
+    }
+
+    #[test]
+    fn test_game_mode_markers() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        let mode = |tags: &str| {
+            plugin
+                .parse_ratsignal(&format!(
+                    r#"RATSIGNAL Case #1 {tags} - CMDR Rescuee - System: "Fuelum""#
+                ))
+                .unwrap()
+                .mode
+        };
+
+        assert_eq!(mode("PC ODY").as_deref(), Some("Odyssey"));
+        assert_eq!(mode("PC HOR").as_deref(), Some("Horizons"));
+        assert_eq!(mode("PC LIVE").as_deref(), Some("Live"));
+        assert_eq!(mode("PC"), None);
+        // Malformed (lower-case) markers are not guessed at
+        assert_eq!(mode("PC ody"), None);
+
+        let signal = plugin
+            .parse_ratsignal(r#"RATSIGNAL Case #1 PC ODY - CMDR Rescuee - System: "Fuelum""#)
+            .unwrap();
+        assert_eq!(signal.case_label(), "Case #1 [PC Odyssey]");
+    }
+
+    #[test]
+    fn test_cmdr_separators() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+
+        for dash in ["-", "–", "—"] {
+            let signal = plugin
+                .parse_ratsignal(&format!(
+                    r#"RATSIGNAL Case #9 PC ODY {dash} CMDR Whit3Arrow {dash} System: "Fuelum" (Neutron star) {dash} Language: English (en-GB)"#
+                ))
+                .unwrap_or_else(|| panic!("failed to parse with {dash}"));
+
+            assert_eq!(signal.platform, "PC", "separator {dash}");
+            assert_eq!(signal.cmdr_name, "Whit3Arrow", "separator {dash}");
+            assert_eq!(signal.system_name, "Fuelum", "separator {dash}");
+            assert_eq!(
+                signal.language.as_deref(),
+                Some("en-GB"),
+                "separator {dash}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_inara_location_fallback() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 203, "msg": "Commander name/API Key not found"}"#,
+        );
+        server.respond(
+            "/inara",
+            200,
+            r#"{
+                "header": {"eventStatus": 200},
+                "events": [{"eventStatus": 200, "eventData": {"starsystemName": "Fuelum"}}]
+            }"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_location_retries(0, Duration::ZERO);
+
+        // Without Inara configured the route falls back to Sol
+        assert_eq!(plugin.commander_location(), None);
+
+        plugin.inara_client = Some(
+            InaraClient::new("test-key")
+                .unwrap()
+                .with_api_url(&server.url("/inara")),
+        );
+        let planned = plugin.calculate_jumps_with_origin("Fuelum").unwrap();
+        assert_eq!(planned.origin, "Fuelum");
+        assert!(planned.warnings.is_empty());
+        assert_eq!(server.hits("/inara"), 1);
+    }
+
+    #[test]
+    fn test_verify_ship_command() {
+        let server = test_support::MockServer::start();
+        let ship = |range: f64| {
+            format!(
+                r#"{{"header": {{"eventStatus": 200}}, "events": [{{"eventStatus": 200, "eventData": {{
+                    "commanderName": "TestCMDR",
+                    "commanderMainShip": {{"shipType": "Diamondback Explorer", "shipName": "Rat Trap", "shipMaxJumpRange": {range}}}
+                }}}}]}}"#
+            )
+        };
+        server.respond("/inara", 200, &ship(58.2));
+        server.respond("/inara", 200, &ship(50.0));
+        server.respond("/inara", 200, &ship(50.0));
+        server.respond("/inara", 200, &ship(40.0));
+        server.respond("/inara", 200, &ship(0.0));
+
+        let mut config = test_config();
+        config.ships = vec![config::ShipConfig {
+            name: "DBX".to_string(),
+            laden_jump_range: 45.0,
+            max_jump_range: Some(57.0),
+            ..Default::default()
+        }];
+        let mut plugin = EdJumpCalculator::from_config(config).unwrap();
+        assert_eq!(
+            plugin.handle_verify_ship_command(),
+            "❌ /verify-ship looks your ship up on Inara: set inara_api_key in edjc.toml"
+        );
+
+        plugin.inara_client = Some(
+            InaraClient::new("test-key")
+                .unwrap()
+                .with_api_url(&server.url("/inara"))
+                .with_rate_limit(0),
+        );
+        assert_eq!(
+            plugin.handle_verify_ship_command(),
+            "✓ Jump range matches Inara: max_jump_range 57.0 LY in ship profile 'DBX', 58.2 LY on Inara for Rat Trap (Diamondback Explorer) (-1.2 LY, -2.1%)"
+        );
+        assert_eq!(
+            plugin.handle_verify_ship_command(),
+            "⚠️ Jump range differs by more than 5%: max_jump_range 57.0 LY in ship profile 'DBX', 50.0 LY on Inara for Rat Trap (Diamondback Explorer) (+7.0 LY, +14.0%). Update max_jump_range if the ship was re-engineered"
+        );
+
+        // The laden range is only checked against Inara's maximum
+        plugin.ships.get_mut().unwrap()[0].max_jump_range = None;
+        assert_eq!(
+            plugin.handle_verify_ship_command(),
+            "✓ Laden range is within Inara's maximum: laden_jump_range 45.0 LY in ship profile 'DBX', 50.0 LY maximum on Inara for Rat Trap (Diamondback Explorer). Set max_jump_range to compare it exactly"
+        );
+        assert!(plugin
+            .handle_verify_ship_command()
+            .starts_with("⚠️ Laden range exceeds Inara's maximum: laden_jump_range 45.0 LY"));
+
+        // A zero range is as good as none
+        assert_eq!(
+            plugin.handle_verify_ship_command(),
+            "⚠️ Inara doesn't list a jump range for Rat Trap (Diamondback Explorer)"
+        );
+    }
+
+    #[test]
+    fn test_refresh_refetches_commander_location() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Sol"}"#,
+        );
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Fuelum"}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        assert_eq!(plugin.commander_location().as_deref(), Some("Sol"));
+        assert_eq!(plugin.commander_location().as_deref(), Some("Sol"));
+        assert_eq!(server.hits("/logs/get-position"), 1);
+
+        assert_eq!(
+            plugin.handle_refresh_command(""),
+            "🔄 Forgot CMDR TestCMDR's cached location"
+        );
+        assert_eq!(plugin.commander_location().as_deref(), Some("Fuelum"));
+        assert_eq!(server.hits("/logs/get-position"), 2);
+
+        assert_eq!(
+            plugin.handle_refresh_command("all"),
+            "🔄 Cleared all cached systems and locations"
+        );
+        plugin.commander_location();
+        assert_eq!(server.hits("/logs/get-position"), 3);
+
+        assert_eq!(
+            plugin.handle_refresh_command("everything"),
+            "Usage: /edjc-refresh [all]"
+        );
+    }
+
+    #[test]
+    fn test_sysinfo_shows_system_details() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0},
+                "requirePermit": true, "permitName": "Sol",
+                "information": {"population": 22780919531, "security": "High"},
+                "primaryStar": {"type": "G (White-Yellow) Star"}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let response = plugin.handle_sysinfo_command("Sol");
+        assert_eq!(response.lines().count(), 6, "{response}");
+        assert!(response.starts_with("📍 Sol: (0.00, 0.00, 0.00)"));
+        assert!(response.contains("\n🛡️ Security: High Security\n"));
+        assert!(response.contains("\n👥 Population: 22,780,919,531\n"));
+        assert!(response.ends_with("🔒 Permit: requires the Sol permit"));
+
+        // Other sources only know where systems are
+        let plugin = plugin.with_system_source(Box::new(FakeSource));
+        assert_eq!(
+            plugin.handle_sysinfo_command("Target"),
+            "📍 Target: (60.00, 0.00, 80.00) - 100.0 LY from Sol"
+        );
+    }
+
+    #[test]
+    fn test_lookup_failures_explain_themselves() {
+        let server = test_support::MockServer::start();
+        server.respond("/api/system", 200, "[]");
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        assert_eq!(
+            plugin.handle_sysinfo_command("Nowhere"),
+            "❌ System lookup failed for Nowhere: System not found: Nowhere (check the spelling)"
+        );
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let unreachable = format!("http://127.0.0.1:{port}");
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&unreachable, &unreachable);
+        let response = plugin.handle_route_command("\"Colonia\" Sol");
+        assert!(
+            response.starts_with("❌ Route calculation failed for Colonia: Network error:"),
+            "{response}"
+        );
+        assert!(response.ends_with("(server unreachable, try again later)"));
+    }
+
+    #[test]
+    fn test_offline_mode_routes_between_bundled_systems() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let unreachable = format!("http://127.0.0.1:{port}");
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&unreachable, &unreachable);
+        plugin.validate_config().unwrap();
+
+        let response = plugin.handle_route_command("Colonia \"Sagittarius A*\"");
+        assert!(response.starts_with("🚀 "), "{response}");
+        assert!(response.contains(" jumps to Colonia ("), "{response}");
+
+        let response = plugin.handle_route_command("beagle point");
+        assert!(response.contains("jumps to Beagle Point"), "{response}");
+        assert!(response.contains("routing from Sol"), "{response}");
+
+        assert_eq!(
+            plugin.handle_distance_command("\"Deciat\" Sol"),
+            "📏 Sol → Deciat: 131.4 LY"
+        );
+        assert_eq!(
+            plugin.handle_sysinfo_command("Fuelum"),
+            "❌ System lookup failed for Fuelum: System not bundled for offline mode: Fuelum (offline mode only knows a few well-known systems)"
+        );
+        assert_eq!(
+            plugin.handle_neutron_command("Sol"),
+            "❌ Neutron star lookups are not available in offline mode"
+        );
+    }
+
+    #[test]
+    fn test_process_log_plans_every_case() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            ..test_config()
+        })
+        .unwrap();
+        let log = r#"[20:01] <MechaSqueak[BOT]> RATSIGNAL Case #3 PC ODY - CMDR Whit3Arrow - System: "Colonia" - Language: English (en-US)
+[20:01] <RatOne> 3 cr
+[20:02] <MechaSqueak[BOT]> RATSIGNAL Case #4 XB - CMDR Fuel Less - System: "Deciat" - Language: German (de-DE)
+[20:03] <MechaSqueak[BOT]> RATSIGNAL Case #3 PC ODY - CMDR Whit3Arrow - System: "Colonia" - Language: English (en-US)
+[20:04] <MechaSqueak[BOT]> RATSIGNAL Case #5 PS - CMDR Lost - System: "Nowhere" - Language: English (en-GB)
+"#;
+
+        let results = plugin.process_log(log);
+        let summary: Vec<(&str, &str, Option<u32>)> = results
+            .iter()
+            .map(|(signal, result)| {
+                (
+                    signal.case_number.as_str(),
+                    signal.system_name.as_str(),
+                    result.as_ref().ok().map(|result| result.jumps),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("3", "Colonia", Some(295)),
+                ("4", "Deciat", Some(3)),
+                ("5", "Nowhere", None),
+            ]
+        );
+        assert!(matches!(results[2].1, Err(EdjcError::SystemNotBundled(_))));
+
+        assert!(plugin.process_log("no signals here\n\n").is_empty());
+    }
+
+    /// Source with fixed systems and commander location, standing in for EDSM
+    #[derive(Debug)]
+    struct FakeSource;
+
+    impl SystemSource for FakeSource {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+            match system_name {
+                "Home" => Ok(system("Home", 0.0, 0.0, 0.0)),
+                "Target" => Ok(system("Target", 60.0, 0.0, 80.0)),
+                _ => Err(EdjcError::system_not_found(system_name)),
+            }
+        }
+
+        fn get_commander_location(
+            &self,
+            cmdr_name: &str,
+            _api_key: Option<&str>,
+        ) -> EdjcResult<String> {
+            assert_eq!(cmdr_name, "TestCMDR");
+            Ok("Home".to_string())
+        }
+    }
+
+    #[test]
+    fn test_number_format_in_chat() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            number_format: NumberFormat::De,
+            show_fuel_estimates: true,
+            ..test_config()
+        })
+        .unwrap()
+        .with_system_source(Box::new(FakeSource));
+
+        assert_eq!(
+            plugin.handle_distance_command("Target"),
+            "📏 Home → Target: 100,0 LY"
+        );
+        assert_eq!(
+            plugin.handle_route_command("Target"),
+            "🚀 3 jumps to Target (100,0ly) via neutron highway | ⛽ ~9,0t fuel"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_command() {
+        let server = test_support::MockServer::start();
+        server.respond("/api/sphere-systems", 200, "{}");
+        let mut plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_system_source(Box::new(FakeSource));
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        // Without boost stars either way, there and back cost the same
+        let (there, back) = plugin.plan_round_trip("Target", None, false).unwrap();
+        assert_eq!(
+            (there.origin.as_str(), back.origin.as_str()),
+            ("Home", "Target")
+        );
+        assert_eq!(back.result.to_system, "Home");
+        assert_eq!(there.result.jumps, back.result.jumps);
+        let total = there.result.jumps + back.result.jumps;
+        assert_eq!(
+            plugin.handle_roundtrip_command("Target"),
+            format!(
+                "🔁 Round trip Home ⇄ Target: {total} jumps (200.0 LY)\n➡️ There: {} jumps (100.0 LY) via {}\n↩️ Back: {} jumps (100.0 LY) via {}",
+                there.result.jumps,
+                there.result.route_type,
+                back.result.jumps,
+                back.result.route_type
+            )
+        );
+
+        assert!(plugin
+            .handle_roundtrip_command("")
+            .starts_with("Usage: /roundtrip"));
+        assert_eq!(
+            plugin.handle_roundtrip_command("Home"),
+            "📍 You're already in Home"
+        );
+    }
+
+    #[test]
+    fn test_compare_command() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            ..test_config()
+        })
+        .unwrap();
+
+        let response = plugin.handle_compare_command("Colonia");
+        let lines: Vec<&str> = response.lines().collect();
+        assert!(
+            lines[0].starts_with("⚖️ Sol → Colonia (22000.5 LY, "),
+            "{response}"
+        );
+        assert!(lines[1].trim_start().starts_with("direct: "), "{response}");
+        assert!(
+            lines[2].trim_start().starts_with("white dwarf assisted: "),
+            "{response}"
+        );
+        assert!(
+            lines[3].trim_start().starts_with("neutron highway: "),
+            "{response}"
+        );
+        assert!(lines[3].ends_with("⏩ fastest"), "{response}");
+        assert_eq!(response.matches("fastest").count(), 1);
+
+        // The neutron highway is the route /route picks too
+        let planned = plugin.calculate_jumps_with_origin("Colonia").unwrap();
+        assert_eq!(
+            lines[4],
+            format!(
+                "🧭 /route plans {} jumps via neutron highway",
+                planned.result.jumps
+            )
+        );
+        assert!(lines[3]
+            .trim_start()
+            .starts_with(&format!("neutron highway: {} jumps", planned.result.jumps)));
+
+        assert!(plugin
+            .handle_compare_command("")
+            .starts_with("Usage: /compare"));
+        assert!(plugin
+            .handle_compare_command("Nowhere Special")
+            .starts_with("❌ Route comparison failed for Nowhere Special"));
+    }
+
+    #[test]
+    fn test_roundtrip_with_one_sided_boost() {
+        // A neutron star next to Home speeds up the way out, not the way back
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[{"name": "Doorstep Neutron", "coords": {"x": 3.0, "y": 0.0, "z": 4.0},
+                 "primaryStar": {"type": "Neutron Star"}}]"#,
+        );
+        let mut plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_system_source(Box::new(FakeSource));
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let (there, back) = plugin.plan_round_trip("Target", None, false).unwrap();
+        assert_eq!(there.result.legs[0].system, "Doorstep Neutron");
+        assert_eq!((there.result.jumps, back.result.jumps), (2, 4));
+        assert_eq!(back.result.supercharges, 0);
+        assert_eq!(
+            plugin.handle_roundtrip_command("Target"),
+            "🔁 Round trip Home ⇄ Target: 6 jumps (200.0 LY)\n➡️ There: 2 jumps (100.0 LY) via neutron highway (1 boost)\n↩️ Back: 4 jumps (100.0 LY) via direct"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_with_destination_boost() {
+        let server = test_support::MockServer::start();
+        server.respond("/api/sphere-systems", 200, "{}");
+        let mut plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_system_source(Box::new(FakeSource));
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        // 100 LY at 30 LY: four jumps out; back, the first jump covers 120 LY
+        let (there, back) = plugin.plan_round_trip("Target", None, true).unwrap();
+        assert_eq!((there.result.jumps, there.result.supercharges), (4, 0));
+        assert_eq!(there.result.route_type, "direct");
+        assert_eq!((back.result.jumps, back.result.supercharges), (1, 1));
+        assert_eq!(
+            back.result.route_type,
+            crate::jump_calculator::ORIGIN_BOOST_ROUTE
+        );
+        assert_eq!(
+            (back.origin.as_str(), back.result.to_system.as_str()),
+            ("Target", "Home")
+        );
+
+        let expected = "🔁 Round trip Home ⇄ Target: 5 jumps (200.0 LY)\n➡️ There: 4 jumps (100.0 LY) via direct\n↩️ Back: 1 jumps (100.0 LY) via supercharge at start";
+        assert_eq!(
+            plugin.handle_roundtrip_command("--destination-boost Target"),
+            expected
+        );
+        assert_eq!(
+            plugin.handle_route_command("Target --destination-boost"),
+            expected
+        );
+        // A smaller range needs more jumps after the supercharge too
+        let response = plugin.handle_route_command("Target --destination-boost --range 20");
+        assert!(response.contains("Back: 2 jumps"), "{response}");
+        assert!(plugin
+            .handle_route_command("Target +5 --destination-boost")
+            .starts_with("❌ --destination-boost can't be combined"));
+    }
+
+    #[test]
+    fn test_injected_system_source() {
+        let plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_system_source(Box::new(FakeSource));
+
+        let planned = plugin.calculate_jumps_with_origin("Target").unwrap();
+        assert_eq!(planned.origin, "Home");
+        assert!(planned.warnings.is_empty(), "{:?}", planned.warnings);
+        assert_eq!(planned.result.total_distance, 100.0);
+        assert_eq!(planned.result.jumps, 3);
+
+        assert_eq!(
+            plugin.handle_distance_command("Target"),
+            "📏 Home → Target: 100.0 LY"
+        );
+        assert_eq!(
+            plugin.handle_sysinfo_command("Elsewhere"),
+            "❌ System lookup failed for Elsewhere: System not found: Elsewhere (check the spelling)"
+        );
+    }
+
+    #[test]
+    fn test_shutdown_flushes_cache_and_log() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("edsm_cache.json");
+        let log_path = dir.path().join("routes.jsonl");
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            route_log_path: Some(log_path.clone()),
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_cache_file(&cache_path);
+
+        plugin.edsm_client.get_system_coordinates("Sol").unwrap();
+        assert!(!cache_path.exists());
+
+        // The plugin is never dropped, so shutdown is what writes the cache
+        plugin.shutdown();
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&cache_path).unwrap()).unwrap();
+        assert_eq!(saved[0]["name"], "sol");
+        assert_eq!(saved[0]["coordinates"]["name"], "Sol");
+        assert!(log_path.exists());
+
+        // Still usable, and shutting down twice is harmless
+        assert_eq!(
+            plugin.edsm_client.get_system_coordinates("Sol").unwrap().x,
+            0.0
+        );
+        assert_eq!(server.hits("/api/system"), 1);
+        plugin.shutdown();
+    }
+
+    #[test]
+    fn test_routes_are_logged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("routes.jsonl");
+        let plugin = EdJumpCalculator::from_config(Config {
+            route_log_path: Some(path.clone()),
+            ..test_config()
+        })
+        .unwrap()
+        .with_system_source(Box::new(FakeSource));
+
+        plugin.handle_route_command("Target");
+        plugin
+            .process_message(
+                "MechaSqueak[BOT]",
+                r#"RATSIGNAL Case #7 PC - CMDR Rescuee - System: "Target""#,
+            )
+            .unwrap()
+            .unwrap();
+        // Failed lookups aren't logged
+        plugin.handle_route_command("Elsewhere");
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let entries: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        for entry in &entries {
+            assert_eq!(entry["from"], "Home");
+            assert_eq!(entry["to"], "Target");
+            assert_eq!(entry["jumps"], 3);
+            assert_eq!(entry["distance_ly"], 100.0);
+            assert!(entry["timestamp"].is_string());
+        }
+        assert_eq!(entries[0]["case"], serde_json::Value::Null);
+        assert_eq!(entries[1]["case"], "7");
+
+        // A log that can't be opened doesn't stop routes being answered
+        let plugin = EdJumpCalculator::from_config(Config {
+            route_log_path: Some(dir.path().to_path_buf()),
+            ..test_config()
+        })
+        .unwrap()
+        .with_system_source(Box::new(FakeSource));
+        assert!(plugin
+            .handle_route_command("Target")
+            .starts_with("🚀 3 jumps"));
+    }
+
+    #[test]
+    fn test_builder_with_mock_source() {
+        let plugin = EdJumpCalculator::builder()
+            .cmdr_name("TestCMDR")
+            .ship_jump_range(50.0)
+            .source(Box::new(FakeSource))
+            .build()
+            .unwrap();
+        assert!(!plugin.config.persist_cache);
+        assert_eq!(plugin.ship_jump_range(), 50.0);
+
+        assert_eq!(
+            plugin
+                .process_message(
+                    "MechaSqueak[BOT]",
+                    r#"RATSIGNAL Case #4 PC - CMDR Rescuee - System: "Target""#,
+                )
+                .unwrap(),
+            Some("🚀 Case #4 [PC]: 2 jumps to Target (100.0ly, Bubble) via direct route (from Home with 50.0ly range)".to_string())
+        );
+
+        let plugin = EdJumpCalculator::builder()
+            .cmdr_name("TestCMDR")
+            .source(Box::new(FakeSource))
+            .ratsignal_pattern(r"^CASE (?P<case>\d+): (?P<cmdr>\S+) in (?P<system>.+)$")
+            .build()
+            .unwrap();
+        assert_eq!(
+            plugin
+                .process_message("MechaSqueak[BOT]", "CASE 9: Rescuee in Target")
+                .unwrap(),
+            // Too short for the default boost thresholds
+            Some("🚀 Case #9 [Unknown]: 4 jumps to Target (100.0ly, Bubble) via direct route (from Home with 30.0ly range)".to_string())
+        );
+
+        let err = EdJumpCalculator::builder()
+            .ratsignal_pattern(r"Case (?P<case>\d+)")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("(?P<cmdr>"), "{err}");
+    }
+
+    #[test]
+    fn test_signal_feed() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let plugin = EdJumpCalculator::from_config(Config {
+            signal_feed_url: Some(format!("tcp://{}", listener.local_addr().unwrap())),
+            ..test_config()
+        })
+        .unwrap()
+        .with_system_source(Box::new(FakeSource));
+        assert!(plugin.process_signal_feed().is_empty());
+
+        let (mut bot, _) = listener.accept().unwrap();
+        std::io::Write::write_all(
+            &mut bot,
+            b"Just chatting\r\nRATSIGNAL Case #3 PC - CMDR Rescuee - System: \"Target\"\r\n",
+        )
+        .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut notices = Vec::new();
+        while notices.is_empty() && Instant::now() < deadline {
+            notices = plugin.process_signal_feed();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(notices.len(), 1, "{notices:?}");
+        assert!(
+            notices[0].starts_with("🚀 Case #3 [PC]: "),
+            "{}",
+            notices[0]
+        );
+        assert!(notices[0].contains("Target"), "{}", notices[0]);
+        assert_eq!(plugin.stats.get(Counter::SignalsParsed), 1);
+
+        plugin.shutdown();
+        assert!(plugin.signal_feed.lock().unwrap().is_none());
+    }
+
+    #[cfg(feature = "http-api")]
+    #[test]
+    fn test_http_api_serves_routes() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            http_api_port: Some(0),
+            ..test_config()
+        })
+        .unwrap()
+        .with_system_source(Box::new(FakeSource));
+        let plugin = Arc::new(plugin);
+
+        let addr = plugin.start_http_api().unwrap().unwrap();
+        assert!(addr.ip().is_loopback());
+        let response = reqwest::blocking::get(format!("http://{addr}/route?to=Target")).unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        let body: serde_json::Value = response.json().unwrap();
+        assert_eq!(body["result"]["from_system"], "Home");
+        assert_eq!(body["result"]["to_system"], "Target");
+        assert_eq!(body["result"]["jumps"], 3);
+        assert_eq!(body["result"]["total_distance"], 100.0);
+        assert_eq!(body["warnings"], serde_json::json!([]));
+
+        let response = reqwest::blocking::get(format!("http://{addr}/route?to=Elsewhere")).unwrap();
+        assert_eq!(response.status().as_u16(), 404);
+
+        plugin.shutdown();
+        assert!(std::net::TcpStream::connect(addr).is_err());
+    }
+
+    #[test]
+    fn test_run_diagnostics() {
+        let server = test_support::MockServer::start();
+        server.respond("/api/system", 503, "");
+
+        let mut plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_system_source(Box::new(FakeSource));
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let diagnostics = plugin.run_diagnostics();
+        assert!(!diagnostics.all_passed());
+        assert!(diagnostics.check("Configuration").unwrap().passed);
+        let edsm = diagnostics.check("EDSM").unwrap();
+        assert!(!edsm.passed);
+        assert!(edsm.detail.contains("503"), "{}", edsm.detail);
+        assert_eq!(diagnostics.check("Inara"), None);
+        assert_eq!(
+            diagnostics.check("CMDR location").unwrap().detail,
+            "CMDR TestCMDR in Home"
+        );
+        let report = diagnostics.render();
+        assert!(report.starts_with("🩺 EDJC self-test: 1 of 3 checks failed"));
+        assert!(report.contains("\n✗ EDSM: "), "{report}");
+        assert!(report.ends_with("✓ CMDR location: CMDR TestCMDR in Home"));
+
+        plugin.config.fallback_origin = String::new();
+        plugin.config.offline_mode = true;
+        plugin.system_source = None;
+        let diagnostics = plugin.run_diagnostics();
+        assert!(!diagnostics.check("Configuration").unwrap().passed);
+        assert_eq!(
+            diagnostics.check("EDSM").unwrap().detail,
+            "skipped in offline mode"
+        );
+        assert!(!diagnostics.check("CMDR location").unwrap().passed);
+    }
+
+    #[test]
+    fn test_private_profile_noticed_once() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 203, "msg": "Commander name/API Key not found"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+
+        let sink = std::sync::Arc::new(output::MemorySink::new());
+        let mut plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_output_sink(Box::new(sink.clone()));
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        for _ in 0..3 {
+            let response = plugin.handle_route_command("Alpha Centauri");
+            assert!(response.contains("routing from Sol"), "{response}");
+        }
+        assert_eq!(
+            sink.lines(),
+            vec!["⚠️ CMDR TestCMDR can't be located on EDSM without an API key: set edsm_api_key"]
+        );
+    }
+
+    #[test]
+    fn test_ratsignal_handler_receives_cases() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Sol"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+
+        let received = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let handled = received.clone();
+        let mut plugin = EdJumpCalculator::from_config(test_config())
+            .unwrap()
+            .with_ratsignal_handler(move |signal, result| {
+                handled.lock().unwrap().push((signal.clone(), result.jumps));
+            });
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let message = r#"RATSIGNAL - Case #7 PC – CMDR Whit3Arrow – System: "Alpha Centauri" (Brown dwarf 4.4 LY from Sol) – Language: English (en-US)"#;
+        plugin.process_message("MechaSqueak[BOT]", message).unwrap();
+        plugin.process_message("SomeRat", message).unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (signal, jumps) = &received[0];
+        assert_eq!(signal.case_number, "7");
+        assert_eq!(signal.cmdr_name, "Whit3Arrow");
+        assert_eq!(signal.system_name, "Alpha Centauri");
+        assert_eq!(signal.platform, "PC");
+        assert_eq!(*jumps, 1);
+    }
+
+    #[test]
+    fn test_stats_command() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Sol"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+        server.respond("/api/system", 404, "");
+        server.respond("/api/sphere-systems", 200, "{}");
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_stats(Arc::clone(&plugin.stats));
+
+        let message = r#"RATSIGNAL - Case #7 PC – CMDR Whit3Arrow – System: "Alpha Centauri" (Brown dwarf 4.4 LY from Sol) – Language: English (en-US)"#;
+        plugin.process_message("MechaSqueak[BOT]", message).unwrap();
+        plugin
+            .process_message("MechaSqueak[BOT]", "RATSIGNAL garbled beyond repair")
+            .unwrap();
+        plugin.process_message("SomeRat", message).unwrap();
+        assert!(plugin.edsm_client.get_system_coordinates("Sol").is_ok());
+        assert!(plugin
+            .edsm_client
+            .get_system_coordinates("Nowhere")
+            .is_err());
+
+        assert_eq!(plugin.stats.get(Counter::SignalsSeen), 2);
+        assert_eq!(plugin.stats.get(Counter::SignalsParsed), 1);
+        assert_eq!(plugin.stats.get(Counter::RoutesComputed), 1);
+        assert_eq!(plugin.stats.get(Counter::CacheHits), 1);
+        assert_eq!(plugin.stats.get(Counter::CacheMisses), 3);
+        assert_eq!(plugin.stats.get(Counter::ApiErrors), 1);
+        assert_eq!(
+            plugin.handle_stats_command(),
+            "📊 This session: signals seen: 2 | signals parsed: 1 | routes computed: 1 | cache hits: 1 | cache misses: 3 | API errors: 1"
+        );
+    }
+
+    #[test]
+    fn test_preview_is_not_counted() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Sol"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond("/api/system", 500, "");
+        server.respond("/api/sphere-systems", 200, "{}");
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+            .with_retry_policy(edsm::RetryPolicy {
+                max_attempts: 1,
+                base_delay: Duration::ZERO,
+            })
+            .with_stats(Arc::clone(&plugin.stats));
+        let stats = plugin.handle_stats_command();
+
+        // Looks Sol up (a miss, then a hit) and fails on the case's system
+        let message = r#"RATSIGNAL - Case #7 PC – CMDR Whit3Arrow – System: "Alpha Centauri" – Language: English (en-US)"#;
+        for _ in 0..2 {
+            plugin
+                .process_message_preview("MechaSqueak[BOT]", message)
+                .unwrap();
+        }
+        assert!(server.hits("/api/system") >= 2);
+        assert_eq!(plugin.handle_stats_command(), stats);
+
+        // The same lookups count outside a preview
+        plugin.edsm_client.get_system_coordinates("Sol").unwrap();
+        assert_eq!(plugin.stats.get(Counter::CacheHits), 1);
+    }
+
+    #[test]
+    fn test_signal_outcomes() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Sol"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+        server.respond("/api/system", 200, "[]");
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let message = r#"RATSIGNAL - Case #7 PC – CMDR Whit3Arrow – System: "Alpha Centauri" (Brown dwarf 4.4 LY from Sol) – Language: English (en-US)"#;
+        match plugin.process_signal("MechaSqueak[BOT]", message) {
+            SignalOutcome::Planned { signal, route } => {
+                assert_eq!(signal.case_number, "7");
+                assert_eq!(route.result.to_system, "Alpha Centauri");
+                assert_eq!(route.result.jumps, 1);
+            }
+            outcome => panic!("{outcome:?}"),
+        }
+
+        // Announced already
+        assert!(matches!(
+            plugin.process_signal("MechaSqueak[BOT]", message),
+            SignalOutcome::Ignored
+        ));
+        assert!(matches!(
+            plugin.process_signal("SomeRat", message),
+            SignalOutcome::Ignored
+        ));
+        assert!(matches!(
+            plugin.process_signal("MechaSqueak[BOT]", "Good evening, rats"),
+            SignalOutcome::Ignored
+        ));
+        assert!(matches!(
+            plugin.process_signal("MechaSqueak[BOT]", "RATSIGNAL - something odd"),
+            SignalOutcome::Unparseable
+        ));
+
+        let message = r#"RATSIGNAL - Case #8 PC – CMDR Whit3Arrow – System: "Nowhere Real""#;
+        match plugin.process_signal("MechaSqueak[BOT]", message) {
+            SignalOutcome::Failed { signal, error } => {
+                assert_eq!(signal.system_name, "Nowhere Real");
+                assert!(matches!(error, EdjcError::SystemNotFound { .. }), "{error}");
+            }
+            outcome => panic!("{outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_system_routes_to_landmark() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "msg": "OK", "system": "Sol"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        // Asked once: the repeated lookup is answered from the not-found cache
+        server.respond("/api/system", 200, "[]");
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            route_via_landmark: false,
+            dedupe_window_seconds: 0,
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let message = r#"RATSIGNAL - Case #3 PC – CMDR Whit3Arrow – System: "CRUCIS SECTOR IW-N A6-5" (Brown dwarf 51 LY from Fuelum) – Language: English (en-US)"#;
+        let response = plugin
+            .process_message("MechaSqueak[BOT]", message)
+            .unwrap()
+            .unwrap();
+        assert!(
+            response.starts_with("❌ Case #3 [PC]: Jump calculation failed"),
+            "{response}"
+        );
+
+        plugin.config.route_via_landmark = true;
+        let response = plugin
+            .process_message("MechaSqueak[BOT]", message)
+            .unwrap()
+            .unwrap();
+        let lines: Vec<&str> = response.lines().collect();
+        assert!(lines[0].contains("jumps to Fuelum"), "{response}");
+        assert_eq!(
+            lines[1],
+            "⚠️ CRUCIS SECTOR IW-N A6-5 isn't in EDSM, routed to Fuelum instead (the case's brown dwarf is 51 LY from it)"
+        );
+    }
+
+    #[test]
+    fn test_platform_filter() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            platforms: vec!["PC".to_string()],
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let signal = |platform: &str| {
+            format!(
+                r#"RATSIGNAL - Case #7 {platform} – CMDR Whit3Arrow – System: "Alpha Centauri" (Brown dwarf 4.4 LY from Sol) – Language: English (en-US)"#
+            )
+        };
+        let xbox = plugin
+            .process_message("MechaSqueak[BOT]", &signal("XB"))
+            .unwrap();
+        assert_eq!(xbox, None);
+        assert_eq!(server.hits("/api/system"), 0);
+
+        let pc = plugin
+            .process_message("MechaSqueak[BOT]", &signal("PC"))
+            .unwrap()
+            .unwrap();
+        assert!(pc.starts_with("🚀 Case #7 [PC]: "), "{pc}");
+
+        assert!(plugin.config.responds_to_platform("Unknown"));
+        plugin.config.platforms = vec!["ps".to_string(), "Xbox".to_string()];
+        assert!(plugin.config.responds_to_platform("PS5"));
+        assert!(plugin.config.responds_to_platform("XB"));
+        assert!(!plugin.config.responds_to_platform("PC"));
+        plugin.config.platforms.clear();
+        assert!(plugin.config.responds_to_platform("PC"));
+    }
+
+    #[test]
+    fn test_trigger_nicks() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            trigger_nicks: vec!["RatMama[BOT]".to_string(), "MechaSqueak[BOT]2".to_string()],
+            dedupe_window_seconds: 0,
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let message = r#"RATSIGNAL - Case #7 PC – CMDR Whit3Arrow – System: "Alpha Centauri" (Brown dwarf 4.4 LY from Sol) – Language: English (en-US)"#;
+        let response = plugin
+            .process_message("RatMama[BOT]", message)
+            .unwrap()
+            .unwrap();
+        assert!(response.starts_with("🚀 Case #7 [PC]: "), "{response}");
+
+        assert_eq!(
+            plugin.process_message("MechaSqueak[BOT]", message).unwrap(),
+            None
+        );
+        assert_eq!(
+            plugin.process_message("ratmama[bot]", message).unwrap(),
+            None
+        );
+
+        plugin.config.trigger_nicks_ignore_case = true;
+        assert!(plugin
+            .process_message("ratmama[bot]", message)
+            .unwrap()
+            .is_some());
+        assert!(!plugin.config.is_trigger_nick("SomeRat"));
+    }
+
+    #[test]
+    fn test_min_jumps_to_announce() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(Config {
+            dedupe_window_seconds: 0,
+            ..test_config()
+        })
+        .unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let signal = r#"RATSIGNAL - Case #7 PC – CMDR Whit3Arrow – System: "Alpha Centauri" (Brown dwarf 4.4 LY from Sol) – Language: English (en-US)"#;
+
+        // Alpha Centauri is a single jump from Sol
+        for (threshold, announced) in [(0, true), (1, true), (2, false)] {
+            plugin.config.min_jumps_to_announce = threshold;
+            let response = plugin.process_message("MechaSqueak[BOT]", signal).unwrap();
+            assert_eq!(response.is_some(), announced, "threshold {threshold}");
+        }
+    }
+
+    #[test]
+    fn test_repeated_ratsignals_deduplicated() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let signal = |case: u32| {
+            format!(
+                r#"RATSIGNAL - Case #{case} PC – CMDR Whit3Arrow – System: "Alpha Centauri" (Brown dwarf 4.4 LY from Sol) – Language: English (en-US)"#
+            )
+        };
+
+        let announce = |case| {
+            plugin
+                .process_message("MechaSqueak[BOT]", &signal(case))
+                .unwrap()
+        };
+        assert!(announce(7).is_some());
+        assert_eq!(announce(7), None);
+        assert!(announce(8).is_some());
+
+        // Once the window has passed the case is announced again
+        let window = Duration::from_secs(plugin.config.dedupe_window_seconds);
+        for announced in plugin.seen_cases.lock().unwrap().values_mut() {
+            // Machines up for less than the window can't go back that far
+            let Some(earlier) = announced.checked_sub(window) else {
+                return;
+            };
+            *announced = earlier;
+        }
+        assert!(announce(7).is_some());
+        assert_eq!(announce(7), None);
+    }
+
+    #[test]
+    fn test_failed_route_is_tried_again() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond("/api/system", 400, "");
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let message = r#"RATSIGNAL - Case #7 PC – CMDR Whit3Arrow – System: "Alpha Centauri" – Language: English (en-US)"#;
+        let announce = || {
+            plugin
+                .process_message("MechaSqueak[BOT]", message)
+                .unwrap()
+                .unwrap_or_default()
+        };
+
+        assert!(announce().contains("❌"), "{}", announce());
+        assert!(plugin.seen_cases.lock().unwrap().is_empty());
+        // The re-post is planned again, and only then counts as announced
+        assert!(announce().starts_with("🚀 Case #7 [PC]: "));
+        assert_eq!(announce(), "");
+    }
+
+    #[test]
+    fn test_parse_case_updates() {
+        let plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        let update = |message: &str| {
+            plugin
+                .parse_case_update(message)
+                .map(|update| (update.case_number, update.event))
+        };
+
+        for message in [
+            "Closed case #7 (CMDR Whit3Arrow)",
+            "Case #7 (CMDR Whit3Arrow) has been closed",
+            "case #7 cleared by RatOne",
+        ] {
+            assert_eq!(
+                update(message),
+                Some(("7".to_string(), CaseEvent::Closed)),
+                "{message}"
+            );
+        }
+        for message in [
+            "Assigned RatOne, RatTwo to case #12",
+            "Case #12 assigned to RatOne",
+        ] {
+            assert_eq!(
+                update(message),
+                Some(("12".to_string(), CaseEvent::Assigned)),
+                "{message}"
+            );
+        }
+
+        assert_eq!(update("Good evening, rats"), None);
+        assert_eq!(update("Case #7 is still open"), None);
+        let signal = r#"RATSIGNAL - Case #7 PC – CMDR Whit3Arrow – System: "Closed Sector AB-C" – Language: English (en-US)"#;
+        assert_eq!(update(signal), None);
+    }
+
+    #[test]
+    fn test_open_cases_are_listed_until_closed() {
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            dedupe_window_seconds: 0,
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(
+            plugin.handle_cases_command(),
+            "📋 No open cases this session"
+        );
+
+        for (case, system) in [(3, "Colonia"), (4, "Sagittarius A*")] {
+            let signal = format!(
+                r#"RATSIGNAL - Case #{case} PC – CMDR Whit3Arrow – System: "{system}" (Brown dwarf 4.4 LY from Sol) – Language: English (en-US)"#
+            );
+            plugin.process_message("MechaSqueak[BOT]", &signal).unwrap();
+            // Distinct arrival times for the ordering
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let cases = plugin.open_cases();
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].case_number, "4");
+        assert_eq!(cases[1].system_name, "Colonia");
+
+        plugin
+            .process_message("MechaSqueak[BOT]", "Closed case #4 (CMDR Whit3Arrow)")
+            .unwrap();
+        let cases = plugin.open_cases();
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].case_number, "3");
+
+        let listed = plugin.handle_cases_command();
+        assert!(
+            listed.starts_with("📋 Open cases (1):\n  #3 Colonia: "),
+            "{listed}"
+        );
+        assert!(listed.ends_with(", just now"), "{listed}");
+        assert!(!listed.contains("#4"), "{listed}");
+    }
+
+    #[test]
+    fn test_recompute_open_cases_after_ship_switch() {
+        let _guard = test_support::CONFIG_PATH_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        config::set_config_path_override(Some(dir.path().join("edjc.toml")));
+
+        let plugin = EdJumpCalculator::from_config(Config {
+            offline_mode: true,
+            dedupe_window_seconds: 0,
+            ships: vec![
+                config::ShipConfig {
+                    name: "Anaconda".to_string(),
+                    laden_jump_range: 30.0,
+                    ..Default::default()
+                },
+                config::ShipConfig {
+                    name: "DBX".to_string(),
+                    laden_jump_range: 60.0,
+                    ..Default::default()
+                },
+            ],
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(
+            plugin.handle_recompute_command(),
+            "📋 No open cases to recompute"
+        );
+
+        for (case, system) in [
+            (3, "Colonia"),
+            (4, "Sagittarius A*"),
+            (5, "Nowhere Special"),
+        ] {
+            let signal = format!(
+                r#"RATSIGNAL - Case #{case} PC – CMDR Whit3Arrow – System: "{system}" – Language: English (en-US)"#
+            );
+            plugin.process_message("MechaSqueak[BOT]", &signal).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let before: HashMap<String, Option<u32>> = plugin
+            .open_cases()
+            .into_iter()
+            .map(|case| (case.case_number, case.jumps))
+            .collect();
+        assert_eq!(before["5"], None);
+
+        plugin.handle_ship_command("DBX");
+        let recomputed = plugin.recompute_open_cases();
+        let cases: Vec<&str> = recomputed
+            .iter()
+            .map(|(case, _)| case.case_number.as_str())
+            .collect();
+        assert_eq!(cases, ["5", "4", "3"]);
+        assert!(matches!(
+            recomputed[0].1,
+            Err(EdjcError::SystemNotBundled(_))
+        ));
+        for (case, jumps) in &recomputed[1..] {
+            let previous = before[&case.case_number].unwrap();
+            let jumps = *jumps.as_ref().unwrap();
+            assert!(
+                jumps < previous,
+                "#{}: {jumps} vs {previous}",
+                case.case_number
+            );
+        }
+
+        // The new counts are what /edjc-cases lists from now on
+        let after: HashMap<String, Option<u32>> = plugin
+            .open_cases()
+            .into_iter()
+            .map(|case| (case.case_number, case.jumps))
+            .collect();
+        assert_eq!(after["3"], recomputed[2].1.as_ref().ok().copied());
+        assert_eq!(after["5"], None);
+
+        let response = plugin.handle_recompute_command();
+        assert!(
+            response.starts_with(
+                "🔄 Recomputed 3 open cases for DBX (60.0 LY):\n  #5 Nowhere Special: skipped, "
+            ),
+            "{response}"
+        );
+        plugin.handle_ship_command("Anaconda");
+        let response = plugin.handle_recompute_command();
+        assert!(
+            response.contains(&format!(
+                "#3 Colonia: {} jumps (was {})",
+                before["3"].unwrap(),
+                after["3"].unwrap()
+            )),
+            "{response}"
+        );
+        config::set_config_path_override(None);
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(59)), "just now");
+        assert_eq!(format_age(Duration::from_secs(12 * 60 + 30)), "12m ago");
+        assert_eq!(
+            format_age(Duration::from_secs(2 * 3600 + 5 * 60)),
+            "2h 05m ago"
+        );
+    }
+
+    #[test]
+    fn test_closed_case_is_forgotten() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Alpha Centauri", "coords": {"x": 3.03, "y": 1.39, "z": 0.16}}"#,
+        );
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let signal = r#"RATSIGNAL - Case #7 PC – CMDR Whit3Arrow – System: "Alpha Centauri" (Brown dwarf 4.4 LY from Sol) – Language: English (en-US)"#;
+        let say = |plugin: &EdJumpCalculator, message: &str| {
+            plugin.process_message("MechaSqueak[BOT]", message).unwrap()
+        };
+
+        assert!(say(&plugin, signal).is_some());
+        assert_eq!(say(&plugin, "Case #7 assigned to RatOne"), None);
+        assert_eq!(say(&plugin, signal), None);
+        assert!(plugin.seen_cases.lock().unwrap().contains_key("7"));
+
+        // Closed quietly, then the number comes up for a new case
+        match plugin.process_signal("MechaSqueak[BOT]", "Closed case #7 (CMDR Whit3Arrow)") {
+            SignalOutcome::CaseUpdated(update) => {
+                assert_eq!(update.case_number, "7");
+                assert_eq!(update.event, CaseEvent::Closed);
+            }
+            outcome => panic!("{outcome:?}"),
+        }
+        assert!(plugin.seen_cases.lock().unwrap().is_empty());
+        assert!(say(&plugin, signal).is_some());
+
+        plugin.config.announce_case_closed = true;
+        assert_eq!(
+            say(&plugin, "Closed case #7 (CMDR Whit3Arrow)").as_deref(),
+            Some("✅ Case #7 closed")
+        );
+        assert_eq!(say(&plugin, "Case #7 assigned to RatOne"), None);
+        // Only the relay bots are listened to
+        assert!(matches!(
+            plugin.process_signal("SomeRat", "Closed case #8"),
+            SignalOutcome::Ignored
+        ));
+    }
+
+    #[test]
+    fn test_announce_cooldown_policies() {
+        let signal = |case: u32, system: &str| {
+            format!(
+                r#"RATSIGNAL - Case #{case} PC – CMDR Whit3Arrow – System: "{system}" (L star 20 LY from Sol) – Language: English (en-US)"#
+            )
+        };
+        let burst = |plugin: &EdJumpCalculator| {
+            [(1, "Deciat"), (2, "Maia"), (3, "Colonia")]
+                .into_iter()
+                .filter_map(|(case, system)| {
+                    plugin
+                        .process_message("MechaSqueak[BOT]", &signal(case, system))
+                        .unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+        let config = |cooldown_policy| Config {
+            offline_mode: true,
+            announce_cooldown_seconds: 60,
+            cooldown_policy,
+            ..test_config()
+        };
+        let end_cooldown = |plugin: &EdJumpCalculator| {
+            let mut gate = plugin.announce_gate.lock().unwrap();
+            // Machines up for less than a minute count as never having shown one
+            gate.last_shown = gate
+                .last_shown
+                .and_then(|shown| shown.checked_sub(Duration::from_secs(60)));
+        };
+
+        // Dropped: only the first of the burst is shown, the rest are gone
+        let plugin = EdJumpCalculator::from_config(config(CooldownPolicy::Drop)).unwrap();
+        let shown = burst(&plugin);
+        assert_eq!(shown.len(), 1);
+        assert!(shown[0].contains("Case #1"), "{}", shown[0]);
+        end_cooldown(&plugin);
+        assert_eq!(plugin.next_queued_notice(), None);
+
+        // Queued: the rest come out one per cooldown
+        let plugin = EdJumpCalculator::from_config(config(CooldownPolicy::Queue)).unwrap();
+        let shown = burst(&plugin);
+        assert_eq!(shown.len(), 1);
+        assert!(shown[0].contains("Case #1"), "{}", shown[0]);
+        assert_eq!(plugin.next_queued_notice(), None);
+        end_cooldown(&plugin);
+        let queued = plugin.next_queued_notice().unwrap();
+        assert!(queued.contains("Case #2"), "{queued}");
+        assert_eq!(plugin.next_queued_notice(), None);
+        end_cooldown(&plugin);
+        let queued = plugin.next_queued_notice().unwrap();
+        assert!(queued.contains("Case #3"), "{queued}");
+        end_cooldown(&plugin);
+        assert_eq!(plugin.next_queued_notice(), None);
+
+        // A long burst only keeps the latest notices
+        let plugin = EdJumpCalculator::from_config(config(CooldownPolicy::Queue)).unwrap();
+        for case in 1..=MAX_QUEUED_NOTICES + 2 {
+            plugin
+                .process_message("MechaSqueak[BOT]", &signal(case as u32, "Deciat"))
+                .unwrap();
+        }
+        assert_eq!(
+            plugin.announce_gate.lock().unwrap().queued.len(),
+            MAX_QUEUED_NOTICES
+        );
+        end_cooldown(&plugin);
+        let queued = plugin.next_queued_notice().unwrap();
+        assert!(queued.contains("Case #3"), "{queued}");
+
+        // Without a cooldown every notice is shown straight away
+        let plugin = EdJumpCalculator::from_config(Config {
+            announce_cooldown_seconds: 0,
+            ..config(CooldownPolicy::Drop)
+        })
+        .unwrap();
+        assert_eq!(burst(&plugin).len(), 3);
+    }
+
+    #[test]
+    fn test_case_system_not_yet_trilaterated() {
+        let server = test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond("/api/system", 200, r#"{"name": "Oochost XY-Z a1"}"#);
+
+        let mut plugin = EdJumpCalculator::from_config(test_config()).unwrap();
+        plugin.edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+
+        let signal = r#"RATSIGNAL - Case #4 PC – CMDR Whit3Arrow – System: "Oochost XY-Z a1" (Unconfirmed) – Language: English (en-US)"#;
+        assert_eq!(
+            plugin.process_message("MechaSqueak[BOT]", signal).unwrap(),
+            Some("📡 Case #4 [PC]: Oochost XY-Z a1 is known to EDSM but not yet trilaterated, ask for a nearby known system".to_string())
+        );
+    }
 }
@@ -0,0 +1,203 @@
+/*!
+Output sinks for plugin responses.
+
+Rendering a response and delivering it are kept separate: the plugin formats
+lines and hands them to an [`OutputSink`], which decides where they end up
+(HexChat, stderr, a file, or memory for tests and embedders).
+*/
+
+use log::error;
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::hexchat;
+
+/// Plain-text stand-ins for status glyphs, for `use_emoji = false`
+const PLAIN_GLYPHS: &[(&str, &str)] = &[
+    ("🚀", "[OK]"),
+    ("✓", "[OK]"),
+    ("❌", "[ERR]"),
+    ("✗", "[ERR]"),
+    ("🚫", "[ERR]"),
+    ("⚠️", "[WARN]"),
+    ("⚠", "[WARN]"),
+    ("⇄", "<->"),
+    ("→", "->"),
+];
+
+/// Text with its status glyphs written as ASCII markers ("[OK]", "[ERR]",
+/// "[WARN]") and other emoji left out, for themes and terminals that show
+/// them as boxes
+pub fn plain_glyphs(text: &str) -> String {
+    let text = PLAIN_GLYPHS
+        .iter()
+        .fold(text.to_string(), |text, (glyph, plain)| {
+            text.replace(glyph, plain)
+        });
+
+    let mut plain = String::with_capacity(text.len());
+    let mut dropped = false;
+    for c in text.chars() {
+        if is_emoji(c) {
+            dropped = true;
+            continue;
+        }
+        // The space after a dropped emoji would be left dangling
+        if !(dropped && c == ' ') {
+            plain.push(c);
+        }
+        dropped = false;
+    }
+    plain
+}
+
+/// Whether a character is (part of) an emoji: pictographs, dingbats, arrows
+/// drawn as emoji, and the selectors and joiners between them
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1F000}'..='\u{1FAFF}'
+            | '\u{2190}'..='\u{21FF}'
+            | '\u{2300}'..='\u{23FF}'
+            | '\u{2600}'..='\u{27BF}'
+            | '\u{2B00}'..='\u{2BFF}'
+            | '\u{FE0F}'
+            | '\u{200D}'
+    )
+}
+
+/// Destination for lines produced by the plugin
+pub trait OutputSink: Debug + Send + Sync {
+    /// Emit a single line of output
+    fn emit(&self, line: &str);
+}
+
+/// Prints lines through HexChat
+#[derive(Debug, Default)]
+pub struct HexChatSink;
+
+impl OutputSink for HexChatSink {
+    fn emit(&self, line: &str) {
+        hexchat::print_text(line);
+    }
+}
+
+/// Says lines in the current HexChat channel, for everyone there to see
+#[derive(Debug, Default)]
+pub struct ChannelSink;
+
+impl OutputSink for ChannelSink {
+    fn emit(&self, line: &str) {
+        hexchat::print_to_active_channel(line);
+    }
+}
+
+/// Writes lines to stderr
+#[derive(Debug, Default)]
+pub struct StderrSink;
+
+impl OutputSink for StderrSink {
+    fn emit(&self, line: &str) {
+        eprintln!("{line}");
+    }
+}
+
+/// Appends lines to a file
+#[derive(Debug)]
+pub struct FileSink {
+    file: Mutex<File>,
+}
+
+impl FileSink {
+    /// Open (or create) a file for appending
+    pub fn new(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl OutputSink for FileSink {
+    fn emit(&self, line: &str) {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(file, "{line}") {
+            error!("Could not write output line to file: {e}");
+        }
+    }
+}
+
+/// Collects lines in memory
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    lines: Mutex<Vec<String>>,
+}
+
+impl MemorySink {
+    /// Create an empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lines emitted so far
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn emit(&self, line: &str) {
+        self.lines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(line.to_string());
+    }
+}
+
+impl<T: OutputSink + ?Sized> OutputSink for std::sync::Arc<T> {
+    fn emit(&self, line: &str) {
+        (**self).emit(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_plain_glyphs() {
+        assert_eq!(
+            plain_glyphs("🚀 5 jumps to Colonia"),
+            "[OK] 5 jumps to Colonia"
+        );
+        assert_eq!(
+            plain_glyphs("❌ Route calculation failed\n⚠️ CMDR position unknown"),
+            "[ERR] Route calculation failed\n[WARN] CMDR position unknown"
+        );
+        assert_eq!(
+            plain_glyphs("🔁 Round trip Sol ⇄ Colonia\n➡️ There: Sol → Colonia"),
+            "Round trip Sol <-> Colonia\nThere: Sol -> Colonia"
+        );
+        assert_eq!(
+            plain_glyphs("Col 285 Sector AB-C d1-2"),
+            "Col 285 Sector AB-C d1-2"
+        );
+    }
+
+    #[test]
+    fn test_file_sink_appends_lines() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("output.log");
+
+        let sink = FileSink::new(&path).unwrap();
+        sink.emit("first");
+        sink.emit("second");
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "first\nsecond\n");
+    }
+}
@@ -0,0 +1,998 @@
+/*!
+Route planning shared by the plugin and the standalone tools.
+
+[`RoutePlanner`] works out where a route starts (a given origin, the
+commander's location or Sol), looks both ends up in a [`SystemSource`] and
+calculates the route, with Spansh or real neutron stars along the corridor
+when those are wired in.
+*/
+
+use log::{info, warn};
+use std::time::Duration;
+
+use crate::config::{Config, ShipConfig};
+use crate::edsm::EdsmClient;
+use crate::jump_calculator::{JumpCalculator, RouteDetails};
+use crate::regions::{self, Region};
+use crate::spansh::SpanshClient;
+use crate::system_source::SystemSource;
+use crate::telemetry::ApiSpan;
+use crate::types::{
+    Deadline, EdjcError, EdjcResult, JumpResult, RouteOptions, SystemCoordinates, TimeModel,
+};
+
+/// Default `fallback_origin`, used when the commander's location is unknown
+pub const DEFAULT_FALLBACK_ORIGIN: &str = "Sol";
+
+/// A calculated route together with where it starts
+#[derive(Debug, Clone)]
+pub struct PlannedRoute {
+    pub result: JumpResult,
+    /// Origin system as requested (or located)
+    pub origin: String,
+    /// Jump range the route was planned with, laden or maximum
+    pub jump_range: f64,
+    /// Coordinates of the origin system
+    pub from: SystemCoordinates,
+    /// Coordinates of the target system
+    pub to: SystemCoordinates,
+    /// Galactic region of the target system
+    pub region: Region,
+    /// Warnings about the route (fallback origin, permits, hidden systems)
+    pub warnings: Vec<String>,
+    /// Heuristic notes about the route, shown when `show_route_hints` is set
+    pub hints: Vec<String>,
+}
+
+/// Plans routes for a configuration against a system source
+#[derive(Debug)]
+pub struct RoutePlanner<'a> {
+    config: &'a Config,
+    systems: &'a dyn SystemSource,
+    /// Sources asked for the commander's location, in order
+    location_sources: Vec<&'a dyn SystemSource>,
+    ship: ShipConfig,
+    /// Commander whose location routes start from
+    cmdr_name: String,
+    spansh_client: Option<&'a SpanshClient>,
+    /// Where real neutron stars along long routes are looked up, if anywhere
+    corridor_client: Option<&'a EdsmClient>,
+    /// Plan with the ship's maximum jump range instead of the laden one
+    use_max_range: bool,
+    /// Whether routes may supercharge at neutron stars
+    use_neutron_stars: bool,
+    /// Whether routes may supercharge at white dwarfs
+    use_white_dwarfs: bool,
+    /// Whether routes start supercharged at the origin's neutron star
+    boost_at_origin: bool,
+    /// How long planning one route may take, lookups included
+    timeout: Duration,
+    /// Where routes start instead of a looked-up origin, if given by hand
+    origin_coordinates: Option<SystemCoordinates>,
+    /// Where routes end instead of a looked-up target, if already known
+    target_coordinates: Option<SystemCoordinates>,
+}
+
+impl<'a> RoutePlanner<'a> {
+    /// Plan for the configured ship, looking systems up in `systems`
+    pub fn new(config: &'a Config, systems: &'a dyn SystemSource) -> Self {
+        Self {
+            config,
+            systems,
+            location_sources: vec![systems],
+            ship: config.ship().clone(),
+            cmdr_name: config.cmdr_name.clone(),
+            spansh_client: None,
+            corridor_client: None,
+            use_max_range: false,
+            use_neutron_stars: true,
+            use_white_dwarfs: true,
+            boost_at_origin: false,
+            timeout: Duration::from_secs(config.plan_timeout_seconds),
+            origin_coordinates: None,
+            target_coordinates: None,
+        }
+    }
+
+    /// Also ask another source for the commander's location
+    pub fn with_location_fallback(mut self, source: &'a dyn SystemSource) -> Self {
+        self.location_sources.push(source);
+        self
+    }
+
+    /// Ask these sources for the commander's location, in this order,
+    /// instead of the system source and its fallbacks
+    pub fn with_location_sources(mut self, sources: Vec<&'a dyn SystemSource>) -> Self {
+        self.location_sources = sources;
+        self
+    }
+
+    /// Plan for another ship than the configured default
+    pub fn with_ship(mut self, ship: ShipConfig) -> Self {
+        self.ship = ship;
+        self
+    }
+
+    /// Locate another commander than the configured one
+    pub fn with_cmdr_name(mut self, cmdr_name: impl Into<String>) -> Self {
+        self.cmdr_name = cmdr_name.into();
+        self
+    }
+
+    /// Plot routes with Spansh, falling back to the built-in calculator
+    pub fn with_spansh(mut self, spansh_client: &'a SpanshClient) -> Self {
+        self.spansh_client = Some(spansh_client);
+        self
+    }
+
+    /// Route long trips through real neutron stars found on EDSM
+    pub fn with_neutron_corridor(mut self, edsm_client: &'a EdsmClient) -> Self {
+        self.corridor_client = Some(edsm_client);
+        self
+    }
+
+    /// Plan with the ship's `max_jump_range` (empty, fuel-optimized jumps)
+    /// instead of its laden range
+    pub fn with_max_range(mut self, use_max_range: bool) -> Self {
+        self.use_max_range = use_max_range;
+        self
+    }
+
+    /// Start routes at these coordinates (e.g. read off the galaxy map)
+    /// instead of looking the origin up, whatever origin is passed
+    pub fn with_origin_coordinates(mut self, origin: SystemCoordinates) -> Self {
+        self.origin_coordinates = Some(origin);
+        self
+    }
+
+    /// End routes at these coordinates instead of looking the target up,
+    /// e.g. on the way back to a point given with
+    /// [`Self::with_origin_coordinates`]
+    pub fn with_target_coordinates(mut self, target: SystemCoordinates) -> Self {
+        self.target_coordinates = Some(target);
+        self
+    }
+
+    /// Only supercharge at the kinds of star allowed here, e.g. for pilots
+    /// avoiding the FSD damage of neutron stars
+    ///
+    /// Without neutron stars Spansh (a neutron plotter) and the EDSM corridor
+    /// survey are skipped too.
+    pub fn with_boost_stars(mut self, neutron_stars: bool, white_dwarfs: bool) -> Self {
+        self.use_neutron_stars = neutron_stars;
+        self.use_white_dwarfs = white_dwarfs;
+        self
+    }
+
+    /// Plan as if the origin had a neutron star to supercharge the first
+    /// jump at, e.g. the way back from a case whose system has one
+    ///
+    /// Spansh doesn't know about the boost, so it isn't asked.
+    pub fn with_origin_boost(mut self, boost_at_origin: bool) -> Self {
+        self.boost_at_origin = boost_at_origin;
+        self
+    }
+
+    /// Give up planning a route after `timeout` instead of
+    /// `plan_timeout_seconds`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// A deadline for planning one route, starting now
+    ///
+    /// Pass it to [`Self::locate_commander_by`] and [`Self::plan_from_by`]
+    /// when locating the commander and planning are done separately, so
+    /// both count towards the same limit.
+    pub fn deadline(&self) -> Deadline {
+        Deadline::after(self.timeout)
+    }
+
+    /// Jump calculator for the ship
+    pub fn jump_calculator(&self) -> JumpCalculator {
+        // validate_config rejects incomplete FSD settings; estimate without them
+        JumpCalculator::new()
+            .with_fsd_profile(self.ship.fsd_profile().ok().flatten())
+            .with_multipliers(
+                self.config.neutron_multiplier,
+                self.config.white_dwarf_multiplier,
+            )
+            .with_boost_thresholds(
+                self.config.neutron_highway_threshold_ly,
+                self.config.white_dwarf_threshold_ly,
+            )
+            .with_time_model(TimeModel {
+                seconds_per_jump: self.config.seconds_per_jump,
+                supercharge_seconds: self.config.boost_penalty_seconds,
+                neutron_seconds: self.config.neutron_penalty_seconds,
+                scoop_stop_seconds: self.config.scoop_stop_seconds,
+            })
+            .with_fuel_capacity(self.ship.fuel_capacity)
+    }
+
+    /// Route options derived from the configuration
+    pub fn route_options(&self) -> RouteOptions {
+        RouteOptions {
+            use_neutron_stars: self.use_neutron_stars,
+            use_white_dwarfs: self.use_white_dwarfs,
+            require_boost: self.config.require_boost,
+            max_detour_ly: self.config.max_detour_ly,
+            max_detour_percent: self.config.max_detour_percent,
+            prefer_populated: self.config.prefer_populated,
+            populated_detour_jumps: self.config.populated_detour_jumps,
+            optimize_for: self.config.optimize_for,
+            seconds_per_jump: self.config.seconds_per_jump,
+            boost_penalty_seconds: self.config.boost_penalty_seconds,
+            neutron_penalty_seconds: self.config.neutron_penalty_seconds,
+            scoop_stop_seconds: self.config.scoop_stop_seconds,
+            premium_injections: self.config.premium_injections,
+            interpolate_range: self.config.interpolate_jump_range,
+            max_jump_range: self.ship.max_jump_range,
+            use_max_range: self.use_max_range,
+            boost_at_origin: self.boost_at_origin,
+            fuel_capacity: self.ship.fuel_capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Look up the commander's current location, trying each source in turn
+    ///
+    /// When no source knows it, the first error is returned, preferring one
+    /// that says why (a private profile, a network failure) over a source
+    /// that just doesn't know the commander.
+    pub fn locate_commander(&self) -> EdjcResult<String> {
+        self.locate_commander_by(&self.deadline())
+    }
+
+    /// [`Self::locate_commander`], failing with a timeout instead of asking
+    /// the next source once `deadline` has passed
+    pub fn locate_commander_by(&self, deadline: &Deadline) -> EdjcResult<String> {
+        let cmdr_name = &self.cmdr_name;
+        let api_key = self.config.edsm_api_key.as_deref();
+
+        let mut first_error: Option<EdjcError> = None;
+        for &source in &self.location_sources {
+            match source.get_commander_location_by(cmdr_name, api_key, deadline) {
+                Ok(system) => {
+                    info!(
+                        "Using CMDR {cmdr_name}'s current location from {}: {system}",
+                        source.name()
+                    );
+                    return Ok(system);
+                }
+                Err(e @ EdjcError::Timeout(_)) => return Err(e),
+                Err(e) => {
+                    warn!("Could not get CMDR location from {}: {e}", source.name());
+                    let unknown = |e: &EdjcError| matches!(e, EdjcError::CmdrNotFound(_));
+                    if first_error
+                        .as_ref()
+                        .is_none_or(|first| unknown(first) && !unknown(&e))
+                    {
+                        first_error = Some(e);
+                    }
+                }
+            }
+        }
+        Err(first_error.unwrap_or_else(|| {
+            EdjcError::CmdrNotFound(format!("{cmdr_name} (no location sources)"))
+        }))
+    }
+
+    /// The commander's current location, if any source knows it
+    pub fn commander_location(&self) -> Option<String> {
+        self.locate_commander().ok()
+    }
+
+    /// Plan a route to the target system for a jump range, from `origin` or
+    /// else the commander's location (`fallback_origin` when that is unknown)
+    pub fn plan(
+        &self,
+        target_system: &str,
+        jump_range: f64,
+        origin: Option<&str>,
+    ) -> EdjcResult<PlannedRoute> {
+        let deadline = self.deadline();
+        let origin = origin
+            .map(str::to_string)
+            .or_else(|| self.locate_commander_by(&deadline).ok());
+        self.plan_from_by(target_system, jump_range, origin, &deadline)
+    }
+
+    /// Plan a route from an already resolved origin, or from `fallback_origin`
+    /// when the commander's location is unknown (`None`)
+    ///
+    /// `jump_range` is the laden range; see [`Self::with_max_range`].
+    pub fn plan_from(
+        &self,
+        target_system: &str,
+        jump_range: f64,
+        origin: Option<String>,
+    ) -> EdjcResult<PlannedRoute> {
+        self.plan_from_by(target_system, jump_range, origin, &self.deadline())
+    }
+
+    /// [`Self::plan_from`], failing with a timeout instead of making the
+    /// next lookup once `deadline` has passed
+    pub fn plan_from_by(
+        &self,
+        target_system: &str,
+        jump_range: f64,
+        origin: Option<String>,
+        deadline: &Deadline,
+    ) -> EdjcResult<PlannedRoute> {
+        let span = ApiSpan::plan_route(target_system);
+        let mut warnings = Vec::new();
+
+        let origin = match (&self.origin_coordinates, origin) {
+            (Some(coordinates), _) => coordinates.name.clone(),
+            (None, Some(system)) => system,
+            (None, None) => {
+                let fallback = &self.config.fallback_origin;
+                warn!("Using {fallback} as fallback origin");
+                warnings.push(format!("CMDR position unknown, routing from {fallback}"));
+                fallback.clone()
+            }
+        };
+        span.record("origin", &origin);
+
+        let options = self.route_options();
+        if self.use_max_range && self.ship.max_jump_range.is_none() {
+            warnings.push("No max_jump_range configured, planned with the laden range".to_string());
+        }
+        let planning_range = options.planning_range(jump_range);
+
+        let from = match &self.origin_coordinates {
+            Some(coordinates) => coordinates.clone(),
+            None => self.systems.get_system_coordinates_by(&origin, deadline)?,
+        };
+        let to = match &self.target_coordinates {
+            Some(coordinates) => coordinates.clone(),
+            None => self
+                .systems
+                .get_system_coordinates_by(target_system, deadline)?,
+        };
+        let calculator = self.jump_calculator();
+
+        // Both ends are known by now, so running out of time still leaves
+        // the direct estimate to give
+        let result =
+            match self.find_route(&from, &to, jump_range, &options, &mut warnings, deadline) {
+                Err(EdjcError::Timeout(detail)) => {
+                    warn!("Pathfinding timed out ({detail}), giving the direct estimate");
+                    calculator.timed_out_estimate(&from, &to, jump_range, &options)
+                }
+                result => result?,
+            };
+        warnings.extend(calculator.route_warnings(&to));
+        span.record("route", &result.route_type);
+        span.jumps(result.jumps);
+
+        Ok(PlannedRoute {
+            result,
+            origin,
+            region: regions::galactic_region(&to),
+            warnings,
+            hints: calculator.route_hints(&from, &to),
+            jump_range: planning_range,
+            from,
+            to,
+        })
+    }
+
+    /// The route between two looked-up systems: Spansh's when it is wired
+    /// in and answers (and the route doesn't start supercharged), otherwise
+    /// the built-in calculator's through any neutron stars found along the
+    /// corridor
+    fn find_route(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        jump_range: f64,
+        options: &RouteOptions,
+        warnings: &mut Vec<String>,
+        deadline: &Deadline,
+    ) -> EdjcResult<JumpResult> {
+        // Spansh can't be told about a supercharge at the origin
+        let spansh = if self.boost_at_origin {
+            None
+        } else {
+            if self.spansh_client.is_some() {
+                deadline.check("plotting the route with Spansh")?;
+            }
+            self.plot_spansh_route(from, to, options.planning_range(jump_range))
+        };
+        match spansh {
+            Some(Ok(result)) => Ok(result),
+            spansh => {
+                if let Some(Err(e)) = spansh {
+                    warn!("Spansh route failed: {e}");
+                    warnings.push(format!(
+                        "Spansh unavailable ({e}), using the built-in estimate"
+                    ));
+                }
+                let corridor_neutrons = self.corridor_neutron_stars(from, to, deadline)?;
+                let populated = self.corridor_populated_systems(
+                    from,
+                    to,
+                    corridor_neutrons.as_deref(),
+                    deadline,
+                )?;
+                self.jump_calculator()
+                    .with_populated_systems(&populated)
+                    .calculate_route_with_corridor(
+                        from,
+                        to,
+                        jump_range,
+                        options,
+                        corridor_neutrons.as_deref(),
+                    )
+            }
+        }
+    }
+
+    /// Plot the route with Spansh, when it is wired in and neutron stars
+    /// may be used
+    fn plot_spansh_route(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        jump_range: f64,
+    ) -> Option<EdjcResult<JumpResult>> {
+        let spansh_client = self.spansh_client.filter(|_| self.use_neutron_stars)?;
+        Some(spansh_client.plot_neutron_route(
+            &from.name,
+            &to.name,
+            jump_range,
+            self.config.spansh_efficiency,
+        ))
+    }
+
+    /// Real neutron stars between two systems, for routes long enough to
+    /// justify the neutron highway (`None` falls back to the estimate)
+    ///
+    /// Only running out of time is an error; a failed lookup just means
+    /// estimating.
+    fn corridor_neutron_stars(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        deadline: &Deadline,
+    ) -> EdjcResult<Option<Vec<SystemCoordinates>>> {
+        let Some(edsm_client) = self.corridor_client.filter(|_| self.use_neutron_stars) else {
+            return Ok(None);
+        };
+        let distance = self.jump_calculator().calculate_distance(from, to);
+        if distance < self.config.neutron_highway_threshold_ly {
+            return Ok(None);
+        }
+
+        match edsm_client.corridor_neutron_stars_by(from, to, deadline) {
+            Ok(neutron_stars) => Ok(Some(neutron_stars)),
+            Err(e @ EdjcError::Timeout(_)) => Err(e),
+            Err(e) => {
+                warn!("Neutron corridor lookup failed, estimating instead: {e}");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Populated systems along the corridor, when `prefer_populated` has
+    /// neutron stars to choose between
+    ///
+    /// As with the neutron stars, only running out of time is an error; a
+    /// failed lookup just means no preference.
+    fn corridor_populated_systems(
+        &self,
+        from: &SystemCoordinates,
+        to: &SystemCoordinates,
+        neutron_stars: Option<&[SystemCoordinates]>,
+        deadline: &Deadline,
+    ) -> EdjcResult<Vec<SystemCoordinates>> {
+        let (Some(edsm_client), Some(neutron_stars)) = (self.corridor_client, neutron_stars) else {
+            return Ok(Vec::new());
+        };
+        if !self.config.prefer_populated || neutron_stars.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        match edsm_client.corridor_populated_systems_by(from, to, deadline) {
+            Ok(populated) => Ok(populated),
+            Err(e @ EdjcError::Timeout(_)) => Err(e),
+            Err(e) => {
+                warn!("Populated systems lookup failed, not preferring any: {e}");
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+/// Plan a route for the planner's ship and describe it with its estimates
+/// and warnings
+///
+/// Starts from `origin`, or else the commander's location (`fallback_origin`
+/// when that is unknown).
+pub fn plan_route(
+    planner: &RoutePlanner<'_>,
+    target: &str,
+    origin: Option<&str>,
+) -> EdjcResult<RouteDetails> {
+    let planned = planner.plan(target, planner.ship.laden_jump_range, origin)?;
+    let details = planner
+        .jump_calculator()
+        .describe_route(planned.result, planned.jump_range);
+    Ok(RouteDetails {
+        warnings: planned.warnings,
+        ..details
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jump_calculator::TIMED_OUT_ROUTE;
+
+    /// Source with three systems in a line and the commander at Home
+    #[derive(Debug)]
+    struct FakeSource {
+        cmdr_location: Option<&'static str>,
+    }
+
+    impl SystemSource for FakeSource {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+            let z = match system_name {
+                "Sol" => 0.0,
+                "Home" => 20.0,
+                "Target" => 120.0,
+                _ => return Err(EdjcError::system_not_found(system_name)),
+            };
+            Ok(SystemCoordinates {
+                name: system_name.to_string(),
+                x: 0.0,
+                y: 0.0,
+                z,
+                has_neutron_star: false,
+                has_white_dwarf: false,
+                primary_star_kind: None,
+                is_hidden: false,
+                required_permit: None,
+            })
+        }
+
+        fn get_commander_location(
+            &self,
+            cmdr_name: &str,
+            _api_key: Option<&str>,
+        ) -> EdjcResult<String> {
+            self.cmdr_location
+                .map(str::to_string)
+                .ok_or_else(|| EdjcError::CmdrNotFound(cmdr_name.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_plan_route_from_given_origin() {
+        let config = Config::default();
+        let systems = FakeSource {
+            cmdr_location: Some("Home"),
+        };
+
+        let planner = RoutePlanner::new(&config, &systems);
+        let details = plan_route(&planner, "Target", Some("Sol")).unwrap();
+        assert_eq!(details.result.from_system, "Sol");
+        assert_eq!(details.result.to_system, "Target");
+        assert_eq!(details.result.total_distance, 120.0);
+        assert!(details.estimated_fuel_usage > 0.0);
+
+        let err = plan_route(&planner, "Nowhere", Some("Sol")).unwrap_err();
+        assert!(matches!(
+            err,
+            EdjcError::SystemNotFound { name, .. } if name == "Nowhere"
+        ));
+
+        let err = RoutePlanner::new(&config, &systems)
+            .plan("Target", 0.0, Some("Sol"))
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::InvalidJumpRange(_)), "{err}");
+    }
+
+    #[test]
+    fn test_plan_route_from_commander_location() {
+        let config = Config::default();
+        let located = FakeSource {
+            cmdr_location: Some("Home"),
+        };
+
+        let planned = RoutePlanner::new(&config, &located)
+            .plan("Target", 30.0, None)
+            .unwrap();
+        assert_eq!(planned.origin, "Home");
+        assert_eq!(planned.from.z, 20.0);
+        assert_eq!(planned.result.total_distance, 100.0);
+        assert!(planned.warnings.is_empty(), "{:?}", planned.warnings);
+        let details = plan_route(&RoutePlanner::new(&config, &located), "Target", None).unwrap();
+        assert_eq!(details.result.from_system, "Home");
+
+        // Fallback sources are asked when the first cannot locate the CMDR
+        let unlocated = FakeSource {
+            cmdr_location: None,
+        };
+        let planned = RoutePlanner::new(&config, &unlocated)
+            .with_location_fallback(&located)
+            .plan("Target", 30.0, None)
+            .unwrap();
+        assert_eq!(planned.origin, "Home");
+
+        let planned = RoutePlanner::new(&config, &unlocated)
+            .plan("Target", 30.0, None)
+            .unwrap();
+        assert_eq!(planned.origin, DEFAULT_FALLBACK_ORIGIN);
+        assert_eq!(
+            planned.warnings,
+            vec!["CMDR position unknown, routing from Sol".to_string()]
+        );
+
+        let config = Config {
+            fallback_origin: "Home".to_string(),
+            ..Config::default()
+        };
+        let planned = RoutePlanner::new(&config, &unlocated)
+            .plan("Target", 30.0, None)
+            .unwrap();
+        assert_eq!(planned.origin, "Home");
+        assert_eq!(planned.result.total_distance, 100.0);
+        assert_eq!(
+            planned.warnings,
+            vec!["CMDR position unknown, routing from Home".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_location_sources_in_priority_order() {
+        let config = Config::default();
+        let systems = FakeSource {
+            cmdr_location: Some("Home"),
+        };
+        let at_sol = FakeSource {
+            cmdr_location: Some("Sol"),
+        };
+        let unlocated = FakeSource {
+            cmdr_location: None,
+        };
+
+        // The first source that knows the CMDR wins, in the order given
+        let planner = RoutePlanner::new(&config, &systems)
+            .with_location_sources(vec![&unlocated, &at_sol, &systems]);
+        assert_eq!(planner.locate_commander().unwrap(), "Sol");
+        let planner =
+            RoutePlanner::new(&config, &systems).with_location_sources(vec![&systems, &at_sol]);
+        assert_eq!(planner.locate_commander().unwrap(), "Home");
+
+        #[derive(Debug)]
+        struct PrivateSource;
+        impl SystemSource for PrivateSource {
+            fn name(&self) -> &'static str {
+                "private"
+            }
+
+            fn get_system_coordinates(&self, name: &str) -> EdjcResult<SystemCoordinates> {
+                Err(EdjcError::system_not_found(name))
+            }
+
+            fn get_commander_location(&self, cmdr: &str, _: Option<&str>) -> EdjcResult<String> {
+                Err(EdjcError::ProfilePrivate(cmdr.to_string()))
+            }
+        }
+
+        // A private profile explains more than a source not knowing the CMDR
+        let err = RoutePlanner::new(&config, &systems)
+            .with_location_sources(vec![&unlocated, &PrivateSource])
+            .locate_commander()
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::ProfilePrivate(_)), "{err}");
+
+        let planner = RoutePlanner::new(&config, &systems).with_location_sources(Vec::new());
+        let err = planner.locate_commander().unwrap_err();
+        assert!(matches!(err, EdjcError::CmdrNotFound(_)), "{err}");
+        let planned = planner.plan("Target", 30.0, None).unwrap();
+        assert_eq!(planned.origin, DEFAULT_FALLBACK_ORIGIN);
+    }
+
+    #[test]
+    fn test_plan_timeout() {
+        /// A source taking 100 ms per lookup, like a sluggish EDSM
+        #[derive(Debug)]
+        struct SlowSource(FakeSource);
+        impl SystemSource for SlowSource {
+            fn name(&self) -> &'static str {
+                "slow"
+            }
+
+            fn get_system_coordinates(&self, name: &str) -> EdjcResult<SystemCoordinates> {
+                std::thread::sleep(Duration::from_millis(100));
+                self.0.get_system_coordinates(name)
+            }
+
+            fn get_commander_location(&self, cmdr: &str, key: Option<&str>) -> EdjcResult<String> {
+                std::thread::sleep(Duration::from_millis(100));
+                self.0.get_commander_location(cmdr, key)
+            }
+        }
+
+        let config = Config::default();
+        let systems = SlowSource(FakeSource {
+            cmdr_location: Some("Home"),
+        });
+        let planner = RoutePlanner::new(&config, &systems).with_timeout(Duration::from_millis(150));
+
+        // Two lookups fit in the deadline
+        let planned = planner.plan("Target", 30.0, Some("Sol")).unwrap();
+        assert_eq!(planned.result.total_distance, 120.0);
+
+        // Locating the CMDR and the origin use it up, the target is never asked for
+        let err = planner.plan("Target", 30.0, None).unwrap_err();
+        assert!(
+            matches!(&err, EdjcError::Timeout(detail) if detail.ends_with("before looking up Target")),
+            "{err}"
+        );
+        assert!(err.hint().is_some());
+
+        // A deadline shared with an earlier step is already partly used
+        let deadline = planner.deadline();
+        planner.locate_commander_by(&deadline).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        let err = planner
+            .plan_from_by("Target", 30.0, Some("Home".to_string()), &deadline)
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::Timeout(_)), "{err}");
+    }
+
+    #[test]
+    fn test_pathfinding_timeout_gives_direct_estimate() {
+        #[derive(Debug)]
+        struct SlowSource(FakeSource);
+        impl SystemSource for SlowSource {
+            fn name(&self) -> &'static str {
+                "slow"
+            }
+
+            fn get_system_coordinates(&self, name: &str) -> EdjcResult<SystemCoordinates> {
+                std::thread::sleep(Duration::from_millis(100));
+                self.0.get_system_coordinates(name)
+            }
+        }
+
+        let server = crate::test_support::MockServer::start();
+        server.respond("/api/sphere-systems", 200, "[]");
+        let edsm_client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let config = Config {
+            neutron_highway_threshold_ly: 50.0,
+            ..Config::default()
+        };
+        let systems = SlowSource(FakeSource {
+            cmdr_location: Some("Home"),
+        });
+        let planner = RoutePlanner::new(&config, &systems)
+            .with_neutron_corridor(&edsm_client)
+            .with_timeout(Duration::from_millis(150));
+
+        // Both lookups use up the deadline, leaving no time for the corridor
+        let planned = planner.plan("Target", 30.0, Some("Home")).unwrap();
+        assert_eq!(planned.result.route_type, TIMED_OUT_ROUTE);
+        assert_eq!(planned.result.jumps, 4);
+        assert_eq!(planned.result.total_distance, 100.0);
+        assert_eq!(planned.result.legs.len(), 1);
+        assert_eq!(server.hits("/api/sphere-systems"), 0);
+
+        // With time to spare the corridor is searched as usual
+        let planner = RoutePlanner::new(&config, &systems)
+            .with_neutron_corridor(&edsm_client)
+            .with_timeout(Duration::from_secs(5));
+        let planned = planner.plan("Target", 30.0, Some("Home")).unwrap();
+        assert_ne!(planned.result.route_type, TIMED_OUT_ROUTE);
+        assert_eq!(server.hits("/api/sphere-systems"), 1);
+    }
+
+    /// EDSM client answering from a mock server
+    fn mock_edsm(server: &crate::test_support::MockServer) -> EdsmClient {
+        EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"))
+    }
+
+    #[test]
+    fn test_plan_route_against_mock_edsm() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Colonia", "coords": {"x": -9530.5, "y": -910.28125, "z": 19808.125}}"#,
+        );
+        server.respond("/api/system", 200, "[]");
+        let edsm_client = mock_edsm(&server);
+
+        let config = Config::default();
+        let planner = RoutePlanner::new(&config, &edsm_client);
+        let details = plan_route(&planner, "Colonia", Some("Sol")).unwrap();
+        assert_eq!(details.result.from_system, "Sol");
+        assert_eq!(details.result.to_system, "Colonia");
+        assert!((details.result.total_distance - 22000.47).abs() < 0.01);
+        assert!(details.result.jumps > 0);
+        let requests = server.requests("/api/system");
+        assert!(requests[0].contains("systemName=Sol"), "{requests:?}");
+        assert!(requests[1].contains("systemName=Colonia"), "{requests:?}");
+
+        // Both ends are cached now; an unknown target still reaches EDSM
+        let err = plan_route(&planner, "Nowhere", Some("Sol")).unwrap_err();
+        assert!(matches!(err, EdjcError::SystemNotFound { .. }), "{err}");
+        assert_eq!(server.hits("/api/system"), 3);
+    }
+
+    #[test]
+    fn test_plan_from_mock_commander_position() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "system": "Fuelum"}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Sol", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        let edsm_client = mock_edsm(&server);
+        let config = Config {
+            cmdr_name: "Whit3Arrow".to_string(),
+            ..Config::default()
+        };
+
+        let planned = RoutePlanner::new(&config, &edsm_client)
+            .plan("Sol", 30.0, None)
+            .unwrap();
+        assert_eq!(planned.origin, "Fuelum");
+        assert_eq!(planned.from.x, 52.0);
+        assert!(planned.warnings.is_empty(), "{:?}", planned.warnings);
+        let positions = server.requests("/logs/get-position");
+        assert_eq!(positions.len(), 1);
+        assert!(
+            positions[0].contains("commanderName=Whit3Arrow"),
+            "{positions:?}"
+        );
+    }
+
+    #[test]
+    fn test_plan_through_mock_neutron_corridor() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Start", "coords": {"x": 0.0, "y": 0.0, "z": 0.0}}"#,
+        );
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Finish", "coords": {"x": 0.0, "y": 0.0, "z": 200.0}}"#,
+        );
+        server.respond(
+            "/api/sphere-systems",
+            200,
+            r#"[
+                {"name": "First Neutron", "coords": {"x": 0.0, "y": 0.0, "z": 30.0},
+                 "primaryStar": {"type": "Neutron Star", "isScoopable": false}},
+                {"name": "Yellow Star", "coords": {"x": 0.0, "y": 10.0, "z": 100.0},
+                 "primaryStar": {"type": "G (White-Yellow) Star", "isScoopable": true}},
+                {"name": "Second Neutron", "coords": {"x": 0.0, "y": 0.0, "z": 150.0},
+                 "primaryStar": {"type": "Neutron Star", "isScoopable": false}}
+            ]"#,
+        );
+        let edsm_client = mock_edsm(&server);
+        let config = Config {
+            neutron_highway_threshold_ly: 0.0,
+            ..Config::default()
+        };
+
+        let planned = RoutePlanner::new(&config, &edsm_client)
+            .with_neutron_corridor(&edsm_client)
+            .plan("Finish", 30.0, Some("Start"))
+            .unwrap();
+        assert_eq!(planned.result.route_type, "neutron highway (2 boosts)");
+        let legs: Vec<_> = planned
+            .result
+            .legs
+            .iter()
+            .map(|leg| leg.system.as_str())
+            .collect();
+        assert_eq!(
+            legs,
+            ["First Neutron", "Second Neutron", "Finish"],
+            "{:?}",
+            planned.result
+        );
+        assert_eq!(planned.result.supercharges, 2);
+        assert_eq!(server.hits("/api/sphere-systems"), 1);
+    }
+
+    #[test]
+    fn test_plan_with_max_range() {
+        let config = Config {
+            ships: vec![ShipConfig {
+                laden_jump_range: 20.0,
+                max_jump_range: Some(35.0),
+                ..Default::default()
+            }],
+            // Direct routes only, so the jump count follows the range
+            neutron_multiplier: 1.0,
+            white_dwarf_multiplier: 1.0,
+            ..Config::default()
+        };
+        let systems = FakeSource {
+            cmdr_location: Some("Home"),
+        };
+
+        let laden = RoutePlanner::new(&config, &systems)
+            .plan("Target", 20.0, None)
+            .unwrap();
+        let optimized = RoutePlanner::new(&config, &systems)
+            .with_max_range(true)
+            .plan("Target", 20.0, None)
+            .unwrap();
+        assert_eq!(laden.jump_range, 20.0);
+        assert_eq!(optimized.jump_range, 35.0);
+        assert_eq!(laden.result.total_distance, optimized.result.total_distance);
+        assert!(
+            optimized.result.jumps < laden.result.jumps,
+            "{} vs {}",
+            optimized.result.jumps,
+            laden.result.jumps
+        );
+        assert!(optimized.warnings.is_empty(), "{:?}", optimized.warnings);
+
+        // Without a maximum range, the laden range is used with a note
+        let config = Config::default();
+        let fallback = RoutePlanner::new(&config, &systems)
+            .with_max_range(true)
+            .plan("Target", 30.0, None)
+            .unwrap();
+        assert_eq!(fallback.jump_range, 30.0);
+        assert_eq!(
+            fallback.warnings,
+            vec!["No max_jump_range configured, planned with the laden range".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_route_details_as_json() {
+        let config = Config::default();
+        let systems = FakeSource {
+            cmdr_location: None,
+        };
+
+        let planner = RoutePlanner::new(&config, &systems);
+        let details = plan_route(&planner, "Target", Some("Home")).unwrap();
+        let json = serde_json::to_value(&details).unwrap();
+        assert_eq!(json["result"]["jumps"], details.result.jumps);
+        assert_eq!(json["result"]["total_distance"], 100.0);
+        assert_eq!(
+            json["result"]["route_type"],
+            details.result.route_type.as_str()
+        );
+        assert!(json["estimated_fuel_usage"].is_number());
+    }
+}
@@ -0,0 +1,133 @@
+/*!
+Background refresh of frequently-requested systems.
+
+Staging systems and bubble anchors are looked up constantly. Instead of
+letting their cache entries expire mid-session, a [`PrefetchRefresher`]
+re-fetches them on a fixed interval that is a bit shorter than the cache TTL,
+so they stay warm for as long as the plugin is loaded.
+*/
+
+use anyhow::Result;
+use log::{debug, warn};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Refresh entries this fraction of the way into their TTL
+const REFRESH_FRACTION: f64 = 0.9;
+
+/// Interval at which to refresh entries cached for `ttl`
+pub fn refresh_interval(ttl: Duration) -> Duration {
+    ttl.mul_f64(REFRESH_FRACTION)
+}
+
+/// Background thread that keeps a set of systems warm in the cache
+#[derive(Debug)]
+pub struct PrefetchRefresher {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PrefetchRefresher {
+    /// Start refreshing `systems` every `interval`
+    ///
+    /// `refresh` is called for each system immediately and then once per
+    /// interval; it is expected to fetch the system and update the cache.
+    pub fn start<F>(systems: Vec<String>, interval: Duration, refresh: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Result<()> + Send + 'static,
+    {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let thread_stop = Arc::clone(&stop);
+
+        let handle = thread::Builder::new()
+            .name("edjc-prefetch".to_string())
+            .spawn(move || {
+                let (stopped, signal) = &*thread_stop;
+                loop {
+                    for system in &systems {
+                        match refresh(system) {
+                            Ok(()) => debug!("Refreshed prefetched system: {system}"),
+                            Err(e) => warn!("Could not refresh prefetched system {system}: {e}"),
+                        }
+                    }
+
+                    let guard = stopped.lock().unwrap_or_else(|e| e.into_inner());
+                    let (guard, _) = signal
+                        .wait_timeout_while(guard, interval, |stopped| !*stopped)
+                        .unwrap_or_else(|e| e.into_inner());
+                    if *guard {
+                        break;
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Signal the thread to stop and wait for it to finish
+    pub fn shutdown(&mut self) {
+        let (stopped, signal) = &*self.stop;
+        *stopped.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        signal.notify_all();
+
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!("Prefetch thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for PrefetchRefresher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use moka::sync::Cache;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Instant;
+
+    #[test]
+    fn test_entries_refreshed_before_expiry() {
+        let ttl = Duration::from_millis(300);
+        let cache: Cache<String, u32> = Cache::builder().time_to_live(ttl).build();
+        let fetches = Arc::new(AtomicU32::new(0));
+
+        let source_cache = cache.clone();
+        let source_fetches = Arc::clone(&fetches);
+        let mut refresher = PrefetchRefresher::start(
+            vec!["Fuelum".to_string()],
+            refresh_interval(ttl),
+            move |system| {
+                let count = source_fetches.fetch_add(1, Ordering::SeqCst) + 1;
+                source_cache.insert(system.to_lowercase(), count);
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        // Well past the TTL, the entry must never have been missing
+        let deadline = Instant::now() + ttl * 4;
+        thread::sleep(Duration::from_millis(50));
+        while Instant::now() < deadline {
+            assert!(cache.get("fuelum").is_some(), "entry expired");
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        refresher.shutdown();
+        let refreshed = fetches.load(Ordering::SeqCst);
+        assert!(refreshed >= 4, "only {refreshed} fetches");
+
+        // No more refreshes after shutdown
+        thread::sleep(ttl);
+        assert_eq!(fetches.load(Ordering::SeqCst), refreshed);
+    }
+}
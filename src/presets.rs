@@ -0,0 +1,130 @@
+/*!
+Laden jump range presets for common builds.
+
+For commanders who don't know their ship's range yet: `ship_preset` in the
+config, or `/route --preset <name>` for a single route, plans with a typical
+range for the build. A range configured for the ship always wins over the
+preset. Add a build with one more line in [`SHIP_PRESETS`].
+*/
+
+use crate::config::ShipConfig;
+
+/// A build's typical laden jump range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShipPreset {
+    /// Name given to `ship_preset` and `--preset`, lowercase with dashes
+    pub name: &'static str,
+    /// Ship and build, shown as the ship's name
+    pub description: &'static str,
+    /// Laden jump range in light years
+    pub laden_jump_range: f64,
+}
+
+/// Built-in presets, roughly in order of range
+pub const SHIP_PRESETS: &[ShipPreset] = &[
+    ShipPreset {
+        name: "mandalay-explorer",
+        description: "Mandalay (explorer)",
+        laden_jump_range: 70.0,
+    },
+    ShipPreset {
+        name: "anaconda-explorer",
+        description: "Anaconda (explorer)",
+        laden_jump_range: 65.0,
+    },
+    ShipPreset {
+        name: "dbx-explorer",
+        description: "Diamondback Explorer (explorer)",
+        laden_jump_range: 60.0,
+    },
+    ShipPreset {
+        name: "krait-phantom-explorer",
+        description: "Krait Phantom (explorer)",
+        laden_jump_range: 55.0,
+    },
+    ShipPreset {
+        name: "asp-explorer",
+        description: "Asp Explorer (explorer)",
+        laden_jump_range: 50.0,
+    },
+    ShipPreset {
+        name: "krait-mk2-combat",
+        description: "Krait Mk II (combat)",
+        laden_jump_range: 30.0,
+    },
+    ShipPreset {
+        name: "anaconda-combat",
+        description: "Anaconda (combat)",
+        laden_jump_range: 30.0,
+    },
+    ShipPreset {
+        name: "python-combat",
+        description: "Python (combat)",
+        laden_jump_range: 25.0,
+    },
+    ShipPreset {
+        name: "fdl-combat",
+        description: "Fer-de-Lance (combat)",
+        laden_jump_range: 25.0,
+    },
+];
+
+/// Look up a preset by name (case-insensitive)
+pub fn find_preset(name: &str) -> Option<&'static ShipPreset> {
+    SHIP_PRESETS
+        .iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Names of all presets, comma separated, for error messages
+pub fn preset_names() -> String {
+    SHIP_PRESETS
+        .iter()
+        .map(|preset| preset.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl ShipPreset {
+    /// A ship profile with the preset's range and nothing else configured
+    pub fn ship(&self) -> ShipConfig {
+        ShipConfig {
+            name: self.description.to_string(),
+            laden_jump_range: self.laden_jump_range,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_preset() {
+        assert_eq!(find_preset("dbx-explorer").unwrap().laden_jump_range, 60.0);
+        assert_eq!(find_preset(" FDL-Combat ").unwrap().laden_jump_range, 25.0);
+        assert_eq!(
+            find_preset("anaconda-combat").unwrap().laden_jump_range,
+            30.0
+        );
+        assert!(find_preset("sidewinder").is_none());
+
+        let ship = find_preset("asp-explorer").unwrap().ship();
+        assert_eq!(ship.name, "Asp Explorer (explorer)");
+        assert_eq!(ship.laden_jump_range, 50.0);
+        assert_eq!(ship.max_jump_range, None);
+    }
+
+    #[test]
+    fn test_presets_are_unique_and_sane() {
+        for (index, preset) in SHIP_PRESETS.iter().enumerate() {
+            assert!(preset.laden_jump_range > 0.0, "{}", preset.name);
+            assert_eq!(preset.name, preset.name.to_lowercase());
+            assert!(!SHIP_PRESETS[..index]
+                .iter()
+                .any(|other| other.name == preset.name));
+        }
+        assert!(preset_names().starts_with("mandalay-explorer, anaconda-explorer"));
+    }
+}
@@ -0,0 +1,116 @@
+/*!
+Galactic region classification.
+
+Coordinators like to know at a glance whether a case is in the Bubble, out
+in Colonia, or somewhere in the black. [`galactic_region`] places a system by
+its distance from a few well-known anchors; the radii are rough and kept as
+constants so they are easy to tune.
+*/
+
+use std::fmt;
+
+use crate::types::SystemCoordinates;
+
+/// Sol's distance within which a system counts as the Bubble
+pub const BUBBLE_RADIUS_LY: f64 = 500.0;
+/// Distance from Colonia within which a system counts as the Colonia region
+pub const COLONIA_RADIUS_LY: f64 = 1000.0;
+/// Distance from Sagittarius A* within which the dense neutron fields of the
+/// galactic core begin
+pub const NEUTRON_FIELDS_RADIUS_LY: f64 = 5000.0;
+
+/// Colonia's coordinates
+const COLONIA: (f64, f64, f64) = (-9530.5, -910.28125, 19808.125);
+/// Sagittarius A*'s coordinates, the galactic core
+const GALACTIC_CORE: (f64, f64, f64) = (25.21875, -20.90625, 25899.96875);
+
+/// Broad region of the galaxy a system is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// Human space around Sol
+    Bubble,
+    /// The Colonia bubble and its surroundings
+    ColoniaRegion,
+    /// The neutron-rich galactic core
+    NeutronFields,
+    /// Anywhere else
+    DeepSpace,
+}
+
+impl Region {
+    /// Human-readable name of the region
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Region::Bubble => "Bubble",
+            Region::ColoniaRegion => "Colonia region",
+            Region::NeutronFields => "neutron fields",
+            Region::DeepSpace => "deep space",
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Classify a system into a galactic region
+pub fn galactic_region(system: &SystemCoordinates) -> Region {
+    let distance_to = |(x, y, z): (f64, f64, f64)| {
+        ((system.x - x).powi(2) + (system.y - y).powi(2) + (system.z - z).powi(2)).sqrt()
+    };
+
+    if distance_to((0.0, 0.0, 0.0)) <= BUBBLE_RADIUS_LY {
+        Region::Bubble
+    } else if distance_to(COLONIA) <= COLONIA_RADIUS_LY {
+        Region::ColoniaRegion
+    } else if distance_to(GALACTIC_CORE) <= NEUTRON_FIELDS_RADIUS_LY {
+        Region::NeutronFields
+    } else {
+        Region::DeepSpace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system(name: &str, x: f64, y: f64, z: f64) -> SystemCoordinates {
+        SystemCoordinates {
+            name: name.to_string(),
+            x,
+            y,
+            z,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        }
+    }
+
+    #[test]
+    fn test_well_known_systems() {
+        let sol = system("Sol", 0.0, 0.0, 0.0);
+        assert_eq!(galactic_region(&sol), Region::Bubble);
+
+        let fuelum = system("Fuelum", 52.0, -52.65625, 49.8125);
+        assert_eq!(galactic_region(&fuelum), Region::Bubble);
+
+        let colonia = system("Colonia", -9530.5, -910.28125, 19808.125);
+        assert_eq!(galactic_region(&colonia), Region::ColoniaRegion);
+
+        let sagittarius = system("Sagittarius A*", 25.21875, -20.90625, 25899.96875);
+        assert_eq!(galactic_region(&sagittarius), Region::NeutronFields);
+
+        let beagle_point = system("Beagle Point", -1111.5625, -134.21875, 65269.75);
+        assert_eq!(galactic_region(&beagle_point), Region::DeepSpace);
+    }
+
+    #[test]
+    fn test_region_names() {
+        assert_eq!(Region::Bubble.to_string(), "Bubble");
+        assert_eq!(Region::DeepSpace.to_string(), "deep space");
+    }
+}
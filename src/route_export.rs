@@ -0,0 +1,335 @@
+/*!
+Route export for other tools.
+
+A planned route's waypoints can be written as Spansh's JSON route result
+(`system_jumps`, as the neutron plotter returns it) or as CSV with the column
+names of Spansh's CSV download. Both start with the origin, which has no
+jumps, and end at the destination.
+*/
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::jump_calculator::RouteDetails;
+use crate::route_log::csv_field;
+use crate::types::RouteLeg;
+
+/// CSV header, matching Spansh's neutron plotter download
+const CSV_HEADER: &str = "System Name,Distance Jumped,Distance Remaining,Neutron Star,Jumps";
+
+/// File format for [`export_route`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteExportFormat {
+    /// Spansh's route result JSON
+    SpanshJson,
+    /// One waypoint per line with a header
+    Csv,
+}
+
+impl RouteExportFormat {
+    /// CSV for `.csv` files, Spansh JSON for anything else
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::SpanshJson,
+        }
+    }
+}
+
+/// A waypoint as Spansh writes it in `system_jumps`
+#[derive(Debug, Serialize, Deserialize)]
+struct SpanshWaypoint {
+    system: String,
+    /// Straight-line distance from the previous waypoint
+    distance_jumped: f64,
+    /// Straight-line distance still to go to the destination
+    distance_left: f64,
+    /// Jumps from the previous waypoint
+    jumps: u32,
+    neutron_star: bool,
+}
+
+/// Spansh's route result
+#[derive(Debug, Serialize, Deserialize)]
+struct SpanshRoute {
+    source_system: String,
+    destination_system: String,
+    distance: f64,
+    total_jumps: u32,
+    system_jumps: Vec<SpanshWaypoint>,
+}
+
+/// The route's waypoints from the origin on, each with the leg ending there
+///
+/// Estimated routes have no waypoints of their own and export as a single
+/// leg to the destination.
+fn waypoints(details: &RouteDetails) -> Vec<RouteLeg> {
+    let result = &details.result;
+    let origin = RouteLeg {
+        system: result.from_system.clone(),
+        boost_star: false,
+        jumps: 0,
+        distance: 0.0,
+    };
+    let legs = if details.legs.is_empty() {
+        vec![RouteLeg {
+            system: result.to_system.clone(),
+            boost_star: false,
+            jumps: result.jumps,
+            distance: result.total_distance,
+        }]
+    } else {
+        details.legs.clone()
+    };
+    std::iter::once(origin).chain(legs).collect()
+}
+
+/// Write a route's waypoints in `format`
+pub fn export_route(details: &RouteDetails, format: RouteExportFormat) -> String {
+    let waypoints = waypoints(details);
+    let total: f64 = waypoints.iter().map(|waypoint| waypoint.distance).sum();
+    let mut travelled = 0.0;
+    let waypoints = waypoints.into_iter().map(|waypoint| {
+        travelled += waypoint.distance;
+        SpanshWaypoint {
+            system: waypoint.system,
+            distance_jumped: waypoint.distance,
+            distance_left: (total - travelled).max(0.0),
+            jumps: waypoint.jumps,
+            neutron_star: waypoint.boost_star,
+        }
+    });
+
+    match format {
+        RouteExportFormat::SpanshJson => {
+            let route = SpanshRoute {
+                source_system: details.result.from_system.clone(),
+                destination_system: details.result.to_system.clone(),
+                distance: details.result.total_distance,
+                total_jumps: details.result.jumps,
+                system_jumps: waypoints.collect(),
+            };
+            serde_json::to_string_pretty(&route).expect("route serializes to JSON")
+        }
+        RouteExportFormat::Csv => {
+            let mut csv = format!("{CSV_HEADER}\n");
+            for waypoint in waypoints {
+                csv.push_str(&format!(
+                    "{},{:.2},{:.2},{},{}\n",
+                    csv_field(&waypoint.system),
+                    waypoint.distance_jumped,
+                    waypoint.distance_left,
+                    if waypoint.neutron_star { "Yes" } else { "No" },
+                    waypoint.jumps
+                ));
+            }
+            csv
+        }
+    }
+}
+
+/// Read back the waypoints of an exported route, the origin first
+pub fn parse_route_export(text: &str, format: RouteExportFormat) -> Result<Vec<RouteLeg>> {
+    match format {
+        RouteExportFormat::SpanshJson => {
+            let route: SpanshRoute = serde_json::from_str(text).context("Invalid route JSON")?;
+            Ok(route
+                .system_jumps
+                .into_iter()
+                .map(|waypoint| RouteLeg {
+                    system: waypoint.system,
+                    boost_star: waypoint.neutron_star,
+                    jumps: waypoint.jumps,
+                    distance: waypoint.distance_jumped,
+                })
+                .collect())
+        }
+        RouteExportFormat::Csv => {
+            let mut lines = text.lines();
+            if lines.next() != Some(CSV_HEADER) {
+                return Err(anyhow!("Route CSV has no header"));
+            }
+            lines
+                .enumerate()
+                .map(|(index, line)| {
+                    parse_csv_waypoint(line)
+                        .with_context(|| format!("Invalid waypoint on line {}", index + 2))
+                })
+                .collect()
+        }
+    }
+}
+
+/// Export a route to `path`, in the format its extension asks for
+///
+/// The written file is read back and must hold every waypoint.
+pub fn write_route_export(details: &RouteDetails, path: &Path) -> Result<usize> {
+    let format = RouteExportFormat::for_path(path);
+    let exported = export_route(details, format);
+    let expected = waypoints(details).len();
+    let parsed = parse_route_export(&exported, format)?.len();
+    if parsed != expected {
+        return Err(anyhow!(
+            "Exported route has {parsed} waypoints instead of {expected}"
+        ));
+    }
+
+    std::fs::write(path, exported)
+        .with_context(|| format!("Could not write {}", path.display()))?;
+    Ok(expected)
+}
+
+/// One waypoint from a CSV line
+fn parse_csv_waypoint(line: &str) -> Result<RouteLeg> {
+    let fields = split_csv_line(line);
+    let [system, distance, _remaining, neutron_star, jumps] = fields.as_slice() else {
+        return Err(anyhow!("expected 5 fields, found {}", fields.len()));
+    };
+    Ok(RouteLeg {
+        system: system.clone(),
+        boost_star: neutron_star == "Yes",
+        jumps: jumps.parse().context("jumps")?,
+        distance: distance.parse().context("distance")?,
+    })
+}
+
+/// Split a CSV line written by [`csv_field`] into its fields
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("at least one field");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::JumpResult;
+
+    fn leg(system: &str, boost_star: bool, jumps: u32, distance: f64) -> RouteLeg {
+        RouteLeg {
+            system: system.to_string(),
+            boost_star,
+            jumps,
+            distance,
+        }
+    }
+
+    fn route(legs: Vec<RouteLeg>) -> RouteDetails {
+        RouteDetails {
+            result: JumpResult {
+                jumps: legs.iter().map(|leg| leg.jumps).sum(),
+                total_distance: 500.0,
+                route_type: "neutron highway".to_string(),
+                from_system: "Fuelum".to_string(),
+                to_system: "Case, \"The\" System".to_string(),
+                supercharges: legs.iter().filter(|leg| leg.boost_star).count() as u32,
+                exact_jumps: None,
+                legs: legs.clone(),
+            },
+            estimated_fuel_usage: 10.0,
+            estimated_time_minutes: 12.0,
+            can_use_neutron: true,
+            can_use_white_dwarf: false,
+            scoop_stops: None,
+            warnings: Vec::new(),
+            legs,
+        }
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let legs = vec![
+            leg("Neutron A", true, 2, 140.0),
+            leg("Neutron B", true, 1, 180.5),
+            leg("Case, \"The\" System", false, 3, 179.5),
+        ];
+        let details = route(legs.clone());
+
+        let csv = export_route(&details, RouteExportFormat::Csv);
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            [
+                CSV_HEADER,
+                "Fuelum,0.00,500.00,No,0",
+                "Neutron A,140.00,360.00,Yes,2",
+                "Neutron B,180.50,179.50,Yes,1",
+                "\"Case, \"\"The\"\" System\",179.50,0.00,No,3",
+            ]
+        );
+
+        let parsed = parse_route_export(&csv, RouteExportFormat::Csv).unwrap();
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(parsed[0], leg("Fuelum", false, 0, 0.0));
+        assert_eq!(parsed[1..], legs);
+    }
+
+    #[test]
+    fn test_spansh_json_round_trip() {
+        let legs = vec![
+            leg("Neutron A", true, 2, 140.0),
+            leg("Colonia", false, 3, 360.0),
+        ];
+        let json = export_route(&route(legs.clone()), RouteExportFormat::SpanshJson);
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["source_system"], "Fuelum");
+        assert_eq!(value["total_jumps"], 5);
+        assert_eq!(value["system_jumps"][1]["neutron_star"], true);
+        assert_eq!(value["system_jumps"][1]["distance_left"], 360.0);
+
+        let parsed = parse_route_export(&json, RouteExportFormat::SpanshJson).unwrap();
+        assert_eq!(parsed[1..], legs);
+    }
+
+    #[test]
+    fn test_estimated_route_exports_one_leg() {
+        let csv = export_route(&route(Vec::new()), RouteExportFormat::Csv);
+        let parsed = parse_route_export(&csv, RouteExportFormat::Csv).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].system, "Case, \"The\" System");
+        assert_eq!(parsed[1].distance, 500.0);
+    }
+
+    #[test]
+    fn test_write_route_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let details = route(vec![leg("Neutron A", true, 2, 140.0)]);
+
+        let path = dir.path().join("route.CSV");
+        assert_eq!(write_route_export(&details, &path).unwrap(), 2);
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with(CSV_HEADER));
+
+        let path = dir.path().join("route.json");
+        write_route_export(&details, &path).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            parse_route_export(&written, RouteExportFormat::SpanshJson)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        assert!(parse_route_export("System,Jumps\n", RouteExportFormat::Csv).is_err());
+        assert!(
+            parse_route_export(&format!("{CSV_HEADER}\nA,1,2\n"), RouteExportFormat::Csv)
+                .unwrap_err()
+                .to_string()
+                .contains("line 2")
+        );
+    }
+}
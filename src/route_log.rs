@@ -0,0 +1,186 @@
+/*!
+Append-only record of calculated routes.
+
+Coordinators keep track of the cases they assisted. With `route_log_path` set,
+[`RouteLogger`] appends one line per calculated route: JSON Lines by default,
+or CSV (with a header) when the file name ends in `.csv`.
+*/
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::planner::PlannedRoute;
+
+/// Column order of the CSV format
+const CSV_HEADER: &str = "case,timestamp,from,to,jumps,distance_ly,route_type";
+
+/// One logged route
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteLogEntry {
+    /// Case number, for routes calculated for a RATSIGNAL
+    pub case: Option<String>,
+    /// When the route was calculated (RFC 3339, UTC)
+    pub timestamp: String,
+    pub from: String,
+    pub to: String,
+    pub jumps: u32,
+    pub distance_ly: f64,
+    pub route_type: String,
+}
+
+impl RouteLogEntry {
+    /// Entry for a route calculated now
+    pub fn new(case: Option<&str>, planned: &PlannedRoute) -> Self {
+        Self {
+            case: case.map(str::to_string),
+            timestamp: Utc::now().to_rfc3339(),
+            from: planned.result.from_system.clone(),
+            to: planned.result.to_system.clone(),
+            jumps: planned.result.jumps,
+            distance_ly: planned.result.total_distance,
+            route_type: planned.result.route_type.clone(),
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        [
+            csv_field(self.case.as_deref().unwrap_or_default()),
+            csv_field(&self.timestamp),
+            csv_field(&self.from),
+            csv_field(&self.to),
+            self.jumps.to_string(),
+            format!("{:.2}", self.distance_ly),
+            csv_field(&self.route_type),
+        ]
+        .join(",")
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Appends routes to the file at `route_log_path`
+#[derive(Debug)]
+pub struct RouteLogger {
+    file: Mutex<File>,
+    csv: bool,
+}
+
+impl RouteLogger {
+    /// Open (or create) a route log for appending
+    ///
+    /// A new or empty CSV log starts with a header line.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let csv = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+        if csv && file.metadata()?.len() == 0 {
+            writeln!(file, "{CSV_HEADER}")?;
+        }
+
+        Ok(Self {
+            file: Mutex::new(file),
+            csv,
+        })
+    }
+
+    /// Append one entry
+    pub fn log(&self, entry: &RouteLogEntry) -> Result<()> {
+        let line = if self.csv {
+            entry.to_csv()
+        } else {
+            serde_json::to_string(entry)?
+        };
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{line}")?;
+        Ok(())
+    }
+
+    /// Make sure every entry logged so far has reached the disk
+    pub fn flush(&self) -> Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.flush()?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(case: Option<&str>, to: &str) -> RouteLogEntry {
+        RouteLogEntry {
+            case: case.map(str::to_string),
+            timestamp: "2026-10-17T12:00:00+00:00".to_string(),
+            from: "Sol".to_string(),
+            to: to.to_string(),
+            jumps: 4,
+            distance_ly: 100.0,
+            route_type: "direct".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_jsonl_log_appends_entries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("routes.jsonl");
+
+        let logger = RouteLogger::open(&path).unwrap();
+        logger.log(&entry(Some("3"), "Fuelum")).unwrap();
+        logger.log(&entry(None, "Colonia")).unwrap();
+        // Reopening appends to the existing log
+        let logger = RouteLogger::open(&path).unwrap();
+        logger.log(&entry(None, "Maia")).unwrap();
+        logger.flush().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["case"], "3");
+        assert_eq!(lines[0]["to"], "Fuelum");
+        assert_eq!(lines[1]["case"], serde_json::Value::Null);
+        assert_eq!(lines[2]["jumps"], 4);
+    }
+
+    #[test]
+    fn test_csv_log_has_one_header() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("routes.csv");
+
+        RouteLogger::open(&path)
+            .unwrap()
+            .log(&entry(Some("3"), "Fuelum"))
+            .unwrap();
+        RouteLogger::open(&path)
+            .unwrap()
+            .log(&entry(None, "Col 285 Sector, \"A\""))
+            .unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            contents.lines().collect::<Vec<_>>(),
+            [
+                CSV_HEADER,
+                "3,2026-10-17T12:00:00+00:00,Sol,Fuelum,4,100.00,direct",
+                ",2026-10-17T12:00:00+00:00,Sol,\"Col 285 Sector, \"\"A\"\"\",4,100.00,direct",
+            ]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+/*!
+Ship profiles from Coriolis and EDSY builds.
+
+Both outfitting tools export a build as JSON: Coriolis in its own
+`ship-loadout` format, with the ship's ranges and masses worked out under
+`stats`, and EDSY (like Coriolis' "Export" to the game's format) as a journal
+`Loadout` event. [`import_ship_build`] reads either into a [`ShipConfig`]
+with the jump ranges, drive, mass and fuel tank filled in, so nobody has to
+guess their range.
+
+The drive's class, rating and, for an engineered drive, optimal mass are
+kept for fuel estimates; the ranges already include any engineering.
+*/
+
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config::ShipConfig;
+use crate::types::FsdProfile;
+
+/// Drive ratings by the number in a journal module name ("class5" is A)
+const RATINGS: [char; 5] = ['E', 'D', 'C', 'B', 'A'];
+
+/// Journal engineering modifier holding a drive's optimal mass
+const OPTIMAL_MASS_MODIFIER: &str = "FSDOptimalMass";
+
+/// A Coriolis `ship-loadout` export, as far as routing cares
+#[derive(Debug, Deserialize)]
+struct CoriolisBuild {
+    /// Build name given in Coriolis, if any
+    name: Option<String>,
+    /// Ship type, e.g. "Diamondback Explorer"
+    ship: String,
+    #[serde(default)]
+    components: CoriolisComponents,
+    stats: CoriolisStats,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CoriolisComponents {
+    #[serde(default)]
+    standard: CoriolisStandard,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CoriolisStandard {
+    #[serde(rename = "frameShiftDrive")]
+    frame_shift_drive: Option<CoriolisModule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CoriolisModule {
+    class: u8,
+    rating: char,
+    /// Engineering modifiers in hundredths of a percent ("optmass": 5500 is
+    /// +55%)
+    #[serde(default)]
+    modifications: HashMap<String, f64>,
+}
+
+/// Figures Coriolis works out for a build
+#[derive(Debug, Deserialize)]
+struct CoriolisStats {
+    /// Range with full tank and hold
+    #[serde(rename = "ladenRange")]
+    laden_range: Option<f64>,
+    /// Range with a full tank and an empty hold
+    #[serde(rename = "fullTankRange")]
+    full_tank_range: Option<f64>,
+    /// Range with an empty hold and just enough fuel for one jump
+    #[serde(rename = "unladenRange")]
+    unladen_range: Option<f64>,
+    /// Hull and modules, without fuel or cargo
+    #[serde(rename = "unladenMass")]
+    unladen_mass: Option<f64>,
+    #[serde(rename = "fuelCapacity")]
+    fuel_capacity: Option<f64>,
+    #[serde(rename = "cargoCapacity", default)]
+    cargo_capacity: f64,
+}
+
+/// A journal `Loadout` event, as EDSY exports builds
+#[derive(Debug, Deserialize)]
+struct LoadoutEvent {
+    #[serde(rename = "Ship")]
+    ship: String,
+    #[serde(rename = "ShipName")]
+    ship_name: Option<String>,
+    #[serde(rename = "MaxJumpRange")]
+    max_jump_range: f64,
+    #[serde(rename = "UnladenMass")]
+    unladen_mass: Option<f64>,
+    #[serde(rename = "CargoCapacity", default)]
+    cargo_capacity: f64,
+    #[serde(rename = "FuelCapacity")]
+    fuel_capacity: Option<LoadoutFuel>,
+    #[serde(rename = "Modules", default)]
+    modules: Vec<LoadoutModule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoadoutFuel {
+    #[serde(rename = "Main")]
+    main: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoadoutModule {
+    #[serde(rename = "Slot")]
+    slot: String,
+    #[serde(rename = "Item")]
+    item: String,
+    #[serde(rename = "Engineering")]
+    engineering: Option<LoadoutEngineering>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoadoutEngineering {
+    #[serde(rename = "Modifiers", default)]
+    modifiers: Vec<LoadoutModifier>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoadoutModifier {
+    #[serde(rename = "Label")]
+    label: String,
+    #[serde(rename = "Value")]
+    value: f64,
+}
+
+/// Read a Coriolis or EDSY build export into a ship profile
+pub fn import_ship_build(path: &Path) -> Result<ShipConfig> {
+    let contents = fs::read_to_string(path).with_context(|| format!("Could not read {path:?}"))?;
+    parse_ship_build(&contents).with_context(|| format!("Could not import {path:?}"))
+}
+
+/// Parse a Coriolis or EDSY build export into a ship profile
+///
+/// The profile is named after the build (or the ship, for unnamed builds).
+/// Builds without a frame shift drive or without masses still import, just
+/// without fuel estimates; a build without a jump range doesn't.
+pub fn parse_ship_build(json: &str) -> Result<ShipConfig> {
+    let build: serde_json::Value = serde_json::from_str(json).context("Not a JSON build")?;
+    let ship = if build.get("event").and_then(|event| event.as_str()) == Some("Loadout") {
+        debug!("Importing a journal Loadout build");
+        from_loadout(serde_json::from_value(build)?)
+    } else if build.get("stats").is_some() {
+        debug!("Importing a Coriolis build");
+        from_coriolis(serde_json::from_value(build)?)?
+    } else {
+        return Err(anyhow!(
+            "Neither a Coriolis export nor a Loadout event (export the build as JSON)"
+        ));
+    };
+
+    if !(ship.laden_jump_range > 0.0 && ship.laden_jump_range.is_finite()) {
+        return Err(anyhow!(
+            "The build's jump range must be greater than 0 LY, got {}",
+            ship.laden_jump_range
+        ));
+    }
+    ship.fsd_profile()?;
+    Ok(ship)
+}
+
+/// Ship profile from a Coriolis export
+fn from_coriolis(build: CoriolisBuild) -> Result<ShipConfig> {
+    let stats = build.stats;
+    let laden_jump_range = stats
+        .laden_range
+        .or(stats.full_tank_range)
+        .or(stats.unladen_range)
+        .ok_or_else(|| anyhow!("The build has no jump range (ladenRange) in its stats"))?;
+    // The fuel formula wants the mass at the jump: hull and modules plus a
+    // full tank and hold
+    let laden_mass = stats
+        .unladen_mass
+        .map(|mass| mass + stats.fuel_capacity.unwrap_or(0.0) + stats.cargo_capacity);
+    let (fsd_class, fsd_rating, ship_mass, fsd_optimal_mass) =
+        match (build.components.standard.frame_shift_drive, laden_mass) {
+            (Some(fsd), Some(mass)) => {
+                let optimal_mass = fsd.modifications.get("optmass").and_then(|&modifier| {
+                    let stock = FsdProfile::new(fsd.class, fsd.rating, mass).ok()?;
+                    Some(stock.optimal_mass() * (1.0 + modifier / 10_000.0))
+                });
+                (Some(fsd.class), Some(fsd.rating), Some(mass), optimal_mass)
+            }
+            _ => (None, None, None, None),
+        };
+
+    Ok(ShipConfig {
+        name: build
+            .name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or(build.ship),
+        laden_jump_range,
+        max_jump_range: stats.unladen_range.filter(|&max| max > laden_jump_range),
+        fsd_class,
+        fsd_rating,
+        ship_mass,
+        fsd_optimal_mass,
+        fuel_capacity: stats.fuel_capacity,
+    })
+}
+
+/// Ship profile from a journal `Loadout` event
+///
+/// The event only has the maximum range; range goes down with the mass
+/// carried, so the laden range scales it by the mass with a full tank and
+/// hold.
+fn from_loadout(loadout: LoadoutEvent) -> ShipConfig {
+    let fuel_capacity = loadout.fuel_capacity.map(|fuel| fuel.main);
+    let laden_mass = loadout
+        .unladen_mass
+        .map(|mass| mass + fuel_capacity.unwrap_or(0.0) + loadout.cargo_capacity);
+    let laden_jump_range = match (loadout.unladen_mass, laden_mass) {
+        (Some(unladen), Some(laden)) if laden > 0.0 => loadout.max_jump_range * unladen / laden,
+        _ => loadout.max_jump_range,
+    };
+    let drive = loadout
+        .modules
+        .iter()
+        .find(|module| module.slot.eq_ignore_ascii_case("FrameShiftDrive"));
+    let fsd = drive.and_then(|module| drive_class_and_rating(&module.item));
+    let (fsd_class, fsd_rating, ship_mass, fsd_optimal_mass) = match (fsd, laden_mass) {
+        (Some((class, rating)), Some(mass)) => {
+            let optimal_mass = drive
+                .and_then(|module| module.engineering.as_ref())
+                .and_then(|engineering| {
+                    engineering
+                        .modifiers
+                        .iter()
+                        .find(|modifier| modifier.label == OPTIMAL_MASS_MODIFIER)
+                })
+                .map(|modifier| modifier.value);
+            (Some(class), Some(rating), Some(mass), optimal_mass)
+        }
+        _ => (None, None, None, None),
+    };
+
+    ShipConfig {
+        name: loadout
+            .ship_name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or(loadout.ship),
+        laden_jump_range,
+        max_jump_range: Some(loadout.max_jump_range).filter(|&max| max > laden_jump_range),
+        fsd_class,
+        fsd_rating,
+        ship_mass,
+        fsd_optimal_mass,
+        fuel_capacity,
+    }
+}
+
+/// Class and rating of a journal drive module, e.g. (5, 'A') for
+/// "int_hyperdrive_size5_class5" (or its "overcharge" variant)
+fn drive_class_and_rating(item: &str) -> Option<(u8, char)> {
+    let item = item.to_ascii_lowercase();
+    let (_, size) = item.split_once("_size")?;
+    let (class, rating) = size.split_once("_class")?;
+    let rating = rating.parse::<usize>().ok()?.checked_sub(1)?;
+    Some((class.parse().ok()?, *RATINGS.get(rating)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORIOLIS_BUILD: &str = include_str!("../tests/fixtures/coriolis_dbx.json");
+
+    #[test]
+    fn test_coriolis_export() {
+        let ship = parse_ship_build(CORIOLIS_BUILD).unwrap();
+        assert_eq!(ship.name, "Rat Trap");
+        assert_eq!(ship.laden_jump_range, 60.16);
+        assert_eq!(ship.max_jump_range, Some(65.31));
+        assert_eq!(ship.fsd_class, Some(5));
+        assert_eq!(ship.fsd_rating, Some('A'));
+        assert_eq!(ship.ship_mass, Some(349.41));
+        assert_eq!(ship.fuel_capacity, Some(40.0));
+
+        // The long range drive's +55% optimal mass, not the stock 1050 t
+        let optimal_mass = ship.fsd_optimal_mass.unwrap();
+        assert!((optimal_mass - 1627.5).abs() < 1e-9, "{optimal_mass}");
+        let fsd = ship.fsd_profile().unwrap().unwrap();
+        assert_eq!(fsd.optimal_mass(), optimal_mass);
+        let stock = FsdProfile::new(5, 'A', 349.41).unwrap();
+        assert!(fsd.fuel_for_jump(60.0) < stock.fuel_for_jump(60.0));
+    }
+
+    #[test]
+    fn test_coriolis_export_without_drive_or_name() {
+        let mut build: serde_json::Value = serde_json::from_str(CORIOLIS_BUILD).unwrap();
+        build["name"] = serde_json::Value::Null;
+        build["components"]["standard"]["frameShiftDrive"] = serde_json::Value::Null;
+        build["stats"]
+            .as_object_mut()
+            .unwrap()
+            .retain(|key, _| key == "ladenRange");
+
+        let ship = parse_ship_build(&build.to_string()).unwrap();
+        assert_eq!(ship.name, "Diamondback Explorer");
+        assert_eq!(ship.laden_jump_range, 60.16);
+        assert_eq!(ship.max_jump_range, None);
+        assert_eq!((ship.fsd_class, ship.ship_mass), (None, None));
+        assert_eq!(ship.fsd_optimal_mass, None);
+        assert_eq!(ship.fuel_capacity, None);
+
+        build["stats"] = serde_json::json!({});
+        let err = parse_ship_build(&build.to_string()).unwrap_err();
+        assert!(err.to_string().contains("no jump range"), "{err}");
+    }
+
+    #[test]
+    fn test_loadout_export() {
+        let loadout = r#"{"timestamp":"2026-10-01T18:00:00Z","event":"Loadout","Ship":"diamondbackxl",
+            "ShipName":"Rat Trap","UnladenMass":309.4,"CargoCapacity":0,"MaxJumpRange":65.3,
+            "FuelCapacity":{"Main":40.0,"Reserve":0.52},
+            "Modules":[{"Slot":"FrameShiftDrive","Item":"int_hyperdrive_overcharge_size5_class5",
+                        "Engineering":{"BlueprintName":"FSD_LongRange","Level":5,
+                          "Modifiers":[{"Label":"FSDOptimalMass","Value":1627.5,"OriginalValue":1050.0}]}},
+                       {"Slot":"FuelTank","Item":"int_fueltank_size5_class3"}]}"#;
+
+        let ship = parse_ship_build(loadout).unwrap();
+        assert_eq!(ship.name, "Rat Trap");
+        assert_eq!(ship.max_jump_range, Some(65.3));
+        assert!((ship.laden_jump_range - 65.3 * 309.4 / 349.4).abs() < 1e-9);
+        assert_eq!((ship.fsd_class, ship.fsd_rating), (Some(5), Some('A')));
+        assert_eq!(ship.ship_mass, Some(349.4));
+        assert_eq!(ship.fsd_optimal_mass, Some(1627.5));
+        assert_eq!(ship.fuel_capacity, Some(40.0));
+    }
+
+    #[test]
+    fn test_not_a_build() {
+        assert!(parse_ship_build("not json").is_err());
+        let err = parse_ship_build(r#"{"event": "FSDJump"}"#).unwrap_err();
+        assert!(err.to_string().contains("Neither"), "{err}");
+        assert_eq!(
+            drive_class_and_rating("int_hyperdrive_size2_class1"),
+            Some((2, 'E'))
+        );
+        assert_eq!(drive_class_and_rating("int_fuelscoop_size4"), None);
+    }
+}
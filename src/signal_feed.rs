@@ -0,0 +1,531 @@
+/*!
+Signals relayed from an external rat-bot (`signal_feed_url`).
+
+Some setups run a separate IRC bot that forwards MechaSqueak's lines over a
+local socket. [`SignalFeed`] connects to it on a background thread, collects
+every line it receives and reconnects when the bot goes away. Two kinds of
+feed are understood:
+
+- `tcp://host:port`: one line per signal, `\n` or `\r\n` terminated
+- `ws://host:port/path`: WebSocket text messages of one or more lines
+
+HexChat must only be called from its own thread, so lines are not handled
+here: the plugin picks them up from a timer with [`SignalFeed::received`].
+*/
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How often a blocked read checks whether the feed was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Give up on a connection attempt after this long
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// First wait before reconnecting; doubles up to [`MAX_RECONNECT_DELAY`]
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Longest line or WebSocket message accepted
+const MAX_MESSAGE_BYTES: u64 = 64 * 1024;
+
+/// Where a signal feed is, from `signal_feed_url`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedUrl {
+    /// Line-delimited TCP
+    Tcp { addr: String },
+    /// WebSocket, unencrypted
+    WebSocket {
+        addr: String,
+        host: String,
+        path: String,
+    },
+}
+
+impl FeedUrl {
+    /// Parse a `tcp://host:port` or `ws://host[:port][/path]` URL
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = url.trim();
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| anyhow!("Signal feed URL needs tcp:// or ws://, got {url:?}"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        if authority.is_empty() {
+            return Err(anyhow!("Signal feed URL has no host: {url:?}"));
+        }
+        let has_port = authority
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "tcp" if has_port => Ok(Self::Tcp {
+                addr: authority.to_string(),
+            }),
+            "tcp" => Err(anyhow!("Signal feed URL needs a port: {url:?}")),
+            "ws" => Ok(Self::WebSocket {
+                addr: if has_port {
+                    authority.to_string()
+                } else {
+                    format!("{authority}:80")
+                },
+                host: authority.to_string(),
+                path: path.to_string(),
+            }),
+            "wss" => Err(anyhow!(
+                "wss:// signal feeds aren't supported, use ws:// or tcp:// on a local address"
+            )),
+            _ => Err(anyhow!(
+                "Unsupported signal feed scheme {scheme:?}, use tcp:// or ws://"
+            )),
+        }
+    }
+
+    fn addr(&self) -> &str {
+        match self {
+            Self::Tcp { addr } | Self::WebSocket { addr, .. } => addr,
+        }
+    }
+}
+
+/// Set to stop the feed thread; the condvar cuts a reconnect wait short
+type Stop = Arc<(Mutex<bool>, Condvar)>;
+
+fn is_stopped(stop: &Stop) -> bool {
+    *stop.0.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Background thread reading lines from a signal feed
+#[derive(Debug)]
+pub struct SignalFeed {
+    stop: Stop,
+    handle: Option<JoinHandle<()>>,
+    lines: Receiver<String>,
+}
+
+impl SignalFeed {
+    /// Connect to the feed at `url` and keep collecting its lines until
+    /// [`Self::shutdown`]
+    ///
+    /// Only a malformed URL fails here; an unreachable feed is retried in
+    /// the background.
+    pub fn start(url: &str) -> Result<Self> {
+        let url = FeedUrl::parse(url)?;
+        let stop: Stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let (sender, lines) = mpsc::channel();
+
+        let thread_stop = Arc::clone(&stop);
+        let handle = thread::Builder::new()
+            .name("edjc-signal-feed".to_string())
+            .spawn(move || run(&url, &thread_stop, &sender))?;
+
+        Ok(Self {
+            stop,
+            handle: Some(handle),
+            lines,
+        })
+    }
+
+    /// Lines received since the last call, oldest first
+    pub fn received(&self) -> Vec<String> {
+        self.lines.try_iter().collect()
+    }
+
+    /// Disconnect and wait for the thread to finish
+    pub fn shutdown(&mut self) {
+        let (stopped, signal) = &*self.stop;
+        *stopped.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        signal.notify_all();
+
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!("Signal feed thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for SignalFeed {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Connect, read until the feed drops, wait and connect again
+fn run(url: &FeedUrl, stop: &Stop, lines: &Sender<String>) {
+    let mut delay = RECONNECT_DELAY;
+    while !is_stopped(stop) {
+        match connect(url.addr()) {
+            Ok(stream) => {
+                info!("Connected to signal feed {}", url.addr());
+                delay = RECONNECT_DELAY;
+                let result = match url {
+                    FeedUrl::Tcp { .. } => read_lines(stream, stop, lines),
+                    FeedUrl::WebSocket { host, path, .. } => {
+                        read_websocket(stream, host, path, stop, lines)
+                    }
+                };
+                match result {
+                    _ if is_stopped(stop) => break,
+                    Ok(()) => info!("Signal feed {} closed", url.addr()),
+                    Err(e) => warn!("Signal feed {} failed: {e}", url.addr()),
+                }
+            }
+            Err(e) => warn!("Could not connect to signal feed {}: {e}", url.addr()),
+        }
+
+        let (stopped, signal) = &**stop;
+        let guard = stopped.lock().unwrap_or_else(|e| e.into_inner());
+        let (guard, _) = signal
+            .wait_timeout_while(guard, delay, |stopped| !*stopped)
+            .unwrap_or_else(|e| e.into_inner());
+        if *guard {
+            break;
+        }
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+    debug!("Signal feed thread stopped");
+}
+
+fn connect(addr: &str) -> Result<TcpStream> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .with_context(|| format!("{addr} has no address"))?;
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    Ok(stream)
+}
+
+/// A stream whose reads wait out the poll timeout until data arrives or
+/// the feed is stopped
+struct StoppableStream<'a> {
+    stream: TcpStream,
+    stop: &'a Stop,
+}
+
+impl Read for StoppableStream<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.stream.read(buf) {
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    if is_stopped(self.stop) {
+                        return Err(io::Error::other("signal feed stopped"));
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Hand on every non-empty line of a line-delimited feed
+fn read_lines(stream: TcpStream, stop: &Stop, lines: &Sender<String>) -> Result<()> {
+    let mut reader = BufReader::new(StoppableStream { stream, stop });
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader
+            .by_ref()
+            .take(MAX_MESSAGE_BYTES)
+            .read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        send_lines(&line, lines);
+    }
+}
+
+/// Send the non-empty lines of `text` to the plugin
+fn send_lines(text: &[u8], lines: &Sender<String>) {
+    for line in String::from_utf8_lossy(text).lines() {
+        let line = line.trim();
+        if !line.is_empty() {
+            debug!("Signal feed: {line}");
+            // The receiving end only goes away when the plugin is dropped
+            let _ = lines.send(line.to_string());
+        }
+    }
+}
+
+// WebSocket opcodes (RFC 6455)
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Upgrade the connection and hand on the lines of every text message
+fn read_websocket(
+    stream: TcpStream,
+    host: &str,
+    path: &str,
+    stop: &Stop,
+    lines: &Sender<String>,
+) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    write!(
+        writer,
+        "GET {path} HTTP/1.1\r\nHost: {host}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        base64(&random_bytes::<16>())
+    )?;
+
+    let mut reader = BufReader::new(StoppableStream { stream, stop });
+    let mut status = String::new();
+    reader.read_line(&mut status)?;
+    if status.split_whitespace().nth(1) != Some("101") {
+        return Err(anyhow!("no WebSocket upgrade: {}", status.trim()));
+    }
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut message = Vec::new();
+    loop {
+        let (fin, opcode, payload) = read_frame(&mut reader)?;
+        match opcode {
+            OP_CONTINUATION | OP_TEXT | OP_BINARY => {
+                message.extend_from_slice(&payload);
+                if message.len() as u64 > MAX_MESSAGE_BYTES {
+                    return Err(anyhow!("WebSocket message over {MAX_MESSAGE_BYTES} bytes"));
+                }
+                if fin {
+                    send_lines(&message, lines);
+                    message.clear();
+                }
+            }
+            OP_PING => write_frame(&mut writer, OP_PONG, &payload)?,
+            OP_CLOSE => {
+                // Echo the status code, as the close handshake asks
+                let _ = write_frame(&mut writer, OP_CLOSE, &payload[..payload.len().min(2)]);
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Read one frame: whether it is the final fragment, its opcode and payload
+fn read_frame(reader: &mut impl Read) -> Result<(bool, u8, Vec<u8>)> {
+    let mut head = [0; 2];
+    reader.read_exact(&mut head)?;
+    let fin = head[0] & 0x80 != 0;
+    let opcode = head[0] & 0x0f;
+    let len = match head[1] & 0x7f {
+        126 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0; 8];
+            reader.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+    if len > MAX_MESSAGE_BYTES {
+        return Err(anyhow!("WebSocket frame over {MAX_MESSAGE_BYTES} bytes"));
+    }
+    // Servers shouldn't mask their frames, but unmask them if they do
+    let mask = if head[1] & 0x80 != 0 {
+        let mut mask = [0; 4];
+        reader.read_exact(&mut mask)?;
+        Some(mask)
+    } else {
+        None
+    };
+
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload)?;
+    if let Some(mask) = mask {
+        apply_mask(&mut payload, mask);
+    }
+    Ok((fin, opcode, payload))
+}
+
+/// Write a control frame; clients must mask everything they send
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    // Control frame payloads are at most 125 bytes
+    let payload = &payload[..payload.len().min(125)];
+    let mask = random_bytes::<4>();
+    let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8];
+    frame.extend_from_slice(&mask);
+    let start = frame.len();
+    frame.extend_from_slice(payload);
+    apply_mask(&mut frame[start..], mask);
+    writer.write_all(&frame)
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (index, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[index % 4];
+    }
+}
+
+/// Unpredictable bytes for the handshake key and frame masks
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    for chunk in bytes.chunks_mut(8) {
+        let random = RandomState::new().build_hasher().finish().to_le_bytes();
+        chunk.copy_from_slice(&random[..chunk.len()]);
+    }
+    bytes
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (index, &byte)| {
+                group | u32::from(byte) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    /// Wait up to two seconds for `count` lines from the feed
+    fn wait_for_lines(feed: &SignalFeed, count: usize) -> Vec<String> {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut lines = Vec::new();
+        while lines.len() < count && Instant::now() < deadline {
+            lines.extend(feed.received());
+            thread::sleep(Duration::from_millis(10));
+        }
+        lines
+    }
+
+    #[test]
+    fn test_parse_feed_url() {
+        assert_eq!(
+            FeedUrl::parse("tcp://127.0.0.1:9000").unwrap(),
+            FeedUrl::Tcp {
+                addr: "127.0.0.1:9000".to_string()
+            }
+        );
+        assert_eq!(
+            FeedUrl::parse(" ws://localhost/signals ").unwrap(),
+            FeedUrl::WebSocket {
+                addr: "localhost:80".to_string(),
+                host: "localhost".to_string(),
+                path: "/signals".to_string(),
+            }
+        );
+        assert!(FeedUrl::parse("tcp://localhost").is_err());
+        assert!(FeedUrl::parse("localhost:9000").is_err());
+        let err = FeedUrl::parse("wss://example.com").unwrap_err();
+        assert!(err.to_string().contains("wss://"), "{err}");
+        assert!(FeedUrl::parse("http://localhost:9000").is_err());
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(b"the sample nonce"), "dGhlIHNhbXBsZSBub25jZQ==");
+    }
+
+    #[test]
+    fn test_tcp_feed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut feed = SignalFeed::start(&format!("tcp://{addr}")).unwrap();
+
+        let (mut bot, _) = listener.accept().unwrap();
+        bot.write_all(b"RATSIGNAL one\r\n\r\nRATSIGNAL two\n")
+            .unwrap();
+        assert_eq!(
+            wait_for_lines(&feed, 2),
+            vec!["RATSIGNAL one", "RATSIGNAL two"]
+        );
+
+        // Stops while blocked reading from a live connection
+        let started = Instant::now();
+        feed.shutdown();
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_websocket_feed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut feed = SignalFeed::start(&format!("ws://{addr}/signals")).unwrap();
+
+        let (bot, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(bot.try_clone().unwrap());
+        let mut request = String::new();
+        reader.read_line(&mut request).unwrap();
+        assert_eq!(request, "GET /signals HTTP/1.1\r\n");
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header == "\r\n" {
+                break;
+            }
+        }
+
+        let mut bot = bot;
+        bot.write_all(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n")
+            .unwrap();
+        // A text message split over two fragments, one of two lines, a ping
+        let mut frames = vec![0x01, 10];
+        frames.extend_from_slice(b"RATSIGNAL ");
+        frames.extend_from_slice(&[0x80, 3]);
+        frames.extend_from_slice(b"one");
+        frames.extend_from_slice(&[0x81, 29]);
+        frames.extend_from_slice(b"RATSIGNAL two\nRATSIGNAL three");
+        frames.extend_from_slice(&[0x89, 2]);
+        frames.extend_from_slice(b"hi");
+        bot.write_all(&frames).unwrap();
+
+        assert_eq!(
+            wait_for_lines(&feed, 3),
+            vec!["RATSIGNAL one", "RATSIGNAL two", "RATSIGNAL three"]
+        );
+        let (fin, opcode, payload) = read_frame(&mut reader).unwrap();
+        assert_eq!(
+            (fin, opcode, payload.as_slice()),
+            (true, OP_PONG, &b"hi"[..])
+        );
+
+        feed.shutdown();
+    }
+}
@@ -0,0 +1,356 @@
+/*!
+Spansh neutron plotter client.
+
+This module submits routes to Spansh's neutron plotter, which plans long hauls
+(Sol → Colonia and beyond) through its full star database. Plotting is
+asynchronous: the route request returns a job id that is polled until the
+route is ready or the job fails.
+*/
+
+use log::debug;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::thread;
+use std::time::Duration;
+
+use crate::jump_calculator::neutron_route_type;
+use crate::types::{EdjcError, EdjcResult, JumpResult, RouteLeg};
+
+const SPANSH_API_URL: &str = "https://spansh.co.uk/api";
+/// Time between two polls of a queued job
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Polls made before giving up on a job
+const MAX_POLLS: u32 = 30;
+
+/// Spansh neutron plotter client
+#[derive(Debug)]
+pub struct SpanshClient {
+    client: Client,
+    api_url: String,
+    poll_interval: Duration,
+    max_polls: u32,
+}
+
+/// Answer to a route submission or a job poll
+#[derive(Debug, Deserialize)]
+struct SpanshJobResponse {
+    job: Option<String>,
+    status: Option<String>,
+    error: Option<String>,
+    result: Option<SpanshRoute>,
+}
+
+/// A plotted neutron route
+#[derive(Debug, Deserialize)]
+struct SpanshRoute {
+    /// Straight-line distance between origin and destination
+    distance: Option<f64>,
+    total_jumps: Option<u32>,
+    system_jumps: Vec<SpanshWaypoint>,
+}
+
+/// A waypoint of a plotted route
+#[derive(Debug, Deserialize)]
+struct SpanshWaypoint {
+    system: String,
+    /// Jumps from the previous waypoint
+    #[serde(default)]
+    jumps: u32,
+    #[serde(default)]
+    distance_jumped: f64,
+    /// Whether the waypoint is a neutron star to supercharge at
+    #[serde(default)]
+    neutron_star: bool,
+}
+
+impl SpanshClient {
+    /// Create a new Spansh client
+    pub fn new() -> EdjcResult<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent(crate::USER_AGENT)
+            .build()?;
+
+        Ok(Self {
+            client,
+            api_url: SPANSH_API_URL.to_string(),
+            poll_interval: POLL_INTERVAL,
+            max_polls: MAX_POLLS,
+        })
+    }
+
+    /// Point the client at a different API endpoint
+    #[cfg(test)]
+    pub(crate) fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.to_string();
+        self
+    }
+
+    /// Poll a queued job `max_polls` times, `interval` apart
+    pub fn with_polling(mut self, interval: Duration, max_polls: u32) -> Self {
+        self.poll_interval = interval;
+        self.max_polls = max_polls;
+        self
+    }
+
+    /// Plot a neutron route between two systems
+    ///
+    /// `efficiency` (1-100) is Spansh's trade-off between fewer jumps (low)
+    /// and staying close to the direct line (high).
+    pub fn plot_neutron_route(
+        &self,
+        from: &str,
+        to: &str,
+        jump_range: f64,
+        efficiency: u8,
+    ) -> EdjcResult<JumpResult> {
+        debug!("Plotting Spansh neutron route from {from} to {to}");
+
+        let range = format!("{jump_range:.2}");
+        let efficiency = efficiency.to_string();
+        let response = self
+            .client
+            .post(format!("{}/route", self.api_url))
+            .form(&[
+                ("from", from),
+                ("to", to),
+                ("range", range.as_str()),
+                ("efficiency", efficiency.as_str()),
+            ])
+            .send()?;
+
+        let submitted = job_response(response)?;
+        let job = submitted
+            .job
+            .ok_or_else(|| EdjcError::Spansh("no job id in response".to_string()))?;
+
+        let route = self.wait_for_route(&job)?;
+        route_to_result(route)
+    }
+
+    /// Poll a job until its route is ready, it fails, or we run out of polls
+    fn wait_for_route(&self, job: &str) -> EdjcResult<SpanshRoute> {
+        let url = format!("{}/results/{}", self.api_url, job);
+
+        for poll in 1..=self.max_polls {
+            let response = job_response(self.client.get(&url).send()?)?;
+
+            match response.status.as_deref() {
+                Some("ok") => {
+                    return response.result.ok_or_else(|| {
+                        EdjcError::Spansh(format!("job {job} finished without a route"))
+                    })
+                }
+                Some("queued") | Some("running") | None => {
+                    debug!(
+                        "Spansh job {job} not ready (poll {poll}/{})",
+                        self.max_polls
+                    );
+                    thread::sleep(self.poll_interval);
+                }
+                Some(status) => {
+                    return Err(EdjcError::Spansh(format!("job {job} {status}")));
+                }
+            }
+        }
+
+        Err(EdjcError::Spansh(format!(
+            "job {job} not finished after {} polls",
+            self.max_polls
+        )))
+    }
+}
+
+/// Decode a Spansh response, turning reported errors into `EdjcError::Spansh`
+///
+/// Spansh reports problems (unknown systems, failed jobs) as an `error` field,
+/// usually alongside a 400 status.
+fn job_response(response: reqwest::blocking::Response) -> EdjcResult<SpanshJobResponse> {
+    let status = response.status();
+    let body: SpanshJobResponse = response
+        .json()
+        .map_err(|e| EdjcError::Spansh(format!("unexpected response ({status}): {e}")))?;
+
+    match body.error {
+        Some(error) => Err(EdjcError::Spansh(error)),
+        None if !status.is_success() => Err(EdjcError::Spansh(format!("request failed: {status}"))),
+        None => Ok(body),
+    }
+}
+
+/// Convert a plotted route into a jump result, named as Spansh spells the systems
+fn route_to_result(route: SpanshRoute) -> EdjcResult<JumpResult> {
+    let (Some(first), Some(last)) = (route.system_jumps.first(), route.system_jumps.last()) else {
+        return Err(EdjcError::Spansh("route has no waypoints".to_string()));
+    };
+
+    let jumps = route.total_jumps.unwrap_or_else(|| {
+        route
+            .system_jumps
+            .iter()
+            .map(|waypoint| waypoint.jumps)
+            .sum()
+    });
+    let total_distance = route.distance.unwrap_or_else(|| {
+        route
+            .system_jumps
+            .iter()
+            .map(|waypoint| waypoint.distance_jumped)
+            .sum()
+    });
+    // Supercharging happens on the way, never at the destination
+    let boosts = route.system_jumps[..route.system_jumps.len() - 1]
+        .iter()
+        .filter(|waypoint| waypoint.neutron_star)
+        .count() as u32;
+    let legs = route.system_jumps[1..]
+        .iter()
+        .map(|waypoint| RouteLeg {
+            system: waypoint.system.clone(),
+            boost_star: waypoint.neutron_star,
+            jumps: waypoint.jumps,
+            distance: waypoint.distance_jumped,
+        })
+        .collect();
+
+    Ok(JumpResult {
+        jumps,
+        total_distance,
+        route_type: neutron_route_type(boosts),
+        from_system: first.system.clone(),
+        to_system: last.system.clone(),
+        legs,
+        supercharges: boosts,
+        exact_jumps: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MockServer;
+
+    const ROUTE: &str = r#"{
+        "status": "ok",
+        "job": "abc",
+        "result": {
+            "distance": 262.5,
+            "total_jumps": 5,
+            "system_jumps": [
+                {"system": "Sol", "jumps": 0, "distance_jumped": 0, "neutron_star": false},
+                {"system": "Neutron A", "jumps": 1, "distance_jumped": 18.2, "neutron_star": true},
+                {"system": "Neutron B", "jumps": 2, "distance_jumped": 140.0, "neutron_star": true},
+                {"system": "Target", "jumps": 2, "distance_jumped": 110.1, "neutron_star": false}
+            ]
+        }
+    }"#;
+
+    fn client(server: &MockServer) -> SpanshClient {
+        SpanshClient::new()
+            .unwrap()
+            .with_api_url(&server.url("/api"))
+            .with_polling(Duration::ZERO, 3)
+    }
+
+    #[test]
+    fn test_polls_until_route_is_ready() {
+        let server = MockServer::start();
+        server.respond("/api/route", 202, r#"{"job": "abc", "status": "queued"}"#);
+        server.respond(
+            "/api/results/abc",
+            200,
+            r#"{"job": "abc", "status": "queued"}"#,
+        );
+        server.respond("/api/results/abc", 200, ROUTE);
+
+        let result = client(&server)
+            .plot_neutron_route("Sol", "Target", 20.0, 60)
+            .unwrap();
+        assert_eq!(result.jumps, 5);
+        assert_eq!(result.total_distance, 262.5);
+        assert_eq!(result.route_type, "neutron highway (2 boosts)");
+        assert_eq!(result.supercharges, 2);
+        assert_eq!(result.from_system, "Sol");
+        assert_eq!(result.to_system, "Target");
+        assert_eq!(server.hits("/api/results/abc"), 2);
+
+        assert_eq!(result.legs.len(), 3);
+        assert_eq!(result.legs.iter().map(|leg| leg.jumps).sum::<u32>(), 5);
+        assert_eq!(
+            result.legs[1].describe(),
+            "Neutron B: 2 jumps, 140.0 LY (supercharge)"
+        );
+        assert!(!result.legs[2].boost_star);
+
+        let submitted = &server.requests("/api/route")[0];
+        assert!(submitted.contains("from=Sol"));
+        assert!(submitted.contains("range=20.00"));
+        assert!(submitted.contains("efficiency=60"));
+    }
+
+    #[test]
+    fn test_failed_job_is_reported() {
+        let server = MockServer::start();
+        server.respond("/api/route", 202, r#"{"job": "abc", "status": "queued"}"#);
+        server.respond(
+            "/api/results/abc",
+            400,
+            r#"{"error": "Could not find a route", "status": "failed"}"#,
+        );
+
+        let err = client(&server)
+            .plot_neutron_route("Sol", "Target", 20.0, 60)
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::Spansh(_)), "{err}");
+        assert_eq!(
+            err.to_string(),
+            "Spansh route error: Could not find a route"
+        );
+    }
+
+    #[test]
+    fn test_unreachable_plotter_is_a_network_error() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let err = SpanshClient::new()
+            .unwrap()
+            .with_api_url(&format!("http://127.0.0.1:{port}/api"))
+            .plot_neutron_route("Sol", "Target", 20.0, 60)
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::Network(_)), "{err}");
+        assert_eq!(err.hint(), Some("server unreachable, try again later"));
+    }
+
+    #[test]
+    fn test_gives_up_after_bounded_polls() {
+        let server = MockServer::start();
+        server.respond("/api/route", 202, r#"{"job": "abc", "status": "queued"}"#);
+        server.respond(
+            "/api/results/abc",
+            200,
+            r#"{"job": "abc", "status": "queued"}"#,
+        );
+
+        let err = client(&server)
+            .plot_neutron_route("Sol", "Target", 20.0, 60)
+            .unwrap_err();
+        assert!(err.to_string().contains("not finished after 3 polls"));
+        assert_eq!(server.hits("/api/results/abc"), 3);
+    }
+
+    /// Plots a real route against spansh.co.uk
+    #[cfg(feature = "network-tests")]
+    #[test]
+    fn test_live_sol_to_colonia() {
+        let result = SpanshClient::new()
+            .unwrap()
+            .plot_neutron_route("Sol", "Colonia", 50.0, 60)
+            .unwrap();
+        assert!(result.jumps > 0);
+        assert!(result.route_type.starts_with("neutron highway"));
+        assert!((result.total_distance - 22000.0).abs() < 500.0);
+    }
+}
@@ -0,0 +1,140 @@
+/*!
+Session counters shown by `/edjc-stats`.
+
+A quiet plugin either has nothing to do or is missing the signals. [`Stats`]
+counts what happened since it was loaded (RATSIGNALs seen and parsed, routes
+calculated, EDSM cache hits and misses, failed EDSM requests), so users can
+tell the two apart. The counters are atomics, shared by the plugin and its
+EDSM client. Work done inside [`uncounted`] (a notice preview, say) isn't
+counted.
+*/
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+thread_local! {
+    /// Set while this thread runs [`uncounted`]
+    static UNCOUNTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` without counting anything it does on this thread, e.g. the EDSM
+/// lookups for a preview; other threads keep counting
+pub fn uncounted<T>(f: impl FnOnce() -> T) -> T {
+    /// Restores the previous state, also when `f` panics
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            UNCOUNTED.with(|uncounted| uncounted.set(self.0));
+        }
+    }
+
+    let _restore = Restore(UNCOUNTED.with(|uncounted| uncounted.replace(true)));
+    f()
+}
+
+/// Something counted by [`Stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    /// Messages from a trigger nick that mention a RATSIGNAL
+    SignalsSeen,
+    /// RATSIGNALs the configured patterns could parse
+    SignalsParsed,
+    /// Routes calculated, for commands and RATSIGNALs alike
+    RoutesComputed,
+    /// System coordinates served from the cache
+    CacheHits,
+    /// System coordinates that had to be fetched
+    CacheMisses,
+    /// EDSM requests that failed or came back with an error status
+    ApiErrors,
+}
+
+impl Counter {
+    const ALL: [Counter; 6] = [
+        Counter::SignalsSeen,
+        Counter::SignalsParsed,
+        Counter::RoutesComputed,
+        Counter::CacheHits,
+        Counter::CacheMisses,
+        Counter::ApiErrors,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::SignalsSeen => "signals seen",
+            Self::SignalsParsed => "signals parsed",
+            Self::RoutesComputed => "routes computed",
+            Self::CacheHits => "cache hits",
+            Self::CacheMisses => "cache misses",
+            Self::ApiErrors => "API errors",
+        }
+    }
+}
+
+/// Counters for the current session
+#[derive(Debug, Default)]
+pub struct Stats {
+    counts: [AtomicU64; Counter::ALL.len()],
+}
+
+impl Stats {
+    /// Count one more of something, unless running [`uncounted`]
+    pub fn record(&self, counter: Counter) {
+        if UNCOUNTED.with(Cell::get) {
+            return;
+        }
+        self.counts[counter as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// How many of something were counted so far
+    pub fn get(&self, counter: Counter) -> u64 {
+        self.counts[counter as usize].load(Ordering::Relaxed)
+    }
+
+    /// One line with every counter, e.g. "signals seen: 3 | signals parsed: 2 | ..."
+    pub fn summary(&self) -> String {
+        Counter::ALL
+            .iter()
+            .map(|&counter| format!("{}: {}", counter.label(), self.get(counter)))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let stats = Stats::default();
+        stats.record(Counter::SignalsSeen);
+        stats.record(Counter::SignalsSeen);
+        stats.record(Counter::ApiErrors);
+
+        assert_eq!(stats.get(Counter::SignalsSeen), 2);
+        assert_eq!(stats.get(Counter::SignalsParsed), 0);
+        assert_eq!(
+            stats.summary(),
+            "signals seen: 2 | signals parsed: 0 | routes computed: 0 | cache hits: 0 | cache misses: 0 | API errors: 1"
+        );
+    }
+
+    #[test]
+    fn test_uncounted() {
+        let stats = Stats::default();
+        let answer = uncounted(|| {
+            stats.record(Counter::CacheMisses);
+            // Other threads still count
+            std::thread::scope(|scope| {
+                scope.spawn(|| stats.record(Counter::CacheMisses));
+            });
+            42
+        });
+        assert_eq!(answer, 42);
+        assert_eq!(stats.get(Counter::CacheMisses), 1);
+
+        stats.record(Counter::CacheMisses);
+        assert_eq!(stats.get(Counter::CacheMisses), 2);
+    }
+}
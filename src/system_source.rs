@@ -0,0 +1,257 @@
+/*!
+Where system coordinates come from.
+
+Route planning only needs to turn system names into coordinates and find the
+commander. The [`SystemSource`] trait abstracts those lookups so the plugin
+and the standalone tools can run against EDSM, Inara or, with `offline_mode`,
+against [`LocalSystemSource`]: a handful of well-known systems baked into the
+binary, enough to exercise routing in CI or without a network connection.
+*/
+
+use crate::edsm::{calculate_3d_distance, normalize_system_name, EdsmClient};
+use crate::inara::InaraClient;
+use crate::journal::JournalLocation;
+use crate::types::{Deadline, EdjcError, EdjcResult, SystemCoordinates};
+
+/// Resolves system names into coordinates and commanders into systems
+pub trait SystemSource: std::fmt::Debug + Send + Sync {
+    /// Name of the source, for log messages
+    fn name(&self) -> &'static str;
+
+    /// Coordinates (and boost stars) of a system, by name
+    fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates>;
+
+    /// The system a commander was last seen in
+    ///
+    /// `api_key` is the commander's EDSM API key, needed for private profiles;
+    /// sources with credentials of their own ignore it. By default commanders
+    /// cannot be located.
+    fn get_commander_location(
+        &self,
+        cmdr_name: &str,
+        _api_key: Option<&str>,
+    ) -> EdjcResult<String> {
+        Err(EdjcError::CmdrNotFound(format!(
+            "{cmdr_name} (not located by {})",
+            self.name()
+        )))
+    }
+
+    /// [`Self::get_system_coordinates`] within a deadline
+    ///
+    /// Sources that can cut their requests short override this; by default
+    /// the deadline is only checked before the lookup.
+    fn get_system_coordinates_by(
+        &self,
+        system_name: &str,
+        deadline: &Deadline,
+    ) -> EdjcResult<SystemCoordinates> {
+        deadline.check(&format!("looking up {system_name}"))?;
+        self.get_system_coordinates(system_name)
+    }
+
+    /// [`Self::get_commander_location`] within a deadline, like
+    /// [`Self::get_system_coordinates_by`]
+    fn get_commander_location_by(
+        &self,
+        cmdr_name: &str,
+        api_key: Option<&str>,
+        deadline: &Deadline,
+    ) -> EdjcResult<String> {
+        deadline.check(&format!("asking {} for the CMDR's location", self.name()))?;
+        self.get_commander_location(cmdr_name, api_key)
+    }
+
+    /// Straight-line distance between two systems in light years
+    fn calculate_distance(&self, from_system: &str, to_system: &str) -> EdjcResult<f64> {
+        let from = self.get_system_coordinates(from_system)?;
+        let to = self.get_system_coordinates(to_system)?;
+        Ok(calculate_3d_distance(&from, &to))
+    }
+}
+
+impl SystemSource for EdsmClient {
+    fn name(&self) -> &'static str {
+        "EDSM"
+    }
+
+    fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+        EdsmClient::get_system_coordinates(self, system_name)
+    }
+
+    fn get_commander_location(&self, cmdr_name: &str, api_key: Option<&str>) -> EdjcResult<String> {
+        EdsmClient::get_commander_location(self, cmdr_name, api_key)
+    }
+
+    fn get_system_coordinates_by(
+        &self,
+        system_name: &str,
+        deadline: &Deadline,
+    ) -> EdjcResult<SystemCoordinates> {
+        self.bounded_by(deadline)
+            .get_system_coordinates(system_name)
+    }
+
+    fn get_commander_location_by(
+        &self,
+        cmdr_name: &str,
+        api_key: Option<&str>,
+        deadline: &Deadline,
+    ) -> EdjcResult<String> {
+        self.bounded_by(deadline)
+            .get_commander_location(cmdr_name, api_key)
+    }
+}
+
+impl SystemSource for InaraClient {
+    fn name(&self) -> &'static str {
+        "Inara"
+    }
+
+    fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+        InaraClient::get_system_coordinates(self, system_name)
+    }
+
+    fn get_commander_location(
+        &self,
+        cmdr_name: &str,
+        _api_key: Option<&str>,
+    ) -> EdjcResult<String> {
+        self.get_cmdr_location(cmdr_name)
+    }
+}
+
+/// Locates the commander only; the journal knows nothing about other systems
+impl SystemSource for JournalLocation {
+    fn name(&self) -> &'static str {
+        "the journal"
+    }
+
+    fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+        Err(EdjcError::system_not_found(format!(
+            "{system_name} (the journal has no coordinates)"
+        )))
+    }
+
+    fn get_commander_location(
+        &self,
+        cmdr_name: &str,
+        _api_key: Option<&str>,
+    ) -> EdjcResult<String> {
+        self.current_system()
+            .map_err(|e| EdjcError::CmdrNotFound(format!("{cmdr_name} ({e})")))
+    }
+}
+
+/// A bundled system: name, coordinates and the permit it needs, if any
+type BundledSystem = (&'static str, [f64; 3], Option<&'static str>);
+
+/// Well-known systems available offline
+const BUNDLED_SYSTEMS: &[BundledSystem] = &[
+    ("Sol", [0.0, 0.0, 0.0], Some("Sol")),
+    ("Deciat", [122.625, -0.8125, -47.28125], None),
+    ("Maia", [-81.78125, -149.4375, -343.375], None),
+    ("Colonia", [-9530.5, -910.28125, 19808.125], None),
+    ("Sagittarius A*", [25.21875, -20.90625, 25899.96875], None),
+    ("Beagle Point", [-1111.5625, -134.21875, 65269.75], None),
+];
+
+/// Offline source that only knows a few bundled, well-known systems
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalSystemSource;
+
+impl LocalSystemSource {
+    /// Names of the systems available offline
+    pub fn system_names(&self) -> impl Iterator<Item = &'static str> {
+        BUNDLED_SYSTEMS.iter().map(|&(name, _, _)| name)
+    }
+}
+
+impl SystemSource for LocalSystemSource {
+    fn name(&self) -> &'static str {
+        "bundled systems"
+    }
+
+    fn get_system_coordinates(&self, system_name: &str) -> EdjcResult<SystemCoordinates> {
+        let normalized = normalize_system_name(system_name);
+        let &(name, [x, y, z], permit) = BUNDLED_SYSTEMS
+            .iter()
+            .find(|(name, _, _)| *name == normalized)
+            .ok_or_else(|| EdjcError::SystemNotBundled(system_name.to_string()))?;
+
+        Ok(SystemCoordinates {
+            name: name.to_string(),
+            x,
+            y,
+            z,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: permit.map(str::to_string),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_systems() {
+        let systems = LocalSystemSource;
+
+        let colonia = systems.get_system_coordinates(" colonia ").unwrap();
+        assert_eq!(colonia.name, "Colonia");
+        assert_eq!(colonia.z, 19808.125);
+        assert!(systems
+            .get_system_coordinates("Sol")
+            .unwrap()
+            .requires_permit());
+
+        let distance = systems.calculate_distance("Sol", "Colonia").unwrap();
+        assert!(systems.get_system_coordinates("sagittarius  a*").is_ok());
+        assert!((distance - 22000.0).abs() < 50.0, "{distance}");
+
+        let err = systems.get_system_coordinates("Fuelum").unwrap_err();
+        assert!(
+            matches!(&err, EdjcError::SystemNotBundled(name) if name == "Fuelum"),
+            "{err}"
+        );
+        assert_eq!(systems.system_names().count(), 6);
+
+        let err = systems
+            .get_commander_location("Whit3Arrow", None)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "CMDR not found: Whit3Arrow (not located by bundled systems)"
+        );
+    }
+
+    #[test]
+    fn test_inara_errors_keep_their_type() {
+        let server = crate::test_support::MockServer::start();
+        server.respond(
+            "/inara",
+            200,
+            r#"{"header": {"eventStatus": 200}, "events": [{"eventStatus": 204}]}"#,
+        );
+        server.respond("/inara", 503, "");
+        let inara = InaraClient::new("test-key")
+            .unwrap()
+            .with_api_url(&server.url("/inara"))
+            .with_rate_limit(0);
+        let systems: &dyn SystemSource = &inara;
+
+        let err = systems
+            .get_commander_location("Whit3Arrow", None)
+            .unwrap_err();
+        assert!(matches!(err, EdjcError::CmdrNotFound(_)), "{err}");
+        let err = systems.get_system_coordinates("Sol").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Inara API error: request failed: 503 Service Unavailable"
+        );
+    }
+}
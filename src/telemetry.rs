@@ -0,0 +1,267 @@
+/*!
+`tracing` spans around API lookups and route planning.
+
+With the `tracing` feature, [`get_system_coordinates`](crate::edsm::EdsmClient::get_system_coordinates),
+[`get_commander_location`](crate::edsm::EdsmClient::get_commander_location) and
+[`plan_from_by`](crate::planner::RoutePlanner::plan_from_by) each run in a
+debug-level span recording the system (or commander), whether the cache
+answered (`cache = "hit"` or `"miss"`) and how long the call took
+(`duration_ms`). Without it the spans compile away and the plugin logs
+through `log` alone.
+*/
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+/// A span entered for the duration of an API call or route plan; closed,
+/// with its `duration_ms` recorded, when dropped
+#[derive(Debug)]
+pub(crate) struct ApiSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+    #[cfg(feature = "tracing")]
+    started: Instant,
+}
+
+impl ApiSpan {
+    #[cfg(feature = "tracing")]
+    fn enter(span: tracing::Span) -> Self {
+        Self {
+            span: span.entered(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Span for a system coordinates lookup
+    pub(crate) fn system_coordinates(system: &str) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            Self::enter(tracing::debug_span!(
+                "get_system_coordinates",
+                system = %system,
+                cache = tracing::field::Empty,
+                duration_ms = tracing::field::Empty,
+            ))
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = system;
+            Self {}
+        }
+    }
+
+    /// Span for a commander location lookup
+    pub(crate) fn commander_location(cmdr: &str) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            Self::enter(tracing::debug_span!(
+                "get_commander_location",
+                cmdr = %cmdr,
+                system = tracing::field::Empty,
+                cache = tracing::field::Empty,
+                duration_ms = tracing::field::Empty,
+            ))
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = cmdr;
+            Self {}
+        }
+    }
+
+    /// Span for planning a route to `system`
+    pub(crate) fn plan_route(system: &str) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            Self::enter(tracing::debug_span!(
+                "plan_route",
+                system = %system,
+                origin = tracing::field::Empty,
+                route = tracing::field::Empty,
+                jumps = tracing::field::Empty,
+                duration_ms = tracing::field::Empty,
+            ))
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = system;
+            Self {}
+        }
+    }
+
+    /// Record whether the cache answered
+    pub(crate) fn cache(&self, hit: bool) {
+        #[cfg(feature = "tracing")]
+        self.span.record("cache", if hit { "hit" } else { "miss" });
+        #[cfg(not(feature = "tracing"))]
+        let _ = hit;
+    }
+
+    /// Record a text field declared by the span's constructor
+    pub(crate) fn record(&self, field: &'static str, value: &str) {
+        #[cfg(feature = "tracing")]
+        self.span.record(field, value);
+        #[cfg(not(feature = "tracing"))]
+        let _ = (field, value);
+    }
+
+    /// Record a route's jump count on a [`Self::plan_route`] span
+    pub(crate) fn jumps(&self, jumps: u32) {
+        #[cfg(feature = "tracing")]
+        self.span.record("jumps", jumps);
+        #[cfg(not(feature = "tracing"))]
+        let _ = jumps;
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for ApiSpan {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed().as_secs_f64() * 1000.0;
+        self.span.record("duration_ms", elapsed);
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::config::Config;
+    use crate::edsm::EdsmClient;
+    use crate::system_source::LocalSystemSource;
+    use crate::test_support::MockServer;
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// A span as the subscriber saw it: its name and fields, as text
+    #[derive(Debug, Clone)]
+    struct CapturedSpan {
+        name: &'static str,
+        fields: HashMap<&'static str, String>,
+    }
+
+    impl CapturedSpan {
+        fn field(&self, name: &str) -> Option<&str> {
+            self.fields.get(name).map(String::as_str)
+        }
+    }
+
+    impl Visit for CapturedSpan {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.fields.insert(field.name(), format!("{value:?}"));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.fields.insert(field.name(), value.to_string());
+        }
+    }
+
+    /// Subscriber keeping every span, in the order they were opened
+    #[derive(Debug, Clone, Default)]
+    struct SpanCapture {
+        spans: Arc<Mutex<Vec<CapturedSpan>>>,
+    }
+
+    impl SpanCapture {
+        fn spans(&self, name: &str) -> Vec<CapturedSpan> {
+            self.spans
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|span| span.name == name)
+                .cloned()
+                .collect()
+        }
+    }
+
+    impl Subscriber for SpanCapture {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut captured = CapturedSpan {
+                name: span.metadata().name(),
+                fields: HashMap::new(),
+            };
+            span.record(&mut captured);
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(captured);
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut spans = self.spans.lock().unwrap();
+            values.record(&mut spans[span.into_u64() as usize - 1]);
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_lookups_record_spans() {
+        let server = MockServer::start();
+        server.respond(
+            "/api/system",
+            200,
+            r#"{"name": "Fuelum", "coords": {"x": 52.0, "y": -52.65625, "z": 49.8125}}"#,
+        );
+        server.respond(
+            "/logs/get-position",
+            200,
+            r#"{"msgnum": 100, "system": "Fuelum"}"#,
+        );
+        let client = EdsmClient::new()
+            .unwrap()
+            .with_api_urls(&server.url("/api"), &server.url("/logs"));
+        let capture = SpanCapture::default();
+
+        tracing::subscriber::with_default(capture.clone(), || {
+            client.get_system_coordinates("Fuelum").unwrap();
+            client.get_system_coordinates("fuelum").unwrap();
+            client.get_commander_location("Whit3Arrow", None).unwrap();
+        });
+
+        let lookups = capture.spans("get_system_coordinates");
+        assert_eq!(lookups.len(), 2);
+        assert_eq!(lookups[0].field("system"), Some("Fuelum"));
+        assert_eq!(lookups[0].field("cache"), Some("miss"));
+        assert_eq!(lookups[1].field("cache"), Some("hit"));
+        for lookup in &lookups {
+            let duration: f64 = lookup.field("duration_ms").unwrap().parse().unwrap();
+            assert!(duration >= 0.0);
+        }
+
+        let [location] = capture.spans("get_commander_location").try_into().unwrap();
+        assert_eq!(location.field("cmdr"), Some("Whit3Arrow"));
+        assert_eq!(location.field("system"), Some("Fuelum"));
+        assert_eq!(location.field("cache"), Some("miss"));
+        assert!(location.field("duration_ms").is_some());
+    }
+
+    #[test]
+    fn test_route_planning_records_span() {
+        let config = Config::default();
+        let capture = SpanCapture::default();
+
+        tracing::subscriber::with_default(capture.clone(), || {
+            let planner = crate::planner::RoutePlanner::new(&config, &LocalSystemSource);
+            crate::planner::plan_route(&planner, "Colonia", Some("Sol")).unwrap();
+        });
+
+        let [plan] = capture.spans("plan_route").try_into().unwrap();
+        assert_eq!(plan.field("system"), Some("Colonia"));
+        assert_eq!(plan.field("origin"), Some("Sol"));
+        assert!(plan.field("route").is_some());
+        assert!(plan.field("jumps").unwrap().parse::<u32>().unwrap() > 0);
+        assert!(plan.field("duration_ms").is_some());
+    }
+}
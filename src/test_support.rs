@@ -0,0 +1,225 @@
+/*!
+Test helpers shared by the unit tests.
+
+[`MockServer`] is a tiny HTTP server on a local port that answers requests
+with canned responses per path, so API clients can be exercised without
+touching the real EDSM/Inara services. [`CONFIG_PATH_LOCK`] serializes tests
+that redirect the config file, [`HEXCHAT_HANDLE_LOCK`] those that print
+through HexChat.
+*/
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Serializes tests that touch process-wide config path state
+pub static CONFIG_PATH_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes tests that store the HexChat plugin handle or print through it,
+/// since the handle is global
+pub static HEXCHAT_HANDLE_LOCK: Mutex<()> = Mutex::new(());
+
+/// A canned HTTP response
+#[derive(Debug, Clone)]
+struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    /// How long the server waits before answering
+    delay: Duration,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    /// Queued responses per path; the last one keeps being served
+    responses: HashMap<String, VecDeque<MockResponse>>,
+    /// Request target (path and query) plus body of every request, per path
+    requests: HashMap<String, Vec<String>>,
+}
+
+/// Local HTTP server answering with canned responses
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    /// Start a server on a free local port
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let addr = listener.local_addr().expect("mock server address");
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread_state = Arc::clone(&state);
+        let thread_stopped = Arc::clone(&stopped);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    handle_connection(stream, &thread_state);
+                }
+            }
+        });
+
+        Self {
+            addr,
+            state,
+            stopped,
+        }
+    }
+
+    /// Full URL for a path on this server
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// Queue a response for a path
+    ///
+    /// Responses are served in order; the last queued one is repeated.
+    pub fn respond(&self, path: &str, status: u16, body: &str) {
+        self.respond_with_headers(path, status, &[], body);
+    }
+
+    /// Queue a response that only comes after `delay`, for a slow server
+    pub fn respond_after(&self, path: &str, delay: Duration, status: u16, body: &str) {
+        self.respond_with_headers(path, status, &[], body);
+        if let Some(response) = self
+            .lock()
+            .responses
+            .get_mut(path)
+            .and_then(|queue| queue.back_mut())
+        {
+            response.delay = delay;
+        }
+    }
+
+    /// Queue a response with extra headers for a path
+    pub fn respond_with_headers(
+        &self,
+        path: &str,
+        status: u16,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) {
+        self.lock()
+            .responses
+            .entry(path.to_string())
+            .or_default()
+            .push_back(MockResponse {
+                status,
+                headers: headers
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+                body: body.to_string(),
+                delay: Duration::ZERO,
+            });
+    }
+
+    /// Number of requests received for a path
+    pub fn hits(&self, path: &str) -> usize {
+        self.requests(path).len()
+    }
+
+    /// Requests received for a path (request target, then body if any)
+    pub fn requests(&self, path: &str) -> Vec<String> {
+        self.lock().requests.get(path).cloned().unwrap_or_default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it notices the flag
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Read one request and answer it from the queued responses
+fn handle_connection(stream: TcpStream, state: &Mutex<MockState>) {
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let target = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .to_string();
+    let path = target.split('?').next().unwrap_or("/").to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).is_err() || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    let _ = reader.read_exact(&mut body);
+
+    let response = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut request = target;
+        if !body.is_empty() {
+            request.push('\n');
+            request.push_str(&String::from_utf8_lossy(&body));
+        }
+        state
+            .requests
+            .entry(path.clone())
+            .or_default()
+            .push(request);
+
+        match state.responses.get_mut(&path) {
+            Some(queue) if queue.len() > 1 => queue.pop_front(),
+            Some(queue) => queue.front().cloned(),
+            None => None,
+        }
+        .unwrap_or(MockResponse {
+            status: 404,
+            headers: Vec::new(),
+            body: String::new(),
+            delay: Duration::ZERO,
+        })
+    };
+    thread::sleep(response.delay);
+
+    let headers: String = response
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{name}: {value}\r\n"))
+        .collect();
+
+    let mut stream = stream;
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        headers,
+        response.body.len(),
+        response.body
+    );
+    let _ = stream.flush();
+}
@@ -6,6 +6,7 @@ for representing Elite Dangerous game data and calculation results.
 */
 
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 /// Information about a CMDR (player)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +19,10 @@ pub struct CmdrInfo {
     pub current_station: Option<String>,
 }
 
+/// Steps per light year of the grid EDSM and the journal give coordinates
+/// on: every coordinate is a multiple of 1/32 LY
+pub const COORDINATE_STEPS_PER_LY: f64 = 32.0;
+
 /// 3D coordinates of a star system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemCoordinates {
@@ -33,6 +38,24 @@ pub struct SystemCoordinates {
     pub has_neutron_star: bool,
     /// Whether the system has a white dwarf
     pub has_white_dwarf: bool,
+    /// What kind of star the primary is, when known; overrides the flags
+    /// above, so a black hole never counts as a boost star
+    #[serde(default)]
+    pub primary_star_kind: Option<PrimaryStarKind>,
+    /// Whether EDSM flags the system as hidden (e.g. a duplicate entry)
+    #[serde(default)]
+    pub is_hidden: bool,
+    /// Permit needed to enter the system, if any (may be empty when unnamed)
+    #[serde(default)]
+    pub required_permit: Option<String>,
+}
+
+/// One of several systems sharing a name, told apart by EDSM's system id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemCandidate {
+    /// EDSM's id for the system, for [`get_system_by_id`](crate::edsm::EdsmClient::get_system_by_id)
+    pub id: u64,
+    pub coordinates: SystemCoordinates,
 }
 
 /// Result of a jump calculation
@@ -48,6 +71,77 @@ pub struct JumpResult {
     pub from_system: String,
     /// Destination system name
     pub to_system: String,
+    /// Waypoints of the route in order, when it was routed through real
+    /// systems rather than estimated
+    #[serde(default)]
+    pub legs: Vec<RouteLeg>,
+    /// Supercharged jumps on the route, counted from its waypoints when it
+    /// was routed through real neutron stars
+    #[serde(default)]
+    pub supercharges: u32,
+    /// Distance over jump range, for unboosted routes whose last jump may
+    /// only be partly needed; see [`JumpRounding`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact_jumps: Option<f64>,
+}
+
+/// One leg of a route, ending at a waypoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteLeg {
+    /// Waypoint system the leg ends at
+    pub system: String,
+    /// Whether the waypoint is a boost star to supercharge at
+    pub boost_star: bool,
+    /// Jumps flown on the leg
+    pub jumps: u32,
+    /// Straight-line distance of the leg in light years
+    pub distance: f64,
+}
+
+impl RouteLeg {
+    /// One-line description, e.g. "Neutron A: 2 jumps, 140.0 LY (supercharge)"
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: {} {}, {:.1} LY{}",
+            self.system,
+            self.jumps,
+            if self.jumps == 1 { "jump" } else { "jumps" },
+            self.distance,
+            if self.boost_star {
+                " (supercharge)"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+/// A single hop along a plotted corridor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorridorHop {
+    /// Destination system of the hop
+    pub system: String,
+    /// Spectral class of the destination's primary star, if known
+    pub star_class: Option<String>,
+}
+
+impl CorridorHop {
+    /// Whether the destination's primary star can be fuel-scooped
+    pub fn is_scoopable(&self) -> bool {
+        self.star_class.as_deref().is_some_and(is_scoopable_class)
+    }
+}
+
+/// Whether a star class belongs to the scoopable main sequence (KGBFOAM)
+///
+/// Accepts both bare classes ("K", "G2V") and EDSM subtype strings
+/// ("K (Yellow-Orange) Star"). Classes that merely start with one of the
+/// letters, such as "MS-type Star", are not scoopable.
+pub fn is_scoopable_class(star_class: &str) -> bool {
+    let mut chars = star_class.trim().chars();
+    let scoopable_letter = matches!(chars.next(), Some('K' | 'G' | 'B' | 'F' | 'O' | 'A' | 'M'));
+    let ends_class = chars.next().is_none_or(|c| c.is_ascii_digit() || c == ' ');
+    scoopable_letter && ends_class
 }
 
 /// Information about a star system from various sources
@@ -67,6 +161,49 @@ pub struct SystemInfo {
     pub security: Option<SecurityLevel>,
 }
 
+/// What kind of star a system's primary is, as far as routing cares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimaryStarKind {
+    /// Supercharges 4x
+    Neutron,
+    /// Supercharges 1.5x (any D-sequence class)
+    WhiteDwarf,
+    /// Never supercharges, whatever else it is filed as
+    BlackHole,
+    /// Scoopable KGBFOAM classes
+    MainSequence,
+    /// Brown dwarfs, T Tauri, Wolf-Rayet, carbon stars and the rest
+    Other,
+}
+
+impl PrimaryStarKind {
+    /// Kind of a regular star of spectral class `class`
+    pub fn of_class(class: &str) -> Self {
+        if is_scoopable_class(class) {
+            Self::MainSequence
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Whether an FSD can be supercharged at this kind of star
+    pub fn can_supercharge(self) -> bool {
+        matches!(self, Self::Neutron | Self::WhiteDwarf)
+    }
+
+    /// Name for /sysinfo
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Neutron => "neutron star",
+            Self::WhiteDwarf => "white dwarf",
+            Self::BlackHole => "black hole",
+            Self::MainSequence => "main sequence",
+            Self::Other => "other",
+        }
+    }
+}
+
 /// Information about a star
 #[derive(Debug, Clone)]
 pub struct StarInfo {
@@ -82,6 +219,8 @@ pub struct StarInfo {
     pub can_supercharge: bool,
     /// Supercharge multiplier (1.0 for no boost, 1.5 for white dwarf, 4.0 for neutron)
     pub supercharge_multiplier: f64,
+    /// Neutron star, white dwarf, black hole, ...
+    pub kind: PrimaryStarKind,
 }
 
 /// System security levels
@@ -120,6 +259,327 @@ impl SecurityLevel {
     }
 }
 
+/// Frame Shift Drive fuel model for a fitted ship
+///
+/// Fuel per jump follows the in-game formula
+/// `linear_constant * 0.001 * (distance * ship_mass / optimal_mass) ^ power_constant`,
+/// with the constants taken from the drive's class (2-7) and rating (A-E).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FsdProfile {
+    /// Drive class (size), 2-7
+    pub class: u8,
+    /// Drive rating, 'A' (best) to 'E'
+    pub rating: char,
+    /// Total ship mass in tons (hull, modules, fuel and cargo)
+    pub ship_mass: f64,
+    /// Optimal mass of an engineered drive, replacing the stock drive's
+    pub engineered_optimal_mass: Option<f64>,
+}
+
+impl FsdProfile {
+    /// Build a profile, rejecting drives that don't exist and non-positive masses
+    pub fn new(class: u8, rating: char, ship_mass: f64) -> EdjcResult<Self> {
+        let rating = rating.to_ascii_uppercase();
+        if !(2..=7).contains(&class) {
+            return Err(EdjcError::Config(format!(
+                "FSD class must be between 2 and 7, got {class}"
+            )));
+        }
+        if !('A'..='E').contains(&rating) {
+            return Err(EdjcError::Config(format!(
+                "FSD rating must be A to E, got {rating}"
+            )));
+        }
+        if ship_mass <= 0.0 {
+            return Err(EdjcError::Config(
+                "Ship mass must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            class,
+            rating,
+            ship_mass,
+            engineered_optimal_mass: None,
+        })
+    }
+
+    /// Use an engineered drive's optimal mass instead of the stock drive's
+    pub fn with_optimal_mass(mut self, optimal_mass: Option<f64>) -> Self {
+        self.engineered_optimal_mass = optimal_mass;
+        self
+    }
+
+    /// Mass the drive is optimised for, in tons
+    pub fn optimal_mass(&self) -> f64 {
+        if let Some(optimal_mass) = self.engineered_optimal_mass {
+            return optimal_mass;
+        }
+
+        // Per class, ratings E, D, C, B, A
+        const OPTIMAL_MASS: [[f64; 5]; 6] = [
+            [48.0, 54.0, 60.0, 75.0, 90.0],
+            [80.0, 90.0, 100.0, 125.0, 150.0],
+            [280.0, 315.0, 350.0, 438.0, 525.0],
+            [560.0, 630.0, 700.0, 875.0, 1050.0],
+            [960.0, 1080.0, 1200.0, 1500.0, 1800.0],
+            [1440.0, 1620.0, 1800.0, 2250.0, 2700.0],
+        ];
+        OPTIMAL_MASS[self.class_index()][self.rating_index()]
+    }
+
+    /// Most fuel the drive can use on a single jump, in tons
+    pub fn max_fuel_per_jump(&self) -> f64 {
+        // Per class, ratings E, D, C, B, A
+        const MAX_FUEL: [[f64; 5]; 6] = [
+            [0.6, 0.6, 0.6, 0.8, 0.9],
+            [1.2, 1.2, 1.2, 1.5, 1.8],
+            [2.0, 2.0, 2.0, 2.5, 3.0],
+            [3.3, 3.3, 3.3, 4.1, 5.0],
+            [5.3, 5.3, 5.3, 6.6, 8.0],
+            [8.5, 8.5, 8.5, 10.6, 12.8],
+        ];
+        MAX_FUEL[self.class_index()][self.rating_index()]
+    }
+
+    /// Rating-dependent multiplier of the fuel formula
+    pub fn linear_constant(&self) -> f64 {
+        match self.rating {
+            'A' => 12.0,
+            'B' => 10.0,
+            'C' => 8.0,
+            'D' => 10.0,
+            _ => 11.0,
+        }
+    }
+
+    /// Class-dependent exponent of the fuel formula
+    pub fn power_constant(&self) -> f64 {
+        [2.00, 2.15, 2.30, 2.45, 2.60, 2.75][self.class_index()]
+    }
+
+    /// Fuel used by a single jump of `distance` light years, in tons
+    pub fn fuel_for_jump(&self, distance: f64) -> f64 {
+        let mass_ratio = self.ship_mass / self.optimal_mass();
+        self.linear_constant() * 0.001 * (distance * mass_ratio).powf(self.power_constant())
+    }
+
+    /// Longest single jump on a full tank at this mass, in light years
+    pub fn max_jump_range(&self) -> f64 {
+        let optimal_ratio = self.optimal_mass() / self.ship_mass;
+        optimal_ratio
+            * (self.max_fuel_per_jump() / (self.linear_constant() * 0.001))
+                .powf(1.0 / self.power_constant())
+    }
+
+    fn class_index(&self) -> usize {
+        usize::from(self.class - 2)
+    }
+
+    fn rating_index(&self) -> usize {
+        // 'E' is the first column, 'A' the last
+        (b'E' - self.rating as u8) as usize
+    }
+}
+
+/// How long routes take to fly, for the `{time}` estimate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeModel {
+    /// Average time spent on a single jump, in seconds
+    pub seconds_per_jump: f64,
+    /// Extra time per supercharge (approach, jet cone, heat), in seconds
+    pub supercharge_seconds: f64,
+    /// Further time per neutron supercharge (finding the star), in seconds
+    pub neutron_seconds: f64,
+    /// Extra time per fuel scoop stop, in seconds
+    pub scoop_stop_seconds: f64,
+}
+
+impl TimeModel {
+    /// Estimated minutes for a route; `neutron_boosts` are the supercharges
+    /// among `supercharges` taken at neutron stars
+    pub fn minutes(
+        &self,
+        jumps: u32,
+        supercharges: u32,
+        neutron_boosts: u32,
+        scoop_stops: u32,
+    ) -> f64 {
+        let seconds = jumps as f64 * self.seconds_per_jump
+            + supercharges as f64 * self.supercharge_seconds
+            + neutron_boosts as f64 * self.neutron_seconds
+            + scoop_stops as f64 * self.scoop_stop_seconds;
+        seconds / 60.0
+    }
+}
+
+impl Default for TimeModel {
+    fn default() -> Self {
+        Self {
+            seconds_per_jump: 120.0,
+            supercharge_seconds: 60.0,
+            neutron_seconds: 90.0,
+            scoop_stop_seconds: 180.0,
+        }
+    }
+}
+
+/// What the route planner should minimize
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteOptimization {
+    /// Fewest jumps
+    #[default]
+    Jumps,
+    /// Shortest estimated wall-clock time
+    Time,
+}
+
+/// Where routes are planned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteBackend {
+    /// The built-in calculator (with EDSM corridor lookups)
+    #[default]
+    Builtin,
+    /// Spansh's neutron plotter
+    Spansh,
+}
+
+/// Where RATSIGNAL notices are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    /// Printed in the current tab, visible only to you
+    #[default]
+    Console,
+    /// Said in the channel the RATSIGNAL came from, for everyone there
+    Channel,
+}
+
+/// What happens to RATSIGNAL notices during `announce_cooldown_seconds`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CooldownPolicy {
+    /// Held back and shown one at a time as the cooldown runs out
+    #[default]
+    Queue,
+    /// Only logged, never shown
+    Drop,
+}
+
+/// What a RATSIGNAL gets when the commander's location is unknown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingLocationPolicy {
+    /// A route from `fallback_origin`, with a warning
+    #[default]
+    Fallback,
+    /// No route, just a notice that the origin is unknown
+    Skip,
+    /// No route, but a prompt to route by hand from a given origin
+    Ask,
+}
+
+/// How numbers (distances, fuel, minutes) are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    /// No thousands separator, decimal point: "25900.0"
+    #[default]
+    Plain,
+    /// Comma thousands separator, decimal point: "25,900.0"
+    En,
+    /// Dot thousands separator, decimal comma: "25.900,0"
+    De,
+}
+
+impl NumberFormat {
+    /// A number with `decimals` decimal places in this style
+    pub fn format(self, value: f64, decimals: usize) -> String {
+        let plain = format!("{value:.decimals$}");
+        let (separator, decimal_mark) = match self {
+            Self::Plain => return plain,
+            Self::En => (',', '.'),
+            Self::De => ('.', ','),
+        };
+
+        let (sign, unsigned) = match plain.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", plain.as_str()),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let mut formatted = format!("{sign}{}", group_digits(integer, separator));
+        if !fraction.is_empty() {
+            formatted.push(decimal_mark);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+
+    /// A number like [`Self::format`], signed even when positive ("+2.5")
+    pub fn signed(self, value: f64, decimals: usize) -> String {
+        let formatted = self.format(value, decimals);
+        if formatted.starts_with('-') {
+            formatted
+        } else {
+            format!("+{formatted}")
+        }
+    }
+
+    /// A distance to one decimal place with its unit ("25,900.0 LY")
+    pub fn distance(self, ly: f64) -> String {
+        format!("{} LY", self.format(ly, 1))
+    }
+}
+
+/// How a direct route's jump count is shown when the last jump is partial
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JumpRounding {
+    /// Every jump it takes to get there: 7.3 jumps is 8
+    #[default]
+    Ceil,
+    /// The nearest whole number of jumps: 7.3 is 7, 7.6 is 8
+    Nearest,
+    /// Both counts when the last jump is nearly complete: 7.8 is "7–8"
+    Range,
+}
+
+impl JumpRounding {
+    /// Fraction of the last jump from which [`Self::Range`] shows both counts
+    pub const RANGE_REMAINDER: f64 = 0.75;
+
+    /// The jump count of a route as this policy shows it, given its exact
+    /// (fractional) count when known
+    pub fn jumps(self, jumps: u32, exact_jumps: Option<f64>) -> String {
+        let Some(exact) = exact_jumps.filter(|exact| exact.is_finite() && *exact > 0.0) else {
+            return jumps.to_string();
+        };
+        let whole = exact.floor() as u32;
+        match self {
+            Self::Ceil => jumps.to_string(),
+            Self::Nearest => (exact.round() as u32).max(1).to_string(),
+            Self::Range if exact.fract() >= Self::RANGE_REMAINDER && whole > 0 => {
+                format!("{whole}–{jumps}")
+            }
+            Self::Range => jumps.to_string(),
+        }
+    }
+}
+
+/// Where the commander's current location can come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LocationSource {
+    /// The latest jump in the journal at `journal_path`
+    Journal,
+    /// EDSM's flight log (or an injected system source)
+    Edsm,
+    /// Inara, when an API key is set
+    Inara,
+}
+
 /// Route planning options
 #[derive(Debug, Clone)]
 pub struct RouteOptions {
@@ -127,14 +587,66 @@ pub struct RouteOptions {
     pub use_neutron_stars: bool,
     /// Whether to use white dwarfs for supercharging
     pub use_white_dwarfs: bool,
-    /// Maximum detour distance for finding supercharge stars
+    /// How far off the straight line a supercharge star may lie, in LY
     pub max_detour_ly: f64,
+    /// The same as a percentage of the route's length; the larger of the
+    /// two applies (0 uses `max_detour_ly` alone)
+    pub max_detour_percent: f64,
     /// Minimum fuel tank capacity in tons
     pub fuel_capacity: Option<f64>,
     /// Whether to avoid dangerous systems
     pub avoid_dangerous: bool,
-    /// Whether to prefer populated systems
+    /// Whether to prefer waypoints in populated systems
     pub prefer_populated: bool,
+    /// With `prefer_populated`, how many more jumps a populated waypoint may
+    /// cost than the best one elsewhere (0 only breaks ties)
+    pub populated_detour_jumps: u32,
+    /// Report "no boost route available" instead of falling back to a direct route
+    pub require_boost: bool,
+    /// Whether to pick the route with the fewest jumps or the shortest time
+    pub optimize_for: RouteOptimization,
+    /// Average time spent on a single jump, in seconds
+    pub seconds_per_jump: f64,
+    /// Extra time spent on each supercharged jump (approach, cone, cooldown), in seconds
+    pub boost_penalty_seconds: f64,
+    /// Further time spent on each neutron supercharge (finding the star,
+    /// heat, the jet cone), in seconds
+    pub neutron_penalty_seconds: f64,
+    /// Time spent on each fuel scoop stop, in seconds, counted when
+    /// `fuel_capacity` is known
+    pub scoop_stop_seconds: f64,
+    /// Premium FSD injections (+100% range for one jump) that may be spent on the route
+    pub premium_injections: u32,
+    /// Plan with the range between laden and `max_jump_range` as the route
+    /// burns fuel out of `fuel_capacity`, instead of the laden range for
+    /// every jump
+    pub interpolate_range: bool,
+    /// The ship's maximum (empty tank) jump range, used by `interpolate_range`
+    pub max_jump_range: Option<f64>,
+    /// Plan every jump with `max_jump_range` (stripped, fuel-optimized ship)
+    /// instead of the laden range
+    pub use_max_range: bool,
+    /// Assume the first jump leaves supercharged from a neutron star at the
+    /// origin, e.g. on the way back from a case next to one
+    pub boost_at_origin: bool,
+}
+
+impl RouteOptions {
+    /// Range routes are planned with: the laden range, or with
+    /// `use_max_range` the maximum range when it is known
+    pub fn planning_range(&self, laden_jump_range: f64) -> f64 {
+        match self.max_jump_range {
+            Some(max) if self.use_max_range => max,
+            _ => laden_jump_range,
+        }
+    }
+
+    /// How far off the straight line a supercharge star may lie on a route
+    /// of `route_distance` LY
+    pub fn detour_budget(&self, route_distance: f64) -> f64 {
+        self.max_detour_ly
+            .max(route_distance * self.max_detour_percent / 100.0)
+    }
 }
 
 impl Default for RouteOptions {
@@ -143,9 +655,22 @@ impl Default for RouteOptions {
             use_neutron_stars: true,
             use_white_dwarfs: true,
             max_detour_ly: 50.0,
+            max_detour_percent: 0.0,
             fuel_capacity: None,
             avoid_dangerous: true,
             prefer_populated: false,
+            populated_detour_jumps: 1,
+            require_boost: false,
+            optimize_for: RouteOptimization::Jumps,
+            seconds_per_jump: 120.0,
+            boost_penalty_seconds: 60.0,
+            neutron_penalty_seconds: 90.0,
+            scoop_stop_seconds: 180.0,
+            premium_injections: 0,
+            interpolate_range: false,
+            max_jump_range: None,
+            use_max_range: false,
+            boost_at_origin: false,
         }
     }
 }
@@ -165,24 +690,108 @@ pub struct RatsignalInfo {
     pub system_name: String,
     /// Additional system information (e.g., "Brown dwarf 51 LY from Fuelum")
     pub system_info: Option<String>,
+    /// Nearby landmark named in `system_info`, if any
+    pub landmark: Option<LandmarkHint>,
     /// Language code
     pub language: Option<String>,
     /// Full original message
     pub raw_message: String,
 }
 
+impl RatsignalInfo {
+    /// Short label identifying the case in responses ("Case #3 [PC Odyssey]")
+    pub fn case_label(&self) -> String {
+        match &self.mode {
+            Some(mode) => format!("Case #{} [{} {}]", self.case_number, self.platform, mode),
+            None => format!("Case #{} [{}]", self.case_number, self.platform),
+        }
+    }
+}
+
+/// What a relay bot said became of a case after its RATSIGNAL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseEvent {
+    /// The case was closed; its number may come up again for a new case
+    Closed,
+    /// Rats were assigned to the case
+    Assigned,
+}
+
+/// A case closed or assigned, as announced by a relay bot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseUpdate {
+    pub case_number: String,
+    pub event: CaseEvent,
+}
+
+/// Landmark from a RATSIGNAL's system information, e.g. "Brown dwarf 51 LY from Fuelum"
+#[derive(Debug, Clone, PartialEq)]
+pub struct LandmarkHint {
+    /// Primary star of the case's system ("Brown dwarf")
+    pub body_type: String,
+    /// Distance between the case's system and the landmark in light years
+    pub distance_ly: f64,
+    /// The landmark system ("Fuelum")
+    pub reference_system: String,
+}
+
+impl LandmarkHint {
+    /// Parse "<body type> <distance> LY from <system>"
+    pub fn parse(system_info: &str) -> Option<Self> {
+        let lower = system_info.to_ascii_lowercase();
+        let split = lower.find(" ly from ")?;
+        let (description, reference_system) = (
+            system_info[..split].trim(),
+            system_info[split + " ly from ".len()..].trim(),
+        );
+        let (body_type, distance) = description.rsplit_once(' ')?;
+        let distance_ly = distance.replace(',', "").parse().ok()?;
+
+        if body_type.trim().is_empty() || reference_system.is_empty() {
+            return None;
+        }
+        Some(Self {
+            body_type: body_type.trim().to_string(),
+            distance_ly,
+            reference_system: reference_system.to_string(),
+        })
+    }
+}
+
 /// Error types specific to EDJC operations
 #[derive(Debug, thiserror::Error)]
 pub enum EdjcError {
+    #[error("EDSM API error: {0}")]
+    EdsmApi(String),
+
     #[error("Inara API error: {0}")]
     InaraApi(String),
 
-    #[error("System not found: {0}")]
-    SystemNotFound(String),
+    #[error("Spansh route error: {0}")]
+    Spansh(String),
+
+    #[error("System not found: {name}{}", did_you_mean(.suggestions))]
+    SystemNotFound {
+        name: String,
+        /// Similarly named systems that were meant instead, closest first
+        suggestions: Vec<String>,
+    },
+
+    #[error("System not bundled for offline mode: {0}")]
+    SystemNotBundled(String),
+
+    #[error("System known but not yet trilaterated: {0}")]
+    CoordinatesUnavailable(String),
 
     #[error("CMDR not found: {0}")]
     CmdrNotFound(String),
 
+    #[error("CMDR {0}'s EDSM flight log is private")]
+    ProfilePrivate(String),
+
+    #[error("CMDR {0} can't be located on EDSM without an API key")]
+    ApiKeyMissing(String),
+
     #[error("Invalid jump range: {0}")]
     InvalidJumpRange(f64),
 
@@ -197,11 +806,247 @@ pub enum EdjcError {
 
     #[error("Parsing error: {0}")]
     Parse(String),
+
+    #[error("Route planning timed out: {0}")]
+    Timeout(String),
+
+    #[error("CMDR {0}'s location is unknown")]
+    OriginUnknown(String),
+}
+
+impl EdjcError {
+    /// "System not found" without any systems to suggest instead
+    pub fn system_not_found(name: impl Into<String>) -> Self {
+        EdjcError::SystemNotFound {
+            name: name.into(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// What the user can do about the error, when there is an obvious answer
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            EdjcError::SystemNotFound { .. } => Some("check the spelling"),
+            EdjcError::SystemNotBundled(_) => {
+                Some("offline mode only knows a few well-known systems")
+            }
+            EdjcError::CoordinatesUnavailable(_) => Some("ask for a nearby known system"),
+            EdjcError::Network(_) => Some("server unreachable, try again later"),
+            EdjcError::InvalidJumpRange(_) => Some("jump ranges must be above 0 LY"),
+            EdjcError::Timeout(_) => {
+                Some("planning took too long, try again or raise plan_timeout_seconds")
+            }
+            EdjcError::OriginUnknown(_) => Some("set your location source"),
+            EdjcError::ProfilePrivate(_) => {
+                Some("check edsm_api_key or make the flight log public on EDSM")
+            }
+            EdjcError::ApiKeyMissing(_) => Some("set edsm_api_key"),
+            _ => None,
+        }
+    }
+}
+
+/// " (did you mean: Colonia, Colonis?)" for the suggestions of an unknown
+/// system, empty without any
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" (did you mean: {}?)", suggestions.join(", "))
+    }
 }
 
 /// Result type alias for EDJC operations
 pub type EdjcResult<T> = Result<T, EdjcError>;
 
+/// When an operation has to be done by, checked between its lookups
+///
+/// Lookups after it has passed are skipped; clients bounded by it (see
+/// [`EdsmClient::bounded_by`](crate::edsm::EdsmClient::bounded_by)) also cut
+/// a request still under way, and its retries, short.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    timeout: Duration,
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    /// A deadline `timeout` from now
+    pub fn after(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            at: Instant::now().checked_add(timeout),
+        }
+    }
+
+    /// No deadline: every check passes
+    pub fn never() -> Self {
+        Self {
+            timeout: Duration::MAX,
+            at: None,
+        }
+    }
+
+    /// Time left until the deadline, zero once it has passed and `None`
+    /// without one
+    pub fn remaining(&self) -> Option<Duration> {
+        self.at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether the deadline has passed
+    pub fn has_passed(&self) -> bool {
+        self.at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    /// Fail with [`EdjcError::Timeout`] once the deadline has passed, naming
+    /// the step that would have been next
+    pub fn check(&self, next_step: &str) -> EdjcResult<()> {
+        if self.has_passed() {
+            return Err(EdjcError::Timeout(format!(
+                "gave up after {:?}, before {next_step}",
+                self.timeout
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Configuration problems detected while loading `edjc.toml`
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("CMDR name is not configured. Please set 'cmdr_name' in {0}")]
+    MissingCmdrName(String),
+}
+
+/// Outcome of an API connection self-test
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionHealth {
+    /// The API answered with the expected data
+    Healthy,
+    /// The host name could not be resolved
+    Dns(String),
+    /// The TLS handshake or certificate check failed
+    Tls(String),
+    /// The connection could not be established (refused, unreachable, ...)
+    Connect(String),
+    /// The request timed out
+    Timeout(String),
+    /// The server answered with a non-success HTTP status
+    Http(u16),
+    /// The request could not be built or sent
+    Request(String),
+    /// The server answered, but not with the data we expected
+    UnexpectedResponse(String),
+}
+
+impl ConnectionHealth {
+    /// Classify a failed request
+    pub fn from_reqwest_error(error: &reqwest::Error) -> Self {
+        Self::classify(
+            error.is_timeout(),
+            error.is_connect(),
+            error.status().map(|status| status.as_u16()),
+            &error_chain(error),
+        )
+    }
+
+    /// Map the properties of a failed request onto a health state
+    ///
+    /// Connection failures are split further by looking at the error chain,
+    /// since reqwest reports DNS and TLS problems as plain connect errors.
+    pub fn classify(timeout: bool, connect: bool, status: Option<u16>, detail: &str) -> Self {
+        let lower = detail.to_lowercase();
+
+        if timeout {
+            Self::Timeout(detail.to_string())
+        } else if let Some(status) = status {
+            Self::Http(status)
+        } else if connect && (lower.contains("dns") || lower.contains("lookup address")) {
+            Self::Dns(detail.to_string())
+        } else if connect
+            && (lower.contains("certificate") || lower.contains("tls") || lower.contains("ssl"))
+        {
+            Self::Tls(detail.to_string())
+        } else if connect {
+            Self::Connect(detail.to_string())
+        } else {
+            Self::Request(detail.to_string())
+        }
+    }
+
+    /// Whether the connection works
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Self::Healthy)
+    }
+}
+
+impl std::fmt::Display for ConnectionHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Healthy => write!(f, "connection OK"),
+            Self::Dns(detail) => write!(f, "DNS lookup failed: {detail}"),
+            Self::Tls(detail) => write!(f, "TLS handshake failed: {detail}"),
+            Self::Connect(detail) => write!(f, "could not connect: {detail}"),
+            Self::Timeout(detail) => write!(f, "request timed out: {detail}"),
+            Self::Http(status) => write!(f, "server returned HTTP {status}"),
+            Self::Request(detail) => write!(f, "request failed: {detail}"),
+            Self::UnexpectedResponse(detail) => write!(f, "unexpected response: {detail}"),
+        }
+    }
+}
+
+/// Round trips at least this long make a healthy connection "slow"
+pub const SLOW_CONNECTION: Duration = Duration::from_secs(2);
+
+/// What an API connection self-test saw, beyond whether it passed
+///
+/// The latency and status tell an API that is slow apart from one that is
+/// down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionDiagnostics {
+    pub health: ConnectionHealth,
+    /// Round trip of the test request, if the server answered at all
+    pub latency: Option<Duration>,
+    /// HTTP status the server answered with
+    pub status: Option<u16>,
+    /// How far from the galactic origin the API puts Sol, in LY
+    pub sol_offset_ly: Option<f64>,
+}
+
+impl ConnectionDiagnostics {
+    /// Whether the server answered, but took [`SLOW_CONNECTION`] or longer
+    pub fn is_slow(&self) -> bool {
+        self.latency
+            .is_some_and(|latency| latency >= SLOW_CONNECTION)
+    }
+}
+
+impl std::fmt::Display for ConnectionDiagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.health)?;
+        match self.latency {
+            Some(latency) if self.is_slow() => {
+                write!(f, " (slow, {} ms)", latency.as_millis())
+            }
+            Some(latency) => write!(f, " ({} ms)", latency.as_millis()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Join an error and its sources into a single message
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
 impl StarInfo {
     /// Create a new StarInfo for a neutron star
     pub fn neutron_star() -> Self {
@@ -212,6 +1057,7 @@ impl StarInfo {
             temperature: None,
             can_supercharge: true,
             supercharge_multiplier: 4.0,
+            kind: PrimaryStarKind::Neutron,
         }
     }
 
@@ -224,10 +1070,35 @@ impl StarInfo {
             temperature: None,
             can_supercharge: true,
             supercharge_multiplier: 1.5,
+            kind: PrimaryStarKind::WhiteDwarf,
         }
     }
 
-    /// Create a new StarInfo for a regular star
+    /// Create a new StarInfo for a black hole, which can't supercharge
+    pub fn black_hole() -> Self {
+        Self {
+            kind: PrimaryStarKind::BlackHole,
+            ..Self::regular_star("Black Hole", "H")
+        }
+    }
+
+    /// Whether this is a neutron star (4x supercharge)
+    pub fn is_neutron_star(&self) -> bool {
+        self.kind == PrimaryStarKind::Neutron
+    }
+
+    /// Whether this is a white dwarf (1.5x supercharge)
+    pub fn is_white_dwarf(&self) -> bool {
+        self.kind == PrimaryStarKind::WhiteDwarf
+    }
+
+    /// Whether this is a black hole (no supercharge)
+    pub fn is_black_hole(&self) -> bool {
+        self.kind == PrimaryStarKind::BlackHole
+    }
+
+    /// Create a new StarInfo for a regular star, main sequence or other by
+    /// its class
     pub fn regular_star(star_type: &str, star_class: &str) -> Self {
         Self {
             star_type: star_type.to_string(),
@@ -236,6 +1107,7 @@ impl StarInfo {
             temperature: None,
             can_supercharge: false,
             supercharge_multiplier: 1.0,
+            kind: PrimaryStarKind::of_class(star_class),
         }
     }
 }
@@ -249,16 +1121,109 @@ impl SystemCoordinates {
         (dx * dx + dy * dy + dz * dz).sqrt()
     }
 
+    /// Distance to another system, worked out on the 1/32 LY coordinate grid
+    ///
+    /// The coordinates are snapped to the grid (they're already on it when
+    /// they come from EDSM or the journal) and differenced in whole steps.
+    /// Across the galaxy the sum of the squared steps stays far below 2^53,
+    /// so it is exact and the square root is the only rounding.
+    pub fn distance_to_precise(&self, other: &SystemCoordinates) -> f64 {
+        let steps = |from: f64, to: f64| {
+            (to * COORDINATE_STEPS_PER_LY).round() - (from * COORDINATE_STEPS_PER_LY).round()
+        };
+        let dx = steps(self.x, other.x);
+        let dy = steps(self.y, other.y);
+        let dz = steps(self.z, other.z);
+        (dx * dx + dy * dy + dz * dz).sqrt() / COORDINATE_STEPS_PER_LY
+    }
+
     /// Check if this system can provide FSD supercharging
     pub fn can_supercharge(&self) -> bool {
-        self.has_neutron_star || self.has_white_dwarf
+        self.is_neutron_boost() || (self.has_white_dwarf && self.primary_can_supercharge())
+    }
+
+    /// Whether a neutron star here supercharges the FSD 4x
+    pub fn is_neutron_boost(&self) -> bool {
+        self.has_neutron_star && self.primary_can_supercharge()
+    }
+
+    /// False only when the primary is known not to supercharge (a black hole)
+    fn primary_can_supercharge(&self) -> bool {
+        self.primary_star_kind
+            .is_none_or(|kind| kind != PrimaryStarKind::BlackHole)
+    }
+
+    /// One-line summary of the system's position and boost stars
+    pub fn summary(&self) -> String {
+        let distance_from_sol = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        let mut summary = format!(
+            "{}: ({:.2}, {:.2}, {:.2}) - {:.1} LY from Sol",
+            self.name, self.x, self.y, self.z, distance_from_sol
+        );
+
+        if self.primary_star_kind == Some(PrimaryStarKind::BlackHole) {
+            summary.push_str(" [black hole]");
+        } else if self.has_neutron_star {
+            summary.push_str(" [neutron star]");
+        } else if self.has_white_dwarf {
+            summary.push_str(" [white dwarf]");
+        }
+        if let Some(note) = self.permit_note() {
+            summary.push_str(&format!(" [{note}]"));
+        }
+        if let Some(note) = self.hidden_note() {
+            summary.push_str(&format!(" {note}"));
+        }
+
+        summary
+    }
+
+    /// Whether entering this system needs a permit
+    pub fn requires_permit(&self) -> bool {
+        self.required_permit.is_some()
+    }
+
+    /// Note for permit-locked systems, naming the permit when EDSM knows it
+    pub fn permit_note(&self) -> Option<String> {
+        self.required_permit.as_deref().map(|permit| match permit {
+            "" => "requires a permit".to_string(),
+            permit => format!("requires the {permit} permit"),
+        })
+    }
+
+    /// Note for systems EDSM only returns when hidden systems are included
+    pub fn hidden_note(&self) -> Option<&'static str> {
+        self.is_hidden.then_some("(hidden/duplicate system)")
+    }
+
+    /// Make the boost star flags agree with each other and the primary's kind
+    ///
+    /// A primary is a single star: a known kind decides both flags, and
+    /// without one a neutron star wins over a white dwarf, as in
+    /// [`Self::supercharge_multiplier`]. Returns whether the flags were
+    /// contradictory.
+    pub fn normalize_star_flags(&mut self) -> bool {
+        let flags = match self.primary_star_kind {
+            Some(PrimaryStarKind::Neutron) => (true, false),
+            Some(PrimaryStarKind::WhiteDwarf) => (false, true),
+            Some(_) => (false, false),
+            None if self.has_neutron_star => (true, false),
+            None => (false, self.has_white_dwarf),
+        };
+        let contradictory = flags != (self.has_neutron_star, self.has_white_dwarf);
+        (self.has_neutron_star, self.has_white_dwarf) = flags;
+        contradictory
     }
 
     /// Get the supercharge multiplier for this system
+    ///
+    /// A neutron star (4x) takes precedence over a white dwarf (1.5x) should
+    /// both flags be set; [`Self::normalize_star_flags`] settles which one
+    /// the primary really is. Black holes never supercharge.
     pub fn supercharge_multiplier(&self) -> f64 {
-        if self.has_neutron_star {
+        if self.is_neutron_boost() {
             4.0
-        } else if self.has_white_dwarf {
+        } else if self.can_supercharge() {
             1.5
         } else {
             1.0
@@ -266,13 +1231,130 @@ impl SystemCoordinates {
     }
 }
 
+/// Distances from `origin` to each of `systems`, in order
+///
+/// Gives the same results as calling [`SystemCoordinates::distance_to`] on
+/// each system, but walks the slice in one pass with the origin hoisted out,
+/// which the compiler can vectorize. Scoring a corridor of thousands of boost
+/// stars is cheaper this way than one call per star.
+pub fn distances_from(origin: &SystemCoordinates, systems: &[SystemCoordinates]) -> Vec<f64> {
+    let (x, y, z) = (origin.x, origin.y, origin.z);
+    systems
+        .iter()
+        .map(|system| {
+            let dx = system.x - x;
+            let dy = system.y - y;
+            let dz = system.z - z;
+            (dx * dx + dy * dy + dz * dz).sqrt()
+        })
+        .collect()
+}
+
+impl SystemInfo {
+    /// Multi-line description for /sysinfo: position, primary star,
+    /// security, population, stations and permit
+    ///
+    /// EDSM leaves population and security out for uninhabited systems.
+    pub fn details(&self) -> String {
+        let star = match &self.primary_star {
+            Some(star) if star.can_supercharge => format!(
+                "{} ({}), supercharges {}x",
+                star.star_type, star.star_class, star.supercharge_multiplier
+            ),
+            Some(star) if star.is_black_hole() => {
+                format!(
+                    "{} ({}), black hole, no supercharge",
+                    star.star_type, star.star_class
+                )
+            }
+            Some(star) if star.star_class.is_empty() => {
+                format!("{}, {}", star.star_type, star.kind.as_str())
+            }
+            Some(star) => format!(
+                "{} ({}), {}",
+                star.star_type,
+                star.star_class,
+                star.kind.as_str()
+            ),
+            None => "unknown".to_string(),
+        };
+        let population = match self.population {
+            Some(population) => group_thousands(population),
+            None => "uninhabited".to_string(),
+        };
+
+        [
+            format!("📍 {}", self.coordinates.summary()),
+            format!("⭐ Primary star: {star}"),
+            format!(
+                "🛡️ Security: {}",
+                self.security.map_or("none", |security| security.as_str())
+            ),
+            format!("👥 Population: {population}"),
+            format!(
+                "🏗️ Stations: {}",
+                if self.has_stations { "yes" } else { "none" }
+            ),
+            format!(
+                "🔒 Permit: {}",
+                self.coordinates
+                    .permit_note()
+                    .unwrap_or_else(|| "not required".to_string())
+            ),
+        ]
+        .join("\n")
+    }
+}
+
+/// A number with comma thousands separators ("22,780,919,531")
+fn group_thousands(value: u64) -> String {
+    group_digits(&value.to_string(), ',')
+}
+
+/// A run of digits split into groups of three
+fn group_digits(digits: &str, separator: char) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
 impl JumpResult {
+    /// Whether the origin and destination are the same system, by name
+    /// (case-insensitive) or because they are at the same coordinates
+    pub fn is_same_system(&self) -> bool {
+        self.route_type == crate::jump_calculator::ALREADY_IN_SYSTEM_ROUTE
+            || self.from_system.eq_ignore_ascii_case(&self.to_system)
+    }
+
     /// Format the result as a human-readable string
     pub fn format(&self, template: &str) -> String {
+        self.format_with(template, NumberFormat::Plain)
+    }
+
+    /// Format the result like [`Self::format`], writing the distance in a
+    /// number format
+    pub fn format_with(&self, template: &str, numbers: NumberFormat) -> String {
+        self.format_rounded(template, numbers, JumpRounding::Ceil)
+    }
+
+    /// Format the result like [`Self::format_with`], showing the jump count
+    /// with a rounding policy
+    pub fn format_rounded(
+        &self,
+        template: &str,
+        numbers: NumberFormat,
+        rounding: JumpRounding,
+    ) -> String {
+        let distance = numbers.format(self.total_distance, 1);
         template
-            .replace("{jumps}", &self.jumps.to_string())
-            .replace("{distance:.1}", &format!("{:.1}", self.total_distance))
-            .replace("{distance}", &format!("{:.1}", self.total_distance))
+            .replace("{jumps}", &rounding.jumps(self.jumps, self.exact_jumps))
+            .replace("{distance:.1}", &distance)
+            .replace("{distance}", &distance)
             .replace("{system}", &self.to_system)
             .replace("{route}", &self.route_type)
             .replace("{from}", &self.from_system)
@@ -284,6 +1366,121 @@ impl JumpResult {
 mod tests {
     use super::*;
 
+    fn sol() -> SystemCoordinates {
+        SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: Some("Sol".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_system_info_details() {
+        let info = SystemInfo {
+            coordinates: sol(),
+            distance_from_reference: None,
+            population: Some(22780919531),
+            has_stations: true,
+            primary_star: Some(StarInfo::regular_star("G (White-Yellow) Star", "G")),
+            security: Some(SecurityLevel::High),
+        };
+        assert_eq!(
+            info.details(),
+            "📍 Sol: (0.00, 0.00, 0.00) - 0.0 LY from Sol [requires the Sol permit]\n\
+             ⭐ Primary star: G (White-Yellow) Star (G), main sequence\n\
+             🛡️ Security: High Security\n\
+             👥 Population: 22,780,919,531\n\
+             🏗️ Stations: yes\n\
+             🔒 Permit: requires the Sol permit"
+        );
+    }
+
+    #[test]
+    fn test_sparse_system_info_details() {
+        let info = SystemInfo {
+            coordinates: SystemCoordinates {
+                name: "Jackson's Lighthouse".to_string(),
+                x: 157.0,
+                y: -27.0,
+                z: -70.0,
+                has_neutron_star: true,
+                required_permit: None,
+                ..sol()
+            },
+            distance_from_reference: None,
+            population: None,
+            has_stations: false,
+            primary_star: None,
+            security: None,
+        };
+        let details = info.details();
+        let lines: Vec<&str> = details.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "📍 Jackson's Lighthouse: (157.00, -27.00, -70.00) - 174.0 LY from Sol [neutron star]",
+                "⭐ Primary star: unknown",
+                "🛡️ Security: none",
+                "👥 Population: uninhabited",
+                "🏗️ Stations: none",
+                "🔒 Permit: not required",
+            ]
+        );
+
+        let neutron = SystemInfo {
+            primary_star: Some(StarInfo::neutron_star()),
+            ..info
+        };
+        assert!(neutron
+            .details()
+            .contains("⭐ Primary star: Neutron Star (N), supercharges 4x"));
+
+        let black_hole = SystemInfo {
+            primary_star: Some(StarInfo::black_hole()),
+            ..neutron
+        };
+        assert!(black_hole
+            .details()
+            .contains("⭐ Primary star: Black Hole (H), black hole, no supercharge"));
+        let t_tauri = SystemInfo {
+            primary_star: Some(StarInfo::regular_star("T Tauri Star", "TTS")),
+            ..black_hole
+        };
+        assert!(t_tauri
+            .details()
+            .contains("⭐ Primary star: T Tauri Star (TTS), other"));
+
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1000), "1,000");
+    }
+
+    #[test]
+    fn test_landmark_hint_parse() {
+        assert_eq!(
+            LandmarkHint::parse("Brown dwarf 51 LY from Fuelum"),
+            Some(LandmarkHint {
+                body_type: "Brown dwarf".to_string(),
+                distance_ly: 51.0,
+                reference_system: "Fuelum".to_string(),
+            })
+        );
+
+        let hint = LandmarkHint::parse("Neutron Star 1,234.5 ly from Sagittarius A*").unwrap();
+        assert_eq!(hint.distance_ly, 1234.5);
+        assert_eq!(hint.reference_system, "Sagittarius A*");
+
+        assert_eq!(LandmarkHint::parse("Unconfirmed"), None);
+        assert_eq!(LandmarkHint::parse("Brown dwarf far LY from Fuelum"), None);
+        assert_eq!(LandmarkHint::parse("51 LY from Fuelum"), None);
+    }
+
     #[test]
     fn test_system_distance_calculation() {
         let sol = SystemCoordinates {
@@ -293,6 +1490,9 @@ mod tests {
             z: 0.0,
             has_neutron_star: false,
             has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
         };
 
         let alpha_centauri = SystemCoordinates {
@@ -302,6 +1502,9 @@ mod tests {
             z: 0.16,
             has_neutron_star: false,
             has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
         };
 
         let distance = sol.distance_to(&alpha_centauri);
@@ -317,6 +1520,9 @@ mod tests {
             z: 0.0,
             has_neutron_star: true,
             has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
         };
 
         let white_dwarf_system = SystemCoordinates {
@@ -326,6 +1532,9 @@ mod tests {
             z: 0.0,
             has_neutron_star: false,
             has_white_dwarf: true,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
         };
 
         let normal_system = SystemCoordinates {
@@ -335,11 +1544,194 @@ mod tests {
             z: 0.0,
             has_neutron_star: false,
             has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
         };
 
         assert_eq!(neutron_system.supercharge_multiplier(), 4.0);
         assert_eq!(white_dwarf_system.supercharge_multiplier(), 1.5);
         assert_eq!(normal_system.supercharge_multiplier(), 1.0);
+
+        // Both flags without a known kind: the neutron star wins
+        let both = SystemCoordinates {
+            has_neutron_star: true,
+            has_white_dwarf: true,
+            ..normal_system
+        };
+        assert_eq!(both.supercharge_multiplier(), 4.0);
+    }
+
+    #[test]
+    fn test_contradictory_star_flags_are_normalized() {
+        let system = |neutron, white_dwarf, kind| SystemCoordinates {
+            name: "Test".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            has_neutron_star: neutron,
+            has_white_dwarf: white_dwarf,
+            primary_star_kind: kind,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let normalized = |mut system: SystemCoordinates| {
+            let contradictory = system.normalize_star_flags();
+            (
+                system.has_neutron_star,
+                system.has_white_dwarf,
+                contradictory,
+            )
+        };
+
+        assert_eq!(normalized(system(true, true, None)), (true, false, true));
+        assert_eq!(
+            normalized(system(true, true, Some(PrimaryStarKind::WhiteDwarf))),
+            (false, true, true)
+        );
+        assert_eq!(
+            normalized(system(true, false, Some(PrimaryStarKind::BlackHole))),
+            (false, false, true)
+        );
+
+        // Consistent flags are left alone
+        assert_eq!(normalized(system(true, false, None)), (true, false, false));
+        assert_eq!(normalized(system(false, true, None)), (false, true, false));
+        assert_eq!(
+            normalized(system(true, false, Some(PrimaryStarKind::Neutron))),
+            (true, false, false)
+        );
+        assert_eq!(
+            normalized(system(false, false, Some(PrimaryStarKind::MainSequence))),
+            (false, false, false)
+        );
+
+        let mut both = system(true, true, Some(PrimaryStarKind::WhiteDwarf));
+        both.normalize_star_flags();
+        assert_eq!(both.supercharge_multiplier(), 1.5);
+        assert!(!both.is_neutron_boost());
+    }
+
+    #[test]
+    fn test_distance_on_the_coordinate_grid() {
+        let at = |x, y, z| SystemCoordinates {
+            name: String::new(),
+            x,
+            y,
+            z,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let sol = at(0.0, 0.0, 0.0);
+        let sagittarius_a = at(25.21875, -20.90625, 25899.96875);
+        let beagle_point = at(-1111.5625, -134.21875, 65269.75);
+
+        assert!((sol.distance_to_precise(&sagittarius_a) - 25899.989465395873).abs() < 1e-9);
+        assert!(
+            (sagittarius_a.distance_to_precise(&beagle_point) - 39386.35280152073).abs() < 1e-9
+        );
+        assert_eq!(
+            sol.distance_to_precise(&sagittarius_a),
+            sagittarius_a.distance_to_precise(&sol)
+        );
+        // Off-grid coordinates count from the nearest step
+        assert_eq!(sol.distance_to_precise(&at(0.0, 0.0, 4.01)), 4.0);
+    }
+
+    #[test]
+    fn test_batch_distances_match_distance_to() {
+        let at = |x, y, z| SystemCoordinates {
+            name: String::new(),
+            x,
+            y,
+            z,
+            has_neutron_star: false,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+        let origin = at(-9530.5, -910.28125, 19808.125);
+        let systems: Vec<_> = (0..1000)
+            .map(|i| {
+                let i = i as f64;
+                at(
+                    i * 37.5 - 9000.0,
+                    (i * 0.7).sin() * 500.0,
+                    65000.0 - i * 61.25,
+                )
+            })
+            .collect();
+
+        let distances = distances_from(&origin, &systems);
+        assert_eq!(distances.len(), systems.len());
+        for (system, distance) in systems.iter().zip(&distances) {
+            assert_eq!(*distance, origin.distance_to(system));
+        }
+        assert!(distances_from(&origin, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_system_summary() {
+        let system = SystemCoordinates {
+            name: "Jackson's Lighthouse".to_string(),
+            x: 157.0,
+            y: -27.0,
+            z: -70.0,
+            has_neutron_star: true,
+            has_white_dwarf: false,
+            primary_star_kind: None,
+            is_hidden: false,
+            required_permit: None,
+        };
+
+        let summary = system.summary();
+        assert!(summary.contains("(157.00, -27.00, -70.00)"));
+        assert!(summary.contains("174.0 LY from Sol"));
+        assert!(summary.contains("[neutron star]"));
+        assert!(!summary.contains("[white dwarf]"));
+        assert!(!summary.contains("permit"));
+
+        let sol = SystemCoordinates {
+            name: "Sol".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            required_permit: Some("Sol".to_string()),
+            ..system
+        };
+        assert!(sol.requires_permit());
+        assert!(sol.summary().ends_with("[requires the Sol permit]"));
+    }
+
+    #[test]
+    fn test_scoopable_classes() {
+        for class in [
+            "K",
+            "G2V",
+            "B (Blue-White) Star",
+            "M (Red dwarf) Star",
+            "O",
+            "A",
+            "F5",
+        ] {
+            assert!(is_scoopable_class(class), "{class} should be scoopable");
+        }
+        for class in [
+            "MS-type Star",
+            "L (Brown dwarf) Star",
+            "White Dwarf (DA) Star",
+            "Neutron Star",
+            "",
+        ] {
+            assert!(
+                !is_scoopable_class(class),
+                "{class} should not be scoopable"
+            );
+        }
     }
 
     #[test]
@@ -364,9 +1756,161 @@ mod tests {
             route_type: "neutron highway".to_string(),
             from_system: "Sol".to_string(),
             to_system: "Colonia".to_string(),
+            legs: Vec::new(),
+            supercharges: 0,
+            exact_jumps: None,
         };
 
         let formatted = result.format("{jumps} jumps to {system} ({distance:.1}ly)");
         assert_eq!(formatted, "5 jumps to Colonia (123.5ly)");
+
+        let colonia = JumpResult {
+            total_distance: 25900.0,
+            ..result
+        };
+        let template = "{jumps} jumps ({distance} LY)";
+        assert_eq!(
+            colonia.format_with(template, NumberFormat::En),
+            "5 jumps (25,900.0 LY)"
+        );
+        assert_eq!(
+            colonia.format_with(template, NumberFormat::De),
+            "5 jumps (25.900,0 LY)"
+        );
+        assert_eq!(colonia.format(template), "5 jumps (25900.0 LY)");
+    }
+
+    #[test]
+    fn test_jump_rounding() {
+        let partial = JumpResult {
+            jumps: 8,
+            total_distance: 234.0,
+            route_type: "direct".to_string(),
+            from_system: "Sol".to_string(),
+            to_system: "Deciat".to_string(),
+            legs: Vec::new(),
+            supercharges: 0,
+            exact_jumps: Some(7.8),
+        };
+        let format = |result: &JumpResult, rounding| {
+            result.format_rounded("{jumps} jumps", NumberFormat::Plain, rounding)
+        };
+
+        assert_eq!(format(&partial, JumpRounding::Ceil), "8 jumps");
+        assert_eq!(format(&partial, JumpRounding::Nearest), "8 jumps");
+        assert_eq!(format(&partial, JumpRounding::Range), "7–8 jumps");
+
+        let barely = JumpResult {
+            exact_jumps: Some(7.3),
+            ..partial.clone()
+        };
+        assert_eq!(format(&barely, JumpRounding::Ceil), "8 jumps");
+        assert_eq!(format(&barely, JumpRounding::Nearest), "7 jumps");
+        assert_eq!(format(&barely, JumpRounding::Range), "8 jumps");
+
+        // Under one jump is still one, and boosted routes have no exact count
+        assert_eq!(JumpRounding::Nearest.jumps(1, Some(0.2)), "1");
+        assert_eq!(JumpRounding::Range.jumps(1, Some(0.9)), "1");
+        let boosted = JumpResult {
+            exact_jumps: None,
+            ..partial
+        };
+        assert_eq!(format(&boosted, JumpRounding::Range), "8 jumps");
+        assert_eq!(format(&boosted, JumpRounding::Nearest), "8 jumps");
+    }
+
+    #[test]
+    fn test_number_formats() {
+        assert_eq!(NumberFormat::Plain.distance(25900.0), "25900.0 LY");
+        assert_eq!(NumberFormat::En.distance(25900.0), "25,900.0 LY");
+        assert_eq!(NumberFormat::De.distance(25900.0), "25.900,0 LY");
+
+        assert_eq!(NumberFormat::En.format(1234567.891, 2), "1,234,567.89");
+        assert_eq!(NumberFormat::De.format(-65279.96, 1), "-65.280,0");
+        assert_eq!(NumberFormat::De.format(999.0, 0), "999");
+        assert_eq!(NumberFormat::En.format(0.4, 1), "0.4");
+
+        assert_eq!(NumberFormat::De.signed(2.5, 1), "+2,5");
+        assert_eq!(NumberFormat::En.signed(-1500.0, 1), "-1,500.0");
+    }
+
+    #[test]
+    fn test_connection_health_classification() {
+        assert!(matches!(
+            ConnectionHealth::classify(true, false, None, "operation timed out"),
+            ConnectionHealth::Timeout(_)
+        ));
+        assert!(matches!(
+            ConnectionHealth::classify(
+                false,
+                true,
+                None,
+                "error trying to connect: dns error: failed to lookup address information"
+            ),
+            ConnectionHealth::Dns(_)
+        ));
+        assert!(matches!(
+            ConnectionHealth::classify(
+                false,
+                true,
+                None,
+                "error trying to connect: invalid peer certificate: Expired"
+            ),
+            ConnectionHealth::Tls(_)
+        ));
+        assert!(matches!(
+            ConnectionHealth::classify(false, true, None, "Connection refused (os error 111)"),
+            ConnectionHealth::Connect(_)
+        ));
+        assert_eq!(
+            ConnectionHealth::classify(false, false, Some(503), "Service Unavailable"),
+            ConnectionHealth::Http(503)
+        );
+        assert!(matches!(
+            ConnectionHealth::classify(false, false, None, "builder error"),
+            ConnectionHealth::Request(_)
+        ));
+    }
+
+    #[test]
+    fn test_connection_refused_is_connect_error() {
+        // Bind and drop a listener to get a local port nothing is listening on
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let error = reqwest::blocking::get(format!("http://127.0.0.1:{port}/")).unwrap_err();
+
+        let health = ConnectionHealth::from_reqwest_error(&error);
+        assert!(matches!(health, ConnectionHealth::Connect(_)), "{health:?}");
+        assert!(!health.is_healthy());
+    }
+
+    #[test]
+    fn test_fsd_fuel_matches_reference_values() {
+        // Asp Explorer with a 5A FSD at 420 t (optimal mass 1050 t, 5 t max per jump)
+        let fsd = FsdProfile::new(5, 'a', 420.0).unwrap();
+        assert_eq!(fsd.optimal_mass(), 1050.0);
+        assert_eq!(fsd.max_fuel_per_jump(), 5.0);
+
+        // In-game reference: ~1.96 t for a 20 LY jump, ~29.3 LY on a full jump
+        let fuel = fsd.fuel_for_jump(20.0);
+        assert!((fuel - 1.96).abs() / 1.96 < 0.05, "{fuel}");
+        let range = fsd.max_jump_range();
+        assert!((range - 29.3).abs() / 29.3 < 0.05, "{range}");
+        assert!((fsd.fuel_for_jump(range) - 5.0).abs() < 1e-9);
+
+        // A lower rated drive of the same class burns more for the same jump
+        let fsd_5d = FsdProfile::new(5, 'D', 420.0).unwrap();
+        assert!(fsd_5d.fuel_for_jump(20.0) > fuel);
+    }
+
+    #[test]
+    fn test_fsd_profile_rejects_unknown_drives() {
+        assert!(FsdProfile::new(1, 'A', 420.0).is_err());
+        assert!(FsdProfile::new(8, 'A', 420.0).is_err());
+        assert!(FsdProfile::new(5, 'F', 420.0).is_err());
+        assert!(FsdProfile::new(5, 'A', 0.0).is_err());
     }
 }